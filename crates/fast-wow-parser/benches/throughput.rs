@@ -0,0 +1,86 @@
+//! Parser throughput in MB/s and lines/s, split into the stages a performance change
+//! usually targets: tokenizing lines, dispatching events to the trackers, and building
+//! the summary.
+//!
+//! The sample is the golden-test fixtures repeated to a few MB; set `WOWLOG_BENCH_FILE` to
+//! a real `WoWCombatLog-*.txt` to measure against that instead.
+//!
+//! ```text
+//! cargo bench -p fast-wow-parser --bench throughput
+//! ```
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use fast_wow_parser::parser::{parse_csv_fields, split_timestamp_event};
+use fast_wow_parser::CombatLogParser;
+use std::hint::black_box;
+use std::path::Path;
+
+/// Copies of the fixtures in the bundled sample
+const SAMPLE_REPEATS: usize = 60;
+
+fn sample_log() -> String {
+    if let Some(path) = std::env::var_os("WOWLOG_BENCH_FILE") {
+        return std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("reading {}: {}", Path::new(&path).display(), e));
+    }
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let mut fixtures: Vec<_> = std::fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    fixtures.sort();
+    let fixtures: Vec<String> = fixtures.iter().map(|path| std::fs::read_to_string(path).unwrap()).collect();
+    fixtures.join("").repeat(SAMPLE_REPEATS)
+}
+
+/// A parser that has been fed every line of `log`, ready for `finish`
+fn fed_parser(log: &str) -> CombatLogParser {
+    let mut parser = CombatLogParser::new("bench.txt");
+    for line in log.lines() {
+        parser.process_line(line);
+    }
+    parser
+}
+
+fn throughput(c: &mut Criterion) {
+    let log = sample_log();
+    let lines: Vec<&str> = log.lines().collect();
+    let bytes = Throughput::Bytes(log.len() as u64);
+    let line_count = Throughput::Elements(lines.len() as u64);
+
+    // Each stage is measured twice, once per unit, so reports show both MB/s and lines/s
+    let mut tokenize = c.benchmark_group("tokenize");
+    for (unit, throughput) in [("bytes", bytes.clone()), ("lines", line_count.clone())] {
+        tokenize.throughput(throughput);
+        tokenize.bench_function(unit, |b| b.iter(|| {
+            let mut fields = 0;
+            for line in &lines {
+                if let Some((_, event)) = split_timestamp_event(line.trim()) {
+                    fields += parse_csv_fields(event).len();
+                }
+            }
+            black_box(fields)
+        }));
+    }
+    tokenize.finish();
+
+    // Tokenizing plus event handling, without the summary
+    let mut process = c.benchmark_group("process_lines");
+    process.sample_size(20);
+    for (unit, throughput) in [("bytes", bytes.clone()), ("lines", line_count.clone())] {
+        process.throughput(throughput);
+        process.bench_function(unit, |b| b.iter(|| black_box(fed_parser(&log))));
+    }
+    process.finish();
+
+    let mut summary = c.benchmark_group("finish");
+    summary.sample_size(20);
+    for (unit, throughput) in [("bytes", bytes), ("lines", line_count)] {
+        summary.throughput(throughput);
+        summary.bench_function(unit, |b| b.iter_batched(|| fed_parser(&log), |parser| black_box(parser.finish()), BatchSize::LargeInput));
+    }
+    summary.finish();
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+//! Feed arbitrary lines through the event dispatcher and build the summary. Each input is
+//! prefixed with a retail header so the retail field layout is exercised; a panic anywhere
+//! is a bug (a malformed line from a real log would hit the same path).
+#![no_main]
+
+use fast_wow_parser::CombatLogParser;
+use libfuzzer_sys::fuzz_target;
+
+const HEADER: &str = "2/14/2025 20:00:00.000  COMBAT_LOG_VERSION,22,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,11.1.0,PROJECT_ID,1";
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let mut parser = CombatLogParser::new("fuzz.txt");
+    parser.process_line(HEADER);
+    for line in text.lines() {
+        parser.process_line(line);
+    }
+    let _ = parser.finish();
+});
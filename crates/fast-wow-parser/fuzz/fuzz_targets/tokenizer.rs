@@ -0,0 +1,14 @@
+//! Splitting a line into timestamp, event and fields must never panic, and unquoting any
+//! field must not either
+#![no_main]
+
+use fast_wow_parser::parser::{parse_csv_fields, split_timestamp_event, unquote};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let line = String::from_utf8_lossy(data);
+    let event_part = split_timestamp_event(&line).map_or(line.as_ref(), |(_, event)| event);
+    for field in parse_csv_fields(event_part) {
+        unquote(field);
+    }
+});
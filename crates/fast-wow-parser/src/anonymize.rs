@@ -0,0 +1,483 @@
+//! Privacy filters for reports that are shared publicly or shown on stream
+//!
+//! [`Pseudonyms`]: every player becomes a role pseudonym ("Tank1", "Healer2", "DPS3"), numbered per role in
+//! order of first appearance. Names and GUIDs are replaced wherever they appear as a whole
+//! string or map key in the serialized report (and full "Name-Realm" names inside longer
+//! strings), so new fields are covered without listing them here.
+//!
+//! [`redact_players`]: chosen players' rows are folded into one aggregate row, so the group
+//! totals stay right but nobody's individual numbers or breakdowns are shown. The replay, spell
+//! search and live mode, served apart from the summary, have their own versions.
+
+use crate::models::{
+    AvoidableHit, AvoidablePlayer, BossTrashSplit, BuffUptime, CastStats, CrowdControlReceived, DeathEvent,
+    EncounterSummary, EnemyBreakdown, EnemyDamageTaken, EnemyPlayerDamage, HealingSplit, HpSnapshot, LivePlayer,
+    NearDeath, PlayerSummary, PriorityTarget, PriorityTargetPlayer, PullWindow, PullWindowPlayer, ReplayData,
+    SpellSearchResult, TargetCountSplit, TrashPull,
+};
+use crate::parser::eq_ignore_case;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct Pseudonyms {
+    /// (guid, "Name-Realm", pseudonym) in the order players were added
+    players: Vec<(String, String, String)>,
+    /// Pseudonyms handed out per role label
+    role_counts: HashMap<&'static str, u32>,
+}
+
+fn role_label(role: &str) -> &'static str {
+    match role {
+        "tank" => "Tank",
+        "healer" => "Healer",
+        _ => "DPS",
+    }
+}
+
+impl Pseudonyms {
+    /// Pseudonyms for every player in `encounters`
+    pub fn from_encounters(encounters: &[EncounterSummary]) -> Self {
+        let mut pseudonyms = Pseudonyms::default();
+        for enc in encounters {
+            pseudonyms.add_players(&enc.players);
+        }
+        pseudonyms
+    }
+
+    /// Give a pseudonym to each player that doesn't have one yet
+    pub fn add_players(&mut self, players: &[PlayerSummary]) {
+        for player in players {
+            if self.players.iter().any(|(guid, _, _)| *guid == player.guid) {
+                continue;
+            }
+            let label = role_label(&player.role);
+            let count = self.role_counts.entry(label).or_insert(0);
+            *count += 1;
+            self.players.push((player.guid.clone(), player.name.clone(), format!("{}{}", label, count)));
+        }
+    }
+
+    /// The pseudonym for a player name or GUID
+    pub fn get(&self, name_or_guid: &str) -> Option<&str> {
+        self.players.iter()
+            .find(|(guid, name, _)| guid == name_or_guid || name == name_or_guid)
+            .map(|(_, _, pseudonym)| pseudonym.as_str())
+    }
+
+    /// Serialize `report` with every player identity replaced
+    pub fn anonymize<T: Serialize>(&self, report: &T) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(report)?;
+        self.apply(&mut value);
+        Ok(value)
+    }
+
+    /// Replace player identities in an already serialized report
+    pub fn apply(&self, value: &mut Value) {
+        let exact = self.exact();
+        self.replace(value, &exact);
+    }
+
+    /// Replace player identities in each of `texts`, for output that isn't JSON (a spreadsheet)
+    pub fn apply_to_strings<'a>(&self, texts: impl IntoIterator<Item = &'a mut String>) {
+        let exact = self.exact();
+        for text in texts {
+            *text = self.replace_str(text, &exact);
+        }
+    }
+
+    /// Whole strings to replace: GUIDs, full names and bare names
+    fn exact(&self) -> HashMap<&str, String> {
+        let mut exact: HashMap<&str, String> = HashMap::new();
+        for (guid, name, pseudonym) in &self.players {
+            exact.insert(guid, format!("Player-{}", pseudonym));
+            exact.insert(name, pseudonym.clone());
+            // Some fields carry the bare character name; the first player with it wins
+            if let Some((bare, _)) = name.split_once('-') {
+                exact.entry(bare).or_insert_with(|| pseudonym.clone());
+            }
+        }
+        exact
+    }
+
+    fn replace(&self, value: &mut Value, exact: &HashMap<&str, String>) {
+        match value {
+            Value::String(s) => *s = self.replace_str(s, exact),
+            Value::Array(items) => {
+                for item in items {
+                    self.replace(item, exact);
+                }
+            }
+            Value::Object(map) => {
+                for (key, mut item) in std::mem::take(map) {
+                    self.replace(&mut item, exact);
+                    map.insert(self.replace_str(&key, exact), item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn replace_str(&self, s: &str, exact: &HashMap<&str, String>) -> String {
+        if let Some(pseudonym) = exact.get(s) {
+            return pseudonym.clone();
+        }
+        let mut result = s.to_string();
+        if s.contains('-') {
+            for (_, name, pseudonym) in &self.players {
+                if result.contains(name.as_str()) {
+                    result = result.replace(name.as_str(), pseudonym);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Whether `player_name` ("Name-Realm-Region") is one of `names` (bare or full, any case)
+pub fn is_listed(player_name: &str, names: &[String]) -> bool {
+    let bare = player_name.split('-').next().unwrap_or(player_name);
+    names.iter().any(|n| eq_ignore_case(n, player_name) || eq_ignore_case(n, bare))
+}
+
+/// Whether `name` is one of `names` where a row names players bare or in full, either way
+/// round: by character name alone, so a same-named character of another realm is hidden too
+fn is_mentioned(name: &str, names: &[String]) -> bool {
+    fn bare(name: &str) -> &str {
+        name.split('-').next().unwrap_or(name)
+    }
+    names.iter().any(|n| eq_ignore_case(bare(n), bare(name)))
+}
+
+/// Replace the listed players in every encounter (and key segment and pull) with a single
+/// "Private (N)" row holding their combined totals and no breakdowns, and fold their rows of
+/// the per-enemy, priority target, boss/trash and pull window tables the same way. Their
+/// deaths, near deaths, cooldown usage, buff uptimes, avoidable hits, replay snapshots,
+/// per-second and per-ability damage, raw ability events, defensive coverage, healing report
+/// rows and columns, custom metric rows, practice casts and arena rounds are dropped, and
+/// where they show up in someone else's row (a death recap, a tank swap, a healer cooldown)
+/// they are "Private".
+pub fn redact_players(encounters: &mut [EncounterSummary], names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    for enc in encounters {
+        let guids = redact_player_rows(&mut enc.players, names);
+        if guids.is_empty() {
+            continue;
+        }
+        let private = Private { guids: &guids, names };
+        private.deaths(&mut enc.deaths);
+        private.near_deaths(&mut enc.near_deaths);
+        private.buff_uptimes(&mut enc.buff_uptimes);
+        private.buff_uptimes(&mut enc.key_buff_uptimes);
+        private.enemy_tables(&mut enc.enemy_breakdowns, &mut enc.enemy_damage_taken, &mut enc.priority_targets);
+        for phase in &mut enc.phases {
+            private.enemy_tables(&mut phase.enemy_breakdowns, &mut Vec::new(), &mut Vec::new());
+        }
+        private.pull_window(&mut enc.pre_pull);
+        private.pull_window(&mut enc.post_pull);
+        fold_rows(&mut enc.boss_trash_split, |row| private.is(&row.guid, &row.name), |rows| BossTrashSplit {
+            guid: PRIVATE.to_string(),
+            name: private_label(rows.len()),
+            boss_damage: rows.iter().map(|r| r.boss_damage).sum(),
+            trash_damage: rows.iter().map(|r| r.trash_damage).sum(),
+            boss_dps: rows.iter().map(|r| r.boss_dps).sum(),
+            trash_dps: rows.iter().map(|r| r.trash_dps).sum(),
+        });
+        enc.cooldown_usage.retain(|c| !private.is(&c.guid, &c.name));
+        for swap in &mut enc.tank_swaps {
+            private.rename(&mut swap.from_tank);
+            private.rename(&mut swap.to_tank);
+        }
+        for cooldown in enc.healer_cd_coverage.iter_mut().flat_map(|d| &mut d.cooldowns) {
+            private.rename(&mut cooldown.caster_name);
+        }
+        fold_rows(&mut enc.avoidable_damage.players, |row| private.named(&row.player_name), |rows| AvoidablePlayer {
+            player_name: private_label(rows.len()),
+            hits: rows.iter().map(|r| r.hits).sum(),
+            damage: rows.iter().map(|r| r.damage).sum(),
+            top_spell: String::new(),
+        });
+        private.replay(&mut enc.replay_timeline, &mut enc.avoidable_hits, &mut enc.raw_ability_events);
+        for segment in &mut enc.segments {
+            redact_player_rows(&mut segment.players, names);
+            private.deaths(&mut segment.deaths);
+            private.near_deaths(&mut segment.near_deaths);
+            private.buff_uptimes(&mut segment.buff_uptimes);
+            private.buff_uptimes(&mut segment.key_buff_uptimes);
+            private.enemy_tables(&mut segment.enemy_breakdowns, &mut segment.enemy_damage_taken, &mut segment.priority_targets);
+            for phase in &mut segment.phases {
+                private.enemy_tables(&mut phase.enemy_breakdowns, &mut Vec::new(), &mut Vec::new());
+            }
+            private.pull_window(&mut segment.pre_pull);
+            private.pull_window(&mut segment.post_pull);
+            for pull in &mut segment.pulls {
+                private.pull(pull);
+            }
+        }
+        for bucket in enc.time_bucketed_player_damage.values_mut() {
+            bucket.retain(|guid, _| !guids.contains(guid));
+        }
+        enc.ability_damage_over_time.retain(|guid, _| !guids.contains(guid));
+        enc.defensive_coverage.retain(|c| !guids.contains(&c.player_guid));
+        enc.insights.retain(|i| !i.players.iter().any(|p| is_listed(p, names)));
+        for metric in &mut enc.custom_metrics {
+            metric.players.retain(|p| !guids.contains(&p.guid));
+        }
+        if let Some(report) = &mut enc.healing_report {
+            report.remove_players(&guids);
+        }
+        if let Some(practice) = &mut enc.practice {
+            practice.players.retain(|p| !guids.contains(&p.guid));
+        }
+        if let Some(arena) = &mut enc.arena {
+            for round in &mut arena.rounds {
+                round.players.retain(|p| !guids.contains(&p.guid));
+            }
+        }
+    }
+}
+
+/// Drop the listed players (of the encounter's `players`) from its replay: their snapshots,
+/// avoidable hits and ability events, and make them "Private" as the target of the others'
+pub fn redact_replay(replay: &mut ReplayData, players: &[PlayerSummary], names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    let guids: Vec<String> = players.iter().filter(|p| is_listed(&p.name, names)).map(|p| p.guid.clone()).collect();
+    let private = Private { guids: &guids, names };
+    private.replay(&mut replay.replay_timeline, &mut replay.avoidable_hits, &mut replay.raw_ability_events);
+}
+
+/// Make the listed players "Private" where spell search hits name them
+pub fn redact_spell_search(result: &mut SpellSearchResult, names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    let private = Private { guids: &[], names };
+    for hit in result.encounters.iter_mut().flat_map(|e| &mut e.hits) {
+        private.rename(&mut hit.source_name);
+        private.rename(&mut hit.dest_name);
+    }
+}
+
+/// Drop the listed players from live mode, and make them "Private" in the others' last death
+pub fn redact_live_players(players: &mut Vec<LivePlayer>, names: &[String]) {
+    if names.is_empty() {
+        return;
+    }
+    players.retain(|p| !is_listed(&p.name, names));
+    let private = Private { guids: &[], names };
+    for player in players {
+        if let Some(death) = &mut player.last_death {
+            private.death_sources(death);
+        }
+    }
+}
+
+/// GUID of the folded rows
+const PRIVATE: &str = "Private";
+
+/// An encounter's raw ability event: (elapsed_secs, player_guid, spell_id, spell_name,
+/// spell_school, amount, target_name)
+type AbilityEvent = (f64, String, u64, String, u32, u64, String);
+
+/// Name of a row folding `count` players
+fn private_label(count: usize) -> String {
+    format!("{} ({})", PRIVATE, count)
+}
+
+/// Move the rows `is_private` picks to the end, folded into one by `fold`
+fn fold_rows<T>(rows: &mut Vec<T>, is_private: impl Fn(&T) -> bool, fold: impl FnOnce(&[T]) -> T) {
+    let (private, public): (Vec<T>, Vec<T>) = std::mem::take(rows).into_iter().partition(|row| is_private(row));
+    *rows = public;
+    if !private.is_empty() {
+        rows.push(fold(&private));
+    }
+}
+
+/// The redacted players of an encounter
+struct Private<'a> {
+    guids: &'a [String],
+    names: &'a [String],
+}
+
+impl Private<'_> {
+    fn is(&self, guid: &str, name: &str) -> bool {
+        self.guids.iter().any(|g| g == guid) || self.named(name)
+    }
+
+    fn named(&self, name: &str) -> bool {
+        is_mentioned(name, self.names)
+    }
+
+    fn rename(&self, name: &mut String) {
+        if self.named(name) {
+            *name = PRIVATE.to_string();
+        }
+    }
+
+    fn rename_opt(&self, name: &mut Option<String>) {
+        if let Some(name) = name {
+            self.rename(name);
+        }
+    }
+
+    /// Drop their deaths, and hide them as a source in everyone else's
+    fn deaths(&self, deaths: &mut Vec<DeathEvent>) {
+        deaths.retain(|d| !self.is(&d.player_guid, &d.player_name));
+        for death in deaths {
+            self.death_sources(death);
+        }
+    }
+
+    fn death_sources(&self, death: &mut DeathEvent) {
+        self.rename_opt(&mut death.killing_blow_source);
+        self.rename_opt(&mut death.resurrected_by);
+        for event in &mut death.recap {
+            self.rename(&mut event.source_name);
+        }
+    }
+
+    /// Drop their replay snapshots, avoidable hits and ability events, and hide them as the
+    /// target of everyone else's
+    fn replay(&self, timeline: &mut Vec<HpSnapshot>, avoidable_hits: &mut Vec<AvoidableHit>, ability_events: &mut Vec<AbilityEvent>) {
+        timeline.retain(|s| !self.is(&s.guid, &s.name));
+        avoidable_hits.retain(|h| !self.named(&h.player_name));
+        ability_events.retain(|(_, guid, ..)| !self.guids.contains(guid));
+        for (.., target) in ability_events {
+            self.rename(target);
+        }
+    }
+
+    fn near_deaths(&self, near_deaths: &mut Vec<NearDeath>) {
+        near_deaths.retain(|n| !self.named(&n.player_name));
+        for near_death in near_deaths {
+            self.rename(&mut near_death.source_name);
+            self.rename_opt(&mut near_death.saved_by);
+        }
+    }
+
+    /// Drop their uptimes (keyed by GUID), and hide them as the source of everyone else's
+    fn buff_uptimes(&self, uptimes: &mut HashMap<String, Vec<BuffUptime>>) {
+        uptimes.retain(|guid, _| !self.guids.contains(guid));
+        for uptime in uptimes.values_mut().flatten() {
+            self.rename(&mut uptime.source_name);
+        }
+    }
+
+    fn enemy_tables(&self, breakdowns: &mut [EnemyBreakdown], taken: &mut [EnemyDamageTaken], targets: &mut [PriorityTarget]) {
+        let players = breakdowns.iter_mut().map(|b| &mut b.players).chain(taken.iter_mut().map(|t| &mut t.players));
+        for players in players {
+            fold_rows(players, |row| self.named(&row.player_name), |rows| EnemyPlayerDamage {
+                player_name: private_label(rows.len()),
+                class_name: String::new(),
+                damage: rows.iter().map(|r| r.damage).sum(),
+            });
+        }
+        for target in targets {
+            fold_rows(&mut target.players, |row| self.is(&row.guid, &row.name), |rows| PriorityTargetPlayer {
+                guid: PRIVATE.to_string(),
+                name: private_label(rows.len()),
+                damage: rows.iter().map(|r| r.damage).sum(),
+                dps: rows.iter().map(|r| r.dps).sum(),
+            });
+        }
+    }
+
+    fn pull_window(&self, window: &mut Option<PullWindow>) {
+        let Some(window) = window else { return };
+        fold_rows(&mut window.players, |row| self.is(&row.guid, &row.name), |rows| PullWindowPlayer {
+            guid: PRIVATE.to_string(),
+            name: private_label(rows.len()),
+            damage: rows.iter().map(|r| r.damage).sum(),
+            healing: rows.iter().map(|r| r.healing).sum(),
+        });
+    }
+
+    /// Fold their pull rows and drop their deaths, and hide them in who opened the pull, its
+    /// first targets, taunts and redirects, and who tanked it
+    fn pull(&self, pull: &mut TrashPull) {
+        redact_player_rows(&mut pull.players, self.names);
+        self.deaths(&mut pull.deaths);
+        let opener = &mut pull.opener;
+        self.rename_opt(&mut opener.opened_by);
+        for target in &mut opener.first_targets {
+            self.rename(&mut target.player_name);
+        }
+        for cast in opener.taunts.iter_mut().chain(&mut opener.redirects) {
+            self.rename(&mut cast.caster_name);
+            self.rename(&mut cast.target_name);
+        }
+        if let Some(early) = &mut opener.top_early_damage {
+            self.rename(&mut early.player_name);
+        }
+        for span in pull.tanking.iter_mut().flat_map(|t| &mut t.spans) {
+            self.rename(&mut span.player_name);
+        }
+    }
+}
+
+/// Fold the listed players' rows into one, and make them "Private" where the others'
+/// abilities and damage spikes name them; returns their GUIDs
+fn redact_player_rows(players: &mut Vec<PlayerSummary>, names: &[String]) -> Vec<String> {
+    let (private, public): (Vec<PlayerSummary>, Vec<PlayerSummary>) =
+        std::mem::take(players).into_iter().partition(|p| is_listed(&p.name, names));
+    *players = public;
+    for player in players.iter_mut() {
+        let abilities = player.abilities.iter_mut().chain(&mut player.heal_abilities).chain(&mut player.damage_taken_abilities);
+        for target in abilities.flat_map(|a| &mut a.targets).filter(|t| is_mentioned(&t.target_name, names)) {
+            target.target_name = PRIVATE.to_string();
+        }
+        let spikes = player.damage_spikes.iter_mut().flat_map(|s| &mut s.abilities);
+        for ability in spikes.filter(|a| is_mentioned(&a.source_name, names)) {
+            ability.source_name = PRIVATE.to_string();
+        }
+    }
+    if private.is_empty() {
+        return Vec::new();
+    }
+    let sum = |f: fn(&PlayerSummary) -> u64| private.iter().map(f).sum::<u64>();
+    players.push(PlayerSummary {
+        guid: "Private".to_string(),
+        name: format!("Private ({})", private.len()),
+        class_name: String::new(),
+        spec_name: String::new(),
+        role: "dps".to_string(),
+        spec_inferred: false,
+        talents: Vec::new(),
+        damage_done: sum(|p| p.damage_done),
+        healing_done: sum(|p| p.healing_done),
+        damage_taken: sum(|p| p.damage_taken),
+        deaths: private.iter().map(|p| p.deaths).sum(),
+        near_deaths: private.iter().map(|p| p.near_deaths).sum(),
+        saves: private.iter().map(|p| p.saves).sum(),
+        deaths_prevented: private.iter().map(|p| p.deaths_prevented).sum(),
+        interrupts: private.iter().map(|p| p.interrupts).sum(),
+        dispels: private.iter().map(|p| p.dispels).sum(),
+        dps: private.iter().map(|p| p.dps).sum(),
+        hps: private.iter().map(|p| p.hps).sum(),
+        damage_rank: 0,
+        damage_share: private.iter().map(|p| p.damage_share).sum(),
+        healing_rank: 0,
+        healing_share: private.iter().map(|p| p.healing_share).sum(),
+        abilities: Vec::new(),
+        heal_abilities: Vec::new(),
+        damage_taken_abilities: Vec::new(),
+        damage_spikes: Vec::new(),
+        cast_stats: CastStats::default(),
+        target_count_split: TargetCountSplit::default(),
+        filtered_damage: sum(|p| p.filtered_damage),
+        filtered_dps: private.iter().map(|p| p.filtered_dps).sum(),
+        healing_split: HealingSplit::default(),
+        crowd_control: CrowdControlReceived::default(),
+        performance_delta: None,
+        reference_dps: None,
+        efficiency: None,
+        schools: None,
+        damage_types: None,
+    });
+    private.into_iter().map(|p| p.guid).collect()
+}
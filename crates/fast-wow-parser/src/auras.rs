@@ -0,0 +1,106 @@
+//! The auras a player had at any moment of a fight, rebuilt from the aura events kept in
+//! `buff_uptimes`, for questions like "what was up when I pressed my cooldowns", and the same
+//! events as lanes of intervals for a buff band view
+//!
+//! An aura is up from its apply until the next remove, and stack events change its stacks.
+//! Events at the moment asked for have already happened, as in the defensive coverage. An aura
+//! whose first event isn't an apply was already up at the pull.
+
+use crate::models::{ActiveAura, AuraInterval, AuraLane, BuffEvent, BuffUptime};
+
+/// Whether the aura of `timeline` was up at `time`, with its stacks and when it was applied
+fn state_at(timeline: &[BuffEvent], time: f64) -> Option<(u32, f64)> {
+    let first = timeline.first()?;
+    let mut state = (first.event_type != "apply").then_some((first.stacks.max(1), 0.0));
+    for event in timeline.iter().take_while(|e| e.time <= time) {
+        state = match (event.event_type.as_str(), state) {
+            // A refresh doesn't start a new application
+            ("apply", Some((_, applied_at))) => Some((event.stacks, applied_at)),
+            ("apply", None) => Some((event.stacks, event.time)),
+            ("remove", _) => None,
+            ("stack", Some((_, applied_at))) => Some((event.stacks, applied_at)),
+            (_, state) => state,
+        };
+    }
+    state
+}
+
+fn close(intervals: &mut Vec<AuraInterval>, (start, stacks): (f64, u32), end: f64) {
+    if end > start {
+        intervals.push(AuraInterval { start, end, stacks });
+    }
+}
+
+/// When the aura of `timeline` was up during a fight of `duration` seconds, split where its
+/// stacks change
+fn intervals(timeline: &[BuffEvent], duration: f64) -> Vec<AuraInterval> {
+    let Some(first) = timeline.first() else {
+        return Vec::new();
+    };
+    let mut intervals = Vec::new();
+    // Start and stacks of the open interval
+    let mut open = (first.event_type != "apply").then_some((0.0, first.stacks.max(1)));
+    for event in timeline {
+        open = match (event.event_type.as_str(), open) {
+            ("apply" | "stack", Some((start, stacks))) if stacks == event.stacks => Some((start, stacks)),
+            ("apply" | "stack", Some(interval)) => {
+                close(&mut intervals, interval, event.time);
+                Some((event.time, event.stacks))
+            }
+            ("apply", None) => Some((event.time, event.stacks)),
+            ("remove", Some(interval)) => {
+                close(&mut intervals, interval, event.time);
+                None
+            }
+            (_, open) => open,
+        };
+    }
+    if let Some(interval) = open {
+        close(&mut intervals, interval, duration);
+    }
+    intervals
+}
+
+/// Lanes of a player with `uptimes` over a fight of `duration` seconds
+pub fn lanes(uptimes: &[BuffUptime], duration: f64) -> Vec<AuraLane> {
+    let mut lanes: Vec<AuraLane> = uptimes.iter()
+        .map(|uptime| AuraLane {
+            spell_id: uptime.spell_id,
+            spell_name: uptime.spell_name.clone(),
+            source_name: uptime.source_name.clone(),
+            aura_type: uptime.aura_type.clone(),
+            uptime_pct: uptime.uptime_pct,
+            wowhead_url: uptime.wowhead_url.clone(),
+            intervals: intervals(&uptime.timeline, duration),
+        })
+        .filter(|lane| !lane.intervals.is_empty())
+        .collect();
+    lanes.sort_by(|a, b| {
+        (a.aura_type == "DEBUFF").cmp(&(b.aura_type == "DEBUFF"))
+            .then_with(|| b.uptime_pct.partial_cmp(&a.uptime_pct).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    lanes
+}
+
+/// Auras up on a player with `uptimes` at `time` seconds into the fight
+pub fn auras_at(uptimes: &[BuffUptime], time: f64) -> Vec<ActiveAura> {
+    let mut auras: Vec<ActiveAura> = uptimes.iter()
+        .filter_map(|uptime| {
+            let (stacks, applied_at) = state_at(&uptime.timeline, time)?;
+            Some(ActiveAura {
+                spell_id: uptime.spell_id,
+                spell_name: uptime.spell_name.clone(),
+                source_name: uptime.source_name.clone(),
+                aura_type: uptime.aura_type.clone(),
+                stacks,
+                applied_at,
+                wowhead_url: uptime.wowhead_url.clone(),
+            })
+        })
+        .collect();
+    auras.sort_by(|a, b| {
+        (a.aura_type == "DEBUFF").cmp(&(b.aura_type == "DEBUFF"))
+            .then_with(|| a.spell_name.cmp(&b.spell_name))
+    });
+    auras
+}
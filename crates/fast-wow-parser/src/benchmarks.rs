@@ -0,0 +1,90 @@
+//! Typical DPS per spec, for a reference line on the damage charts ("typical Fire Mage at +10")
+//!
+//! The table gives each spec's median DPS at a few Mythic+ key levels and per raid difficulty.
+//! A table is bundled in `game_data/spec_benchmarks.json`; the server can swap in a newer one
+//! fetched from a URL. Key levels between the table's levels are interpolated, and levels past
+//! either end take the nearest one.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::EncounterSummary;
+
+const BUILTIN_TABLE: &str = include_str!("game_data/spec_benchmarks.json");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkTable {
+    /// When the numbers were taken, e.g. "2025-03-01"
+    pub updated: String,
+    /// Where they come from
+    #[serde(default)]
+    pub source: String,
+    /// "Spec Class" (e.g. "Fire Mage") -> medians
+    pub specs: BTreeMap<String, SpecBenchmark>,
+}
+
+/// One spec's median DPS
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpecBenchmark {
+    /// Key level -> median DPS
+    #[serde(default)]
+    pub mythic_plus: BTreeMap<u32, f64>,
+    /// "lfr", "normal", "heroic" or "mythic" -> median DPS
+    #[serde(default)]
+    pub raid: BTreeMap<String, f64>,
+}
+
+impl BenchmarkTable {
+    /// The table bundled with this version
+    pub fn builtin() -> BenchmarkTable {
+        serde_json::from_str(BUILTIN_TABLE).expect("built-in spec benchmarks are valid")
+    }
+
+    /// Median DPS of the spec in a key or raid boss pull like `enc`; `None` for other
+    /// encounters and for specs missing from the table
+    pub fn reference_dps(&self, class_name: &str, spec_name: &str, enc: &EncounterSummary) -> Option<f64> {
+        let spec = self.specs.get(&format!("{} {}", spec_name, class_name))?;
+        match enc.encounter_type.as_str() {
+            "mythic_plus" => interpolate(&spec.mythic_plus, enc.key_level?),
+            "boss" => spec.raid.get(raid_difficulty(enc.difficulty_id)?).copied(),
+            _ => None,
+        }
+    }
+
+    /// Set `reference_dps` on the players of every key and raid boss pull
+    pub fn annotate(&self, encounters: &mut [EncounterSummary]) {
+        for enc in encounters.iter_mut() {
+            let references: Vec<Option<f64>> = enc.players.iter()
+                .map(|p| self.reference_dps(&p.class_name, &p.spec_name, enc))
+                .collect();
+            for (player, reference) in enc.players.iter_mut().zip(references) {
+                player.reference_dps = reference;
+            }
+        }
+    }
+}
+
+/// Table key of a raid difficulty ID
+fn raid_difficulty(difficulty_id: u32) -> Option<&'static str> {
+    match difficulty_id {
+        17 => Some("lfr"),
+        14 => Some("normal"),
+        15 => Some("heroic"),
+        16 => Some("mythic"),
+        _ => None,
+    }
+}
+
+/// The value at `level`, linear between the levels around it and clamped to the ends
+fn interpolate(by_level: &BTreeMap<u32, f64>, level: u32) -> Option<f64> {
+    let below = by_level.range(..=level).next_back();
+    let above = by_level.range(level..).next();
+    match (below, above) {
+        (Some((&lo, &lo_dps)), Some((&hi, &hi_dps))) if hi > lo => {
+            Some(lo_dps + (hi_dps - lo_dps) * (level - lo) as f64 / (hi - lo) as f64)
+        }
+        (Some((_, &dps)), _) | (None, Some((_, &dps))) => Some(dps),
+        (None, None) => None,
+    }
+}
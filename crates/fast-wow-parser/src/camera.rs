@@ -0,0 +1,123 @@
+//! Where the replay map should look: the area the whole fight covered and a few presets for
+//! the places it was fought in, computed from the positions in the replay data
+//!
+//! Clusters are found on a grid of [`CLUSTER_CELL`]-yard cells: cells that hold position
+//! samples and touch each other form one area, and the areas holding at least
+//! [`CLUSTER_MIN_SHARE`] of the samples become presets. A fight that stayed in one place gets
+//! no area presets, since the whole fight already frames it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{CameraPreset, EncounterSummary, ReplayCamera, ViewBox};
+
+/// Side of a clustering cell, in yards
+pub const CLUSTER_CELL: f64 = 10.0;
+/// Share of the position samples an area needs to get a preset
+pub const CLUSTER_MIN_SHARE: f64 = 0.15;
+/// Most area presets per fight
+pub const MAX_AREA_PRESETS: usize = 3;
+/// Smallest side of a preset, in yards, so a boss that never moved isn't zoomed in to a point
+pub const MIN_PRESET_SPAN: f64 = 30.0;
+
+/// The camera for `enc`'s replay: bounds and presets, empty if the log had no positions
+pub fn replay_camera(enc: &EncounterSummary) -> ReplayCamera {
+    // (time, x, y) of every player and boss position
+    let samples: Vec<(f64, f64, f64)> = enc.replay_timeline.iter()
+        .filter_map(|s| Some((s.time, s.pos_x?, s.pos_y?)))
+        .chain(enc.boss_positions.iter().copied())
+        .collect();
+    let Some(fight) = bounding_box(samples.iter().map(|&(_, x, y)| (x, y))) else {
+        return ReplayCamera::default();
+    };
+    // A fight on a single map with known bounds is framed by the map itself, so positions
+    // line up with that map's background
+    let bounds = match enc.replay_maps.as_slice() {
+        [m] if m.x_max > m.x_min && m.y_max > m.y_min => ViewBox { min_x: m.x_min, max_x: m.x_max, min_y: m.y_min, max_y: m.y_max },
+        _ => fight,
+    };
+
+    let whole = preset("Whole fight", bounds, bounds, None);
+    let bounds = whole.view;
+    let mut presets = vec![whole];
+    if let Some(boss) = bounding_box(enc.boss_positions.iter().map(|&(_, x, y)| (x, y))) {
+        presets.push(preset("Boss", boss, bounds, None));
+    }
+    let areas = clusters(&samples);
+    if areas.len() > 1 {
+        for (i, (view, from, to)) in areas.into_iter().enumerate() {
+            presets.push(preset(&format!("Area {}", i + 1), view, bounds, Some((from, to))));
+        }
+    }
+    ReplayCamera { bounds: Some(bounds), presets }
+}
+
+fn bounding_box(points: impl Iterator<Item = (f64, f64)>) -> Option<ViewBox> {
+    points.fold(None, |view: Option<ViewBox>, (x, y)| Some(match view {
+        None => ViewBox { min_x: x, max_x: x, min_y: y, max_y: y },
+        Some(v) => ViewBox { min_x: v.min_x.min(x), max_x: v.max_x.max(x), min_y: v.min_y.min(y), max_y: v.max_y.max(y) },
+    }))
+}
+
+/// `view` grown to at least [`MIN_PRESET_SPAN`] on each side, zoomed relative to `bounds`
+fn preset(name: &str, view: ViewBox, bounds: ViewBox, times: Option<(f64, f64)>) -> CameraPreset {
+    let grow = |min: f64, max: f64| {
+        let pad = ((MIN_PRESET_SPAN - (max - min)) / 2.0).max(0.0);
+        (min - pad, max + pad)
+    };
+    let (min_x, max_x) = grow(view.min_x, view.max_x);
+    let (min_y, max_y) = grow(view.min_y, view.max_y);
+    let span = (max_x - min_x).max(max_y - min_y);
+    let bounds_span = (bounds.max_x - bounds.min_x).max(bounds.max_y - bounds.min_y);
+    CameraPreset {
+        name: name.to_string(),
+        view: ViewBox { min_x, max_x, min_y, max_y },
+        zoom: (bounds_span / span).max(1.0),
+        from_secs: times.map(|(from, _)| from),
+        to_secs: times.map(|(_, to)| to),
+    }
+}
+
+/// The areas holding at least [`CLUSTER_MIN_SHARE`] of the samples, most samples first:
+/// (bounds, first sample time, last sample time)
+fn clusters(samples: &[(f64, f64, f64)]) -> Vec<(ViewBox, f64, f64)> {
+    let cell_of = |x: f64, y: f64| ((x / CLUSTER_CELL).floor() as i64, (y / CLUSTER_CELL).floor() as i64);
+    let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, &(_, x, y)) in samples.iter().enumerate() {
+        cells.entry(cell_of(x, y)).or_default().push(i);
+    }
+
+    let mut seen: HashSet<(i64, i64)> = HashSet::new();
+    let mut areas: Vec<Vec<usize>> = Vec::new();
+    for &start in cells.keys() {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut members = Vec::new();
+        let mut stack = vec![start];
+        while let Some((cx, cy)) = stack.pop() {
+            members.extend_from_slice(&cells[&(cx, cy)]);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let next = (cx + dx, cy + dy);
+                    if cells.contains_key(&next) && seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        areas.push(members);
+    }
+
+    let min_samples = samples.len() as f64 * CLUSTER_MIN_SHARE;
+    areas.retain(|members| members.len() as f64 >= min_samples);
+    areas.sort_by_key(|members| std::cmp::Reverse(members.len()));
+    areas.truncate(MAX_AREA_PRESETS);
+    areas.into_iter()
+        .filter_map(|members| {
+            let view = bounding_box(members.iter().map(|&i| (samples[i].1, samples[i].2)))?;
+            let from = members.iter().map(|&i| samples[i].0).fold(f64::INFINITY, f64::min);
+            let to = members.iter().map(|&i| samples[i].0).fold(f64::NEG_INFINITY, f64::max);
+            Some((view, from, to))
+        })
+        .collect()
+}
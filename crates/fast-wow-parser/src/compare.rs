@@ -0,0 +1,165 @@
+//! Run-to-run comparison of two Mythic+ keys in the same dungeon, and the post-mortem of a
+//! key that wasn't timed
+//!
+//! Runs are cut into boss-to-boss sections (matched by boss name, so a different boss
+//! order still lines up) and aligned on enemy forces progress pull by pull.
+
+use crate::game_data;
+use crate::models::*;
+use crate::parser::parse_timestamp_to_secs;
+
+/// Number of evenly spaced enemy forces checkpoints compared
+const COUNT_CHECKPOINTS: u32 = 10;
+
+/// Wipes listed in a depletion report
+const DEPLETION_WIPES: usize = 3;
+
+/// Compare two keys of the same dungeon; `a` is the baseline
+pub fn compare_runs(a: &EncounterSummary, b: &EncounterSummary) -> Result<RouteComparison, String> {
+    if a.encounter_type != "mythic_plus" || b.encounter_type != "mythic_plus" {
+        return Err("Both encounters must be Mythic+ keys".to_string());
+    }
+    if a.encounter_id != b.encounter_id {
+        return Err(format!("Different dungeons: {} vs {}", a.name, b.name));
+    }
+
+    let sections_a = sections(a);
+    let sections_b = sections(b);
+    let mut names: Vec<&String> = sections_a.iter().map(|(name, _)| name).collect();
+    for (name, _) in &sections_b {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    let sections = names.into_iter().map(|name| {
+        let find = |list: &[(String, SectionStats)]| list.iter().find(|(n, _)| n == name).map(|(_, s)| s.clone());
+        let (sa, sb) = (find(&sections_a), find(&sections_b));
+        let (time_delta_secs, deaths_delta) = match (&sa, &sb) {
+            (Some(sa), Some(sb)) => (Some(sb.duration_secs - sa.duration_secs), Some(sb.deaths as i32 - sa.deaths as i32)),
+            _ => (None, None),
+        };
+        SectionDiff { name: name.clone(), a: sa, b: sb, time_delta_secs, deaths_delta }
+    }).collect();
+
+    let progress_a = forces_progress(a);
+    let progress_b = forces_progress(b);
+    let total = progress_a.last().map(|p| p.1).unwrap_or(0).min(progress_b.last().map(|p| p.1).unwrap_or(0));
+    let count_checkpoints = (1..=COUNT_CHECKPOINTS).filter(|_| total > 0).map(|i| {
+        let enemy_forces = total * i / COUNT_CHECKPOINTS;
+        let reached = |progress: &[(f64, u32)]| progress.iter().find(|(_, f)| *f >= enemy_forces).map(|(t, _)| *t);
+        let (a_secs, b_secs) = (reached(&progress_a), reached(&progress_b));
+        CountCheckpoint { enemy_forces, a_secs, b_secs, delta_secs: a_secs.zip(b_secs).map(|(ta, tb)| tb - ta) }
+    }).collect();
+
+    Ok(RouteComparison {
+        dungeon_name: a.name.clone(),
+        a: run_info(a),
+        b: run_info(b),
+        sections,
+        count_checkpoints,
+    })
+}
+
+/// Post-mortem of an untimed key on its own; the server compares it against the best run
+/// in its history with `DepletionReport::set_best_run`
+pub fn depletion_report(enc: &EncounterSummary) -> DepletionReport {
+    let mut biggest_wipes = enc.wipes.clone();
+    biggest_wipes.sort_by(|a, b| b.run_back_secs.unwrap_or(0.0).partial_cmp(&a.run_back_secs.unwrap_or(0.0)).unwrap_or(std::cmp::Ordering::Equal));
+    biggest_wipes.truncate(DEPLETION_WIPES);
+    let deaths = enc.deaths.len() as u32;
+    DepletionReport {
+        over_time_secs: enc.time_remaining_secs.map(|remaining| (-remaining).max(0.0)),
+        deaths,
+        death_penalty_secs: deaths as f64 * game_data::death_penalty_secs(enc.affixes.iter().map(|a| a.id)),
+        biggest_wipes,
+        enemy_forces: enemy_forces(enc),
+        sections: sections(enc).into_iter().map(|(name, stats)| DepletionSection {
+            name,
+            duration_secs: stats.duration_secs,
+            deaths: stats.deaths,
+            best_secs: None,
+            delta_secs: None,
+        }).collect(),
+        best_run: None,
+        enemy_forces_shortfall: None,
+    }
+}
+
+/// Boss-to-boss sections of a key and how long each took
+pub fn section_times(enc: &EncounterSummary) -> Vec<(String, f64)> {
+    sections(enc).into_iter().map(|(name, stats)| (name, stats.duration_secs)).collect()
+}
+
+/// Boss-to-boss sections of a key and the deaths in each
+pub fn section_deaths(enc: &EncounterSummary) -> Vec<(String, u32)> {
+    sections(enc).into_iter().map(|(name, stats)| (name, stats.deaths)).collect()
+}
+
+/// Enemy forces counted over a whole key
+pub fn enemy_forces(enc: &EncounterSummary) -> u32 {
+    forces_progress(enc).last().map(|p| p.1).unwrap_or(0)
+}
+
+fn run_info(enc: &EncounterSummary) -> RunInfo {
+    RunInfo {
+        stable_id: enc.stable_id.clone(),
+        start_time: enc.start_time.clone(),
+        key_level: enc.key_level,
+        duration_secs: enc.duration_secs,
+        timed: enc.timed,
+        deaths: enc.deaths.len() as u32,
+    }
+}
+
+/// Seconds from the start of the key to the start of a segment
+fn segment_offset(enc: &EncounterSummary, seg: &KeySegment) -> f64 {
+    parse_timestamp_to_secs(&seg.start_time) - parse_timestamp_to_secs(&enc.start_time)
+}
+
+/// Boss-to-boss sections, named after the boss that ends them
+fn sections(enc: &EncounterSummary) -> Vec<(String, SectionStats)> {
+    let mut result = Vec::new();
+    let mut current = SectionStats::default();
+    let mut last_boss: Option<&str> = None;
+    for seg in &enc.segments {
+        current.duration_secs += seg.duration_secs;
+        current.deaths += seg.deaths.len() as u32;
+        current.end_secs = segment_offset(enc, seg) + seg.duration_secs;
+        if seg.segment_type == "boss" {
+            current.boss_secs += seg.duration_secs;
+            current.avg_pull_forces = if current.pulls > 0 { current.enemy_forces as f64 / current.pulls as f64 } else { 0.0 };
+            result.push((seg.name.clone(), std::mem::take(&mut current)));
+            last_boss = Some(&seg.name);
+        } else {
+            current.trash_secs += seg.duration_secs;
+            for pull in &seg.pulls {
+                current.pulls += 1;
+                current.enemy_forces += pull.enemy_forces;
+                current.largest_pull_forces = current.largest_pull_forces.max(pull.enemy_forces);
+            }
+        }
+    }
+    if current.duration_secs > 0.0 {
+        current.avg_pull_forces = if current.pulls > 0 { current.enemy_forces as f64 / current.pulls as f64 } else { 0.0 };
+        let name = match last_boss {
+            Some(boss) => format!("After {}", boss),
+            None => "Trash".to_string(),
+        };
+        result.push((name, current));
+    }
+    result
+}
+
+/// Cumulative enemy forces at the end of each pull: (seconds into the key, forces so far)
+fn forces_progress(enc: &EncounterSummary) -> Vec<(f64, u32)> {
+    let mut total = 0;
+    let mut progress = Vec::new();
+    for seg in enc.segments.iter().filter(|s| s.segment_type != "boss") {
+        let seg_offset = segment_offset(enc, seg);
+        for pull in &seg.pulls {
+            total += pull.enemy_forces;
+            progress.push((seg_offset + pull.start_time_offset + pull.duration_secs, total));
+        }
+    }
+    progress
+}
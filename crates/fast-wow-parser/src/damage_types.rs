@@ -0,0 +1,88 @@
+//! Damage done by where it came from: direct hits (melee, casts), periodic ticks (DoTs), pets
+//! and guardians, and procs (trinkets, embellishments, weapon enchants and class procs)
+//!
+//! The log marks ticks with `SPELL_PERIODIC_DAMAGE`, so a spell with both an initial hit and a
+//! DoT (Immolate, Rip) is split between direct and periodic. Pets are the abilities the parser
+//! already groups under a pet's name. Procs can't be told from the log and come from a spell
+//! table, `game_data::PROC_SPELLS` plus the configured `proc_spell_ids`; a proc counts as proc
+//! whether it hits once or ticks.
+
+use std::collections::HashMap;
+
+use crate::models::{AbilityBreakdown, DamageTypeAbility, DamageTypeSplit};
+
+pub const DIRECT: &str = "direct";
+pub const PERIODIC: &str = "periodic";
+pub const PET: &str = "pet";
+pub const PROC: &str = "proc";
+
+/// Split `abilities` (a player's damage done) given the periodic part of each of their own
+/// spells
+pub fn split(abilities: &[AbilityBreakdown], periodic: Option<&HashMap<u64, u64>>, is_proc: impl Fn(u64) -> bool) -> DamageTypeSplit {
+    let mut result = DamageTypeSplit::default();
+    for ability in abilities {
+        if !ability.sub_abilities.is_empty() {
+            result.add(ability, PET, ability.total_amount);
+            continue;
+        }
+        if is_proc(ability.spell_id) {
+            result.add(ability, PROC, ability.total_amount);
+            continue;
+        }
+        let ticks = periodic.and_then(|p| p.get(&ability.spell_id)).copied().unwrap_or(0).min(ability.total_amount);
+        result.add(ability, PERIODIC, ticks);
+        result.add(ability, DIRECT, ability.total_amount - ticks);
+    }
+    result.abilities.sort_by_key(|a| std::cmp::Reverse(a.amount));
+    result.set_percentages();
+    result
+}
+
+impl DamageTypeSplit {
+    fn total(&self) -> u64 {
+        self.direct + self.periodic + self.pet + self.procs
+    }
+
+    fn add(&mut self, ability: &AbilityBreakdown, damage_type: &'static str, amount: u64) {
+        if amount == 0 {
+            return;
+        }
+        match damage_type {
+            DIRECT => self.direct += amount,
+            PERIODIC => self.periodic += amount,
+            PET => self.pet += amount,
+            _ => self.procs += amount,
+        }
+        self.abilities.push(DamageTypeAbility {
+            spell_id: ability.spell_id,
+            spell_name: ability.spell_name.clone(),
+            damage_type,
+            amount,
+        });
+    }
+
+    fn set_percentages(&mut self) {
+        let total = self.total();
+        let pct = |amount: u64| if total > 0 { amount as f64 / total as f64 * 100.0 } else { 0.0 };
+        self.direct_pct = pct(self.direct);
+        self.periodic_pct = pct(self.periodic);
+        self.pet_pct = pct(self.pet);
+        self.procs_pct = pct(self.procs);
+    }
+
+    /// Add another encounter's split, e.g. for a whole dungeon run
+    pub fn merge(&mut self, other: &DamageTypeSplit) {
+        self.direct += other.direct;
+        self.periodic += other.periodic;
+        self.pet += other.pet;
+        self.procs += other.procs;
+        for ability in &other.abilities {
+            match self.abilities.iter_mut().find(|a| a.spell_id == ability.spell_id && a.spell_name == ability.spell_name && a.damage_type == ability.damage_type) {
+                Some(existing) => existing.amount += ability.amount,
+                None => self.abilities.push(ability.clone()),
+            }
+        }
+        self.abilities.sort_by_key(|a| std::cmp::Reverse(a.amount));
+        self.set_percentages();
+    }
+}
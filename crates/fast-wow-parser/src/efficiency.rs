@@ -0,0 +1,98 @@
+//! One 0-100 efficiency score per player for the whole fight, weighing what they put out
+//! against what they took
+//!
+//! The score is the sum of four parts, each reported on its own so nobody has to take the
+//! number on trust:
+//!
+//! - output (40): damage share among the non-healers, or healing share among the healers,
+//!   against an even split of that group; a player at or above the even split gets all 40
+//! - avoidable damage (25): the part of the damage taken that didn't come from avoidable
+//!   abilities
+//! - deaths (20): 10 off per death
+//! - utility (15): interrupts plus dispels against the player with the most in the group;
+//!   full points for everyone if nobody interrupted or dispelled
+
+use std::collections::HashMap;
+
+use crate::models::{EncounterSummary, FightEfficiency, PlayerSummary};
+
+pub const OUTPUT_POINTS: f64 = 40.0;
+pub const AVOIDABLE_POINTS: f64 = 25.0;
+pub const DEATH_POINTS: f64 = 20.0;
+pub const POINTS_PER_DEATH: f64 = 10.0;
+pub const UTILITY_POINTS: f64 = 15.0;
+
+/// The formula, as shown next to the scores
+pub const FORMULA: &str = "score = output + avoidable + deaths + utility. \
+    output = 40 × min(1, share ÷ even share), where share is the player's % of the damage done by non-healers \
+    (of the healing done by healers, for healers) and even share is 100 ÷ players in that group. \
+    avoidable = 25 × (1 − avoidable damage taken ÷ damage taken), 25 if no damage was taken. \
+    deaths = max(0, 20 − 10 × deaths). \
+    utility = 15 × (interrupts + dispels) ÷ the most interrupts + dispels of any player, 15 if nobody had any.";
+
+/// Score every player of `enc` and record the formula on it
+pub fn annotate(enc: &mut EncounterSummary) {
+    if enc.players.is_empty() {
+        return;
+    }
+    let avoidable: HashMap<&str, u64> = enc.avoidable_damage.players.iter()
+        .map(|p| (p.player_name.as_str(), p.damage))
+        .collect();
+    let is_healer = |p: &PlayerSummary| p.role == "healer";
+    let output = |p: &PlayerSummary| if is_healer(p) { p.healing_done } else { p.damage_done };
+    let group_totals = |healers: bool| {
+        let group = enc.players.iter().filter(|p| is_healer(p) == healers);
+        let size = group.clone().count();
+        let total: u64 = group.clone().map(output).sum();
+        let most_utility = group.map(|p| p.interrupts + p.dispels).max().unwrap_or(0);
+        (size, total, most_utility)
+    };
+    let healers = group_totals(true);
+    let others = group_totals(false);
+
+    let scores: Vec<FightEfficiency> = enc.players.iter().map(|p| {
+        let (size, total, most_utility) = if is_healer(p) { healers } else { others };
+        let output_share = if total > 0 { output(p) as f64 / total as f64 * 100.0 } else { 0.0 };
+        let even_share = 100.0 / size as f64;
+        let output_points = OUTPUT_POINTS * (output_share / even_share).min(1.0);
+
+        let avoidable_damage = avoidable.get(p.name.as_str()).copied().unwrap_or(0);
+        let avoidable_points = if p.damage_taken > 0 {
+            AVOIDABLE_POINTS * (1.0 - (avoidable_damage as f64 / p.damage_taken as f64).min(1.0))
+        } else {
+            AVOIDABLE_POINTS
+        };
+
+        let death_points = (DEATH_POINTS - POINTS_PER_DEATH * p.deaths as f64).max(0.0);
+
+        let utility_points = if most_utility > 0 {
+            UTILITY_POINTS * (p.interrupts + p.dispels) as f64 / most_utility as f64
+        } else {
+            UTILITY_POINTS
+        };
+
+        let output_points = round1(output_points);
+        let avoidable_points = round1(avoidable_points);
+        let death_points = round1(death_points);
+        let utility_points = round1(utility_points);
+        FightEfficiency {
+            score: round1(output_points + avoidable_points + death_points + utility_points),
+            output_points,
+            avoidable_points,
+            death_points,
+            utility_points,
+            output_share: round1(output_share),
+            even_share: round1(even_share),
+            avoidable_damage,
+        }
+    }).collect();
+
+    for (p, score) in enc.players.iter_mut().zip(scores) {
+        p.efficiency = Some(score);
+    }
+    enc.efficiency_formula = Some(FORMULA.to_string());
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
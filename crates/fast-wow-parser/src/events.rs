@@ -0,0 +1,122 @@
+//! Event types the parser knows about, and handlers for the ones it doesn't
+//!
+//! A patch that adds a new event type doesn't break parsing: lines with an event type not
+//! in [`is_known`] are counted and sampled into [`ParseStats`](crate::models::ParseStats).
+//! To make use of one without touching the parser, register an [`EventHandler`] with
+//! [`CombatLogParser::with_handler`](crate::CombatLogParser::with_handler); its report ends
+//! up in `CombatLogSummary::extensions`. The parser's own features are handlers too, run by
+//! each encounter's tracker on events that carry a [`CombatContext`].
+
+use std::collections::HashMap;
+
+use crate::log_format::FieldMap;
+use crate::parser::unquote;
+
+/// One tokenized log line, as handlers see it
+#[derive(Debug, Clone, Copy)]
+pub struct LogEvent<'a> {
+    pub timestamp: &'a str,
+    pub timestamp_secs: f64,
+    pub event_type: &'a str,
+    /// All fields, the event type first (see `parser::parse_csv_fields`)
+    pub fields: &'a [&'a str],
+    /// What the encounter being tracked knows about the event. `None` for handlers registered
+    /// with `with_handler`, which see every line.
+    pub combat: Option<CombatContext<'a>>,
+}
+
+impl LogEvent<'_> {
+    /// Spell id of a spell event (0 for swings and bookkeeping events)
+    pub fn spell_id(&self) -> u64 {
+        self.fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0)
+    }
+
+    pub fn spell_name(&self) -> String {
+        self.fields.get(10).map(|s| unquote(s)).unwrap_or_default()
+    }
+}
+
+/// A combat event's units, resolved by the tracker of the encounter it is part of
+#[derive(Debug, Clone, Copy)]
+pub struct CombatContext<'a> {
+    pub source_guid: &'a str,
+    /// Without the realm
+    pub source_name: &'a str,
+    pub dest_guid: &'a str,
+    /// Without the realm
+    pub dest_name: &'a str,
+    /// The source, or the player owning it if it is a pet
+    pub effective_source: &'a str,
+    /// Owner of each pet and guardian summoned so far
+    pub pet_owners: &'a HashMap<String, String>,
+    /// Arena team of each player
+    pub pvp_teams: &'a HashMap<String, u32>,
+    /// Where the fields are in this log's format
+    pub field_map: &'a FieldMap,
+    /// Boss phase the event is in, counting from 1
+    pub phase: u32,
+}
+
+impl CombatContext<'_> {
+    /// Whether the (effective) source is a player acting against an enemy of theirs
+    pub fn is_hostile(&self) -> bool {
+        crate::handlers::is_enemy(self.pvp_teams, self.effective_source, self.dest_guid)
+    }
+
+    /// The player owning `guid`, following pets of pets; `None` for players and unowned units
+    pub fn owner_of(&self, guid: &str) -> Option<String> {
+        crate::handlers::owner_of(self.pet_owners, guid)
+    }
+}
+
+/// Extra event handling plugged into a parse
+pub trait EventHandler: Send {
+    /// Key of the handler's report in `CombatLogSummary::extensions`
+    fn name(&self) -> &str;
+
+    /// Whether `handle` wants events of this type. Claiming an unknown type keeps it out of
+    /// the unknown event stats.
+    fn handles(&self, event_type: &str) -> bool;
+
+    fn handle(&mut self, event: &LogEvent);
+
+    /// What to put in the summary once the log is parsed
+    fn report(&self) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+/// Whether the event type is one the game writes as of the current patch, handled or not.
+/// Anything else is new (or garbage) and is reported as unknown.
+pub fn is_known(event_type: &str) -> bool {
+    matches!(event_type,
+        // Log and instance bookkeeping
+        "COMBAT_LOG_VERSION" | "ZONE_CHANGE" | "MAP_CHANGE" | "COMBATANT_INFO" |
+        "ENCOUNTER_START" | "ENCOUNTER_END" | "ENCOUNTER_PHASE_CHANGE" |
+        "CHALLENGE_MODE_START" | "CHALLENGE_MODE_END" | "ARENA_MATCH_START" | "ARENA_MATCH_END" |
+        "WORLD_MARKER_PLACED" | "WORLD_MARKER_REMOVED" | "EMOTE" |
+        // Melee and ranged
+        "SWING_DAMAGE" | "SWING_DAMAGE_LANDED" | "SWING_MISSED" | "RANGE_DAMAGE" | "RANGE_MISSED" |
+        // Spells
+        "SPELL_DAMAGE" | "SPELL_DAMAGE_SUPPORT" | "SPELL_MISSED" | "SPELL_HEAL" | "SPELL_HEAL_SUPPORT" |
+        "SPELL_ABSORBED" | "SPELL_ENERGIZE" | "SPELL_DRAIN" | "SPELL_LEECH" | "SPELL_INSTAKILL" |
+        "SPELL_INTERRUPT" | "SPELL_DISPEL" | "SPELL_DISPEL_FAILED" | "SPELL_STOLEN" |
+        "SPELL_EXTRA_ATTACKS" | "SPELL_DURABILITY_DAMAGE" | "SPELL_DURABILITY_DAMAGE_ALL" |
+        "SPELL_CAST_START" | "SPELL_CAST_SUCCESS" | "SPELL_CAST_FAILED" |
+        "SPELL_EMPOWER_START" | "SPELL_EMPOWER_END" | "SPELL_EMPOWER_INTERRUPT" |
+        "SPELL_CREATE" | "SPELL_SUMMON" | "SPELL_RESURRECT" |
+        "SPELL_BUILDING_DAMAGE" | "SPELL_BUILDING_HEAL" |
+        // Periodic
+        "SPELL_PERIODIC_DAMAGE" | "SPELL_PERIODIC_MISSED" | "SPELL_PERIODIC_HEAL" |
+        "SPELL_PERIODIC_ENERGIZE" | "SPELL_PERIODIC_DRAIN" | "SPELL_PERIODIC_LEECH" |
+        // Auras
+        "SPELL_AURA_APPLIED" | "SPELL_AURA_APPLIED_DOSE" | "SPELL_AURA_REMOVED" | "SPELL_AURA_REMOVED_DOSE" |
+        "SPELL_AURA_REFRESH" | "SPELL_AURA_BROKEN" | "SPELL_AURA_BROKEN_SPELL" |
+        "ENCHANT_APPLIED" | "ENCHANT_REMOVED" |
+        // Other damage
+        "ENVIRONMENTAL_DAMAGE" | "DAMAGE_SHIELD" | "DAMAGE_SHIELD_MISSED" | "DAMAGE_SPLIT" |
+        "STAGGER_CLEAR" | "STAGGER_PREVENTED" |
+        // Units
+        "UNIT_DIED" | "UNIT_DESTROYED" | "UNIT_DISSIPATES" | "PARTY_KILL"
+    )
+}
@@ -0,0 +1,123 @@
+//! Chat-sized text summaries of a fight, laid out like the reports Details! pastes into
+//! chat, for quick Discord pastes
+
+use crate::models::{AbilityBreakdown, EncounterSummary, PlayerSummary};
+
+/// Abilities listed under each player
+const TOP_ABILITIES: usize = 3;
+
+/// Which meter to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Meter {
+    #[default]
+    Damage,
+    Healing,
+}
+
+impl Meter {
+    /// "damage" or "healing" (also "dps"/"hps"); anything else is None
+    pub fn parse(text: &str) -> Option<Meter> {
+        match text.to_ascii_lowercase().as_str() {
+            "damage" | "dps" => Some(Meter::Damage),
+            "healing" | "hps" | "heal" => Some(Meter::Healing),
+            _ => None,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Meter::Damage => "Damage Done",
+            Meter::Healing => "Healing Done",
+        }
+    }
+
+    fn total(self, player: &PlayerSummary) -> u64 {
+        match self {
+            Meter::Damage => player.damage_done,
+            Meter::Healing => player.healing_done,
+        }
+    }
+
+    fn per_second(self, player: &PlayerSummary) -> f64 {
+        match self {
+            Meter::Damage => player.dps,
+            Meter::Healing => player.hps,
+        }
+    }
+
+    fn abilities(self, player: &PlayerSummary) -> &[AbilityBreakdown] {
+        match self {
+            Meter::Damage => &player.abilities,
+            // Older summaries only have the combined list
+            Meter::Healing if player.heal_abilities.is_empty() => &player.abilities,
+            Meter::Healing => &player.heal_abilities,
+        }
+    }
+}
+
+/// "45.2M", "248.1K", "950"
+pub fn short_number(value: f64) -> String {
+    if value >= 1e9 {
+        format!("{:.2}B", value / 1e9)
+    } else if value >= 1e6 {
+        format!("{:.1}M", value / 1e6)
+    } else if value >= 1e3 {
+        format!("{:.1}K", value / 1e3)
+    } else {
+        format!("{:.0}", value)
+    }
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Character name without realm and region
+fn short_name(name: &str) -> &str {
+    name.split('-').next().unwrap_or(name)
+}
+
+/// The top `players` of a meter, each with their top abilities:
+///
+/// ```text
+/// Details!: Damage Done for Ulgrax the Devourer [3:02 Kill]
+/// 1. Warriorone 45.2M (248.1K, 31.2%)
+///    Bladestorm 12.1M (26.8%), Mortal Strike 8.0M (17.7%), Overpower 4.1M (9.1%)
+/// ```
+pub fn details_text(enc: &EncounterSummary, meter: Meter, players: usize) -> String {
+    let outcome = match enc.encounter_type.as_str() {
+        "boss" | "dungeon" => Some(if enc.success { "Kill" } else { "Wipe" }),
+        "mythic_plus" => Some(if enc.timed == Some(true) { "Timed" } else { "Over time" }),
+        _ => None,
+    };
+    let header = match outcome {
+        Some(outcome) => format!("{} {}", format_duration(enc.duration_secs), outcome),
+        None => format_duration(enc.duration_secs),
+    };
+    let mut lines = vec![format!("Details!: {} for {} [{}]", meter.title(), enc.name, header)];
+
+    let mut ranked: Vec<&PlayerSummary> = enc.players.iter().filter(|p| meter.total(p) > 0).collect();
+    ranked.sort_by_key(|p| std::cmp::Reverse(meter.total(p)));
+    let group_total: u64 = ranked.iter().map(|p| meter.total(p)).sum();
+    for (rank, player) in ranked.iter().take(players).enumerate() {
+        let total = meter.total(player);
+        lines.push(format!(
+            "{}. {} {} ({}, {:.1}%)",
+            rank + 1,
+            short_name(&player.name),
+            short_number(total as f64),
+            short_number(meter.per_second(player)),
+            total as f64 / group_total.max(1) as f64 * 100.0,
+        ));
+        let mut abilities: Vec<&AbilityBreakdown> = meter.abilities(player).iter().filter(|a| a.total_amount > 0).collect();
+        abilities.sort_by_key(|a| std::cmp::Reverse(a.total_amount));
+        let top: Vec<String> = abilities.iter().take(TOP_ABILITIES)
+            .map(|a| format!("{} {} ({:.1}%)", a.spell_name, short_number(a.total_amount as f64), a.total_amount as f64 / total as f64 * 100.0))
+            .collect();
+        if !top.is_empty() {
+            lines.push(format!("   {}", top.join(", ")));
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,642 @@
+//! Bundled static game data tables (instances, seasons, ...)
+//!
+//! IDs come straight from the combat log: `ENCOUNTER_START` encounter IDs and the
+//! instance/map IDs used by `ZONE_CHANGE` and `CHALLENGE_MODE_START`.
+
+mod spell_cooldowns;
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Static metadata for a raid or dungeon instance
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceInfo {
+    pub name: &'static str,
+    pub season: &'static str,
+    /// Blizzard icon file name (render via `icon_url`)
+    pub icon: &'static str,
+}
+
+const NERUBAR_PALACE: InstanceInfo = InstanceInfo {
+    name: "Nerub-ar Palace",
+    season: "The War Within Season 1",
+    icon: "inv_achievement_raidnerubian",
+};
+const LIBERATION_OF_UNDERMINE: InstanceInfo = InstanceInfo {
+    name: "Liberation of Undermine",
+    season: "The War Within Season 2",
+    icon: "inv_achievement_raid_undermine",
+};
+const MANAFORGE_OMEGA: InstanceInfo = InstanceInfo {
+    name: "Manaforge Omega",
+    season: "The War Within Season 3",
+    icon: "inv_achievement_raid_manaforgeomega",
+};
+
+/// Map an instance/map ID (from ZONE_CHANGE or CHALLENGE_MODE_START) to instance metadata
+pub fn instance_for_zone(zone_id: u64) -> Option<InstanceInfo> {
+    let (name, season, icon) = match zone_id {
+        // Raids
+        2657 => return Some(NERUBAR_PALACE),
+        2769 => return Some(LIBERATION_OF_UNDERMINE),
+        2810 => return Some(MANAFORGE_OMEGA),
+        // The War Within dungeons
+        2652 => ("The Stonevault", "The War Within Season 1", "inv_achievement_dungeon_stonevault"),
+        2660 => ("Ara-Kara, City of Echoes", "The War Within Season 1", "inv_achievement_dungeon_arakaracityofechoes"),
+        2669 => ("City of Threads", "The War Within Season 1", "inv_achievement_dungeon_cityofthreads"),
+        2662 => ("The Dawnbreaker", "The War Within Season 1", "inv_achievement_dungeon_dawnbreaker"),
+        2651 => ("Darkflame Cleft", "The War Within Season 2", "inv_achievement_dungeon_darkflamecleft"),
+        2649 => ("Priory of the Sacred Flame", "The War Within Season 2", "inv_achievement_dungeon_prioryofthesacredflame"),
+        2648 => ("The Rookery", "The War Within Season 2", "inv_achievement_dungeon_rookery"),
+        2661 => ("Cinderbrew Meadery", "The War Within Season 2", "inv_achievement_dungeon_cinderbrewmeadery"),
+        2773 => ("Operation: Floodgate", "The War Within Season 2", "inv_achievement_dungeon_operationfloodgate"),
+        2830 => ("Eco-Dome Al'dani", "The War Within Season 3", "inv_achievement_dungeon_ecodomealdani"),
+        // Returning dungeons
+        2290 => ("Mists of Tirna Scithe", "The War Within Season 1", "achievement_dungeon_mistsoftirnascithe"),
+        2286 => ("The Necrotic Wake", "The War Within Season 1", "achievement_dungeon_theneroticwake"),
+        1822 => ("Siege of Boralus", "The War Within Season 1", "achievement_dungeon_siegeofboralus"),
+        670 => ("Grim Batol", "The War Within Season 1", "achievement_dungeon_grimbatol"),
+        2097 => ("Operation: Mechagon - Workshop", "The War Within Season 2", "achievement_boss_mechagon"),
+        1594 => ("The MOTHERLODE!!", "The War Within Season 2", "achievement_dungeon_kezan"),
+        2293 => ("Theater of Pain", "The War Within Season 2", "achievement_dungeon_theaterofpain"),
+        2287 => ("Halls of Atonement", "The War Within Season 3", "achievement_dungeon_hallsofattonement"),
+        2441 => ("Tazavesh, the Veiled Market", "The War Within Season 3", "achievement_dungeon_brokerdungeon"),
+        _ => return None,
+    };
+    Some(InstanceInfo { name, season, icon })
+}
+
+/// Map a boss encounter ID (from ENCOUNTER_START) to the instance it belongs to
+pub fn instance_for_encounter(encounter_id: u64) -> Option<InstanceInfo> {
+    match encounter_id {
+        // Nerub-ar Palace
+        2902 | 2917 | 2898 | 2918 | 2919 | 2920 | 2921 | 2922 => Some(NERUBAR_PALACE),
+        // Liberation of Undermine
+        3009..=3016 => Some(LIBERATION_OF_UNDERMINE),
+        // Manaforge Omega
+        3122 | 3129..=3135 => Some(MANAFORGE_OMEGA),
+        _ => None,
+    }
+}
+
+/// Full icon URL for a Blizzard icon file name
+pub fn icon_url(icon: &str) -> String {
+    format!("https://wow.zamimg.com/images/wow/icons/large/{}.jpg", icon)
+}
+
+/// Map a Mythic+ affix ID to (name, description, icon)
+pub fn affix_info(affix_id: u32) -> Option<(&'static str, &'static str, &'static str)> {
+    match affix_id {
+        9 => Some(("Tyrannical", "Bosses have 30% more health and inflict up to 15% increased damage.", "achievement_boss_archaedas")),
+        10 => Some(("Fortified", "Non-boss enemies have 20% more health and inflict up to 30% increased damage.", "ability_toughness")),
+        147 => Some(("Xal'atath's Guile", "Xal'atath betrays players, revoking her bargains and increasing the time penalty for deaths by 15 sec.", "ability_racial_chillofnight")),
+        148 => Some(("Xal'atath's Bargain: Ascendant", "Xal'atath's Ascendant orbs empower players who gather them.", "inv_cosmicvoid_orb")),
+        152 => Some(("Challenger's Peril", "Dying subtracts 15 seconds from the timer; the timer is increased by 90 seconds.", "ability_socererking_arcanewrath")),
+        158 => Some(("Xal'atath's Bargain: Voidbound", "Xal'atath summons a Void Emissary that empowers enemies while alive.", "inv_cosmicvoid_buff")),
+        159 => Some(("Xal'atath's Bargain: Oblivion", "Oblivion shards empower enemies unless collected by players.", "spell_priest_void_blast")),
+        160 => Some(("Xal'atath's Bargain: Devour", "Devouring essence drains players until healed through.", "ability_argus_soulbombdebuffsmall")),
+        162 => Some(("Xal'atath's Bargain: Pulsar", "Pulsar orbs release energy that empowers players who absorb it.", "inv_ability_voidweaverpriest_entropicrift")),
+        3 => Some(("Volcanic", "Enemies cause volcanic plumes to erupt beneath distant players.", "spell_shaman_lavasurge")),
+        4 => Some(("Necrotic", "Enemy melee attacks apply a stacking blight that reduces healing received.", "spell_deathknight_necroticplague")),
+        6 => Some(("Raging", "Non-boss enemies enrage at 30% health remaining.", "ability_warrior_focusedrage")),
+        7 => Some(("Bolstering", "When a non-boss enemy dies, its death empowers nearby allies.", "ability_warrior_battleshout")),
+        8 => Some(("Sanguine", "When slain, non-boss enemies leave behind a lingering pool of ichor.", "spell_shadow_bloodboil")),
+        11 => Some(("Bursting", "When slain, non-boss enemies explode, causing all players to suffer damage over time.", "ability_ironmaidens_whirlofblood")),
+        12 => Some(("Grievous", "Injured players suffer increasing damage over time until healed above 90% health.", "ability_backstab")),
+        13 => Some(("Explosive", "Enemies periodically summon Explosive Orbs that detonate if not destroyed.", "spell_fire_felflamering_red")),
+        14 => Some(("Quaking", "Periodically, all players emit a shockwave, inflicting damage and interrupting nearby allies.", "spell_nature_earthquake")),
+        123 => Some(("Spiteful", "Fiends rise from the corpses of non-boss enemies and pursue random players.", "spell_holy_prayerofshadowprotection")),
+        124 => Some(("Storming", "Enemies periodically summon damaging whirlwinds.", "spell_nature_cyclone")),
+        134 => Some(("Entangling", "Entangling vines periodically appear and snare players.", "inv_misc_root_01")),
+        135 => Some(("Afflicted", "Afflicted Souls appear that must be healed or dispelled.", "spell_misc_emotionsad")),
+        136 => Some(("Incorporeal", "Incorporeal Beings appear that must be interrupted or crowd controlled.", "achievement_boss_anomalus")),
+        _ => None,
+    }
+}
+
+/// Challenge mode IDs of the two Tazavesh dungeons, which share instance ID 2441
+const TAZAVESH_STREETS_OF_WONDER: u64 = 391;
+const TAZAVESH_SOLEAHS_GAMBIT: u64 = 392;
+
+/// Mythic+ par time (in seconds) for a dungeon, keyed by instance ID and, where dungeons
+/// share an instance, challenge mode ID
+pub fn par_time_secs(zone_id: u64, challenge_mode_id: u64) -> Option<f64> {
+    let minutes = match zone_id {
+        // The War Within Season 1
+        2652 => 33.0, // The Stonevault
+        2660 => 30.0, // Ara-Kara, City of Echoes
+        2669 => 38.0, // City of Threads
+        2662 => 35.0, // The Dawnbreaker
+        2290 => 30.0, // Mists of Tirna Scithe
+        2286 => 36.0, // The Necrotic Wake
+        1822 => 33.0, // Siege of Boralus
+        670 => 34.0,  // Grim Batol
+        // The War Within Season 2
+        2651 => 31.0, // Darkflame Cleft
+        2649 => 32.5, // Priory of the Sacred Flame
+        2648 => 29.0, // The Rookery
+        2661 => 33.0, // Cinderbrew Meadery
+        2773 => 33.0, // Operation: Floodgate
+        2097 => 32.0, // Operation: Mechagon - Workshop
+        1594 => 33.0, // The MOTHERLODE!!
+        2293 => 34.0, // Theater of Pain
+        // The War Within Season 3
+        2830 => 31.0, // Eco-Dome Al'dani
+        2287 => 31.0, // Halls of Atonement
+        2441 => match challenge_mode_id {
+            TAZAVESH_STREETS_OF_WONDER => 35.0,
+            TAZAVESH_SOLEAHS_GAMBIT => 30.0,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(minutes * 60.0)
+}
+
+/// Seconds each player death adds to the Mythic+ timer
+pub const DEATH_PENALTY_SECS: f64 = 5.0;
+
+/// Xal'atath's Guile, which raises the death penalty by 15 seconds
+const GUILE_AFFIX_ID: u32 = 147;
+
+/// Death penalty of a key with these affixes
+pub fn death_penalty_secs(affix_ids: impl IntoIterator<Item = u32>) -> f64 {
+    let guile = affix_ids.into_iter().any(|id| id == GUILE_AFFIX_ID);
+    DEATH_PENALTY_SECS + if guile { 15.0 } else { 0.0 }
+}
+
+/// Number of bosses in a Mythic+ dungeon, keyed like [`par_time_secs`]
+pub fn dungeon_boss_count(zone_id: u64, challenge_mode_id: u64) -> Option<u32> {
+    let count = match zone_id {
+        // The War Within Season 1
+        2652 => 4, // The Stonevault
+        2660 => 3, // Ara-Kara, City of Echoes
+        2669 => 4, // City of Threads
+        2662 => 3, // The Dawnbreaker
+        2290 => 3, // Mists of Tirna Scithe
+        2286 => 4, // The Necrotic Wake
+        1822 => 4, // Siege of Boralus
+        670 => 4,  // Grim Batol
+        // The War Within Season 2
+        2651 => 4, // Darkflame Cleft
+        2649 => 3, // Priory of the Sacred Flame
+        2648 => 3, // The Rookery
+        2661 => 4, // Cinderbrew Meadery
+        2773 => 4, // Operation: Floodgate
+        2097 => 4, // Operation: Mechagon - Workshop
+        1594 => 4, // The MOTHERLODE!!
+        2293 => 5, // Theater of Pain
+        // The War Within Season 3
+        2830 => 3, // Eco-Dome Al'dani
+        2287 => 4, // Halls of Atonement
+        2441 => match challenge_mode_id {
+            TAZAVESH_STREETS_OF_WONDER => 5,
+            TAZAVESH_SOLEAHS_GAMBIT => 3,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(count)
+}
+
+/// Enemy forces count granted by a dungeon NPC when it dies, keyed by NPC ID.
+/// Unknown NPCs (and bosses) grant nothing.
+pub fn enemy_forces(npc_id: u64) -> Option<u32> {
+    let count = match npc_id {
+        // Darkflame Cleft
+        208450 => 5,  // Wandering Candle
+        210812 => 5,  // Royal Wicklighter
+        210818 => 1,  // Lowly Moleherd
+        211121 => 2,  // Rank Overseer
+        211977 => 4,  // Pack Mole
+        212383 => 10, // Kobold Taskworker
+        213913 => 10, // Kobold Flametender
+        // The Rookery
+        207186 => 8,  // Unruly Stormrook
+        207198 => 4,  // Cursed Thunderer
+        209801 => 8,  // Quartermaster Koratite
+        212739 => 20, // Radiating Voidstone
+        212786 => 5,  // Voidrider
+        214419 => 3,  // Void-Cursed Crusher
+        // Priory of the Sacred Flame
+        206694 => 4,  // Fervent Sharpshooter
+        206696 => 8,  // Arathi Knight
+        206697 => 6,  // Devout Priest
+        206698 => 6,  // Fanatical Conjuror
+        206699 => 5,  // War Lynx
+        206710 => 12, // Lightspawn
+        _ => return None,
+    };
+    Some(count)
+}
+
+/// Spells that resurrect a player in combat
+pub const BATTLE_RES_SPELLS: &[u64] = &[
+    20484,  // Rebirth
+    61999,  // Raise Ally
+    95750,  // Soulstone Resurrection
+    391054, // Intercession
+    345130, // Disposable Spectrophasic Reanimator
+];
+
+/// Health potion spell IDs (all share one potion cooldown)
+pub const HEALTH_POTION_SPELLS: &[u64] = &[
+    431416, // Algari Healing Potion
+    431419, // Cavedweller's Delight
+    452767, // Heartseeking Health Injector
+];
+
+/// Shared potion cooldown in seconds
+pub const POTION_COOLDOWN_SECS: f64 = 300.0;
+
+/// Leech (tertiary stat) heal
+pub const LEECH_SPELL_ID: u64 = 143924;
+
+/// Common channeled spells and their base (unhasted) channel time in seconds
+pub const CHANNELED_SPELLS: &[(u64, f64)] = &[
+    (15407, 4.5),  // Mind Flay
+    (263165, 3.0), // Void Torrent
+    (47540, 2.0),  // Penance
+    (64843, 8.0),  // Divine Hymn
+    (5143, 2.5),   // Arcane Missiles
+    (205021, 4.0), // Ray of Frost
+    (234153, 5.0), // Drain Life
+    (198013, 2.0), // Eye Beam
+    (113656, 4.0), // Fists of Fury
+    (115175, 8.0), // Soothing Mist
+    (257044, 2.0), // Rapid Fire
+    (356995, 3.0), // Disintegrate
+    (740, 8.0),    // Tranquility
+];
+
+/// Base channel time of a known channeled spell
+pub fn channel_duration(spell_id: u64) -> Option<f64> {
+    CHANNELED_SPELLS.iter().find(|(id, _)| *id == spell_id).map(|(_, secs)| *secs)
+}
+
+/// Taunt spells
+pub const TAUNT_SPELLS: &[(u64, &str)] = &[
+    (355, "Taunt"),
+    (62124, "Hand of Reckoning"),
+    (56222, "Dark Command"),
+    (49576, "Death Grip"),
+    (115546, "Provoke"),
+    (6795, "Growl"),
+    (185245, "Torment"),
+];
+
+pub fn taunt_name(spell_id: u64) -> Option<&'static str> {
+    TAUNT_SPELLS.iter().find(|(id, _)| *id == spell_id).map(|(_, name)| *name)
+}
+
+/// Crowd control auras as (spell_id, kind). Kinds follow the diminishing returns
+/// categories: stun, incapacitate, disorient, silence, root.
+pub const CROWD_CONTROL_SPELLS: &[(u64, &str)] = &[
+    (408, "stun"),            // Kidney Shot
+    (1833, "stun"),           // Cheap Shot
+    (853, "stun"),            // Hammer of Justice
+    (5211, "stun"),           // Mighty Bash
+    (163505, "stun"),         // Rake
+    (203123, "stun"),         // Maim
+    (179057, "stun"),         // Chaos Nova
+    (211881, "stun"),         // Fel Eruption
+    (119381, "stun"),         // Leg Sweep
+    (30283, "stun"),          // Shadowfury
+    (89766, "stun"),          // Axe Toss
+    (132168, "stun"),         // Shockwave
+    (132169, "stun"),         // Storm Bolt
+    (108194, "stun"),         // Asphyxiate
+    (221562, "stun"),         // Asphyxiate (Blood)
+    (24394, "stun"),          // Intimidation
+    (305485, "stun"),         // Lightning Lasso
+    (20549, "stun"),          // War Stomp
+    (118, "incapacitate"),    // Polymorph
+    (6770, "incapacitate"),   // Sap
+    (1776, "incapacitate"),   // Gouge
+    (20066, "incapacitate"),  // Repentance
+    (51514, "incapacitate"),  // Hex
+    (3355, "incapacitate"),   // Freezing Trap
+    (213691, "incapacitate"), // Scatter Shot
+    (115078, "incapacitate"), // Paralysis
+    (99, "incapacitate"),     // Incapacitating Roar
+    (217832, "incapacitate"), // Imprison
+    (82691, "incapacitate"),  // Ring of Frost
+    (710, "incapacitate"),    // Banish
+    (6358, "incapacitate"),   // Seduction
+    (197214, "incapacitate"), // Sundering
+    (2094, "disorient"),      // Blind
+    (118699, "disorient"),    // Fear
+    (5484, "disorient"),      // Howl of Terror
+    (5246, "disorient"),      // Intimidating Shout
+    (8122, "disorient"),      // Psychic Scream
+    (605, "disorient"),       // Mind Control
+    (31661, "disorient"),     // Dragon's Breath
+    (105421, "disorient"),    // Blinding Light
+    (207167, "disorient"),    // Blinding Sleet
+    (33786, "disorient"),     // Cyclone
+    (198909, "disorient"),    // Song of Chi-Ji
+    (360806, "disorient"),    // Sleep Walk
+    (15487, "silence"),       // Silence
+    (47476, "silence"),       // Strangulate
+    (1330, "silence"),        // Garrote
+    (81261, "silence"),       // Solar Beam
+    (204490, "silence"),      // Sigil of Silence
+    (339, "root"),            // Entangling Roots
+    (122, "root"),            // Frost Nova
+    (33395, "root"),          // Freeze
+    (64695, "root"),          // Earthgrab
+    (102359, "root"),         // Mass Entanglement
+    (116706, "root"),         // Disable
+    (162480, "root"),         // Steel Trap
+];
+
+pub fn crowd_control(spell_id: u64) -> Option<&'static str> {
+    CROWD_CONTROL_SPELLS.iter().find(|(id, _)| *id == spell_id).map(|(_, kind)| *kind)
+}
+
+/// Threat redirects a damage dealer casts on the tank before a pull
+pub const THREAT_REDIRECT_SPELLS: &[(u64, &str)] = &[
+    (34477, "Misdirection"),
+    (57934, "Tricks of the Trade"),
+];
+
+pub fn threat_redirect_name(spell_id: u64) -> Option<&'static str> {
+    THREAT_REDIRECT_SPELLS.iter().find(|(id, _)| *id == spell_id).map(|(_, name)| *name)
+}
+
+/// Avoidable affix damage, common to every dungeon. Dungeon and boss mechanics are
+/// configured per user (`avoidable_spell_ids`).
+pub const AVOIDABLE_SPELLS: &[u64] = &[
+    209862, // Volcanic Plume
+    240448, // Quaking
+    226512, // Sanguine Ichor
+    343520, // Storming
+];
+
+/// Damage that procs from weapons, passives and class mechanics rather than being cast.
+/// Trinkets and embellishments change every season and are configured per user
+/// (`proc_spell_ids`).
+pub const PROC_SPELLS: &[u64] = &[
+    25504,  // Windfury Attack
+    10444,  // Flametongue Attack
+    86392,  // Main Gauche
+    22482,  // Blade Flurry
+];
+
+/// Training dummy NPC IDs from the capital cities, class halls and garrisons. Dummies are
+/// also recognised by name, so this mainly covers localized clients.
+pub const TRAINING_DUMMIES: &[u64] = &[
+    31144, 31146, 32666, 32667, 46647, 67127,         // Northrend to Pandaria
+    87317, 87318, 87320, 87321, 87322, 87329,         // Garrison
+    113858, 113859, 113862, 113863, 113864, 113871,   // Class halls
+    144081, 144082, 144085, 144086,                   // Boralus and Dazar'alor
+    174569, 174570, 174571, 174572,                   // Oribos
+    194643, 194644, 194648, 194649,                   // Valdrakken
+    225983, 225984, 225985,                           // Dornogal
+];
+
+/// Whether an enemy is a training dummy, by NPC ID or (English) name
+pub fn is_training_dummy(npc_id: Option<u64>, name: &str) -> bool {
+    npc_id.is_some_and(|id| TRAINING_DUMMIES.contains(&id))
+        || name.ends_with("Training Dummy")
+        || name.ends_with("Target Dummy")
+}
+
+/// Raid healing cooldowns as (spell_id, name, effect duration in seconds). Instant ones
+/// (Revival, Holy Word: Salvation) get a few seconds so a cast just before the damage counts.
+pub const RAID_HEALING_COOLDOWNS: &[(u64, &str, f64)] = &[
+    (31821, "Aura Mastery", 8.0),
+    (740, "Tranquility", 8.0),
+    (62618, "Power Word: Barrier", 10.0),
+    (64843, "Divine Hymn", 8.0),
+    (265202, "Holy Word: Salvation", 3.0),
+    (115310, "Revival", 3.0),
+    (388615, "Restoral", 3.0),
+    (98008, "Spirit Link Totem", 6.0),
+    (108280, "Healing Tide Totem", 10.0),
+    (363534, "Rewind", 5.0),
+    (359816, "Dream Flight", 6.0),
+];
+
+/// Name and effect duration of a raid healing cooldown
+pub fn raid_healing_cooldown(spell_id: u64) -> Option<(&'static str, f64)> {
+    RAID_HEALING_COOLDOWNS.iter().find(|(id, _, _)| *id == spell_id).map(|(_, name, secs)| (*name, *secs))
+}
+
+/// What a cooldown in the spell cooldown table is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CooldownCategory {
+    /// Personal defensive
+    Defensive,
+    /// Defensive cast on another player (Pain Suppression, Ironbark)
+    External,
+    /// Raid-wide healing or damage reduction
+    RaidCooldown,
+    Interrupt,
+    Taunt,
+    BattleRes,
+    Potion,
+    /// A major damage cooldown of 2-3 minutes a spec's damage is planned around (Combustion,
+    /// Avenging Wrath)
+    Burst,
+    /// Anything else with a cooldown (damage and utility abilities)
+    Other,
+}
+
+/// A spell's base cooldown, before haste, talents and cooldown reduction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpellCooldown {
+    pub spell_id: u64,
+    pub cooldown_secs: f64,
+    pub category: CooldownCategory,
+    /// Class that has the spell (as in `spec_info`), empty for items
+    pub class: &'static str,
+    pub name: &'static str,
+}
+
+/// The bundled spell cooldown table, sorted by spell ID. It is generated with
+/// `spell_fetcher --cooldowns` from the Blizzard talent trees.
+pub fn spell_cooldowns() -> &'static [SpellCooldown] {
+    spell_cooldowns::SPELL_COOLDOWNS
+}
+
+/// Base cooldown of a spell, if it is in the bundled table
+pub fn spell_cooldown(spell_id: u64) -> Option<&'static SpellCooldown> {
+    let table = spell_cooldowns::SPELL_COOLDOWNS;
+    table.binary_search_by_key(&spell_id, |c| c.spell_id).ok().map(|i| &table[i])
+}
+
+/// Whether an aura on a player is a defensive: personal, external or raid-wide
+pub fn is_defensive_aura(spell_id: u64) -> bool {
+    spell_cooldown(spell_id).is_some_and(|c| matches!(c.category, CooldownCategory::Defensive | CooldownCategory::External | CooldownCategory::RaidCooldown))
+}
+
+/// Whether a spell is a major damage cooldown
+pub fn is_burst_cooldown(spell_id: u64) -> bool {
+    spell_cooldown(spell_id).is_some_and(|c| c.category == CooldownCategory::Burst)
+}
+
+/// Whether a spell is an interrupt (a kick)
+pub fn is_interrupt(spell_id: u64) -> bool {
+    spell_cooldown(spell_id).is_some_and(|c| c.category == CooldownCategory::Interrupt)
+}
+
+/// Personal defensive cooldowns for a class/spec as (spell_id, name, cooldown_secs), the
+/// cooldown from the spell cooldown table. Class and spec names match `spec_info` in the parser.
+pub fn personal_defensives(class: &str, spec: &str) -> Vec<(u64, &'static str, f64)> {
+    let class_wide: &[(u64, &'static str, f64)] = match class {
+        "Warrior" => &[(97462, "Rallying Cry", 180.0)],
+        "Paladin" => &[(642, "Divine Shield", 300.0), (633, "Lay on Hands", 600.0)],
+        "Hunter" => &[(186265, "Aspect of the Turtle", 180.0), (109304, "Exhilaration", 120.0), (264735, "Survival of the Fittest", 180.0)],
+        "Rogue" => &[(31224, "Cloak of Shadows", 120.0), (5277, "Evasion", 120.0), (185311, "Crimson Vial", 30.0)],
+        "Priest" => &[(19236, "Desperate Prayer", 90.0)],
+        "Death Knight" => &[(48792, "Icebound Fortitude", 180.0), (48707, "Anti-Magic Shell", 60.0)],
+        "Shaman" => &[(108271, "Astral Shift", 120.0)],
+        "Mage" => &[(45438, "Ice Block", 240.0), (55342, "Mirror Image", 120.0)],
+        "Warlock" => &[(104773, "Unending Resolve", 180.0), (108416, "Dark Pact", 60.0)],
+        "Monk" => &[(115203, "Fortifying Brew", 120.0), (122783, "Diffuse Magic", 90.0)],
+        "Druid" => &[(22812, "Barkskin", 60.0), (108238, "Renewal", 90.0)],
+        "Evoker" => &[(363916, "Obsidian Scales", 90.0), (374348, "Renewing Blaze", 90.0)],
+        _ => &[],
+    };
+    let spec_specific: &[(u64, &'static str, f64)] = match (class, spec) {
+        ("Warrior", "Arms") => &[(118038, "Die by the Sword", 120.0)],
+        ("Warrior", "Fury") => &[(184364, "Enraged Regeneration", 120.0)],
+        ("Warrior", "Protection") => &[(871, "Shield Wall", 180.0), (12975, "Last Stand", 180.0)],
+        ("Paladin", "Holy") => &[(498, "Divine Protection", 60.0)],
+        ("Paladin", "Retribution") => &[(184662, "Shield of Vengeance", 90.0)],
+        ("Paladin", "Protection") => &[(31850, "Ardent Defender", 120.0), (86659, "Guardian of Ancient Kings", 300.0)],
+        ("Priest", "Shadow") => &[(47585, "Dispersion", 120.0)],
+        ("Death Knight", "Blood") => &[(55233, "Vampiric Blood", 90.0)],
+        ("Monk", "Windwalker") => &[(122470, "Touch of Karma", 90.0)],
+        ("Druid", "Feral") | ("Druid", "Guardian") => &[(61336, "Survival Instincts", 180.0)],
+        ("Demon Hunter", "Havoc") => &[(198589, "Blur", 60.0)],
+        ("Demon Hunter", "Vengeance") => &[(204021, "Fiery Brand", 60.0), (187827, "Metamorphosis", 180.0)],
+        _ => &[],
+    };
+    class_wide.iter().chain(spec_specific)
+        .map(|&(spell_id, name, listed)| (spell_id, name, spell_cooldown(spell_id).map_or(listed, |c| c.cooldown_secs)))
+        .collect()
+}
+
+/// Spec fingerprint: map a spec-defining ability to its spec ID, used to infer
+/// class/spec when COMBATANT_INFO is missing (logging started mid-session).
+pub fn spec_for_signature_spell(spell_id: u64) -> Option<u32> {
+    let spec_id = match spell_id {
+        // Warrior
+        12294 => 71,           // Mortal Strike
+        23881 => 72,           // Bloodthirst
+        23922 => 73,           // Shield Slam
+        // Paladin
+        20473 | 25914 => 65,   // Holy Shock
+        31935 => 66,           // Avenger's Shield
+        85256 | 383328 => 70,  // Templar's Verdict / Final Verdict
+        184575 => 70,          // Blade of Justice
+        // Hunter
+        217200 => 253,         // Barbed Shot
+        19434 => 254,          // Aimed Shot
+        186270 | 259495 => 255, // Raptor Strike / Wildfire Bomb
+        // Rogue
+        1329 => 259,           // Mutilate
+        185763 => 260,         // Pistol Shot
+        185438 => 261,         // Shadowstrike
+        // Priest
+        47540 | 47666 | 47750 => 256, // Penance
+        2050 => 257,           // Holy Word: Serenity
+        34914 | 335467 => 258, // Vampiric Touch / Devouring Plague
+        // Death Knight
+        206930 | 195182 => 250, // Heart Strike / Marrowrend
+        49020 | 49143 => 251,  // Obliterate / Frost Strike
+        85948 | 55090 => 252,  // Festering Strike / Scourge Strike
+        // Shaman
+        8042 => 262,           // Earth Shock
+        17364 => 263,          // Stormstrike
+        61295 => 264,          // Riptide
+        // Mage
+        30451 => 62,           // Arcane Blast
+        11366 => 63,           // Pyroblast
+        30455 | 44614 => 64,   // Ice Lance / Flurry
+        // Warlock
+        316099 | 980 => 265,   // Unstable Affliction / Agony
+        105174 => 266,         // Hand of Gul'dan
+        116858 => 267,         // Chaos Bolt
+        // Monk
+        121253 => 268,         // Keg Smash
+        124682 => 270,         // Enveloping Mist
+        113656 => 269,         // Fists of Fury
+        // Druid
+        78674 => 102,          // Starsurge
+        5221 => 103,           // Shred
+        192081 => 104,         // Ironfur
+        33763 => 105,          // Lifebloom
+        // Demon Hunter
+        198013 | 162794 => 577, // Eye Beam / Chaos Strike
+        228477 => 581,         // Soul Cleave
+        // Evoker
+        357211 | 356995 => 1467, // Pyre / Disintegrate
+        364343 | 366155 => 1468, // Echo / Reversion
+        395160 | 395152 => 1473, // Eruption / Ebon Might
+        _ => return None,
+    };
+    Some(spec_id)
+}
+
+/// Curated buffs worth showing in the "key buffs" view, by spell ID
+pub const TRACKED_BUFFS: &[u64] = &[
+    // Raid buffs
+    1459,   // Arcane Intellect
+    21562,  // Power Word: Fortitude
+    6673,   // Battle Shout
+    1126,   // Mark of the Wild
+    462854, // Skyfury
+    381748, // Blessing of the Bronze
+    // Lust
+    2825,   // Bloodlust
+    32182,  // Heroism
+    80353,  // Time Warp
+    390386, // Fury of the Aspects
+    264667, // Primal Rage
+    381301, // Feral Hide Drums
+    // Flasks and augment runes
+    432021, // Flask of Alchemical Chaos
+    431971, // Flask of Tempered Aggression
+    431972, // Flask of Tempered Swiftness
+    431973, // Flask of Tempered Mastery
+    431974, // Flask of Tempered Versatility
+    453250, // Crystallized Augment Rune
+    // Major externals
+    10060,  // Power Infusion
+    29166,  // Innervate
+    395152, // Ebon Might
+    410089, // Prescience
+];
+
+/// Tracked buffs matched by name (food buffs use many spell IDs)
+const TRACKED_BUFF_NAMES: &[&str] = &["Well Fed", "Hearty Well Fed"];
+
+/// File next to the executable with extra spell IDs to track (a JSON array of numbers)
+const TRACKED_BUFFS_FILE: &str = "tracked_buffs.json";
+
+/// Built-in tracked buffs plus any user additions from `tracked_buffs.json`
+fn tracked_buff_ids() -> &'static HashSet<u64> {
+    static IDS: OnceLock<HashSet<u64>> = OnceLock::new();
+    IDS.get_or_init(|| {
+        let mut ids: HashSet<u64> = TRACKED_BUFFS.iter().copied().collect();
+        let path = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(TRACKED_BUFFS_FILE)))
+            .filter(|p| p.exists())
+            .unwrap_or_else(|| TRACKED_BUFFS_FILE.into());
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            match serde_json::from_str::<Vec<u64>>(&contents) {
+                Ok(extra) => ids.extend(extra),
+                Err(e) => tracing::warn!("Ignoring {}: {}", path.display(), e),
+            }
+        }
+        ids
+    })
+}
+
+pub fn is_tracked_buff(spell_id: u64, spell_name: &str) -> bool {
+    tracked_buff_ids().contains(&spell_id) || TRACKED_BUFF_NAMES.contains(&spell_name)
+}
@@ -0,0 +1,116 @@
+// Generated by `spell_fetcher --cooldowns` from the Blizzard talent trees, on top of the rows
+// already here. Rows can be added or fixed by hand; the generator keeps their category and
+// class and only updates the cooldown when the API has one.
+
+use super::{CooldownCategory, SpellCooldown};
+
+/// Base cooldowns, sorted by spell id
+pub(super) const SPELL_COOLDOWNS: &[SpellCooldown] = &[
+    SpellCooldown { spell_id: 355, cooldown_secs: 8.0, category: CooldownCategory::Taunt, class: "Warrior", name: "Taunt" },
+    SpellCooldown { spell_id: 498, cooldown_secs: 60.0, category: CooldownCategory::Defensive, class: "Paladin", name: "Divine Protection" },
+    SpellCooldown { spell_id: 633, cooldown_secs: 600.0, category: CooldownCategory::Defensive, class: "Paladin", name: "Lay on Hands" },
+    SpellCooldown { spell_id: 642, cooldown_secs: 300.0, category: CooldownCategory::Defensive, class: "Paladin", name: "Divine Shield" },
+    SpellCooldown { spell_id: 740, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Druid", name: "Tranquility" },
+    SpellCooldown { spell_id: 871, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Warrior", name: "Shield Wall" },
+    SpellCooldown { spell_id: 1022, cooldown_secs: 300.0, category: CooldownCategory::External, class: "Paladin", name: "Blessing of Protection" },
+    SpellCooldown { spell_id: 1122, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Warlock", name: "Summon Infernal" },
+    SpellCooldown { spell_id: 1766, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Rogue", name: "Kick" },
+    SpellCooldown { spell_id: 2139, cooldown_secs: 24.0, category: CooldownCategory::Interrupt, class: "Mage", name: "Counterspell" },
+    SpellCooldown { spell_id: 5277, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Rogue", name: "Evasion" },
+    SpellCooldown { spell_id: 6552, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Warrior", name: "Pummel" },
+    SpellCooldown { spell_id: 6795, cooldown_secs: 8.0, category: CooldownCategory::Taunt, class: "Druid", name: "Growl" },
+    SpellCooldown { spell_id: 6940, cooldown_secs: 120.0, category: CooldownCategory::External, class: "Paladin", name: "Blessing of Sacrifice" },
+    SpellCooldown { spell_id: 12472, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Mage", name: "Icy Veins" },
+    SpellCooldown { spell_id: 12975, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Warrior", name: "Last Stand" },
+    SpellCooldown { spell_id: 13750, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Rogue", name: "Adrenaline Rush" },
+    SpellCooldown { spell_id: 15487, cooldown_secs: 45.0, category: CooldownCategory::Interrupt, class: "Priest", name: "Silence" },
+    SpellCooldown { spell_id: 19236, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Priest", name: "Desperate Prayer" },
+    SpellCooldown { spell_id: 19647, cooldown_secs: 24.0, category: CooldownCategory::Interrupt, class: "Warlock", name: "Spell Lock" },
+    SpellCooldown { spell_id: 20484, cooldown_secs: 600.0, category: CooldownCategory::BattleRes, class: "Druid", name: "Rebirth" },
+    SpellCooldown { spell_id: 22812, cooldown_secs: 60.0, category: CooldownCategory::Defensive, class: "Druid", name: "Barkskin" },
+    SpellCooldown { spell_id: 31224, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Rogue", name: "Cloak of Shadows" },
+    SpellCooldown { spell_id: 31821, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Paladin", name: "Aura Mastery" },
+    SpellCooldown { spell_id: 31850, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Paladin", name: "Ardent Defender" },
+    SpellCooldown { spell_id: 31884, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Paladin", name: "Avenging Wrath" },
+    SpellCooldown { spell_id: 33206, cooldown_secs: 180.0, category: CooldownCategory::External, class: "Priest", name: "Pain Suppression" },
+    SpellCooldown { spell_id: 45438, cooldown_secs: 240.0, category: CooldownCategory::Defensive, class: "Mage", name: "Ice Block" },
+    SpellCooldown { spell_id: 47528, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Death Knight", name: "Mind Freeze" },
+    SpellCooldown { spell_id: 47585, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Priest", name: "Dispersion" },
+    SpellCooldown { spell_id: 47788, cooldown_secs: 180.0, category: CooldownCategory::External, class: "Priest", name: "Guardian Spirit" },
+    SpellCooldown { spell_id: 48707, cooldown_secs: 60.0, category: CooldownCategory::Defensive, class: "Death Knight", name: "Anti-Magic Shell" },
+    SpellCooldown { spell_id: 48792, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Death Knight", name: "Icebound Fortitude" },
+    SpellCooldown { spell_id: 49206, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Death Knight", name: "Summon Gargoyle" },
+    SpellCooldown { spell_id: 49576, cooldown_secs: 25.0, category: CooldownCategory::Taunt, class: "Death Knight", name: "Death Grip" },
+    SpellCooldown { spell_id: 51052, cooldown_secs: 120.0, category: CooldownCategory::RaidCooldown, class: "Death Knight", name: "Anti-Magic Zone" },
+    SpellCooldown { spell_id: 55233, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Death Knight", name: "Vampiric Blood" },
+    SpellCooldown { spell_id: 55342, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Mage", name: "Mirror Image" },
+    SpellCooldown { spell_id: 56222, cooldown_secs: 8.0, category: CooldownCategory::Taunt, class: "Death Knight", name: "Dark Command" },
+    SpellCooldown { spell_id: 57994, cooldown_secs: 12.0, category: CooldownCategory::Interrupt, class: "Shaman", name: "Wind Shear" },
+    SpellCooldown { spell_id: 61336, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Druid", name: "Survival Instincts" },
+    SpellCooldown { spell_id: 61999, cooldown_secs: 600.0, category: CooldownCategory::BattleRes, class: "Death Knight", name: "Raise Ally" },
+    SpellCooldown { spell_id: 62124, cooldown_secs: 8.0, category: CooldownCategory::Taunt, class: "Paladin", name: "Hand of Reckoning" },
+    SpellCooldown { spell_id: 62618, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Priest", name: "Power Word: Barrier" },
+    SpellCooldown { spell_id: 64843, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Priest", name: "Divine Hymn" },
+    SpellCooldown { spell_id: 78675, cooldown_secs: 60.0, category: CooldownCategory::Interrupt, class: "Druid", name: "Solar Beam" },
+    SpellCooldown { spell_id: 86659, cooldown_secs: 300.0, category: CooldownCategory::Defensive, class: "Paladin", name: "Guardian of Ancient Kings" },
+    SpellCooldown { spell_id: 95750, cooldown_secs: 600.0, category: CooldownCategory::BattleRes, class: "Warlock", name: "Soulstone Resurrection" },
+    SpellCooldown { spell_id: 96231, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Paladin", name: "Rebuke" },
+    SpellCooldown { spell_id: 97462, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Warrior", name: "Rallying Cry" },
+    SpellCooldown { spell_id: 98008, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Shaman", name: "Spirit Link Totem" },
+    SpellCooldown { spell_id: 102342, cooldown_secs: 90.0, category: CooldownCategory::External, class: "Druid", name: "Ironbark" },
+    SpellCooldown { spell_id: 102543, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Druid", name: "Incarnation: Avatar of Ashamane" },
+    SpellCooldown { spell_id: 102560, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Druid", name: "Incarnation: Chosen of Elune" },
+    SpellCooldown { spell_id: 104773, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Warlock", name: "Unending Resolve" },
+    SpellCooldown { spell_id: 106839, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Druid", name: "Skull Bash" },
+    SpellCooldown { spell_id: 106951, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Druid", name: "Berserk" },
+    SpellCooldown { spell_id: 108238, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Druid", name: "Renewal" },
+    SpellCooldown { spell_id: 108271, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Shaman", name: "Astral Shift" },
+    SpellCooldown { spell_id: 108280, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Shaman", name: "Healing Tide Totem" },
+    SpellCooldown { spell_id: 108416, cooldown_secs: 60.0, category: CooldownCategory::Defensive, class: "Warlock", name: "Dark Pact" },
+    SpellCooldown { spell_id: 109304, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Hunter", name: "Exhilaration" },
+    SpellCooldown { spell_id: 114050, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Shaman", name: "Ascendance" },
+    SpellCooldown { spell_id: 114051, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Shaman", name: "Ascendance" },
+    SpellCooldown { spell_id: 115203, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Monk", name: "Fortifying Brew" },
+    SpellCooldown { spell_id: 115310, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Monk", name: "Revival" },
+    SpellCooldown { spell_id: 115546, cooldown_secs: 8.0, category: CooldownCategory::Taunt, class: "Monk", name: "Provoke" },
+    SpellCooldown { spell_id: 116705, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Monk", name: "Spear Hand Strike" },
+    SpellCooldown { spell_id: 116849, cooldown_secs: 120.0, category: CooldownCategory::External, class: "Monk", name: "Life Cocoon" },
+    SpellCooldown { spell_id: 118038, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Warrior", name: "Die by the Sword" },
+    SpellCooldown { spell_id: 122470, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Monk", name: "Touch of Karma" },
+    SpellCooldown { spell_id: 122783, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Monk", name: "Diffuse Magic" },
+    SpellCooldown { spell_id: 123904, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Monk", name: "Invoke Xuen, the White Tiger" },
+    SpellCooldown { spell_id: 147362, cooldown_secs: 24.0, category: CooldownCategory::Interrupt, class: "Hunter", name: "Counter Shot" },
+    SpellCooldown { spell_id: 183752, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Demon Hunter", name: "Disrupt" },
+    SpellCooldown { spell_id: 184364, cooldown_secs: 120.0, category: CooldownCategory::Defensive, class: "Warrior", name: "Enraged Regeneration" },
+    SpellCooldown { spell_id: 184662, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Paladin", name: "Shield of Vengeance" },
+    SpellCooldown { spell_id: 185245, cooldown_secs: 8.0, category: CooldownCategory::Taunt, class: "Demon Hunter", name: "Torment" },
+    SpellCooldown { spell_id: 185311, cooldown_secs: 30.0, category: CooldownCategory::Defensive, class: "Rogue", name: "Crimson Vial" },
+    SpellCooldown { spell_id: 186265, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Hunter", name: "Aspect of the Turtle" },
+    SpellCooldown { spell_id: 187707, cooldown_secs: 15.0, category: CooldownCategory::Interrupt, class: "Hunter", name: "Muzzle" },
+    SpellCooldown { spell_id: 187827, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Demon Hunter", name: "Metamorphosis" },
+    SpellCooldown { spell_id: 190319, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Mage", name: "Combustion" },
+    SpellCooldown { spell_id: 194223, cooldown_secs: 180.0, category: CooldownCategory::Burst, class: "Druid", name: "Celestial Alignment" },
+    SpellCooldown { spell_id: 196718, cooldown_secs: 300.0, category: CooldownCategory::RaidCooldown, class: "Demon Hunter", name: "Darkness" },
+    SpellCooldown { spell_id: 198589, cooldown_secs: 60.0, category: CooldownCategory::Defensive, class: "Demon Hunter", name: "Blur" },
+    SpellCooldown { spell_id: 204021, cooldown_secs: 60.0, category: CooldownCategory::Defensive, class: "Demon Hunter", name: "Fiery Brand" },
+    SpellCooldown { spell_id: 205180, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Warlock", name: "Summon Darkglare" },
+    SpellCooldown { spell_id: 228260, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Priest", name: "Void Eruption" },
+    SpellCooldown { spell_id: 264735, cooldown_secs: 180.0, category: CooldownCategory::Defensive, class: "Hunter", name: "Survival of the Fittest" },
+    SpellCooldown { spell_id: 265202, cooldown_secs: 720.0, category: CooldownCategory::RaidCooldown, class: "Priest", name: "Holy Word: Salvation" },
+    SpellCooldown { spell_id: 288613, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Hunter", name: "Trueshot" },
+    SpellCooldown { spell_id: 351338, cooldown_secs: 40.0, category: CooldownCategory::Interrupt, class: "Evoker", name: "Quell" },
+    SpellCooldown { spell_id: 357170, cooldown_secs: 60.0, category: CooldownCategory::External, class: "Evoker", name: "Time Dilation" },
+    SpellCooldown { spell_id: 359816, cooldown_secs: 120.0, category: CooldownCategory::RaidCooldown, class: "Evoker", name: "Dream Flight" },
+    SpellCooldown { spell_id: 359844, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Hunter", name: "Call of the Wild" },
+    SpellCooldown { spell_id: 360194, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Rogue", name: "Deathmark" },
+    SpellCooldown { spell_id: 360952, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Hunter", name: "Coordinated Assault" },
+    SpellCooldown { spell_id: 363534, cooldown_secs: 240.0, category: CooldownCategory::RaidCooldown, class: "Evoker", name: "Rewind" },
+    SpellCooldown { spell_id: 363916, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Evoker", name: "Obsidian Scales" },
+    SpellCooldown { spell_id: 374348, cooldown_secs: 90.0, category: CooldownCategory::Defensive, class: "Evoker", name: "Renewing Blaze" },
+    SpellCooldown { spell_id: 375087, cooldown_secs: 120.0, category: CooldownCategory::Burst, class: "Evoker", name: "Dragonrage" },
+    SpellCooldown { spell_id: 388615, cooldown_secs: 180.0, category: CooldownCategory::RaidCooldown, class: "Monk", name: "Restoral" },
+    SpellCooldown { spell_id: 391054, cooldown_secs: 600.0, category: CooldownCategory::BattleRes, class: "Paladin", name: "Intercession" },
+    SpellCooldown { spell_id: 431416, cooldown_secs: 300.0, category: CooldownCategory::Potion, class: "", name: "Algari Healing Potion" },
+    SpellCooldown { spell_id: 431419, cooldown_secs: 300.0, category: CooldownCategory::Potion, class: "", name: "Cavedweller's Delight" },
+    SpellCooldown { spell_id: 452767, cooldown_secs: 300.0, category: CooldownCategory::Potion, class: "", name: "Heartseeking Health Injector" },
+];
@@ -0,0 +1,185 @@
+//! Boss pulls of a log grouped the way raiders read a night: instance, then boss, then
+//! every pull of that boss in order, with per-boss totals, whether progress on it is
+//! trending better or worse, and how the phase timings of the wipes compare to the kill
+
+use crate::models::*;
+use crate::parser::from_millis;
+use crate::trend::slope;
+
+/// Pulls needed before a progression trend is worth showing
+const MIN_TREND_PULLS: usize = 3;
+
+/// Boss HP % per pull the trend has to move by before it counts as improving or declining
+const HP_PCT_SLOPE_THRESHOLD: f64 = 0.5;
+
+/// Seconds per pull the first death has to move by, when boss HP is flat
+const FIRST_DEATH_SLOPE_THRESHOLD: f64 = 1.0;
+
+/// Group the boss encounters of a log by instance (and difficulty), then by boss, in the
+/// order each was first pulled. Keys, dungeon runs, trash and other combat are left out.
+pub fn group_encounters<'a>(encounters: impl IntoIterator<Item = &'a EncounterSummary>) -> Vec<InstanceGroup> {
+    let mut instances: Vec<InstanceGroup> = Vec::new();
+    for enc in encounters.into_iter().filter(|e| e.encounter_type == "boss") {
+        let instance_name = enc.instance_name.clone().unwrap_or_else(|| "Unknown instance".to_string());
+        let instance = match instances.iter_mut()
+            .position(|g| g.instance_name == instance_name && g.difficulty_id == enc.difficulty_id)
+        {
+            Some(i) => &mut instances[i],
+            None => {
+                instances.push(InstanceGroup {
+                    instance_name,
+                    instance_icon: enc.instance_icon.clone(),
+                    difficulty_id: enc.difficulty_id,
+                    difficulty_name: enc.difficulty_name.clone(),
+                    bosses: Vec::new(),
+                });
+                instances.last_mut().unwrap()
+            }
+        };
+        let boss = match instance.bosses.iter().position(|b| b.encounter_id == enc.encounter_id) {
+            Some(i) => &mut instance.bosses[i],
+            None => {
+                instance.bosses.push(BossGroup {
+                    encounter_id: enc.encounter_id,
+                    name: enc.name.clone(),
+                    pull_count: 0,
+                    kills: 0,
+                    first_kill_pull: None,
+                    kill_time_secs: None,
+                    best_pull: None,
+                    best_hp_pct: None,
+                    total_secs: 0.0,
+                    progression: None,
+                    phase_timings: Vec::new(),
+                    pulls: Vec::new(),
+                });
+                instance.bosses.last_mut().unwrap()
+            }
+        };
+        boss.pull_count += 1;
+        boss.total_secs += enc.duration_secs;
+        if enc.success {
+            boss.kills += 1;
+            boss.first_kill_pull.get_or_insert(boss.pull_count);
+            if boss.kill_time_secs.is_none_or(|best| enc.duration_secs < best) {
+                boss.kill_time_secs = Some(enc.duration_secs);
+            }
+        }
+        boss.pulls.push(GroupedPull {
+            index: enc.index,
+            stable_id: enc.stable_id.clone(),
+            pull_number: boss.pull_count,
+            start_time: enc.start_time.clone(),
+            duration_secs: enc.duration_secs,
+            success: enc.success,
+            boss_hp_pct: if enc.success { Some(0.0) } else { enc.boss_hp_pct },
+            deaths: enc.deaths.len() as u32,
+            phase_reached: enc.phases.iter().map(|p| p.phase_id).max(),
+            first_death_secs: enc.deaths.iter().map(|d| from_millis(d.time_ms)).min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)),
+            phases: enc.phases.iter()
+                .map(|p| PullPhase {
+                    phase_id: p.phase_id,
+                    start_secs: p.start_time_secs,
+                    duration_secs: (p.end_time_secs - p.start_time_secs).max(0.0),
+                })
+                .collect(),
+        });
+    }
+
+    for boss in instances.iter_mut().flat_map(|g| g.bosses.iter_mut()) {
+        let best = boss.pulls.iter().min_by(|a, b| pull_rank(a).partial_cmp(&pull_rank(b)).unwrap_or(std::cmp::Ordering::Equal));
+        boss.best_pull = best.map(|p| p.stable_id.clone());
+        boss.best_hp_pct = best.and_then(|p| p.boss_hp_pct);
+        boss.progression = progression_trend(&boss.pulls);
+        boss.phase_timings = phase_timings(&boss.pulls);
+    }
+    instances
+}
+
+/// Each phase's start and duration across the pulls, in the order the phases were first
+/// reached, against the first kill
+fn phase_timings(pulls: &[GroupedPull]) -> Vec<PhaseTiming> {
+    let kill = pulls.iter().find(|p| p.success);
+    let mut phase_ids: Vec<u32> = Vec::new();
+    for phase in pulls.iter().flat_map(|p| &p.phases) {
+        if !phase_ids.contains(&phase.phase_id) {
+            phase_ids.push(phase.phase_id);
+        }
+    }
+    let average = |values: &[f64]| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+    phase_ids.into_iter().map(|phase_id| {
+        let mut starts = Vec::new();
+        let mut durations = Vec::new();
+        let mut wipes = 0;
+        for pull in pulls {
+            let Some(position) = pull.phases.iter().position(|p| p.phase_id == phase_id) else {
+                continue;
+            };
+            let phase = &pull.phases[position];
+            starts.push(phase.start_secs);
+            if pull.success || position + 1 < pull.phases.len() {
+                durations.push(phase.duration_secs);
+            } else {
+                wipes += 1;
+            }
+        }
+        let on_kill = kill.and_then(|k| k.phases.iter().find(|p| p.phase_id == phase_id));
+        let average_start_secs = average(&starts).unwrap_or(0.0);
+        let average_duration_secs = average(&durations);
+        PhaseTiming {
+            phase_id,
+            pulls_reached: starts.len() as u32,
+            wipes,
+            average_start_secs,
+            average_duration_secs,
+            kill_start_secs: on_kill.map(|p| p.start_secs),
+            kill_duration_secs: on_kill.map(|p| p.duration_secs),
+            start_vs_kill_secs: on_kill.map(|p| average_start_secs - p.start_secs),
+            duration_vs_kill_secs: on_kill.zip(average_duration_secs).map(|(p, avg)| avg - p.duration_secs),
+        }
+    }).collect()
+}
+
+/// Trend over the pulls up to and including the first kill; farm kills after it would only
+/// flatten the line
+fn progression_trend(pulls: &[GroupedPull]) -> Option<ProgressionTrend> {
+    let progression = match pulls.iter().position(|p| p.success) {
+        Some(kill) => &pulls[..=kill],
+        None => pulls,
+    };
+    if progression.len() < MIN_TREND_PULLS {
+        return None;
+    }
+    let x = |p: &GroupedPull| p.pull_number as f64;
+    let boss_hp_pct_slope = slope(progression.iter().filter_map(|p| p.boss_hp_pct.map(|hp| (x(p), hp))));
+    let phase_slope = slope(progression.iter().filter_map(|p| p.phase_reached.map(|phase| (x(p), phase as f64))));
+    // A pull nobody died in counts as lasting the whole pull
+    let first_death_secs_slope = slope(progression.iter().map(|p| (x(p), p.first_death_secs.unwrap_or(p.duration_secs))));
+    let direction = if boss_hp_pct_slope <= -HP_PCT_SLOPE_THRESHOLD {
+        "improving"
+    } else if boss_hp_pct_slope >= HP_PCT_SLOPE_THRESHOLD {
+        "declining"
+    } else if first_death_secs_slope >= FIRST_DEATH_SLOPE_THRESHOLD {
+        "improving"
+    } else if first_death_secs_slope <= -FIRST_DEATH_SLOPE_THRESHOLD {
+        "declining"
+    } else {
+        "steady"
+    };
+    Some(ProgressionTrend {
+        pulls: progression.len() as u32,
+        boss_hp_pct_slope,
+        phase_slope,
+        first_death_secs_slope,
+        direction: direction.to_string(),
+    })
+}
+
+/// Lower is better: the fastest kill, then the wipe with the boss lowest
+fn pull_rank(pull: &GroupedPull) -> (f64, f64) {
+    if pull.success {
+        (0.0, pull.duration_secs)
+    } else {
+        (1.0, pull.boss_hp_pct.unwrap_or(100.0))
+    }
+}
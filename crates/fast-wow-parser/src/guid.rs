@@ -0,0 +1,94 @@
+//! WoW GUID decoding
+//!
+//! Combat log GUIDs are dash-separated:
+//! - `Player-<serverID>-<playerUID>`
+//! - `Creature-0-<serverID>-<instanceID>-<zoneUID>-<npcID>-<spawnUID>` (same for Pet, Vehicle, GameObject)
+//! - `0000000000000000` for "no unit"
+
+/// Unit type from the GUID prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitType {
+    Player,
+    Creature,
+    Pet,
+    Vehicle,
+    GameObject,
+    Other,
+}
+
+impl UnitType {
+    pub fn from_guid(guid: &str) -> UnitType {
+        match guid.split('-').next().unwrap_or("") {
+            "Player" => UnitType::Player,
+            "Creature" => UnitType::Creature,
+            "Pet" => UnitType::Pet,
+            "Vehicle" => UnitType::Vehicle,
+            "GameObject" => UnitType::GameObject,
+            _ => UnitType::Other,
+        }
+    }
+
+    /// Name recorded as an enemy's creature type; game objects count as "Other"
+    pub fn name(self) -> &'static str {
+        match self {
+            UnitType::Player => "Player",
+            UnitType::Creature => "Creature",
+            UnitType::Pet => "Pet",
+            UnitType::Vehicle => "Vehicle",
+            UnitType::GameObject | UnitType::Other => "Other",
+        }
+    }
+}
+
+/// A decoded GUID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Guid<'a> {
+    pub unit_type: UnitType,
+    /// Realm/server ID
+    pub server_id: Option<u32>,
+    /// NPC ID for creatures, pets, vehicles and game objects
+    pub npc_id: Option<u64>,
+    /// Spawn UID for NPCs, player UID for players
+    pub spawn_uid: Option<&'a str>,
+}
+
+/// Decode a GUID into its components. Returns `None` for empty/nil GUIDs.
+pub fn parse(guid: &str) -> Option<Guid<'_>> {
+    let parts: Vec<&str> = guid.split('-').collect();
+    if parts.len() < 2 {
+        return None;
+    }
+    let unit_type = UnitType::from_guid(guid);
+    match unit_type {
+        UnitType::Player => Some(Guid {
+            unit_type,
+            server_id: parts.get(1).and_then(|s| s.parse().ok()),
+            npc_id: None,
+            spawn_uid: parts.get(2).copied(),
+        }),
+        UnitType::Creature | UnitType::Pet | UnitType::Vehicle | UnitType::GameObject => Some(Guid {
+            unit_type,
+            server_id: parts.get(2).and_then(|s| s.parse().ok()),
+            npc_id: parts.get(5).and_then(|s| s.parse().ok()),
+            spawn_uid: parts.get(6).copied(),
+        }),
+        UnitType::Other => Some(Guid { unit_type, server_id: None, npc_id: None, spawn_uid: None }),
+    }
+}
+
+pub fn is_player(guid: &str) -> bool {
+    guid.starts_with("Player-")
+}
+
+/// Creatures and vehicles (hostile NPCs, bosses); excludes pets
+pub fn is_npc(guid: &str) -> bool {
+    matches!(UnitType::from_guid(guid), UnitType::Creature | UnitType::Vehicle)
+}
+
+/// NPC ID of a creature/vehicle GUID
+pub fn npc_id(guid: &str) -> Option<u64> {
+    if !is_npc(guid) {
+        return None;
+    }
+    parse(guid).and_then(|g| g.npc_id)
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::events::{EventHandler, LogEvent};
+use crate::guid;
+use crate::parser::{parse_hex_or_dec, unquote};
+
+/// Damage soaked by a player's shield, pets' credited to their owner
+pub(crate) struct Absorb {
+    /// The shield's caster, or the player owning it if it is a pet
+    pub player_guid: String,
+    pub caster_guid: String,
+    /// Without the realm
+    pub caster_name: String,
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub school: u32,
+    pub amount: u64,
+    pub dest_guid: String,
+    /// Without the realm
+    pub dest_name: String,
+}
+
+/// Players' shields soaking damage. The tracker takes each absorb as it lands to credit it as
+/// healing; the latest one on each unit is kept to credit a lethal hit it soaked.
+#[derive(Default)]
+pub(crate) struct Absorbs {
+    /// The absorb of the event just handled, until the tracker takes it
+    landed: Option<Absorb>,
+    /// dest_guid -> (timestamp_secs, player_guid, absorb spell name)
+    latest: HashMap<String, (f64, String, String)>,
+}
+
+impl Absorbs {
+    /// The absorb of the event just handled, if it was a player's shield
+    pub fn take_landed(&mut self) -> Option<Absorb> {
+        self.landed.take()
+    }
+
+    /// The latest absorb on the unit: (timestamp_secs, player_guid, absorb spell name)
+    pub fn latest_on(&self, guid: &str) -> Option<&(f64, String, String)> {
+        self.latest.get(guid)
+    }
+}
+
+impl EventHandler for Absorbs {
+    fn name(&self) -> &str {
+        "absorbs"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        event_type == "SPELL_ABSORBED"
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        self.landed = None;
+        let Some(combat) = event.combat else {
+            return;
+        };
+        let fields = event.fields;
+        // SPELL_ABSORBED has two formats:
+        // Spell damage absorbed: ...,spellID,spellName,spellSchool,absorbCasterGUID,absorbCasterName,...,absorbSpellID,absorbSpellName,absorbSchool,absorbAmount
+        // Swing damage absorbed: ...,absorbCasterGUID,absorbCasterName,...,absorbSpellID,absorbSpellName,absorbSchool,absorbAmount
+        // Detect format by checking if field 9 looks like a spell ID (number) or a GUID
+        let field9 = fields.get(9).unwrap_or(&"");
+        let is_spell_absorbed = field9.parse::<u64>().is_ok();
+        let offset: usize = if is_spell_absorbed { 3 } else { 0 }; // spell absorbed has 3 extra fields (spellID, spellName, spellSchool)
+
+        let caster_guid = fields.get(9 + offset).map(|s| s.to_string()).unwrap_or_default();
+        let caster_name = fields.get(10 + offset).map(|s| unquote(s)).unwrap_or_default();
+        let caster_name = caster_name.split('-').next().unwrap_or(&caster_name).to_string();
+        let spell_id: u64 = fields.get(13 + offset).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let spell_name = fields.get(14 + offset).map(|s| unquote(s)).unwrap_or_default();
+        let school: u32 = fields.get(15 + offset).and_then(|s| parse_hex_or_dec(s)).unwrap_or(0);
+        let amount: u64 = fields.get(16 + offset).and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        // Resolve absorb caster to player (could be a pet)
+        let player_guid = if guid::is_player(&caster_guid) {
+            caster_guid.clone()
+        } else {
+            combat.owner_of(&caster_guid).unwrap_or(caster_guid.clone())
+        };
+        if !guid::is_player(&player_guid) || amount == 0 || spell_id == 0 {
+            return;
+        }
+        self.latest.insert(combat.dest_guid.to_string(), (event.timestamp_secs, player_guid.clone(), spell_name.clone()));
+        self.landed = Some(Absorb {
+            player_guid,
+            caster_guid,
+            caster_name,
+            spell_id,
+            spell_name,
+            school,
+            amount,
+            dest_guid: combat.dest_guid.to_string(),
+            dest_name: combat.dest_name.to_string(),
+        });
+    }
+}
@@ -0,0 +1,40 @@
+use crate::events::{EventHandler, LogEvent};
+use crate::game_data;
+use crate::guid;
+
+/// Casts of major damage cooldowns (`CooldownCategory::Burst`) by players, for the cooldown
+/// usage report
+#[derive(Default)]
+pub(crate) struct BurstCooldowns {
+    /// (timestamp_secs, caster_guid, spell_id, spell_name)
+    casts: Vec<(f64, String, u64, String)>,
+}
+
+impl BurstCooldowns {
+    pub fn casts(&self) -> &[(f64, String, u64, String)] {
+        &self.casts
+    }
+}
+
+impl EventHandler for BurstCooldowns {
+    fn name(&self) -> &str {
+        "burst_cooldowns"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        event_type == "SPELL_CAST_SUCCESS"
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        let Some(combat) = event.combat else {
+            return;
+        };
+        if !guid::is_player(combat.source_guid) {
+            return;
+        }
+        let spell_id = event.spell_id();
+        if game_data::is_burst_cooldown(spell_id) {
+            self.casts.push((event.timestamp_secs, combat.source_guid.to_string(), spell_id, event.spell_name()));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use crate::events::{EventHandler, LogEvent};
+use crate::game_data;
+use crate::guid;
+use crate::models::{CrowdControlEvent, CrowdControlReceived};
+use crate::options::ParseOptions;
+use crate::parser::to_millis;
+
+/// A crowd control aura on a player, open until it is removed
+struct CrowdControlAura {
+    player_guid: String,
+    spell_id: u64,
+    spell_name: String,
+    kind: &'static str,
+    source_name: String,
+    start_secs: f64,
+    end_secs: Option<f64>,
+}
+
+/// Crowd control players received, and how much each player applied to enemy players
+pub(crate) struct CrowdControl {
+    /// Configured spells counted as crowd control on top of the built-in list
+    extra_spell_ids: Vec<u64>,
+    /// Crowd control auras applied to enemy players, per caster
+    applied: HashMap<String, u32>,
+    /// Crowd control auras on players, in the order they were applied
+    auras: Vec<CrowdControlAura>,
+}
+
+impl CrowdControl {
+    pub fn new(options: &ParseOptions) -> Self {
+        CrowdControl {
+            extra_spell_ids: options.crowd_control_spell_ids.clone(),
+            applied: HashMap::new(),
+            auras: Vec::new(),
+        }
+    }
+
+    /// Crowd control auras the player applied to enemy players
+    pub fn applied_by(&self, player_guid: &str) -> u32 {
+        self.applied.get(player_guid).copied().unwrap_or(0)
+    }
+
+    /// Crowd control kind of an aura: the built-in PvP list, then configured spells
+    fn kind(&self, spell_id: u64) -> Option<&'static str> {
+        game_data::crowd_control(spell_id)
+            .or_else(|| self.extra_spell_ids.contains(&spell_id).then_some("control"))
+    }
+
+    /// Start a crowd control effect on a player; reapplying one that is still up extends it
+    fn open(&mut self, player_guid: &str, spell_id: u64, spell_name: String, kind: &'static str, source_name: &str, timestamp_secs: f64) {
+        let already_open = self.auras.iter()
+            .any(|a| a.end_secs.is_none() && a.player_guid == player_guid && a.spell_id == spell_id);
+        if !already_open {
+            self.auras.push(CrowdControlAura {
+                player_guid: player_guid.to_string(),
+                spell_id,
+                spell_name,
+                kind,
+                source_name: source_name.to_string(),
+                start_secs: timestamp_secs,
+                end_secs: None,
+            });
+        }
+    }
+
+    /// End a player's crowd control effect (`None`: all of them, on death)
+    fn close(&mut self, player_guid: &str, spell_id: Option<u64>, timestamp_secs: f64) {
+        for aura in self.auras.iter_mut().rev() {
+            if aura.end_secs.is_none() && aura.player_guid == player_guid && spell_id.is_none_or(|id| id == aura.spell_id) {
+                aura.end_secs = Some(timestamp_secs);
+            }
+        }
+    }
+
+    /// Crowd control a player received between `from_secs` and `to_secs`; effects still up
+    /// run to `last_event_secs`. `duration` is what the percent is taken of.
+    pub fn build(&self, guid: &str, from_secs: f64, to_secs: f64, duration: f64, last_event_secs: f64, fight_start_secs: f64) -> CrowdControlReceived {
+        let mut events = Vec::new();
+        let mut spans: Vec<(f64, f64)> = Vec::new();
+        for aura in self.auras.iter().filter(|a| a.player_guid == guid) {
+            let start = aura.start_secs.max(from_secs);
+            let end = aura.end_secs.unwrap_or(last_event_secs).min(to_secs);
+            if end <= start {
+                continue;
+            }
+            spans.push((start, end));
+            events.push(CrowdControlEvent {
+                spell_id: aura.spell_id,
+                spell_name: aura.spell_name.clone(),
+                kind: aura.kind.to_string(),
+                source_name: aura.source_name.clone(),
+                time_ms: to_millis(start - fight_start_secs),
+                duration_secs: end - start,
+            });
+        }
+        // Overlapping effects (a stun during a root) count once
+        spans.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        let mut total_secs = 0.0;
+        let mut covered_until = f64::MIN;
+        for (start, end) in spans {
+            let start = start.max(covered_until);
+            if end > start {
+                total_secs += end - start;
+                covered_until = end;
+            }
+        }
+        let pct = if duration > 0.0 { (total_secs / duration * 100.0).min(100.0) } else { 0.0 };
+        CrowdControlReceived { events, total_secs, pct }
+    }
+}
+
+impl EventHandler for CrowdControl {
+    fn name(&self) -> &str {
+        "crowd_control"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        matches!(event_type, "SPELL_AURA_APPLIED" | "SPELL_AURA_REMOVED" | "UNIT_DIED")
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        let Some(combat) = event.combat.filter(|c| guid::is_player(c.dest_guid)) else {
+            return;
+        };
+        match event.event_type {
+            "SPELL_AURA_APPLIED" => {
+                let spell_id = event.spell_id();
+                let Some(kind) = self.kind(spell_id).filter(|_| spell_id > 0) else {
+                    return;
+                };
+                if combat.is_hostile() {
+                    *self.applied.entry(combat.effective_source.to_string()).or_insert(0) += 1;
+                }
+                self.open(combat.dest_guid, spell_id, event.spell_name(), kind, combat.source_name, event.timestamp_secs);
+            }
+            "SPELL_AURA_REMOVED" => {
+                let spell_id = event.spell_id();
+                if spell_id > 0 {
+                    self.close(combat.dest_guid, Some(spell_id), event.timestamp_secs);
+                }
+            }
+            "UNIT_DIED" => self.close(combat.dest_guid, None, event.timestamp_secs),
+            _ => {}
+        }
+    }
+}
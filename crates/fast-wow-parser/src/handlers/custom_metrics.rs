@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use crate::events::{EventHandler, LogEvent};
+use crate::guid;
+use crate::log_format::FieldMap;
+use crate::models::{CustomMetric, CustomMetricPlayer, PlayerSummary};
+use crate::options::{MetricMeasure, MetricPer, MetricRule, ParseOptions};
+use crate::parser::{find_damage_amount, find_heal_amount};
+
+/// Stands in for the target of rules without `target_roles`
+const ANY_TARGET: u32 = u32::MAX;
+
+/// The configured custom metrics (`ParseOptions::custom_metrics`), added up per rule and
+/// player as events come in. Target roles are only known once the players are built, so
+/// rules filtering on them keep the target of each value too.
+pub(crate) struct CustomMetrics {
+    rules: Vec<MetricRule>,
+    /// Player GUIDs, numbered in the order they were first credited or targeted
+    units: HashMap<String, u32>,
+    /// (rule index, credited player, target player or `ANY_TARGET`) -> value
+    values: HashMap<(usize, u32, u32), u64>,
+}
+
+impl CustomMetrics {
+    pub fn new(options: &ParseOptions) -> Self {
+        CustomMetrics {
+            rules: options.custom_metrics.clone(),
+            units: HashMap::new(),
+            values: HashMap::new(),
+        }
+    }
+
+    fn unit_id(&mut self, guid: &str) -> u32 {
+        if let Some(&id) = self.units.get(guid) {
+            return id;
+        }
+        let id = self.units.len() as u32;
+        self.units.insert(guid.to_string(), id);
+        id
+    }
+
+    /// The rules that apply to encounter `encounter_id`, credited to `players`
+    pub fn build(&self, encounter_id: u64, players: &[PlayerSummary]) -> Vec<CustomMetric> {
+        let mut guids = vec![""; self.units.len()];
+        for (guid, &id) in &self.units {
+            guids[id as usize] = guid;
+        }
+        let role = |id: u32| {
+            let guid = guids.get(id as usize)?;
+            players.iter().find(|p| p.guid == *guid).map(|p| p.role.as_str())
+        };
+        self.rules.iter().enumerate()
+            .filter(|(_, rule)| rule.encounter_ids.is_empty() || rule.encounter_ids.contains(&encounter_id))
+            .map(|(index, rule)| {
+                let mut by_player: HashMap<&str, u64> = HashMap::new();
+                for ((_, credited, target), value) in self.values.iter().filter(|((i, _, _), _)| *i == index) {
+                    if !rule.target_roles.is_empty() && !role(*target).is_some_and(|r| rule.target_roles.iter().any(|t| t == r)) {
+                        continue;
+                    }
+                    *by_player.entry(guids[*credited as usize]).or_default() += value;
+                }
+                let mut players: Vec<CustomMetricPlayer> = players.iter()
+                    .filter_map(|p| Some(CustomMetricPlayer {
+                        guid: p.guid.clone(),
+                        name: p.name.clone(),
+                        value: *by_player.get(p.guid.as_str()).filter(|v| **v > 0)?,
+                    }))
+                    .collect();
+                players.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.name.cmp(&b.name)));
+                let total = players.iter().map(|p| p.value).sum();
+                CustomMetric {
+                    name: rule.name.clone(),
+                    measure: match rule.measure {
+                        MetricMeasure::Count => "count",
+                        MetricMeasure::Amount => "amount",
+                    }.to_string(),
+                    total,
+                    players,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Damage, or effective healing, of the event; 0 for other events
+fn amount(event: &LogEvent, map: &FieldMap) -> u64 {
+    if event.event_type.starts_with("SWING_DAMAGE") {
+        find_damage_amount(event.fields, map.swing_amount)
+    } else if event.event_type.ends_with("_HEAL") {
+        find_heal_amount(event.fields, map.spell_amount, map.overkill_gap)
+    } else if event.event_type.ends_with("_DAMAGE") {
+        find_damage_amount(event.fields, map.spell_amount)
+    } else {
+        0
+    }
+}
+
+impl EventHandler for CustomMetrics {
+    fn name(&self) -> &str {
+        "custom_metrics"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        self.rules.iter().any(|rule| rule.events.iter().any(|e| e == event_type))
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        let Some(combat) = event.combat else {
+            return;
+        };
+        let spell_id = if event.event_type.starts_with("SWING") { 0 } else { event.spell_id() };
+        for index in 0..self.rules.len() {
+            let rule = &self.rules[index];
+            if !rule.events.iter().any(|e| e == event.event_type)
+                || (!rule.spell_ids.is_empty() && !rule.spell_ids.contains(&spell_id))
+                || (!rule.phases.is_empty() && !rule.phases.contains(&combat.phase))
+            {
+                continue;
+            }
+            // Only players are listed; pets are already credited to their owner as the source
+            let credited = match rule.per {
+                MetricPer::Source => combat.effective_source,
+                MetricPer::Target => combat.dest_guid,
+            };
+            let by_role = !rule.target_roles.is_empty();
+            if !guid::is_player(credited) || (by_role && !guid::is_player(combat.dest_guid)) {
+                continue;
+            }
+            let value = match rule.measure {
+                MetricMeasure::Count => 1,
+                MetricMeasure::Amount => amount(event, combat.field_map),
+            };
+            let credited = self.unit_id(credited);
+            let target = if by_role { self.unit_id(combat.dest_guid) } else { ANY_TARGET };
+            *self.values.entry((index, credited, target)).or_default() += value;
+        }
+    }
+}
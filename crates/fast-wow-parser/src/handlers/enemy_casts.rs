@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::events::{CombatContext, EventHandler, LogEvent};
+use crate::guid;
+
+/// What enemies cast, for the replay: each cast from SPELL_CAST_START until it finished, failed
+/// or was interrupted, and where casts without a target (ground effects, mostly) went off
+#[derive(Default)]
+pub(crate) struct EnemyCasts {
+    /// Casts in time order
+    casts: Vec<CastWindow>,
+    /// Casts still in progress: caster_guid -> index into `casts`
+    casting: HashMap<String, usize>,
+    /// Untargeted casts: (timestamp_secs, enemy_name, spell_id, spell_name, pos_x, pos_y)
+    markers: Vec<(f64, String, u64, String, f64, f64)>,
+}
+
+pub(crate) struct CastWindow {
+    pub start_secs: f64,
+    /// None while the cast is in progress
+    pub end_secs: Option<f64>,
+    pub enemy_name: String,
+    pub spell_id: u64,
+    pub spell_name: String,
+    /// "success", "interrupted" or "failed"; None while the cast is in progress
+    pub outcome: Option<&'static str>,
+}
+
+impl EnemyCasts {
+    pub fn casts(&self) -> &[CastWindow] {
+        &self.casts
+    }
+
+    pub fn markers(&self) -> &[(f64, String, u64, String, f64, f64)] {
+        &self.markers
+    }
+
+    fn start(&mut self, event: &LogEvent, combat: &CombatContext) {
+        // A new cast ends one the enemy was still casting
+        self.finish(combat.source_guid, event.timestamp_secs, "failed");
+        self.casting.insert(combat.source_guid.to_string(), self.casts.len());
+        self.casts.push(CastWindow {
+            start_secs: event.timestamp_secs,
+            end_secs: None,
+            enemy_name: combat.source_name.to_string(),
+            spell_id: event.spell_id(),
+            spell_name: event.spell_name(),
+            outcome: None,
+        });
+    }
+
+    fn finish(&mut self, caster_guid: &str, timestamp_secs: f64, outcome: &'static str) {
+        if let Some(cast) = self.casting.remove(caster_guid).and_then(|i| self.casts.get_mut(i)) {
+            cast.end_secs = Some(timestamp_secs);
+            cast.outcome = Some(outcome);
+        }
+    }
+
+    /// A cast that went off without a target, at the position in the event's advanced info
+    /// (the log has no separate ground position)
+    fn record_marker(&mut self, event: &LogEvent, combat: &CombatContext) {
+        if guid::parse(combat.dest_guid).is_some() {
+            return;
+        }
+        let position = |offset: usize| event.fields.get(combat.field_map.spell_pos_x + offset).and_then(|s| s.parse::<f64>().ok());
+        if let (Some(x), Some(y)) = (position(0), position(1)) {
+            if x.abs() > 0.01 || y.abs() > 0.01 {
+                self.markers.push((event.timestamp_secs, combat.source_name.to_string(), event.spell_id(), event.spell_name(), x, y));
+            }
+        }
+    }
+}
+
+impl EventHandler for EnemyCasts {
+    fn name(&self) -> &str {
+        "enemy_casts"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        matches!(event_type, "SPELL_CAST_START" | "SPELL_CAST_SUCCESS" | "SPELL_CAST_FAILED" | "SPELL_INTERRUPT" | "UNIT_DIED")
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        let Some(combat) = event.combat else {
+            return;
+        };
+        match event.event_type {
+            "SPELL_CAST_START" if guid::is_npc(combat.source_guid) => self.start(event, &combat),
+            "SPELL_CAST_SUCCESS" if guid::is_npc(combat.source_guid) => {
+                if self.casting.get(combat.source_guid).is_some_and(|&i| self.casts[i].spell_id == event.spell_id()) {
+                    self.finish(combat.source_guid, event.timestamp_secs, "success");
+                }
+                self.record_marker(event, &combat);
+            }
+            "SPELL_CAST_FAILED" => self.finish(combat.source_guid, event.timestamp_secs, "failed"),
+            "SPELL_INTERRUPT" => self.finish(combat.dest_guid, event.timestamp_secs, "interrupted"),
+            "UNIT_DIED" => self.finish(combat.dest_guid, event.timestamp_secs, "failed"),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Self-contained combat event features
+//!
+//! `process_combat_event` keeps the core bookkeeping (damage, healing, deaths, auras) that
+//! most of the summary is built from. A feature that only needs to watch a few event types
+//! and report on them afterwards lives here instead, owning its state: implement
+//! [`EventHandler`] for it, add it to [`CombatHandlers`] and read it back from the `build_*`
+//! method that needs it. Every event these handlers see has a `combat` context.
+
+mod absorbs;
+mod burst_cooldowns;
+mod crowd_control;
+mod custom_metrics;
+mod enemy_casts;
+mod opener;
+mod practice;
+mod tanking;
+mod utility;
+
+use std::collections::HashMap;
+
+use crate::events::{EventHandler, LogEvent};
+use crate::guid;
+use crate::options::{AnalysisFeatures, ParseOptions};
+
+pub(crate) use absorbs::Absorbs;
+pub(crate) use burst_cooldowns::BurstCooldowns;
+pub(crate) use crowd_control::CrowdControl;
+pub(crate) use custom_metrics::CustomMetrics;
+pub(crate) use enemy_casts::EnemyCasts;
+pub(crate) use opener::PullOpeners;
+pub(crate) use practice::PracticeCasts;
+pub(crate) use tanking::EnemyTargets;
+pub(crate) use utility::UtilityActions;
+
+/// Whether `dest` is an enemy of the player: any non-player, or a player on the other arena team
+pub(crate) fn is_enemy(pvp_teams: &HashMap<String, u32>, player_guid: &str, dest: &str) -> bool {
+    if !guid::is_player(dest) {
+        return true;
+    }
+    matches!((pvp_teams.get(player_guid), pvp_teams.get(dest)), (Some(a), Some(b)) if a != b)
+}
+
+/// The player owning `guid`, following pets of pets; `None` for players and unowned units
+pub(crate) fn owner_of(pet_owners: &HashMap<String, String>, guid: &str) -> Option<String> {
+    let mut current = guid;
+    for _ in 0..5 {
+        match pet_owners.get(current) {
+            Some(owner) if guid::is_player(owner) => return Some(owner.clone()),
+            Some(owner) => current = owner,
+            None => return None,
+        }
+    }
+    None
+}
+
+/// The handlers an `EventTracker` runs after its core bookkeeping has seen an event,
+/// dispatched in field order. Handlers for a disabled analysis feature never see an event.
+pub(crate) struct CombatHandlers {
+    features: AnalysisFeatures,
+    pub absorbs: Absorbs,
+    pub burst_cooldowns: BurstCooldowns,
+    pub crowd_control: CrowdControl,
+    pub custom_metrics: CustomMetrics,
+    pub enemy_casts: EnemyCasts,
+    pub practice: PracticeCasts,
+    pub opener: PullOpeners,
+    pub tanking: EnemyTargets,
+    pub utility: UtilityActions,
+}
+
+impl CombatHandlers {
+    pub fn new(options: &ParseOptions) -> Self {
+        CombatHandlers {
+            features: options.features,
+            absorbs: Absorbs::default(),
+            burst_cooldowns: BurstCooldowns::default(),
+            crowd_control: CrowdControl::new(options),
+            custom_metrics: CustomMetrics::new(options),
+            enemy_casts: EnemyCasts::default(),
+            practice: PracticeCasts::default(),
+            opener: PullOpeners::default(),
+            tanking: EnemyTargets::default(),
+            utility: UtilityActions::default(),
+        }
+    }
+
+    pub fn handle(&mut self, event: &LogEvent) {
+        let handlers: [(bool, &mut dyn EventHandler); 9] = [
+            (true, &mut self.absorbs),
+            (true, &mut self.burst_cooldowns),
+            (self.features.crowd_control, &mut self.crowd_control),
+            (true, &mut self.custom_metrics),
+            (self.features.replay, &mut self.enemy_casts),
+            (true, &mut self.practice),
+            (self.features.pull_openers, &mut self.opener),
+            (self.features.pull_openers, &mut self.tanking),
+            (true, &mut self.utility),
+        ];
+        for (_, handler) in handlers.into_iter().filter(|(enabled, h)| *enabled && h.handles(event.event_type)) {
+            handler.handle(event);
+        }
+    }
+}
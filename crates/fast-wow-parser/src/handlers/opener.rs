@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+
+use crate::events::{CombatContext, EventHandler, LogEvent};
+use crate::game_data;
+use crate::guid;
+
+/// How pulls were opened: who each enemy went for first and which threat redirects were cast
+#[derive(Default)]
+pub(crate) struct PullOpeners {
+    /// Threat redirects: (timestamp_secs, caster_guid, spell_id, target_name)
+    redirect_casts: Vec<(f64, String, u64, String)>,
+    /// The first player each enemy meleed: (timestamp_secs, enemy_name, player_guid)
+    first_melee_targets: Vec<(f64, String, String)>,
+    /// Enemies that have meleed anyone, for `first_melee_targets`
+    meleeing_enemies: HashSet<String>,
+}
+
+impl PullOpeners {
+    pub fn redirect_casts(&self) -> &[(f64, String, u64, String)] {
+        &self.redirect_casts
+    }
+
+    pub fn first_melee_targets(&self) -> &[(f64, String, String)] {
+        &self.first_melee_targets
+    }
+
+    /// Keep an enemy's first melee swing at a player
+    fn record_melee(&mut self, event: &LogEvent, combat: &CombatContext) {
+        if guid::is_npc(combat.source_guid) && guid::is_player(combat.dest_guid)
+            && self.meleeing_enemies.insert(combat.source_guid.to_string())
+        {
+            self.first_melee_targets.push((event.timestamp_secs, combat.source_name.to_string(), combat.dest_guid.to_string()));
+        }
+    }
+}
+
+impl EventHandler for PullOpeners {
+    fn name(&self) -> &str {
+        "pull_openers"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        matches!(event_type, "SWING_DAMAGE" | "SWING_DAMAGE_LANDED" | "SPELL_CAST_SUCCESS")
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        let Some(combat) = event.combat else {
+            return;
+        };
+        match event.event_type {
+            "SWING_DAMAGE" | "SWING_DAMAGE_LANDED" => self.record_melee(event, &combat),
+            "SPELL_CAST_SUCCESS" if guid::is_player(combat.source_guid) => {
+                let spell_id = event.spell_id();
+                if game_data::threat_redirect_name(spell_id).is_some() {
+                    self.redirect_casts.push((event.timestamp_secs, combat.source_guid.to_string(), spell_id, combat.dest_name.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
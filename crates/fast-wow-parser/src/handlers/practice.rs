@@ -0,0 +1,36 @@
+use crate::events::{EventHandler, LogEvent};
+use crate::guid;
+
+/// Every player cast, for practice sessions. Off unless `enabled` is set.
+#[derive(Default)]
+pub(crate) struct PracticeCasts {
+    pub enabled: bool,
+    /// (timestamp_secs, caster_guid, spell_id, spell_name)
+    casts: Vec<(f64, String, u64, String)>,
+}
+
+impl PracticeCasts {
+    pub fn casts(&self) -> &[(f64, String, u64, String)] {
+        &self.casts
+    }
+}
+
+impl EventHandler for PracticeCasts {
+    fn name(&self) -> &str {
+        "practice_casts"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        self.enabled && event_type == "SPELL_CAST_SUCCESS"
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        let Some(combat) = event.combat.filter(|c| guid::is_player(c.source_guid)) else {
+            return;
+        };
+        let spell_id = event.spell_id();
+        if spell_id > 0 {
+            self.casts.push((event.timestamp_secs, combat.source_guid.to_string(), spell_id, event.spell_name()));
+        }
+    }
+}
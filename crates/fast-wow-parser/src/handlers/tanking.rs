@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use crate::events::{CombatContext, EventHandler, LogEvent};
+use crate::guid;
+
+/// Width of the windows an enemy's hits are counted in to tell who it was attacking
+const BUCKET_SECS: f64 = 3.0;
+
+/// Hits each enemy landed on players, counted per `BUCKET_SECS` window, to follow who it was
+/// attacking. Periodic damage is left out: a DoT keeps ticking whoever the enemy turned to.
+#[derive(Default)]
+pub(crate) struct EnemyTargets {
+    /// enemy_guid -> (enemy_name, windows in time order)
+    enemies: HashMap<String, (String, Vec<TargetBucket>)>,
+}
+
+/// The hits of one enemy in one window
+pub(crate) struct TargetBucket {
+    pub index: i64,
+    pub first_secs: f64,
+    pub last_secs: f64,
+    /// (player_guid, melee swings, other hits)
+    pub hits: Vec<(String, u32, u32)>,
+}
+
+impl TargetBucket {
+    /// The player the enemy was mostly attacking: the most melee swings, then the most hits
+    pub fn primary_target(&self) -> Option<&str> {
+        self.hits.iter()
+            .max_by(|a, b| (a.1, a.2).cmp(&(b.1, b.2)).then_with(|| b.0.cmp(&a.0)))
+            .map(|(guid, ..)| guid.as_str())
+    }
+}
+
+impl EnemyTargets {
+    /// (enemy_guid, enemy_name, windows) of every enemy that hit a player
+    pub fn enemies(&self) -> impl Iterator<Item = (&str, &str, &[TargetBucket])> {
+        self.enemies.iter().map(|(guid, (name, buckets))| (guid.as_str(), name.as_str(), buckets.as_slice()))
+    }
+
+    fn record_hit(&mut self, event: &LogEvent, combat: &CombatContext, swing: bool) {
+        if !guid::is_npc(combat.source_guid) || !guid::is_player(combat.dest_guid) {
+            return;
+        }
+        let index = (event.timestamp_secs / BUCKET_SECS).floor() as i64;
+        let (_, buckets) = self.enemies.entry(combat.source_guid.to_string())
+            .or_insert_with(|| (combat.source_name.to_string(), Vec::new()));
+        if buckets.last().is_none_or(|b| b.index != index) {
+            buckets.push(TargetBucket { index, first_secs: event.timestamp_secs, last_secs: event.timestamp_secs, hits: Vec::new() });
+        }
+        let bucket = buckets.last_mut().expect("bucket was just pushed");
+        bucket.last_secs = event.timestamp_secs;
+        let entry = match bucket.hits.iter_mut().position(|(guid, ..)| guid == combat.dest_guid) {
+            Some(i) => &mut bucket.hits[i],
+            None => {
+                bucket.hits.push((combat.dest_guid.to_string(), 0, 0));
+                bucket.hits.last_mut().expect("hit was just pushed")
+            }
+        };
+        if swing {
+            entry.1 += 1;
+        } else {
+            entry.2 += 1;
+        }
+    }
+}
+
+impl EventHandler for EnemyTargets {
+    fn name(&self) -> &str {
+        "enemy_targets"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        matches!(event_type, "SWING_DAMAGE" | "SWING_MISSED" | "SPELL_DAMAGE" | "SPELL_MISSED" | "RANGE_DAMAGE" | "RANGE_MISSED")
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        if let Some(combat) = event.combat {
+            self.record_hit(event, &combat, event.event_type.starts_with("SWING"));
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::events::{EventHandler, LogEvent};
+use crate::guid;
+
+/// Interrupts and dispels by each player, pets' credited to their owner. Purges and
+/// spellsteals count as dispels.
+#[derive(Default)]
+pub(crate) struct UtilityActions {
+    /// (timestamp_secs, player_guid)
+    interrupts: Vec<(f64, String)>,
+    /// (timestamp_secs, player_guid)
+    dispels: Vec<(f64, String)>,
+}
+
+impl UtilityActions {
+    /// Interrupts and dispels by the player between `from_secs` and `to_secs`
+    pub fn counts(&self, player_guid: &str, from_secs: f64, to_secs: f64) -> (u32, u32) {
+        let count = |actions: &[(f64, String)]| {
+            actions.iter()
+                .filter(|(at, guid)| guid == player_guid && *at >= from_secs && *at <= to_secs)
+                .count() as u32
+        };
+        (count(&self.interrupts), count(&self.dispels))
+    }
+}
+
+impl EventHandler for UtilityActions {
+    fn name(&self) -> &str {
+        "utility_actions"
+    }
+
+    fn handles(&self, event_type: &str) -> bool {
+        matches!(event_type, "SPELL_INTERRUPT" | "SPELL_DISPEL" | "SPELL_STOLEN")
+    }
+
+    fn handle(&mut self, event: &LogEvent) {
+        let Some(combat) = event.combat else {
+            return;
+        };
+        let actions = match event.event_type {
+            "SPELL_INTERRUPT" => &mut self.interrupts,
+            "SPELL_DISPEL" | "SPELL_STOLEN" => &mut self.dispels,
+            _ => return,
+        };
+        if guid::is_player(combat.effective_source) {
+            actions.push((event.timestamp_secs, combat.effective_source.to_string()));
+        }
+    }
+}
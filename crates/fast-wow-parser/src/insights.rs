@@ -0,0 +1,244 @@
+//! Human-readable insights per encounter ("3 players hit by Web Bomb", "Bloodlust used 40s
+//! late", "Tankone died with Shield Wall available"), from declarative rules
+//!
+//! The built-in rules live in `game_data/insight_rules.json`. More can be added without code
+//! changes in an `insight_rules.json` next to the executable (a JSON array of rules); a rule
+//! with the id of a built-in one replaces it. Each rule picks a check with `type` and
+//! describes its finding with a `message` template whose `{placeholders}` depend on the check.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{EncounterSummary, Insight};
+use crate::parser::from_millis;
+
+const BUILTIN_RULES: &str = include_str!("game_data/insight_rules.json");
+
+/// File next to the executable with extra (or replacement) rules
+const RULES_FILE: &str = "insight_rules.json";
+
+/// When a rule applies and what it reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsightRule {
+    pub id: String,
+    /// What the rule is for; not shown with the insight
+    #[serde(default)]
+    pub description: String,
+    /// Boss encounter IDs (or Mythic+ dungeon instance IDs) the rule is limited to
+    #[serde(default)]
+    pub encounter_ids: Vec<u64>,
+    /// "boss", "mythic_plus", ...; empty for every type
+    #[serde(default)]
+    pub encounter_types: Vec<String>,
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    pub message: String,
+    #[serde(flatten)]
+    pub check: InsightCheck,
+}
+
+fn default_severity() -> String {
+    "info".to_string()
+}
+
+fn default_min() -> u32 {
+    1
+}
+
+/// What a rule looks for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InsightCheck {
+    /// Players who took damage from any of `spell_ids`: `{count}`, `{spell}`, `{players}`
+    HitBy {
+        spell_ids: Vec<u64>,
+        #[serde(default = "default_min")]
+        min_players: u32,
+    },
+    /// The first of `spell_ids` to go up on anyone, if later than `expected_by_secs` plus
+    /// `tolerance_secs`: `{spell}`, `{time_secs}`, `{late_secs}`. Needs buff timelines.
+    AuraTiming {
+        spell_ids: Vec<u64>,
+        expected_by_secs: f64,
+        #[serde(default)]
+        tolerance_secs: f64,
+        /// Reported when none of the spells went up; nothing is reported without it
+        #[serde(default)]
+        missing_message: Option<String>,
+    },
+    /// One insight per death of a player in `roles` (all if empty) who had a personal
+    /// defensive or potion unused: `{player}`, `{spells}`, `{time_secs}`
+    DeathWithDefensive {
+        #[serde(default)]
+        roles: Vec<String>,
+    },
+    /// Deaths, optionally only those in the first `within_secs`: `{count}`, `{count_deaths}`
+    /// ("death"/"deaths"), `{players}`
+    Deaths {
+        #[serde(default)]
+        within_secs: Option<f64>,
+        #[serde(default = "default_min")]
+        min_deaths: u32,
+    },
+    /// One insight per player whose damage taken was at least `min_pct` percent avoidable:
+    /// `{player}`, `{pct}`, `{damage}`, `{spell}`
+    AvoidableDamage {
+        min_pct: f64,
+    },
+}
+
+/// Built-in rules with the ones from `insight_rules.json` merged in
+pub fn rules() -> &'static [InsightRule] {
+    static RULES: OnceLock<Vec<InsightRule>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let mut rules: Vec<InsightRule> = serde_json::from_str(BUILTIN_RULES).expect("built-in insight rules are valid");
+        let path = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(RULES_FILE)))
+            .filter(|p| p.exists())
+            .unwrap_or_else(|| RULES_FILE.into());
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            match serde_json::from_str::<Vec<InsightRule>>(&contents) {
+                Ok(extra) => {
+                    for rule in extra {
+                        match rules.iter_mut().find(|r| r.id == rule.id) {
+                            Some(existing) => *existing = rule,
+                            None => rules.push(rule),
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring {}: {}", path.display(), e),
+            }
+        }
+        rules
+    })
+}
+
+/// Fill `{name}` placeholders
+fn render(template: &str, values: &[(&str, String)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in values {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    if count == 1 { one.to_string() } else { many.to_string() }
+}
+
+/// Insights `rules` find in an encounter, in rule order
+pub fn evaluate(enc: &EncounterSummary, rules: &[InsightRule]) -> Vec<Insight> {
+    let mut insights = Vec::new();
+    for rule in rules {
+        if !rule.encounter_ids.is_empty() && !rule.encounter_ids.contains(&enc.encounter_id) {
+            continue;
+        }
+        if !rule.encounter_types.is_empty() && !rule.encounter_types.contains(&enc.encounter_type) {
+            continue;
+        }
+        let mut found = |message: String, players: Vec<String>| insights.push(Insight {
+            rule_id: rule.id.clone(),
+            severity: rule.severity.clone(),
+            message,
+            players,
+        });
+        match &rule.check {
+            InsightCheck::HitBy { spell_ids, min_players } => {
+                let mut spell = String::new();
+                let players: Vec<String> = enc.players.iter()
+                    .filter(|p| p.damage_taken_abilities.iter().any(|a| {
+                        let hit = spell_ids.contains(&a.spell_id) && a.hit_count > 0;
+                        if hit && spell.is_empty() {
+                            spell = a.spell_name.clone();
+                        }
+                        hit
+                    }))
+                    .map(|p| p.name.clone())
+                    .collect();
+                if !players.is_empty() && players.len() as u32 >= *min_players {
+                    let message = render(&rule.message, &[
+                        ("count", players.len().to_string()),
+                        ("spell", spell),
+                        ("players", players.join(", ")),
+                    ]);
+                    found(message, players);
+                }
+            }
+            InsightCheck::AuraTiming { spell_ids, expected_by_secs, tolerance_secs, missing_message } => {
+                // Buff timeline times are seconds into the fight
+                let first = enc.buff_uptimes.values()
+                    .flatten()
+                    .filter(|b| spell_ids.contains(&b.spell_id))
+                    .filter_map(|b| b.timeline.iter().find(|e| e.event_type == "apply").map(|e| (e.time, &b.spell_name)))
+                    .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+                match first {
+                    Some((time, spell)) if time > expected_by_secs + tolerance_secs => {
+                        let message = render(&rule.message, &[
+                            ("spell", spell.clone()),
+                            ("time_secs", format!("{:.0}", time)),
+                            ("late_secs", format!("{:.0}", time - expected_by_secs)),
+                        ]);
+                        found(message, Vec::new());
+                    }
+                    None if enc.buff_uptimes.values().flatten().any(|b| !b.timeline.is_empty()) => {
+                        if let Some(missing) = missing_message {
+                            found(missing.clone(), Vec::new());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            InsightCheck::DeathWithDefensive { roles } => {
+                let role_of: HashMap<&str, &str> = enc.players.iter().map(|p| (p.guid.as_str(), p.role.as_str())).collect();
+                for death in enc.deaths.iter().filter(|d| !d.could_have_used.is_empty()) {
+                    // Players without a row (no damage or healing done) only match rules for any role
+                    let role = role_of.get(death.player_guid.as_str()).copied();
+                    if !roles.is_empty() && !roles.iter().any(|r| Some(r.as_str()) == role) {
+                        continue;
+                    }
+                    let message = render(&rule.message, &[
+                        ("player", death.player_name.clone()),
+                        ("spells", death.could_have_used.join(", ")),
+                        ("time_secs", format!("{:.0}", from_millis(death.time_ms))),
+                    ]);
+                    found(message, vec![death.player_name.clone()]);
+                }
+            }
+            InsightCheck::Deaths { within_secs, min_deaths } => {
+                let deaths: Vec<String> = enc.deaths.iter()
+                    .filter(|d| within_secs.is_none_or(|secs| from_millis(d.time_ms) <= secs))
+                    .map(|d| d.player_name.clone())
+                    .collect();
+                if !deaths.is_empty() && deaths.len() as u32 >= *min_deaths {
+                    let message = render(&rule.message, &[
+                        ("count", deaths.len().to_string()),
+                        ("count_deaths", plural(deaths.len(), "death", "deaths")),
+                        ("players", deaths.join(", ")),
+                    ]);
+                    found(message, deaths);
+                }
+            }
+            InsightCheck::AvoidableDamage { min_pct } => {
+                for avoidable in &enc.avoidable_damage.players {
+                    let Some(player) = enc.players.iter().find(|p| p.name == avoidable.player_name) else {
+                        continue;
+                    };
+                    let pct = avoidable.damage as f64 / player.damage_taken.max(1) as f64 * 100.0;
+                    if avoidable.damage > 0 && pct >= *min_pct {
+                        let message = render(&rule.message, &[
+                            ("player", player.name.clone()),
+                            ("pct", format!("{:.0}", pct)),
+                            ("damage", avoidable.damage.to_string()),
+                            ("spell", avoidable.top_spell.clone()),
+                        ]);
+                        found(message, vec![player.name.clone()]);
+                    }
+                }
+            }
+        }
+    }
+    insights
+}
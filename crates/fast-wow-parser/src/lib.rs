@@ -0,0 +1,79 @@
+//! World of Warcraft combat log parser
+//!
+//! Turns a `WoWCombatLog-*.txt` file into a [`models::CombatLogSummary`]: encounters,
+//! Mythic+ keys with their boss/trash segments, per-player damage, healing, deaths,
+//! buff uptimes and replay data.
+//!
+//! Parse a whole file:
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! let summary = fast_wow_parser::parse_combat_log(Path::new("WoWCombatLog.txt")).unwrap();
+//! for enc in &summary.encounters {
+//!     println!("{} ({:.0}s)", enc.name, enc.duration_secs);
+//! }
+//! ```
+//!
+//! Or feed lines as they arrive with the streaming parser:
+//!
+//! ```no_run
+//! use fast_wow_parser::CombatLogParser;
+//!
+//! let mut parser = CombatLogParser::new("live.txt");
+//! # let lines: Vec<String> = Vec::new();
+//! for line in &lines {
+//!     parser.process_line(line);
+//! }
+//! let summary = parser.finish();
+//! ```
+//!
+//! For custom analysis, drive an [`parser::EventTracker`] directly with
+//! [`parser::process_combat_event`] and read it back with its `build_*` methods.
+
+pub mod anonymize;
+pub mod auras;
+pub mod benchmarks;
+pub mod camera;
+pub mod compare;
+pub mod damage_types;
+pub mod efficiency;
+pub mod events;
+pub mod export;
+pub mod game_data;
+pub mod grouping;
+pub mod guid;
+pub mod insights;
+mod handlers;
+pub mod log_file;
+pub mod log_format;
+pub mod merge;
+pub mod models;
+pub mod options;
+pub mod parser;
+pub mod probe;
+pub mod resolution;
+pub mod schools;
+pub mod split;
+pub mod trend;
+
+pub use options::{AnalysisFeatures, ParseOptions};
+
+/// Version of this crate, recorded next to data derived from a parse so it can be told stale
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the summary the parser produces. Bump it whenever a change to the parser changes
+/// its output, so cached parses and history records from older parser logic are dropped
+/// rather than served.
+pub const SCHEMA_VERSION: u32 = 17;
+
+/// JSON Schema of [`models::CombatLogSummary`], with every model nested in it under `$defs`,
+/// for tools reading the summary (validation, codegen). Derived from the models themselves,
+/// so it can't drift from what is serialized; `x-schema-version` is [`SCHEMA_VERSION`].
+pub fn summary_schema() -> serde_json::Value {
+    let generator = schemars::generate::SchemaSettings::default().for_serialize().into_generator();
+    let mut schema = generator.into_root_schema_for::<models::CombatLogSummary>();
+    schema.insert("x-schema-version".to_string(), SCHEMA_VERSION.into());
+    schema.to_value()
+}
+pub use parser::{parse_combat_log, CombatLogParser};
@@ -0,0 +1,70 @@
+//! Access to combat log files that WoW may still be writing
+//!
+//! On Windows, opening or stat-ing the log while the client flushes it can fail with a
+//! sharing/lock violation; these are transient, so retry a few times before giving up.
+//! The retries sleep, so async code calls these from a blocking task.
+
+use std::fs::{File, Metadata};
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const RETRY_ATTEMPTS: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A log modified within this window is treated as still being written
+pub const ACTIVE_WINDOW_SECS: u64 = 300;
+
+/// ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION (Windows)
+fn is_share_violation(e: &io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(32) | Some(33))
+}
+
+/// Run `op`, retrying with a short delay while it fails with a sharing violation
+pub fn with_retry<T>(mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < RETRY_ATTEMPTS && is_share_violation(&e) => {
+                tracing::debug!("share violation (attempt {}): {}", attempt, e);
+                std::thread::sleep(RETRY_DELAY * attempt);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `reader.read_until(byte, buf)`, retried like `with_retry` but only while nothing was read:
+/// after a partial read the bytes are already in `buf`, and a retry would append to them
+pub fn read_until(reader: &mut impl BufRead, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+    let start = buf.len();
+    let mut attempt = 1;
+    loop {
+        match reader.read_until(byte, buf) {
+            Err(e) if buf.len() == start && attempt < RETRY_ATTEMPTS && is_share_violation(&e) => {
+                tracing::debug!("share violation (attempt {}): {}", attempt, e);
+                std::thread::sleep(RETRY_DELAY * attempt);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+pub fn open(path: &Path) -> io::Result<File> {
+    with_retry(|| File::open(path))
+}
+
+pub fn metadata(path: &Path) -> io::Result<Metadata> {
+    with_retry(|| std::fs::metadata(path))
+}
+
+/// Whether the log was modified recently enough that WoW is probably still appending to it
+pub fn is_active(metadata: &Metadata) -> bool {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|m| SystemTime::now().duration_since(m).ok())
+        .is_some_and(|age| age.as_secs() < ACTIVE_WINDOW_SECS)
+}
@@ -0,0 +1,79 @@
+//! Combat log format detection and per-format field offsets
+//!
+//! Retail and the Classic clients (Era, Season of Discovery, Cataclysm/Mists Classic)
+//! share the event names but not the field layout: Classic has a shorter advanced-info
+//! block, no `baseAmount` in damage/heal suffixes and no COMBATANT_INFO spec ID.
+
+/// Which client produced the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Retail,
+    Classic,
+}
+
+/// Field indices for the events whose layout differs between formats.
+/// Indices count from the event name at 0 (as returned by `parse_csv_fields`).
+#[derive(Debug, Clone, Copy)]
+pub struct FieldMap {
+    /// Amount field of SPELL_* damage/heal events
+    pub spell_amount: usize,
+    /// Amount field of SWING_* damage events
+    pub swing_amount: usize,
+    /// posX for SPELL_* events (posY follows it)
+    pub spell_pos_x: usize,
+    /// posX for SWING_* events (posY follows it)
+    pub swing_pos_x: usize,
+    /// Distance from the amount field to overkill / overhealing
+    pub overkill_gap: usize,
+    /// COMBATANT_INFO carries a spec ID at field 25
+    pub has_combatant_spec: bool,
+}
+
+impl LogFormat {
+    /// Detect the format from a COMBAT_LOG_VERSION line, e.g.
+    /// `COMBAT_LOG_VERSION,22,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,11.0.5,PROJECT_ID,1`
+    pub fn detect(fields: &[&str]) -> LogFormat {
+        // PROJECT_ID 1 is retail; everything else is a Classic flavor
+        if let Some(project_id) = fields.get(7).and_then(|s| s.parse::<u32>().ok()) {
+            return if project_id == 1 { LogFormat::Retail } else { LogFormat::Classic };
+        }
+        // Older logs without PROJECT_ID: Classic builds are all below 10.x
+        let build_major = fields.get(5)
+            .and_then(|s| s.trim_matches('"').split('.').next())
+            .and_then(|s| s.parse::<u32>().ok());
+        match build_major {
+            Some(major) if major < 10 => LogFormat::Classic,
+            _ => LogFormat::Retail,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogFormat::Retail => "retail",
+            LogFormat::Classic => "classic",
+        }
+    }
+
+    pub fn field_map(self) -> FieldMap {
+        match self {
+            // 19-field advanced block, suffix: amount, baseAmount, overkill/overhealing, ...
+            LogFormat::Retail => FieldMap {
+                spell_amount: 31,
+                swing_amount: 28,
+                spell_pos_x: 26,
+                swing_pos_x: 23,
+                overkill_gap: 2,
+                has_combatant_spec: true,
+            },
+            // 17-field advanced block, suffix: amount, overkill/overhealing, ...
+            LogFormat::Classic => FieldMap {
+                spell_amount: 29,
+                swing_amount: 26,
+                spell_pos_x: 24,
+                swing_pos_x: 21,
+                overkill_gap: 1,
+                has_combatant_spec: false,
+            },
+        }
+    }
+}
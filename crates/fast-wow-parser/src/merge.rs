@@ -0,0 +1,184 @@
+//! Joining the logs of one session back into a single log, for when a game restart or crash
+//! made WoW start a new file mid-raid
+//!
+//! The logs are written in time order. A header identical to the one already in force is
+//! dropped, and where a log starts before the previous one ended (a copied or resumed file)
+//! the lines already written are skipped, so the result reads like one uninterrupted log.
+//! An existing file is never overwritten.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::log_file;
+use crate::parser::{parse_timestamp_to_secs, split_timestamp_event};
+use crate::split::{read_lines, HEADER_EVENT};
+
+/// What a merge wrote and what it left out
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeReport {
+    pub lines: u64,
+    /// Repeated COMBAT_LOG_VERSION headers left out
+    pub headers_dropped: u64,
+    /// Lines a log shared with the one before it
+    pub overlapping_lines: u64,
+}
+
+/// Why a merge failed
+#[derive(Debug)]
+pub enum MergeError {
+    /// `out_path` already exists; nothing was written
+    Exists,
+    Failed(String),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::Exists => write!(f, "The merged log already exists"),
+            MergeError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<String> for MergeError {
+    fn from(e: String) -> Self {
+        MergeError::Failed(e)
+    }
+}
+
+/// Write the logs at `paths` into `out_path` as one log, ordered by their first line.
+/// `out_path` must not exist yet.
+pub fn merge(paths: &[PathBuf], out_path: &Path) -> Result<MergeReport, MergeError> {
+    let mut ordered = Vec::with_capacity(paths.len());
+    for path in paths {
+        ordered.push((first_timestamp(path)?, path));
+    }
+    ordered.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+
+    let file = match std::fs::OpenOptions::new().write(true).create_new(true).open(out_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Err(MergeError::Exists),
+        Err(e) => return Err(MergeError::Failed(format!("Failed to create {}: {}", out_path.display(), e))),
+    };
+    let mut out = BufWriter::new(file);
+    let mut report = MergeReport::default();
+    let mut header: Option<String> = None;
+    // The last written timestamp and the lines written at it, to recognise an overlap
+    let mut last_secs = f64::MIN;
+    let mut at_last_secs: HashSet<String> = HashSet::new();
+    let mut write_error = None;
+
+    for (_, path) in ordered {
+        let mut overlapping = true;
+        read_lines(path, |line, _, _| {
+            if write_error.is_some() {
+                return;
+            }
+            let Some((timestamp, event)) = split_timestamp_event(line) else {
+                return;
+            };
+            if event.starts_with(HEADER_EVENT) {
+                if header.as_deref() == Some(event) {
+                    report.headers_dropped += 1;
+                    return;
+                }
+                header = Some(event.to_string());
+            } else {
+                let secs = parse_timestamp_to_secs(timestamp);
+                if overlapping && (secs < last_secs || (secs == last_secs && at_last_secs.contains(line))) {
+                    report.overlapping_lines += 1;
+                    return;
+                }
+                overlapping = false;
+                if secs != last_secs {
+                    last_secs = secs;
+                    at_last_secs.clear();
+                }
+                at_last_secs.insert(line.to_string());
+            }
+            if let Err(e) = writeln!(out, "{}", line) {
+                write_error = Some(e);
+            }
+            report.lines += 1;
+        })?;
+    }
+    if let Some(e) = write_error {
+        return Err(MergeError::Failed(format!("Failed to write {}: {}", out_path.display(), e)));
+    }
+    out.flush().map_err(|e| MergeError::Failed(format!("Failed to write {}: {}", out_path.display(), e)))?;
+    Ok(report)
+}
+
+/// Seconds of the log's first line, to order the logs; an empty log sorts first
+fn first_timestamp(path: &Path) -> Result<f64, String> {
+    let file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(split_timestamp_event(line.trim_end())
+        .map(|(timestamp, _)| parse_timestamp_to_secs(timestamp))
+        .unwrap_or(f64::MIN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// part_b is a copy of part_a that kept recording: same header, the same first six lines
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("merge").join(name)
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fast-wow-parser-merge-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn drops_the_repeated_header_and_the_overlap() {
+        let dir = scratch_dir("overlap");
+        let out_path = dir.join("merged.txt");
+        // Given in reverse: the logs are ordered by their first line, ties by path
+        let report = merge(&[fixture("part_b.txt"), fixture("part_a.txt")], &out_path).unwrap();
+        assert_eq!(report.headers_dropped, 1);
+        assert_eq!(report.overlapping_lines, 5);
+        assert_eq!(report.lines, 8);
+
+        let merged = std::fs::read_to_string(&out_path).unwrap();
+        let part_b = std::fs::read_to_string(fixture("part_b.txt")).unwrap();
+        assert_eq!(merged, part_b);
+        assert_eq!(merged.matches(HEADER_EVENT).count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_a_header_that_changed() {
+        let dir = scratch_dir("header");
+        let later = dir.join("later.txt");
+        std::fs::write(&later, "3/2/2025 21:30:00.000  COMBAT_LOG_VERSION,22,ADVANCED_LOG_ENABLED,0,BUILD_VERSION,11.1.0,PROJECT_ID,1\n\
+            3/2/2025 21:30:00.100  ZONE_CHANGE,2657,\"Nerub-ar Palace\",16\n").unwrap();
+        let out_path = dir.join("merged.txt");
+        let report = merge(&[fixture("part_a.txt"), later], &out_path).unwrap();
+        assert_eq!(report.headers_dropped, 0);
+        assert_eq!(report.overlapping_lines, 0);
+        assert_eq!(report.lines, 8);
+        assert_eq!(std::fs::read_to_string(&out_path).unwrap().matches(HEADER_EVENT).count(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn never_overwrites_an_existing_file() {
+        let dir = scratch_dir("exists");
+        let out_path = dir.join("merged.txt");
+        std::fs::write(&out_path, "kept\n").unwrap();
+        let result = merge(&[fixture("part_a.txt"), fixture("part_b.txt")], &out_path);
+        assert!(matches!(result, Err(MergeError::Exists)));
+        assert_eq!(std::fs::read_to_string(&out_path).unwrap(), "kept\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
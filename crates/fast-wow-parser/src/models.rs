@@ -0,0 +1,1988 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A parsed combat log file
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CombatLogSummary {
+    pub filename: String,
+    pub log_version: Option<u32>,
+    pub build_version: Option<String>,
+    /// Client flavor the log came from: "retail" or "classic"
+    pub log_format: String,
+    /// False when a COMBAT_LOG_VERSION header said advanced combat logging was off: the
+    /// lines then carry no positions, HP or power, and the amounts sit in other fields
+    pub advanced_logging: bool,
+    pub encounters: Vec<EncounterSummary>,
+    pub zone_changes: Vec<ZoneChange>,
+    /// Time spent in each instance, one entry per visit
+    pub instance_visits: Vec<InstanceVisit>,
+    pub stats: ParseStats,
+    /// Reports of the `EventHandler`s registered for the parse, by handler name
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub extensions: std::collections::BTreeMap<String, serde_json::Value>,
+    /// Set when the parser crashed on a line; the summary then only covers the log up to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_error: Option<ParseError>,
+    /// Parse timing breakdown, only when profiling was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ParseProfile>,
+}
+
+/// What the parser saw, beyond the encounters it built
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct ParseStats {
+    pub lines: u64,
+    /// Event types the parser doesn't know (usually added by a newer patch), most frequent first
+    pub unknown_events: Vec<UnknownEvent>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct UnknownEvent {
+    pub event_type: String,
+    pub count: u64,
+    /// The first few lines of this type, without the timestamp
+    pub samples: Vec<String>,
+}
+
+/// The line the parser crashed on, for bug reports
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ParseError {
+    /// 1-based, counted from where this parse started reading
+    pub line_number: u64,
+    /// Byte offset of the line in the file
+    pub byte_offset: u64,
+    /// The line itself, cut to `MAX_LINE_EXCERPT_LEN` characters
+    pub line: String,
+    pub message: String,
+}
+
+/// Where parse time went, in seconds
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct ParseProfile {
+    pub total_secs: f64,
+    /// Reading lines from disk
+    pub io_secs: f64,
+    /// Timestamp and CSV field splitting
+    pub tokenize_secs: f64,
+    /// Event handling (sum of `events`)
+    pub handle_secs: f64,
+    /// Post-processing and building the summary
+    pub summary_secs: f64,
+    pub lines: u64,
+    /// Handling time per event type, slowest first
+    pub events: Vec<EventTiming>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EventTiming {
+    pub event_type: String,
+    pub count: u64,
+    pub secs: f64,
+}
+
+impl ParseProfile {
+    /// Add another profile's timings into this one
+    pub fn merge(&mut self, other: &ParseProfile) {
+        self.total_secs += other.total_secs;
+        self.io_secs += other.io_secs;
+        self.tokenize_secs += other.tokenize_secs;
+        self.handle_secs += other.handle_secs;
+        self.summary_secs += other.summary_secs;
+        self.lines += other.lines;
+        for ev in &other.events {
+            match self.events.iter_mut().find(|e| e.event_type == ev.event_type) {
+                Some(e) => {
+                    e.count += ev.count;
+                    e.secs += ev.secs;
+                }
+                None => self.events.push(ev.clone()),
+            }
+        }
+        self.events.sort_by(|a, b| b.secs.partial_cmp(&a.secs).unwrap_or(std::cmp::Ordering::Equal));
+    }
+}
+
+/// Summary of an encounter (boss fight or M+ key run)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EncounterSummary {
+    /// Position in the log; shifts when a re-parse merges or adds encounters
+    pub index: usize,
+    /// Survives re-parses of a growing log: see `stable_encounter_id`
+    pub stable_id: String,
+    pub encounter_id: u64,
+    pub name: String,
+    pub difficulty_id: u32,
+    pub difficulty_name: String,
+    /// Raid/dungeon name from the bundled instance table
+    pub instance_name: Option<String>,
+    /// Season/tier label (e.g. "The War Within Season 2")
+    pub season: Option<String>,
+    /// Instance icon URL
+    pub instance_icon: Option<String>,
+    pub group_size: u32,
+    pub success: bool,
+    pub duration_secs: f64,
+    /// When the fight started, as the log wrote it; times of events in it (`time_ms`) are
+    /// milliseconds from here
+    pub start_time: String,
+    pub end_time: String,
+    pub key_level: Option<u32>,
+    /// M+ par time from the bundled dungeon table
+    pub par_time_secs: Option<f64>,
+    /// Whether the key was completed within par time (M+ only)
+    pub timed: Option<bool>,
+    /// Keystone upgrade: 0 = depleted, 1-3 = +1/+2/+3 (M+ only)
+    pub plus_level: Option<u32>,
+    /// Par time minus completion time; negative when over time (M+ only)
+    pub time_remaining_secs: Option<f64>,
+    pub affixes: Vec<Affix>,
+    pub encounter_type: String,  // "boss", "mythic_plus", "dungeon", "trash", "combat", "practice", "arena"
+    pub boss_encounters: Vec<BossEncounter>,  // bosses within a M+ key
+    pub players: Vec<PlayerSummary>,
+    pub deaths: Vec<DeathEvent>,
+    /// Lethal hits soaked by absorbs
+    pub near_deaths: Vec<NearDeath>,
+    /// Full-group wipes (M+ only)
+    pub wipes: Vec<WipeEvent>,
+    /// Key duration minus estimated wipe run-back time (M+ only)
+    pub duration_excluding_wipes_secs: Option<f64>,
+    pub segments: Vec<KeySegment>,
+    /// Pulls granting the most enemy forces across the key, biggest first (M+ only)
+    pub top_count_pulls: Vec<CountPull>,
+    /// Per-player buff uptimes: player_guid -> Vec<BuffUptime>
+    pub buff_uptimes: std::collections::HashMap<String, Vec<BuffUptime>>,
+    /// Curated subset of `buff_uptimes` (raid buffs, food, flasks, lust, major externals)
+    pub key_buff_uptimes: std::collections::HashMap<String, Vec<BuffUptime>>,
+    /// Per-enemy damage breakdown
+    pub enemy_breakdowns: Vec<EnemyBreakdown>,
+    /// Damage each enemy dealt to each player
+    pub enemy_damage_taken: Vec<EnemyDamageTaken>,
+    /// Damage the group took from each enemy ability, most damage first
+    pub mechanics: Vec<Mechanic>,
+    /// Player damage to configured priority targets (adds that must die fast)
+    pub priority_targets: Vec<PriorityTarget>,
+    /// Per-player damage on boss vs trash segments (M+ only)
+    pub boss_trash_split: Vec<BossTrashSplit>,
+    /// Enemies whose damage is left out of players' filtered damage
+    pub pad_targets: Vec<PadTarget>,
+    /// Biggest raid-wide damage windows in fight order, with the healer cooldowns that
+    /// covered them; windows without any are the coverage gaps
+    pub healer_cd_coverage: Vec<RaidDamageEvent>,
+    /// Enemies switching their melee target from one tank to another, in fight order
+    pub tank_swaps: Vec<TankSwap>,
+    /// Each player's major damage cooldowns and how far they drifted, for keys and boss pulls
+    pub cooldown_usage: Vec<PlayerCooldowns>,
+    /// When each enemy joined and left the fight, for raid boss pulls
+    pub add_timeline: Option<AddTimeline>,
+    /// Casts and cooldown usage, for fights against only training dummies ("practice")
+    pub practice: Option<PracticeSession>,
+    /// Teams and rounds, for arena matches
+    pub arena: Option<ArenaMatch>,
+    /// Who healed whom, for raid boss encounters
+    pub healing_report: Option<HealingReport>,
+    /// What went wrong, for Mythic+ keys that weren't timed
+    pub depletion_report: Option<DepletionReport>,
+    /// How `PlayerSummary::efficiency` is calculated, set along with the scores
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub efficiency_formula: Option<String>,
+    /// Findings of the insight rules (see `insights`), in rule order
+    pub insights: Vec<Insight>,
+    /// Values of the configured `custom_metrics` rules that apply here, in rule order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub custom_metrics: Vec<CustomMetric>,
+    /// Damage taken from avoidable abilities, per player and per map location
+    pub avoidable_damage: AvoidableDamage,
+    /// Every avoidable hit with where the player stood, for the replay overlay
+    #[serde(skip_serializing)]
+    pub avoidable_hits: Vec<AvoidableHit>,
+    /// Hits from dangerous enemy abilities per player, and how many found no defensive up.
+    /// Empty when buff timelines are off.
+    pub defensive_coverage: Vec<DefensiveCoverage>,
+    /// Player activity just before ENCOUNTER_START (boss encounters only)
+    pub pre_pull: Option<PullWindow>,
+    /// Player activity just after ENCOUNTER_END (boss encounters only)
+    pub post_pull: Option<PullWindow>,
+    /// User-written notes and tags, attached by the server from its history store
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<EncounterNotes>,
+    /// Location in the log file, for re-parsing just this encounter (boss fights and M+ keys)
+    #[serde(skip)]
+    pub range: Option<EncounterRange>,
+    /// Boss remaining HP percentage (0.0 for kills, e.g. 35.2 for 35.2% wipe)
+    pub boss_hp_pct: Option<f64>,
+    /// Boss max HP
+    pub boss_max_hp: Option<u64>,
+    /// Per-phase enemy breakdowns (from ENCOUNTER_PHASE_CHANGE events)
+    pub phases: Vec<PhaseBreakdown>,
+    /// Time-bucketed player damage: elapsed second -> player_guid -> damage
+    pub time_bucketed_player_damage: std::collections::HashMap<u32, std::collections::HashMap<String, u64>>,
+    /// The same per ability: player_guid -> series, biggest first (served per player by the
+    /// ability timeline endpoint)
+    #[serde(skip_serializing)]
+    pub ability_damage_over_time: std::collections::HashMap<String, Vec<AbilityDamageSeries>>,
+    /// Boss HP timeline: Vec of (elapsed_secs, hp_pct) sampled at damage events
+    pub boss_hp_timeline: Vec<(f64, f64)>,
+    /// Replay timeline: per-player HP snapshots sampled every 0.5s
+    #[serde(skip_serializing)]
+    pub replay_timeline: Vec<HpSnapshot>,
+    /// Boss positions on the map: (elapsed_secs, pos_x, pos_y)
+    #[serde(skip_serializing)]
+    pub boss_positions: Vec<(f64, f64, f64)>,
+    /// Enemy casts with a cast time, for the replay's cast bars
+    #[serde(skip_serializing)]
+    pub enemy_casts: Vec<EnemyCast>,
+    /// Enemy casts that went off without a target, for the replay's markers
+    #[serde(skip_serializing)]
+    pub mechanic_markers: Vec<MechanicMarker>,
+    /// Maps the fight took place on (from MAP_CHANGE), for the replay map background
+    #[serde(skip_serializing)]
+    pub replay_maps: Vec<ReplayMap>,
+    /// Raw ability events for time filtering: (elapsed_secs, player_guid, spell_id, spell_name, spell_school, amount, target_name)
+    #[serde(skip_serializing)]
+    pub raw_ability_events: Vec<(f64, String, u64, String, u32, u64, String)>,
+}
+
+/// Replay data served via a separate endpoint (lazy-loaded)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ReplayData {
+    pub replay_timeline: Vec<HpSnapshot>,
+    pub boss_positions: Vec<(f64, f64, f64)>,
+    pub enemy_casts: Vec<EnemyCast>,
+    pub mechanic_markers: Vec<MechanicMarker>,
+    pub maps: Vec<ReplayMap>,
+    /// Suggested framing for the position map
+    pub camera: ReplayCamera,
+    pub avoidable_hits: Vec<AvoidableHit>,
+    pub raw_ability_events: Vec<(f64, String, u64, String, u32, u64, String)>,
+    /// Points a user marked in the encounter, attached by the server from its history store
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl ReplayData {
+    /// The replay of `enc`, with the `bookmarks` stored for it
+    pub fn from_encounter(enc: &EncounterSummary, bookmarks: Vec<Bookmark>) -> Self {
+        ReplayData {
+            replay_timeline: enc.replay_timeline.clone(),
+            boss_positions: enc.boss_positions.clone(),
+            enemy_casts: enc.enemy_casts.clone(),
+            mechanic_markers: enc.mechanic_markers.clone(),
+            maps: enc.replay_maps.clone(),
+            camera: crate::camera::replay_camera(enc),
+            avoidable_hits: enc.avoidable_hits.clone(),
+            raw_ability_events: enc.raw_ability_events.clone(),
+            bookmarks,
+        }
+    }
+}
+
+/// An enemy's cast, from SPELL_CAST_START until it finished, failed or was interrupted
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EnemyCast {
+    /// Seconds from the start of the fight (the replay timeline's clock)
+    pub start_secs: f64,
+    /// The end of the fight for a cast still going when it ended
+    pub end_secs: f64,
+    pub enemy_name: String,
+    pub spell_id: u64,
+    pub spell_name: String,
+    /// "success", "interrupted", "failed" or "unfinished"
+    pub outcome: String,
+}
+
+/// An enemy cast without a target, usually a ground effect. The position is where the log
+/// placed the cast, which for most ground effects is under the caster.
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct MechanicMarker {
+    /// Seconds from the start of the fight (the replay timeline's clock)
+    pub offset_secs: f64,
+    pub enemy_name: String,
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub pos_x: f64,
+    pub pos_y: f64,
+}
+
+/// Where the replay map looks, in world coordinates (pos_x/pos_y as in the log)
+#[derive(Debug, Serialize, JsonSchema, Clone, Copy, PartialEq)]
+pub struct ViewBox {
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64,
+}
+
+/// Framing for the replay map, computed from the fight's positions
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct ReplayCamera {
+    /// The area the whole fight covered, at least 30 yards a side (the map's bounds, for a
+    /// fight on one map with known bounds); None if the log had no positions
+    pub bounds: Option<ViewBox>,
+    /// "Whole fight" first, then "Boss" if the boss's position was logged, then the areas the
+    /// fight was mostly fought in, if it moved between several
+    pub presets: Vec<CameraPreset>,
+}
+
+/// A suggested view of the replay map
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CameraPreset {
+    pub name: String,
+    pub view: ViewBox,
+    /// How far the view is zoomed in relative to `ReplayCamera::bounds` (1.0 = the whole fight)
+    pub zoom: f64,
+    /// When the fight was in this area, for area presets
+    pub from_secs: Option<f64>,
+    pub to_secs: Option<f64>,
+}
+
+/// An arena match: one round for 2v2/3v3, six for Solo Shuffle, where the teams are
+/// reshuffled every round. Teams are 0 and 1 as in the log.
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ArenaMatch {
+    /// Match type from ARENA_MATCH_START ("Rated Solo Shuffle", "Rated Arena 3v3", "Skirmish", ...)
+    pub bracket: String,
+    /// Team of the player who wrote the log (in the first round, for Solo Shuffle)
+    pub log_team: u32,
+    /// From ARENA_MATCH_END; None when the log stops before the match ends
+    pub winning_team: Option<u32>,
+    pub rounds: Vec<ArenaRound>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ArenaRound {
+    /// 1-based
+    pub number: u32,
+    /// First hostile action between the teams
+    pub start_time: String,
+    pub duration_secs: f64,
+    /// The team that got the first kill; None for a draw
+    pub winning_team: Option<u32>,
+    /// Name of the first player to die
+    pub first_death: Option<String>,
+    pub players: Vec<ArenaRoundPlayer>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ArenaRoundPlayer {
+    pub guid: String,
+    pub name: String,
+    pub team: u32,
+    pub damage_done: u64,
+    pub healing_done: u64,
+    /// Crowd control auras applied to enemy players
+    pub cc_applied: u32,
+}
+
+/// A training dummy session: what each player pressed and when
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PracticeSession {
+    pub players: Vec<PracticePlayer>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PracticePlayer {
+    pub guid: String,
+    pub name: String,
+    pub dps: f64,
+    /// Spells never recast within 30 seconds, with when they were used
+    pub cooldowns: Vec<CooldownUsage>,
+    /// Every cast in order: the rotation timeline
+    pub casts: Vec<PracticeCast>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CooldownUsage {
+    pub spell_id: u64,
+    pub spell_name: String,
+    /// Seconds into the session of each use
+    pub uses: Vec<f64>,
+    /// How far the uses drifted from the base cooldown, for major damage cooldowns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift: Option<CooldownDrift>,
+}
+
+/// How close to on cooldown a major damage cooldown was used over a fight
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CooldownDrift {
+    pub base_cooldown_secs: f64,
+    /// Average of how much longer than the base cooldown the gaps between uses were (gaps
+    /// shorter than it count as 0); None with fewer than two uses
+    pub average_drift_secs: Option<f64>,
+    /// Uses the fight had room for: one at the pull and one every base cooldown after
+    pub potential_uses: u32,
+    /// Potential uses not made
+    pub lost_uses: u32,
+}
+
+/// A player's major damage cooldowns over a fight
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PlayerCooldowns {
+    pub guid: String,
+    pub name: String,
+    pub cooldowns: Vec<CooldownUsage>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PracticeCast {
+    pub offset_secs: f64,
+    pub spell_id: u64,
+    pub spell_name: String,
+}
+
+/// One ability's damage over a fight, for graphing when a player's cooldowns and spells hit
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AbilityDamageSeries {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub total: u64,
+    /// (elapsed second, damage) for every second the ability did damage
+    pub buckets: Vec<(u32, u64)>,
+}
+
+/// A player's per-ability damage over time, served via a separate endpoint
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AbilityTimeline {
+    pub player_guid: String,
+    pub player_name: String,
+    pub duration_secs: f64,
+    pub abilities: Vec<AbilityDamageSeries>,
+}
+
+/// A (sub)map from MAP_CHANGE: which map the players were on from `offset_secs` into the
+/// fight, and the world-coordinate bounds of that map (the same space as `pos_x`/`pos_y`)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ReplayMap {
+    pub offset_secs: f64,
+    pub map_id: u64,
+    pub name: String,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+/// A Mythic+ affix resolved from the bundled affix table
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct Affix {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub icon_url: String,
+}
+
+/// Individual boss encounter within a M+ run
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct BossEncounter {
+    pub name: String,
+    pub encounter_id: u64,
+    pub success: bool,
+    pub duration_secs: f64,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+/// Phase breakdown for a boss encounter
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PhaseBreakdown {
+    pub phase_id: u32,
+    pub start_time_secs: f64,
+    pub end_time_secs: f64,
+    pub enemy_breakdowns: Vec<EnemyBreakdown>,
+}
+
+/// A segment within a M+ key (trash pack or boss fight)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct KeySegment {
+    pub segment_type: String,  // "trash" or "boss"
+    pub name: String,
+    pub index: usize,
+    pub duration_secs: f64,
+    pub start_time: String,
+    pub end_time: String,
+    pub players: Vec<PlayerSummary>,
+    pub deaths: Vec<DeathEvent>,
+    pub near_deaths: Vec<NearDeath>,
+    pub buff_uptimes: std::collections::HashMap<String, Vec<BuffUptime>>,
+    pub key_buff_uptimes: std::collections::HashMap<String, Vec<BuffUptime>>,
+    pub enemy_breakdowns: Vec<EnemyBreakdown>,
+    /// Damage each enemy dealt to each player (who tanked / took what)
+    pub enemy_damage_taken: Vec<EnemyDamageTaken>,
+    /// Player damage to configured priority targets (adds that must die fast)
+    pub priority_targets: Vec<PriorityTarget>,
+    /// Per-phase enemy breakdowns for boss segments (from ENCOUNTER_PHASE_CHANGE events)
+    pub phases: Vec<PhaseBreakdown>,
+    /// Boss HP % over time for boss segments: (seconds since pull, hp_pct)
+    pub boss_hp_timeline: Vec<(f64, f64)>,
+    /// Individual pulls within a trash segment (empty for boss segments)
+    pub pulls: Vec<TrashPull>,
+    /// Player activity just before the boss was pulled (boss segments only)
+    pub pre_pull: Option<PullWindow>,
+    /// Player activity just after the boss ended (boss segments only)
+    pub post_pull: Option<PullWindow>,
+}
+
+/// An individual pull within a trash segment
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct TrashPull {
+    pub pull_index: usize,
+    pub duration_secs: f64,
+    pub start_time_offset: f64,  // seconds from segment start
+    /// Total enemy forces count granted by kills in this pull
+    pub enemy_forces: u32,
+    pub enemies: Vec<PullEnemy>,
+    pub players: Vec<PlayerSummary>,
+    pub deaths: Vec<DeathEvent>,
+    /// Who opened the pull, who the enemies went for first, taunts and threat redirects
+    pub opener: PullOpener,
+    /// Who each enemy was attacking over the pull, in order of its first hit
+    pub tanking: Vec<EnemyTanking>,
+}
+
+/// Who one enemy was attacking, from its hits on players in 3 second windows; the player it
+/// swung at (or hit) most in a window is its target. Offsets are seconds from the pull start.
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EnemyTanking {
+    pub enemy_name: String,
+    pub npc_id: Option<u64>,
+    /// Stretches with the same target, in order
+    pub spans: Vec<TankedSpan>,
+    /// Times the enemy changed target
+    pub target_swaps: u32,
+    /// Seconds spent attacking players who aren't tanks: a loose mob
+    pub loose_secs: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct TankedSpan {
+    pub start_offset_secs: f64,
+    pub end_offset_secs: f64,
+    pub player_name: String,
+    pub on_tank: bool,
+    /// Hits on the player during the span
+    pub hits: u32,
+}
+
+/// How a pull was opened, for tanks reviewing their threat habits. Offsets are seconds from
+/// the pull start (negative before it).
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct PullOpener {
+    /// Player who damaged an enemy first
+    pub opened_by: Option<String>,
+    /// The first player each enemy meleed, in order
+    pub first_targets: Vec<FirstTarget>,
+    pub taunts: Vec<OpenerCast>,
+    /// Misdirection and Tricks of the Trade
+    pub redirects: Vec<OpenerCast>,
+    /// The non-tank with the most damage in the pull's first seconds, the likeliest to pull
+    /// threat (threat itself isn't logged)
+    pub top_early_damage: Option<EarlyDamage>,
+    /// Some enemy's first melee swing went to a non-tank
+    pub aggro_lost: bool,
+}
+
+/// Damage in the first seconds of a pull (`parser::EARLY_DAMAGE_SECS`)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EarlyDamage {
+    pub player_name: String,
+    pub damage: u64,
+    /// The most any tank did in the same seconds
+    pub tank_damage: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct FirstTarget {
+    pub offset_secs: f64,
+    pub enemy_name: String,
+    pub player_name: String,
+    pub on_tank: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct OpenerCast {
+    pub offset_secs: f64,
+    pub caster_name: String,
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub target_name: String,
+}
+
+/// Pull openers of a Mythic+ key, for `/api/logs/{filename}/encounter/{index}/threat`
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ThreatReview {
+    pub encounter_name: String,
+    pub pulls: Vec<ThreatReviewPull>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ThreatReviewPull {
+    pub segment_index: usize,
+    pub segment_name: String,
+    pub pull_index: usize,
+    pub start_time_offset: f64,
+    pub duration_secs: f64,
+    pub opener: PullOpener,
+    pub tanking: Vec<EnemyTanking>,
+}
+
+/// Reference to a high-count pull within a M+ key
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CountPull {
+    pub segment_index: usize,
+    pub segment_name: String,
+    pub pull_index: usize,
+    pub enemy_forces: u32,
+    pub duration_secs: f64,
+    /// Enemy forces gained per minute of pull time
+    pub forces_per_min: f64,
+}
+
+/// An enemy within a specific pull
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PullEnemy {
+    pub name: String,
+    pub damage_taken: u64,
+    pub mob_type: String,
+    /// Enemy forces count granted by kills of this enemy within the pull
+    pub enemy_forces: u32,
+}
+
+/// Per-player stats in an encounter
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PlayerSummary {
+    pub guid: String,
+    pub name: String,
+    pub class_name: String,
+    pub spec_name: String,
+    pub role: String,  // "tank", "healer" or "dps" (inferred when COMBATANT_INFO is missing)
+    /// Class/spec were inferred from abilities used rather than read from COMBATANT_INFO
+    pub spec_inferred: bool,
+    /// Talent entry IDs picked, from COMBATANT_INFO (ranks left out), sorted; empty without it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub talents: Vec<u32>,
+    pub damage_done: u64,
+    pub healing_done: u64,
+    pub damage_taken: u64,
+    pub deaths: u32,
+    /// Lethal hits soaked by absorbs ("saved by shield")
+    pub near_deaths: u32,
+    /// Near-deaths this player's absorbs prevented
+    pub saves: u32,
+    /// Estimated deaths this player's heals prevented: heals on another player below 15% HP
+    /// who then took at least the HP they had left within 2 seconds and lived
+    pub deaths_prevented: u32,
+    /// Enemy casts interrupted, by the player or their pets
+    pub interrupts: u32,
+    /// Auras dispelled, purged or spellstolen
+    pub dispels: u32,
+    pub dps: f64,
+    pub hps: f64,
+    /// Position by damage done within this player list (1 = top)
+    pub damage_rank: u32,
+    /// Percent of the group's damage done
+    pub damage_share: f64,
+    /// Position by healing done within this player list (1 = top)
+    pub healing_rank: u32,
+    /// Percent of the group's healing done
+    pub healing_share: f64,
+    pub abilities: Vec<AbilityBreakdown>,
+    pub heal_abilities: Vec<AbilityBreakdown>,
+    pub damage_taken_abilities: Vec<AbilityBreakdown>,
+    /// Worst damage-taken windows (largest first)
+    pub damage_spikes: Vec<DamageSpike>,
+    /// Cancelled casts and clipped channels
+    pub cast_stats: CastStats,
+    /// Damage split by how many enemies were engaged when it landed
+    pub target_count_split: TargetCountSplit,
+    /// Damage excluding pad targets (enemies that despawned alive or never fought back)
+    pub filtered_damage: u64,
+    pub filtered_dps: f64,
+    /// Healing done split by who received it
+    pub healing_split: HealingSplit,
+    /// Stuns, fears, silences and roots this player was under
+    pub crowd_control: CrowdControlReceived,
+    /// How this compares to the player's own recent pulls of the same boss and difficulty (or
+    /// key of the same dungeon and level), attached by the server from its history store
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub performance_delta: Option<PerformanceDelta>,
+    /// Median DPS of the spec at this key level or raid difficulty, attached by the server from
+    /// its benchmark table (see `benchmarks`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_dps: Option<f64>,
+    /// One 0-100 number for the whole fight (see `efficiency`); not on segment and pull rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub efficiency: Option<FightEfficiency>,
+    /// Damage done and taken by spell school (see `schools`); not on segment and pull rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schools: Option<SchoolProfile>,
+    /// Damage done split into direct, periodic, pet and proc (see `damage_types`); not on
+    /// pull rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub damage_types: Option<DamageTypeSplit>,
+}
+
+/// A player's damage done by where it came from
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct DamageTypeSplit {
+    pub direct: u64,
+    pub periodic: u64,
+    pub pet: u64,
+    pub procs: u64,
+    /// Percent of the player's damage done
+    pub direct_pct: f64,
+    pub periodic_pct: f64,
+    pub pet_pct: f64,
+    pub procs_pct: f64,
+    /// Each ability's part of each type, largest first; a spell that hits and ticks is listed
+    /// as direct and as periodic
+    pub abilities: Vec<DamageTypeAbility>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct DamageTypeAbility {
+    /// 0 with the pet's name for a pet
+    pub spell_id: u64,
+    pub spell_name: String,
+    /// "direct", "periodic", "pet" or "proc"
+    pub damage_type: &'static str,
+    pub amount: u64,
+}
+
+/// A player's fight efficiency with the points of each part, which add up to `score`
+/// (the formula is in `EncounterSummary::efficiency_formula`)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct FightEfficiency {
+    pub score: f64,
+    /// Out of 40: damage share (healing share for healers) against an even split
+    pub output_points: f64,
+    /// Out of 25: the part of the damage taken that wasn't avoidable
+    pub avoidable_points: f64,
+    /// Out of 20: 10 off per death
+    pub death_points: f64,
+    /// Out of 15: interrupts and dispels against the group's most
+    pub utility_points: f64,
+    /// The share `output_points` is based on, and the even split it is measured against
+    pub output_share: f64,
+    pub even_share: f64,
+    pub avoidable_damage: u64,
+}
+
+/// A player's damage done and damage taken, by spell school
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct SchoolProfile {
+    pub damage_done: SchoolSplit,
+    pub damage_taken: SchoolSplit,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct SchoolSplit {
+    /// Plain physical damage
+    pub physical: u64,
+    /// Every other school, mixed schools included
+    pub magic: u64,
+    /// Per school, largest first
+    pub schools: Vec<SchoolAmount>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct SchoolAmount {
+    /// School bit mask as in the log (1 physical, 2 holy, 4 fire, ...)
+    pub school: u32,
+    /// "Fire", "Shadowflame", ...
+    pub name: String,
+    pub amount: u64,
+    /// Percent of the split's total
+    pub pct: f64,
+}
+
+/// A player's numbers in one encounter against their average over earlier ones like it
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PerformanceDelta {
+    /// Earlier encounters the average is taken over
+    pub samples: u32,
+    pub average_dps: f64,
+    /// Percent above (or below, negative) `average_dps`
+    pub dps_delta_pct: f64,
+    pub average_hps: f64,
+    pub hps_delta_pct: f64,
+}
+
+/// Crowd control a player received over a fight
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct CrowdControlReceived {
+    pub events: Vec<CrowdControlEvent>,
+    /// Time spent under any crowd control, overlapping effects counted once
+    pub total_secs: f64,
+    /// `total_secs` as a percent of the fight
+    pub pct: f64,
+}
+
+impl CrowdControlReceived {
+    /// Add up time spent crowd controlled; events are relative to their own fight, so they
+    /// aren't merged
+    pub fn merge(&mut self, other: &CrowdControlReceived) {
+        self.total_secs += other.total_secs;
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CrowdControlEvent {
+    pub spell_id: u64,
+    pub spell_name: String,
+    /// "stun", "incapacitate", "disorient", "silence", "root", or "control" for spells
+    /// from `crowd_control_spell_ids`
+    pub kind: String,
+    pub source_name: String,
+    /// Milliseconds from the pull
+    pub time_ms: i64,
+    pub duration_secs: f64,
+}
+
+/// Where a player's healing went; `external + self_healing` is their total healing
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct HealingSplit {
+    /// Heals and absorbs on other players
+    pub external: u64,
+    /// Heals and absorbs on themselves, including leech
+    pub self_healing: u64,
+    /// Leech share of `self_healing`
+    pub leech: u64,
+    /// Damage absorbed by the player's shields, on anyone
+    pub absorbs: u64,
+}
+
+impl HealingSplit {
+    pub fn merge(&mut self, other: &HealingSplit) {
+        self.external += other.external;
+        self.self_healing += other.self_healing;
+        self.leech += other.leech;
+        self.absorbs += other.absorbs;
+    }
+}
+
+/// A player's damage by the number of enemies in combat with the group at the time.
+/// Funnel specs keep a high single-target share on packs; padders don't.
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct TargetCountSplit {
+    /// Damage done while 1 enemy was engaged
+    pub single_target: u64,
+    /// Damage done while 2-4 enemies were engaged
+    pub cleave: u64,
+    /// Damage done while 5+ enemies were engaged
+    pub aoe: u64,
+    pub single_target_pct: f64,
+    pub cleave_pct: f64,
+    pub aoe_pct: f64,
+}
+
+impl TargetCountSplit {
+    /// Add damage dealt while `engaged` enemies were in combat
+    pub fn add(&mut self, engaged: usize, amount: u64) {
+        match engaged {
+            0 | 1 => self.single_target += amount,
+            2..=4 => self.cleave += amount,
+            _ => self.aoe += amount,
+        }
+    }
+
+    pub fn merge(&mut self, other: &TargetCountSplit) {
+        self.single_target += other.single_target;
+        self.cleave += other.cleave;
+        self.aoe += other.aoe;
+        self.update_pcts();
+    }
+
+    pub fn update_pcts(&mut self) {
+        let total = (self.single_target + self.cleave + self.aoe) as f64;
+        let pct = |amount: u64| if total > 0.0 { amount as f64 / total * 100.0 } else { 0.0 };
+        self.single_target_pct = pct(self.single_target);
+        self.cleave_pct = pct(self.cleave);
+        self.aoe_pct = pct(self.aoe);
+    }
+}
+
+/// Cast-time and channel discipline for a player
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct CastStats {
+    /// Cast-time spells started (SPELL_CAST_START)
+    pub casts_started: u32,
+    pub casts_completed: u32,
+    /// Started casts that never finished: cancelled, interrupted or replaced by another cast
+    pub casts_cancelled: u32,
+    /// Percent of started casts that were cancelled
+    pub cancel_pct: f64,
+    /// Known channeled spells cast
+    pub channels: u32,
+    /// Channels cut short by another cast before half their base duration
+    pub channels_clipped: u32,
+}
+
+impl CastStats {
+    pub fn merge(&mut self, other: &CastStats) {
+        self.casts_started += other.casts_started;
+        self.casts_completed += other.casts_completed;
+        self.casts_cancelled += other.casts_cancelled;
+        self.channels += other.channels;
+        self.channels_clipped += other.channels_clipped;
+        self.update_cancel_pct();
+    }
+
+    pub fn update_cancel_pct(&mut self) {
+        self.cancel_pct = if self.casts_started > 0 {
+            self.casts_cancelled as f64 / self.casts_started as f64 * 100.0
+        } else {
+            0.0
+        };
+    }
+}
+
+/// A short window in which a player took a burst of damage
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct DamageSpike {
+    /// Seconds from the start of the fight/segment
+    pub start_offset_secs: f64,
+    pub window_secs: f64,
+    pub total_damage: u64,
+    /// The player died during (or right after) the window
+    pub ended_in_death: bool,
+    pub abilities: Vec<SpikeAbility>,
+}
+
+/// An ability's contribution to a damage spike
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct SpikeAbility {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub source_name: String,
+    pub amount: u64,
+}
+
+/// Damage/healing breakdown per ability
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AbilityBreakdown {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub spell_school: u32,
+    pub total_amount: u64,
+    pub hit_count: u32,
+    pub wowhead_url: String,
+    pub targets: Vec<TargetBreakdown>,
+    /// Sub-abilities for pet groups (individual pet spells grouped under pet name)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sub_abilities: Vec<AbilityBreakdown>,
+}
+
+/// Damage/healing per target for an ability
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct TargetBreakdown {
+    pub target_name: String,
+    pub amount: u64,
+}
+
+/// Per-enemy damage summary
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EnemyBreakdown {
+    pub target_name: String,
+    pub total_damage: u64,
+    pub kill_count: u32,
+    pub mob_type: String,
+    /// NPC ID decoded from the creature GUID
+    pub npc_id: Option<u64>,
+    /// Enemy forces count granted by all kills of this enemy
+    pub enemy_forces: u32,
+    pub players: Vec<EnemyPlayerDamage>,
+}
+
+/// When each enemy of a fight joined and left it, to spot adds left alive too long and spawns
+/// piling up
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AddTimeline {
+    /// In order of appearance
+    pub enemies: Vec<EnemyLifetime>,
+    /// Most adds (enemies other than the boss) alive at once, and when it was reached
+    pub peak_alive: u32,
+    pub peak_offset_secs: f64,
+}
+
+/// One enemy (one GUID) over a fight
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EnemyLifetime {
+    pub name: String,
+    pub npc_id: Option<u64>,
+    /// Seconds into the fight it first traded damage with the group
+    pub first_seen_secs: f64,
+    /// Seconds into the fight it died, or was last seen if it didn't
+    pub last_seen_secs: f64,
+    /// Killed, rather than despawned or still alive at the end
+    pub died: bool,
+    pub is_boss: bool,
+}
+
+/// Damage to a priority target NPC, aggregated over all of its spawns
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PriorityTarget {
+    pub npc_id: u64,
+    pub name: String,
+    pub spawns: u32,
+    pub kills: u32,
+    /// Average time from first hit to death (or last hit if it never died)
+    pub avg_lifetime_secs: f64,
+    pub total_damage: u64,
+    pub players: Vec<PriorityTargetPlayer>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PriorityTargetPlayer {
+    pub guid: String,
+    pub name: String,
+    pub damage: u64,
+    /// Damage over the target's combined lifetime
+    pub dps: f64,
+}
+
+/// A player's damage split between boss and trash segments of a key
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct BossTrashSplit {
+    pub guid: String,
+    pub name: String,
+    pub boss_damage: u64,
+    pub trash_damage: u64,
+    /// Damage over the total duration of boss segments
+    pub boss_dps: f64,
+    /// Damage over the total duration of trash segments
+    pub trash_dps: f64,
+}
+
+/// Enemies of one name excluded from filtered damage, and why
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PadTarget {
+    pub name: String,
+    /// "despawned" (left alive before the fight ended) or "harmless" (never damaged the group)
+    pub reason: String,
+    pub spawns: u32,
+    /// Player damage they absorbed
+    pub total_damage: u64,
+}
+
+/// A short window in which much of the group took damage at once
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct RaidDamageEvent {
+    /// Seconds from the start of the fight
+    pub start_offset_secs: f64,
+    pub window_secs: f64,
+    pub total_damage: u64,
+    pub players_hit: u32,
+    /// Top damaging abilities in the window
+    pub abilities: Vec<SpikeAbility>,
+    /// Raid healing cooldowns whose effect overlapped the window
+    pub cooldowns: Vec<HealerCdUse>,
+    /// False when no healer cooldown was active: a gap for the healing plan
+    pub covered: bool,
+}
+
+/// An enemy's melee moving from one tank to another
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct TankSwap {
+    /// Seconds from the start of the fight
+    pub offset_secs: f64,
+    pub enemy_name: String,
+    pub from_tank: String,
+    pub to_tank: String,
+    /// Taunt the new tank cast on the enemy just before the swap
+    pub taunt_spell: Option<String>,
+    /// The old tank's highest-stacked debuff at the swap
+    pub debuff_name: Option<String>,
+    pub debuff_stacks: u32,
+    /// `debuff_stacks` was above the configured taunt threshold
+    pub late: bool,
+}
+
+/// A player hit by an avoidable ability
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AvoidableHit {
+    /// Seconds from the start of the fight (the replay timeline's clock)
+    pub offset_secs: f64,
+    pub player_name: String,
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub amount: u64,
+    /// Where the player stood, when the log has advanced info
+    pub pos_x: Option<f64>,
+    pub pos_y: Option<f64>,
+}
+
+/// Effective healing (absorbs included) by each healer on each player, for checking
+/// healing assignments
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct HealingReport {
+    /// Rows, most healing done first
+    pub healers: Vec<HealingReportPlayer>,
+    /// Columns: tanks, then healers, then damage dealers, each most healing received first
+    pub targets: Vec<HealingReportPlayer>,
+    /// `amounts[healer][target]`
+    pub amounts: Vec<Vec<u64>>,
+}
+
+impl HealingReport {
+    /// Drop the players' rows and columns
+    pub fn remove_players(&mut self, guids: &[String]) {
+        let keep_targets: Vec<bool> = self.targets.iter().map(|t| !guids.contains(&t.guid)).collect();
+        let rows = std::mem::take(&mut self.healers).into_iter().zip(std::mem::take(&mut self.amounts));
+        for (healer, amounts) in rows {
+            if guids.contains(&healer.guid) {
+                continue;
+            }
+            self.amounts.push(amounts.into_iter().zip(&keep_targets).filter(|(_, keep)| **keep).map(|(a, _)| a).collect());
+            self.healers.push(healer);
+        }
+        self.targets.retain(|t| !guids.contains(&t.guid));
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct HealingReportPlayer {
+    pub guid: String,
+    pub name: String,
+    pub role: String,
+    pub total: u64,
+}
+
+/// A player's hits from one dangerous enemy ability (configured, or big enough) and how
+/// many of them landed with no defensive aura up
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct DefensiveCoverage {
+    pub player_guid: String,
+    pub player_name: String,
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub hits: u32,
+    /// Hits taken without a personal, external or raid defensive
+    pub undefended: u32,
+    pub undefended_damage: u64,
+}
+
+/// Avoidable damage in a fight
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct AvoidableDamage {
+    pub total_damage: u64,
+    pub hits: u32,
+    /// Most avoidable damage taken first
+    pub players: Vec<AvoidablePlayer>,
+    /// Hits binned into square cells of the map, most hits first
+    pub heat: Vec<HitHeatCell>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AvoidablePlayer {
+    pub player_name: String,
+    pub hits: u32,
+    pub damage: u64,
+    /// The ability that hit them for the most damage
+    pub top_spell: String,
+}
+
+/// One cell of the avoidable-hit heat map
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct HitHeatCell {
+    /// Centre of the cell, in the same world coordinates as replay positions
+    pub pos_x: f64,
+    pub pos_y: f64,
+    pub hits: u32,
+    pub damage: u64,
+}
+
+/// A raid healing cooldown cast
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct HealerCdUse {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub caster_name: String,
+    /// Seconds from the start of the fight
+    pub offset_secs: f64,
+}
+
+/// Two runs of the same dungeon side by side ("a" is the baseline, deltas are b - a)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct RouteComparison {
+    pub dungeon_name: String,
+    pub a: RunInfo,
+    pub b: RunInfo,
+    /// Boss-to-boss sections in route order
+    pub sections: Vec<SectionDiff>,
+    /// Time into each run at which it reached the same enemy forces count
+    pub count_checkpoints: Vec<CountCheckpoint>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct RunInfo {
+    pub stable_id: String,
+    pub start_time: String,
+    pub key_level: Option<u32>,
+    pub duration_secs: f64,
+    pub timed: Option<bool>,
+    pub deaths: u32,
+}
+
+/// The trash leading up to a boss plus the boss itself (or the trash after the last boss)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct SectionDiff {
+    pub name: String,
+    /// None when that run has no such section (e.g. a boss it didn't kill)
+    pub a: Option<SectionStats>,
+    pub b: Option<SectionStats>,
+    pub time_delta_secs: Option<f64>,
+    pub deaths_delta: Option<i32>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct SectionStats {
+    /// Seconds into the key when the section ended
+    pub end_secs: f64,
+    pub duration_secs: f64,
+    pub trash_secs: f64,
+    pub boss_secs: f64,
+    pub deaths: u32,
+    pub pulls: u32,
+    pub enemy_forces: u32,
+    /// Mean enemy forces per pull
+    pub avg_pull_forces: f64,
+    pub largest_pull_forces: u32,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CountCheckpoint {
+    pub enemy_forces: u32,
+    pub a_secs: Option<f64>,
+    pub b_secs: Option<f64>,
+    pub delta_secs: Option<f64>,
+}
+
+/// A finding of an insight rule, e.g. "3 players hit by Web Bomb"
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct Insight {
+    pub rule_id: String,
+    /// "info", "warning" or "critical"
+    pub severity: String,
+    pub message: String,
+    /// Players the insight is about
+    pub players: Vec<String>,
+}
+
+/// A user-defined metric (`ParseOptions::custom_metrics`) over an encounter
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CustomMetric {
+    pub name: String,
+    /// "count" or "amount"
+    pub measure: String,
+    /// Sum of the players' values
+    pub total: u64,
+    /// Highest first; players without any matching events are left out
+    pub players: Vec<CustomMetricPlayer>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct CustomMetricPlayer {
+    pub guid: String,
+    pub name: String,
+    pub value: u64,
+}
+
+/// Post-mortem of a key that went over time (or was abandoned)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct DepletionReport {
+    /// Past par on the key timer; None when the par time is unknown
+    pub over_time_secs: Option<f64>,
+    pub deaths: u32,
+    /// Time the deaths added to the key timer
+    pub death_penalty_secs: f64,
+    /// Wipes with the longest run-back first
+    pub biggest_wipes: Vec<WipeEvent>,
+    /// Enemy forces counted from the enemies with a known value
+    pub enemy_forces: u32,
+    /// Boss-to-boss sections, the most time lost against the best run first once compared
+    pub sections: Vec<DepletionSection>,
+    /// Highest timed run of the dungeon in the history (the fastest of that level), filled
+    /// in by the server
+    pub best_run: Option<BestRun>,
+    /// Enemy forces the best run counted beyond this one's
+    pub enemy_forces_shortfall: Option<u32>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct DepletionSection {
+    pub name: String,
+    pub duration_secs: f64,
+    pub deaths: u32,
+    /// The same section in the best run
+    pub best_secs: Option<f64>,
+    /// Seconds slower than the best run (negative when faster)
+    pub delta_secs: Option<f64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct BestRun {
+    pub stable_id: String,
+    pub start_time: String,
+    pub key_level: u32,
+    pub duration_secs: f64,
+    pub enemy_forces: u32,
+}
+
+impl DepletionReport {
+    /// Compare against `best`, whose boss-to-boss sections took `best_sections` (name, seconds)
+    pub fn set_best_run(&mut self, best: BestRun, best_sections: &[(String, f64)]) {
+        for section in &mut self.sections {
+            section.best_secs = best_sections.iter().find(|(name, _)| *name == section.name).map(|(_, secs)| *secs);
+            section.delta_secs = section.best_secs.map(|best| section.duration_secs - best);
+        }
+        self.sections.sort_by(|a, b| {
+            let lost = |s: &DepletionSection| s.delta_secs.unwrap_or(f64::MIN);
+            lost(b).partial_cmp(&lost(a)).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        self.enemy_forces_shortfall = Some(best.enemy_forces.saturating_sub(self.enemy_forces));
+        self.best_run = Some(best);
+    }
+}
+
+/// Boss pulls in one instance and difficulty, for the grouped encounter list
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct InstanceGroup {
+    pub instance_name: String,
+    pub instance_icon: Option<String>,
+    pub difficulty_id: u32,
+    pub difficulty_name: String,
+    /// In the order each boss was first pulled
+    pub bosses: Vec<BossGroup>,
+}
+
+/// Every pull of one boss, with totals across them
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct BossGroup {
+    pub encounter_id: u64,
+    pub name: String,
+    pub pull_count: u32,
+    pub kills: u32,
+    /// 1-based pull number of the first kill
+    pub first_kill_pull: Option<u32>,
+    /// Duration of the fastest kill
+    pub kill_time_secs: Option<f64>,
+    /// Stable ID of the best pull: the fastest kill, or the wipe with the boss lowest
+    pub best_pull: Option<String>,
+    /// Boss HP % left on the best pull (0 for a kill)
+    pub best_hp_pct: Option<f64>,
+    /// Time spent in the fight across all pulls
+    pub total_secs: f64,
+    /// Whether the raid got closer pull after pull, up to the first kill (3 pulls or more)
+    pub progression: Option<ProgressionTrend>,
+    /// When each phase was reached and how long it took across the pulls, against the first
+    /// kill (bosses with phases only)
+    pub phase_timings: Vec<PhaseTiming>,
+    pub pulls: Vec<GroupedPull>,
+}
+
+/// One phase of a boss across its pulls. Durations only count pulls that got past the
+/// phase (or killed the boss in it); a wipe ends the phase it happened in early.
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PhaseTiming {
+    pub phase_id: u32,
+    /// Pulls that got to the phase
+    pub pulls_reached: u32,
+    /// Wipes that ended in the phase
+    pub wipes: u32,
+    /// Average seconds into the pull the phase started
+    pub average_start_secs: f64,
+    pub average_duration_secs: Option<f64>,
+    /// When the phase started and how long it took on the first kill
+    pub kill_start_secs: Option<f64>,
+    pub kill_duration_secs: Option<f64>,
+    /// Average start minus the kill's: positive when pulls got there later than the kill did
+    pub start_vs_kill_secs: Option<f64>,
+    /// Average duration minus the kill's: positive when the phase took longer than on the kill
+    pub duration_vs_kill_secs: Option<f64>,
+}
+
+/// Least-squares slopes per pull over the pulls of a boss up to its first kill
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ProgressionTrend {
+    pub pulls: u32,
+    /// Change in boss HP % left per pull: negative is getting closer
+    pub boss_hp_pct_slope: f64,
+    /// Change in the phase reached per pull (0 when the boss has no phases)
+    pub phase_slope: f64,
+    /// Change in seconds until the first death per pull: positive is living longer
+    pub first_death_secs_slope: f64,
+    /// "improving", "declining" or "steady"
+    pub direction: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct GroupedPull {
+    pub index: usize,
+    pub stable_id: String,
+    /// 1-based, counting pulls of this boss only
+    pub pull_number: u32,
+    pub start_time: String,
+    pub duration_secs: f64,
+    pub success: bool,
+    /// Boss HP % left when the pull ended (0 for a kill)
+    pub boss_hp_pct: Option<f64>,
+    pub deaths: u32,
+    /// Highest phase the pull got to, for bosses with phases
+    pub phase_reached: Option<u32>,
+    /// Seconds into the pull of the first death
+    pub first_death_secs: Option<f64>,
+    /// The phases the pull went through, in order
+    pub phases: Vec<PullPhase>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PullPhase {
+    pub phase_id: u32,
+    /// Seconds into the pull
+    pub start_secs: f64,
+    pub duration_secs: f64,
+}
+
+/// Avoidable and unavoidable damage each player took across a log's boss pulls and keys,
+/// in the order they happened
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct AvoidableTrend {
+    pub encounters: Vec<TrendEncounter>,
+    /// Most avoidable damage taken first
+    pub players: Vec<PlayerAvoidableTrend>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct TrendEncounter {
+    pub index: usize,
+    pub stable_id: String,
+    pub name: String,
+    pub start_time: String,
+    pub success: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PlayerAvoidableTrend {
+    pub player_name: String,
+    pub damage_taken: u64,
+    pub avoidable_damage: u64,
+    pub unavoidable_damage: u64,
+    /// Percent of their damage taken that was avoidable, over the whole log
+    pub avoidable_pct: f64,
+    /// Change in `avoidable_pct` per encounter, from a least-squares fit over the encounters
+    /// they took damage in: negative is improving, positive is getting sloppier
+    pub avoidable_pct_slope: f64,
+    /// One point per encounter they took damage in
+    pub points: Vec<AvoidableTrendPoint>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AvoidableTrendPoint {
+    /// Position in `AvoidableTrend::encounters`
+    pub encounter: usize,
+    pub avoidable_damage: u64,
+    pub unavoidable_damage: u64,
+    pub avoidable_pct: f64,
+}
+
+/// Hits from avoidable mechanics per player across every pull of one boss (and difficulty)
+/// in a log
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct BossMechanicHits {
+    pub encounter_id: u64,
+    pub name: String,
+    pub difficulty_name: String,
+    pub pulls: u32,
+    pub kills: u32,
+    /// Every avoidable ability that hit someone, most hits first
+    pub spells: Vec<MechanicSpellHits>,
+    /// In the requested order, most hits first by default
+    pub players: Vec<PlayerMechanicHits>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct MechanicSpellHits {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub hits: u32,
+    pub damage: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PlayerMechanicHits {
+    pub player_name: String,
+    pub hits: u32,
+    pub damage: u64,
+    /// Pulls of the boss they were in, and how many of those they got hit in
+    pub pulls: u32,
+    pub pulls_hit: u32,
+    pub hits_per_pull: f64,
+    /// Their hits per ability, most hits first
+    pub spells: Vec<MechanicSpellHits>,
+}
+
+/// How an in-progress Mythic+ key is doing against par, for live mode
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct KeyPace {
+    pub dungeon_name: String,
+    pub key_level: u32,
+    pub elapsed_secs: f64,
+    pub par_time_secs: Option<f64>,
+    /// Par minus elapsed; negative once over time
+    pub time_remaining_secs: Option<f64>,
+    pub bosses_killed: u32,
+    pub boss_count: Option<u32>,
+    /// Seconds into the key of each boss kill
+    pub boss_kill_secs: Vec<f64>,
+    /// Bosses a run on par would have killed by now (bosses spread evenly up to par time)
+    pub expected_bosses: Option<f64>,
+    /// Finish time if the rest of the key goes at the pace of the bosses so far
+    pub projected_finish_secs: Option<f64>,
+    pub on_pace: Option<bool>,
+}
+
+/// One group member's view of the fight in progress, for live mode
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct LivePlayer {
+    pub guid: String,
+    pub name: String,
+    pub class_name: String,
+    pub spec_name: String,
+    /// "tank", "healer" or "dps"; empty if the spec isn't known
+    pub role: String,
+    /// Their latest death in the fight, with its recap
+    pub last_death: Option<DeathEvent>,
+    /// Their personal defensives and the cooldowns they have pressed, what their role leans on
+    /// first
+    pub cooldowns: Vec<LiveCooldown>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct LiveCooldown {
+    pub spell_id: u64,
+    pub spell_name: String,
+    /// "defensive", "external", "raid", "battle_res" or "offensive"
+    pub category: String,
+    /// Base cooldown, before haste, talents and cooldown reduction
+    pub cooldown_secs: f64,
+    /// Seconds until it is ready again by its base cooldown; 0 when ready
+    pub ready_in_secs: f64,
+}
+
+/// Where a spell showed up in a log, grouped by encounter
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct SpellSearchResult {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub wowhead_url: String,
+    pub encounters: Vec<SpellSearchEncounter>,
+    /// Occurrences between encounters (not listed)
+    pub outside_encounters: u32,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct SpellSearchEncounter {
+    pub index: usize,
+    pub stable_id: String,
+    pub name: String,
+    pub encounter_type: String,
+    pub start_time: String,
+    pub casts: u32,
+    pub damage: u32,
+    pub heals: u32,
+    pub auras: u32,
+    /// In log order, capped (the counts above are not)
+    pub hits: Vec<SpellHit>,
+}
+
+/// One log event involving the searched spell
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct SpellHit {
+    /// Milliseconds from the start of the encounter (filled in when grouped)
+    pub time_ms: i64,
+    /// "cast", "damage", "heal" or "aura"
+    pub kind: String,
+    pub source_name: String,
+    pub dest_name: String,
+    #[serde(skip)]
+    pub timestamp_secs: f64,
+}
+
+/// Damage and healing just outside an encounter's start/end events. Reported on its own
+/// and not included in the encounter's totals.
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PullWindow {
+    pub window_secs: f64,
+    pub damage: u64,
+    pub healing: u64,
+    pub players: Vec<PullWindowPlayer>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PullWindowPlayer {
+    pub guid: String,
+    pub name: String,
+    pub damage: u64,
+    pub healing: u64,
+}
+
+/// Where an encounter lives in its log file, plus the state from earlier in the log needed
+/// to re-parse just that slice with `parse_combat_log_range`
+#[derive(Debug, Clone, Default)]
+pub struct EncounterRange {
+    /// Byte offset of the encounter's first line (including its pre-pull window)
+    pub start: u64,
+    /// Byte offset just past its last line (including its post-pull window)
+    pub end: u64,
+    /// Player names, specs, talents and pet owners known when the encounter began
+    pub player_names: std::collections::HashMap<String, String>,
+    pub player_specs: std::collections::HashMap<String, u32>,
+    pub player_talents: std::collections::HashMap<String, Vec<u32>>,
+    pub pet_owners: std::collections::HashMap<String, String>,
+    /// Zone the encounter was in
+    pub zone: Option<ZoneChange>,
+}
+
+/// Notes a user attached to an encounter when reviewing it
+#[derive(Debug, Serialize, JsonSchema, Deserialize, Clone, Default)]
+pub struct EncounterNotes {
+    pub text: String,
+    /// Short labels, e.g. "new strat P2"
+    pub tags: Vec<String>,
+    /// RFC 3339 time of the last edit
+    pub updated_at: String,
+}
+
+/// A named point in an encounter to jump the replay to, e.g. "P2 start issue"
+#[derive(Debug, Serialize, JsonSchema, Deserialize, Clone)]
+pub struct Bookmark {
+    /// Unique within the encounter
+    pub id: u32,
+    pub name: String,
+    /// Seconds from the start of the fight (the replay timeline's clock)
+    pub offset_secs: f64,
+    /// RFC 3339 time it was created
+    pub created_at: String,
+}
+
+/// Damage dealt by one enemy, split by the players it hit
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EnemyDamageTaken {
+    pub enemy_name: String,
+    pub total_damage: u64,
+    pub players: Vec<EnemyPlayerDamage>,
+}
+
+/// Player damage to a specific enemy
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct EnemyPlayerDamage {
+    pub player_name: String,
+    pub class_name: String,
+    pub damage: u64,
+}
+
+/// Damage the whole group took from one enemy ability (melee swings aren't counted)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct Mechanic {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub wowhead_url: String,
+    /// Enemies that hit players with it
+    pub enemies: Vec<String>,
+    pub total_damage: u64,
+    pub hits: u32,
+    pub average_hit: u64,
+    /// Players it landed the killing blow on
+    pub deaths: u32,
+}
+
+/// Buff uptime data for a single buff on a single player
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct BuffUptime {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub source_name: String,
+    pub aura_type: String,  // "BUFF" or "DEBUFF"
+    pub uptime_secs: f64,
+    pub uptime_pct: f64,
+    pub avg_stacks: f64,
+    pub max_stacks: u32,
+    pub wowhead_url: String,
+    /// Timeline events for visualization
+    pub timeline: Vec<BuffEvent>,
+}
+
+/// Individual buff state change for timeline
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct BuffEvent {
+    /// Seconds into fight
+    pub time: f64,
+    /// "apply", "remove", "stack"
+    pub event_type: String,
+    pub stacks: u32,
+}
+
+/// The auras up on a player at one moment of a fight, served via a separate endpoint
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AuraSnapshot {
+    pub player_guid: String,
+    pub player_name: String,
+    /// Seconds into fight
+    pub time: f64,
+    /// Buffs first, then debuffs, each by name
+    pub auras: Vec<ActiveAura>,
+}
+
+/// An aura in an [`AuraSnapshot`]
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ActiveAura {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub source_name: String,
+    pub aura_type: String,  // "BUFF" or "DEBUFF"
+    pub stacks: u32,
+    /// When this application of it started, seconds into fight (0 for an aura already up at
+    /// the pull)
+    pub applied_at: f64,
+    pub wowhead_url: String,
+}
+
+/// A player's auras as lanes of intervals, for drawing a buff band view
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct PlayerAuraLanes {
+    pub player_guid: String,
+    pub player_name: String,
+    /// Buffs first, then debuffs, each highest uptime first
+    pub lanes: Vec<AuraLane>,
+}
+
+/// One aura's lane: when it was up, in order
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AuraLane {
+    pub spell_id: u64,
+    pub spell_name: String,
+    pub source_name: String,
+    pub aura_type: String,  // "BUFF" or "DEBUFF"
+    pub uptime_pct: f64,
+    pub wowhead_url: String,
+    /// Split where the stacks change; none overlap
+    pub intervals: Vec<AuraInterval>,
+}
+
+/// A stretch of an aura being up at one stack count, seconds into fight
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct AuraInterval {
+    pub start: f64,
+    pub end: f64,
+    pub stacks: u32,
+}
+
+/// A death event
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct DeathEvent {
+    /// Milliseconds from the pull
+    pub time_ms: i64,
+    pub player_name: String,
+    pub player_guid: String,
+    pub killing_blow_spell: Option<String>,
+    pub killing_blow_source: Option<String>,
+    pub killing_blow_amount: Option<u64>,
+    pub overkill: Option<i64>,
+    pub recap: Vec<RecapEvent>,
+    /// Personal defensives / health potions that were available but unused before the death
+    pub could_have_used: Vec<String>,
+    /// Seconds spent dead, until back in action or the end of the fight/segment
+    pub time_dead_secs: f64,
+    /// Resurrected in combat by a battle res spell
+    pub battle_res: bool,
+    /// Who cast the resurrection, if any
+    pub resurrected_by: Option<String>,
+    /// Killing blow was larger than the player's max HP
+    pub one_shot: bool,
+}
+
+/// A hit that would have killed a player if absorbs hadn't soaked it
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct NearDeath {
+    /// Milliseconds from the pull
+    pub time_ms: i64,
+    pub player_name: String,
+    pub spell_name: String,
+    pub source_name: String,
+    /// Damage absorbed from the hit
+    pub absorbed: u64,
+    /// HP the player was left with
+    pub hp_remaining: u64,
+    /// Player whose shield absorbed the hit
+    pub saved_by: Option<String>,
+    pub shield_spell: Option<String>,
+}
+
+/// A moment when the whole group was dead at once
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct WipeEvent {
+    /// Milliseconds from the pull
+    pub time_ms: i64,
+    /// Key segment the wipe happened in
+    pub segment_index: Option<usize>,
+    pub segment_name: Option<String>,
+    /// Estimated run-back: from the wipe until the group dealt damage again
+    pub run_back_secs: Option<f64>,
+}
+
+/// A single event in a death recap timeline
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct RecapEvent {
+    /// Milliseconds from the pull
+    pub time_ms: i64,
+    pub event_type: String,  // "damage", "healing", "buff_applied", "buff_removed"
+    pub amount: u64,
+    pub spell_name: String,
+    pub spell_id: u64,
+    pub source_name: String,
+    pub wowhead_url: String,
+    pub current_hp: u64,
+    pub max_hp: u64,
+}
+
+/// A single HP snapshot for a player at a point in time (for replay)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct HpSnapshot {
+    pub time: f64,
+    pub guid: String,
+    pub name: String,
+    pub class_name: String,
+    pub current_hp: u64,
+    pub max_hp: u64,
+    pub is_dead: bool,
+    pub pos_x: Option<f64>,
+    pub pos_y: Option<f64>,
+}
+
+/// A zone change event
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ZoneChange {
+    pub timestamp: String,
+    pub zone_id: u64,
+    pub zone_name: String,
+    pub difficulty_id: u32,
+}
+
+/// One stay in an instance, from entering it to leaving it. Loading back in (a reload, or a
+/// short corpse run through the entrance) continues the same visit.
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct InstanceVisit {
+    pub zone_id: u64,
+    pub zone_name: String,
+    pub difficulty_id: u32,
+    pub difficulty_name: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub duration_secs: f64,
+    /// Names of the players seen acting during the visit
+    pub players: Vec<String>,
+    /// Stable IDs of the encounters that started during the visit
+    pub encounter_ids: Vec<String>,
+}
+
+/// File listing info
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct LogFileInfo {
+    pub filename: String,
+    pub size_bytes: u64,
+    pub size_display: String,
+    pub date_str: String,
+    /// Modified recently — WoW is probably still writing to it
+    pub active: bool,
+    /// Kept by the retention policy however old it gets
+    pub favorite: bool,
+    /// Bosses and keys in the file, from a quick scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe: Option<LogProbe>,
+}
+
+/// What a log contains, from its encounter and key boundary lines only (`probe::probe_log`)
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct LogProbe {
+    pub boss_pulls: u32,
+    pub boss_kills: u32,
+    /// In the order first pulled
+    pub bosses: Vec<ProbeBoss>,
+    pub keys: Vec<ProbeKey>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ProbeBoss {
+    pub name: String,
+    pub difficulty_name: String,
+    pub pulls: u32,
+    pub kills: u32,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct ProbeKey {
+    pub dungeon: String,
+    pub level: u32,
+    /// `None` if the log has no CHALLENGE_MODE_END for the key (still running, or the log stops)
+    pub success: Option<bool>,
+}
+
+/// Difficulty ID to name mapping
+pub fn difficulty_name(id: u32) -> String {
+    match id {
+        0 => "Open World".to_string(),
+        1 => "Normal".to_string(),
+        2 => "Heroic".to_string(),
+        8 => "Mythic Keystone".to_string(),
+        14 => "Normal (Raid)".to_string(),
+        15 => "Heroic (Raid)".to_string(),
+        16 => "Mythic (Raid)".to_string(),
+        17 => "Looking for Raid".to_string(),
+        23 => "Mythic".to_string(),
+        24 => "Timewalking".to_string(),
+        // Classic raid sizes
+        3 => "10 Player".to_string(),
+        4 => "25 Player".to_string(),
+        5 => "10 Player (Heroic)".to_string(),
+        6 => "25 Player (Heroic)".to_string(),
+        9 => "40 Player".to_string(),
+        148 => "20 Player".to_string(),
+        175 => "10 Player".to_string(),
+        176 => "25 Player".to_string(),
+        193 => "10 Player (Heroic)".to_string(),
+        194 => "25 Player (Heroic)".to_string(),
+        _ => format!("Unknown ({})", id),
+    }
+}
+
+/// Resolve an affix ID to its name/description/icon, with a fallback for unknown IDs
+pub fn affix_from_id(id: u32) -> Affix {
+    match crate::game_data::affix_info(id) {
+        Some((name, description, icon)) => Affix {
+            id,
+            name: name.to_string(),
+            description: description.to_string(),
+            icon_url: crate::game_data::icon_url(icon),
+        },
+        None => Affix {
+            id,
+            name: format!("Affix {}", id),
+            description: String::new(),
+            icon_url: crate::game_data::icon_url("inv_misc_questionmark"),
+        },
+    }
+}
+
+/// Stable encounter ID: 64-bit FNV-1a hash of the start timestamp and encounter ID, as 16 hex
+/// digits. Unlike `index`, it doesn't change as the log grows, so it is safe in links.
+pub fn stable_encounter_id(start_time: &str, encounter_id: u64) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in format!("{}|{}", start_time, encounter_id).bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Generate a Wowhead URL for a spell
+pub fn wowhead_url(spell_id: u64) -> String {
+    format!("https://www.wowhead.com/spell={}", spell_id)
+}
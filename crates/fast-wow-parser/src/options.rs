@@ -0,0 +1,193 @@
+//! Parser tunables
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Options that change what a parse produces. Missing fields fall back to the defaults,
+/// so a partial JSON object is a valid config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParseOptions {
+    /// Seconds of events before a death shown in its recap
+    pub recap_window_secs: f64,
+    /// Upper bound on events kept in one death recap (the oldest are dropped first)
+    pub recap_max_events: usize,
+    /// NPC IDs of adds whose damage is reported separately as priority targets
+    pub priority_npc_ids: Vec<u64>,
+    /// Seconds before ENCOUNTER_START captured as the pre-pull window (precasts, pre-pull DoTs)
+    pub pre_pull_secs: f64,
+    /// Seconds after ENCOUNTER_END captured as the post-pull window (trailing ticks)
+    pub post_pull_secs: f64,
+    /// A tank swap is flagged as a late taunt when the old tank's debuff had more stacks than this
+    pub taunt_stack_threshold: u32,
+    /// Spell IDs of avoidable damage (swirlies, frontals, pools), on top of the built-in affix list
+    pub avoidable_spell_ids: Vec<u64>,
+    /// Spell IDs of damage procs (trinkets, embellishments, enchants), on top of the built-in
+    /// list; counted as proc damage in `damage_types`
+    pub proc_spell_ids: Vec<u64>,
+    /// Spell IDs of crowd control from enemies (boss fears, mind controls), on top of the
+    /// built-in PvP list
+    pub crowd_control_spell_ids: Vec<u64>,
+    /// Spell IDs of dangerous enemy abilities (frontals, tank busters) checked for a defensive
+    /// on the player they hit
+    pub dangerous_spell_ids: Vec<u64>,
+    /// Any enemy hit taking at least this percent of the player's max HP is checked too
+    pub major_hit_pct: f64,
+    /// NPC IDs treated as training dummies, on top of the built-in list; fights against only
+    /// dummies become practice sessions
+    pub training_dummy_npc_ids: Vec<u64>,
+    /// Name trash segments after the map area of their first pull (from `MAP_CHANGE`)
+    /// instead of "Trash 1", "Trash 2", ...
+    pub trash_area_names: bool,
+    /// uiMapID -> name, overriding the in-game map name for areas whose name is unhelpful
+    pub area_names: HashMap<u64, String>,
+    /// User-defined counters evaluated during the parse, reported per encounter in
+    /// `custom_metrics`
+    pub custom_metrics: Vec<MetricRule>,
+    /// Analyses to run; a disabled one isn't tracked at all and its output stays empty
+    pub features: AnalysisFeatures,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            recap_window_secs: 15.0,
+            recap_max_events: 500,
+            priority_npc_ids: Vec::new(),
+            pre_pull_secs: 3.0,
+            post_pull_secs: 3.0,
+            taunt_stack_threshold: 3,
+            avoidable_spell_ids: Vec::new(),
+            proc_spell_ids: Vec::new(),
+            crowd_control_spell_ids: Vec::new(),
+            dangerous_spell_ids: Vec::new(),
+            major_hit_pct: 30.0,
+            training_dummy_npc_ids: Vec::new(),
+            trash_area_names: true,
+            area_names: HashMap::new(),
+            custom_metrics: Vec::new(),
+            features: AnalysisFeatures::default(),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// The options with the numeric tunables clamped to their supported ranges (NaN takes
+    /// the default), so options that parse the same compare equal
+    pub fn normalized(mut self) -> Self {
+        let defaults = ParseOptions::default();
+        let clamp = |value: f64, default: f64, min: f64, max: f64| if value.is_nan() { default } else { value.clamp(min, max) };
+        self.recap_window_secs = clamp(self.recap_window_secs, defaults.recap_window_secs, 1.0, 120.0);
+        self.pre_pull_secs = clamp(self.pre_pull_secs, defaults.pre_pull_secs, 0.0, 30.0);
+        self.post_pull_secs = clamp(self.post_pull_secs, defaults.post_pull_secs, 0.0, 30.0);
+        self.major_hit_pct = clamp(self.major_hit_pct, defaults.major_hit_pct, 1.0, 100.0);
+        self
+    }
+}
+
+/// A custom metric: which events count, and what of them is added up. Every filter left
+/// empty matches anything.
+///
+/// ```json
+/// { "name": "Healing on tanks in P2", "measure": "amount", "events": ["SPELL_HEAL", "SPELL_PERIODIC_HEAL"],
+///   "target_roles": ["tank"], "phases": [2] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricRule {
+    pub name: String,
+    pub measure: MetricMeasure,
+    /// Event types matched; damage and healing events by default
+    pub events: Vec<String>,
+    pub spell_ids: Vec<u64>,
+    /// Who each match is credited to
+    pub per: MetricPer,
+    /// Only events on players of these roles ("tank", "healer", "dps")
+    pub target_roles: Vec<String>,
+    /// Only events in these boss phases, counting from 1
+    pub phases: Vec<u32>,
+    /// Only in these encounters (boss encounter IDs, or the dungeon's zone ID for a key)
+    pub encounter_ids: Vec<u64>,
+}
+
+impl Default for MetricRule {
+    fn default() -> Self {
+        MetricRule {
+            name: String::new(),
+            measure: MetricMeasure::Count,
+            events: ["SPELL_DAMAGE", "SPELL_PERIODIC_DAMAGE", "RANGE_DAMAGE", "SWING_DAMAGE", "SPELL_HEAL", "SPELL_PERIODIC_HEAL"]
+                .into_iter().map(String::from).collect(),
+            spell_ids: Vec::new(),
+            per: MetricPer::Source,
+            target_roles: Vec::new(),
+            phases: Vec::new(),
+            encounter_ids: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricMeasure {
+    /// Number of matching events
+    Count,
+    /// Damage or effective healing of the matching events
+    Amount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricPer {
+    /// The player doing it, pets counting for their owner
+    Source,
+    /// The player it happened to
+    Target,
+}
+
+/// Analyses that cost noticeable time or memory on big logs and can be switched off.
+/// All are on by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisFeatures {
+    /// Player HP and position samples for the replay view (`replay_timeline`, `boss_positions`),
+    /// and enemy casts (`enemy_casts`, `mechanic_markers`)
+    pub replay: bool,
+    /// Aura history for buff uptimes (`buff_uptimes`)
+    pub buff_timelines: bool,
+    /// Every damage and healing hit, kept for the ability timeline (`raw_ability_events`) and
+    /// the per-pull ability breakdowns
+    pub ability_events: bool,
+    /// Crowd control received and applied
+    pub crowd_control: bool,
+    /// Who pulled, first targets and threat redirects (`PullOpener`), and who each enemy of a
+    /// pull was attacking (`TrashPull::tanking`)
+    pub pull_openers: bool,
+}
+
+impl Default for AnalysisFeatures {
+    fn default() -> Self {
+        AnalysisFeatures {
+            replay: true,
+            buff_timelines: true,
+            ability_events: true,
+            crowd_control: true,
+            pull_openers: true,
+        }
+    }
+}
+
+impl AnalysisFeatures {
+    /// Turn a feature on or off by name; `false` if there is no such feature
+    pub fn set(&mut self, name: &str, enabled: bool) -> bool {
+        let flag = match name {
+            "replay" => &mut self.replay,
+            "buff_timelines" => &mut self.buff_timelines,
+            "ability_events" => &mut self.ability_events,
+            "crowd_control" => &mut self.crowd_control,
+            "pull_openers" => &mut self.pull_openers,
+            _ => return false,
+        };
+        *flag = enabled;
+        true
+    }
+}
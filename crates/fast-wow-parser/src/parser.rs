@@ -0,0 +1,6475 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::compare;
+use crate::damage_types;
+use crate::efficiency;
+use crate::events::{self, CombatContext, EventHandler, LogEvent};
+use crate::game_data;
+use crate::guid;
+use crate::insights;
+use crate::handlers::{self, CombatHandlers};
+use crate::log_file;
+use crate::log_format::{FieldMap, LogFormat};
+use crate::models::*;
+use crate::options::ParseOptions;
+use crate::schools;
+
+/// Parse a WoW combat log file and return a summary.
+/// A final line without a newline is still being written by WoW and is held back.
+pub fn parse_combat_log(path: &Path) -> Result<CombatLogSummary, String> {
+    parse_combat_log_with(path, ParseOptions::default(), false)
+}
+
+/// Like `parse_combat_log`, with a timing breakdown in `CombatLogSummary::profile`
+pub fn parse_combat_log_profiled(path: &Path) -> Result<CombatLogSummary, String> {
+    parse_combat_log_with(path, ParseOptions::default(), true)
+}
+
+/// Parse with explicit options, optionally collecting a timing breakdown
+pub fn parse_combat_log_with(path: &Path, options: ParseOptions, profile: bool) -> Result<CombatLogSummary, String> {
+    parse_file(path, options, profile, None)
+}
+
+/// Like `parse_combat_log_with`, storing the byte offset read up to in `progress` as it goes
+pub fn parse_combat_log_with_progress(path: &Path, options: ParseOptions, profile: bool, progress: Arc<AtomicU64>) -> Result<CombatLogSummary, String> {
+    parse_file(path, options, profile, Some(progress))
+}
+
+fn parse_file(path: &Path, options: ParseOptions, profile: bool, progress: Option<Arc<AtomicU64>>) -> Result<CombatLogSummary, String> {
+    let file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::with_capacity(1024 * 1024, file); // 1MB buffer
+
+    let mut parser = CombatLogParser::new(log_filename(path)).with_options(options);
+    if profile {
+        parser = parser.with_profiling();
+    }
+    if let Some(progress) = progress {
+        parser = parser.with_progress(progress);
+    }
+    feed_lines(&mut reader, &mut parser, 0, u64::MAX, path);
+    finish_guarded(parser)
+}
+
+/// Feed a live parser the complete lines appended to `path` since byte `offset`.
+/// Returns the offset to continue from; a partial last line is left for the next call.
+pub fn tail_combat_log(path: &Path, parser: &mut CombatLogParser, offset: u64) -> Result<u64, String> {
+    let file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::with_capacity(1024 * 1024, file);
+    reader.seek(SeekFrom::Start(offset)).map_err(|e| format!("Failed to seek: {}", e))?;
+    Ok(feed_lines(&mut reader, parser, offset, u64::MAX, path))
+}
+
+/// Feed a parser that will start reading `path` partway through just the header line
+/// (COMBAT_LOG_VERSION selects the field layout); follow up with `tail_combat_log`
+pub fn read_log_header(path: &Path, parser: &mut CombatLogParser) -> Result<(), String> {
+    let file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::new(file);
+    feed_lines(&mut reader, parser, 0, 1, path);
+    Ok(())
+}
+
+/// Parse just one encounter's slice of a log (from `EncounterSummary::range` of an earlier
+/// parse) plus the header line. Logs are append-only, so a range stays valid as the file grows.
+pub fn parse_combat_log_range(path: &Path, options: ParseOptions, range: &EncounterRange) -> Result<CombatLogSummary, String> {
+    let file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::with_capacity(1024 * 1024, file);
+
+    let mut parser = CombatLogParser::new(log_filename(path)).with_options(options).with_context(range);
+    // COMBAT_LOG_VERSION selects the field layout
+    feed_lines(&mut reader, &mut parser, 0, 1, path);
+    reader.seek(SeekFrom::Start(range.start)).map_err(|e| format!("Failed to seek: {}", e))?;
+    feed_lines(&mut reader, &mut parser, range.start, range.end, path);
+    finish_guarded(parser)
+}
+
+/// Every cast, damage, heal and aura event of `spell_id` in a log, in order, with the spell's
+/// name. Group them by encounter with `group_spell_hits`.
+pub fn find_spell_events(path: &Path, spell_id: u64) -> Result<(String, Vec<SpellHit>), String> {
+    let file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::with_capacity(1024 * 1024, file);
+
+    // Cheap substring check before tokenizing; the spell ID is always its own field
+    let needle = format!(",{},", spell_id);
+    let mut spell_name = String::new();
+    let mut hits = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match log_file::read_until(&mut reader, b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Stopped searching {} early: {}", path.display(), e);
+                break;
+            }
+        }
+        let line = String::from_utf8_lossy(&buf);
+        if !line.contains(&needle) {
+            continue;
+        }
+        let Some((timestamp_str, event_part)) = split_timestamp_event(line.trim()) else { continue };
+        let fields = parse_csv_fields(event_part);
+        if fields.get(9).and_then(|s| s.parse::<u64>().ok()) != Some(spell_id) {
+            continue;
+        }
+        let event_type = fields[0];
+        let kind = match event_type {
+            "SPELL_CAST_SUCCESS" => "cast",
+            "SPELL_AURA_APPLIED" | "SPELL_AURA_REFRESH" | "SPELL_AURA_APPLIED_DOSE" => "aura",
+            _ if event_type.ends_with("_DAMAGE") => "damage",
+            _ if event_type.ends_with("_HEAL") => "heal",
+            _ => continue,
+        };
+        if spell_name.is_empty() {
+            spell_name = fields.get(10).map(|s| unquote(s)).unwrap_or_default();
+        }
+        hits.push(SpellHit {
+            time_ms: 0,
+            kind: kind.to_string(),
+            source_name: fields.get(2).map(|s| unquote(s)).unwrap_or_default(),
+            dest_name: fields.get(6).map(|s| unquote(s)).unwrap_or_default(),
+            timestamp_secs: parse_timestamp_to_secs(timestamp_str),
+        });
+    }
+    Ok((spell_name, hits))
+}
+
+/// Sort spell hits into the encounters they happened in. A hit inside a key and one of its
+/// trash/boss encounters is listed under both.
+pub fn group_spell_hits(spell_id: u64, spell_name: String, hits: &[SpellHit], encounters: &[EncounterSummary]) -> SpellSearchResult {
+    const MAX_HITS_PER_ENCOUNTER: usize = 200;
+
+    let mut matched = vec![false; hits.len()];
+    let mut groups = Vec::new();
+    for enc in encounters {
+        let start = parse_timestamp_to_secs(&enc.start_time);
+        let end = parse_timestamp_to_secs(&enc.end_time);
+        let mut group = SpellSearchEncounter {
+            index: enc.index,
+            stable_id: enc.stable_id.clone(),
+            name: enc.name.clone(),
+            encounter_type: enc.encounter_type.clone(),
+            start_time: enc.start_time.clone(),
+            casts: 0,
+            damage: 0,
+            heals: 0,
+            auras: 0,
+            hits: Vec::new(),
+        };
+        for (i, hit) in hits.iter().enumerate().filter(|(_, h)| h.timestamp_secs >= start && h.timestamp_secs <= end) {
+            matched[i] = true;
+            match hit.kind.as_str() {
+                "cast" => group.casts += 1,
+                "damage" => group.damage += 1,
+                "heal" => group.heals += 1,
+                _ => group.auras += 1,
+            }
+            if group.hits.len() < MAX_HITS_PER_ENCOUNTER {
+                group.hits.push(SpellHit { time_ms: to_millis(hit.timestamp_secs - start), ..hit.clone() });
+            }
+        }
+        if !group.hits.is_empty() {
+            groups.push(group);
+        }
+    }
+    SpellSearchResult {
+        spell_id,
+        spell_name,
+        wowhead_url: wowhead_url(spell_id),
+        encounters: groups,
+        outside_encounters: matched.iter().filter(|m| !**m).count() as u32,
+    }
+}
+
+fn log_filename(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Longest excerpt of a log line kept in reports (`ParseError::line`, unknown event samples)
+pub const MAX_LINE_EXCERPT_LEN: usize = 500;
+
+/// Sample lines kept per unknown event type
+pub const MAX_UNKNOWN_SAMPLES: usize = 3;
+/// Unknown event types tracked per parse; past this the log is likely not a combat log
+const MAX_UNKNOWN_EVENT_TYPES: usize = 100;
+
+/// The message a panic was raised with
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// `finish`, turning a panic into an error instead of taking the caller's thread down
+fn finish_guarded(parser: CombatLogParser) -> Result<CombatLogSummary, String> {
+    let parse_error = parser.parse_error.clone();
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| parser.finish())).map_err(|payload| {
+        let message = panic_message(payload.as_ref());
+        tracing::error!("Parser crashed building the summary: {}", message);
+        match parse_error {
+            Some(e) => format!("Parser crashed on line {} ({}), then building the summary: {}", e.line_number, e.message, message),
+            None => format!("Parser crashed building the summary: {}", message),
+        }
+    })
+}
+
+/// Feed complete lines starting at byte `offset` to the parser until `end` or EOF. A last
+/// line without a newline is held back while WoW may still be writing it, and fed otherwise.
+/// Returns the offset after the last line fed. A line that makes the parser panic stops
+/// the feed (the parser's state is no longer trustworthy) and is kept in `parse_error`.
+fn feed_lines(reader: &mut impl BufRead, parser: &mut CombatLogParser, mut offset: u64, end: u64, path: &Path) -> u64 {
+    if parser.parse_error.is_some() {
+        return offset;
+    }
+    let mut buf = Vec::new();
+    while offset < end {
+        buf.clear();
+        let read_start = parser.profiler.is_some().then(Instant::now);
+        let read = log_file::read_until(reader, b'\n', &mut buf);
+        if let (Some(profiler), Some(t)) = (parser.profiler.as_mut(), read_start) {
+            profiler.io += t.elapsed();
+        }
+        match read {
+            Ok(0) => break,
+            Ok(_) if buf.last() != Some(&b'\n') && log_file::metadata(path).is_ok_and(|m| log_file::is_active(&m)) => {
+                tracing::debug!("Holding back partial trailing line ({} bytes)", buf.len());
+                break;
+            }
+            Ok(n) => {
+                let next = offset + n as u64;
+                let line = String::from_utf8_lossy(&buf);
+                let fed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| parser.process_line_at(offset..next, &line)));
+                if let Err(payload) = fed {
+                    let error = ParseError {
+                        line_number: parser.lines_seen,
+                        byte_offset: offset,
+                        line: line.trim_end().chars().take(MAX_LINE_EXCERPT_LEN).collect(),
+                        message: panic_message(payload.as_ref()),
+                    };
+                    tracing::error!(line_number = error.line_number, byte_offset = error.byte_offset, line = %error.line,
+                        "Parser crashed reading {}, keeping the results so far: {}", path.display(), error.message);
+                    parser.parse_error = Some(error);
+                    return offset;
+                }
+                offset = next;
+                if let Some(progress) = &parser.progress {
+                    progress.store(offset, Ordering::Relaxed);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Stopped reading {} early: {}", path.display(), e);
+                break;
+            }
+        }
+    }
+    offset
+}
+
+/// A generic combat segment ends after this long without a hostile exchange
+const COMBAT_SEGMENT_GAP_SECS: f64 = 10.0;
+
+/// Streaming combat log parser. Feed it lines in order (from a file, a network stream or a
+/// live log being appended to) with `process_line`, then call `finish` for the summary.
+pub struct CombatLogParser {
+    filename: String,
+    log_version: Option<u32>,
+    build_version: Option<String>,
+    /// Cleared by a header with ADVANCED_LOG_ENABLED,0
+    advanced_logging: bool,
+    log_format: LogFormat,
+    field_map: FieldMap,
+    zone_changes: Vec<ZoneChange>,
+    /// Players (guid -> name) seen acting since the last ZONE_CHANGE
+    zone_players: HashMap<String, String>,
+    /// Players seen during each entry of `zone_changes`, kept in step with it
+    zone_rosters: Vec<HashMap<String, String>>,
+    last_event_secs: f64,
+    last_event_str: String,
+    /// Every MAP_CHANGE so far: (timestamp_secs, map with offset 0)
+    map_changes: Vec<(f64, ReplayMap)>,
+    encounters: Vec<EncounterSummary>,
+
+    // M+ key tracking
+    in_key: bool,
+    key_start_time: Option<f64>,
+    key_start_str: String,
+    key_name: String,
+    key_zone_id: u64,
+    /// Tells apart dungeons sharing an instance ID (the two halves of Tazavesh)
+    key_challenge_mode_id: u64,
+    key_level: u32,
+    key_affixes: Vec<u32>,
+    key_boss_encounters: Vec<BossEncounter>,
+    /// Pre-pull window of the key boss in progress, captured at its ENCOUNTER_START
+    key_boss_pre_pull: Option<PullWindow>,
+    /// Key boss segment waiting for its post-pull window: (index into key_segments, end secs)
+    pending_key_post_pull: Option<(usize, f64)>,
+
+    // Per-encounter/key tracking
+    tracker: EventTracker,
+
+    // Segment tracking within M+ keys
+    key_segments: Vec<KeySegment>,
+    segment_tracker: EventTracker,
+    segment_start_secs: f64,
+    segment_start_str: String,
+    segment_boss_count: usize,
+
+    // Boss encounter sub-tracking (within a key)
+    in_boss: bool,
+    boss_start_time: Option<f64>,
+    boss_start_str: String,
+    boss_name: String,
+    boss_id: u64,
+
+    // Standalone boss encounters (raids, non-M+ dungeons)
+    standalone_boss: bool,
+    standalone_start_time: Option<f64>,
+    standalone_start_str: String,
+    standalone_name: String,
+    standalone_id: u64,
+    standalone_difficulty: u32,
+    standalone_group_size: u32,
+    standalone_tracker: EventTracker,
+    /// Pre-pull window captured at ENCOUNTER_START
+    standalone_pre_pull: Option<PullWindow>,
+    /// Encounter waiting for its post-pull window: (index into encounters, end secs)
+    pending_post_pull: Option<(usize, f64)>,
+    /// File range of the boss encounter in progress (end filled in at ENCOUNTER_END)
+    standalone_range: Option<EncounterRange>,
+
+    // Trash tracking (between boss encounters)
+    trash_tracker: EventTracker,
+    trash_start_secs: f64,
+    trash_start_str: String,
+    trash_has_combat: bool,
+    trash_difficulty: u32,
+    trash_group_size: u32,
+
+    // Dungeon zone tracking — detect entry/exit via ZONE_CHANGE
+    in_dungeon_zone: bool,
+
+    // Generic combat segments (open world, raid trash, target dummies)
+    combat_tracker: EventTracker,
+    /// Start of the open segment, if any
+    combat_start_secs: Option<f64>,
+    combat_start_str: String,
+    /// Last hostile exchange in the open segment: it ends there
+    combat_last_hostile_secs: f64,
+    combat_last_hostile_str: String,
+
+    // Arena matches (ARENA_MATCH_START to ARENA_MATCH_END)
+    in_arena: bool,
+    arena_start_secs: f64,
+    arena_start_str: String,
+    arena_bracket: String,
+    arena_log_team: u32,
+    arena_tracker: EventTracker,
+    arena_rounds: Vec<ArenaRound>,
+    /// Current round, reset by each batch of COMBATANT_INFO (a new Solo Shuffle round)
+    round_tracker: EventTracker,
+    /// Team of each player this round, from COMBATANT_INFO
+    round_teams: HashMap<String, u32>,
+    /// First hostile action between the teams this round
+    round_start: Option<(f64, String)>,
+    round_last_hostile_secs: f64,
+    round_last_hostile_str: String,
+
+    // Map area naming for trash segments
+    /// Area from the last MAP_CHANGE (None when area naming is off)
+    current_area: Option<String>,
+    /// Area of the first pull in the current key segment
+    segment_area: Option<String>,
+    /// Area of the first pull in the current trash run
+    trash_area: Option<String>,
+
+    // Byte offsets, when lines are fed with `process_line_at`
+    offsets_known: bool,
+    line_offset: u64,
+    next_line_offset: u64,
+    key_start_offset: u64,
+    /// (timestamp_secs, offset) of lines within the pre-pull window, outside encounters
+    recent_line_offsets: VecDeque<(f64, u64)>,
+    options: ParseOptions,
+    profiler: Option<Profiler>,
+    /// Byte offset `feed_lines` has read up to, for watching a long parse from another thread
+    progress: Option<Arc<AtomicU64>>,
+    /// Lines passed to `process_line` so far
+    lines_seen: u64,
+    /// The line the parser crashed on, if it did
+    parse_error: Option<ParseError>,
+    handlers: Vec<Box<dyn EventHandler>>,
+    /// Lines with an event type neither the parser nor a handler knows
+    unknown_events: HashMap<String, UnknownEvent>,
+}
+
+impl CombatLogParser {
+    pub fn new(filename: impl Into<String>) -> Self {
+        CombatLogParser {
+            filename: filename.into(),
+            log_version: None,
+            build_version: None,
+            advanced_logging: true,
+            log_format: LogFormat::Retail,
+            field_map: LogFormat::Retail.field_map(),
+            zone_changes: Vec::new(),
+            zone_players: HashMap::new(),
+            zone_rosters: Vec::new(),
+            last_event_secs: 0.0,
+            last_event_str: String::new(),
+            map_changes: Vec::new(),
+            encounters: Vec::new(),
+            in_key: false,
+            key_start_time: None,
+            key_start_str: String::new(),
+            key_name: String::new(),
+            key_zone_id: 0,
+            key_challenge_mode_id: 0,
+            key_level: 0,
+            key_affixes: Vec::new(),
+            key_boss_encounters: Vec::new(),
+            key_boss_pre_pull: None,
+            pending_key_post_pull: None,
+            tracker: EventTracker::new(),
+            key_segments: Vec::new(),
+            segment_tracker: EventTracker::new(),
+            segment_start_secs: 0.0,
+            segment_start_str: String::new(),
+            segment_boss_count: 0,
+            in_boss: false,
+            boss_start_time: None,
+            boss_start_str: String::new(),
+            boss_name: String::new(),
+            boss_id: 0,
+            standalone_boss: false,
+            standalone_start_time: None,
+            standalone_start_str: String::new(),
+            standalone_name: String::new(),
+            standalone_id: 0,
+            standalone_difficulty: 0,
+            standalone_group_size: 0,
+            standalone_tracker: EventTracker::new(),
+            standalone_pre_pull: None,
+            pending_post_pull: None,
+            standalone_range: None,
+            trash_tracker: EventTracker::new(),
+            trash_start_secs: 0.0,
+            trash_start_str: String::new(),
+            trash_has_combat: false,
+            trash_difficulty: 0,
+            trash_group_size: 0,
+            in_dungeon_zone: false,
+            combat_tracker: EventTracker::new(),
+            combat_start_secs: None,
+            combat_start_str: String::new(),
+            combat_last_hostile_secs: 0.0,
+            combat_last_hostile_str: String::new(),
+            in_arena: false,
+            arena_start_secs: 0.0,
+            arena_start_str: String::new(),
+            arena_bracket: String::new(),
+            arena_log_team: 0,
+            arena_tracker: EventTracker::new(),
+            arena_rounds: Vec::new(),
+            round_tracker: EventTracker::new(),
+            round_teams: HashMap::new(),
+            round_start: None,
+            round_last_hostile_secs: 0.0,
+            round_last_hostile_str: String::new(),
+            current_area: None,
+            segment_area: None,
+            trash_area: None,
+            offsets_known: false,
+            line_offset: 0,
+            next_line_offset: 0,
+            key_start_offset: 0,
+            recent_line_offsets: VecDeque::new(),
+            options: ParseOptions::default(),
+            profiler: None,
+            progress: None,
+            lines_seen: 0,
+            parse_error: None,
+            handlers: Vec::new(),
+            unknown_events: HashMap::new(),
+        }
+    }
+
+    /// Use non-default options; call before feeding any lines
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.tracker = EventTracker::with_options(&options);
+        self.segment_tracker = EventTracker::with_options(&options);
+        self.standalone_tracker = EventTracker::with_options(&options);
+        self.trash_tracker = EventTracker::with_options(&options);
+        self.combat_tracker = EventTracker::with_options(&options);
+        self.arena_tracker = EventTracker::with_options(&options);
+        self.round_tracker = EventTracker::with_options(&options);
+        self.options = options;
+        self
+    }
+
+    /// Seed the player/pet state a range parse can't see from earlier in the log
+    pub fn with_context(mut self, range: &EncounterRange) -> Self {
+        for tracker in [&mut self.tracker, &mut self.segment_tracker, &mut self.standalone_tracker, &mut self.trash_tracker] {
+            tracker.player_names = range.player_names.clone();
+            tracker.player_specs = range.player_specs.clone();
+            tracker.player_talents = range.player_talents.clone();
+            tracker.pet_owners = range.pet_owners.clone();
+        }
+        // Instance metadata and difficulty come from the zone, whose ZONE_CHANGE is outside the slice
+        self.zone_changes = range.zone.iter().cloned().collect();
+        self
+    }
+
+    /// Pass events to `handler` as well; its report is returned in `CombatLogSummary::extensions`
+    pub fn with_handler(mut self, handler: Box<dyn EventHandler>) -> Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    /// Collect a timing breakdown, returned in `CombatLogSummary::profile`
+    pub fn with_profiling(mut self) -> Self {
+        self.profiler = Some(Profiler::default());
+        self
+    }
+
+    /// Store the byte offset reached in `progress` while reading a file
+    pub fn with_progress(mut self, progress: Arc<AtomicU64>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Like `process_line`, for a line at `bytes` in the file; encounters then get a `range`
+    pub fn process_line_at(&mut self, bytes: std::ops::Range<u64>, line: &str) {
+        self.offsets_known = true;
+        self.line_offset = bytes.start;
+        self.next_line_offset = bytes.end;
+        self.process_line(line);
+    }
+
+    /// Process one raw log line (`<timestamp>  <EVENT>,<fields...>`)
+    pub fn process_line(&mut self, line: &str) {
+        self.lines_seen += 1;
+        let started = self.profiler.is_some().then(Instant::now);
+        // A file re-saved by an editor can start with a byte order mark
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            return;
+        }
+
+        // Parse timestamp and event
+        let (timestamp_str, event_part) = match split_timestamp_event(line) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let timestamp_secs = parse_timestamp_to_secs(timestamp_str);
+        let fields: Vec<&str> = parse_csv_fields(event_part);
+
+        if fields.is_empty() {
+            return;
+        }
+
+        let event_type = fields[0];
+
+        self.last_event_secs = timestamp_secs;
+        self.last_event_str.clear();
+        self.last_event_str.push_str(timestamp_str);
+
+        let tokenized = started.map(|_| Instant::now());
+        self.handle_event(event_type, &fields, timestamp_str, timestamp_secs);
+        let mut claimed = false;
+        if !self.handlers.is_empty() {
+            let event = LogEvent { timestamp: timestamp_str, timestamp_secs, event_type, fields: &fields, combat: None };
+            for handler in self.handlers.iter_mut().filter(|h| h.handles(event_type)) {
+                handler.handle(&event);
+                claimed = true;
+            }
+        }
+        if !claimed && !events::is_known(event_type) {
+            self.record_unknown_event(event_type, event_part);
+        }
+        if let (Some(profiler), Some(started), Some(tokenized)) = (self.profiler.as_mut(), started, tokenized) {
+            profiler.record(event_type, tokenized - started, tokenized.elapsed());
+        }
+    }
+
+    /// Count a line whose event type nobody handles, keeping the first few as samples
+    fn record_unknown_event(&mut self, event_type: &str, event_part: &str) {
+        if !self.unknown_events.contains_key(event_type) {
+            if self.unknown_events.len() >= MAX_UNKNOWN_EVENT_TYPES {
+                return;
+            }
+            tracing::debug!(event_type, "unknown combat log event type");
+            self.unknown_events.insert(event_type.to_string(), UnknownEvent {
+                event_type: event_type.to_string(),
+                count: 0,
+                samples: Vec::new(),
+            });
+        }
+        let unknown = self.unknown_events.get_mut(event_type).unwrap();
+        unknown.count += 1;
+        if unknown.samples.len() < MAX_UNKNOWN_SAMPLES {
+            unknown.samples.push(event_part.chars().take(MAX_LINE_EXCERPT_LEN).collect());
+        }
+    }
+
+    /// Attach the post-pull window to the encounter that ended, from the trash tracker
+    /// that has been recording since ENCOUNTER_END. `end_offset` is where the window ends in the file.
+    fn flush_post_pull(&mut self, end_offset: u64) {
+        if let Some((index, end_secs)) = self.pending_post_pull.take() {
+            let window = self.trash_tracker.build_pull_window(end_secs, end_secs + self.options.post_pull_secs);
+            if let Some(enc) = self.encounters.get_mut(index) {
+                enc.post_pull = window;
+                if let Some(range) = enc.range.as_mut() {
+                    range.end = end_offset;
+                }
+            }
+        }
+    }
+
+    /// Attach the post-pull window to the key boss segment that ended, from the segment
+    /// tracker that has been recording the trash after it
+    fn flush_key_post_pull(&mut self) {
+        if let Some((index, end_secs)) = self.pending_key_post_pull.take() {
+            let window = self.segment_tracker.build_pull_window(end_secs, end_secs + self.options.post_pull_secs);
+            if let Some(segment) = self.key_segments.get_mut(index) {
+                segment.post_pull = window;
+            }
+        }
+    }
+
+    /// Dispatch one tokenized event to the encounter/key state machine and trackers
+    fn handle_event(&mut self, event_type: &str, fields: &[&str], timestamp_str: &str, timestamp_secs: f64) {
+        if let Some((_, end_secs)) = self.pending_post_pull {
+            let window_over = timestamp_secs > end_secs + self.options.post_pull_secs;
+            // These reset the trash tracker, so take the window before they do
+            if window_over || matches!(event_type, "ENCOUNTER_START" | "ZONE_CHANGE" | "CHALLENGE_MODE_START") {
+                self.flush_post_pull(self.line_offset);
+            }
+        }
+        if let Some((_, end_secs)) = self.pending_key_post_pull {
+            let window_over = timestamp_secs > end_secs + self.options.post_pull_secs;
+            // These reset the segment tracker or close the key
+            if window_over || matches!(event_type, "ENCOUNTER_START" | "CHALLENGE_MODE_START" | "CHALLENGE_MODE_END") {
+                self.flush_key_post_pull();
+            }
+        }
+        if self.offsets_known && !self.in_key && !self.standalone_boss {
+            self.recent_line_offsets.push_back((timestamp_secs, self.line_offset));
+            while self.recent_line_offsets.front().is_some_and(|(ts, _)| timestamp_secs - ts > self.options.pre_pull_secs) {
+                self.recent_line_offsets.pop_front();
+            }
+        }
+
+        match event_type {
+            "COMBAT_LOG_VERSION" => {
+                if fields.len() > 1 {
+                    self.log_version = fields[1].parse().ok();
+                }
+                if fields.len() > 5 {
+                    self.build_version = Some(fields[5].trim_matches('"').to_string());
+                }
+                if fields.get(2) == Some(&"ADVANCED_LOG_ENABLED") && fields.get(3) == Some(&"0") {
+                    self.advanced_logging = false;
+                }
+                self.log_format = LogFormat::detect(fields);
+                self.field_map = self.log_format.field_map();
+            }
+            "COMBATANT_INFO" => {
+                // In arenas the faction field is the team. A new batch of combatants after
+                // the teams have fought starts the next Solo Shuffle round.
+                if self.in_arena && self.round_start.is_some() {
+                    self.flush_arena_round();
+                    self.round_tracker = EventTracker::new_with_context(&self.arena_tracker);
+                    self.round_teams.clear();
+                }
+                if let Some(team) = fields.get(2).and_then(|f| f.parse::<u32>().ok()) {
+                    self.round_teams.insert(fields[1].to_string(), team);
+                    if self.in_arena {
+                        self.arena_tracker.pvp_teams.insert(fields[1].to_string(), team);
+                        self.round_tracker.pvp_teams.insert(fields[1].to_string(), team);
+                    }
+                }
+                if self.field_map.has_combatant_spec && fields.len() > 25 {
+                    let guid = fields[1];
+                    let spec_id = fields[25].parse::<u32>().ok().filter(|&id| id > 0);
+                    let talents = fields.get(26).map(|f| parse_talents(f)).filter(|t| !t.is_empty());
+                    for tracker in [
+                        &mut self.tracker, &mut self.segment_tracker, &mut self.standalone_tracker,
+                        &mut self.arena_tracker, &mut self.round_tracker, &mut self.trash_tracker,
+                    ] {
+                        if let Some(spec_id) = spec_id {
+                            tracker.player_specs.insert(guid.to_string(), spec_id);
+                        }
+                        if let Some(talents) = &talents {
+                            tracker.player_talents.insert(guid.to_string(), talents.clone());
+                        }
+                    }
+                }
+            }
+            "ARENA_MATCH_START" => {
+                self.flush_combat_segment();
+                if self.in_arena {
+                    self.flush_arena_match(None, timestamp_str);
+                }
+                self.in_arena = true;
+                self.arena_start_secs = timestamp_secs;
+                self.arena_start_str = timestamp_str.to_string();
+                self.arena_bracket = fields.get(3).map(|f| unquote(f)).unwrap_or_default();
+                self.arena_log_team = fields.get(4).and_then(|f| f.parse().ok()).unwrap_or(0);
+                self.arena_tracker = EventTracker::new_with_context(&self.trash_tracker);
+                self.round_tracker = EventTracker::new_with_context(&self.trash_tracker);
+                // COMBATANT_INFO for the first round may come just before the match start
+                self.arena_tracker.pvp_teams = self.round_teams.clone();
+                self.round_tracker.pvp_teams = self.round_teams.clone();
+                self.arena_rounds.clear();
+                self.round_start = None;
+            }
+            "ARENA_MATCH_END" => {
+                if self.in_arena {
+                    let winning_team = fields.get(1).and_then(|f| f.parse().ok());
+                    self.flush_arena_match(winning_team, timestamp_str);
+                }
+            }
+            "ZONE_CHANGE" => {
+                self.flush_combat_segment();
+                // Leaving an arena without ARENA_MATCH_END
+                if self.in_arena {
+                    self.flush_arena_match(None, timestamp_str);
+                }
+                if fields.len() >= 4 {
+                    let zc_difficulty: u32 = fields[3].parse().unwrap_or(0);
+                    let zc_zone_name = unquote(fields[2]);
+                    if !self.zone_changes.is_empty() {
+                        self.zone_rosters.push(std::mem::take(&mut self.zone_players));
+                    }
+                    self.zone_players.clear();
+                    self.zone_changes.push(ZoneChange {
+                        timestamp: timestamp_str.to_string(),
+                        zone_id: fields[1].parse().unwrap_or(0),
+                        zone_name: zc_zone_name.clone(),
+                        difficulty_id: zc_difficulty,
+                    });
+                    // The new zone's MAP_CHANGE follows
+                    self.current_area = None;
+
+                    // Detect dungeon zone entry/exit (difficulty 1=Normal, 2=Heroic, 23=Mythic, 24=Timewalking)
+                    let is_dungeon_diff = matches!(zc_difficulty, 1 | 2 | 23 | 24);
+                    if is_dungeon_diff && !self.in_key {
+                        if !self.in_dungeon_zone {
+                            // Entering a dungeon zone — start tracking trash
+                            self.in_dungeon_zone = true;
+                            self.trash_tracker = EventTracker::new_with_context(&self.tracker);
+                            self.trash_start_secs = timestamp_secs;
+                            self.trash_start_str = timestamp_str.to_string();
+                            self.trash_has_combat = false;
+                            self.trash_area = None;
+                            self.trash_difficulty = zc_difficulty;
+                            self.trash_group_size = 5; // dungeons are 5-man
+                        }
+                    } else if self.in_dungeon_zone {
+                        // Leaving a dungeon zone — flush any trailing trash
+                        if self.trash_has_combat {
+                            let trash_duration = timestamp_secs - self.trash_start_secs;
+                            if trash_duration > 1.0 {
+                                let players = self.trash_tracker.build_player_summaries(trash_duration);
+                                let custom_metrics = self.trash_tracker.build_custom_metrics(0, &players);
+                                self.encounters.push(EncounterSummary {
+                                    index: self.encounters.len(),
+                                    stable_id: String::new(),
+                                    encounter_id: 0,
+                                    name: self.trash_area.clone().unwrap_or_else(|| "Trash".to_string()),
+                                    difficulty_id: self.trash_difficulty,
+                                    difficulty_name: difficulty_name(self.trash_difficulty),
+                                    instance_name: None,
+                                    season: None,
+                                    instance_icon: None,
+                                    group_size: self.trash_group_size,
+                                    success: true,
+                                    duration_secs: trash_duration,
+                                    start_time: self.trash_start_str.clone(),
+                                    end_time: timestamp_str.to_string(),
+                                    key_level: None,
+                                    par_time_secs: None,
+                                    timed: None,
+                                    plus_level: None,
+                                    time_remaining_secs: None,
+                                    affixes: Vec::new(),
+                                    encounter_type: "trash".to_string(),
+                                    boss_encounters: Vec::new(),
+                                    players,
+                                    deaths: self.trash_tracker.deaths_until(timestamp_secs),
+                                    near_deaths: self.trash_tracker.near_deaths.clone(),
+                                    wipes: Vec::new(),
+                                    duration_excluding_wipes_secs: None,
+                                    segments: Vec::new(),
+                                    top_count_pulls: Vec::new(),
+                                    buff_uptimes: self.trash_tracker.build_buff_uptimes(trash_duration),
+                                    key_buff_uptimes: HashMap::new(),
+                                    enemy_breakdowns: self.trash_tracker.build_enemy_breakdowns(&[]),
+                                    enemy_damage_taken: self.trash_tracker.build_enemy_damage_taken(),
+                                    mechanics: self.trash_tracker.build_mechanics(),
+                                    priority_targets: self.trash_tracker.build_priority_targets(),
+                                    boss_trash_split: Vec::new(),
+                                    pad_targets: self.trash_tracker.build_pad_targets(),
+                                    healer_cd_coverage: self.trash_tracker.build_healer_cd_coverage(),
+                                    tank_swaps: self.trash_tracker.tank_swaps.clone(),
+                                    cooldown_usage: Vec::new(),
+                                    add_timeline: None,
+                                    practice: None,
+                                    arena: None,
+                                    healing_report: None,
+                                    depletion_report: None,
+                                    efficiency_formula: None,
+                                    insights: Vec::new(),
+                                    custom_metrics,
+                                    avoidable_damage: self.trash_tracker.build_avoidable_damage(),
+                                    avoidable_hits: self.trash_tracker.avoidable_hits.clone(),
+                                    defensive_coverage: self.trash_tracker.build_defensive_coverage(),
+                                    pre_pull: None,
+                                    post_pull: None,
+                                    notes: None,
+                                    range: None,
+                                    boss_hp_pct: None,
+                                    boss_max_hp: None,
+                                    phases: Vec::new(),
+                                    time_bucketed_player_damage: HashMap::new(),
+                                    ability_damage_over_time: HashMap::new(),
+                                    boss_hp_timeline: Vec::new(),
+                                    replay_timeline: Vec::new(),
+                                    boss_positions: Vec::new(),
+                                    enemy_casts: Vec::new(),
+                                    mechanic_markers: Vec::new(),
+                                    replay_maps: Vec::new(),
+                                    raw_ability_events: Vec::new(),
+                                });
+                            }
+                        }
+                        self.in_dungeon_zone = false;
+                        self.trash_tracker = EventTracker::with_options(&self.options);
+                        self.trash_has_combat = false;
+                        self.trash_area = None;
+                    }
+                }
+            }
+            "MAP_CHANGE" => {
+                // MAP_CHANGE,uiMapID,"uiMapName",x0,x1,y0,y1
+                let map_id: u64 = fields.get(1).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let map_name = fields.get(2).map(|s| unquote(s)).unwrap_or_default();
+                if self.options.trash_area_names {
+                    self.current_area = self.options.area_names.get(&map_id).cloned()
+                        .or_else(|| Some(map_name.clone()))
+                        .filter(|name| !name.is_empty());
+                }
+                let coord = |i: usize| fields.get(i).and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
+                let (x0, x1, y0, y1) = (coord(3), coord(4), coord(5), coord(6));
+                self.map_changes.push((timestamp_secs, ReplayMap {
+                    offset_secs: 0.0,
+                    map_id,
+                    name: map_name,
+                    x_min: x0.min(x1),
+                    x_max: x0.max(x1),
+                    y_min: y0.min(y1),
+                    y_max: y0.max(y1),
+                }));
+            }
+            "CHALLENGE_MODE_START" => {
+                self.flush_combat_segment();
+                // Start tracking a whole M+ key as one encounter
+                self.in_key = true;
+                self.key_start_time = Some(timestamp_secs);
+                self.key_start_str = timestamp_str.to_string();
+                self.key_start_offset = self.line_offset;
+                self.key_name = fields.get(1).map(|s| unquote(s)).unwrap_or_default();
+                self.key_zone_id = fields.get(2).and_then(|s| s.parse().ok()).unwrap_or(0);
+                self.key_challenge_mode_id = fields.get(3).and_then(|s| s.parse().ok()).unwrap_or(0);
+                self.key_level = fields.get(4).and_then(|s| s.parse().ok()).unwrap_or(0);
+
+                // Parse affixes from bracket-enclosed list like [9,10,147]
+                self.key_affixes = Vec::new();
+                if let Some(affix_str) = fields.get(5) {
+                    let cleaned = affix_str.trim_matches(|c| c == '[' || c == ']');
+                    for part in cleaned.split(',') {
+                        if let Ok(v) = part.trim().parse::<u32>() {
+                            self.key_affixes.push(v);
+                        }
+                    }
+                }
+
+                self.key_boss_encounters.clear();
+                self.key_segments.clear();
+                self.tracker = EventTracker::with_options(&self.options);
+                self.segment_tracker = EventTracker::with_options(&self.options);
+                self.segment_start_secs = timestamp_secs;
+                self.segment_area = None;
+                self.segment_start_str = timestamp_str.to_string();
+                self.segment_boss_count = 0;
+            }
+            "CHALLENGE_MODE_END" => {
+                if self.in_key {
+                    let success = fields.get(2).and_then(|s| s.parse::<u32>().ok()).unwrap_or(0) == 1;
+                    let end_time = timestamp_secs;
+                    let duration = end_time - self.key_start_time.unwrap_or(end_time);
+                    // Official key timer (ms, includes death penalties) at field 4; fall back to wall-clock duration
+                    let completion_secs = fields.get(4)
+                        .and_then(|s| s.parse::<f64>().ok())
+                        .filter(|ms| *ms > 0.0)
+                        .map(|ms| ms / 1000.0)
+                        .unwrap_or(duration);
+                    let par_time = game_data::par_time_secs(self.key_zone_id, self.key_challenge_mode_id);
+                    let plus_level = par_time.map(|par| if success { keystone_upgrade(completion_secs, par) } else { 0 });
+
+                    // Flush any trailing trash segment after the last boss
+                    let trailing_duration = timestamp_secs - self.segment_start_secs;
+                    if trailing_duration > 0.5 {
+                        let trailing_players = self.segment_tracker.build_player_summaries(trailing_duration);
+                        self.key_segments.push(KeySegment {
+                            segment_type: "trash".to_string(),
+                            name: trash_segment_name(self.segment_area.as_deref(), self.segment_boss_count + 1, &self.key_segments),
+                            index: self.key_segments.len(),
+                            duration_secs: trailing_duration,
+                            start_time: self.segment_start_str.clone(),
+                            end_time: timestamp_str.to_string(),
+                            players: trailing_players,
+                            deaths: self.segment_tracker.deaths_until(timestamp_secs),
+                            near_deaths: self.segment_tracker.near_deaths.clone(),
+                            buff_uptimes: self.segment_tracker.build_buff_uptimes(trailing_duration),
+                            key_buff_uptimes: HashMap::new(),
+                            enemy_breakdowns: self.segment_tracker.build_enemy_breakdowns(
+                                &self.key_boss_encounters.iter().map(|b| b.name.clone()).collect::<Vec<_>>()
+                            ),
+                            enemy_damage_taken: self.segment_tracker.build_enemy_damage_taken(),
+                            priority_targets: self.segment_tracker.build_priority_targets(),
+                            phases: Vec::new(),
+                            boss_hp_timeline: Vec::new(),
+                            pulls: self.segment_tracker.build_pulls(self.segment_start_secs),
+                            pre_pull: None,
+                            post_pull: None,
+                        });
+                    }
+
+                    let players = self.tracker.build_player_summaries(duration);
+                    let cooldown_usage = self.tracker.build_cooldown_usage(self.key_start_time.unwrap_or(end_time), end_time, &players);
+                    let wipes = self.tracker.build_wipes(self.key_start_time.unwrap_or(end_time), &self.key_segments);
+                    let run_back_total: f64 = wipes.iter().filter_map(|w| w.run_back_secs).sum();
+
+                    let custom_metrics = self.tracker.build_custom_metrics(self.key_zone_id, &players);
+                    self.encounters.push(EncounterSummary {
+                        index: self.encounters.len(),
+                        stable_id: String::new(),
+                        encounter_id: self.key_zone_id,
+                        name: format!("{} +{}", self.key_name, self.key_level),
+                        difficulty_id: 8, // Mythic Keystone
+                        difficulty_name: format!("Mythic +{}", self.key_level),
+                        instance_name: None,
+                        season: None,
+                        instance_icon: None,
+                        group_size: 5,
+                        success,
+                        duration_secs: duration,
+                        start_time: self.key_start_str.clone(),
+                        end_time: timestamp_str.to_string(),
+                        key_level: Some(self.key_level),
+                        par_time_secs: par_time,
+                        timed: Some(plus_level.map(|p| p > 0).unwrap_or(success)),
+                        plus_level,
+                        time_remaining_secs: par_time.map(|par| par - completion_secs),
+                        affixes: self.key_affixes.iter().map(|id| affix_from_id(*id)).collect(),
+                        encounter_type: "mythic_plus".to_string(),
+                        boss_encounters: self.key_boss_encounters.clone(),
+                        players,
+                        deaths: self.tracker.deaths_until(timestamp_secs),
+                        near_deaths: self.tracker.near_deaths.clone(),
+                        wipes,
+                        duration_excluding_wipes_secs: Some((duration - run_back_total).max(0.0)),
+                        segments: self.key_segments.clone(),
+                        top_count_pulls: top_count_pulls(&self.key_segments),
+                        buff_uptimes: self.tracker.build_buff_uptimes(duration),
+                        key_buff_uptimes: HashMap::new(),
+                        enemy_breakdowns: self.tracker.build_enemy_breakdowns(
+                            &self.key_boss_encounters.iter().map(|b| b.name.clone()).collect::<Vec<_>>()
+                        ),
+                        enemy_damage_taken: self.tracker.build_enemy_damage_taken(),
+                        mechanics: self.tracker.build_mechanics(),
+                        priority_targets: self.tracker.build_priority_targets(),
+                        boss_trash_split: Vec::new(),
+                        pad_targets: self.tracker.build_pad_targets(),
+                        healer_cd_coverage: self.tracker.build_healer_cd_coverage(),
+                        tank_swaps: self.tracker.tank_swaps.clone(),
+                        cooldown_usage,
+                        add_timeline: None,
+                        practice: None,
+                        arena: None,
+                        healing_report: None,
+                        depletion_report: None,
+                        efficiency_formula: None,
+                        insights: Vec::new(),
+                        custom_metrics,
+                        avoidable_damage: self.tracker.build_avoidable_damage(),
+                        avoidable_hits: self.tracker.avoidable_hits.clone(),
+                        defensive_coverage: self.tracker.build_defensive_coverage(),
+                        pre_pull: None,
+                        post_pull: None,
+                        notes: None,
+                        range: self.offsets_known.then(|| EncounterRange {
+                            start: self.key_start_offset,
+                            end: self.next_line_offset,
+                            zone: self.zone_changes.last().cloned(),
+                            ..Default::default()
+                        }),
+                        boss_hp_pct: None,
+                        boss_max_hp: None,
+                        phases: Vec::new(),
+                        time_bucketed_player_damage: HashMap::new(),
+                        ability_damage_over_time: HashMap::new(),
+                        boss_hp_timeline: Vec::new(),
+                        replay_timeline: self.tracker.build_hp_timeline(duration),
+                        boss_positions: self.tracker.boss_position_events.clone(),
+                        enemy_casts: self.tracker.build_enemy_casts(self.key_start_time.unwrap_or(end_time), end_time),
+                        mechanic_markers: self.tracker.build_mechanic_markers(self.key_start_time.unwrap_or(end_time), end_time),
+                        replay_maps: self.replay_maps(self.key_start_time.unwrap_or(end_time), end_time),
+                        raw_ability_events: self.tracker.player_ability_events.iter()
+                            .map(|(ts, g, sid, sn, sc, amt, tgt)| ((*ts - self.key_start_time.unwrap_or(0.0)).max(0.0), g.clone(), *sid, sn.clone(), *sc, *amt, tgt.clone()))
+                            .collect(),
+                    });
+                    if let Some(key) = self.encounters.last_mut().filter(|e| e.timed == Some(false)) {
+                        key.depletion_report = Some(compare::depletion_report(key));
+                    }
+
+                    self.in_key = false;
+                    self.in_boss = false;
+                }
+            }
+            "ENCOUNTER_START" => {
+                self.flush_combat_segment();
+                let enc_id = fields.get(1).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let enc_name = fields.get(2).map(|s| unquote(s)).unwrap_or_default();
+                let difficulty = fields.get(3).and_then(|s| s.parse::<u32>().ok()).unwrap_or(0);
+                let group_size = fields.get(4).and_then(|s| s.parse::<u32>().ok()).unwrap_or(0);
+
+                if self.in_key {
+                    // Precasts and pre-pull DoTs landed in the trash segment before the boss
+                    self.key_boss_pre_pull = self.segment_tracker.build_pull_window(
+                        timestamp_secs - self.options.pre_pull_secs, timestamp_secs);
+
+                    // Boss within a M+ key — flush current trash segment first
+                    let trash_duration = timestamp_secs - self.segment_start_secs;
+                    if trash_duration > 0.5 {
+                        let trash_players = self.segment_tracker.build_player_summaries(trash_duration);
+                        self.key_segments.push(KeySegment {
+                            segment_type: "trash".to_string(),
+                            name: trash_segment_name(self.segment_area.as_deref(), self.segment_boss_count + 1, &self.key_segments),
+                            index: self.key_segments.len(),
+                            duration_secs: trash_duration,
+                            start_time: self.segment_start_str.clone(),
+                            end_time: timestamp_str.to_string(),
+                            players: trash_players,
+                            deaths: self.segment_tracker.deaths_until(timestamp_secs),
+                            near_deaths: self.segment_tracker.near_deaths.clone(),
+                            buff_uptimes: self.segment_tracker.build_buff_uptimes(trash_duration),
+                            key_buff_uptimes: HashMap::new(),
+                            enemy_breakdowns: self.segment_tracker.build_enemy_breakdowns(
+                                &self.key_boss_encounters.iter().map(|b| b.name.clone()).collect::<Vec<_>>()
+                            ),
+                            enemy_damage_taken: self.segment_tracker.build_enemy_damage_taken(),
+                            priority_targets: self.segment_tracker.build_priority_targets(),
+                            phases: Vec::new(),
+                            boss_hp_timeline: Vec::new(),
+                            pulls: self.segment_tracker.build_pulls(self.segment_start_secs),
+                            pre_pull: None,
+                            post_pull: None,
+                        });
+                    }
+                    self.segment_tracker = EventTracker::new_with_context(&self.tracker);
+                    self.segment_tracker.boss_encounter_name = enc_name.clone();
+                    self.segment_tracker.encounter_start_secs = timestamp_secs;
+                    self.segment_start_secs = timestamp_secs;
+                    self.segment_area = None;
+                    self.segment_start_str = timestamp_str.to_string();
+
+                    // Track the boss sub-encounter
+                    self.in_boss = true;
+                    self.boss_start_time = Some(timestamp_secs);
+                    self.boss_start_str = timestamp_str.to_string();
+                    self.boss_name = enc_name;
+                    self.boss_id = enc_id;
+                } else {
+                    // Precasts and pre-pull DoTs landed before the encounter began
+                    self.standalone_pre_pull = self.trash_tracker.build_pull_window(
+                        timestamp_secs - self.options.pre_pull_secs, timestamp_secs);
+
+                    // Flush accumulated trash as an encounter for dungeons
+                    if self.trash_has_combat && self.in_dungeon_zone {
+                        let trash_duration = timestamp_secs - self.trash_start_secs;
+                        if trash_duration > 1.0 {
+                            let players = self.trash_tracker.build_player_summaries(trash_duration);
+                            let custom_metrics = self.trash_tracker.build_custom_metrics(0, &players);
+                            self.encounters.push(EncounterSummary {
+                                index: self.encounters.len(),
+                                stable_id: String::new(),
+                                encounter_id: 0,
+                                name: self.trash_area.clone().unwrap_or_else(|| "Trash".to_string()),
+                                difficulty_id: self.trash_difficulty,
+                                difficulty_name: difficulty_name(self.trash_difficulty),
+                                instance_name: None,
+                                season: None,
+                                instance_icon: None,
+                                group_size: self.trash_group_size,
+                                success: true,
+                                duration_secs: trash_duration,
+                                start_time: self.trash_start_str.clone(),
+                                end_time: timestamp_str.to_string(),
+                                key_level: None,
+                                par_time_secs: None,
+                                timed: None,
+                                plus_level: None,
+                                time_remaining_secs: None,
+                                affixes: Vec::new(),
+                                encounter_type: "trash".to_string(),
+                                boss_encounters: Vec::new(),
+                                players,
+                                deaths: self.trash_tracker.deaths_until(timestamp_secs),
+                                near_deaths: self.trash_tracker.near_deaths.clone(),
+                                wipes: Vec::new(),
+                                duration_excluding_wipes_secs: None,
+                                segments: Vec::new(),
+                                top_count_pulls: Vec::new(),
+                                buff_uptimes: self.trash_tracker.build_buff_uptimes(trash_duration),
+                                key_buff_uptimes: HashMap::new(),
+                                enemy_breakdowns: self.trash_tracker.build_enemy_breakdowns(&[]),
+                                enemy_damage_taken: self.trash_tracker.build_enemy_damage_taken(),
+                                mechanics: self.trash_tracker.build_mechanics(),
+                                priority_targets: self.trash_tracker.build_priority_targets(),
+                                boss_trash_split: Vec::new(),
+                                pad_targets: self.trash_tracker.build_pad_targets(),
+                                healer_cd_coverage: self.trash_tracker.build_healer_cd_coverage(),
+                                tank_swaps: self.trash_tracker.tank_swaps.clone(),
+                                cooldown_usage: Vec::new(),
+                                add_timeline: None,
+                                practice: None,
+                                arena: None,
+                                healing_report: None,
+                                depletion_report: None,
+                                efficiency_formula: None,
+                                insights: Vec::new(),
+                                custom_metrics,
+                                avoidable_damage: self.trash_tracker.build_avoidable_damage(),
+                                avoidable_hits: self.trash_tracker.avoidable_hits.clone(),
+                                defensive_coverage: self.trash_tracker.build_defensive_coverage(),
+                                pre_pull: None,
+                                post_pull: None,
+                                notes: None,
+                                range: None,
+                                boss_hp_pct: None,
+                                boss_max_hp: None,
+                                phases: Vec::new(),
+                                time_bucketed_player_damage: HashMap::new(),
+                                ability_damage_over_time: HashMap::new(),
+                                boss_hp_timeline: Vec::new(),
+                                replay_timeline: Vec::new(),
+                                boss_positions: Vec::new(),
+                                enemy_casts: Vec::new(),
+                                mechanic_markers: Vec::new(),
+                                replay_maps: Vec::new(),
+                                raw_ability_events: Vec::new(),
+                            });
+                        }
+                    }
+                    self.trash_tracker = EventTracker::new_with_context(&self.tracker);
+                    self.trash_has_combat = false;
+                    self.trash_area = None;
+
+                    // Standalone boss encounter (raid or non-M+ dungeon)
+                    self.standalone_boss = true;
+                    self.standalone_start_time = Some(timestamp_secs);
+                    self.standalone_start_str = timestamp_str.to_string();
+                    self.standalone_name = enc_name;
+                    self.standalone_id = enc_id;
+                    self.standalone_difficulty = difficulty;
+                    self.standalone_group_size = group_size;
+                    self.standalone_tracker = EventTracker::new_with_context(&self.trash_tracker);
+                    self.standalone_tracker.boss_encounter_name = self.standalone_name.clone();
+                    self.standalone_tracker.encounter_start_secs = timestamp_secs;
+                    self.standalone_range = self.offsets_known.then(|| EncounterRange {
+                        // Start early enough to include the pre-pull window
+                        start: self.recent_line_offsets.front().map(|(_, offset)| *offset).unwrap_or(self.line_offset),
+                        end: self.line_offset,
+                        player_names: self.standalone_tracker.player_names.clone(),
+                        player_specs: self.standalone_tracker.player_specs.clone(),
+                        player_talents: self.standalone_tracker.player_talents.clone(),
+                        pet_owners: self.standalone_tracker.pet_owners.clone(),
+                        zone: self.zone_changes.last().cloned(),
+                    });
+                }
+            }
+            "ENCOUNTER_PHASE_CHANGE" => {
+                // Blizzard's native phase change event
+                // Format: ENCOUNTER_PHASE_CHANGE,phaseNumber
+                let phase_id: u32 = fields.get(1).and_then(|s| s.parse().ok()).unwrap_or(1);
+                if self.standalone_boss {
+                    self.standalone_tracker.current_phase = phase_id;
+                    self.standalone_tracker.phase_transitions.push((timestamp_secs, phase_id));
+                }
+                if self.in_key && self.in_boss {
+                    self.segment_tracker.current_phase = phase_id;
+                    self.segment_tracker.phase_transitions.push((timestamp_secs, phase_id));
+                    self.tracker.current_phase = phase_id;
+                    self.tracker.phase_transitions.push((timestamp_secs, phase_id));
+                }
+            }
+            "ENCOUNTER_END" => {
+                let success = fields.get(5).and_then(|s| s.parse::<u32>().ok()).unwrap_or(0) == 1;
+
+                if self.in_key && self.in_boss {
+                    // Boss ended within M+ — log it as a sub-encounter
+                    let boss_duration = timestamp_secs - self.boss_start_time.unwrap_or(timestamp_secs);
+                    self.key_boss_encounters.push(BossEncounter {
+                        name: self.boss_name.clone(),
+                        encounter_id: self.boss_id,
+                        success,
+                        duration_secs: boss_duration,
+                        start_time: self.boss_start_str.clone(),
+                        end_time: timestamp_str.to_string(),
+                    });
+
+                    // Flush boss segment
+                    let boss_seg_duration = timestamp_secs - self.segment_start_secs;
+                    let boss_players = self.segment_tracker.build_player_summaries(boss_seg_duration);
+                    self.segment_boss_count += 1;
+                    self.key_segments.push(KeySegment {
+                        segment_type: "boss".to_string(),
+                        name: self.boss_name.clone(),
+                        index: self.key_segments.len(),
+                        duration_secs: boss_seg_duration,
+                        start_time: self.segment_start_str.clone(),
+                        end_time: timestamp_str.to_string(),
+                        players: boss_players,
+                        deaths: self.segment_tracker.deaths_until(timestamp_secs),
+                        near_deaths: self.segment_tracker.near_deaths.clone(),
+                        buff_uptimes: self.segment_tracker.build_buff_uptimes(boss_seg_duration),
+                        key_buff_uptimes: HashMap::new(),
+                        enemy_breakdowns: self.segment_tracker.build_enemy_breakdowns(std::slice::from_ref(&self.boss_name)),
+                        enemy_damage_taken: self.segment_tracker.build_enemy_damage_taken(),
+                        priority_targets: self.segment_tracker.build_priority_targets(),
+                        phases: self.segment_tracker.build_phase_breakdowns(
+                            self.boss_start_time.unwrap_or(timestamp_secs),
+                            timestamp_secs,
+                            std::slice::from_ref(&self.boss_name)
+                        ),
+                        boss_hp_timeline: self.segment_tracker.boss_hp_timeline.clone(),
+                        pulls: Vec::new(),
+                        pre_pull: self.key_boss_pre_pull.take(),
+                        post_pull: None,
+                    });
+                    self.pending_key_post_pull = Some((self.key_segments.len() - 1, timestamp_secs));
+                    self.segment_tracker = EventTracker::new_with_context(&self.tracker);
+                    self.segment_start_secs = timestamp_secs;
+                    self.segment_area = None;
+                    self.segment_start_str = timestamp_str.to_string();
+                    // Phases only apply to the boss that just ended
+                    self.tracker.current_phase = 1;
+
+                    self.in_boss = false;
+                } else if self.standalone_boss {
+                    // Standalone boss encounter ended
+                    let duration = timestamp_secs - self.standalone_start_time.unwrap_or(timestamp_secs);
+                    // Skip pulls shorter than 10 seconds
+                    if duration < 10.0 {
+                        self.standalone_boss = false;
+                        self.standalone_tracker = EventTracker::new_with_context(&self.standalone_tracker);
+                        self.trash_tracker = EventTracker::new_with_context(&self.standalone_tracker);
+                        self.trash_has_combat = false;
+                        self.trash_area = None;
+                        self.trash_start_secs = timestamp_secs;
+                        self.trash_start_str = timestamp_str.to_string();
+                    } else {
+                    let players = self.standalone_tracker.build_player_summaries(duration);
+                    let healing_report = (self.standalone_group_size > 5)
+                        .then(|| self.standalone_tracker.build_healing_report(&players));
+                    let cooldown_usage = self.standalone_tracker.build_cooldown_usage(
+                        self.standalone_tracker.encounter_start_secs, timestamp_secs, &players);
+                    let add_timeline = self.standalone_tracker.build_add_timeline(
+                        self.standalone_tracker.encounter_start_secs, std::slice::from_ref(&self.standalone_name));
+
+                    let custom_metrics = self.standalone_tracker.build_custom_metrics(self.standalone_id, &players);
+                    self.encounters.push(EncounterSummary {
+                        index: self.encounters.len(),
+                        stable_id: String::new(),
+                        encounter_id: self.standalone_id,
+                        name: self.standalone_name.clone(),
+                        difficulty_id: self.standalone_difficulty,
+                        difficulty_name: difficulty_name(self.standalone_difficulty),
+                        instance_name: None,
+                        season: None,
+                        instance_icon: None,
+                        group_size: self.standalone_group_size,
+                        success,
+                        duration_secs: duration,
+                        start_time: self.standalone_start_str.clone(),
+                        end_time: timestamp_str.to_string(),
+                        key_level: None,
+                        par_time_secs: None,
+                        timed: None,
+                        plus_level: None,
+                        time_remaining_secs: None,
+                        affixes: Vec::new(),
+                        encounter_type: "boss".to_string(),
+                        boss_encounters: Vec::new(),
+                        players,
+                        deaths: self.standalone_tracker.deaths_until(timestamp_secs),
+                        near_deaths: self.standalone_tracker.near_deaths.clone(),
+                        wipes: Vec::new(),
+                        duration_excluding_wipes_secs: None,
+                        segments: Vec::new(),
+                        top_count_pulls: Vec::new(),
+                        buff_uptimes: self.standalone_tracker.build_buff_uptimes(duration),
+                        key_buff_uptimes: HashMap::new(),
+                        enemy_breakdowns: self.standalone_tracker.build_enemy_breakdowns(
+                            std::slice::from_ref(&self.standalone_name)
+                        ),
+                        enemy_damage_taken: self.standalone_tracker.build_enemy_damage_taken(),
+                        mechanics: self.standalone_tracker.build_mechanics(),
+                        priority_targets: self.standalone_tracker.build_priority_targets(),
+                        boss_trash_split: Vec::new(),
+                        pad_targets: self.standalone_tracker.build_pad_targets(),
+                        healer_cd_coverage: self.standalone_tracker.build_healer_cd_coverage(),
+                        tank_swaps: self.standalone_tracker.tank_swaps.clone(),
+                        cooldown_usage,
+                        add_timeline,
+                        practice: None,
+                        arena: None,
+                        healing_report,
+                        depletion_report: None,
+                        efficiency_formula: None,
+                        insights: Vec::new(),
+                        custom_metrics,
+                        avoidable_damage: self.standalone_tracker.build_avoidable_damage(),
+                        avoidable_hits: self.standalone_tracker.avoidable_hits.clone(),
+                        defensive_coverage: self.standalone_tracker.build_defensive_coverage(),
+                        pre_pull: self.standalone_pre_pull.take(),
+                        post_pull: None,
+                        notes: None,
+                        range: self.standalone_range.take().map(|r| EncounterRange { end: self.next_line_offset, ..r }),
+                        boss_hp_pct: self.standalone_tracker.last_creature_hp.get(&self.standalone_name)
+                            .map(|(cur, max)| if *max > 0 { (*cur as f64 / *max as f64 * 100.0) } else { 0.0 }),
+                        boss_max_hp: self.standalone_tracker.last_creature_hp.get(&self.standalone_name)
+                            .map(|(_, max)| *max),
+                        phases: self.standalone_tracker.build_phase_breakdowns(
+                            self.standalone_start_time.unwrap_or(timestamp_secs),
+                            timestamp_secs,
+                            std::slice::from_ref(&self.standalone_name)
+                        ),
+                        time_bucketed_player_damage: self.standalone_tracker.time_bucketed_player_damage(),
+                        ability_damage_over_time: self.standalone_tracker.ability_damage_over_time(),
+                        boss_hp_timeline: self.standalone_tracker.boss_hp_timeline.clone(),
+                        replay_timeline: self.standalone_tracker.build_hp_timeline(duration),
+                        boss_positions: self.standalone_tracker.boss_position_events.clone(),
+                        enemy_casts: self.standalone_tracker.build_enemy_casts(self.standalone_tracker.encounter_start_secs, timestamp_secs),
+                        mechanic_markers: self.standalone_tracker.build_mechanic_markers(self.standalone_tracker.encounter_start_secs, timestamp_secs),
+                        replay_maps: self.replay_maps(self.standalone_start_time.unwrap_or(timestamp_secs), timestamp_secs),
+                        raw_ability_events: {
+                            let start = self.standalone_tracker.encounter_start_secs;
+                            self.standalone_tracker.player_ability_events.iter()
+                                .map(|(ts, g, sid, sn, sc, amt, tgt)| ((*ts - start).max(0.0), g.clone(), *sid, sn.clone(), *sc, *amt, tgt.clone()))
+                                .collect()
+                        },
+                    });
+
+                    self.standalone_boss = false;
+                    self.pending_post_pull = Some((self.encounters.len() - 1, timestamp_secs));
+                    // Start a new trash segment after this boss
+                    self.trash_tracker = EventTracker::new_with_context(&self.tracker);
+                    self.trash_start_secs = timestamp_secs;
+                    self.trash_start_str = timestamp_str.to_string();
+                    self.trash_has_combat = false;
+                    self.trash_area = None;
+                    self.trash_difficulty = self.standalone_difficulty;
+                    self.trash_group_size = self.standalone_group_size;
+                    }
+                }
+            }
+            _ => {
+                if let (Some(guid), Some(name)) = (fields.get(1), fields.get(2)) {
+                    if guid::is_player(guid) && !self.zone_players.contains_key(*guid) {
+                        self.zone_players.insert(guid.to_string(), unquote(name));
+                    }
+                }
+                // Process combat events
+                let event = LogEvent { timestamp: timestamp_str, timestamp_secs, event_type, fields, combat: None };
+                let is_combat = matches!(event_type,
+                    "SPELL_DAMAGE" | "SPELL_PERIODIC_DAMAGE" | "RANGE_DAMAGE" |
+                    "SWING_DAMAGE" | "SPELL_HEAL" | "SPELL_PERIODIC_HEAL" |
+                    "SPELL_AURA_APPLIED" | "SPELL_AURA_REMOVED" | "SPELL_AURA_REFRESH" |
+                    "UNIT_DIED" | "SPELL_CAST_SUCCESS" | "SPELL_DAMAGE_SUPPORT"
+                );
+                if self.in_arena {
+                    // The first death decides the round, so it ends there at the earliest
+                    let round_death = event_type == "UNIT_DIED" && self.round_start.is_some()
+                        && fields.get(5).is_some_and(|guid| self.round_teams.contains_key(*guid));
+                    if round_death || self.is_pvp_exchange(event_type, fields) {
+                        if self.round_start.is_none() {
+                            self.round_start = Some((timestamp_secs, timestamp_str.to_string()));
+                        }
+                        self.round_last_hostile_secs = timestamp_secs;
+                        self.round_last_hostile_str = timestamp_str.to_string();
+                    }
+                    let round_start_secs = self.round_start.as_ref().map_or(timestamp_secs, |(secs, _)| *secs);
+                    process_combat_event(&event,
+                        self.arena_start_secs, &self.field_map, &mut self.arena_tracker);
+                    process_combat_event(&event,
+                        round_start_secs, &self.field_map, &mut self.round_tracker);
+                } else if self.in_key {
+                    // A trash segment is named after where its first pull happened
+                    if is_combat && !self.in_boss && self.segment_area.is_none() {
+                        self.segment_area = self.current_area.clone();
+                    }
+                    // During M+ key — track everything for the overall key AND the current segment
+                    process_combat_event(&event,
+                        self.key_start_time.unwrap_or(0.0), &self.field_map, &mut self.tracker);
+                    process_combat_event(&event,
+                        self.segment_start_secs, &self.field_map, &mut self.segment_tracker);
+                } else if self.standalone_boss {
+                    // During standalone boss encounter
+                    process_combat_event(&event,
+                        self.standalone_start_time.unwrap_or(0.0), &self.field_map, &mut self.standalone_tracker);
+                } else if !self.in_key {
+                    // Between encounters (trash) — track if it looks like combat
+                    if self.trash_start_secs == 0.0 {
+                        self.trash_start_secs = timestamp_secs;
+                        self.trash_start_str = timestamp_str.to_string();
+                    }
+                    if is_combat {
+                        if !self.trash_has_combat {
+                            self.trash_area = self.current_area.clone();
+                        }
+                        self.trash_has_combat = true;
+                    }
+                    process_combat_event(&event,
+                        self.trash_start_secs, &self.field_map, &mut self.trash_tracker);
+                    // Dungeon trash is already covered by the trash encounters
+                    if !self.in_dungeon_zone {
+                        self.track_combat_segment(&event);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Outside encounters and dungeons, fights are cut into generic combat segments: one
+    /// opens on the first hostile exchange between the group and an NPC and closes once
+    /// there has been none for `COMBAT_SEGMENT_GAP_SECS`
+    fn track_combat_segment(&mut self, event: &LogEvent) {
+        let LogEvent { timestamp: timestamp_str, timestamp_secs, event_type, fields, .. } = *event;
+        if self.combat_start_secs.is_some() && timestamp_secs - self.combat_last_hostile_secs > COMBAT_SEGMENT_GAP_SECS {
+            self.flush_combat_segment();
+        }
+        let hostile = self.is_hostile_exchange(event_type, fields);
+        if hostile && self.combat_start_secs.is_none() {
+            self.combat_tracker = EventTracker::new_with_context(&self.trash_tracker);
+            self.combat_tracker.encounter_start_secs = timestamp_secs;
+            self.combat_tracker.handlers.practice.enabled = true;
+            self.combat_start_secs = Some(timestamp_secs);
+            self.combat_start_str = timestamp_str.to_string();
+        }
+        let Some(start_secs) = self.combat_start_secs else {
+            return;
+        };
+        if hostile {
+            self.combat_last_hostile_secs = timestamp_secs;
+            self.combat_last_hostile_str = timestamp_str.to_string();
+        }
+        process_combat_event(event,
+            start_secs, &self.field_map, &mut self.combat_tracker);
+    }
+
+    /// Damage between a player (or a player's pet) and an NPC
+    fn is_hostile_exchange(&self, event_type: &str, fields: &[&str]) -> bool {
+        if !event_type.ends_with("_DAMAGE") && event_type != "SWING_DAMAGE_LANDED" {
+            return false;
+        }
+        let source = fields.get(1).copied().unwrap_or_default();
+        let dest = fields.get(5).copied().unwrap_or_default();
+        let in_group = |guid: &str| guid::is_player(guid) || self.trash_tracker.resolve_owner(guid).is_some();
+        (in_group(source) && guid::is_npc(dest) && !in_group(dest))
+            || (in_group(dest) && guid::is_npc(source) && !in_group(source))
+    }
+
+    /// Emit the open combat segment, if any, as a "combat" encounter named after the enemy
+    /// that took the most damage
+    fn flush_combat_segment(&mut self) {
+        let Some(start_secs) = self.combat_start_secs.take() else {
+            return;
+        };
+        let tracker = std::mem::replace(&mut self.combat_tracker, EventTracker::with_options(&self.options));
+        let duration = self.combat_last_hostile_secs - start_secs;
+        if duration <= 1.0 {
+            return;
+        }
+        let players = tracker.build_player_summaries(duration);
+        if players.is_empty() {
+            return;
+        }
+        let enemy_breakdowns = tracker.build_enemy_breakdowns(&[]);
+        let name = enemy_breakdowns.first()
+            .map(|e| e.target_name.clone())
+            .unwrap_or_else(|| "Combat".to_string());
+        // Nothing but training dummies: a practice session
+        let is_practice = !enemy_breakdowns.is_empty() && enemy_breakdowns.iter().all(|e| {
+            game_data::is_training_dummy(e.npc_id, &e.target_name)
+                || e.npc_id.is_some_and(|id| self.options.training_dummy_npc_ids.contains(&id))
+        });
+        let end_secs = self.combat_last_hostile_secs;
+        let practice = is_practice.then(|| tracker.build_practice(start_secs, end_secs, &players));
+        let difficulty_id = self.zone_changes.last().map(|z| z.difficulty_id).unwrap_or(0);
+        let custom_metrics = tracker.build_custom_metrics(0, &players);
+        self.encounters.push(EncounterSummary {
+            index: self.encounters.len(),
+            stable_id: String::new(),
+            encounter_id: 0,
+            name,
+            difficulty_id,
+            difficulty_name: difficulty_name(difficulty_id),
+            instance_name: None,
+            season: None,
+            instance_icon: None,
+            group_size: players.len() as u32,
+            success: true,
+            duration_secs: duration,
+            start_time: self.combat_start_str.clone(),
+            end_time: self.combat_last_hostile_str.clone(),
+            key_level: None,
+            par_time_secs: None,
+            timed: None,
+            plus_level: None,
+            time_remaining_secs: None,
+            affixes: Vec::new(),
+            encounter_type: if is_practice { "practice" } else { "combat" }.to_string(),
+            boss_encounters: Vec::new(),
+            players,
+            deaths: tracker.deaths_until(end_secs),
+            near_deaths: tracker.near_deaths.clone(),
+            wipes: Vec::new(),
+            duration_excluding_wipes_secs: None,
+            segments: Vec::new(),
+            top_count_pulls: Vec::new(),
+            buff_uptimes: tracker.build_buff_uptimes(duration),
+            key_buff_uptimes: HashMap::new(),
+            enemy_breakdowns,
+            enemy_damage_taken: tracker.build_enemy_damage_taken(),
+            mechanics: tracker.build_mechanics(),
+            priority_targets: tracker.build_priority_targets(),
+            boss_trash_split: Vec::new(),
+            pad_targets: tracker.build_pad_targets(),
+            healer_cd_coverage: tracker.build_healer_cd_coverage(),
+            tank_swaps: tracker.tank_swaps.clone(),
+            cooldown_usage: Vec::new(),
+            add_timeline: None,
+            practice,
+            arena: None,
+            healing_report: None,
+            depletion_report: None,
+            efficiency_formula: None,
+            insights: Vec::new(),
+            custom_metrics,
+            avoidable_damage: tracker.build_avoidable_damage(),
+            avoidable_hits: tracker.avoidable_hits.clone(),
+            defensive_coverage: tracker.build_defensive_coverage(),
+            pre_pull: None,
+            post_pull: None,
+            notes: None,
+            range: None,
+            boss_hp_pct: None,
+            boss_max_hp: None,
+            phases: Vec::new(),
+            time_bucketed_player_damage: tracker.time_bucketed_player_damage(),
+            ability_damage_over_time: tracker.ability_damage_over_time(),
+            boss_hp_timeline: Vec::new(),
+            replay_timeline: Vec::new(),
+            boss_positions: Vec::new(),
+            enemy_casts: Vec::new(),
+            mechanic_markers: Vec::new(),
+            replay_maps: Vec::new(),
+            raw_ability_events: Vec::new(),
+        });
+    }
+
+    /// Damage, crowd control or a cast from a player (or pet) on one arena team at a player
+    /// on the other
+    fn is_pvp_exchange(&self, event_type: &str, fields: &[&str]) -> bool {
+        let hostile_event = event_type.ends_with("_DAMAGE") || event_type.ends_with("_MISSED")
+            || matches!(event_type, "SWING_DAMAGE_LANDED" | "SPELL_AURA_APPLIED" | "SPELL_CAST_SUCCESS");
+        if !hostile_event {
+            return false;
+        }
+        let source = fields.get(1).copied().unwrap_or_default();
+        let source = self.round_tracker.resolve_owner(source).unwrap_or_else(|| source.to_string());
+        let dest = fields.get(5).copied().unwrap_or_default();
+        matches!((self.round_teams.get(&source), self.round_teams.get(dest)), (Some(a), Some(b)) if a != b)
+    }
+
+    /// Close the current arena round. The team that gets the first kill wins it; a round
+    /// nobody dies in is a draw.
+    fn flush_arena_round(&mut self) {
+        let Some((start_secs, start_str)) = self.round_start.take() else {
+            return;
+        };
+        let duration = (self.round_last_hostile_secs - start_secs).max(1.0);
+        let first_death = self.round_tracker.death_events.first();
+        let winning_team = first_death
+            .and_then(|d| self.round_teams.get(&d.player_guid))
+            .map(|team| if *team == 0 { 1 } else { 0 });
+        let first_death = first_death.map(|d| d.player_name.clone());
+        let summaries = self.round_tracker.build_player_summaries(duration);
+        let mut players: Vec<ArenaRoundPlayer> = self.round_teams.iter()
+            .map(|(guid, &team)| {
+                let summary = summaries.iter().find(|p| p.guid == *guid);
+                ArenaRoundPlayer {
+                    guid: guid.clone(),
+                    name: summary.map(|p| p.name.clone())
+                        .or_else(|| self.round_tracker.player_names.get(guid).cloned())
+                        .unwrap_or_else(|| guid.clone()),
+                    team,
+                    damage_done: summary.map_or(0, |p| p.damage_done),
+                    healing_done: summary.map_or(0, |p| p.healing_done),
+                    cc_applied: self.round_tracker.handlers.crowd_control.applied_by(guid),
+                }
+            })
+            .collect();
+        players.sort_by(|a, b| a.team.cmp(&b.team)
+            .then(b.damage_done.cmp(&a.damage_done))
+            .then_with(|| a.name.cmp(&b.name)));
+        self.arena_rounds.push(ArenaRound {
+            number: self.arena_rounds.len() as u32 + 1,
+            start_time: start_str,
+            duration_secs: duration,
+            winning_team,
+            first_death,
+            players,
+        });
+    }
+
+    /// Emit the arena match in progress as an "arena" encounter, named after the arena.
+    /// Its duration is the time spent fighting, without the gaps between rounds.
+    fn flush_arena_match(&mut self, winning_team: Option<u32>, end_str: &str) {
+        self.flush_arena_round();
+        self.in_arena = false;
+        self.round_teams.clear();
+        let tracker = std::mem::replace(&mut self.arena_tracker, EventTracker::with_options(&self.options));
+        let mut rounds = std::mem::take(&mut self.arena_rounds);
+        if rounds.is_empty() {
+            return;
+        }
+        // A 2v2/3v3 match is one round, won by whoever won the match
+        if let ([round], Some(team)) = (rounds.as_mut_slice(), winning_team) {
+            round.winning_team = Some(team);
+        }
+        let duration: f64 = rounds.iter().map(|r| r.duration_secs).sum();
+        let players = tracker.build_player_summaries(duration);
+        let zone = self.zone_changes.last();
+        let name = zone.map(|z| z.zone_name.clone()).unwrap_or_else(|| self.arena_bracket.clone());
+        let end_secs = parse_timestamp_to_secs(end_str);
+        let custom_metrics = tracker.build_custom_metrics(0, &players);
+        self.encounters.push(EncounterSummary {
+            index: self.encounters.len(),
+            stable_id: String::new(),
+            encounter_id: 0,
+            name,
+            difficulty_id: zone.map(|z| z.difficulty_id).unwrap_or(0),
+            difficulty_name: self.arena_bracket.clone(),
+            instance_name: None,
+            season: None,
+            instance_icon: None,
+            group_size: players.len() as u32,
+            success: winning_team == Some(self.arena_log_team),
+            duration_secs: duration,
+            start_time: self.arena_start_str.clone(),
+            end_time: end_str.to_string(),
+            key_level: None,
+            par_time_secs: None,
+            timed: None,
+            plus_level: None,
+            time_remaining_secs: None,
+            affixes: Vec::new(),
+            encounter_type: "arena".to_string(),
+            boss_encounters: Vec::new(),
+            players,
+            deaths: tracker.deaths_until(end_secs),
+            near_deaths: tracker.near_deaths.clone(),
+            wipes: Vec::new(),
+            duration_excluding_wipes_secs: None,
+            segments: Vec::new(),
+            top_count_pulls: Vec::new(),
+            buff_uptimes: tracker.build_buff_uptimes(duration),
+            key_buff_uptimes: HashMap::new(),
+            enemy_breakdowns: Vec::new(),
+            enemy_damage_taken: Vec::new(),
+            mechanics: Vec::new(),
+            priority_targets: Vec::new(),
+            boss_trash_split: Vec::new(),
+            pad_targets: Vec::new(),
+            healer_cd_coverage: Vec::new(),
+            tank_swaps: Vec::new(),
+            cooldown_usage: Vec::new(),
+            add_timeline: None,
+            practice: None,
+            arena: Some(ArenaMatch {
+                bracket: self.arena_bracket.clone(),
+                log_team: self.arena_log_team,
+                winning_team,
+                rounds,
+            }),
+            healing_report: None,
+            depletion_report: None,
+            efficiency_formula: None,
+            insights: Vec::new(),
+            custom_metrics,
+            avoidable_damage: AvoidableDamage::default(),
+            avoidable_hits: Vec::new(),
+            defensive_coverage: Vec::new(),
+            pre_pull: None,
+            post_pull: None,
+            notes: None,
+            range: None,
+            boss_hp_pct: None,
+            boss_max_hp: None,
+            phases: Vec::new(),
+            time_bucketed_player_damage: HashMap::new(),
+            ability_damage_over_time: HashMap::new(),
+            boss_hp_timeline: Vec::new(),
+            replay_timeline: Vec::new(),
+            boss_positions: Vec::new(),
+            enemy_casts: Vec::new(),
+            mechanic_markers: Vec::new(),
+            replay_maps: Vec::new(),
+            raw_ability_events: Vec::new(),
+        });
+    }
+
+    /// Maps in use between `start_secs` and `end_secs`: the one current at the start, then
+    /// each change, with offsets from the start
+    fn replay_maps(&self, start_secs: f64, end_secs: f64) -> Vec<ReplayMap> {
+        let from = self.map_changes.iter().rposition(|(ts, _)| *ts <= start_secs).unwrap_or(0);
+        self.map_changes[from..].iter()
+            .take_while(|(ts, _)| *ts <= end_secs)
+            .map(|(ts, map)| ReplayMap { offset_secs: (ts - start_secs).max(0.0), ..map.clone() })
+            .collect()
+    }
+
+    /// Pace of the Mythic+ key in progress, if any (for live mode)
+    pub fn key_pace(&self) -> Option<KeyPace> {
+        let key_start = self.key_start_time.filter(|_| self.in_key)?;
+        let elapsed_secs = (self.tracker.last_event_secs - key_start).max(0.0);
+        let par_time_secs = game_data::par_time_secs(self.key_zone_id, self.key_challenge_mode_id);
+        let boss_count = game_data::dungeon_boss_count(self.key_zone_id, self.key_challenge_mode_id);
+        let boss_kill_secs: Vec<f64> = self.key_boss_encounters.iter()
+            .filter(|b| b.success)
+            .map(|b| parse_timestamp_to_secs(&b.end_time) - key_start)
+            .collect();
+        let bosses_killed = boss_kill_secs.len() as u32;
+        let expected_bosses = par_time_secs.zip(boss_count)
+            .map(|(par, count)| (elapsed_secs / par * count as f64).min(count as f64));
+        let projected_finish_secs = match (boss_kill_secs.last(), boss_count) {
+            (Some(&last_kill), Some(count)) => Some(last_kill / bosses_killed as f64 * count as f64),
+            _ => None,
+        };
+        let on_pace = par_time_secs.map(|par| match projected_finish_secs {
+            Some(projected) => projected <= par && elapsed_secs <= par,
+            None => elapsed_secs <= par,
+        });
+        Some(KeyPace {
+            dungeon_name: self.key_name.clone(),
+            key_level: self.key_level,
+            elapsed_secs,
+            par_time_secs,
+            time_remaining_secs: par_time_secs.map(|par| par - elapsed_secs),
+            bosses_killed,
+            boss_count,
+            boss_kill_secs,
+            expected_bosses,
+            projected_finish_secs,
+            on_pace,
+        })
+    }
+
+    /// False once a header said advanced combat logging was off
+    pub fn advanced_logging(&self) -> bool {
+        self.advanced_logging
+    }
+
+    /// Byte offset a new parser can read from to rebuild the fight in progress: the start of
+    /// the key or boss encounter under way, otherwise the end of the lines read so far. Only
+    /// known for lines fed with `process_line_at`.
+    pub fn resume_offset(&self) -> u64 {
+        if self.in_key {
+            return self.key_start_offset;
+        }
+        match &self.standalone_range {
+            Some(range) if self.standalone_boss => range.start,
+            _ => self.next_line_offset,
+        }
+    }
+
+    /// Each group member's personal view of the fight in progress (for live mode): the key,
+    /// boss encounter or arena match under way, otherwise the trash since the last encounter
+    pub fn live_players(&self) -> Vec<LivePlayer> {
+        let tracker = if self.in_arena {
+            &self.arena_tracker
+        } else if self.in_key {
+            &self.tracker
+        } else if self.standalone_boss {
+            &self.standalone_tracker
+        } else {
+            &self.trash_tracker
+        };
+        tracker.build_live_players()
+    }
+
+    /// Post-process all encounters seen so far and build the summary
+    pub fn finish(mut self) -> CombatLogSummary {
+        let profiler = self.profiler.take();
+        let summary_start = Instant::now();
+
+        self.flush_post_pull(self.next_line_offset);
+        self.flush_key_post_pull();
+        self.flush_combat_segment();
+        if self.in_arena {
+            let end_str = self.round_last_hostile_str.clone();
+            self.flush_arena_match(None, &end_str);
+        }
+
+        // Flush any trailing trash at the end of the log (disabled for now)
+        // Trash encounters disabled for raids
+
+        // Post-processing: aggregate consecutive non-M+ dungeon bosses into compound "dungeon" encounters
+        self.encounters = aggregate_dungeon_runs(self.encounters, &self.zone_changes);
+        enrich_instance_metadata(&mut self.encounters, &self.zone_changes);
+        fill_key_buff_uptimes(&mut self.encounters);
+        fill_boss_trash_split(&mut self.encounters);
+        for enc in &mut self.encounters {
+            enc.stable_id = stable_encounter_id(&enc.start_time, enc.encounter_id);
+            enc.insights = insights::evaluate(enc, insights::rules());
+            efficiency::annotate(enc);
+            schools::annotate(enc);
+        }
+        if !self.zone_changes.is_empty() {
+            self.zone_rosters.push(std::mem::take(&mut self.zone_players));
+        }
+        let mut unknown_events: Vec<UnknownEvent> = self.unknown_events.into_values().collect();
+        unknown_events.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.event_type.cmp(&b.event_type)));
+        if !unknown_events.is_empty() {
+            tracing::info!(types = unknown_events.len(), "log has unknown event types: {}",
+                unknown_events.iter().map(|u| format!("{} ({})", u.event_type, u.count)).collect::<Vec<_>>().join(", "));
+        }
+        let extensions = self.handlers.iter()
+            .filter_map(|h| h.report().map(|report| (h.name().to_string(), report)))
+            .collect();
+        let instance_visits = build_instance_visits(&self.zone_changes, &self.zone_rosters,
+            &self.encounters, self.last_event_secs, &self.last_event_str);
+
+        CombatLogSummary {
+            filename: self.filename,
+            log_version: self.log_version,
+            build_version: self.build_version,
+            log_format: self.log_format.name().to_string(),
+            advanced_logging: self.advanced_logging,
+            encounters: self.encounters,
+            zone_changes: self.zone_changes,
+            instance_visits,
+            stats: ParseStats { lines: self.lines_seen, unknown_events },
+            extensions,
+            parse_error: self.parse_error,
+            profile: profiler.map(|p| p.into_profile(summary_start.elapsed())),
+        }
+    }
+}
+
+/// Parse-time accumulators, only allocated when profiling
+#[derive(Default)]
+struct Profiler {
+    io: Duration,
+    tokenize: Duration,
+    lines: u64,
+    /// event_type -> (count, handling time)
+    events: HashMap<String, (u64, Duration)>,
+}
+
+impl Profiler {
+    fn record(&mut self, event_type: &str, tokenize: Duration, handle: Duration) {
+        self.lines += 1;
+        self.tokenize += tokenize;
+        let entry = self.events.entry(event_type.to_string()).or_insert((0, Duration::ZERO));
+        entry.0 += 1;
+        entry.1 += handle;
+    }
+
+    fn into_profile(self, summary: Duration) -> ParseProfile {
+        let mut events: Vec<EventTiming> = self.events.into_iter()
+            .map(|(event_type, (count, time))| EventTiming { event_type, count, secs: time.as_secs_f64() })
+            .collect();
+        events.sort_by(|a, b| b.secs.partial_cmp(&a.secs).unwrap_or(std::cmp::Ordering::Equal));
+        let handle_secs: f64 = events.iter().map(|e| e.secs).sum();
+        ParseProfile {
+            total_secs: self.io.as_secs_f64() + self.tokenize.as_secs_f64() + handle_secs + summary.as_secs_f64(),
+            io_secs: self.io.as_secs_f64(),
+            tokenize_secs: self.tokenize.as_secs_f64(),
+            handle_secs,
+            summary_secs: summary.as_secs_f64(),
+            lines: self.lines,
+            events,
+        }
+    }
+}
+
+/// Aggregate consecutive non-M+ dungeon encounters (group_size <= 5) in the same zone/difficulty
+/// into a single compound encounter with segments, matching the M+ data shape.
+/// Now also includes interleaved "trash" encounters with real player data.
+fn aggregate_dungeon_runs(encounters: Vec<EncounterSummary>, zone_changes: &[ZoneChange]) -> Vec<EncounterSummary> {
+    let zc: Vec<&ZoneChange> = {
+        let mut sorted: Vec<&ZoneChange> = zone_changes.iter().collect();
+        sorted.sort_by_key(|z| z.timestamp.clone());
+        sorted
+    };
+
+    fn zone_for(enc: &EncounterSummary, zc: &[&ZoneChange]) -> String {
+        let mut zone = String::new();
+        for z in zc {
+            if z.timestamp <= enc.start_time {
+                zone = z.zone_name.clone();
+            } else {
+                break;
+            }
+        }
+        zone
+    }
+
+    // Identify runs: consecutive "boss" + "trash" encounters with group_size <= 5,
+    // same zone, same difficulty, within 15 min gap.
+    // A run must have at least 2 boss encounters.
+    let mut runs: Vec<Vec<usize>> = Vec::new(); // indices into encounters (boss + trash)
+    let mut current_run: Vec<usize> = Vec::new();
+    let mut boss_count_in_run = 0;
+    let mut last_boss_idx: Option<usize> = None;
+
+    for (i, enc) in encounters.iter().enumerate() {
+        let is_dungeon = enc.group_size <= 5 && (enc.encounter_type == "boss" || enc.encounter_type == "trash");
+        if !is_dungeon {
+            if boss_count_in_run >= 2 {
+                runs.push(current_run.clone());
+            }
+            current_run.clear();
+            boss_count_in_run = 0;
+            last_boss_idx = None;
+            continue;
+        }
+
+        if enc.encounter_type == "trash" {
+            // Include trash in the current run if we have at least one boss already
+            if !current_run.is_empty() {
+                current_run.push(i);
+            }
+            continue;
+        }
+
+        // It's a boss encounter
+        if current_run.is_empty() {
+            // Look backwards for a preceding trash encounter to include
+            if i > 0 && encounters[i - 1].encounter_type == "trash" && encounters[i - 1].group_size <= 5 {
+                current_run.push(i - 1);
+            }
+            current_run.push(i);
+            boss_count_in_run = 1;
+            last_boss_idx = Some(i);
+        } else {
+            let prev_boss = &encounters[last_boss_idx.unwrap()];
+            let gap_secs = parse_timestamp_to_secs(&enc.start_time) - parse_timestamp_to_secs(&prev_boss.end_time);
+            let same_zone = zone_for(enc, &zc) == zone_for(prev_boss, &zc);
+            let same_diff = enc.difficulty_id == prev_boss.difficulty_id;
+            if same_zone && same_diff && gap_secs < 900.0 {
+                current_run.push(i);
+                boss_count_in_run += 1;
+                last_boss_idx = Some(i);
+            } else {
+                if boss_count_in_run >= 2 {
+                    runs.push(current_run.clone());
+                }
+                current_run = vec![i];
+                boss_count_in_run = 1;
+                last_boss_idx = Some(i);
+            }
+        }
+    }
+    if boss_count_in_run >= 2 {
+        runs.push(current_run);
+    }
+
+    if runs.is_empty() {
+        return encounters;
+    }
+
+    // Build a set of all indices that belong to a run
+    let mut run_indices: std::collections::HashSet<usize> = std::collections::HashSet::new();
+    for run in &runs {
+        for &idx in run {
+            run_indices.insert(idx);
+        }
+    }
+
+    // Build result: replace each run with a single compound encounter
+    let mut result: Vec<EncounterSummary> = Vec::new();
+    let mut next_run = 0;
+
+    let mut i = 0;
+    while i < encounters.len() {
+        if run_indices.contains(&i) && next_run < runs.len() && runs[next_run].contains(&i) {
+            let run = &runs[next_run];
+            let run_all: Vec<&EncounterSummary> = run.iter().map(|&idx| &encounters[idx]).collect();
+            let run_bosses: Vec<&EncounterSummary> = run_all.iter().filter(|e| e.encounter_type == "boss").copied().collect();
+
+            let zone_name = zone_for(run_bosses[0], &zc);
+            let diff_name = run_bosses[0].difficulty_name.clone();
+            let diff_id = run_bosses[0].difficulty_id;
+            let group_size = run_bosses[0].group_size;
+            let start_time = run_all.first().unwrap().start_time.clone();
+            let end_time = run_all.last().unwrap().end_time.clone();
+            let all_success = run_bosses.iter().all(|e| e.success);
+
+            // Build boss_encounters from boss encounters only
+            let boss_encounters: Vec<BossEncounter> = run_bosses.iter().map(|e| BossEncounter {
+                name: e.name.clone(),
+                encounter_id: e.encounter_id,
+                success: e.success,
+                duration_secs: e.duration_secs,
+                start_time: e.start_time.clone(),
+                end_time: e.end_time.clone(),
+            }).collect();
+
+            // Build segments from all encounters (boss + trash) in order
+            let mut segments: Vec<KeySegment> = Vec::new();
+            let mut total_duration = 0.0f64;
+            let mut trash_count = 0;
+
+            for &enc in &run_all {
+                total_duration += enc.duration_secs;
+                if enc.encounter_type == "trash" {
+                    trash_count += 1;
+                    segments.push(KeySegment {
+                        segment_type: "trash".to_string(),
+                        name: trash_segment_name((enc.name != "Trash").then_some(enc.name.as_str()), trash_count, &segments),
+                        index: segments.len(),
+                        duration_secs: enc.duration_secs,
+                        start_time: enc.start_time.clone(),
+                        end_time: enc.end_time.clone(),
+                        players: enc.players.clone(),
+                        deaths: enc.deaths.clone(),
+                        near_deaths: enc.near_deaths.clone(),
+                        buff_uptimes: enc.buff_uptimes.clone(),
+                        key_buff_uptimes: HashMap::new(),
+                        enemy_breakdowns: enc.enemy_breakdowns.clone(),
+                        enemy_damage_taken: enc.enemy_damage_taken.clone(),
+                        priority_targets: enc.priority_targets.clone(),
+                        phases: Vec::new(),
+                        boss_hp_timeline: Vec::new(),
+                        pulls: Vec::new(),
+                        pre_pull: None,
+                        post_pull: None,
+                    });
+                } else {
+                    segments.push(KeySegment {
+                        segment_type: "boss".to_string(),
+                        name: enc.name.clone(),
+                        index: segments.len(),
+                        duration_secs: enc.duration_secs,
+                        start_time: enc.start_time.clone(),
+                        end_time: enc.end_time.clone(),
+                        players: enc.players.clone(),
+                        deaths: enc.deaths.clone(),
+                        near_deaths: enc.near_deaths.clone(),
+                        buff_uptimes: enc.buff_uptimes.clone(),
+                        key_buff_uptimes: HashMap::new(),
+                        enemy_breakdowns: enc.enemy_breakdowns.clone(),
+                        enemy_damage_taken: enc.enemy_damage_taken.clone(),
+                        priority_targets: enc.priority_targets.clone(),
+                        phases: enc.phases.clone(),
+                        boss_hp_timeline: enc.boss_hp_timeline.clone(),
+                        pulls: Vec::new(),
+                        pre_pull: enc.pre_pull.clone(),
+                        post_pull: enc.post_pull.clone(),
+                    });
+                }
+            }
+
+            // Merge player summaries across ALL encounters (boss + trash)
+            let all_player_sources: Vec<Vec<PlayerSummary>> = run_all.iter().map(|e| e.players.clone()).collect();
+            let merged_players = merge_player_summaries(&all_player_sources, total_duration);
+
+            // Merge deaths, moving their times from each encounter's start to the run's
+            let run_start_secs = parse_timestamp_to_secs(&start_time);
+            let since_run_start = |e: &EncounterSummary| to_millis(parse_timestamp_to_secs(&e.start_time) - run_start_secs);
+            let total_deaths: Vec<DeathEvent> = run_all.iter()
+                .flat_map(|e| e.deaths.iter().map(move |d| DeathEvent {
+                    time_ms: d.time_ms + since_run_start(e),
+                    recap: d.recap.iter().map(|r| RecapEvent { time_ms: r.time_ms + since_run_start(e), ..r.clone() }).collect(),
+                    ..d.clone()
+                }))
+                .collect();
+            let total_near_deaths: Vec<NearDeath> = run_all.iter()
+                .flat_map(|e| e.near_deaths.iter().map(move |n| NearDeath { time_ms: n.time_ms + since_run_start(e), ..n.clone() }))
+                .collect();
+
+            // Merge buff uptimes
+            let mut merged_buffs: HashMap<String, Vec<BuffUptime>> = HashMap::new();
+            for e in &run_all {
+                for (guid, uptimes) in &e.buff_uptimes {
+                    merged_buffs.entry(guid.clone()).or_default().extend(uptimes.clone());
+                }
+            }
+
+            // Merge enemy breakdowns
+            let merged_enemies: Vec<EnemyBreakdown> = run_all.iter().flat_map(|e| e.enemy_breakdowns.clone()).collect();
+            let merged_enemy_damage_taken: Vec<EnemyDamageTaken> = run_all.iter().flat_map(|e| e.enemy_damage_taken.clone()).collect();
+            let mut merged_mechanics: Vec<Mechanic> = Vec::new();
+            for e in &run_all {
+                merge_mechanics(&mut merged_mechanics, &e.mechanics);
+            }
+            let mut merged_priority_targets: Vec<PriorityTarget> = Vec::new();
+            for e in &run_all {
+                merge_priority_targets(&mut merged_priority_targets, &e.priority_targets);
+            }
+            let mut merged_custom_metrics: Vec<CustomMetric> = Vec::new();
+            for e in &run_all {
+                merge_custom_metrics(&mut merged_custom_metrics, &e.custom_metrics);
+            }
+
+            let compound = EncounterSummary {
+                index: result.len(),
+                stable_id: String::new(),
+                encounter_id: run_bosses[0].encounter_id,
+                name: zone_name.clone(),
+                difficulty_id: diff_id,
+                difficulty_name: diff_name,
+                instance_name: None,
+                season: None,
+                instance_icon: None,
+                group_size,
+                success: all_success,
+                duration_secs: total_duration,
+                start_time,
+                end_time,
+                key_level: None,
+                par_time_secs: None,
+                timed: None,
+                plus_level: None,
+                time_remaining_secs: None,
+                affixes: Vec::new(),
+                encounter_type: "dungeon".to_string(),
+                boss_encounters,
+                players: merged_players,
+                deaths: total_deaths,
+                near_deaths: total_near_deaths,
+                wipes: Vec::new(),
+                duration_excluding_wipes_secs: None,
+                segments,
+                top_count_pulls: Vec::new(),
+                buff_uptimes: merged_buffs,
+                key_buff_uptimes: HashMap::new(),
+                enemy_breakdowns: merged_enemies,
+                enemy_damage_taken: merged_enemy_damage_taken,
+                mechanics: merged_mechanics,
+                priority_targets: merged_priority_targets,
+                boss_trash_split: Vec::new(),
+                pad_targets: run_all.iter().flat_map(|e| e.pad_targets.clone()).collect(),
+                // Offsets are per boss fight, so the run doesn't get a combined list
+                healer_cd_coverage: Vec::new(),
+                tank_swaps: Vec::new(),
+                cooldown_usage: Vec::new(),
+                add_timeline: None,
+                practice: None,
+                arena: None,
+                healing_report: None,
+                depletion_report: None,
+                efficiency_formula: None,
+                insights: Vec::new(),
+                custom_metrics: merged_custom_metrics,
+                avoidable_damage: AvoidableDamage::default(),
+                avoidable_hits: Vec::new(),
+                defensive_coverage: Vec::new(),
+                pre_pull: run_all.first().and_then(|e| e.pre_pull.clone()),
+                post_pull: run_all.last().and_then(|e| e.post_pull.clone()),
+                notes: None,
+                range: None,
+                boss_hp_pct: None,
+                boss_max_hp: None,
+                phases: Vec::new(),
+                time_bucketed_player_damage: HashMap::new(),
+                ability_damage_over_time: HashMap::new(),
+                boss_hp_timeline: Vec::new(),
+                replay_timeline: Vec::new(),
+                boss_positions: Vec::new(),
+                enemy_casts: Vec::new(),
+                mechanic_markers: Vec::new(),
+                replay_maps: Vec::new(),
+                raw_ability_events: Vec::new(),
+            };
+
+            result.push(compound);
+            // Skip past all encounters in this run
+            i = *run.last().unwrap() + 1;
+            next_run += 1;
+        } else if run_indices.contains(&i) {
+            // This encounter is part of a run but we haven't reached its start yet — skip
+            i += 1;
+        } else {
+            let mut enc = encounters[i].clone();
+            enc.index = result.len();
+            result.push(enc);
+            i += 1;
+        }
+    }
+
+    result
+}
+
+/// Fill the curated key_buff_uptimes for every encounter and its segments
+fn fill_key_buff_uptimes(encounters: &mut [EncounterSummary]) {
+    for enc in encounters.iter_mut() {
+        enc.key_buff_uptimes = key_buffs_only(&enc.buff_uptimes);
+        for seg in enc.segments.iter_mut() {
+            seg.key_buff_uptimes = key_buffs_only(&seg.buff_uptimes);
+        }
+    }
+}
+
+/// Per-player boss vs trash damage for M+ keys, summed from the key's segments
+fn fill_boss_trash_split(encounters: &mut [EncounterSummary]) {
+    for enc in encounters.iter_mut().filter(|e| e.encounter_type == "mythic_plus") {
+        let mut split: HashMap<String, BossTrashSplit> = HashMap::new();
+        let mut boss_secs = 0.0;
+        let mut trash_secs = 0.0;
+        for seg in &enc.segments {
+            let is_boss = seg.segment_type == "boss";
+            if is_boss {
+                boss_secs += seg.duration_secs;
+            } else {
+                trash_secs += seg.duration_secs;
+            }
+            for p in &seg.players {
+                let entry = split.entry(p.guid.clone()).or_insert_with(|| BossTrashSplit {
+                    guid: p.guid.clone(),
+                    name: p.name.clone(),
+                    boss_damage: 0,
+                    trash_damage: 0,
+                    boss_dps: 0.0,
+                    trash_dps: 0.0,
+                });
+                if is_boss {
+                    entry.boss_damage += p.damage_done;
+                } else {
+                    entry.trash_damage += p.damage_done;
+                }
+            }
+        }
+        // Keep the encounter's player order
+        enc.boss_trash_split = enc.players.iter()
+            .filter_map(|p| split.remove(&p.guid))
+            .map(|mut s| {
+                s.boss_dps = if boss_secs > 0.0 { s.boss_damage as f64 / boss_secs } else { 0.0 };
+                s.trash_dps = if trash_secs > 0.0 { s.trash_damage as f64 / trash_secs } else { 0.0 };
+                s
+            })
+            .collect();
+    }
+}
+
+/// Keep only the curated tracked buffs (raid buffs, food, lust, ...) from a full uptime map
+fn key_buffs_only(all: &HashMap<String, Vec<BuffUptime>>) -> HashMap<String, Vec<BuffUptime>> {
+    all.iter()
+        .map(|(guid, uptimes)| {
+            let tracked: Vec<BuffUptime> = uptimes.iter()
+                .filter(|b| game_data::is_tracked_buff(b.spell_id, &b.spell_name))
+                .cloned()
+                .collect();
+            (guid.clone(), tracked)
+        })
+        .filter(|(_, tracked)| !tracked.is_empty())
+        .collect()
+}
+
+/// A trip out of an instance shorter than this that ends back in it (a corpse run through
+/// the entrance) doesn't end the visit
+const VISIT_REENTRY_SECS: f64 = 300.0;
+
+/// Time in one zone between loading screens (or merged re-entries of it)
+struct ZoneStay<'a> {
+    zone: &'a ZoneChange,
+    start: f64,
+    end: f64,
+    end_str: &'a str,
+    /// guid -> name
+    roster: HashMap<&'a str, &'a str>,
+}
+
+/// Slice the log into instance visits at zone changes. Re-entering the same instance, right
+/// away or after a short trip outside, continues the visit; open-world stretches are dropped.
+fn build_instance_visits(
+    zone_changes: &[ZoneChange],
+    rosters: &[HashMap<String, String>],
+    encounters: &[EncounterSummary],
+    log_end_secs: f64,
+    log_end_str: &str,
+) -> Vec<InstanceVisit> {
+    let mut stays: Vec<ZoneStay> = Vec::new();
+    for (i, zone) in zone_changes.iter().enumerate() {
+        let start = parse_timestamp_to_secs(&zone.timestamp);
+        let (end, end_str) = match zone_changes.get(i + 1) {
+            Some(next) => (parse_timestamp_to_secs(&next.timestamp), next.timestamp.as_str()),
+            None => (log_end_secs.max(start), log_end_str),
+        };
+        let roster: HashMap<&str, &str> = rosters.get(i).into_iter().flatten()
+            .map(|(guid, name)| (guid.as_str(), name.as_str()))
+            .collect();
+        let same_instance = |stay: &ZoneStay| stay.zone.zone_id == zone.zone_id && stay.zone.difficulty_id == zone.difficulty_id;
+        let n = stays.len();
+        if stays.last().is_some_and(same_instance) {
+            // Reloaded into the same instance
+        } else if n >= 2 && same_instance(&stays[n - 2]) && stays[n - 1].zone.difficulty_id == 0
+            && stays[n - 1].end - stays[n - 1].start < VISIT_REENTRY_SECS
+        {
+            // Back from a short trip outside; whoever was seen outside isn't kept
+            stays.pop();
+        } else {
+            stays.push(ZoneStay { zone, start, end, end_str, roster });
+            continue;
+        }
+        let last = stays.last_mut().unwrap();
+        last.end = end;
+        last.end_str = end_str;
+        last.roster.extend(roster);
+    }
+
+    stays.into_iter()
+        .filter(|stay| stay.zone.difficulty_id != 0)
+        .map(|stay| {
+            let mut players: Vec<String> = stay.roster.into_values().map(str::to_string).collect();
+            players.sort();
+            players.dedup();
+            let encounter_ids = encounters.iter()
+                .filter(|enc| {
+                    let enc_start = parse_timestamp_to_secs(&enc.start_time);
+                    enc_start >= stay.start && enc_start < stay.end
+                })
+                .map(|enc| enc.stable_id.clone())
+                .collect();
+            InstanceVisit {
+                zone_id: stay.zone.zone_id,
+                zone_name: stay.zone.zone_name.clone(),
+                difficulty_id: stay.zone.difficulty_id,
+                difficulty_name: difficulty_name(stay.zone.difficulty_id),
+                start_time: stay.zone.timestamp.clone(),
+                end_time: stay.end_str.to_string(),
+                duration_secs: stay.end - stay.start,
+                players,
+                encounter_ids,
+            }
+        })
+        .collect()
+}
+
+/// Fill instance name/season/icon from the bundled tables: boss encounter ID first
+/// (M+ keys use the instance ID), falling back to the zone the encounter started in.
+fn enrich_instance_metadata(encounters: &mut [EncounterSummary], zone_changes: &[ZoneChange]) {
+    for enc in encounters.iter_mut() {
+        let start_secs = parse_timestamp_to_secs(&enc.start_time);
+        let zone = zone_changes.iter()
+            .rev()
+            .find(|z| parse_timestamp_to_secs(&z.timestamp) <= start_secs);
+
+        let info = if enc.encounter_type == "mythic_plus" {
+            game_data::instance_for_zone(enc.encounter_id)
+        } else {
+            game_data::instance_for_encounter(enc.encounter_id)
+        }.or_else(|| zone.and_then(|z| game_data::instance_for_zone(z.zone_id)));
+
+        if let Some(info) = info {
+            enc.instance_name = Some(info.name.to_string());
+            enc.season = Some(info.season.to_string());
+            enc.instance_icon = Some(game_data::icon_url(info.icon));
+        } else if let Some(z) = zone {
+            // Unknown instance — the log's own zone name is still better than nothing
+            enc.instance_name = Some(z.zone_name.clone());
+        }
+    }
+}
+
+/// How far `uses` (seconds into a fight of `duration_secs`) of a cooldown drifted from its
+/// base cooldown, and how many uses the fight had room for
+fn cooldown_drift(base_cooldown_secs: f64, uses: &[f64], duration_secs: f64) -> CooldownDrift {
+    let drifts: Vec<f64> = uses.windows(2).map(|w| (w[1] - w[0] - base_cooldown_secs).max(0.0)).collect();
+    let potential_uses = if base_cooldown_secs > 0.0 { (duration_secs.max(0.0) / base_cooldown_secs).floor() as u32 + 1 } else { uses.len() as u32 };
+    CooldownDrift {
+        base_cooldown_secs,
+        average_drift_secs: (!drifts.is_empty()).then(|| drifts.iter().sum::<f64>() / drifts.len() as f64),
+        potential_uses,
+        lost_uses: potential_uses.saturating_sub(uses.len() as u32),
+    }
+}
+
+/// Merge player summaries from multiple encounters into one, re-computing DPS/HPS.
+fn merge_player_summaries(sources: &[Vec<PlayerSummary>], total_duration: f64) -> Vec<PlayerSummary> {
+    let mut map: HashMap<String, PlayerSummary> = HashMap::new();
+
+    for players in sources {
+        for p in players {
+            let entry = map.entry(p.guid.clone()).or_insert_with(|| PlayerSummary {
+                guid: p.guid.clone(),
+                name: p.name.clone(),
+                class_name: p.class_name.clone(),
+                spec_name: p.spec_name.clone(),
+                role: p.role.clone(),
+                spec_inferred: p.spec_inferred,
+                talents: p.talents.clone(),
+                damage_done: 0,
+                healing_done: 0,
+                damage_taken: 0,
+                deaths: 0,
+                near_deaths: 0,
+                saves: 0,
+                deaths_prevented: 0,
+                interrupts: 0,
+                dispels: 0,
+                dps: 0.0,
+                hps: 0.0,
+                damage_rank: 0,
+                damage_share: 0.0,
+                healing_rank: 0,
+                healing_share: 0.0,
+                abilities: Vec::new(),
+                heal_abilities: Vec::new(),
+                damage_taken_abilities: Vec::new(),
+                // Spike offsets are relative to each source encounter, so they aren't merged
+                damage_spikes: Vec::new(),
+                cast_stats: CastStats::default(),
+                target_count_split: TargetCountSplit::default(),
+                filtered_damage: 0,
+                filtered_dps: 0.0,
+                healing_split: HealingSplit::default(),
+                crowd_control: CrowdControlReceived::default(),
+                performance_delta: None,
+                reference_dps: None,
+                efficiency: None,
+                schools: None,
+                damage_types: None,
+            });
+            entry.damage_done += p.damage_done;
+            entry.healing_done += p.healing_done;
+            entry.damage_taken += p.damage_taken;
+            entry.deaths += p.deaths;
+            entry.near_deaths += p.near_deaths;
+            entry.cast_stats.merge(&p.cast_stats);
+            entry.target_count_split.merge(&p.target_count_split);
+            entry.filtered_damage += p.filtered_damage;
+            entry.healing_split.merge(&p.healing_split);
+            entry.crowd_control.merge(&p.crowd_control);
+            entry.saves += p.saves;
+            entry.deaths_prevented += p.deaths_prevented;
+            entry.interrupts += p.interrupts;
+            entry.dispels += p.dispels;
+            // Merge abilities
+            merge_abilities(&mut entry.abilities, &p.abilities);
+            merge_abilities(&mut entry.heal_abilities, &p.heal_abilities);
+            merge_abilities(&mut entry.damage_taken_abilities, &p.damage_taken_abilities);
+            if let Some(types) = &p.damage_types {
+                entry.damage_types.get_or_insert_with(Default::default).merge(types);
+            }
+        }
+    }
+
+    let dur = if total_duration > 0.0 { total_duration } else { 1.0 };
+    let mut result: Vec<PlayerSummary> = map.into_values().map(|mut p| {
+        p.dps = p.damage_done as f64 / dur;
+        p.filtered_dps = p.filtered_damage as f64 / dur;
+        p.hps = p.healing_done as f64 / dur;
+        p.crowd_control.pct = p.crowd_control.total_secs / dur * 100.0;
+        p
+    }).collect();
+    sort_players_by_role(&mut result);
+    annotate_rankings(&mut result);
+    result
+}
+
+/// Guess roles for players without COMBATANT_INFO: big healing share -> healer,
+/// damage taken well above the group median -> tank, otherwise dps.
+fn infer_missing_roles(players: &mut [PlayerSummary]) {
+    if players.iter().all(|p| !p.role.is_empty()) {
+        return;
+    }
+    let total_healing: u64 = players.iter().map(|p| p.healing_done).sum();
+    let mut taken: Vec<u64> = players.iter().map(|p| p.damage_taken).collect();
+    taken.sort_unstable();
+    let median_taken = taken.get(taken.len() / 2).copied().unwrap_or(0);
+
+    for p in players.iter_mut().filter(|p| p.role.is_empty()) {
+        let heal_share = if total_healing > 0 { p.healing_done as f64 / total_healing as f64 } else { 0.0 };
+        p.role = if heal_share >= 0.25 && p.healing_done > p.damage_done {
+            "healer"
+        } else if median_taken > 0 && p.damage_taken as f64 >= median_taken as f64 * 2.0 {
+            "tank"
+        } else {
+            "dps"
+        }.to_string();
+    }
+}
+
+/// Group players tank -> healer -> dps, highest damage first within each role
+fn sort_players_by_role(players: &mut [PlayerSummary]) {
+    fn role_rank(role: &str) -> u8 {
+        match role {
+            "tank" => 0,
+            "healer" => 1,
+            "dps" => 2,
+            _ => 3,
+        }
+    }
+    players.sort_by(|a, b| {
+        role_rank(&a.role).cmp(&role_rank(&b.role))
+            .then(b.damage_done.cmp(&a.damage_done))
+    });
+}
+
+/// Fill in each player's rank (1 = top) and percent share of the group's damage and healing
+fn annotate_rankings(players: &mut [PlayerSummary]) {
+    let total_damage: u64 = players.iter().map(|p| p.damage_done).sum();
+    let total_healing: u64 = players.iter().map(|p| p.healing_done).sum();
+    let share = |amount: u64, total: u64| if total > 0 { amount as f64 / total as f64 * 100.0 } else { 0.0 };
+
+    let mut order: Vec<usize> = (0..players.len()).collect();
+    order.sort_by(|&a, &b| players[b].damage_done.cmp(&players[a].damage_done));
+    for (rank, &i) in order.iter().enumerate() {
+        players[i].damage_rank = rank as u32 + 1;
+        players[i].damage_share = share(players[i].damage_done, total_damage);
+    }
+    order.sort_by(|&a, &b| players[b].healing_done.cmp(&players[a].healing_done));
+    for (rank, &i) in order.iter().enumerate() {
+        players[i].healing_rank = rank as u32 + 1;
+        players[i].healing_share = share(players[i].healing_done, total_healing);
+    }
+}
+
+/// Merge ability breakdowns by spell_id, accumulating totals.
+fn merge_abilities(target: &mut Vec<AbilityBreakdown>, source: &[AbilityBreakdown]) {
+    for sa in source {
+        if let Some(existing) = target.iter_mut().find(|a| same_ability(a, sa)) {
+            existing.total_amount += sa.total_amount;
+            existing.hit_count += sa.hit_count;
+            // Merge targets
+            for st in &sa.targets {
+                if let Some(et) = existing.targets.iter_mut().find(|t| t.target_name == st.target_name) {
+                    et.amount += st.amount;
+                } else {
+                    existing.targets.push(st.clone());
+                }
+            }
+            merge_abilities(&mut existing.sub_abilities, &sa.sub_abilities);
+        } else {
+            target.push(sa.clone());
+        }
+    }
+    target.sort_by_key(|a| std::cmp::Reverse(a.total_amount));
+}
+
+/// Add `source`'s mechanics to `target`'s, matching by spell ID
+fn merge_mechanics(target: &mut Vec<Mechanic>, source: &[Mechanic]) {
+    for mechanic in source {
+        match target.iter_mut().find(|m| m.spell_id == mechanic.spell_id) {
+            Some(existing) => {
+                existing.total_damage += mechanic.total_damage;
+                existing.hits += mechanic.hits;
+                existing.average_hit = if existing.hits > 0 { existing.total_damage / existing.hits as u64 } else { 0 };
+                existing.deaths += mechanic.deaths;
+                for enemy in &mechanic.enemies {
+                    if !existing.enemies.contains(enemy) {
+                        existing.enemies.push(enemy.clone());
+                    }
+                }
+                existing.enemies.sort();
+            }
+            None => target.push(mechanic.clone()),
+        }
+    }
+    sort_mechanics(target);
+}
+
+/// Add `source`'s priority targets to `target`'s, matching by NPC ID. Player DPS stays
+/// measured over the combined lifetime of every spawn.
+fn merge_priority_targets(target: &mut Vec<PriorityTarget>, source: &[PriorityTarget]) {
+    for priority in source {
+        let Some(existing) = target.iter_mut().find(|t| t.npc_id == priority.npc_id) else {
+            target.push(priority.clone());
+            continue;
+        };
+        let lifetime = existing.avg_lifetime_secs * existing.spawns as f64 + priority.avg_lifetime_secs * priority.spawns as f64;
+        existing.spawns += priority.spawns;
+        existing.kills += priority.kills;
+        existing.avg_lifetime_secs = if existing.spawns > 0 { lifetime / existing.spawns as f64 } else { 0.0 };
+        existing.total_damage += priority.total_damage;
+        for player in &priority.players {
+            match existing.players.iter_mut().find(|p| p.guid == player.guid) {
+                Some(p) => p.damage += player.damage,
+                None => existing.players.push(player.clone()),
+            }
+        }
+        for player in existing.players.iter_mut() {
+            player.dps = if lifetime > 0.0 { player.damage as f64 / lifetime } else { 0.0 };
+        }
+        existing.players.sort_by_key(|p| std::cmp::Reverse(p.damage));
+    }
+    target.sort_by_key(|t| std::cmp::Reverse(t.total_damage));
+}
+
+/// Add up custom metrics of the same rule, e.g. over a dungeon run's bosses and trash
+fn merge_custom_metrics(target: &mut Vec<CustomMetric>, source: &[CustomMetric]) {
+    for metric in source {
+        let Some(existing) = target.iter_mut().find(|m| m.name == metric.name && m.measure == metric.measure) else {
+            target.push(metric.clone());
+            continue;
+        };
+        existing.total += metric.total;
+        for player in &metric.players {
+            match existing.players.iter_mut().find(|p| p.guid == player.guid) {
+                Some(p) => p.value += player.value,
+                None => existing.players.push(player.clone()),
+            }
+        }
+        existing.players.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.name.cmp(&b.name)));
+    }
+}
+
+fn sort_mechanics(mechanics: &mut [Mechanic]) {
+    mechanics.sort_by(|a, b| b.total_damage.cmp(&a.total_damage).then_with(|| a.spell_name.cmp(&b.spell_name)));
+}
+
+/// Rows are the same ability when they share a spell ID, whatever name each was logged
+/// with (names depend on the client's locale). Pet groups have no spell ID and match by
+/// the pet's name.
+fn same_ability(a: &AbilityBreakdown, b: &AbilityBreakdown) -> bool {
+    a.spell_id == b.spell_id && (a.spell_id != 0 || a.spell_name == b.spell_name)
+}
+
+/// A hit taken by a player, kept briefly for killing blow attribution
+struct DamageHit {
+    spell_name: String,
+    source_name: String,
+    amount: u64,
+    /// -1 when the hit was not lethal
+    overkill: i64,
+    /// Target HP after the hit (advanced log info)
+    current_hp: u64,
+    max_hp: u64,
+}
+
+/// A hit taken by a player: (ts, dest_guid, spell_id, spell_name, spell_school, amount, source_name)
+type DamageTakenEvent = (f64, String, u64, String, u32, u64, String);
+
+/// What became of a player's cast, for cast stats
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CastOutcome {
+    Started,
+    Completed,
+    Cancelled,
+    Channel,
+    ChannelClipped,
+}
+
+/// One spawn of a priority target: lifetime and damage taken from each player
+struct PrioritySpawn {
+    npc_id: u64,
+    name: String,
+    first_secs: f64,
+    last_secs: f64,
+    died: bool,
+    damage_by_player: HashMap<String, u64>,
+}
+
+/// An enemy the group fought, for deciding whether damage to it was padding and for the add
+/// timeline
+struct EnemyActivity {
+    name: String,
+    /// First and last time it traded damage with the group, or died
+    first_secs: f64,
+    last_secs: f64,
+    died: bool,
+    /// Dealt damage to a player at least once
+    threatened: bool,
+    damage_by_player: HashMap<String, u64>,
+}
+
+/// Avoidable hits taken by one player, with damage per spell (name, amount)
+#[derive(Default)]
+struct AvoidableTally<'a> {
+    hits: u32,
+    damage: u64,
+    spells: HashMap<u64, (&'a str, u64)>,
+}
+
+/// Spells outside the defensive categories are only tracked live from this base cooldown up
+const LIVE_COOLDOWN_MIN_SECS: f64 = 30.0;
+
+/// Seconds from the start of a pull its early damage is counted over, for the threat proxy
+pub const EARLY_DAMAGE_SECS: f64 = 5.0;
+
+/// Hits kept per player for killing blow attribution
+const KILLING_BLOW_CANDIDATES: usize = 5;
+
+/// An enemy that hasn't traded damage with the group for this long is no longer engaged
+const ENGAGED_WINDOW_SECS: f64 = 5.0;
+
+/// An enemy left alive that was last seen this long before the fight ended has despawned
+const DESPAWN_GRACE_SECS: f64 = 10.0;
+
+/// A heal on a player below this percent of max HP may be a save...
+const SAVE_HP_PCT: f64 = 15.0;
+
+/// ...if they then take at least the HP they had left within this long, and live
+const SAVE_WINDOW_SECS: f64 = 2.0;
+
+/// Tracks damage/healing/deaths during an encounter or key
+pub struct EventTracker {
+    options: ParseOptions,
+    damage_by_player: HashMap<String, HashMap<u64, (String, u32, u64, u32)>>,
+    /// Damage of each player's own ticks (`SPELL_PERIODIC_DAMAGE`, not their pets'): guid -> spell_id -> amount
+    periodic_damage: HashMap<String, HashMap<u64, u64>>,
+    healing_by_player: HashMap<String, HashMap<u64, (String, u32, u64, u32)>>,
+    damage_taken_by_player: HashMap<String, u64>,
+    /// Damage dealt by enemies to players: enemy_name -> player_guid -> amount
+    enemy_damage_to_players: HashMap<String, HashMap<String, u64>>,
+    /// Enemy spells that hit players: spell_id -> (spell_name, damage, hits, enemy names)
+    mechanic_damage: HashMap<u64, (String, u64, u32, Vec<String>)>,
+    player_names: HashMap<String, String>,
+    death_events: Vec<DeathEvent>,
+    /// Players currently dead: player_guid -> (index into death_events, death timestamp_secs)
+    open_deaths: HashMap<String, (usize, f64)>,
+    /// Combat res events: (elapsed_secs, player_guid)
+    res_events: Vec<(f64, String)>,
+    player_death_counts: HashMap<String, u32>,
+    /// Players currently dead: player_guid -> death time (secs)
+    dead_players: HashMap<String, f64>,
+    /// True while every group member is dead (a wipe is in progress)
+    in_wipe: bool,
+    /// Full-group wipes: (elapsed_secs, run_back_secs once the group re-engaged)
+    wipes: Vec<(f64, Option<f64>)>,
+    /// Last known HP per player (advanced log info)
+    last_player_hp: HashMap<String, u64>,
+    /// Lethal hits soaked by absorbs
+    near_deaths: Vec<NearDeath>,
+    /// The same near-deaths by GUID: (timestamp_secs, player_guid, absorber_guid that saved them)
+    near_death_times: Vec<(f64, String, Option<String>)>,
+    /// Heals on players below `SAVE_HP_PCT`, waiting to see if a lethal amount of damage
+    /// follows: (timestamp_secs, target_guid, healer_guid, HP before the heal, damage taken since)
+    low_hp_heals: Vec<(f64, String, String, u64, u64)>,
+    /// Estimated deaths prevented by heals, per healer
+    deaths_prevented: HashMap<String, u32>,
+    /// Cast-time spells in progress: player_guid -> (spell_id, start secs)
+    pending_casts: HashMap<String, (u64, f64)>,
+    /// Channels in progress: player_guid -> (start secs, base duration)
+    open_channels: HashMap<String, (f64, f64)>,
+    /// Cast outcomes, timed by when the cast started: (start secs, player_guid, outcome)
+    cast_events: Vec<(f64, String, CastOutcome)>,
+    /// Arena team of each player; damage to a player on the other team counts as damage done
+    pvp_teams: HashMap<String, u32>,
+    /// Self-contained features fed every event (crowd control, practice casts, pull openers)
+    handlers: CombatHandlers,
+    /// Raid healing cooldown casts: (timestamp_secs, caster_guid, spell_id)
+    raid_cd_casts: Vec<(f64, String, u64)>,
+    /// Taunts: (timestamp_secs, caster_guid, spell_id, target_guid)
+    taunt_casts: Vec<(f64, String, u64, String)>,
+    /// Current melee target of each enemy hitting a tank: creature_guid -> tank guid
+    melee_targets: HashMap<String, String>,
+    tank_swaps: Vec<TankSwap>,
+    avoidable_hits: Vec<AvoidableHit>,
+    /// Players hit by dangerous enemy abilities: (offset_secs, player_guid, player_name, spell_id, spell_name, amount)
+    dangerous_hits: Vec<(f64, String, String, u64, String, u64)>,
+    /// Enemies in combat with the group: creature_guid -> last time they dealt or took damage
+    engaged_enemies: HashMap<String, f64>,
+    /// Player damage with the enemy count at the time: (timestamp_secs, player_guid, amount, engaged enemies)
+    target_count_events: Vec<(f64, String, u64, usize)>,
+    /// Enemies the group fought: creature_guid -> activity (pad filter)
+    enemy_activity: HashMap<String, EnemyActivity>,
+    /// Timestamp of the latest event processed
+    last_event_secs: f64,
+    /// Spawns of configured priority targets: creature_guid -> spawn
+    priority_spawns: HashMap<String, PrioritySpawn>,
+    /// Most recent hits taken per player, for picking the killing blow
+    last_damage_to: HashMap<String, VecDeque<DamageHit>>,
+    /// Recent damage/heal events per player for death recap (the last `recap_window_secs`)
+    recent_events: HashMap<String, VecDeque<RecapEvent>>,
+    /// Player spec IDs from COMBATANT_INFO
+    player_specs: HashMap<String, u32>,
+    /// Talent entries each player picked, from COMBATANT_INFO (see `parse_talents`)
+    player_talents: HashMap<String, Vec<u32>>,
+    /// Last cast per spell for cooldown tracking: player_guid -> spell_id -> timestamp_secs
+    last_casts: HashMap<String, HashMap<u64, f64>>,
+    /// Pet ownership: pet_guid -> owner_guid (from SPELL_SUMMON events)
+    pet_owners: HashMap<String, String>,
+    /// Units whose melee hits are also logged as SWING_DAMAGE_LANDED; their plain
+    /// SWING_DAMAGE lines are duplicates and skipped
+    landed_swing_sources: std::collections::HashSet<String>,
+    /// Last plain SWING_DAMAGE counted per source: (timestamp_secs, dest_guid, amount)
+    last_plain_swing: HashMap<String, (f64, String, u64)>,
+    /// Per-target damage: player_guid -> spell_id -> target_name -> amount
+    damage_targets: HashMap<String, HashMap<u64, HashMap<String, u64>>>,
+    /// Per-target healing: player_guid -> spell_id -> target_name -> amount
+    healing_targets: HashMap<String, HashMap<u64, HashMap<String, u64>>>,
+    /// Aura events: player_guid -> spell_id -> Vec<(time_secs, event: "apply"/"remove"/"dose", stacks)>
+    raw_aura_events: HashMap<String, HashMap<u64, Vec<(f64, String, u32)>>>,
+    /// Active aura stacks: player_guid -> spell_id -> current_stacks
+    active_aura_stacks: HashMap<String, HashMap<u64, u32>>,
+    /// Spell names for aura: spell_id -> spell_name
+    aura_spell_names: HashMap<u64, String>,
+    /// Aura sources: (player_guid, spell_id) -> source_name
+    aura_sources: HashMap<(String, u64), String>,
+    /// Aura types: spell_id -> "BUFF" or "DEBUFF"
+    aura_types: HashMap<u64, String>,
+    /// Kill counts per target name
+    kill_counts: HashMap<String, u32>,
+    /// NPC ID per creature name (from GUIDs) for enemy forces lookups
+    creature_npc_ids: HashMap<String, u64>,
+    /// Creature deaths for per-pull enemy forces: (timestamp_secs, enemy_name)
+    npc_death_events: Vec<(f64, String)>,
+    /// Creature type from GUID: target_name -> guid_type ("Creature", "Vehicle", "Pet", etc.)
+    creature_types: HashMap<String, String>,
+    /// Last known HP for non-player targets: dest_name -> (currentHP, maxHP)
+    last_creature_hp: HashMap<String, (u64, u64)>,
+    /// Current encounter phase (from ENCOUNTER_PHASE_CHANGE events)
+    current_phase: u32,
+    /// Phase transitions: (timestamp_secs, phase_id)
+    phase_transitions: Vec<(f64, u32)>,
+    /// Per-phase per-target damage: phase_id -> target_name -> total_damage
+    phase_damage_targets: HashMap<u32, HashMap<String, u64>>,
+    /// Creature types per phase for proper enemy labeling
+    phase_creature_types: HashMap<u32, HashMap<String, String>>,
+    /// Boss encounter name for HP tracking
+    boss_encounter_name: String,
+    /// Current boss HP percentage (0.0-100.0), updated from damage events to boss
+    current_boss_hp_pct: f64,
+    /// The highest maxHP seen among creatures — we treat this creature as the boss
+    boss_max_hp_seen: u64,
+    /// Encounter start time in seconds (for time-based bucketing)
+    encounter_start_secs: f64,
+    /// Start time this tracker measures elapsed times from (as passed to process_combat_event)
+    fight_start_secs: f64,
+    /// Time-bucketed damage: elapsed second -> (player_guid, spell_id) -> damage
+    time_bucketed_damage: HashMap<u32, HashMap<(String, u64), u64>>,
+    /// Boss HP timeline: (elapsed_secs, hp_pct) sampled when boss takes damage
+    boss_hp_timeline: Vec<(f64, f64)>,
+    /// Raw NPC damage events for pull detection: (timestamp_secs, enemy_name, damage, creature_type)
+    npc_damage_events: Vec<(f64, String, u64, String)>,
+    /// Player damage events with timestamps for per-pull player damage: (timestamp_secs, source_guid, amount)
+    player_damage_events: Vec<(f64, String, u64)>,
+    /// Player healing events with timestamps for per-pull player healing: (timestamp_secs, source_guid, amount)
+    player_healing_events: Vec<(f64, String, u64)>,
+    /// Per-ability damage events for per-pull ability breakdown: (ts, guid, spell_id, spell_name, spell_school, amount, target_name)
+    player_ability_events: Vec<(f64, String, u64, String, u32, u64, String)>,
+    /// Per-ability heal events for per-pull ability breakdown: (ts, guid, spell_id, spell_name, spell_school, amount, target_name)
+    player_heal_ability_events: Vec<(f64, String, u64, String, u32, u64, String)>,
+    /// Healing by recipient for the self/external split: (ts, source_guid, spell_id, amount, on_self, absorb)
+    heal_recipient_events: Vec<(f64, String, u64, u64, bool, bool)>,
+    /// Per-ability damage taken events: (ts, dest_guid, spell_id, spell_name, spell_school, amount, source_name)
+    player_damage_taken_events: Vec<DamageTakenEvent>,
+    /// Raw player HP events for replay: (elapsed_secs, dest_guid, current_hp, max_hp)
+    hp_events: Vec<(f64, String, u64, u64)>,
+    /// Raw player position events for replay: (elapsed_secs, dest_guid, pos_x, pos_y)
+    position_events: Vec<(f64, String, f64, f64)>,
+    /// Raw boss position events for replay map: (elapsed_secs, pos_x, pos_y)
+    boss_position_events: Vec<(f64, f64, f64)>,
+    /// Pet source names for grouping: (owner_guid, spell_id) -> pet_name
+    /// When a pet does damage, we record which pet name the spell came from.
+    pet_source_names: HashMap<(String, u64), String>,
+    /// Pet damage grouped by owner for ability grouping:
+    /// owner_guid -> pet_name -> spell_id -> (spell_name, school, total, hits)
+    pet_damage_by_owner: HashMap<String, HashMap<String, HashMap<u64, (String, u32, u64, u32)>>>,
+}
+
+impl Default for EventTracker {
+    fn default() -> Self {
+        EventTracker::new()
+    }
+}
+
+impl EventTracker {
+    /// Create an empty tracker with default options
+    pub fn new() -> Self {
+        EventTracker::with_options(&ParseOptions::default())
+    }
+
+    /// Create an empty tracker
+    pub fn with_options(options: &ParseOptions) -> Self {
+        EventTracker {
+            options: options.clone(),
+            damage_by_player: HashMap::new(),
+            periodic_damage: HashMap::new(),
+            healing_by_player: HashMap::new(),
+            damage_taken_by_player: HashMap::new(),
+            enemy_damage_to_players: HashMap::new(),
+            mechanic_damage: HashMap::new(),
+            player_names: HashMap::new(),
+            death_events: Vec::new(),
+            open_deaths: HashMap::new(),
+            res_events: Vec::new(),
+            player_death_counts: HashMap::new(),
+            dead_players: HashMap::new(),
+            in_wipe: false,
+            wipes: Vec::new(),
+            last_player_hp: HashMap::new(),
+            near_deaths: Vec::new(),
+            near_death_times: Vec::new(),
+            low_hp_heals: Vec::new(),
+            deaths_prevented: HashMap::new(),
+            pending_casts: HashMap::new(),
+            open_channels: HashMap::new(),
+            cast_events: Vec::new(),
+            pvp_teams: HashMap::new(),
+            handlers: CombatHandlers::new(options),
+            raid_cd_casts: Vec::new(),
+            taunt_casts: Vec::new(),
+            melee_targets: HashMap::new(),
+            tank_swaps: Vec::new(),
+            avoidable_hits: Vec::new(),
+            dangerous_hits: Vec::new(),
+            engaged_enemies: HashMap::new(),
+            target_count_events: Vec::new(),
+            enemy_activity: HashMap::new(),
+            last_event_secs: 0.0,
+            priority_spawns: HashMap::new(),
+            last_damage_to: HashMap::new(),
+            recent_events: HashMap::new(),
+            player_specs: HashMap::new(),
+            player_talents: HashMap::new(),
+            last_casts: HashMap::new(),
+            pet_owners: HashMap::new(),
+            landed_swing_sources: std::collections::HashSet::new(),
+            last_plain_swing: HashMap::new(),
+            damage_targets: HashMap::new(),
+            healing_targets: HashMap::new(),
+            raw_aura_events: HashMap::new(),
+            active_aura_stacks: HashMap::new(),
+            aura_spell_names: HashMap::new(),
+            aura_sources: HashMap::new(),
+            aura_types: HashMap::new(),
+            kill_counts: HashMap::new(),
+            creature_npc_ids: HashMap::new(),
+            npc_death_events: Vec::new(),
+            creature_types: HashMap::new(),
+            last_creature_hp: HashMap::new(),
+            current_phase: 1,
+            phase_transitions: Vec::new(),
+            phase_damage_targets: HashMap::new(),
+            phase_creature_types: HashMap::new(),
+            boss_encounter_name: String::new(),
+            current_boss_hp_pct: 100.0,
+            boss_max_hp_seen: 0,
+            encounter_start_secs: 0.0,
+            fight_start_secs: 0.0,
+            time_bucketed_damage: HashMap::new(),
+            boss_hp_timeline: Vec::new(),
+            npc_damage_events: Vec::new(),
+            player_damage_events: Vec::new(),
+            player_healing_events: Vec::new(),
+            player_ability_events: Vec::new(),
+            player_heal_ability_events: Vec::new(),
+            heal_recipient_events: Vec::new(),
+            player_damage_taken_events: Vec::new(),
+            hp_events: Vec::new(),
+            position_events: Vec::new(),
+            boss_position_events: Vec::new(),
+            pet_source_names: HashMap::new(),
+            pet_damage_by_owner: HashMap::new(),
+        }
+    }
+
+    /// Create a new tracker, carrying over player identity info from another tracker
+    pub fn new_with_context(other: &EventTracker) -> Self {
+        let mut t = EventTracker::with_options(&other.options);
+        t.player_specs = other.player_specs.clone();
+        t.player_talents = other.player_talents.clone();
+        t.last_casts = other.last_casts.clone();
+        t.player_names = other.player_names.clone();
+        t.pet_owners = other.pet_owners.clone();
+        t.landed_swing_sources = other.landed_swing_sources.clone();
+        t.pet_source_names = other.pet_source_names.clone();
+        t.pet_damage_by_owner = other.pet_damage_by_owner.clone();
+        t
+    }
+
+    fn push_recap_event(&mut self, guid: &str, event: RecapEvent) {
+        let window = self.options.recap_window_secs;
+        let events = self.recent_events.entry(guid.to_string()).or_default();
+        events.push_back(event);
+        // Time-based ring buffer: only the recap window before the newest event is ever needed
+        let latest = events.back().map(|e| from_millis(e.time_ms)).unwrap_or(0.0);
+        while events.front().is_some_and(|e| latest - from_millis(e.time_ms) > window) {
+            events.pop_front();
+        }
+    }
+    fn take_recap(&mut self, guid: &str, death_time: f64) -> Vec<RecapEvent> {
+        let events = self.recent_events.remove(guid).unwrap_or_default();
+        let window = self.options.recap_window_secs;
+        // Keep only events from the recap window before death,
+        // and filter out buff_removed events within 0.5s of death (mass buff removal on death)
+        let mut recap: Vec<RecapEvent> = events.into_iter()
+            .filter(|e| {
+                let time = from_millis(e.time_ms);
+                let in_window = death_time - time <= window && time <= death_time;
+                let is_death_buff_removal = e.event_type == "buff_removed"
+                    && (death_time - time).abs() < 0.5;
+                in_window && !is_death_buff_removal
+            })
+            .collect();
+        if recap.len() > self.options.recap_max_events {
+            recap.drain(..recap.len() - self.options.recap_max_events);
+        }
+        recap
+    }
+
+    fn record_hit_taken(&mut self, guid: &str, hit: DamageHit) {
+        let hits = self.last_damage_to.entry(guid.to_string()).or_default();
+        if hits.len() == KILLING_BLOW_CANDIDATES {
+            hits.pop_front();
+        }
+        hits.push_back(hit);
+    }
+
+    /// A new cast or channel ends any channel in progress; count it as clipped when it
+    /// was cut before half its base duration (haste can't shorten it that much)
+    fn close_channel(&mut self, guid: &str, timestamp_secs: f64) {
+        if let Some((start, duration)) = self.open_channels.remove(guid) {
+            if timestamp_secs - start < duration * 0.5 {
+                self.cast_events.push((start, guid.to_string(), CastOutcome::ChannelClipped));
+            }
+        }
+    }
+
+    /// Cast stats of the casts the player started between `from_secs` and `to_secs`. A cast
+    /// still in progress when the encounter ended never finished, so it counts as cancelled.
+    fn build_cast_stats(&self, guid: &str, from_secs: f64, to_secs: f64) -> CastStats {
+        let in_range = |at: f64| at >= from_secs && at <= to_secs;
+        let mut stats = CastStats::default();
+        for (at, caster, outcome) in &self.cast_events {
+            if caster != guid || !in_range(*at) {
+                continue;
+            }
+            match outcome {
+                CastOutcome::Started => stats.casts_started += 1,
+                CastOutcome::Completed => stats.casts_completed += 1,
+                CastOutcome::Cancelled => stats.casts_cancelled += 1,
+                CastOutcome::Channel => stats.channels += 1,
+                CastOutcome::ChannelClipped => stats.channels_clipped += 1,
+            }
+        }
+        if self.pending_casts.get(guid).is_some_and(|(_, start)| in_range(*start)) {
+            stats.casts_cancelled += 1;
+        }
+        stats.update_cancel_pct();
+        stats
+    }
+
+    /// Add to a player's aura history for buff uptimes
+    fn record_aura_event(&mut self, guid: &str, spell_id: u64, offset_secs: f64, kind: &str, stacks: u32) {
+        if !self.options.features.buff_timelines {
+            return;
+        }
+        self.raw_aura_events
+            .entry(guid.to_string()).or_default()
+            .entry(spell_id).or_default()
+            .push((offset_secs, kind.to_string(), stacks));
+    }
+
+    /// Record player damage along with the number of enemies currently engaged
+    fn record_target_count(&mut self, timestamp_secs: f64, player_guid: &str, amount: u64) {
+        self.engaged_enemies.retain(|_, last| timestamp_secs - *last <= ENGAGED_WINDOW_SECS);
+        let engaged = self.engaged_enemies.len();
+        self.target_count_events.push((timestamp_secs, player_guid.to_string(), amount, engaged));
+    }
+
+    fn build_target_count_split(&self, guid: &str, from_secs: f64, to_secs: f64) -> TargetCountSplit {
+        let mut split = TargetCountSplit::default();
+        for (_, _, amount, engaged) in self.target_count_events.iter()
+            .filter(|e| e.1 == guid && e.0 >= from_secs && e.0 <= to_secs)
+        {
+            split.add(*engaged, *amount);
+        }
+        split.update_pcts();
+        split
+    }
+
+    fn enemy_activity_mut(&mut self, enemy_guid: &str, enemy_name: &str, timestamp_secs: f64) -> &mut EnemyActivity {
+        let activity = self.enemy_activity.entry(enemy_guid.to_string()).or_insert_with(|| EnemyActivity {
+            name: enemy_name.to_string(),
+            first_secs: timestamp_secs,
+            last_secs: timestamp_secs,
+            died: false,
+            threatened: false,
+            damage_by_player: HashMap::new(),
+        });
+        activity.last_secs = timestamp_secs;
+        activity
+    }
+
+    /// Why an enemy's damage counts as padding, if it does: it wasn't killed, and either
+    /// never hit the group or vanished well before `end_secs` (skipped lieutenants, despawns)
+    fn pad_reason(activity: &EnemyActivity, end_secs: f64) -> Option<&'static str> {
+        if activity.died {
+            None
+        } else if !activity.threatened {
+            Some("harmless")
+        } else if activity.last_secs < end_secs - DESPAWN_GRACE_SECS {
+            Some("despawned")
+        } else {
+            None
+        }
+    }
+
+    /// A player's damage to pad targets that first appeared within [from_secs, to_secs]
+    fn pad_damage(&self, guid: &str, from_secs: f64, to_secs: f64) -> u64 {
+        let end_secs = to_secs.min(self.last_event_secs);
+        self.enemy_activity.values()
+            .filter(|a| a.first_secs >= from_secs && a.first_secs <= to_secs)
+            .filter(|a| Self::pad_reason(a, end_secs).is_some())
+            .filter_map(|a| a.damage_by_player.get(guid))
+            .sum()
+    }
+
+    /// Every enemy of the fight from when it first traded damage with the group until it died or
+    /// was last seen, in order of appearance, and the most adds (enemies not named like one of
+    /// `boss_names`) alive at once; None if the group fought nothing
+    fn build_add_timeline(&self, start_secs: f64, boss_names: &[String]) -> Option<AddTimeline> {
+        if self.enemy_activity.is_empty() {
+            return None;
+        }
+        let mut enemies: Vec<EnemyLifetime> = self.enemy_activity.iter().map(|(guid, activity)| EnemyLifetime {
+            name: activity.name.clone(),
+            npc_id: guid::npc_id(guid),
+            first_seen_secs: (activity.first_secs - start_secs).max(0.0),
+            last_seen_secs: (activity.last_secs - start_secs).max(0.0),
+            died: activity.died,
+            is_boss: boss_names.iter().any(|b| eq_ignore_case(b, &activity.name)),
+        }).collect();
+        enemies.sort_by(|a, b| a.first_seen_secs.total_cmp(&b.first_seen_secs).then_with(|| a.name.cmp(&b.name)));
+
+        // Sweep spawns and departures in time order; at equal times departures go first
+        let mut changes: Vec<(f64, i32)> = enemies.iter()
+            .filter(|e| !e.is_boss)
+            .flat_map(|e| [(e.first_seen_secs, 1), (e.last_seen_secs, -1)])
+            .collect();
+        changes.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let (mut alive, mut peak_alive, mut peak_offset_secs) = (0i32, 0i32, 0.0);
+        for (at, change) in changes {
+            alive += change;
+            if alive > peak_alive {
+                peak_alive = alive;
+                peak_offset_secs = at;
+            }
+        }
+        Some(AddTimeline { enemies, peak_alive: peak_alive as u32, peak_offset_secs })
+    }
+
+    /// Pad targets grouped by name and reason, most damaged first
+    fn build_pad_targets(&self) -> Vec<PadTarget> {
+        let mut grouped: HashMap<(&str, &str), (u32, u64)> = HashMap::new();
+        for activity in self.enemy_activity.values() {
+            if let Some(reason) = Self::pad_reason(activity, self.last_event_secs) {
+                let entry = grouped.entry((activity.name.as_str(), reason)).or_insert((0, 0));
+                entry.0 += 1;
+                entry.1 += activity.damage_by_player.values().sum::<u64>();
+            }
+        }
+        let mut result: Vec<PadTarget> = grouped.into_iter()
+            .filter(|(_, (_, damage))| *damage > 0)
+            .map(|((name, reason), (spawns, total_damage))| PadTarget {
+                name: name.to_string(),
+                reason: reason.to_string(),
+                spawns,
+                total_damage,
+            })
+            .collect();
+        result.sort_by_key(|a| std::cmp::Reverse(a.total_damage));
+        result
+    }
+
+    fn build_healing_split(&self, guid: &str, from_secs: f64, to_secs: f64) -> HealingSplit {
+        let mut split = HealingSplit::default();
+        for (_, _, spell_id, amount, on_self, absorb) in self.heal_recipient_events.iter()
+            .filter(|e| e.1 == guid && e.0 >= from_secs && e.0 <= to_secs)
+        {
+            if *on_self {
+                split.self_healing += amount;
+                if *spell_id == game_data::LEECH_SPELL_ID {
+                    split.leech += amount;
+                }
+            } else {
+                split.external += amount;
+            }
+            if *absorb {
+                split.absorbs += amount;
+            }
+        }
+        split
+    }
+
+    /// Player damage and healing in [from_secs, to_secs), or None if there was none
+    fn build_pull_window(&self, from_secs: f64, to_secs: f64) -> Option<PullWindow> {
+        let mut by_player: HashMap<&str, (u64, u64)> = HashMap::new();
+        for (_, guid, amount) in self.player_damage_events.iter().filter(|e| e.0 >= from_secs && e.0 < to_secs) {
+            by_player.entry(guid).or_default().0 += amount;
+        }
+        for (_, guid, amount) in self.player_healing_events.iter().filter(|e| e.0 >= from_secs && e.0 < to_secs) {
+            by_player.entry(guid).or_default().1 += amount;
+        }
+        if by_player.is_empty() {
+            return None;
+        }
+        let mut players: Vec<PullWindowPlayer> = by_player.into_iter().map(|(guid, (damage, healing))| PullWindowPlayer {
+            guid: guid.to_string(),
+            name: self.player_names.get(guid).cloned().unwrap_or_else(|| guid.to_string()),
+            damage,
+            healing,
+        }).collect();
+        players.sort_by_key(|p| std::cmp::Reverse(p.damage));
+        Some(PullWindow {
+            window_secs: to_secs - from_secs,
+            damage: players.iter().map(|p| p.damage).sum(),
+            healing: players.iter().map(|p| p.healing).sum(),
+            players,
+        })
+    }
+
+    /// Credit player damage to a configured priority target, tracking the spawn's lifetime
+    fn record_priority_damage(&mut self, dest_guid: &str, dest_name: &str, player_guid: &str, amount: u64, timestamp_secs: f64) {
+        let Some(npc_id) = guid::npc_id(dest_guid) else { return };
+        if !self.options.priority_npc_ids.contains(&npc_id) {
+            return;
+        }
+        let spawn = self.priority_spawns.entry(dest_guid.to_string()).or_insert_with(|| PrioritySpawn {
+            npc_id,
+            name: dest_name.to_string(),
+            first_secs: timestamp_secs,
+            last_secs: timestamp_secs,
+            died: false,
+            damage_by_player: HashMap::new(),
+        });
+        if !spawn.died {
+            spawn.last_secs = timestamp_secs;
+        }
+        *spawn.damage_by_player.entry(player_guid.to_string()).or_insert(0) += amount;
+    }
+
+    /// Record a lethal hit soaked by absorbs, crediting the shield that landed with it
+    fn record_near_death(&mut self, guid: &str, timestamp_secs: f64, mut near_death: NearDeath) {
+        let mut saved_by = None;
+        if let Some((absorb_secs, absorber, spell)) = self.handlers.absorbs.latest_on(guid) {
+            if (timestamp_secs - absorb_secs).abs() <= 0.1 {
+                near_death.saved_by = Some(self.player_names.get(absorber).cloned().unwrap_or_else(|| absorber.clone()));
+                near_death.shield_spell = Some(spell.clone());
+                saved_by = Some(absorber.clone());
+            }
+        }
+        self.near_death_times.push((timestamp_secs, guid.to_string(), saved_by));
+        self.near_deaths.push(near_death);
+    }
+
+    /// Near-deaths of the player, and near-deaths their shields saved, between `from_secs`
+    /// and `to_secs`
+    fn near_death_counts(&self, guid: &str, from_secs: f64, to_secs: f64) -> (u32, u32) {
+        let mut near_deaths = 0;
+        let mut saves = 0;
+        for (at, player, saved_by) in &self.near_death_times {
+            if *at < from_secs || *at > to_secs {
+                continue;
+            }
+            if player == guid {
+                near_deaths += 1;
+            }
+            if saved_by.as_deref() == Some(guid) {
+                saves += 1;
+            }
+        }
+        (near_deaths, saves)
+    }
+
+    /// Remember a heal that landed on a player below `SAVE_HP_PCT`; `current_hp` is their HP
+    /// after it
+    fn record_low_hp_heal(&mut self, target_guid: &str, healer_guid: &str, timestamp_secs: f64, amount: u64, current_hp: u64, max_hp: u64) {
+        let hp_before = current_hp.saturating_sub(amount);
+        if max_hp > 0 && hp_before > 0 && (hp_before as f64) < max_hp as f64 * SAVE_HP_PCT / 100.0 {
+            self.low_hp_heals.push((timestamp_secs, target_guid.to_string(), healer_guid.to_string(), hp_before, 0));
+        }
+    }
+
+    /// Count damage to a player against recent low-HP heals on them: once it adds up to the
+    /// HP they had before the heal and they are still alive, the heal saved them
+    fn check_heal_saves(&mut self, guid: &str, timestamp_secs: f64, amount: u64, survived: bool) {
+        if self.low_hp_heals.is_empty() {
+            return;
+        }
+        let mut savers = Vec::new();
+        self.low_hp_heals.retain_mut(|(heal_secs, target, healer, hp_before, taken)| {
+            if timestamp_secs - *heal_secs > SAVE_WINDOW_SECS {
+                return false;
+            }
+            if target != guid {
+                return true;
+            }
+            if !survived {
+                return false;
+            }
+            *taken += amount;
+            if *taken >= *hp_before {
+                savers.push(std::mem::take(healer));
+                return false;
+            }
+            true
+        });
+        for healer in savers {
+            *self.deaths_prevented.entry(healer).or_insert(0) += 1;
+        }
+    }
+
+    /// The hit that actually killed a player. DoT ticks landing on an already-dead player
+    /// also carry overkill, so take the first of the trailing lethal hits; without overkill
+    /// info fall back to the last hit that left the player at 0 HP, then the last hit.
+    fn killing_blow(&self, guid: &str) -> Option<&DamageHit> {
+        let hits = self.last_damage_to.get(guid)?;
+        let lethal_run = hits.iter().rev().take_while(|h| h.overkill >= 0).count();
+        if lethal_run > 0 {
+            return hits.get(hits.len() - lethal_run);
+        }
+        hits.iter().rev()
+            .find(|h| h.max_hp > 0 && h.current_hp == 0)
+            .or_else(|| hits.back())
+    }
+
+    /// Personal defensives and health potions that were off cooldown at a player's death
+    /// and not pressed in the preceding window
+    fn unused_defensives(&self, guid: &str, death_secs: f64) -> Vec<String> {
+        const WINDOW_SECS: f64 = 10.0;
+        let casts = self.last_casts.get(guid);
+        let last_cast = |spell_id: &u64| casts.and_then(|c| c.get(spell_id)).copied();
+        let available = |last: Option<f64>, cooldown: f64| match last {
+            Some(t) => t + cooldown <= death_secs && death_secs - t > WINDOW_SECS,
+            None => true,
+        };
+
+        let mut unused = Vec::new();
+        if let Some((class, spec, _)) = self.player_spec(guid).and_then(|(s, _)| spec_info(s)) {
+            for (spell_id, name, cooldown) in game_data::personal_defensives(class, spec) {
+                if available(last_cast(&spell_id), cooldown) {
+                    unused.push(name.to_string());
+                }
+            }
+        }
+        let last_potion = game_data::HEALTH_POTION_SPELLS.iter()
+            .filter_map(last_cast)
+            .fold(None, |acc: Option<f64>, t| Some(acc.map_or(t, |a| a.max(t))));
+        if available(last_potion, game_data::POTION_COOLDOWN_SECS) {
+            unused.push("Health Potion".to_string());
+        }
+        unused
+    }
+
+    /// Spec ID for a player: from COMBATANT_INFO, or inferred from signature abilities
+    /// they used. Returns (spec_id, inferred).
+    fn player_spec(&self, guid: &str) -> Option<(u32, bool)> {
+        if let Some(&spec_id) = self.player_specs.get(guid) {
+            return Some((spec_id, false));
+        }
+        let cast_spells = self.last_casts.get(guid).into_iter().flat_map(|c| c.keys());
+        let damage_spells = self.damage_by_player.get(guid).into_iter().flat_map(|d| d.keys());
+        let heal_spells = self.healing_by_player.get(guid).into_iter().flat_map(|h| h.keys());
+        let mut votes: HashMap<u32, u32> = HashMap::new();
+        for spell_id in cast_spells.chain(damage_spells).chain(heal_spells) {
+            if let Some(spec_id) = game_data::spec_for_signature_spell(*spell_id) {
+                *votes.entry(spec_id).or_insert(0) += 1;
+            }
+        }
+        votes.into_iter()
+            .max_by_key(|&(spec_id, count)| (count, spec_id))
+            .map(|(spec_id, _)| (spec_id, true))
+    }
+
+    /// Number of players in the group (COMBATANT_INFO when available, otherwise players seen)
+    fn group_player_count(&self) -> usize {
+        if !self.player_specs.is_empty() {
+            self.player_specs.len()
+        } else {
+            self.player_names.keys().filter(|g| guid::is_player(g)).count()
+        }
+    }
+
+    /// Each group member's personal view of the fight so far: their latest death and when
+    /// their defensives and the cooldowns they have pressed are ready again (for live mode)
+    pub fn build_live_players(&self) -> Vec<LivePlayer> {
+        let now = self.last_event_secs;
+        let guids: std::collections::HashSet<&String> = self.player_specs.keys()
+            .chain(self.damage_by_player.keys())
+            .chain(self.healing_by_player.keys())
+            .filter(|g| guid::is_player(g))
+            .collect();
+        let mut players: Vec<LivePlayer> = guids.into_iter().map(|guid| {
+            let (class_name, spec_name, role) = self.player_spec(guid)
+                .and_then(|(id, _)| spec_info(id))
+                .unwrap_or(("", "", ""));
+            let casts = self.last_casts.get(guid);
+            let mut cooldowns: Vec<LiveCooldown> = Vec::new();
+            let mut track = |spell_id: u64, spell_name: &str, category: &'static str, cooldown_secs: f64| {
+                if cooldowns.iter().any(|c| c.spell_id == spell_id) {
+                    return;
+                }
+                let last_used = casts.and_then(|c| c.get(&spell_id)).copied();
+                cooldowns.push(LiveCooldown {
+                    spell_id,
+                    spell_name: spell_name.to_string(),
+                    category: category.to_string(),
+                    cooldown_secs,
+                    ready_in_secs: last_used.map_or(0.0, |t| (t + cooldown_secs - now).max(0.0)),
+                });
+            };
+            for (spell_id, name, cooldown) in game_data::personal_defensives(class_name, spec_name) {
+                track(spell_id, name, "defensive", cooldown);
+            }
+            for &spell_id in casts.into_iter().flat_map(|c| c.keys()) {
+                let Some(known) = game_data::spell_cooldown(spell_id) else { continue };
+                let category = match known.category {
+                    game_data::CooldownCategory::Defensive => "defensive",
+                    game_data::CooldownCategory::External => "external",
+                    game_data::CooldownCategory::RaidCooldown => "raid",
+                    game_data::CooldownCategory::BattleRes => "battle_res",
+                    game_data::CooldownCategory::Burst => "offensive",
+                    game_data::CooldownCategory::Other if known.cooldown_secs >= LIVE_COOLDOWN_MIN_SECS => "offensive",
+                    _ => continue,
+                };
+                track(spell_id, known.name, category, known.cooldown_secs);
+            }
+            // What the role leans on comes first: defensives for tanks, group cooldowns for
+            // healers, damage cooldowns for everyone else
+            let rank = |category: &str| match (role, category) {
+                ("tank", "defensive") | ("healer", "raid" | "external") | ("dps", "offensive") => 0,
+                (_, "defensive") => 1,
+                _ => 2,
+            };
+            cooldowns.sort_by(|a, b| rank(&a.category).cmp(&rank(&b.category)).then_with(|| a.spell_name.cmp(&b.spell_name)));
+            LivePlayer {
+                guid: guid.clone(),
+                name: self.player_names.get(guid).cloned().unwrap_or_else(|| "Unknown".to_string()),
+                class_name: class_name.to_string(),
+                spec_name: spec_name.to_string(),
+                role: role.to_string(),
+                last_death: self.death_events.iter().rev().find(|d| d.player_guid == *guid).cloned(),
+                cooldowns,
+            }
+        }).collect();
+        players.sort_by(|a, b| a.name.cmp(&b.name));
+        players
+    }
+
+    /// Once the group re-engages after a wipe, record how long the run back took
+    fn close_wipe_run_back(&mut self, elapsed: f64) {
+        if self.in_wipe {
+            return;
+        }
+        if let Some(wipe) = self.wipes.last_mut() {
+            if wipe.1.is_none() {
+                wipe.1 = Some((elapsed - wipe.0).max(0.0));
+            }
+        }
+    }
+
+    /// Mark a dead player as alive again (resurrected, released and back in action)
+    fn mark_alive(&mut self, guid: &str, timestamp_secs: f64) {
+        if self.dead_players.remove(guid).is_some() {
+            self.in_wipe = false;
+        }
+        if let Some((idx, died_at)) = self.open_deaths.remove(guid) {
+            self.death_events[idx].time_dead_secs = (timestamp_secs - died_at).max(0.0);
+        }
+    }
+
+    /// Death events, with time dead running to `end_secs` for players still dead
+    fn deaths_until(&self, end_secs: f64) -> Vec<DeathEvent> {
+        let mut deaths = self.death_events.clone();
+        for &(idx, died_at) in self.open_deaths.values() {
+            deaths[idx].time_dead_secs = (end_secs - died_at).max(0.0);
+        }
+        deaths
+    }
+
+    /// Whether this swing was already counted. Advanced logging can write each melee hit
+    /// twice, as SWING_DAMAGE and SWING_DAMAGE_LANDED; _LANDED is preferred. A source's plain
+    /// swings count until its first _LANDED appears, and that first _LANDED is dropped if it
+    /// repeats the plain swing just counted.
+    fn is_duplicate_swing(&mut self, event_type: &str, source_guid: &str, dest_guid: &str, amount: u64, timestamp_secs: f64) -> bool {
+        if event_type == "SWING_DAMAGE_LANDED" {
+            let first_landed = self.landed_swing_sources.insert(source_guid.to_string());
+            let plain = self.last_plain_swing.remove(source_guid);
+            first_landed && plain.is_some_and(|(secs, dest, plain_amount)| {
+                dest == dest_guid && plain_amount == amount && (timestamp_secs - secs).abs() < 0.001
+            })
+        } else if self.landed_swing_sources.contains(source_guid) {
+            true
+        } else {
+            self.last_plain_swing.insert(source_guid.to_string(), (timestamp_secs, dest_guid.to_string(), amount));
+            false
+        }
+    }
+
+    /// Resolve a pet GUID to its player owner, walking chains up to 5 hops.
+    fn resolve_owner(&self, guid: &str) -> Option<String> {
+        handlers::owner_of(&self.pet_owners, guid)
+    }
+
+    /// Whether damage or crowd control from a player on `dest` is against an enemy: any
+    /// non-player, or a player on the other arena team
+    fn is_enemy(&self, player_guid: &str, dest: &str) -> bool {
+        handlers::is_enemy(&self.pvp_teams, player_guid, dest)
+    }
+
+    /// For raid trash: compute the effective end time by cutting off when DPS drops below 1k for 5+ seconds.
+    /// Returns (effective_end_secs, total_dps). If no cutoff is needed, returns (original_end, dps).
+    fn compute_trash_cutoff(&self, start_secs: f64, end_secs: f64) -> (f64, f64) {
+        let duration = end_secs - start_secs;
+        if duration <= 0.0 { return (end_secs, 0.0); }
+
+        // Build per-second damage buckets (relative to start)
+        let max_sec = duration.ceil() as usize + 1;
+        let mut per_sec_damage = vec![0u64; max_sec];
+        for &(ts, _, amt) in &self.player_damage_events {
+            let bucket = ((ts - start_secs).max(0.0)) as usize;
+            if bucket < max_sec {
+                per_sec_damage[bucket] += amt;
+            }
+        }
+
+        // Find first point where DPS is below 1000 for 5+ consecutive seconds
+        let mut low_dps_start: Option<usize> = None;
+        for sec in 0..max_sec {
+            let dps = per_sec_damage[sec];
+            if dps < 1000 {
+                if low_dps_start.is_none() {
+                    low_dps_start = Some(sec);
+                }
+                if let Some(start) = low_dps_start {
+                    if sec - start >= 5 {
+                        // Cutoff at the start of the low-DPS window
+                        let cutoff = start_secs + start as f64;
+                        let effective_dur = cutoff - start_secs;
+                        let total_dmg: u64 = self.player_damage_events.iter()
+                            .filter(|&&(ts, _, _)| ts >= start_secs && ts < cutoff)
+                            .map(|&(_, _, amt)| amt)
+                            .sum();
+                        let total_dps = if effective_dur > 0.0 { total_dmg as f64 / effective_dur } else { 0.0 };
+                        return (cutoff, total_dps);
+                    }
+                }
+            } else {
+                low_dps_start = None;
+            }
+        }
+
+        // No cutoff needed — compute overall DPS
+        let total_dmg: u64 = self.player_damage_events.iter()
+            .filter(|&&(ts, _, _)| ts >= start_secs && ts <= end_secs)
+            .map(|&(_, _, amt)| amt)
+            .sum();
+        let total_dps = total_dmg as f64 / duration;
+        (end_secs, total_dps)
+    }
+
+    /// Enemy forces count granted per kill of the named creature (0 if unknown)
+    fn enemy_forces_for(&self, enemy_name: &str) -> u32 {
+        self.creature_npc_ids.get(enemy_name)
+            .and_then(|id| game_data::enemy_forces(*id))
+            .unwrap_or(0)
+    }
+
+    /// Damage per elapsed second and player, summed over abilities
+    fn time_bucketed_player_damage(&self) -> HashMap<u32, HashMap<String, u64>> {
+        self.time_bucketed_damage.iter().map(|(second, damage)| {
+            let mut per_player: HashMap<String, u64> = HashMap::new();
+            for ((guid, _), amount) in damage {
+                *per_player.entry(guid.clone()).or_default() += amount;
+            }
+            (*second, per_player)
+        }).collect()
+    }
+
+    /// Per player, each ability's damage per elapsed second, biggest ability first
+    fn ability_damage_over_time(&self) -> HashMap<String, Vec<AbilityDamageSeries>> {
+        let mut series: HashMap<String, HashMap<u64, AbilityDamageSeries>> = HashMap::new();
+        for (second, damage) in &self.time_bucketed_damage {
+            for ((guid, spell_id), amount) in damage {
+                let entry = series.entry(guid.clone()).or_default().entry(*spell_id).or_insert_with(|| {
+                    let spell_name = self.damage_by_player.get(guid).and_then(|spells| spells.get(spell_id))
+                        .map(|(name, ..)| name.clone())
+                        .unwrap_or_default();
+                    AbilityDamageSeries { spell_id: *spell_id, spell_name, total: 0, buckets: Vec::new() }
+                });
+                entry.total += amount;
+                entry.buckets.push((*second, *amount));
+            }
+        }
+        series.into_iter().map(|(guid, abilities)| {
+            let mut abilities: Vec<AbilityDamageSeries> = abilities.into_values().collect();
+            for ability in &mut abilities {
+                ability.buckets.sort_unstable_by_key(|(second, _)| *second);
+            }
+            abilities.sort_by_key(|a| std::cmp::Reverse(a.total));
+            (guid, abilities)
+        }).collect()
+    }
+
+    /// Healer × target matrix of effective healing. Healers are the players in `players` with
+    /// the healer role; targets are every player they healed, tanks included even if they
+    /// did nothing else.
+    fn build_healing_report(&self, players: &[PlayerSummary]) -> HealingReport {
+        let healed_by = |guid: &str| -> HashMap<&str, u64> {
+            let mut by_target: HashMap<&str, u64> = HashMap::new();
+            for targets in self.healing_targets.get(guid).into_iter().flat_map(|spells| spells.values()) {
+                for (target, amount) in targets {
+                    *by_target.entry(target.as_str()).or_insert(0) += amount;
+                }
+            }
+            by_target
+        };
+        let role = |guid: &str| -> String {
+            players.iter().find(|p| p.guid == guid).map(|p| p.role.clone())
+                .or_else(|| self.player_specs.get(guid).and_then(|&spec_id| spec_info(spec_id)).map(|(_, _, role)| role.to_string()))
+                .unwrap_or_default()
+        };
+
+        let mut rows: Vec<(HealingReportPlayer, HashMap<&str, u64>)> = players.iter()
+            .filter(|p| p.role == "healer")
+            .map(|p| (HealingReportPlayer { guid: p.guid.clone(), name: p.name.clone(), role: p.role.clone(), total: 0 }, healed_by(&p.guid)))
+            .collect();
+
+        // Healing targets are recorded by name; keep the ones that are players
+        let mut targets: Vec<HealingReportPlayer> = self.player_names.iter()
+            .map(|(guid, name)| HealingReportPlayer {
+                guid: guid.clone(),
+                name: name.clone(),
+                role: role(guid),
+                total: rows.iter().filter_map(|(_, by_target)| by_target.get(name.as_str())).sum(),
+            })
+            .filter(|t| t.total > 0)
+            .collect();
+        let role_order = |role: &str| match role {
+            "tank" => 0,
+            "healer" => 1,
+            _ => 2,
+        };
+        targets.sort_by(|a, b| (role_order(&a.role), std::cmp::Reverse(a.total), &a.name)
+            .cmp(&(role_order(&b.role), std::cmp::Reverse(b.total), &b.name)));
+
+        for (healer, by_target) in &mut rows {
+            healer.total = targets.iter().filter_map(|t| by_target.get(t.name.as_str())).sum();
+        }
+        rows.sort_by(|(a, _), (b, _)| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+        let amounts = rows.iter()
+            .map(|(_, by_target)| targets.iter().map(|t| by_target.get(t.name.as_str()).copied().unwrap_or(0)).collect())
+            .collect();
+        HealingReport {
+            healers: rows.into_iter().map(|(healer, _)| healer).collect(),
+            targets,
+            amounts,
+        }
+    }
+
+    /// Each player's casts and cooldown usage, from the recorded practice casts
+    fn build_practice(&self, start_secs: f64, end_secs: f64, players: &[PlayerSummary]) -> PracticeSession {
+        const COOLDOWN_MIN_GAP_SECS: f64 = 30.0;
+        let players = players.iter().map(|p| {
+            let casts: Vec<PracticeCast> = self.handlers.practice.casts().iter()
+                .filter(|(_, guid, ..)| *guid == p.guid)
+                .map(|(ts, _, spell_id, spell_name)| PracticeCast {
+                    offset_secs: ts - start_secs,
+                    spell_id: *spell_id,
+                    spell_name: spell_name.clone(),
+                })
+                .collect();
+            let mut cooldowns: Vec<CooldownUsage> = Vec::new();
+            for cast in &casts {
+                match cooldowns.iter_mut().find(|c| c.spell_id == cast.spell_id) {
+                    Some(cooldown) => cooldown.uses.push(cast.offset_secs),
+                    None => cooldowns.push(CooldownUsage {
+                        spell_id: cast.spell_id,
+                        spell_name: cast.spell_name.clone(),
+                        uses: vec![cast.offset_secs],
+                        drift: None,
+                    }),
+                }
+            }
+            // Spells in the cooldown table are cooldowns if their base cooldown is long enough;
+            // others only if they were never pressed twice in quick succession
+            cooldowns.retain(|c| match game_data::spell_cooldown(c.spell_id) {
+                Some(known) => known.cooldown_secs >= COOLDOWN_MIN_GAP_SECS,
+                None => c.uses.windows(2).all(|w| w[1] - w[0] >= COOLDOWN_MIN_GAP_SECS),
+            });
+            for cooldown in &mut cooldowns {
+                cooldown.drift = game_data::spell_cooldown(cooldown.spell_id)
+                    .filter(|known| known.category == game_data::CooldownCategory::Burst)
+                    .map(|known| cooldown_drift(known.cooldown_secs, &cooldown.uses, end_secs - start_secs));
+            }
+            PracticePlayer { guid: p.guid.clone(), name: p.name.clone(), dps: p.dps, cooldowns, casts }
+        }).collect();
+        PracticeSession { players }
+    }
+
+    /// Each player's major damage cooldowns used between `start_secs` and `end_secs`, with how
+    /// far they drifted from their base cooldown; players who used none are left out
+    fn build_cooldown_usage(&self, start_secs: f64, end_secs: f64, players: &[PlayerSummary]) -> Vec<PlayerCooldowns> {
+        players.iter().filter_map(|p| {
+            let mut cooldowns: Vec<CooldownUsage> = Vec::new();
+            for (ts, _, spell_id, spell_name) in self.handlers.burst_cooldowns.casts().iter()
+                .filter(|(ts, guid, ..)| *guid == p.guid && *ts >= start_secs && *ts <= end_secs)
+            {
+                match cooldowns.iter_mut().find(|c| c.spell_id == *spell_id) {
+                    Some(cooldown) => cooldown.uses.push(ts - start_secs),
+                    None => cooldowns.push(CooldownUsage {
+                        spell_id: *spell_id,
+                        spell_name: spell_name.clone(),
+                        uses: vec![ts - start_secs],
+                        drift: None,
+                    }),
+                }
+            }
+            for cooldown in &mut cooldowns {
+                cooldown.drift = game_data::spell_cooldown(cooldown.spell_id)
+                    .map(|known| cooldown_drift(known.cooldown_secs, &cooldown.uses, end_secs - start_secs));
+            }
+            (!cooldowns.is_empty()).then(|| PlayerCooldowns { guid: p.guid.clone(), name: p.name.clone(), cooldowns })
+        }).collect()
+    }
+
+    /// Per-player summaries for everything recorded so far; `duration` is used for DPS/HPS
+    pub fn build_player_summaries(&self, duration: f64) -> Vec<PlayerSummary> {
+        let mut all_guids: std::collections::HashSet<String> = std::collections::HashSet::new();
+        for g in self.damage_by_player.keys() { all_guids.insert(g.clone()); }
+        for g in self.healing_by_player.keys() { all_guids.insert(g.clone()); }
+
+        let mut players: Vec<PlayerSummary> = Vec::new();
+        let damage_taken = self.damage_taken_by_player();
+
+        for guid in &all_guids {
+            if !guid::is_player(guid) {
+                continue;
+            }
+            let name = self.player_names.get(guid).cloned().unwrap_or_else(|| "Unknown".to_string());
+            let spec = self.player_spec(guid);
+            let spec_inferred = spec.map(|(_, inferred)| inferred).unwrap_or(false);
+            let (class_name, spec_name, role) = spec
+                .and_then(|(id, _)| spec_info(id))
+                .map(|(c, s, r)| (c.to_string(), s.to_string(), r.to_string()))
+                .unwrap_or_else(|| (String::new(), String::new(), String::new()));
+
+            let mut total_damage: u64 = 0;
+            let mut damage_abilities: Vec<AbilityBreakdown> = Vec::new();
+            if let Some(spells) = self.damage_by_player.get(guid) {
+                let player_targets = self.damage_targets.get(guid);
+                // Collect spell_ids that come from pets (they'll be grouped separately)
+                let pet_spell_ids: std::collections::HashSet<u64> = if let Some(pets) = self.pet_damage_by_owner.get(guid) {
+                    pets.values().flat_map(|spells| spells.keys().copied()).collect()
+                } else {
+                    std::collections::HashSet::new()
+                };
+
+                for (spell_id, (spell_name, school, total, hits)) in spells {
+                    total_damage += total;
+                    // Skip pet spells — they'll be added as grouped entries below
+                    if pet_spell_ids.contains(spell_id) {
+                        // Check if this spell also has player-direct damage (rare but possible)
+                        let pet_total: u64 = if let Some(pets) = self.pet_damage_by_owner.get(guid) {
+                            pets.values().filter_map(|s| s.get(spell_id)).map(|v| v.2).sum()
+                        } else { 0 };
+                        let player_only = total.saturating_sub(pet_total);
+                        if player_only > 0 {
+                            // Player also cast this spell directly — add player-only portion
+                            let player_hits = hits.saturating_sub(
+                                if let Some(pets) = self.pet_damage_by_owner.get(guid) {
+                                    pets.values().filter_map(|s| s.get(spell_id)).map(|v| v.3).sum()
+                                } else { 0 }
+                            );
+                            let mut targets: Vec<TargetBreakdown> = Vec::new();
+                            if let Some(pt) = player_targets {
+                                if let Some(spell_targets) = pt.get(spell_id) {
+                                    for (tname, tamount) in spell_targets {
+                                        targets.push(TargetBreakdown { target_name: tname.clone(), amount: *tamount });
+                                    }
+                                }
+                            }
+                            targets.sort_by(|a, b| b.amount.cmp(&a.amount));
+                            damage_abilities.push(AbilityBreakdown {
+                                spell_id: *spell_id, spell_name: spell_name.clone(), spell_school: *school,
+                                total_amount: player_only, hit_count: player_hits,
+                                wowhead_url: wowhead_url(*spell_id), targets, sub_abilities: vec![],
+                            });
+                        }
+                        continue;
+                    }
+                    // Build target breakdown for this spell
+                    let mut targets: Vec<TargetBreakdown> = Vec::new();
+                    if let Some(pt) = player_targets {
+                        if let Some(spell_targets) = pt.get(spell_id) {
+                            for (tname, tamount) in spell_targets {
+                                targets.push(TargetBreakdown {
+                                    target_name: tname.clone(),
+                                    amount: *tamount,
+                                });
+                            }
+                        }
+                    }
+                    targets.sort_by(|a, b| b.amount.cmp(&a.amount));
+                    damage_abilities.push(AbilityBreakdown {
+                        spell_id: *spell_id,
+                        spell_name: spell_name.clone(),
+                        spell_school: *school,
+                        total_amount: *total,
+                        hit_count: *hits,
+                        wowhead_url: wowhead_url(*spell_id),
+                        targets,
+                        sub_abilities: vec![],
+                    });
+                }
+            }
+            // Build grouped pet entries
+            if let Some(pets) = self.pet_damage_by_owner.get(guid) {
+                for (pet_name, spells) in pets {
+                    let mut pet_total: u64 = 0;
+                    let mut pet_hits: u32 = 0;
+                    let mut sub_abilities: Vec<AbilityBreakdown> = Vec::new();
+                    for (spell_id, (spell_name, school, total, hits)) in spells {
+                        pet_total += total;
+                        pet_hits += hits;
+                        sub_abilities.push(AbilityBreakdown {
+                            spell_id: *spell_id,
+                            spell_name: spell_name.clone(),
+                            spell_school: *school,
+                            total_amount: *total,
+                            hit_count: *hits,
+                            wowhead_url: wowhead_url(*spell_id),
+                            targets: vec![],
+                            sub_abilities: vec![],
+                        });
+                    }
+                    sub_abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+                    damage_abilities.push(AbilityBreakdown {
+                        spell_id: 0,
+                        spell_name: pet_name.clone(),
+                        spell_school: 0,
+                        total_amount: pet_total,
+                        hit_count: pet_hits,
+                        wowhead_url: String::new(),
+                        targets: vec![],
+                        sub_abilities,
+                    });
+                }
+            }
+            damage_abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+            let types = damage_types::split(&damage_abilities, self.periodic_damage.get(guid), |spell_id| self.is_proc(spell_id));
+
+            let mut total_healing: u64 = 0;
+            let mut heal_abilities: Vec<AbilityBreakdown> = Vec::new();
+            if let Some(spells) = self.healing_by_player.get(guid) {
+                let player_targets = self.healing_targets.get(guid);
+                for (spell_id, (spell_name, school, total, hits)) in spells {
+                    total_healing += total;
+                    let mut targets: Vec<TargetBreakdown> = Vec::new();
+                    if let Some(pt) = player_targets {
+                        if let Some(spell_targets) = pt.get(spell_id) {
+                            for (tname, tamount) in spell_targets {
+                                targets.push(TargetBreakdown {
+                                    target_name: tname.clone(),
+                                    amount: *tamount,
+                                });
+                            }
+                        }
+                    }
+                    targets.sort_by(|a, b| b.amount.cmp(&a.amount));
+                    heal_abilities.push(AbilityBreakdown {
+                        spell_id: *spell_id,
+                        spell_name: spell_name.clone(),
+                        spell_school: *school,
+                        total_amount: *total,
+                        hit_count: *hits,
+                        wowhead_url: wowhead_url(*spell_id),
+                        targets,
+                        sub_abilities: vec![],
+                    });
+                }
+            }
+            heal_abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+
+            let total_taken = self.damage_taken_by_player.get(guid).copied().unwrap_or(0);
+            let deaths = self.player_death_counts.get(guid).copied().unwrap_or(0);
+            let dps = if duration > 0.0 { total_damage as f64 / duration } else { 0.0 };
+            let filtered_damage = total_damage.saturating_sub(self.pad_damage(guid, f64::MIN, f64::MAX));
+            let filtered_dps = if duration > 0.0 { filtered_damage as f64 / duration } else { 0.0 };
+            let hps = if duration > 0.0 { total_healing as f64 / duration } else { 0.0 };
+            let (interrupts, dispels) = self.handlers.utility.counts(guid, f64::MIN, f64::MAX);
+            let (near_deaths, saves) = self.near_death_counts(guid, f64::MIN, f64::MAX);
+
+            // Build damage taken abilities from events
+            let mut dt_map: HashMap<u64, (String, u32, u64, u32, HashMap<String, u64>)> = HashMap::new();
+            for (_, dest, spell_id, spell_name, school, amount, source) in &self.player_damage_taken_events {
+                if dest == guid {
+                    let entry = dt_map.entry(*spell_id).or_insert_with(|| (spell_name.clone(), *school, 0, 0, HashMap::new()));
+                    entry.2 += amount;
+                    entry.3 += 1;
+                    *entry.4.entry(source.clone()).or_default() += amount;
+                }
+            }
+            let mut damage_taken_abilities: Vec<AbilityBreakdown> = dt_map.into_iter().map(|(spell_id, (name, school, total, hits, sources))| {
+                let mut targets: Vec<TargetBreakdown> = sources.into_iter().map(|(sn, amt)| TargetBreakdown { target_name: sn, amount: amt }).collect();
+                targets.sort_by(|a, b| b.amount.cmp(&a.amount));
+                AbilityBreakdown { spell_id, spell_name: name, spell_school: school, total_amount: total, hit_count: hits, wowhead_url: wowhead_url(spell_id), targets, sub_abilities: vec![] }
+            }).collect();
+            damage_taken_abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+
+            players.push(PlayerSummary {
+                guid: guid.clone(),
+                name,
+                class_name,
+                spec_name,
+                role,
+                spec_inferred,
+                talents: self.player_talents.get(guid).cloned().unwrap_or_default(),
+                damage_done: total_damage,
+                healing_done: total_healing,
+                damage_taken: total_taken,
+                deaths,
+                near_deaths,
+                saves,
+                deaths_prevented: self.deaths_prevented.get(guid).copied().unwrap_or(0),
+                interrupts,
+                dispels,
+                dps,
+                hps,
+                damage_rank: 0,
+                damage_share: 0.0,
+                healing_rank: 0,
+                healing_share: 0.0,
+                abilities: damage_abilities,
+                heal_abilities,
+                damage_taken_abilities,
+                damage_spikes: self.build_damage_spikes(guid, damage_taken.get(guid.as_str()).map_or(&[], Vec::as_slice), f64::MIN, f64::MAX),
+                cast_stats: self.build_cast_stats(guid, f64::MIN, f64::MAX),
+                target_count_split: self.build_target_count_split(guid, f64::MIN, f64::MAX),
+                filtered_damage,
+                filtered_dps,
+                healing_split: self.build_healing_split(guid, f64::MIN, f64::MAX),
+                crowd_control: self.handlers.crowd_control.build(guid, f64::MIN, f64::MAX, duration, self.last_event_secs, self.fight_start_secs),
+                performance_delta: None,
+                reference_dps: None,
+                efficiency: None,
+                schools: None,
+                damage_types: Some(types),
+            });
+        }
+        infer_missing_roles(&mut players);
+        sort_players_by_role(&mut players);
+        annotate_rankings(&mut players);
+        players
+    }
+
+    /// Build buff uptime data for all players
+    /// Buff/debuff uptimes per player over `duration` seconds
+    pub fn build_buff_uptimes(&self, duration: f64) -> HashMap<String, Vec<BuffUptime>> {
+        let mut result: HashMap<String, Vec<BuffUptime>> = HashMap::new();
+
+        for (guid, spells) in &self.raw_aura_events {
+            let mut player_uptimes: Vec<BuffUptime> = Vec::new();
+
+            for (spell_id, events) in spells {
+                let spell_name = self.aura_spell_names.get(spell_id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Spell {}", spell_id));
+
+                let mut timeline: Vec<BuffEvent> = Vec::new();
+                let mut total_uptime = 0.0_f64;
+                let mut weighted_stacks = 0.0_f64;
+                let mut max_stacks: u32 = 0;
+                let mut is_active = false;
+                let mut active_since = 0.0_f64;
+                let mut current_stacks: u32 = 0;
+
+                for (time, etype, stacks) in events {
+                    timeline.push(BuffEvent {
+                        time: *time,
+                        event_type: etype.clone(),
+                        stacks: *stacks,
+                    });
+
+                    match etype.as_str() {
+                        "apply" => {
+                            if is_active {
+                                // Close previous interval
+                                let segment_dur = time - active_since;
+                                total_uptime += segment_dur;
+                                weighted_stacks += current_stacks as f64 * segment_dur;
+                            }
+                            is_active = true;
+                            active_since = *time;
+                            current_stacks = *stacks;
+                            if *stacks > max_stacks { max_stacks = *stacks; }
+                        }
+                        "remove" => {
+                            if is_active {
+                                let segment_dur = time - active_since;
+                                total_uptime += segment_dur;
+                                weighted_stacks += current_stacks as f64 * segment_dur;
+                            }
+                            is_active = false;
+                            current_stacks = 0;
+                        }
+                        "stack" => {
+                            if is_active {
+                                let segment_dur = time - active_since;
+                                total_uptime += segment_dur;
+                                weighted_stacks += current_stacks as f64 * segment_dur;
+                                active_since = *time;
+                            }
+                            current_stacks = *stacks;
+                            if *stacks > max_stacks { max_stacks = *stacks; }
+                        }
+                        _ => {}
+                    }
+                }
+
+                // Close any buff still active at encounter end
+                if is_active && duration > active_since {
+                    let remaining = duration - active_since;
+                    total_uptime += remaining;
+                    weighted_stacks += current_stacks as f64 * remaining;
+                }
+
+                if total_uptime < 0.01 { continue; }
+
+                let uptime_pct = if duration > 0.0 { (total_uptime / duration * 100.0).min(100.0) } else { 0.0 };
+                let avg_stacks = if total_uptime > 0.0 { weighted_stacks / total_uptime } else { 0.0 };
+
+                player_uptimes.push(BuffUptime {
+                    spell_id: *spell_id,
+                    spell_name,
+                    source_name: self.aura_sources.get(&(guid.clone(), *spell_id))
+                        .cloned().unwrap_or_default(),
+                    aura_type: self.aura_types.get(spell_id)
+                        .cloned().unwrap_or_else(|| "BUFF".to_string()),
+                    uptime_secs: total_uptime,
+                    uptime_pct,
+                    avg_stacks,
+                    max_stacks,
+                    wowhead_url: wowhead_url(*spell_id),
+                    timeline,
+                });
+            }
+
+            player_uptimes.sort_by(|a, b| b.uptime_pct.partial_cmp(&a.uptime_pct).unwrap_or(std::cmp::Ordering::Equal));
+            result.insert(guid.clone(), player_uptimes);
+        }
+
+        result
+    }
+
+    /// Damage done to each enemy; `boss_names` are flagged as bosses
+    pub fn build_enemy_breakdowns(&self, boss_names: &[String]) -> Vec<EnemyBreakdown> {
+        // Invert: damage_targets is player_guid -> spell_id -> target_name -> amount
+        // We want: target_name -> player_guid -> total_damage
+        let mut target_map: HashMap<String, HashMap<String, u64>> = HashMap::new();
+
+        for (player_guid, spells) in &self.damage_targets {
+            for (_spell_id, targets) in spells {
+                for (target_name, amount) in targets {
+                    *target_map.entry(target_name.clone()).or_default()
+                        .entry(player_guid.clone()).or_default() += amount;
+                }
+            }
+        }
+
+        // Lowercase boss names for matching
+        let boss_names_lower: Vec<String> = boss_names.iter().map(|n| n.to_lowercase()).collect();
+
+        let mut breakdowns: Vec<EnemyBreakdown> = target_map.into_iter().map(|(target_name, players_map)| {
+            let total_damage: u64 = players_map.values().sum();
+            let mut players: Vec<EnemyPlayerDamage> = players_map.into_iter().map(|(guid, damage)| {
+                let player_name = self.player_names.get(&guid).cloned().unwrap_or_else(|| guid.clone());
+                let spec_id = self.player_spec(&guid).map(|(id, _)| id).unwrap_or(0);
+                let class_name = spec_info(spec_id).map(|(c, _, _)| c.to_string()).unwrap_or_default();
+                EnemyPlayerDamage { player_name, class_name, damage }
+            }).collect();
+            players.sort_by_key(|p| std::cmp::Reverse(p.damage));
+            EnemyBreakdown { target_name, total_damage, kill_count: 0, mob_type: String::new(), npc_id: None, enemy_forces: 0, players }
+        }).collect();
+
+        // Enrich with kill counts and mob types
+        for enemy in &mut breakdowns {
+            enemy.kill_count = self.kill_counts.get(&enemy.target_name).copied().unwrap_or(0);
+            enemy.npc_id = self.creature_npc_ids.get(&enemy.target_name).copied();
+            enemy.enemy_forces = enemy.kill_count * self.enemy_forces_for(&enemy.target_name);
+
+            // Classify mob type
+            let creature_guid_type = self.creature_types.get(&enemy.target_name)
+                .map(|s| s.as_str()).unwrap_or("Unknown");
+            let name_lower = enemy.target_name.to_lowercase();
+
+            if creature_guid_type == "Pet" {
+                enemy.mob_type = "Pet".to_string();
+            } else if boss_names_lower.iter().any(|bn| name_lower.contains(bn) || bn.contains(&name_lower)) {
+                enemy.mob_type = "Boss".to_string();
+            } else {
+                enemy.mob_type = "Trash".to_string();
+            }
+        }
+
+        breakdowns.sort_by(|a, b| b.total_damage.cmp(&a.total_damage));
+        breakdowns
+    }
+
+    /// Track damage an enemy dealt to a player (player-vs-player damage is ignored)
+    fn record_enemy_damage(&mut self, source_guid: &str, source_name: &str, player_guid: &str, amount: u64) {
+        if guid::is_player(source_guid) || source_name.is_empty() {
+            return;
+        }
+        *self.enemy_damage_to_players
+            .entry(source_name.to_string()).or_default()
+            .entry(player_guid.to_string()).or_insert(0) += amount;
+    }
+
+    /// An enemy spell hitting a player, for the mechanics table
+    fn record_mechanic_hit(&mut self, source_guid: &str, source_name: &str, spell_id: u64, spell_name: &str, amount: u64) {
+        if guid::is_player(source_guid) || source_name.is_empty() {
+            return;
+        }
+        let entry = self.mechanic_damage.entry(spell_id).or_insert_with(|| (spell_name.to_string(), 0, 0, Vec::new()));
+        entry.1 += amount;
+        entry.2 += 1;
+        if !entry.3.iter().any(|name| name == source_name) {
+            entry.3.push(source_name.to_string());
+        }
+    }
+
+    /// Damage-taken events per player, in log order, so per-pull spikes only look at
+    /// that player's hits
+    fn damage_taken_by_player(&self) -> HashMap<&str, Vec<&DamageTakenEvent>> {
+        let mut by_player: HashMap<&str, Vec<&DamageTakenEvent>> = HashMap::new();
+        for event in &self.player_damage_taken_events {
+            by_player.entry(event.1.as_str()).or_default().push(event);
+        }
+        by_player
+    }
+
+    /// Worst damage-taken windows for a player within [from_secs, to_secs]:
+    /// the biggest non-overlapping SPIKE_WINDOW_SECS spans, with their abilities.
+    /// `taken` is the player's bucket from `damage_taken_by_player`.
+    fn build_damage_spikes(&self, guid: &str, taken: &[&DamageTakenEvent], from_secs: f64, to_secs: f64) -> Vec<DamageSpike> {
+        const SPIKE_WINDOW_SECS: f64 = 3.0;
+        const MAX_SPIKES: usize = 3;
+
+        let first = taken.partition_point(|e| e.0 < from_secs);
+        let last = taken.partition_point(|e| e.0 <= to_secs).max(first);
+        let hits = &taken[first..last];
+        if hits.is_empty() {
+            return Vec::new();
+        }
+
+        // Sliding window: total damage of every window starting at a hit
+        let mut windows: Vec<(usize, usize, u64)> = Vec::new();
+        let mut end = 0;
+        let mut total: u64 = 0;
+        for start in 0..hits.len() {
+            while end < hits.len() && hits[end].0 < hits[start].0 + SPIKE_WINDOW_SECS {
+                total += hits[end].5;
+                end += 1;
+            }
+            windows.push((start, end, total));
+            total -= hits[start].5;
+        }
+        windows.sort_by_key(|w| std::cmp::Reverse(w.2));
+
+        let mut spikes: Vec<DamageSpike> = Vec::new();
+        let mut taken: Vec<(f64, f64)> = Vec::new();
+        for (start, end, total) in windows {
+            if spikes.len() >= MAX_SPIKES {
+                break;
+            }
+            let window_start = hits[start].0;
+            let window_end = window_start + SPIKE_WINDOW_SECS;
+            if taken.iter().any(|&(s, e)| window_start < e && s < window_end) {
+                continue;
+            }
+            taken.push((window_start, window_end));
+
+            let mut by_spell: HashMap<u64, (String, String, u64)> = HashMap::new();
+            for hit in &hits[start..end] {
+                by_spell.entry(hit.2).or_insert_with(|| (hit.3.clone(), hit.6.clone(), 0)).2 += hit.5;
+            }
+            let mut abilities: Vec<SpikeAbility> = by_spell.into_iter()
+                .map(|(spell_id, (spell_name, source_name, amount))| SpikeAbility { spell_id, spell_name, source_name, amount })
+                .collect();
+            abilities.sort_by_key(|a| std::cmp::Reverse(a.amount));
+
+            let start_offset = window_start - self.fight_start_secs;
+            let ended_in_death = self.death_events.iter().any(|d| {
+                d.player_guid == guid
+                    && from_millis(d.time_ms) >= start_offset
+                    && from_millis(d.time_ms) <= start_offset + SPIKE_WINDOW_SECS + 1.0
+            });
+            spikes.push(DamageSpike {
+                start_offset_secs: start_offset,
+                window_secs: SPIKE_WINDOW_SECS,
+                total_damage: total,
+                ended_in_death,
+                abilities,
+            });
+        }
+        spikes
+    }
+
+    /// Tank by COMBATANT_INFO spec (inferred specs are too unreliable mid-fight)
+    fn is_tank(&self, guid: &str) -> bool {
+        self.player_specs.get(guid)
+            .and_then(|&spec_id| spec_info(spec_id))
+            .is_some_and(|(_, _, role)| role == "tank")
+    }
+
+    /// Follow an enemy's melee target; moving from one tank to another is a tank swap,
+    /// credited to a taunt if the new tank cast one shortly before
+    fn record_melee_target(&mut self, enemy_guid: &str, enemy_name: &str, player_guid: &str, timestamp_secs: f64, start_secs: f64) {
+        const TAUNT_LEAD_SECS: f64 = 3.0;
+
+        if !self.is_tank(player_guid) {
+            return;
+        }
+        let Some(previous) = self.melee_targets.insert(enemy_guid.to_string(), player_guid.to_string()) else {
+            return;
+        };
+        if previous == player_guid {
+            return;
+        }
+        let taunt_spell = self.taunt_casts.iter().rev()
+            .take_while(|(ts, ..)| timestamp_secs - ts <= TAUNT_LEAD_SECS)
+            .find(|(_, caster, _, target)| caster == player_guid && target == enemy_guid)
+            .and_then(|(_, _, spell_id, _)| game_data::taunt_name(*spell_id))
+            .map(|name| name.to_string());
+        let debuff = self.active_aura_stacks.get(&previous)
+            .into_iter()
+            .flatten()
+            .filter(|(spell_id, _)| self.aura_types.get(spell_id).is_some_and(|t| t == "DEBUFF"))
+            .max_by_key(|(_, stacks)| **stacks)
+            .filter(|(_, stacks)| **stacks > 0);
+        let (debuff_name, debuff_stacks) = match debuff {
+            Some((spell_id, stacks)) => (self.aura_spell_names.get(spell_id).cloned(), *stacks),
+            None => (None, 0),
+        };
+        let name_of = |guid: &str| self.player_names.get(guid).cloned().unwrap_or_else(|| guid.to_string());
+        self.tank_swaps.push(TankSwap {
+            offset_secs: timestamp_secs - start_secs,
+            enemy_name: enemy_name.to_string(),
+            from_tank: name_of(&previous),
+            to_tank: name_of(player_guid),
+            taunt_spell,
+            debuff_name,
+            debuff_stacks,
+            late: debuff_stacks > self.options.taunt_stack_threshold,
+        });
+    }
+
+    /// The biggest raid-wide damage windows (non-overlapping, several players hit) and the
+    /// raid healing cooldowns active during each, in fight order
+    fn build_healer_cd_coverage(&self) -> Vec<RaidDamageEvent> {
+        const RAID_DAMAGE_WINDOW_SECS: f64 = 5.0;
+        const MAX_RAID_DAMAGE_EVENTS: usize = 8;
+        /// Fewer players hit is a tank buster or a soak, not raid damage
+        const MIN_PLAYERS_HIT: usize = 3;
+
+        let hits = &self.player_damage_taken_events;
+        if hits.is_empty() {
+            return Vec::new();
+        }
+
+        // Sliding window: total damage of every window starting at a hit
+        let mut windows: Vec<(usize, usize, u64)> = Vec::new();
+        let mut end = 0;
+        let mut total: u64 = 0;
+        for start in 0..hits.len() {
+            while end < hits.len() && hits[end].0 < hits[start].0 + RAID_DAMAGE_WINDOW_SECS {
+                total += hits[end].5;
+                end += 1;
+            }
+            windows.push((start, end, total));
+            total -= hits[start].5;
+        }
+        windows.sort_by_key(|w| std::cmp::Reverse(w.2));
+
+        let mut events: Vec<RaidDamageEvent> = Vec::new();
+        let mut taken: Vec<(f64, f64)> = Vec::new();
+        for (start, end, total) in windows {
+            if events.len() >= MAX_RAID_DAMAGE_EVENTS {
+                break;
+            }
+            let window_start = hits[start].0;
+            let window_end = window_start + RAID_DAMAGE_WINDOW_SECS;
+            if taken.iter().any(|&(s, e)| window_start < e && s < window_end) {
+                continue;
+            }
+            let players_hit = hits[start..end].iter().map(|h| h.1.as_str()).collect::<std::collections::HashSet<_>>().len();
+            if players_hit < MIN_PLAYERS_HIT {
+                continue;
+            }
+            taken.push((window_start, window_end));
+
+            let mut by_spell: HashMap<u64, (String, String, u64)> = HashMap::new();
+            for hit in &hits[start..end] {
+                by_spell.entry(hit.2).or_insert_with(|| (hit.3.clone(), hit.6.clone(), 0)).2 += hit.5;
+            }
+            let mut abilities: Vec<SpikeAbility> = by_spell.into_iter()
+                .map(|(spell_id, (spell_name, source_name, amount))| SpikeAbility { spell_id, spell_name, source_name, amount })
+                .collect();
+            abilities.sort_by_key(|a| std::cmp::Reverse(a.amount));
+            abilities.truncate(3);
+
+            let cooldowns: Vec<HealerCdUse> = self.raid_cd_casts.iter().filter_map(|(ts, caster, spell_id)| {
+                let (name, duration) = game_data::raid_healing_cooldown(*spell_id)?;
+                if *ts > window_end || ts + duration < window_start {
+                    return None;
+                }
+                Some(HealerCdUse {
+                    spell_id: *spell_id,
+                    spell_name: name.to_string(),
+                    caster_name: self.player_names.get(caster).cloned().unwrap_or_else(|| caster.clone()),
+                    offset_secs: ts - self.fight_start_secs,
+                })
+            }).collect();
+
+            events.push(RaidDamageEvent {
+                start_offset_secs: window_start - self.fight_start_secs,
+                window_secs: RAID_DAMAGE_WINDOW_SECS,
+                total_damage: total,
+                players_hit: players_hit as u32,
+                abilities,
+                covered: !cooldowns.is_empty(),
+                cooldowns,
+            });
+        }
+        events.sort_by(|a, b| a.start_offset_secs.total_cmp(&b.start_offset_secs));
+        events
+    }
+
+    fn is_avoidable(&self, spell_id: u64) -> bool {
+        game_data::AVOIDABLE_SPELLS.contains(&spell_id) || self.options.avoidable_spell_ids.contains(&spell_id)
+    }
+
+    fn is_proc(&self, spell_id: u64) -> bool {
+        game_data::PROC_SPELLS.contains(&spell_id) || self.options.proc_spell_ids.contains(&spell_id)
+    }
+
+    /// A configured dangerous ability, or any hit for at least `major_hit_pct` of max HP
+    fn is_dangerous_hit(&self, spell_id: u64, amount: u64, max_hp: u64) -> bool {
+        self.options.dangerous_spell_ids.contains(&spell_id)
+            || (max_hp > 0 && amount as f64 >= max_hp as f64 * self.options.major_hit_pct / 100.0)
+    }
+
+    /// Auras up on a player at `offset_secs` (from the start of the fight), from the
+    /// recorded aura history. Empty when buff timelines are off.
+    fn auras_at(&self, guid: &str, offset_secs: f64) -> Vec<u64> {
+        let Some(spells) = self.raw_aura_events.get(guid) else {
+            return Vec::new();
+        };
+        spells.iter()
+            .filter(|(_, events)| {
+                let seen = events.partition_point(|(time, ..)| *time <= offset_secs);
+                seen > 0 && events[seen - 1].1 != "remove"
+            })
+            .map(|(spell_id, _)| *spell_id)
+            .collect()
+    }
+
+    /// Dangerous hits per player and ability, the most taken without a defensive first
+    fn build_defensive_coverage(&self) -> Vec<DefensiveCoverage> {
+        if !self.options.features.buff_timelines {
+            return Vec::new();
+        }
+        let mut coverage: Vec<DefensiveCoverage> = Vec::new();
+        for (offset_secs, guid, name, spell_id, spell_name, amount) in &self.dangerous_hits {
+            let defended = self.auras_at(guid, *offset_secs).into_iter().any(game_data::is_defensive_aura);
+            let entry = match coverage.iter_mut().position(|c| c.player_guid == *guid && c.spell_id == *spell_id) {
+                Some(i) => &mut coverage[i],
+                None => {
+                    coverage.push(DefensiveCoverage {
+                        player_guid: guid.clone(),
+                        player_name: name.clone(),
+                        spell_id: *spell_id,
+                        spell_name: spell_name.clone(),
+                        hits: 0,
+                        undefended: 0,
+                        undefended_damage: 0,
+                    });
+                    coverage.last_mut().unwrap()
+                }
+            };
+            entry.hits += 1;
+            if !defended {
+                entry.undefended += 1;
+                entry.undefended_damage += amount;
+            }
+        }
+        coverage.sort_by_key(|c| std::cmp::Reverse((c.undefended, c.hits)));
+        coverage
+    }
+
+    /// Avoidable damage per player, and hits binned by where they landed
+    fn build_avoidable_damage(&self) -> AvoidableDamage {
+        /// Side of a heat map cell, in yards
+        const HEAT_CELL_YARDS: f64 = 5.0;
+
+        let mut players: HashMap<&str, AvoidableTally> = HashMap::new();
+        let mut cells: HashMap<(i64, i64), (u32, u64)> = HashMap::new();
+        for hit in &self.avoidable_hits {
+            let player = players.entry(hit.player_name.as_str()).or_default();
+            player.hits += 1;
+            player.damage += hit.amount;
+            player.spells.entry(hit.spell_id).or_insert((hit.spell_name.as_str(), 0)).1 += hit.amount;
+            if let (Some(x), Some(y)) = (hit.pos_x, hit.pos_y) {
+                let cell = cells.entry(((x / HEAT_CELL_YARDS).floor() as i64, (y / HEAT_CELL_YARDS).floor() as i64)).or_default();
+                cell.0 += 1;
+                cell.1 += hit.amount;
+            }
+        }
+
+        let mut players: Vec<AvoidablePlayer> = players.into_iter().map(|(name, tally)| AvoidablePlayer {
+            player_name: name.to_string(),
+            hits: tally.hits,
+            damage: tally.damage,
+            top_spell: tally.spells.into_values().max_by_key(|(_, amount)| *amount).map(|(spell, _)| spell.to_string()).unwrap_or_default(),
+        }).collect();
+        players.sort_by_key(|p| std::cmp::Reverse(p.damage));
+        let mut heat: Vec<HitHeatCell> = cells.into_iter().map(|((cx, cy), (hits, damage))| HitHeatCell {
+            pos_x: (cx as f64 + 0.5) * HEAT_CELL_YARDS,
+            pos_y: (cy as f64 + 0.5) * HEAT_CELL_YARDS,
+            hits,
+            damage,
+        }).collect();
+        heat.sort_by(|a, b| b.hits.cmp(&a.hits).then(b.damage.cmp(&a.damage)));
+
+        AvoidableDamage {
+            total_damage: self.avoidable_hits.iter().map(|h| h.amount).sum(),
+            hits: self.avoidable_hits.len() as u32,
+            players,
+            heat,
+        }
+    }
+
+    /// Priority target damage per NPC ID, aggregated over all spawns. DPS is measured
+    /// over the targets' combined lifetime (first hit until death or last hit).
+    fn build_priority_targets(&self) -> Vec<PriorityTarget> {
+        let mut by_npc: HashMap<u64, Vec<&PrioritySpawn>> = HashMap::new();
+        for spawn in self.priority_spawns.values() {
+            by_npc.entry(spawn.npc_id).or_default().push(spawn);
+        }
+        let mut result: Vec<PriorityTarget> = by_npc.into_iter().map(|(npc_id, spawns)| {
+            let lifetime: f64 = spawns.iter().map(|s| s.last_secs - s.first_secs).sum();
+            let mut damage: HashMap<&str, u64> = HashMap::new();
+            for spawn in &spawns {
+                for (guid, amount) in &spawn.damage_by_player {
+                    *damage.entry(guid).or_insert(0) += amount;
+                }
+            }
+            let mut players: Vec<PriorityTargetPlayer> = damage.into_iter().map(|(guid, amount)| PriorityTargetPlayer {
+                guid: guid.to_string(),
+                name: self.player_names.get(guid).cloned().unwrap_or_else(|| guid.to_string()),
+                damage: amount,
+                dps: if lifetime > 0.0 { amount as f64 / lifetime } else { 0.0 },
+            }).collect();
+            players.sort_by_key(|p| std::cmp::Reverse(p.damage));
+            PriorityTarget {
+                npc_id,
+                name: spawns[0].name.clone(),
+                spawns: spawns.len() as u32,
+                kills: spawns.iter().filter(|s| s.died).count() as u32,
+                avg_lifetime_secs: lifetime / spawns.len() as f64,
+                total_damage: players.iter().map(|p| p.damage).sum(),
+                players,
+            }
+        }).collect();
+        result.sort_by_key(|t| std::cmp::Reverse(t.total_damage));
+        result
+    }
+
+    /// Build the enemy -> player damage matrix, biggest hitters first
+    fn build_enemy_damage_taken(&self) -> Vec<EnemyDamageTaken> {
+        let mut enemies: Vec<EnemyDamageTaken> = self.enemy_damage_to_players.iter().map(|(enemy_name, players_map)| {
+            let mut players: Vec<EnemyPlayerDamage> = players_map.iter().map(|(guid, damage)| {
+                let player_name = self.player_names.get(guid).cloned().unwrap_or_else(|| guid.clone());
+                let class_name = self.player_spec(guid)
+                    .and_then(|(id, _)| spec_info(id))
+                    .map(|(c, _, _)| c.to_string())
+                    .unwrap_or_default();
+                EnemyPlayerDamage { player_name, class_name, damage: *damage }
+            }).collect();
+            players.sort_by(|a, b| b.damage.cmp(&a.damage).then_with(|| a.player_name.cmp(&b.player_name)));
+            EnemyDamageTaken {
+                enemy_name: enemy_name.clone(),
+                total_damage: players.iter().map(|p| p.damage).sum(),
+                players,
+            }
+        }).collect();
+        enemies.sort_by(|a, b| b.total_damage.cmp(&a.total_damage).then_with(|| a.enemy_name.cmp(&b.enemy_name)));
+        enemies
+    }
+
+    /// Values of the custom metric rules applying to encounter `encounter_id`
+    fn build_custom_metrics(&self, encounter_id: u64, players: &[PlayerSummary]) -> Vec<CustomMetric> {
+        self.handlers.custom_metrics.build(encounter_id, players)
+    }
+
+    /// Enemy casts between `start_secs` and `end_secs`, on the replay clock (seconds from
+    /// `start_secs`)
+    fn build_enemy_casts(&self, start_secs: f64, end_secs: f64) -> Vec<EnemyCast> {
+        self.handlers.enemy_casts.casts().iter()
+            .filter(|c| c.start_secs >= start_secs && c.start_secs <= end_secs)
+            .map(|c| EnemyCast {
+                start_secs: c.start_secs - start_secs,
+                end_secs: c.end_secs.unwrap_or(end_secs).min(end_secs) - start_secs,
+                enemy_name: c.enemy_name.clone(),
+                spell_id: c.spell_id,
+                spell_name: c.spell_name.clone(),
+                outcome: c.outcome.unwrap_or("unfinished").to_string(),
+            })
+            .collect()
+    }
+
+    /// Untargeted enemy casts between `start_secs` and `end_secs`, on the replay clock
+    fn build_mechanic_markers(&self, start_secs: f64, end_secs: f64) -> Vec<MechanicMarker> {
+        self.handlers.enemy_casts.markers().iter()
+            .filter(|(ts, ..)| *ts >= start_secs && *ts <= end_secs)
+            .map(|(ts, enemy_name, spell_id, spell_name, pos_x, pos_y)| MechanicMarker {
+                offset_secs: ts - start_secs,
+                enemy_name: enemy_name.clone(),
+                spell_id: *spell_id,
+                spell_name: spell_name.clone(),
+                pos_x: *pos_x,
+                pos_y: *pos_y,
+            })
+            .collect()
+    }
+
+    /// Damage taken from each enemy spell across the group, most damage first, with the
+    /// deaths it landed the killing blow of
+    fn build_mechanics(&self) -> Vec<Mechanic> {
+        let mut mechanics: Vec<Mechanic> = self.mechanic_damage.iter().map(|(&spell_id, (spell_name, damage, hits, enemies))| {
+            let deaths = self.death_events.iter()
+                .filter(|d| d.killing_blow_spell.as_deref() == Some(spell_name.as_str())
+                    && d.killing_blow_source.as_ref().is_some_and(|source| enemies.contains(source)))
+                .count() as u32;
+            let mut enemies = enemies.clone();
+            enemies.sort();
+            Mechanic {
+                spell_id,
+                spell_name: spell_name.clone(),
+                wowhead_url: wowhead_url(spell_id),
+                enemies,
+                total_damage: *damage,
+                hits: *hits,
+                average_hit: if *hits > 0 { *damage / *hits as u64 } else { 0 },
+                deaths,
+            }
+        }).collect();
+        sort_mechanics(&mut mechanics);
+        mechanics
+    }
+
+    /// Build individual pulls from NPC damage events by detecting combat gaps
+    fn build_pulls(&self, segment_start_secs: f64) -> Vec<TrashPull> {
+        if self.npc_damage_events.is_empty() {
+            return Vec::new();
+        }
+
+        const PULL_GAP_SECS: f64 = 4.0;
+
+        // First pass: detect pull time ranges and enemies
+        struct PullRange {
+            start: f64,
+            end: f64,
+            enemies: HashMap<String, (u64, String)>,
+        }
+
+        let mut ranges: Vec<PullRange> = Vec::new();
+        let mut current_start: f64 = self.npc_damage_events[0].0;
+        let mut current_end: f64 = current_start;
+        let mut current_enemies: HashMap<String, (u64, String)> = HashMap::new();
+
+        for (ts, name, dmg, ctype) in &self.npc_damage_events {
+            if *ts - current_end > PULL_GAP_SECS && !current_enemies.is_empty() {
+                ranges.push(PullRange { start: current_start, end: current_end, enemies: current_enemies });
+                current_start = *ts;
+                current_enemies = HashMap::new();
+            }
+            current_end = *ts;
+            let entry = current_enemies.entry(name.clone()).or_insert((0, ctype.clone()));
+            entry.0 += dmg;
+        }
+        if !current_enemies.is_empty() {
+            ranges.push(PullRange { start: current_start, end: current_end, enemies: current_enemies });
+        }
+
+        // Second pass: build per-pull player damage from player_damage_events
+        let damage_taken = self.damage_taken_by_player();
+        let mut pulls: Vec<TrashPull> = Vec::new();
+        for (pi, range) in ranges.iter().enumerate() {
+            // Deaths can land shortly after the last damage event — credit them until the next pull starts
+            let kill_window_end = ranges.get(pi + 1)
+                .map(|next| next.start)
+                .unwrap_or(f64::MAX)
+                .min(range.end + PULL_GAP_SECS);
+            let mut kills: HashMap<&str, u32> = HashMap::new();
+            for (ts, name) in &self.npc_death_events {
+                if *ts >= range.start && *ts <= kill_window_end {
+                    *kills.entry(name.as_str()).or_default() += 1;
+                }
+            }
+            let mut enemies: Vec<PullEnemy> = range.enemies.iter()
+                .map(|(name, (damage, mob_type))| PullEnemy {
+                    name: name.clone(),
+                    damage_taken: *damage,
+                    mob_type: mob_type.clone(),
+                    enemy_forces: kills.get(name.as_str()).copied().unwrap_or(0) * self.enemy_forces_for(name),
+                })
+                .collect();
+            enemies.sort_by(|a, b| b.damage_taken.cmp(&a.damage_taken));
+            let pull_forces: u32 = enemies.iter().map(|e| e.enemy_forces).sum();
+
+            // Sum player damage within this pull's time range
+            let mut player_damage: HashMap<String, u64> = HashMap::new();
+            for (ts, guid, amount) in &self.player_damage_events {
+                if *ts >= range.start && *ts <= range.end {
+                    *player_damage.entry(guid.clone()).or_default() += amount;
+                }
+            }
+
+            // Sum player healing within this pull's time range
+            let mut player_healing: HashMap<String, u64> = HashMap::new();
+            for (ts, guid, amount) in &self.player_healing_events {
+                if *ts >= range.start && *ts <= range.end {
+                    *player_healing.entry(guid.clone()).or_default() += amount;
+                }
+            }
+
+            // Collect all player GUIDs seen in damage or healing
+            let mut all_guids: std::collections::HashSet<String> = std::collections::HashSet::new();
+            for guid in player_damage.keys() { all_guids.insert(guid.clone()); }
+            for guid in player_healing.keys() { all_guids.insert(guid.clone()); }
+
+            // Build per-pull ability breakdowns from ability events
+            // damage abilities: guid -> spell_id -> (spell_name, spell_school, total, hits, targets)
+            let mut pull_dmg_abilities: HashMap<String, HashMap<u64, (String, u32, u64, u32, HashMap<String, u64>)>> = HashMap::new();
+            for (ts, guid, spell_id, spell_name, school, amount, target) in &self.player_ability_events {
+                if *ts >= range.start && *ts <= range.end {
+                    let entry = pull_dmg_abilities.entry(guid.clone()).or_default()
+                        .entry(*spell_id).or_insert_with(|| (spell_name.clone(), *school, 0, 0, HashMap::new()));
+                    entry.2 += amount;
+                    entry.3 += 1;
+                    *entry.4.entry(target.clone()).or_default() += amount;
+                }
+            }
+            // heal abilities
+            let mut pull_heal_abilities: HashMap<String, HashMap<u64, (String, u32, u64, u32, HashMap<String, u64>)>> = HashMap::new();
+            for (ts, guid, spell_id, spell_name, school, amount, target) in &self.player_heal_ability_events {
+                if *ts >= range.start && *ts <= range.end {
+                    let entry = pull_heal_abilities.entry(guid.clone()).or_default()
+                        .entry(*spell_id).or_insert_with(|| (spell_name.clone(), *school, 0, 0, HashMap::new()));
+                    entry.2 += amount;
+                    entry.3 += 1;
+                    *entry.4.entry(target.clone()).or_default() += amount;
+                }
+            }
+            // damage taken abilities (track source in target_name field of TargetBreakdown)
+            let mut pull_dt_abilities: HashMap<String, HashMap<u64, (String, u32, u64, u32, HashMap<String, u64>)>> = HashMap::new();
+            for (ts, dest, spell_id, spell_name, school, amount, source) in &self.player_damage_taken_events {
+                if *ts >= range.start && *ts <= range.end {
+                    let entry = pull_dt_abilities.entry(dest.clone()).or_default()
+                        .entry(*spell_id).or_insert_with(|| (spell_name.clone(), *school, 0, 0, HashMap::new()));
+                    entry.2 += amount;
+                    entry.3 += 1;
+                    *entry.4.entry(source.clone()).or_default() += amount;
+                }
+            }
+
+            let pull_duration = (range.end - range.start).max(1.0);
+            let mut players: Vec<PlayerSummary> = all_guids.into_iter()
+                .map(|guid| {
+                    let name = self.player_names.get(&guid).cloned().unwrap_or_else(|| guid.clone());
+                    let spec = self.player_spec(&guid);
+                    let spec_inferred = spec.map(|(_, inferred)| inferred).unwrap_or(false);
+                    let talents = self.player_talents.get(&guid).cloned().unwrap_or_default();
+                    let (class_name, spec_name, role) = spec
+                        .and_then(|(id, _)| spec_info(id))
+                        .map(|(c, s, r)| (c.to_string(), s.to_string(), r.to_string()))
+                        .unwrap_or_else(|| (String::new(), String::new(), String::new()));
+                    let dmg = player_damage.get(&guid).copied().unwrap_or(0);
+                    let heal = player_healing.get(&guid).copied().unwrap_or(0);
+                    // Build damage abilities for this player in this pull
+                    let mut abilities: Vec<AbilityBreakdown> = pull_dmg_abilities.get(&guid)
+                        .map(|spells| spells.iter().map(|(spell_id, (name, school, total, hits, targets))| {
+                            let mut target_vec: Vec<TargetBreakdown> = targets.iter()
+                                .map(|(tn, amt)| TargetBreakdown { target_name: tn.clone(), amount: *amt })
+                                .collect();
+                            target_vec.sort_by(|a, b| b.amount.cmp(&a.amount));
+                            AbilityBreakdown {
+                                spell_id: *spell_id,
+                                spell_name: name.clone(),
+                                spell_school: *school,
+                                total_amount: *total,
+                                hit_count: *hits,
+                                wowhead_url: format!("https://www.wowhead.com/spell={}", spell_id),
+                                targets: target_vec,
+                                sub_abilities: vec![],
+                            }
+                        }).collect())
+                        .unwrap_or_default();
+                    abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+                    // Build heal abilities
+                    let mut heal_abilities: Vec<AbilityBreakdown> = pull_heal_abilities.get(&guid)
+                        .map(|spells| spells.iter().map(|(spell_id, (name, school, total, hits, targets))| {
+                            let mut target_vec: Vec<TargetBreakdown> = targets.iter()
+                                .map(|(tn, amt)| TargetBreakdown { target_name: tn.clone(), amount: *amt })
+                                .collect();
+                            target_vec.sort_by(|a, b| b.amount.cmp(&a.amount));
+                            AbilityBreakdown {
+                                spell_id: *spell_id,
+                                spell_name: name.clone(),
+                                spell_school: *school,
+                                total_amount: *total,
+                                hit_count: *hits,
+                                wowhead_url: format!("https://www.wowhead.com/spell={}", spell_id),
+                                targets: target_vec,
+                                sub_abilities: vec![],
+                            }
+                        }).collect())
+                        .unwrap_or_default();
+                    heal_abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+                    heal_abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+                    // Build damage taken abilities
+                    let mut damage_taken_abilities: Vec<AbilityBreakdown> = pull_dt_abilities.get(&guid)
+                        .map(|spells| spells.iter().map(|(spell_id, (name, school, total, hits, sources))| {
+                            let mut source_vec: Vec<TargetBreakdown> = sources.iter()
+                                .map(|(sn, amt)| TargetBreakdown { target_name: sn.clone(), amount: *amt })
+                                .collect();
+                            source_vec.sort_by(|a, b| b.amount.cmp(&a.amount));
+                            AbilityBreakdown {
+                                spell_id: *spell_id,
+                                spell_name: name.clone(),
+                                spell_school: *school,
+                                total_amount: *total,
+                                hit_count: *hits,
+                                wowhead_url: format!("https://www.wowhead.com/spell={}", spell_id),
+                                targets: source_vec,
+                                sub_abilities: vec![],
+                            }
+                        }).collect())
+                        .unwrap_or_default();
+                    damage_taken_abilities.sort_by(|a, b| b.total_amount.cmp(&a.total_amount));
+                    
+                    let total_taken = damage_taken_abilities.iter().map(|a| a.total_amount).sum();
+                    let damage_spikes = self.build_damage_spikes(&guid, damage_taken.get(guid.as_str()).map_or(&[], Vec::as_slice), range.start, range.end);
+                    let target_count_split = self.build_target_count_split(&guid, range.start, range.end);
+                    let filtered_damage = dmg.saturating_sub(self.pad_damage(&guid, range.start, range.end));
+                    let healing_split = self.build_healing_split(&guid, range.start, range.end);
+                    let crowd_control = self.handlers.crowd_control.build(&guid, range.start, range.end, pull_duration, self.last_event_secs, self.fight_start_secs);
+                    let (interrupts, dispels) = self.handlers.utility.counts(&guid, range.start, range.end);
+                    let (near_deaths, saves) = self.near_death_counts(&guid, range.start, range.end);
+                    let cast_stats = self.build_cast_stats(&guid, range.start, range.end);
+
+                    PlayerSummary {
+                        guid,
+                        name,
+                        class_name,
+                        spec_name,
+                        role,
+                        spec_inferred,
+                        talents,
+                        damage_done: dmg,
+                        healing_done: heal,
+                        damage_taken: total_taken,
+                        deaths: 0,
+                        near_deaths,
+                        saves,
+                        deaths_prevented: 0,
+                        interrupts,
+                        dispels,
+                        dps: dmg as f64 / pull_duration,
+                        hps: heal as f64 / pull_duration,
+                        damage_rank: 0,
+                        damage_share: 0.0,
+                        healing_rank: 0,
+                        healing_share: 0.0,
+                        abilities,
+                        heal_abilities,
+                        damage_taken_abilities,
+                        damage_spikes,
+                        cast_stats,
+                        target_count_split,
+                        filtered_damage,
+                        filtered_dps: filtered_damage as f64 / pull_duration,
+                        healing_split,
+                        crowd_control,
+                        performance_delta: None,
+                        reference_dps: None,
+                        efficiency: None,
+                        schools: None,
+                        damage_types: None,
+                    }
+                })
+                .collect();
+            infer_missing_roles(&mut players);
+            sort_players_by_role(&mut players);
+            annotate_rankings(&mut players);
+
+            // Deaths within this pull's time range
+            let pull_deaths: Vec<DeathEvent> = self.death_events.iter()
+                .filter(|d| {
+                    let died = segment_start_secs + from_millis(d.time_ms);
+                    died >= range.start && died <= range.end
+                })
+                .cloned()
+                .collect();
+
+            // The opener runs from the end of the previous pull
+            let opener_from = if pi == 0 { segment_start_secs } else { ranges[pi - 1].end };
+            let opener = self.build_pull_opener(opener_from, range.start, range.end);
+            let tanking = self.build_enemy_tanking(range.start, range.end);
+
+            pulls.push(TrashPull {
+                pull_index: pi,
+                duration_secs: range.end - range.start,
+                start_time_offset: range.start - segment_start_secs,
+                enemy_forces: pull_forces,
+                enemies,
+                players,
+                deaths: pull_deaths,
+                opener,
+                tanking,
+            });
+        }
+
+        pulls
+    }
+
+    /// Opener of a pull starting at `pull_start`: events after `from_secs` (the previous
+    /// pull's end) up to `pull_end`. A redirect counts if cast within its 30 second window.
+    fn build_pull_opener(&self, from_secs: f64, pull_start: f64, pull_end: f64) -> PullOpener {
+        const REDIRECT_WINDOW_SECS: f64 = 30.0;
+
+        let in_pull = |ts: f64| ts > from_secs && ts <= pull_end;
+        let name_of = |guid: &str| self.player_names.get(guid).cloned().unwrap_or_else(|| guid.to_string());
+        let opened_by = self.player_damage_events.iter()
+            .find(|(ts, ..)| in_pull(*ts))
+            .map(|(_, guid, _)| name_of(guid));
+        let first_targets: Vec<FirstTarget> = self.handlers.opener.first_melee_targets().iter()
+            .filter(|(ts, ..)| in_pull(*ts))
+            .map(|(ts, enemy_name, player_guid)| FirstTarget {
+                offset_secs: ts - pull_start,
+                enemy_name: enemy_name.clone(),
+                player_name: name_of(player_guid),
+                on_tank: self.is_tank(player_guid),
+            })
+            .collect();
+        let aggro_lost = first_targets.iter().any(|t| !t.on_tank);
+
+        let mut early_damage: HashMap<&str, u64> = HashMap::new();
+        for (_, guid, amount) in self.player_damage_events.iter()
+            .filter(|(ts, ..)| *ts >= pull_start && *ts < pull_start + EARLY_DAMAGE_SECS)
+        {
+            *early_damage.entry(guid.as_str()).or_default() += amount;
+        }
+        let tank_damage = early_damage.iter().filter(|(guid, _)| self.is_tank(guid)).map(|(_, damage)| *damage).max().unwrap_or(0);
+        let top_early_damage = early_damage.into_iter()
+            .filter(|(guid, _)| !self.is_tank(guid))
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(guid, damage)| EarlyDamage { player_name: name_of(guid), damage, tank_damage });
+
+        let taunts = self.taunt_casts.iter()
+            .filter(|(ts, ..)| in_pull(*ts))
+            .map(|(ts, caster, spell_id, target_guid)| OpenerCast {
+                offset_secs: ts - pull_start,
+                caster_name: name_of(caster),
+                spell_id: *spell_id,
+                spell_name: game_data::taunt_name(*spell_id).unwrap_or_default().to_string(),
+                target_name: self.enemy_activity.get(target_guid).map(|a| a.name.clone()).unwrap_or_default(),
+            })
+            .collect();
+        let redirects = self.handlers.opener.redirect_casts().iter()
+            .filter(|(ts, ..)| in_pull(*ts) && *ts >= pull_start - REDIRECT_WINDOW_SECS)
+            .map(|(ts, caster, spell_id, target_name)| OpenerCast {
+                offset_secs: ts - pull_start,
+                caster_name: name_of(caster),
+                spell_id: *spell_id,
+                spell_name: game_data::threat_redirect_name(*spell_id).unwrap_or_default().to_string(),
+                target_name: target_name.clone(),
+            })
+            .collect();
+        PullOpener { opened_by, first_targets, taunts, redirects, top_early_damage, aggro_lost }
+    }
+
+    /// Who each enemy hitting players between `pull_start` and `pull_end` was attacking. A
+    /// window with a tie keeps the previous target, so AoE hitting everyone equally isn't a
+    /// swap. A span lasts until the next one starts, the last one until the enemy's last hit.
+    fn build_enemy_tanking(&self, pull_start: f64, pull_end: f64) -> Vec<EnemyTanking> {
+        let name_of = |guid: &str| self.player_names.get(guid).cloned().unwrap_or_else(|| guid.to_string());
+        let mut enemies: Vec<(f64, EnemyTanking)> = Vec::new();
+        for (enemy_guid, enemy_name, buckets) in self.handlers.tanking.enemies() {
+            let mut spans: Vec<TankedSpan> = Vec::new();
+            let mut current: Option<String> = None;
+            let mut first_secs = None;
+            for bucket in buckets.iter().filter(|b| b.last_secs >= pull_start && b.first_secs <= pull_end) {
+                first_secs.get_or_insert(bucket.first_secs);
+                let hits_on = |guid: &str| bucket.hits.iter().find(|(g, ..)| g == guid).map_or(0, |(_, swings, hits)| swings + hits);
+                let top = bucket.primary_target().unwrap_or_default();
+                let top_hits = bucket.hits.iter().find(|(g, ..)| g == top).map_or((0, 0), |(_, swings, hits)| (*swings, *hits));
+                let target = match current.as_deref() {
+                    Some(previous) if bucket.hits.iter().any(|(g, swings, hits)| g == previous && (*swings, *hits) == top_hits) => previous.to_string(),
+                    _ => top.to_string(),
+                };
+                let start_offset_secs = (bucket.first_secs - pull_start).max(0.0);
+                let end_offset_secs = bucket.last_secs.min(pull_end) - pull_start;
+                if let Some(span) = spans.last_mut().filter(|_| current.as_deref() == Some(target.as_str())) {
+                    span.end_offset_secs = end_offset_secs;
+                    span.hits += hits_on(&target);
+                } else {
+                    if let Some(previous) = spans.last_mut() {
+                        previous.end_offset_secs = start_offset_secs;
+                    }
+                    spans.push(TankedSpan {
+                        start_offset_secs,
+                        end_offset_secs,
+                        player_name: name_of(&target),
+                        on_tank: self.is_tank(&target),
+                        hits: hits_on(&target),
+                    });
+                }
+                current = Some(target);
+            }
+            let Some(first_secs) = first_secs else {
+                continue;
+            };
+            let loose_secs = spans.iter()
+                .filter(|s| !s.on_tank)
+                .map(|s| s.end_offset_secs - s.start_offset_secs)
+                .sum();
+            enemies.push((first_secs, EnemyTanking {
+                enemy_name: enemy_name.to_string(),
+                npc_id: guid::npc_id(enemy_guid),
+                target_swaps: spans.len().saturating_sub(1) as u32,
+                spans,
+                loose_secs,
+            }));
+        }
+        enemies.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.enemy_name.cmp(&b.1.enemy_name)));
+        enemies.into_iter().map(|(_, enemy)| enemy).collect()
+    }
+
+    /// Build per-phase enemy breakdowns from ENCOUNTER_PHASE_CHANGE events
+    fn build_phase_breakdowns(&self, enc_start_secs: f64, enc_end_secs: f64, boss_names: &[String]) -> Vec<PhaseBreakdown> {
+        // Only build phases if we actually saw phase change events
+        if self.phase_transitions.is_empty() {
+            return Vec::new();
+        }
+
+        let boss_names_lower: Vec<String> = boss_names.iter().map(|n| n.to_lowercase()).collect();
+
+        // Collect all unique phases in order
+        let mut phase_ids: Vec<u32> = Vec::new();
+        // Phase 1 is implicit at the start
+        phase_ids.push(1);
+        for &(_, phase_id) in &self.phase_transitions {
+            if !phase_ids.contains(&phase_id) {
+                phase_ids.push(phase_id);
+            }
+        }
+
+        // Build time ranges for each phase
+        let mut phases: Vec<PhaseBreakdown> = Vec::new();
+        for (idx, &phase_id) in phase_ids.iter().enumerate() {
+            let start = if phase_id == 1 {
+                0.0
+            } else {
+                self.phase_transitions.iter()
+                    .find(|&&(_, pid)| pid == phase_id)
+                    .map(|&(ts, _)| ts - enc_start_secs)
+                    .unwrap_or(0.0)
+            };
+
+            let end = if idx + 1 < phase_ids.len() {
+                let next_phase = phase_ids[idx + 1];
+                self.phase_transitions.iter()
+                    .find(|&&(_, pid)| pid == next_phase)
+                    .map(|&(ts, _)| ts - enc_start_secs)
+                    .unwrap_or(enc_end_secs - enc_start_secs)
+            } else {
+                enc_end_secs - enc_start_secs
+            };
+
+            // Build enemy breakdowns for this phase
+            let enemies = if let Some(phase_targets) = self.phase_damage_targets.get(&phase_id) {
+                let mut breakdowns: Vec<EnemyBreakdown> = phase_targets.iter().map(|(target_name, &total_damage)| {
+                    let name_lower = target_name.to_lowercase();
+                    let creature_type = self.phase_creature_types
+                        .get(&phase_id)
+                        .and_then(|m| m.get(target_name))
+                        .map(|s| s.as_str())
+                        .unwrap_or("Unknown");
+                    let mob_type = if creature_type == "Pet" {
+                        "Pet".to_string()
+                    } else if boss_names_lower.iter().any(|bn| name_lower.contains(bn) || bn.contains(&name_lower)) {
+                        "Boss".to_string()
+                    } else {
+                        "Trash".to_string()
+                    };
+                    EnemyBreakdown {
+                        target_name: target_name.clone(),
+                        total_damage,
+                        kill_count: 0,
+                        mob_type,
+                        npc_id: self.creature_npc_ids.get(target_name).copied(),
+                        enemy_forces: 0,
+                        players: Vec::new(), // No per-player breakdown for phases
+                    }
+                }).collect();
+                breakdowns.sort_by(|a, b| b.total_damage.cmp(&a.total_damage));
+                breakdowns
+            } else {
+                Vec::new()
+            };
+
+            phases.push(PhaseBreakdown {
+                phase_id,
+                start_time_secs: start,
+                end_time_secs: end,
+                enemy_breakdowns: enemies,
+            });
+        }
+
+        phases
+    }
+
+    /// Build full-group wipe events, locating each wipe in the key segment it happened in
+    fn build_wipes(&self, start_secs: f64, segments: &[KeySegment]) -> Vec<WipeEvent> {
+        self.wipes.iter().map(|(elapsed, run_back)| {
+            let wipe_secs = start_secs + elapsed;
+            let segment = segments.iter().find(|seg| {
+                parse_timestamp_to_secs(&seg.start_time) <= wipe_secs
+                    && wipe_secs <= parse_timestamp_to_secs(&seg.end_time)
+            });
+            WipeEvent {
+                time_ms: to_millis(*elapsed),
+                segment_index: segment.map(|seg| seg.index),
+                segment_name: segment.map(|seg| seg.name.clone()),
+                run_back_secs: *run_back,
+            }
+        }).collect()
+    }
+
+    /// Build HP timeline for replay: sample each player's HP at 0.5s intervals
+    fn build_hp_timeline(&self, duration: f64) -> Vec<HpSnapshot> {
+        if self.hp_events.is_empty() {
+            return Vec::new();
+        }
+
+        let bucket_size = 0.5_f64;
+        let num_buckets = (duration / bucket_size).ceil() as usize + 1;
+        let num_buckets = num_buckets.min(20000); // cap at ~2.7h
+
+        // Collect all player GUIDs
+        let player_guids: Vec<String> = self.player_names.keys()
+            .filter(|g| guid::is_player(g))
+            .cloned()
+            .collect();
+
+        if player_guids.is_empty() {
+            return Vec::new();
+        }
+
+        // Track last known HP per player
+        let mut last_hp: HashMap<String, (u64, u64)> = HashMap::new();
+        // Track last known position per player
+        let mut last_pos: HashMap<String, (f64, f64)> = HashMap::new();
+        // Track death and res times per player for is_dead determination
+        // Collect all death times
+        let mut death_times_vec: HashMap<String, Vec<f64>> = HashMap::new();
+        for d in &self.death_events {
+            death_times_vec.entry(d.player_guid.clone()).or_default().push(from_millis(d.time_ms));
+        }
+        // Collect all res times
+        let mut res_times_vec: HashMap<String, Vec<f64>> = HashMap::new();
+        for (t, guid) in &self.res_events {
+            res_times_vec.entry(guid.clone()).or_default().push(*t);
+        }
+
+        // Sort hp_events by time
+        let mut sorted_events = self.hp_events.clone();
+        sorted_events.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        // Sort position_events by time
+        let mut sorted_pos = self.position_events.clone();
+        sorted_pos.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut result: Vec<HpSnapshot> = Vec::new();
+        let mut event_idx = 0;
+        let mut pos_idx = 0;
+
+        for bucket in 0..num_buckets {
+            let t = bucket as f64 * bucket_size;
+            if t > duration {
+                break;
+            }
+
+            // Advance through HP events up to this bucket time
+            while event_idx < sorted_events.len() && sorted_events[event_idx].0 <= t {
+                let (_, ref guid, hp, max_hp) = sorted_events[event_idx];
+                if max_hp > 0 {
+                    last_hp.insert(guid.clone(), (hp, max_hp));
+                }
+                event_idx += 1;
+            }
+
+            // Advance through position events up to this bucket time
+            while pos_idx < sorted_pos.len() && sorted_pos[pos_idx].0 <= t {
+                let (_, ref guid, px, py) = sorted_pos[pos_idx];
+                last_pos.insert(guid.clone(), (px, py));
+                pos_idx += 1;
+            }
+
+            // Emit a snapshot for each player
+            for guid in &player_guids {
+                if let Some(&(hp, max_hp)) = last_hp.get(guid) {
+                    let name = self.player_names.get(guid).cloned().unwrap_or_default();
+                    let class_name = self.player_spec(guid)
+                        .and_then(|(id, _)| spec_info(id))
+                        .map(|(c, _, _)| c.to_string())
+                        .unwrap_or_default();
+                    // Determine is_dead: compare latest death time vs latest res time
+                    let last_death = death_times_vec.get(guid)
+                        .and_then(|times| times.iter().filter(|&&dt| dt <= t).last().copied());
+                    let last_res = res_times_vec.get(guid)
+                        .and_then(|times| times.iter().filter(|&&rt| rt <= t).last().copied());
+                    let is_dead = match (last_death, last_res) {
+                        (Some(dt), Some(rt)) => dt > rt, // dead if died after last res
+                        (Some(_), None) => true,          // died, never resurrected
+                        _ => false,                       // never died
+                    };
+                    let (pos_x, pos_y) = last_pos.get(guid)
+                        .map(|&(x, y)| (Some(x), Some(y)))
+                        .unwrap_or((None, None));
+                    result.push(HpSnapshot {
+                        time: (t * 10.0).round() / 10.0,
+                        guid: guid.clone(),
+                        name,
+                        class_name,
+                        current_hp: if is_dead { 0 } else { hp },
+                        max_hp,
+                        is_dead,
+                        pos_x,
+                        pos_y,
+                    });
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Process a single combat event into `tracker`.
+/// `fields` come from `parse_csv_fields`; `start_secs` is the fight start elapsed times are measured from.
+pub fn process_combat_event(
+    event: &LogEvent,
+    start_secs: f64,
+    field_map: &FieldMap,
+    tracker: &mut EventTracker,
+) {
+    let LogEvent { event_type, fields, timestamp_secs, .. } = *event;
+    tracker.fight_start_secs = start_secs;
+    tracker.last_event_secs = timestamp_secs;
+    let source_guid = fields.get(1).map(|s| s.to_string()).unwrap_or_default();
+    let source_name = fields.get(2).map(|s| unquote(s)).unwrap_or_default();
+    let dest_guid = fields.get(5).map(|s| s.to_string()).unwrap_or_default();
+    let dest_name = fields.get(6).map(|s| unquote(s)).unwrap_or_default();
+    // Strip server-realm from names (e.g. "Spougle-TwistingNether-EU" -> "Spougle")
+    let source_name = source_name.split('-').next().unwrap_or(&source_name).to_string();
+    let dest_name = dest_name.split('-').next().unwrap_or(&dest_name).to_string();
+
+    // Register player names
+    if guid::is_player(&source_guid) && !source_name.is_empty() {
+        tracker.player_names.insert(source_guid.clone(), source_name.clone());
+    }
+    if guid::is_player(&dest_guid) && !dest_name.is_empty() {
+        tracker.player_names.insert(dest_guid.clone(), dest_name.clone());
+    }
+
+    // Resolve pet -> owner for damage/heal attribution
+    let effective_source = if guid::is_player(&source_guid) {
+        source_guid.clone()
+    } else {
+        tracker.resolve_owner(&source_guid).unwrap_or(source_guid.clone())
+    };
+
+    // A dead player casting, swinging or taking damage again has been resurrected or ran back
+    if !tracker.dead_players.is_empty() {
+        if matches!(event_type, "SPELL_CAST_SUCCESS" | "SWING_DAMAGE" | "SWING_DAMAGE_LANDED") {
+            tracker.mark_alive(&source_guid, timestamp_secs);
+        }
+        if event_type != "UNIT_DIED" && event_type.ends_with("_DAMAGE") {
+            tracker.mark_alive(&dest_guid, timestamp_secs);
+        }
+    }
+
+    // Enemies trading damage with the group count as engaged
+    if event_type.ends_with("_DAMAGE") {
+        if guid::is_player(&effective_source) && guid::is_npc(&dest_guid) {
+            tracker.engaged_enemies.insert(dest_guid.clone(), timestamp_secs);
+        } else if guid::is_player(&dest_guid) && guid::is_npc(&source_guid) {
+            tracker.engaged_enemies.insert(source_guid.clone(), timestamp_secs);
+            tracker.enemy_activity_mut(&source_guid, &source_name, timestamp_secs).threatened = true;
+        }
+    }
+
+    match event_type {
+        "SPELL_SUMMON" => {
+            // Track pet ownership: source summons dest
+            if !source_guid.is_empty() && !dest_guid.is_empty() {
+                tracker.pet_owners.insert(dest_guid.clone(), source_guid.clone());
+            }
+        }
+        "SPELL_DAMAGE" | "SPELL_PERIODIC_DAMAGE" | "RANGE_DAMAGE" | "SPELL_DAMAGE_SUPPORT" => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            let spell_name = fields.get(10).map(|s| unquote(s)).unwrap_or_default();
+            let spell_school: u32 = fields.get(11).and_then(|s| parse_hex_or_dec(s)).unwrap_or(0);
+            let amount = find_damage_amount(fields, field_map.spell_amount);
+
+            if guid::is_player(&effective_source) && amount > 0 && tracker.is_enemy(&effective_source, &dest_guid) {
+                let entry = tracker.damage_by_player
+                    .entry(effective_source.clone())
+                    .or_default()
+                    .entry(spell_id)
+                    .or_insert_with(|| (spell_name.clone(), spell_school, 0, 0));
+                entry.2 += amount;
+                entry.3 += 1;
+                if event_type == "SPELL_PERIODIC_DAMAGE" && effective_source == source_guid {
+                    *tracker.periodic_damage.entry(effective_source.clone()).or_default().entry(spell_id).or_default() += amount;
+                }
+                // Track per-target
+                *tracker.damage_targets
+                    .entry(effective_source.clone()).or_default()
+                    .entry(spell_id).or_default()
+                    .entry(dest_name.clone()).or_default() += amount;
+                // Track player damage event for per-pull breakdown
+                tracker.player_damage_events.push((timestamp_secs, effective_source.clone(), amount));
+                tracker.record_target_count(timestamp_secs, &effective_source, amount);
+                tracker.close_wipe_run_back(timestamp_secs - start_secs);
+                // Track per-ability damage event for per-pull ability breakdown
+                if tracker.options.features.ability_events {
+                    tracker.player_ability_events.push((timestamp_secs, effective_source.clone(), spell_id, spell_name.clone(), spell_school, amount, dest_name.clone()));
+                }
+                // Track pet source name for grouping
+                if effective_source != source_guid {
+                    tracker.pet_source_names.insert((effective_source.clone(), spell_id), source_name.clone());
+                    let pet_entry = tracker.pet_damage_by_owner
+                        .entry(effective_source.clone()).or_default()
+                        .entry(source_name.clone()).or_default()
+                        .entry(spell_id)
+                        .or_insert_with(|| (spell_name.clone(), spell_school, 0, 0));
+                    pet_entry.2 += amount;
+                    pet_entry.3 += 1;
+                }
+                // Bucket player damage by elapsed second
+                if tracker.encounter_start_secs > 0.0 {
+                    let elapsed = (timestamp_secs - tracker.encounter_start_secs).max(0.0) as u32;
+                    *tracker.time_bucketed_damage
+                        .entry(elapsed).or_default()
+                        .entry((effective_source.clone(), spell_id)).or_default() += amount;
+                }
+                // Record creature type from GUID for enemies tab
+                if !guid::is_player(&dest_guid) && !dest_name.is_empty() {
+                    let guid_type = guid::UnitType::from_guid(&dest_guid).name();
+                    tracker.creature_types.entry(dest_name.clone()).or_insert_with(|| guid_type.to_string());
+                    if let Some(npc_id) = guid::npc_id(&dest_guid) {
+                        tracker.creature_npc_ids.entry(dest_name.clone()).or_insert(npc_id);
+                    }
+                    // Track creature HP from advanced info (fields 14=currentHP, 15=maxHP)
+                    let c_hp: u64 = fields.get(14).and_then(|s| s.parse().ok()).unwrap_or(0);
+                    let m_hp: u64 = fields.get(15).and_then(|s| s.parse().ok()).unwrap_or(0);
+                    if m_hp > 0 {
+                        tracker.last_creature_hp.insert(dest_name.clone(), (c_hp, m_hp));
+                        // Update boss HP % — track the creature with the highest maxHP as the boss
+                        if !tracker.boss_encounter_name.is_empty() && m_hp >= tracker.boss_max_hp_seen {
+                            tracker.boss_max_hp_seen = m_hp;
+                            tracker.current_boss_hp_pct = c_hp as f64 / m_hp as f64 * 100.0;
+                            // Record boss HP timeline point
+                            if tracker.encounter_start_secs > 0.0 {
+                                let elapsed = timestamp_secs - tracker.encounter_start_secs;
+                                tracker.boss_hp_timeline.push((elapsed, tracker.current_boss_hp_pct));
+                                // Track boss position for replay map (SPELL events: posX, posY from the advanced info block)
+                                if let (Some(px), Some(py)) = (
+                                    fields.get(field_map.spell_pos_x).and_then(|s| s.parse::<f64>().ok()),
+                                    fields.get(field_map.spell_pos_x + 1).and_then(|s| s.parse::<f64>().ok()),
+                                ) {
+                                    if tracker.options.features.replay && (px.abs() > 0.01 || py.abs() > 0.01) {
+                                        tracker.boss_position_events.push((elapsed, px, py));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    // Track per-phase damage to enemies
+                    *tracker.phase_damage_targets
+                        .entry(tracker.current_phase).or_default()
+                        .entry(dest_name.clone()).or_default() += amount;
+                    tracker.phase_creature_types
+                        .entry(tracker.current_phase).or_default()
+                        .entry(dest_name.clone()).or_insert_with(|| guid_type.to_string());
+                    // Track NPC damage event for pull detection
+                    if guid_type == "Creature" || guid_type == "Vehicle" {
+                        tracker.npc_damage_events.push((timestamp_secs, dest_name.clone(), amount, guid_type.to_string()));
+                        tracker.record_priority_damage(&dest_guid, &dest_name, &effective_source, amount, timestamp_secs);
+                        *tracker.enemy_activity_mut(&dest_guid, &dest_name, timestamp_secs)
+                            .damage_by_player.entry(effective_source.clone()).or_insert(0) += amount;
+                    }
+
+                }
+            }
+
+            if guid::is_player(&dest_guid) && amount > 0 {
+                *tracker.damage_taken_by_player.entry(dest_guid.clone()).or_insert(0) += amount;
+                tracker.record_enemy_damage(&effective_source, &source_name, &dest_guid, amount);
+                tracker.record_mechanic_hit(&effective_source, &source_name, spell_id, &spell_name, amount);
+                tracker.player_damage_taken_events.push((timestamp_secs, dest_guid.clone(), spell_id, spell_name.clone(), spell_school, amount, source_name.clone()));
+                let overkill: i64 = fields.get(field_map.spell_amount + field_map.overkill_gap).and_then(|s| s.parse().ok()).unwrap_or(-1);
+                // HP from advanced info: for SPELL events, currentHP at [14], maxHP at [15]
+                let current_hp: u64 = fields.get(14).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let max_hp: u64 = fields.get(15).and_then(|s| s.parse().ok()).unwrap_or(0);
+                tracker.check_heal_saves(&dest_guid, timestamp_secs, amount, overkill < 0);
+                tracker.record_hit_taken(&dest_guid, DamageHit {
+                    spell_name: spell_name.clone(),
+                    source_name: source_name.clone(),
+                    amount,
+                    overkill,
+                    current_hp,
+                    max_hp,
+                });
+                // Absorbs soaked at least what the player had left: saved by a shield
+                let absorbed: u64 = fields.get(field_map.spell_amount + field_map.overkill_gap + 4).and_then(|s| s.parse().ok()).unwrap_or(0);
+                if absorbed > 0 && current_hp > 0 && absorbed >= current_hp {
+                    tracker.record_near_death(&dest_guid, timestamp_secs, NearDeath {
+                        time_ms: to_millis(timestamp_secs - start_secs),
+                        player_name: dest_name.clone(),
+                        spell_name: spell_name.clone(),
+                        source_name: source_name.clone(),
+                        absorbed,
+                        hp_remaining: current_hp,
+                        saved_by: None,
+                        shield_spell: None,
+                    });
+                }
+                // Track HP for replay timeline
+                if max_hp > 0 {
+                    if tracker.options.features.replay {
+                        tracker.hp_events.push((timestamp_secs - start_secs, dest_guid.clone(), current_hp, max_hp));
+                    }
+                    tracker.last_player_hp.insert(dest_guid.clone(), current_hp);
+                }
+                // Track position for replay map (SPELL events: posX, posY from the advanced info block)
+                let position = match (
+                    fields.get(field_map.spell_pos_x).and_then(|s| s.parse::<f64>().ok()),
+                    fields.get(field_map.spell_pos_x + 1).and_then(|s| s.parse::<f64>().ok()),
+                ) {
+                    (Some(px), Some(py)) if px.abs() > 0.01 || py.abs() > 0.01 => Some((px, py)),
+                    _ => None,
+                };
+                if let Some((px, py)) = position.filter(|_| tracker.options.features.replay) {
+                    tracker.position_events.push((timestamp_secs - start_secs, dest_guid.clone(), px, py));
+                }
+                if tracker.is_avoidable(spell_id) {
+                    tracker.avoidable_hits.push(AvoidableHit {
+                        offset_secs: timestamp_secs - start_secs,
+                        player_name: dest_name.clone(),
+                        spell_id,
+                        spell_name: spell_name.clone(),
+                        amount,
+                        pos_x: position.map(|(x, _)| x),
+                        pos_y: position.map(|(_, y)| y),
+                    });
+                }
+                if !guid::is_player(&effective_source) && tracker.is_dangerous_hit(spell_id, amount, max_hp) {
+                    tracker.dangerous_hits.push((timestamp_secs - start_secs, dest_guid.clone(), dest_name.clone(), spell_id, spell_name.clone(), amount));
+                }
+                tracker.push_recap_event(&dest_guid, RecapEvent {
+                    time_ms: to_millis(timestamp_secs - start_secs),
+                    event_type: "damage".to_string(),
+                    amount,
+                    spell_name,
+                    spell_id,
+                    source_name: source_name.clone(),
+                    wowhead_url: wowhead_url(spell_id),
+                    current_hp,
+                    max_hp,
+                });
+            }
+        }
+        "SWING_DAMAGE" | "SWING_DAMAGE_LANDED" => {
+            let amount = find_damage_amount(fields, field_map.swing_amount);
+            if tracker.is_duplicate_swing(event_type, &source_guid, &dest_guid, amount, timestamp_secs) {
+                return;
+            }
+            if guid::is_npc(&source_guid) && guid::is_player(&dest_guid) {
+                tracker.record_melee_target(&source_guid, &source_name, &dest_guid, timestamp_secs, start_secs);
+            }
+
+            if guid::is_player(&effective_source) && amount > 0 && tracker.is_enemy(&effective_source, &dest_guid) {
+                let entry = tracker.damage_by_player
+                    .entry(effective_source.clone())
+                    .or_default()
+                    .entry(0)
+                    .or_insert_with(|| ("Melee".to_string(), 1, 0, 0));
+                entry.2 += amount;
+                entry.3 += 1;
+                // Track per-target
+                *tracker.damage_targets
+                    .entry(effective_source.clone()).or_default()
+                    .entry(0u64).or_default()
+                    .entry(dest_name.clone()).or_default() += amount;
+                // Track player damage event for per-pull breakdown
+                tracker.player_damage_events.push((timestamp_secs, effective_source.clone(), amount));
+                tracker.record_target_count(timestamp_secs, &effective_source, amount);
+                tracker.close_wipe_run_back(timestamp_secs - start_secs);
+                // Track per-ability damage event (melee = spell_id 0)
+                if tracker.options.features.ability_events {
+                    tracker.player_ability_events.push((timestamp_secs, effective_source.clone(), 0, "Melee".to_string(), 1, amount, dest_name.clone()));
+                }
+                // Track pet source name for grouping (melee from pets)
+                if effective_source != source_guid {
+                    tracker.pet_source_names.insert((effective_source.clone(), 0), source_name.clone());
+                    let pet_entry = tracker.pet_damage_by_owner
+                        .entry(effective_source.clone()).or_default()
+                        .entry(source_name.clone()).or_default()
+                        .entry(0u64)
+                        .or_insert_with(|| ("Melee".to_string(), 1, 0, 0));
+                    pet_entry.2 += amount;
+                    pet_entry.3 += 1;
+                }
+                // Bucket player damage by elapsed second
+                if tracker.encounter_start_secs > 0.0 {
+                    let elapsed = (timestamp_secs - tracker.encounter_start_secs).max(0.0) as u32;
+                    *tracker.time_bucketed_damage
+                        .entry(elapsed).or_default()
+                        .entry((effective_source.clone(), 0)).or_default() += amount;
+                }
+                // Track per-phase and HP-bucketed damage to enemies
+                if !guid::is_player(&dest_guid) && !dest_name.is_empty() {
+                    *tracker.phase_damage_targets
+                        .entry(tracker.current_phase).or_default()
+                        .entry(dest_name.clone()).or_default() += amount;
+                    // Track NPC damage event for pull detection
+                    if guid::is_npc(&dest_guid) {
+                        tracker.npc_damage_events.push((timestamp_secs, dest_name.clone(), amount, "Creature".to_string()));
+                        tracker.record_priority_damage(&dest_guid, &dest_name, &effective_source, amount, timestamp_secs);
+                        *tracker.enemy_activity_mut(&dest_guid, &dest_name, timestamp_secs)
+                            .damage_by_player.entry(effective_source.clone()).or_insert(0) += amount;
+                    }
+                }
+            }
+
+            if guid::is_player(&dest_guid) && amount > 0 {
+                *tracker.damage_taken_by_player.entry(dest_guid.clone()).or_insert(0) += amount;
+                tracker.record_enemy_damage(&effective_source, &source_name, &dest_guid, amount);
+                tracker.player_damage_taken_events.push((timestamp_secs, dest_guid.clone(), 0, "Melee".to_string(), 1, amount, source_name.clone()));
+                let overkill: i64 = fields.get(field_map.swing_amount + field_map.overkill_gap).and_then(|s| s.parse().ok()).unwrap_or(-1);
+                // HP from advanced info: for SWING events, currentHP at [11], maxHP at [12]
+                let current_hp: u64 = fields.get(11).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let max_hp: u64 = fields.get(12).and_then(|s| s.parse().ok()).unwrap_or(0);
+                tracker.check_heal_saves(&dest_guid, timestamp_secs, amount, overkill < 0);
+                tracker.record_hit_taken(&dest_guid, DamageHit {
+                    spell_name: "Melee".to_string(),
+                    source_name: source_name.clone(),
+                    amount,
+                    overkill,
+                    current_hp,
+                    max_hp,
+                });
+                // Absorbs soaked at least what the player had left: saved by a shield
+                let absorbed: u64 = fields.get(field_map.swing_amount + field_map.overkill_gap + 4).and_then(|s| s.parse().ok()).unwrap_or(0);
+                if absorbed > 0 && current_hp > 0 && absorbed >= current_hp {
+                    tracker.record_near_death(&dest_guid, timestamp_secs, NearDeath {
+                        time_ms: to_millis(timestamp_secs - start_secs),
+                        player_name: dest_name.clone(),
+                        spell_name: "Melee".to_string(),
+                        source_name: source_name.clone(),
+                        absorbed,
+                        hp_remaining: current_hp,
+                        saved_by: None,
+                        shield_spell: None,
+                    });
+                }
+                // Track HP for replay timeline
+                if max_hp > 0 {
+                    if tracker.options.features.replay {
+                        tracker.hp_events.push((timestamp_secs - start_secs, dest_guid.clone(), current_hp, max_hp));
+                    }
+                    tracker.last_player_hp.insert(dest_guid.clone(), current_hp);
+                }
+                // Track position for replay map (SWING events: posX, posY from the advanced info block)
+                if let (Some(px), Some(py)) = (
+                    fields.get(field_map.swing_pos_x).and_then(|s| s.parse::<f64>().ok()),
+                    fields.get(field_map.swing_pos_x + 1).and_then(|s| s.parse::<f64>().ok()),
+                ) {
+                    if tracker.options.features.replay && (px.abs() > 0.01 || py.abs() > 0.01) {
+                        tracker.position_events.push((timestamp_secs - start_secs, dest_guid.clone(), px, py));
+                    }
+                }
+                tracker.push_recap_event(&dest_guid, RecapEvent {
+                    time_ms: to_millis(timestamp_secs - start_secs),
+                    event_type: "damage".to_string(),
+                    amount,
+                    spell_name: "Melee".to_string(),
+                    spell_id: 0,
+                    source_name: source_name.clone(),
+                    wowhead_url: String::new(),
+                    current_hp,
+                    max_hp,
+                });
+            }
+        }
+        "SPELL_HEAL" | "SPELL_PERIODIC_HEAL" | "SPELL_HEAL_SUPPORT" => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            let spell_name = fields.get(10).map(|s| unquote(s)).unwrap_or_default();
+            let spell_school: u32 = fields.get(11).and_then(|s| parse_hex_or_dec(s)).unwrap_or(0);
+            let effective_amount = find_heal_amount(fields, field_map.spell_amount, field_map.overkill_gap);
+            let raw_amount = find_damage_amount(fields, field_map.spell_amount); // raw heal amount before overhealing
+
+            if guid::is_player(&effective_source) && effective_amount > 0 {
+                let entry = tracker.healing_by_player
+                    .entry(effective_source.clone())
+                    .or_default()
+                    .entry(spell_id)
+                    .or_insert_with(|| (spell_name.clone(), spell_school, 0, 0));
+                entry.2 += effective_amount;
+                entry.3 += 1;
+                // Track per-target
+                *tracker.healing_targets
+                    .entry(effective_source.clone()).or_default()
+                    .entry(spell_id).or_default()
+                    .entry(dest_name.clone()).or_default() += effective_amount;
+                // Track healing event for per-pull breakdown
+                tracker.player_healing_events.push((timestamp_secs, effective_source.clone(), effective_amount));
+                // Track per-ability heal event for per-pull ability breakdown
+                if tracker.options.features.ability_events {
+                    tracker.player_heal_ability_events.push((timestamp_secs, effective_source.clone(), spell_id, spell_name.clone(), spell_school, effective_amount, dest_name.clone()));
+                }
+                tracker.heal_recipient_events.push((timestamp_secs, effective_source.clone(), spell_id, effective_amount, dest_guid == effective_source, false));
+            }
+
+            // Track healing received on the target for death recap (use raw amount so heals always show)
+            if guid::is_player(&dest_guid) && raw_amount > 0 {
+                // HP from advanced info: for SPELL events, currentHP at [14], maxHP at [15]
+                let current_hp: u64 = fields.get(14).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let max_hp: u64 = fields.get(15).and_then(|s| s.parse().ok()).unwrap_or(0);
+                // Track HP for replay timeline
+                if max_hp > 0 {
+                    if tracker.options.features.replay {
+                        tracker.hp_events.push((timestamp_secs - start_secs, dest_guid.clone(), current_hp, max_hp));
+                    }
+                    tracker.last_player_hp.insert(dest_guid.clone(), current_hp);
+                }
+                if guid::is_player(&effective_source) && effective_source != dest_guid && effective_amount > 0 {
+                    tracker.record_low_hp_heal(&dest_guid, &effective_source, timestamp_secs, effective_amount, current_hp, max_hp);
+                }
+                // Track position for replay map (SPELL_HEAL: posX, posY from the advanced info block)
+                if let (Some(px), Some(py)) = (
+                    fields.get(field_map.spell_pos_x).and_then(|s| s.parse::<f64>().ok()),
+                    fields.get(field_map.spell_pos_x + 1).and_then(|s| s.parse::<f64>().ok()),
+                ) {
+                    if tracker.options.features.replay && (px.abs() > 0.01 || py.abs() > 0.01) {
+                        tracker.position_events.push((timestamp_secs - start_secs, dest_guid.clone(), px, py));
+                    }
+                }
+                tracker.push_recap_event(&dest_guid, RecapEvent {
+                    time_ms: to_millis(timestamp_secs - start_secs),
+                    event_type: "healing".to_string(),
+                    amount: raw_amount,
+                    spell_name,
+                    spell_id,
+                    source_name: source_name.clone(),
+                    wowhead_url: wowhead_url(spell_id),
+                    current_hp,
+                    max_hp,
+                });
+            }
+        }
+        "SPELL_MISSED" | "SPELL_PERIODIC_MISSED" | "RANGE_MISSED" | "SWING_MISSED" => {
+            // Fully absorbed hits show up as misses: ...,missType,isOffHand,amountMissed,...
+            let (miss_idx, spell_name) = if event_type == "SWING_MISSED" {
+                (9, "Melee".to_string())
+            } else {
+                (12, fields.get(10).map(|s| unquote(s)).unwrap_or_default())
+            };
+            if guid::is_player(&dest_guid) && fields.get(miss_idx) == Some(&"ABSORB") {
+                let absorbed: u64 = fields.get(miss_idx + 2).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let hp = tracker.last_player_hp.get(&dest_guid).copied().unwrap_or(0);
+                if absorbed > 0 && hp > 0 && absorbed >= hp {
+                    tracker.record_near_death(&dest_guid, timestamp_secs, NearDeath {
+                        time_ms: to_millis(timestamp_secs - start_secs),
+                        player_name: dest_name.clone(),
+                        spell_name,
+                        source_name: source_name.clone(),
+                        absorbed,
+                        hp_remaining: hp,
+                        saved_by: None,
+                        shield_spell: None,
+                    });
+                }
+            }
+        }
+        "SPELL_AURA_APPLIED" | "SPELL_AURA_REFRESH" if guid::is_player(&dest_guid) => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            let spell_name = fields.get(10).map(|s| unquote(s)).unwrap_or_default();
+            if spell_id > 0 {
+                tracker.aura_spell_names.insert(spell_id, spell_name.clone());
+                tracker.aura_sources.insert((dest_guid.clone(), spell_id), source_name.clone());
+                // Track aura type (BUFF or DEBUFF) from field 12
+                if let Some(aura_type_str) = fields.get(12) {
+                    let at = unquote(aura_type_str);
+                    if at == "BUFF" || at == "DEBUFF" {
+                        tracker.aura_types.insert(spell_id, at);
+                    }
+                }
+                let stacks = tracker.active_aura_stacks
+                    .entry(dest_guid.clone()).or_default()
+                    .entry(spell_id).or_insert(0);
+                *stacks = 1;
+                tracker.record_aura_event(&dest_guid, spell_id, timestamp_secs - start_secs, "apply", 1);
+            }
+            // Death recap
+            tracker.push_recap_event(&dest_guid, RecapEvent {
+                time_ms: to_millis(timestamp_secs - start_secs),
+                event_type: "buff_applied".to_string(),
+                amount: 0,
+                spell_name,
+                spell_id,
+                source_name: source_name.clone(),
+                wowhead_url: wowhead_url(spell_id),
+                current_hp: 0,
+                max_hp: 0,
+            });
+        }
+        "SPELL_AURA_REMOVED" if guid::is_player(&dest_guid) => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            let spell_name = fields.get(10).map(|s| unquote(s)).unwrap_or_default();
+            if spell_id > 0 {
+                tracker.aura_spell_names.insert(spell_id, spell_name.clone());
+                if let Some(stacks) = tracker.active_aura_stacks
+                    .entry(dest_guid.clone()).or_default()
+                    .get_mut(&spell_id)
+                {
+                    *stacks = 0;
+                }
+                tracker.record_aura_event(&dest_guid, spell_id, timestamp_secs - start_secs, "remove", 0);
+            }
+            // Death recap
+            tracker.push_recap_event(&dest_guid, RecapEvent {
+                time_ms: to_millis(timestamp_secs - start_secs),
+                event_type: "buff_removed".to_string(),
+                amount: 0,
+                spell_name,
+                spell_id,
+                source_name: source_name.clone(),
+                wowhead_url: wowhead_url(spell_id),
+                current_hp: 0,
+                max_hp: 0,
+            });
+        }
+        "SPELL_AURA_APPLIED_DOSE" if guid::is_player(&dest_guid) => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            // Stack count is in field 15 for aura dose events
+            let new_stacks: u32 = fields.get(15).and_then(|s| s.parse().ok()).unwrap_or(0);
+            if spell_id > 0 && new_stacks > 0 {
+                *tracker.active_aura_stacks
+                    .entry(dest_guid.clone()).or_default()
+                    .entry(spell_id).or_insert(0) = new_stacks;
+                tracker.record_aura_event(&dest_guid, spell_id, timestamp_secs - start_secs, "stack", new_stacks);
+            }
+        }
+        "SPELL_AURA_REMOVED_DOSE" if guid::is_player(&dest_guid) => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            let new_stacks: u32 = fields.get(15).and_then(|s| s.parse().ok()).unwrap_or(0);
+            if spell_id > 0 {
+                *tracker.active_aura_stacks
+                    .entry(dest_guid.clone()).or_default()
+                    .entry(spell_id).or_insert(0) = new_stacks;
+                tracker.record_aura_event(&dest_guid, spell_id, timestamp_secs - start_secs, "stack", new_stacks);
+            }
+        }
+        "UNIT_DIED" => {
+            tracker.engaged_enemies.remove(&dest_guid);
+            if guid::is_player(&dest_guid) {
+                let killing_hit = tracker.killing_blow(&dest_guid);
+                let (killing_spell, killing_source, killing_amount, overkill_raw) = killing_hit
+                    .map(|h| (h.spell_name.clone(), h.source_name.clone(), h.amount, h.overkill))
+                    .unwrap_or(("Unknown".to_string(), "Unknown".to_string(), 0, -1));
+                // A single hit bigger than the player's whole health pool
+                let one_shot = killing_hit.is_some_and(|h| h.max_hp > 0 && h.amount >= h.max_hp);
+                tracker.last_damage_to.remove(&dest_guid);
+
+                let time_into_fight = timestamp_secs - start_secs;
+                let recap = tracker.take_recap(&dest_guid, time_into_fight);
+
+                let overkill = if overkill_raw > 0 { Some(overkill_raw) } else { None };
+
+                // Dying again without showing activity in between closes the previous death here
+                tracker.mark_alive(&dest_guid, timestamp_secs);
+                tracker.open_deaths.insert(dest_guid.clone(), (tracker.death_events.len(), timestamp_secs));
+                tracker.death_events.push(DeathEvent {
+                    time_ms: to_millis(time_into_fight),
+                    player_name: dest_name.clone(),
+                    player_guid: dest_guid.clone(),
+                    killing_blow_spell: Some(killing_spell),
+                    killing_blow_source: Some(killing_source),
+                    killing_blow_amount: Some(killing_amount),
+                    overkill,
+                    recap,
+                    could_have_used: tracker.unused_defensives(&dest_guid, timestamp_secs),
+                    time_dead_secs: 0.0,
+                    battle_res: false,
+                    resurrected_by: None,
+                    one_shot,
+                });
+
+                // Everyone dead at once = wipe
+                tracker.dead_players.insert(dest_guid.clone(), time_into_fight);
+                let group_size = tracker.group_player_count();
+                if !tracker.in_wipe && group_size > 0 && tracker.dead_players.len() >= group_size {
+                    tracker.in_wipe = true;
+                    tracker.wipes.push((time_into_fight, None));
+                }
+
+                *tracker.player_death_counts.entry(dest_guid.clone()).or_insert(0) += 1;
+            } else {
+                // Track creature kills
+                *tracker.kill_counts.entry(dest_name.clone()).or_insert(0) += 1;
+                if let Some(spawn) = tracker.priority_spawns.get_mut(&dest_guid) {
+                    spawn.last_secs = timestamp_secs;
+                    spawn.died = true;
+                }
+                if let Some(activity) = tracker.enemy_activity.get_mut(&dest_guid) {
+                    activity.died = true;
+                    activity.last_secs = timestamp_secs;
+                }
+                if let Some(npc_id) = guid::npc_id(&dest_guid) {
+                    tracker.creature_npc_ids.entry(dest_name.clone()).or_insert(npc_id);
+                    tracker.npc_death_events.push((timestamp_secs, dest_name.clone()));
+                }
+                // Detect creature type from GUID prefix
+                let guid_type = guid::UnitType::from_guid(&dest_guid).name();
+                tracker.creature_types.entry(dest_name.clone()).or_insert_with(|| guid_type.to_string());
+            }
+        }
+        "SPELL_CAST_START" if guid::is_player(&source_guid) => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            tracker.close_channel(&source_guid, timestamp_secs);
+            tracker.cast_events.push((timestamp_secs, source_guid.clone(), CastOutcome::Started));
+            // Starting a new cast abandons one still in progress
+            if let Some((_, abandoned_start)) = tracker.pending_casts.insert(source_guid.clone(), (spell_id, timestamp_secs)) {
+                tracker.cast_events.push((abandoned_start, source_guid.clone(), CastOutcome::Cancelled));
+            }
+        }
+        "SPELL_CAST_FAILED" if guid::is_player(&source_guid) => {
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            if let Some(&(_, start)) = tracker.pending_casts.get(&source_guid).filter(|(id, _)| *id == spell_id) {
+                tracker.pending_casts.remove(&source_guid);
+                tracker.cast_events.push((start, source_guid.clone(), CastOutcome::Cancelled));
+            }
+        }
+        "SPELL_CAST_SUCCESS" if guid::is_player(&source_guid) => {
+            // Cast history for defensive/potion cooldown tracking
+            let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+            if spell_id > 0 {
+                tracker.last_casts.entry(source_guid.clone()).or_default()
+                    .insert(spell_id, timestamp_secs);
+            }
+            if let Some(&(_, start)) = tracker.pending_casts.get(&source_guid).filter(|(id, _)| *id == spell_id) {
+                tracker.pending_casts.remove(&source_guid);
+                tracker.cast_events.push((start, source_guid.clone(), CastOutcome::Completed));
+            }
+            if game_data::taunt_name(spell_id).is_some() {
+                tracker.taunt_casts.push((timestamp_secs, source_guid.clone(), spell_id, dest_guid.clone()));
+            }
+            if game_data::raid_healing_cooldown(spell_id).is_some() {
+                tracker.raid_cd_casts.push((timestamp_secs, source_guid.clone(), spell_id));
+            }
+            if let Some(duration) = game_data::channel_duration(spell_id) {
+                tracker.close_channel(&source_guid, timestamp_secs);
+                tracker.open_channels.insert(source_guid.clone(), (timestamp_secs, duration));
+                tracker.cast_events.push((timestamp_secs, source_guid.clone(), CastOutcome::Channel));
+            }
+        }
+        "SPELL_RESURRECT" if guid::is_player(&dest_guid) => {
+            // Track combat resurrections for replay
+            let elapsed = timestamp_secs - start_secs;
+            tracker.res_events.push((elapsed, dest_guid.clone()));
+            // Credit the resurrecter; the death closes once the player is active again
+            if let Some(&(idx, _)) = tracker.open_deaths.get(&dest_guid) {
+                let spell_id: u64 = fields.get(9).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let death = &mut tracker.death_events[idx];
+                death.resurrected_by = Some(source_name.clone());
+                death.battle_res = game_data::BATTLE_RES_SPELLS.contains(&spell_id);
+            }
+        }
+        _ => {}
+    }
+
+    tracker.handlers.handle(&LogEvent {
+        combat: Some(CombatContext {
+            source_guid: &source_guid,
+            source_name: &source_name,
+            dest_guid: &dest_guid,
+            dest_name: &dest_name,
+            effective_source: &effective_source,
+            pet_owners: &tracker.pet_owners,
+            pvp_teams: &tracker.pvp_teams,
+            field_map,
+            phase: tracker.current_phase,
+        }),
+        ..*event
+    });
+
+    // A player's shield soaking damage is healing by them
+    if let Some(absorb) = tracker.handlers.absorbs.take_landed() {
+        let entry = tracker.healing_by_player
+            .entry(absorb.player_guid.clone())
+            .or_default()
+            .entry(absorb.spell_id)
+            .or_insert_with(|| (absorb.spell_name.clone(), absorb.school, 0, 0));
+        entry.2 += absorb.amount;
+        entry.3 += 1;
+        // Track per-target
+        *tracker.healing_targets
+            .entry(absorb.player_guid.clone()).or_default()
+            .entry(absorb.spell_id).or_default()
+            .entry(absorb.dest_name.clone()).or_default() += absorb.amount;
+        // Track healing event for per-pull breakdown
+        tracker.player_healing_events.push((timestamp_secs, absorb.player_guid.clone(), absorb.amount));
+        // Track per-ability heal event for per-pull ability breakdown
+        if tracker.options.features.ability_events {
+            tracker.player_heal_ability_events.push((timestamp_secs, absorb.player_guid.clone(), absorb.spell_id, absorb.spell_name.clone(), absorb.school, absorb.amount, absorb.dest_name.clone()));
+        }
+        tracker.heal_recipient_events.push((timestamp_secs, absorb.player_guid.clone(), absorb.spell_id, absorb.amount, absorb.dest_guid == absorb.player_guid, true));
+        // Register absorb caster name
+        if guid::is_player(&absorb.caster_guid) {
+            tracker.player_names.insert(absorb.caster_guid, absorb.caster_name);
+        }
+    }
+}
+
+/// Try to find the damage amount from fields
+pub(crate) fn find_damage_amount(fields: &[&str], expected_offset: usize) -> u64 {
+    if let Some(val) = fields.get(expected_offset).and_then(|s| s.parse::<i64>().ok()) {
+        if val >= 0 {
+            return val as u64;
+        }
+    }
+    for offset in &[expected_offset.wrapping_sub(1), expected_offset + 1, expected_offset.wrapping_sub(2), expected_offset + 2] {
+        if let Some(val) = fields.get(*offset).and_then(|s| s.parse::<i64>().ok()) {
+            if val > 0 && val < 100_000_000 {
+                return val as u64;
+            }
+        }
+    }
+    0
+}
+
+/// Find healing amount — subtracts overhealing
+/// WoW 12.0 heal suffix: amount, baseAmount, overhealing, absorbed, critical
+pub(crate) fn find_heal_amount(fields: &[&str], expected_offset: usize, overheal_gap: usize) -> u64 {
+    let amount = find_damage_amount(fields, expected_offset);
+    // Overhealing is at offset+2 (offset+1 in Classic and before WoW 12.0 added baseAmount field)
+    let overheal = fields.get(expected_offset + overheal_gap)
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+    amount.saturating_sub(overheal)
+}
+
+/// Whether two names are the same ignoring case, in any script ("Тэнк" and "тЭНК"), unlike
+/// `eq_ignore_ascii_case`; player and boss names are in the client's language
+pub fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars().flat_map(char::to_lowercase).eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Split a log line into timestamp and event parts
+pub fn split_timestamp_event(line: &str) -> Option<(&str, &str)> {
+    let pos = line.find("  ")?;
+    Some((&line[..pos], &line[pos + 2..]))
+}
+
+/// Parse a log timestamp ("3/14/2025 20:01:02.1234", with or without the year, and with or
+/// without a UTC offset after the fraction as newer clients write it) to seconds on a
+/// continuous clock, for durations and offsets. Fractions are read by their number of digits,
+/// and dates as real calendar days so months of any length follow on.
+pub fn parse_timestamp_to_secs(ts: &str) -> f64 {
+    let Some((date, time)) = ts.trim().split_once(' ') else {
+        return 0.0;
+    };
+    let mut date_parts = date.split('/').map(|s| s.parse::<i64>().ok());
+    let (Some(Some(month)), Some(Some(day))) = (date_parts.next(), date_parts.next()) else {
+        return 0.0;
+    };
+    let year = date_parts.next().flatten().unwrap_or(0);
+
+    let mut time_parts = time.split(':');
+    let (Some(hour), Some(minute), Some(second)) = (time_parts.next(), time_parts.next(), time_parts.next()) else {
+        return 0.0;
+    };
+    // "02.1234-4": the UTC offset is left out, the clock only has to be continuous
+    let second = second.split(['+', '-']).next().unwrap_or_default();
+    let (whole, fraction) = second.split_once('.').unwrap_or((second, ""));
+    let fraction = match fraction.parse::<f64>() {
+        Ok(value) => value / 10f64.powi(fraction.len() as i32),
+        Err(_) => 0.0,
+    };
+    let clock = hour.parse::<f64>().unwrap_or(0.0) * 3600.0
+        + minute.parse::<f64>().unwrap_or(0.0) * 60.0
+        + whole.parse::<f64>().unwrap_or(0.0)
+        + fraction;
+    days_from_civil(year, month, day) as f64 * 86400.0 + clock
+}
+
+/// Days from 1970-01-01 to a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Seconds as whole milliseconds, the unit event times (`time_ms`) are given in
+pub fn to_millis(secs: f64) -> i64 {
+    (secs * 1000.0).round() as i64
+}
+
+/// An event time (`time_ms`) back in seconds
+pub fn from_millis(ms: i64) -> f64 {
+    ms as f64 / 1000.0
+}
+
+/// Parse CSV fields, respecting quoted strings and bracketed groups.
+/// Quoted fields may contain commas, brackets and escaped quotes (`\"` or `""`);
+/// they are returned with their quotes intact — use `unquote` to get the value.
+pub fn parse_csv_fields(input: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let bytes = input.as_bytes();
+    let len = bytes.len();
+    let mut i = 0;
+
+    while i < len {
+        while i < len && bytes[i] == b' ' {
+            i += 1;
+        }
+        if i >= len {
+            break;
+        }
+
+        if bytes[i] == b'"' {
+            let start = i;
+            i = skip_quoted(bytes, i);
+            fields.push(&input[start..i]);
+            // Drop any stray bytes after the closing quote so later fields stay aligned
+            while i < len && bytes[i] != b',' {
+                i += 1;
+            }
+        } else {
+            let start = i;
+            let mut depth = 0i32;
+            while i < len {
+                match bytes[i] {
+                    // Quoted strings inside a group, e.g. [(1,"a, b")]
+                    b'"' if depth > 0 => {
+                        i = skip_quoted(bytes, i);
+                        continue;
+                    }
+                    b'(' | b'[' => depth += 1,
+                    b')' | b']' => depth -= 1,
+                    b',' if depth <= 0 => break,
+                    _ => {}
+                }
+                i += 1;
+            }
+            fields.push(&input[start..i]);
+        }
+        if i < len && bytes[i] == b',' {
+            i += 1;
+            // A trailing separator still ends an (empty) field
+            if i == len {
+                fields.push(&input[len..]);
+            }
+        }
+    }
+
+    fields
+}
+
+/// Return the index just past the closing quote of the quoted string starting at `start`
+/// (or the end of input if it is unterminated)
+fn skip_quoted(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if i + 1 < bytes.len() => i += 2,
+            b'"' if bytes.get(i + 1) == Some(&b'"') => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Remove quotes from a string, resolving `\"`, `\\` and `""` escapes
+/// Talent entry IDs out of COMBATANT_INFO's talent tree, `[(nodeID,entryID,rank),...]`,
+/// sorted; ranks are left out, so the list only changes when a different talent is picked
+pub fn parse_talents(field: &str) -> Vec<u32> {
+    let mut talents: Vec<u32> = field.trim_matches(|c| c == '[' || c == ']')
+        .split(')')
+        .filter_map(|node| node.trim_start_matches([',', '(']).split(',').nth(1)?.trim().parse().ok())
+        .collect();
+    talents.sort_unstable();
+    talents.dedup();
+    talents
+}
+
+pub fn unquote(s: &str) -> String {
+    let inner = s.strip_prefix('"').unwrap_or(s);
+    let inner = inner.strip_suffix('"').unwrap_or(inner);
+    if !inner.contains(['\\', '"']) {
+        return inner.to_string();
+    }
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '"' => {
+                if let Some(&next) = chars.peek() {
+                    if next == '"' || (c == '\\' && next == '\\') {
+                        chars.next();
+                        out.push(next);
+                        continue;
+                    }
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parse a hex (0xNN) or decimal number to u32
+pub(crate) fn parse_hex_or_dec(s: &str) -> Option<u32> {
+    if s.starts_with("0x") || s.starts_with("0X") {
+        u32::from_str_radix(&s[2..], 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// Rank all trash pulls of a key by enemy forces granted, keeping the biggest few
+fn top_count_pulls(segments: &[KeySegment]) -> Vec<CountPull> {
+    let mut pulls: Vec<CountPull> = segments.iter()
+        .flat_map(|seg| seg.pulls.iter().map(move |p| (seg, p)))
+        .filter(|(_, p)| p.enemy_forces > 0)
+        .map(|(seg, p)| CountPull {
+            segment_index: seg.index,
+            segment_name: seg.name.clone(),
+            pull_index: p.pull_index,
+            enemy_forces: p.enemy_forces,
+            duration_secs: p.duration_secs,
+            forces_per_min: if p.duration_secs > 0.0 { p.enemy_forces as f64 / p.duration_secs * 60.0 } else { 0.0 },
+        })
+        .collect();
+    pulls.sort_by_key(|p| std::cmp::Reverse(p.enemy_forces));
+    pulls.truncate(5);
+    pulls
+}
+
+/// Name of the `number`th trash segment: its map area, with a counter if an earlier segment
+/// was already in that area ("Gallery approach 2"), or "Trash N" when the area is unknown
+fn trash_segment_name(area: Option<&str>, number: usize, earlier: &[KeySegment]) -> String {
+    let Some(area) = area else {
+        return format!("Trash {}", number);
+    };
+    let taken = |name: &str| earlier.iter().any(|s| s.segment_type == "trash" && s.name == name);
+    if !taken(area) {
+        return area.to_string();
+    }
+    (2..).map(|n| format!("{} {}", area, n)).find(|name| !taken(name)).unwrap_or_default()
+}
+
+/// Keystone upgrade for a completed key: +3 within 60% of par, +2 within 80%, +1 within par, else 0
+fn keystone_upgrade(completion_secs: f64, par_secs: f64) -> u32 {
+    if completion_secs <= par_secs * 0.6 {
+        3
+    } else if completion_secs <= par_secs * 0.8 {
+        2
+    } else if completion_secs <= par_secs {
+        1
+    } else {
+        0
+    }
+}
+
+/// Map WoW specialization ID to (class_name, spec_name, role)
+fn spec_info(spec_id: u32) -> Option<(&'static str, &'static str, &'static str)> {
+    match spec_id {
+        // Warrior
+        71 => Some(("Warrior", "Arms", "dps")),
+        72 => Some(("Warrior", "Fury", "dps")),
+        73 => Some(("Warrior", "Protection", "tank")),
+        // Paladin
+        65 => Some(("Paladin", "Holy", "healer")),
+        66 => Some(("Paladin", "Protection", "tank")),
+        70 => Some(("Paladin", "Retribution", "dps")),
+        // Hunter
+        253 => Some(("Hunter", "Beast Mastery", "dps")),
+        254 => Some(("Hunter", "Marksmanship", "dps")),
+        255 => Some(("Hunter", "Survival", "dps")),
+        // Rogue
+        259 => Some(("Rogue", "Assassination", "dps")),
+        260 => Some(("Rogue", "Outlaw", "dps")),
+        261 => Some(("Rogue", "Subtlety", "dps")),
+        // Priest
+        256 => Some(("Priest", "Discipline", "healer")),
+        257 => Some(("Priest", "Holy", "healer")),
+        258 => Some(("Priest", "Shadow", "dps")),
+        // Death Knight
+        250 => Some(("Death Knight", "Blood", "tank")),
+        251 => Some(("Death Knight", "Frost", "dps")),
+        252 => Some(("Death Knight", "Unholy", "dps")),
+        // Shaman
+        262 => Some(("Shaman", "Elemental", "dps")),
+        263 => Some(("Shaman", "Enhancement", "dps")),
+        264 => Some(("Shaman", "Restoration", "healer")),
+        // Mage
+        62 => Some(("Mage", "Arcane", "dps")),
+        63 => Some(("Mage", "Fire", "dps")),
+        64 => Some(("Mage", "Frost", "dps")),
+        // Warlock
+        265 => Some(("Warlock", "Affliction", "dps")),
+        266 => Some(("Warlock", "Demonology", "dps")),
+        267 => Some(("Warlock", "Destruction", "dps")),
+        // Monk
+        268 => Some(("Monk", "Brewmaster", "tank")),
+        270 => Some(("Monk", "Mistweaver", "healer")),
+        269 => Some(("Monk", "Windwalker", "dps")),
+        // Druid
+        102 => Some(("Druid", "Balance", "dps")),
+        103 => Some(("Druid", "Feral", "dps")),
+        104 => Some(("Druid", "Guardian", "tank")),
+        105 => Some(("Druid", "Restoration", "healer")),
+        // Demon Hunter
+        577 => Some(("Demon Hunter", "Havoc", "dps")),
+        581 => Some(("Demon Hunter", "Vengeance", "tank")),
+        1480 => Some(("Demon Hunter", "Devourer", "dps")),
+        // Evoker
+        1467 => Some(("Evoker", "Devastation", "dps")),
+        1468 => Some(("Evoker", "Preservation", "healer")),
+        1473 => Some(("Evoker", "Augmentation", "dps")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_plain_fields() {
+        assert_eq!(parse_csv_fields("SPELL_DAMAGE,a,b,1"), vec!["SPELL_DAMAGE", "a", "b", "1"]);
+        assert_eq!(parse_csv_fields("a,,b"), vec!["a", "", "b"]);
+        assert_eq!(parse_csv_fields("a,b,"), vec!["a", "b", ""]);
+    }
+
+    #[test]
+    fn keeps_commas_and_brackets_inside_quotes() {
+        let fields = parse_csv_fields(r#"UNIT_DIED,"Kel'Thuzad, the Undying","Boss (Phase 2]",0x10"#);
+        assert_eq!(fields.len(), 4);
+        assert_eq!(unquote(fields[1]), "Kel'Thuzad, the Undying");
+        assert_eq!(unquote(fields[2]), "Boss (Phase 2]");
+        assert_eq!(fields[3], "0x10");
+    }
+
+    #[test]
+    fn resolves_escaped_quotes() {
+        let fields = parse_csv_fields(r#"X,"Say \"hi\", friend","Doubled ""quote"", ok",7"#);
+        assert_eq!(fields.len(), 4);
+        assert_eq!(unquote(fields[1]), r#"Say "hi", friend"#);
+        assert_eq!(unquote(fields[2]), r#"Doubled "quote", ok"#);
+        assert_eq!(fields[3], "7");
+        assert_eq!(unquote(r#""a\\b""#), r"a\b");
+        assert_eq!(unquote(r#""""#), "");
+    }
+
+    #[test]
+    fn keeps_bracket_groups_together() {
+        let fields = parse_csv_fields(r#"COMBATANT_INFO,Player-1-0A,[(1,2,3),(4,5)],(6,"a, [b"),9"#);
+        assert_eq!(fields, vec!["COMBATANT_INFO", "Player-1-0A", "[(1,2,3),(4,5)]", r#"(6,"a, [b")"#, "9"]);
+    }
+
+    #[test]
+    fn reads_talent_entries_without_ranks() {
+        assert_eq!(parse_talents("[(80001,101500,1),(80002,101498,2),(80000,99010,1)]"), vec![99010, 101498, 101500]);
+        assert_eq!(parse_talents("[]"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn malformed_input_does_not_shift_fields() {
+        // Stray text after a closing quote is dropped instead of starting a new field
+        assert_eq!(parse_csv_fields(r#"A,"name"junk,3"#), vec!["A", r#""name""#, "3"]);
+        // Unterminated quote swallows the rest of the line without panicking
+        assert_eq!(parse_csv_fields(r#"A,"open, 1, 2"#), vec!["A", r#""open, 1, 2"#]);
+        assert!(parse_csv_fields("").is_empty());
+        assert!(parse_csv_fields("   ").is_empty());
+    }
+
+    /// Real-world shaped lines with awkward names: the fields after the names must stay aligned
+    #[test]
+    fn corpus_lines_keep_field_alignment() {
+        let corpus = [
+            r#"SPELL_DAMAGE,Player-1403-0A1B2C3D,"Zül'jin-Twisting, Nether",0x512,0x0,Creature-0-3023-2651-1-208450-0000,"Wandering ""Candle""",0xa48,0x0,116858,"Chaos Bolt",0x4,Creature-0-3023-2651-1-208450-0000,0000000000000000,100,200,0,0,0,0,0,-1,0,0,0,1,2,2651,0,0,80,123,456,789,0,0,0,1,nil,nil,ST"#,
+            r#"SPELL_DAMAGE,Player-1403-0A1B2C3D,"O\"Brien",0x512,0x0,Creature-0-3023-2651-1-208450-0000,"Boss, Destroyer of [Worlds]",0xa48,0x0,116858,"Chaos Bolt",0x4,Creature-0-3023-2651-1-208450-0000,0000000000000000,100,200,0,0,0,0,0,-1,0,0,0,1,2,2651,0,0,80,123,456,789,0,0,0,1,nil,nil,ST"#,
+            r#"SPELL_DAMAGE,Player-1403-0A1B2C3D,"Тэнкбой-Гордунни",0x512,0x0,Creature-0-3023-2651-1-208450-0000,"Королева Ансурек",0xa48,0x0,116858,"Chaos Bolt",0x4,Creature-0-3023-2651-1-208450-0000,0000000000000000,100,200,0,0,0,0,0,-1,0,0,0,1,2,2651,0,0,80,123,456,789,0,0,0,1,nil,nil,ST"#,
+            r#"SPELL_DAMAGE,Player-1403-0A1B2C3D,"흑마법사-아즈샤라",0x512,0x0,Creature-0-3023-2651-1-208450-0000,"여왕 안수레크, 파멸의 [군주]",0xa48,0x0,116858,"Chaos Bolt",0x4,Creature-0-3023-2651-1-208450-0000,0000000000000000,100,200,0,0,0,0,0,-1,0,0,0,1,2,2651,0,0,80,123,456,789,0,0,0,1,nil,nil,ST"#,
+        ];
+        for line in corpus {
+            let fields = parse_csv_fields(line);
+            assert_eq!(fields.len(), 41, "{}", line);
+            assert_eq!(fields[0], "SPELL_DAMAGE");
+            assert!(fields[1].starts_with("Player-"));
+            assert!(fields[5].starts_with("Creature-"));
+            assert_eq!(fields[9], "116858");
+            assert_eq!(unquote(fields[10]), "Chaos Bolt");
+            assert_eq!(find_damage_amount(&fields, 31), 123);
+        }
+    }
+
+    /// Small deterministic xorshift generator so the fuzz cases are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[(self.next() % items.len() as u64) as usize]
+        }
+    }
+
+    /// Encode random values as quoted/unquoted fields, then check the tokenizer round-trips them
+    #[test]
+    fn fuzz_round_trip() {
+        let quoted_parts = ["a", "Z", " ", ",", "(", ")", "[", "]", "'", "-", "é", "ß", "\"", "\\"];
+        let plain_parts = ["a", "7", "x", "-", "0x1", "Player", "nil"];
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+
+        for _ in 0..2000 {
+            let doubled_style = rng.next() % 2 == 0;
+            let field_count = 1 + rng.next() % 12;
+            let mut expected: Vec<String> = Vec::new();
+            let mut encoded: Vec<String> = Vec::new();
+            for _ in 0..field_count {
+                let part_count = rng.next() % 8;
+                if rng.next() % 2 == 0 {
+                    let mut value = String::new();
+                    for _ in 0..part_count {
+                        let part = rng.pick(&quoted_parts);
+                        // The doubled-quote style has no backslash escape
+                        if doubled_style && part == "\\" {
+                            continue;
+                        }
+                        value.push_str(part);
+                    }
+                    let escaped = if doubled_style {
+                        value.replace('"', "\"\"")
+                    } else {
+                        value.replace('\\', "\\\\").replace('"', "\\\"")
+                    };
+                    encoded.push(format!("\"{}\"", escaped));
+                    expected.push(value);
+                } else {
+                    // An empty unquoted field is only distinguishable between separators
+                    let value: String = (0..=part_count).map(|_| rng.pick(&plain_parts)).collect();
+                    encoded.push(value.clone());
+                    expected.push(value);
+                }
+            }
+            let line = encoded.join(",");
+            let parsed: Vec<String> = parse_csv_fields(&line).into_iter().map(unquote).collect();
+            assert_eq!(parsed, expected, "line: {}", line);
+        }
+    }
+
+    /// Arbitrary garbage must never panic and every field must be a slice of the input
+    #[test]
+    fn fuzz_arbitrary_input() {
+        let parts = ["a", ",", "\"", "\\", "(", ")", "[", "]", " ", "é", "1"];
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+
+        for _ in 0..5000 {
+            let len = rng.next() % 40;
+            let line: String = (0..len).map(|_| rng.pick(&parts)).collect();
+            for field in parse_csv_fields(&line) {
+                assert!(line.contains(field), "line: {}", line);
+                let _ = unquote(field);
+            }
+        }
+    }
+
+    /// Feed timestamped log lines (retail format) straight into a tracker
+    fn feed(tracker: &mut EventTracker, log: &str) {
+        let field_map = LogFormat::Retail.field_map();
+        for line in log.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (timestamp_str, event_part) = split_timestamp_event(line).unwrap();
+            let fields = parse_csv_fields(event_part);
+            let event = LogEvent {
+                timestamp: timestamp_str,
+                timestamp_secs: parse_timestamp_to_secs(timestamp_str),
+                event_type: fields[0],
+                fields: &fields,
+                combat: None,
+            };
+            process_combat_event(&event, 0.0, &field_map, tracker);
+        }
+    }
+
+    const TANK: &str = "Player-1403-0A1B2C3D";
+
+    fn melee_done(tracker: &EventTracker, guid: &str) -> (u64, u32) {
+        tracker.damage_by_player.get(guid).and_then(|spells| spells.get(&0)).map(|e| (e.2, e.3)).unwrap_or((0, 0))
+    }
+
+    /// Advanced logging writes each melee hit as SWING_DAMAGE and again as SWING_DAMAGE_LANDED
+    #[test]
+    fn melee_logged_twice_counts_once() {
+        let mut tracker = EventTracker::new();
+        feed(&mut tracker, r#"
+            10/16/2026 20:15:03.1230  SWING_DAMAGE,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Player-1403-0A1B2C3D,0000000000000000,812340,812340,52301,1203,41244,0,0,0,1,0,0,0,-2145.12,-1320.45,2651,1.5708,632,12345,17635,-1,1,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:03.1230  SWING_DAMAGE_LANDED,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2841020,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,12345,17635,-1,1,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:04.8770  SWING_DAMAGE,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Player-1403-0A1B2C3D,0000000000000000,812340,812340,52301,1203,41244,0,0,0,1,0,0,0,-2145.12,-1320.45,2651,1.5708,632,24690,17635,-1,1,0,0,0,1,nil,nil,ST
+            10/16/2026 20:15:04.8770  SWING_DAMAGE_LANDED,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2816330,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,24690,17635,-1,1,0,0,0,1,nil,nil,ST
+        "#);
+        assert_eq!(melee_done(&tracker, TANK), (12345 + 24690, 2));
+        assert_eq!(tracker.player_damage_events.len(), 2);
+
+        // The enemy's swings at the tank are deduplicated the same way
+        let mut tracker = EventTracker::new();
+        feed(&mut tracker, r#"
+            10/16/2026 20:15:05.0010  SWING_DAMAGE,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2816330,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,40210,52000,-1,1,0,0,11790,nil,nil,nil,ST
+            10/16/2026 20:15:05.0010  SWING_DAMAGE_LANDED,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Player-1403-0A1B2C3D,0000000000000000,772130,812340,52301,1203,41244,0,0,0,1,0,0,0,-2145.12,-1320.45,2651,1.5708,632,40210,52000,-1,1,0,0,11790,nil,nil,nil,ST
+        "#);
+        assert_eq!(tracker.damage_taken_by_player.get(TANK), Some(&40210));
+    }
+
+    /// Logs without SWING_DAMAGE_LANDED keep counting every plain swing
+    #[test]
+    fn plain_swings_count_without_landed_lines() {
+        let mut tracker = EventTracker::new();
+        feed(&mut tracker, r#"
+            10/16/2026 20:15:03.1230  SWING_DAMAGE,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Player-1403-0A1B2C3D,0000000000000000,812340,812340,52301,1203,41244,0,0,0,1,0,0,0,-2145.12,-1320.45,2651,1.5708,632,12345,17635,-1,1,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:04.8770  SWING_DAMAGE,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Player-1403-0A1B2C3D,0000000000000000,812340,812340,52301,1203,41244,0,0,0,1,0,0,0,-2145.12,-1320.45,2651,1.5708,632,12345,17635,-1,1,0,0,0,nil,nil,nil,ST
+        "#);
+        assert_eq!(melee_done(&tracker, TANK), (24690, 2));
+    }
+
+    /// Pet melee is credited to the owner once, whichever order the two lines come in
+    #[test]
+    fn pet_melee_logged_twice_counts_once() {
+        let mut tracker = EventTracker::new();
+        feed(&mut tracker, r#"
+            10/16/2026 20:15:02.5000  SPELL_SUMMON,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0xa28,0x0,691,"Summon Felhunter",0x20
+            10/16/2026 20:15:03.2000  SWING_DAMAGE_LANDED,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2841020,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,3110,4441,-1,1,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:03.2000  SWING_DAMAGE,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-417-00006F19AA,Player-1403-0A1B2C3D,401994,401994,26201,1203,20622,0,0,0,1,0,0,0,-2144.40,-1321.90,2651,1.2100,80,3110,4441,-1,1,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:05.2000  SWING_DAMAGE,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-417-00006F19AA,Player-1403-0A1B2C3D,401994,401994,26201,1203,20622,0,0,0,1,0,0,0,-2144.40,-1321.90,2651,1.2100,80,6220,4441,-1,1,0,0,0,1,nil,nil,ST
+            10/16/2026 20:15:05.2000  SWING_DAMAGE_LANDED,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2834800,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,6220,4441,-1,1,0,0,0,1,nil,nil,ST
+        "#);
+        assert_eq!(melee_done(&tracker, TANK), (3110 + 6220, 2));
+        let pet_melee = &tracker.pet_damage_by_owner[TANK]["Felhunter"][&0];
+        assert_eq!((pet_melee.2, pet_melee.3), (3110 + 6220, 2));
+    }
+
+    /// Cyrillic and Korean names come through the tracker whole, with only the realm cut off,
+    /// and match whatever their case
+    #[test]
+    fn non_ascii_names_survive_parsing() {
+        let mut tracker = EventTracker::new();
+        feed(&mut tracker, r#"
+            10/16/2026 20:15:03.1230  SPELL_DAMAGE,Player-1403-0A1B2C3D,"Тэнкбой-Гордунни-EU",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Королева Ансурек",0xa48,0x0,116858,"Chaos Bolt",0x4,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2841020,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,4321,4321,-1,4,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:04.1230  SPELL_DAMAGE,Player-205-0B1C2D3E,"흑마법사-아즈샤라-KR",0x512,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Королева Ансурек",0xa48,0x0,116858,"Chaos Bolt",0x4,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2836699,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,1234,1234,-1,4,0,0,0,nil,nil,nil,ST
+        "#);
+        let players = tracker.build_player_summaries(10.0);
+        let damage = |name: &str| players.iter().find(|p| p.name == name).map(|p| p.damage_done);
+        assert_eq!(damage("Тэнкбой"), Some(4321));
+        assert_eq!(damage("흑마법사"), Some(1234));
+
+        assert!(eq_ignore_case("Тэнкбой", "тЭНКБОЙ"));
+        assert!(eq_ignore_case("흑마법사", "흑마법사"));
+        assert!(!eq_ignore_case("Тэнкбой", "Тэнкбои"));
+
+        // A byte order mark from an editor doesn't hide the header line
+        let mut parser = CombatLogParser::new("bom.txt");
+        parser.process_line("\u{feff}10/16/2026 20:15:00.0000  COMBAT_LOG_VERSION,22,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,11.1.5,PROJECT_ID,1");
+        assert_eq!(parser.log_version, Some(22));
+        assert_eq!(parser.last_event_secs, parse_timestamp_to_secs("10/16/2026 20:15:00.0000"));
+    }
+
+    /// Custom metrics credit pets to their owner and leave out events outside their phases
+    #[test]
+    fn custom_metrics_follow_their_rules() {
+        use crate::options::{MetricMeasure, MetricRule};
+        let melee = |name: &str| MetricRule {
+            name: name.to_string(),
+            measure: MetricMeasure::Amount,
+            events: vec!["SWING_DAMAGE".to_string(), "SWING_DAMAGE_LANDED".to_string()],
+            ..Default::default()
+        };
+        let options = ParseOptions {
+            custom_metrics: vec![melee("Melee"), MetricRule { phases: vec![2], ..melee("Melee in P2") }],
+            ..Default::default()
+        };
+        let mut tracker = EventTracker::with_options(&options);
+        feed(&mut tracker, r#"
+            10/16/2026 20:15:02.5000  SPELL_SUMMON,Player-1403-0A1B2C3D,"Tankyboi-Draenor-EU",0x512,0x0,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0xa28,0x0,691,"Summon Felhunter",0x20
+            10/16/2026 20:15:03.2000  SWING_DAMAGE,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-417-00006F19AA,Player-1403-0A1B2C3D,401994,401994,26201,1203,20622,0,0,0,1,0,0,0,-2144.40,-1321.90,2651,1.2100,80,3110,4441,-1,1,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:03.2000  SWING_DAMAGE_LANDED,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2841020,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,3110,4441,-1,1,0,0,0,nil,nil,nil,ST
+            10/16/2026 20:15:05.2000  SWING_DAMAGE,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-417-00006F19AA,Player-1403-0A1B2C3D,401994,401994,26201,1203,20622,0,0,0,1,0,0,0,-2144.40,-1321.90,2651,1.2100,80,6220,4441,-1,1,0,0,0,1,nil,nil,ST
+            10/16/2026 20:15:05.2000  SWING_DAMAGE_LANDED,Creature-0-3023-2651-1-417-00006F19AA,"Felhunter",0x1112,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,"Wandering Candle",0xa48,0x0,Creature-0-3023-2651-1-208450-00006F1A2B,0000000000000000,2834800,2853365,0,0,5040,0,0,0,1,0,0,0,-2143.80,-1318.02,2651,4.7124,81,6220,4441,-1,1,0,0,0,1,nil,nil,ST
+        "#);
+        let players = tracker.build_player_summaries(10.0);
+        let metrics = tracker.build_custom_metrics(0, &players);
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].total, 3110 + 6220);
+        assert_eq!(metrics[0].players.iter().map(|p| (p.guid.as_str(), p.value)).collect::<Vec<_>>(), vec![(TANK, 3110 + 6220)]);
+        assert_eq!(metrics[1].total, 0);
+        assert!(metrics[1].players.is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Quick look at what a log contains, without parsing it
+//!
+//! Only encounter and Mythic+ boundary lines are tokenized. Everything else is skipped
+//! with a substring search over the raw bytes, so even a multi-gigabyte log is probed in
+//! about the time it takes to read it.
+
+use std::io::Read;
+use std::path::Path;
+
+use memchr::memmem;
+
+use crate::log_file;
+use crate::models::{difficulty_name, LogProbe, ProbeBoss, ProbeKey};
+use crate::parser::{parse_csv_fields, split_timestamp_event, unquote};
+
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Every boundary line has one of these right after the timestamp
+const MARKERS: [&[u8]; 2] = [b"  ENCOUNTER_", b"  CHALLENGE_MODE_"];
+
+/// Count the boss pulls and keys in a log from its ENCOUNTER_* and CHALLENGE_MODE_* lines
+pub fn probe_log(path: &Path) -> Result<LogProbe, String> {
+    let mut file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let finders: Vec<memmem::Finder> = MARKERS.iter().map(memmem::Finder::new).collect();
+    let mut probe = LogProbe::default();
+    let mut buf: Vec<u8> = Vec::with_capacity(CHUNK_SIZE);
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = log_file::with_retry(|| file.read(&mut chunk)).map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..read]);
+        // A partial last line waits for the next chunk
+        let Some(complete) = memchr::memrchr(b'\n', &buf).map(|i| i + 1) else {
+            continue;
+        };
+        scan(&buf[..complete], &finders, &mut probe);
+        buf.drain(..complete);
+    }
+    Ok(probe)
+}
+
+/// Feed `probe` the boundary lines in `text` (whole lines only)
+fn scan(text: &[u8], finders: &[memmem::Finder], probe: &mut LogProbe) {
+    let mut hits: Vec<usize> = finders.iter().flat_map(|f| f.find_iter(text)).collect();
+    hits.sort_unstable();
+    let mut scanned_to = 0;
+    for hit in hits {
+        if hit < scanned_to {
+            continue;
+        }
+        let start = memchr::memrchr(b'\n', &text[..hit]).map_or(0, |i| i + 1);
+        let end = memchr::memchr(b'\n', &text[hit..]).map_or(text.len(), |i| hit + i);
+        scanned_to = end;
+        record_line(&String::from_utf8_lossy(&text[start..end]), probe);
+    }
+}
+
+fn record_line(line: &str, probe: &mut LogProbe) {
+    let Some((_, event)) = split_timestamp_event(line.trim_end()) else {
+        return;
+    };
+    let fields = parse_csv_fields(event);
+    let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+    match field(0) {
+        "ENCOUNTER_START" => {
+            let name = unquote(field(2));
+            let difficulty = difficulty_name(field(3).parse().unwrap_or(0));
+            probe.boss_pulls += 1;
+            match probe.bosses.iter_mut().find(|b| b.name == name && b.difficulty_name == difficulty) {
+                Some(boss) => boss.pulls += 1,
+                None => probe.bosses.push(ProbeBoss { name, difficulty_name: difficulty, pulls: 1, kills: 0 }),
+            }
+        }
+        "ENCOUNTER_END" if field(5) == "1" => {
+            let name = unquote(field(2));
+            let difficulty = difficulty_name(field(3).parse().unwrap_or(0));
+            probe.boss_kills += 1;
+            if let Some(boss) = probe.bosses.iter_mut().find(|b| b.name == name && b.difficulty_name == difficulty) {
+                boss.kills += 1;
+            }
+        }
+        "CHALLENGE_MODE_START" => probe.keys.push(ProbeKey {
+            dungeon: unquote(field(1)),
+            level: field(4).parse().unwrap_or(0),
+            success: None,
+        }),
+        "CHALLENGE_MODE_END" => {
+            if let Some(key) = probe.keys.last_mut().filter(|k| k.success.is_none()) {
+                key.success = Some(field(2) == "1");
+            }
+        }
+        _ => {}
+    }
+}
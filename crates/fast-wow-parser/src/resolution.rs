@@ -0,0 +1,102 @@
+//! Coarser time series for overview charts, so a month-old log's charts don't need every
+//! half-second sample sent over
+//!
+//! Totals are summed into windows keyed by their first second, so charts that add buckets
+//! up (the damage range slider, the ability timeline) get the same sums. Samples such as HP,
+//! boss HP and positions keep the first of each window and the last one; a replay snapshot
+//! where a player died or came back is always kept.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::models::{AbilityDamageSeries, EncounterSummary, HpSnapshot, ReplayData};
+
+/// Width of the windows time series are reduced to; without one they stay as parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Resolution {
+    #[serde(rename = "1s")]
+    OneSecond,
+    #[serde(rename = "5s")]
+    FiveSeconds,
+    #[serde(rename = "30s")]
+    ThirtySeconds,
+}
+
+impl Resolution {
+    pub fn secs(self) -> u32 {
+        match self {
+            Resolution::OneSecond => 1,
+            Resolution::FiveSeconds => 5,
+            Resolution::ThirtySeconds => 30,
+        }
+    }
+
+    /// First second of the window `secs` falls in
+    fn window(self, secs: f64) -> i64 {
+        (secs / self.secs() as f64).floor() as i64 * self.secs() as i64
+    }
+
+    /// Reduce the per-second player damage and the boss HP timelines of `enc` and its segments
+    pub fn apply_to_encounter(self, enc: &mut EncounterSummary) {
+        let step = self.secs();
+        let mut buckets: HashMap<u32, HashMap<String, u64>> = HashMap::new();
+        for (sec, players) in std::mem::take(&mut enc.time_bucketed_player_damage) {
+            let window = buckets.entry(sec / step * step).or_default();
+            for (guid, amount) in players {
+                *window.entry(guid).or_default() += amount;
+            }
+        }
+        enc.time_bucketed_player_damage = buckets;
+        self.reduce_samples(&mut enc.boss_hp_timeline, |(secs, _)| *secs);
+        for segment in &mut enc.segments {
+            self.reduce_samples(&mut segment.boss_hp_timeline, |(secs, _)| *secs);
+        }
+    }
+
+    /// Reduce the HP snapshots and boss positions of a replay
+    pub fn apply_to_replay(self, replay: &mut ReplayData) {
+        self.reduce_snapshots(&mut replay.replay_timeline);
+        self.reduce_samples(&mut replay.boss_positions, |(secs, ..)| *secs);
+    }
+
+    /// Sum an ability's per-second damage into windows
+    pub fn apply_to_ability_series(self, series: &mut AbilityDamageSeries) {
+        let step = self.secs();
+        let mut windows: Vec<(u32, u64)> = Vec::new();
+        for &(sec, amount) in &series.buckets {
+            let window = sec / step * step;
+            match windows.last_mut() {
+                Some((last, total)) if *last == window => *total += amount,
+                _ => windows.push((window, amount)),
+            }
+        }
+        series.buckets = windows;
+    }
+
+    /// Keep the first sample of each window of a time-ordered series, and the last sample
+    fn reduce_samples<T>(self, samples: &mut Vec<T>, secs: impl Fn(&T) -> f64) {
+        let total = samples.len();
+        let mut last_window = None;
+        let mut index = 0;
+        samples.retain(|sample| {
+            index += 1;
+            let window = self.window(secs(sample));
+            last_window.replace(window) != Some(window) || index == total
+        });
+    }
+
+    /// Keep each player's first snapshot of each window, and every death or resurrection
+    fn reduce_snapshots(self, snapshots: &mut Vec<HpSnapshot>) {
+        let mut last_kept: HashMap<String, (i64, bool)> = HashMap::new();
+        snapshots.retain(|snapshot| {
+            let window = self.window(snapshot.time);
+            let keep = last_kept.get(&snapshot.guid)
+                .is_none_or(|&(last_window, was_dead)| last_window != window || was_dead != snapshot.is_dead);
+            if keep {
+                last_kept.insert(snapshot.guid.clone(), (window, snapshot.is_dead));
+            }
+            keep
+        });
+    }
+}
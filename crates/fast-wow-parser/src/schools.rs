@@ -0,0 +1,110 @@
+//! Damage by spell school, done and taken, for planning immunities and defensives: a boss that
+//! hits mostly with shadow wants magic defensives, one that hits with melee wants armor
+//!
+//! The log gives each spell's school as a bit mask (physical 1, holy 2, fire 4, nature 8,
+//! frost 16, shadow 32, arcane 64); spells of several schools set several bits and go by names
+//! of their own ("Shadowflame" is shadow and fire). Only plain physical counts as physical in
+//! the physical/magic split; every other school, mixed ones included, counts as magic.
+
+use std::collections::BTreeMap;
+
+use crate::models::{AbilityBreakdown, EncounterSummary, SchoolAmount, SchoolProfile, SchoolSplit};
+
+pub const PHYSICAL: u32 = 0x1;
+
+/// Names of the single schools, by bit
+const SCHOOLS: &[(u32, &str)] = &[
+    (0x1, "Physical"),
+    (0x2, "Holy"),
+    (0x4, "Fire"),
+    (0x8, "Nature"),
+    (0x10, "Frost"),
+    (0x20, "Shadow"),
+    (0x40, "Arcane"),
+];
+
+/// Names the game gives schools mixed from several
+const MIXED_SCHOOLS: &[(u32, &str)] = &[
+    (0x3, "Holystrike"),
+    (0x5, "Flamestrike"),
+    (0x6, "Radiant"),
+    (0x9, "Stormstrike"),
+    (0xA, "Holystorm"),
+    (0xC, "Volcanic"),
+    (0x11, "Froststrike"),
+    (0x12, "Holyfrost"),
+    (0x14, "Frostfire"),
+    (0x18, "Froststorm"),
+    (0x1C, "Elemental"),
+    (0x21, "Shadowstrike"),
+    (0x22, "Twilight"),
+    (0x24, "Shadowflame"),
+    (0x28, "Plague"),
+    (0x30, "Shadowfrost"),
+    (0x41, "Spellstrike"),
+    (0x42, "Divine"),
+    (0x44, "Spellfire"),
+    (0x48, "Astral"),
+    (0x50, "Spellfrost"),
+    (0x60, "Spellshadow"),
+    (0x7C, "Chromatic"),
+    (0x7E, "Magic"),
+    (0x7F, "Chaos"),
+];
+
+/// "Fire", "Shadowflame", or the single schools joined ("Fire/Frost/Arcane") for a mix
+/// without a name of its own
+pub fn name(school: u32) -> String {
+    if let Some((_, name)) = SCHOOLS.iter().chain(MIXED_SCHOOLS).find(|(mask, _)| *mask == school) {
+        return name.to_string();
+    }
+    let names: Vec<&str> = SCHOOLS.iter().filter(|(bit, _)| school & bit != 0).map(|(_, name)| *name).collect();
+    if names.is_empty() { "Unknown".to_string() } else { names.join("/") }
+}
+
+/// Set `schools` on every player of `enc`
+pub fn annotate(enc: &mut EncounterSummary) {
+    for player in &mut enc.players {
+        player.schools = Some(SchoolProfile {
+            damage_done: split(&player.abilities),
+            damage_taken: split(&player.damage_taken_abilities),
+        });
+    }
+}
+
+/// Add up `abilities` by school, largest first; pet groups by their pets' spells
+fn split(abilities: &[AbilityBreakdown]) -> SchoolSplit {
+    let mut by_school: BTreeMap<u32, u64> = BTreeMap::new();
+    add_abilities(&mut by_school, abilities);
+
+    let mut result = SchoolSplit::default();
+    for (&school, &amount) in &by_school {
+        if school == PHYSICAL {
+            result.physical += amount;
+        } else if school != 0 {
+            result.magic += amount;
+        }
+    }
+    let total: u64 = by_school.values().sum();
+    result.schools = by_school.into_iter()
+        .filter(|&(_, amount)| amount > 0)
+        .map(|(school, amount)| SchoolAmount {
+            school,
+            name: name(school),
+            amount,
+            pct: if total > 0 { amount as f64 / total as f64 * 100.0 } else { 0.0 },
+        })
+        .collect();
+    result.schools.sort_by_key(|s| std::cmp::Reverse(s.amount));
+    result
+}
+
+fn add_abilities(by_school: &mut BTreeMap<u32, u64>, abilities: &[AbilityBreakdown]) {
+    for ability in abilities {
+        if ability.sub_abilities.is_empty() {
+            *by_school.entry(ability.spell_school).or_default() += ability.total_amount;
+        } else {
+            add_abilities(by_school, &ability.sub_abilities);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Cutting a long log into logs that stand on their own, for archiving a multi-week file or
+//! sharing one night of it: one per play session, or one per boss pull and Mythic+ key (from
+//! the encounters' byte ranges)
+//!
+//! Every part starts with a COMBAT_LOG_VERSION header, the one in force where the part
+//! begins, so it parses like a log WoW wrote.
+
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::log_file;
+use crate::models::EncounterSummary;
+use crate::parser::{parse_timestamp_to_secs, split_timestamp_event};
+
+/// A gap this long without a line ends a session
+pub const SESSION_GAP_SECS: f64 = 30.0 * 60.0;
+
+pub(crate) const HEADER_EVENT: &str = "COMBAT_LOG_VERSION";
+
+/// A slice of a log to write out as a log of its own
+#[derive(Debug, Clone, Serialize)]
+pub struct LogPart {
+    /// "session", or the encounter's name and result
+    pub label: String,
+    /// Timestamp of its first line
+    pub start_time: String,
+    /// Byte range in the source log
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The play sessions in a log: a new one starts at each COMBAT_LOG_VERSION header (WoW
+/// writes one every time logging is switched on) and after [`SESSION_GAP_SECS`] without a line
+pub fn sessions(path: &Path) -> Result<Vec<LogPart>, String> {
+    let mut parts: Vec<LogPart> = Vec::new();
+    let mut last_secs: Option<f64> = None;
+    let mut offset = 0;
+    read_lines(path, |line, start, end| {
+        offset = end;
+        let Some((timestamp, event)) = split_timestamp_event(line) else {
+            return;
+        };
+        let secs = parse_timestamp_to_secs(timestamp);
+        let new_session = event.starts_with(HEADER_EVENT)
+            || last_secs.is_none_or(|last| secs - last > SESSION_GAP_SECS);
+        last_secs = Some(secs);
+        if new_session {
+            if let Some(part) = parts.last_mut() {
+                part.end = start;
+            }
+            parts.push(LogPart { label: "session".to_string(), start_time: timestamp.to_string(), start, end });
+        }
+    })?;
+    if let Some(part) = parts.last_mut() {
+        part.end = offset;
+    }
+    // Logging switched on and off again without any combat in between
+    parts.retain(|p| p.end - p.start > 0);
+    Ok(parts)
+}
+
+/// One part per encounter that knows where it lives in the log (boss pulls and keys of a
+/// parse that read the file with offsets)
+pub fn encounter_parts(encounters: &[EncounterSummary]) -> Vec<LogPart> {
+    encounters.iter()
+        .filter_map(|enc| {
+            let range = enc.range.as_ref()?;
+            let result = match enc.encounter_type.as_str() {
+                "boss" => if enc.success { "kill" } else { "wipe" },
+                "mythic_plus" => if enc.success { "timed" } else { "depleted" },
+                _ => return None,
+            };
+            Some(LogPart {
+                label: format!("{} {}", enc.name, result),
+                start_time: enc.start_time.clone(),
+                start: range.start,
+                end: range.end,
+            })
+        })
+        .collect()
+}
+
+/// Write each of `parts` of the log at `path` into `out_dir` as
+/// `WoWCombatLog-MMDDYY_HHMMSS-<label>.txt`, named after the part's own start time so the
+/// log list dates and orders it correctly. Parts not starting on a header get the one in
+/// force there. Existing files are never overwritten: a taken name gets `-2`, `-3`, ...
+/// Returns the written files.
+pub fn write_parts(path: &Path, parts: &[LogPart], out_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("Failed to create {}: {}", out_dir.display(), e))?;
+    let headers = headers(path)?;
+    let source_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let mut source = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut written = Vec::new();
+    for part in parts {
+        let (out_path, mut out) = create_new(out_dir, &part_filename(part, source_name))?;
+        let header = headers.iter().rev().find(|(offset, _)| *offset <= part.start);
+        if let Some((_, line)) = header.filter(|(offset, _)| *offset != part.start) {
+            out.write_all(line.as_bytes()).map_err(|e| format!("Failed to write {}: {}", out_path.display(), e))?;
+        }
+        source.seek(SeekFrom::Start(part.start)).map_err(|e| format!("Failed to seek: {}", e))?;
+        std::io::copy(&mut (&mut source).take(part.end - part.start), &mut out)
+            .map_err(|e| format!("Failed to write {}: {}", out_path.display(), e))?;
+        written.push(out_path);
+    }
+    Ok(written)
+}
+
+/// Create `name` in `dir`, or the first of `<stem>-2.<ext>`, `<stem>-3.<ext>`, ... not taken yet
+fn create_new(dir: &Path, name: &str) -> Result<(PathBuf, std::fs::File), String> {
+    let (stem, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+    let mut attempt = 1;
+    loop {
+        let candidate = if attempt == 1 { name.to_string() } else { format!("{}-{}.{}", stem, attempt, ext) };
+        let path = dir.join(candidate);
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Every COMBAT_LOG_VERSION line in the log, with its byte offset (line ending included)
+fn headers(path: &Path) -> Result<Vec<(u64, String)>, String> {
+    let mut headers = Vec::new();
+    read_lines(path, |line, start, _| {
+        if split_timestamp_event(line).is_some_and(|(_, event)| event.starts_with(HEADER_EVENT)) {
+            headers.push((start, format!("{}\n", line)));
+        }
+    })?;
+    Ok(headers)
+}
+
+/// Call `f` with every complete line of the log (without its line ending) and its byte range
+pub(crate) fn read_lines(path: &Path, mut f: impl FnMut(&str, u64, u64)) -> Result<(), String> {
+    let file = log_file::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::with_capacity(1024 * 1024, file);
+    let mut buf = Vec::new();
+    let mut offset = 0;
+    loop {
+        buf.clear();
+        let read = log_file::read_until(&mut reader, b'\n', &mut buf).map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 || buf.last() != Some(&b'\n') {
+            return Ok(());
+        }
+        let end = offset + read as u64;
+        f(String::from_utf8_lossy(&buf).trim_end(), offset, end);
+        offset = end;
+    }
+}
+
+/// `WoWCombatLog-MMDDYY_HHMMSS-<label>.txt` from the part's start time ("M/D[/YYYY] HH:MM:SS.ms");
+/// the year comes from the source log's name when the timestamps don't carry one
+fn part_filename(part: &LogPart, source_name: &str) -> String {
+    let (date, time) = part.start_time.split_once(' ').unwrap_or((&part.start_time, ""));
+    let mut date_parts = date.split('/');
+    let month: u32 = date_parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let day: u32 = date_parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let year = match date_parts.next() {
+        Some(year) if year.len() >= 2 && year.is_ascii() => year[year.len() - 2..].to_string(),
+        _ => source_name.trim_start_matches("WoWCombatLog-").get(4..6).unwrap_or("00").to_string(),
+    };
+    let hms: String = time.split('.').next().unwrap_or_default().split(':')
+        .map(|s| format!("{:0>2}", s))
+        .collect();
+    let label: String = part.label.to_lowercase().chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-");
+    format!("WoWCombatLog-{:02}{:02}{}_{}-{}.txt", month, day, year, hms, label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::merge;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("merge").join(name)
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fast-wow-parser-split-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Byte offset of the first line with `event`
+    fn offset_of(path: &Path, event: &str) -> u64 {
+        let mut found = None;
+        read_lines(path, |line, start, _| {
+            if found.is_none() && line.contains(event) {
+                found = Some(start);
+            }
+        }).unwrap();
+        found.unwrap()
+    }
+
+    fn part(label: &str, start: u64, end: u64) -> LogPart {
+        LogPart { label: label.to_string(), start_time: "3/2/2025 21:00:00.000".to_string(), start, end }
+    }
+
+    #[test]
+    fn starts_every_part_with_exactly_one_header() {
+        let dir = scratch_dir("header");
+        let log = fixture("part_b.txt");
+        let cut = offset_of(&log, "ENCOUNTER_START");
+        let end = std::fs::metadata(&log).unwrap().len();
+        let written = write_parts(&log, &[part("before", 0, cut), part("pull", cut, end)], &dir).unwrap();
+        assert_eq!(written.len(), 2);
+
+        let source = std::fs::read_to_string(&log).unwrap();
+        let header = source.lines().next().unwrap();
+        for path in &written {
+            let text = std::fs::read_to_string(path).unwrap();
+            assert_eq!(text.lines().next(), Some(header), "{}", path.display());
+            assert_eq!(text.matches(HEADER_EVENT).count(), 1, "{}", path.display());
+        }
+        let pull = std::fs::read_to_string(&written[1]).unwrap();
+        assert_eq!(pull, format!("{}\n{}", header, &source[cut as usize..]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn never_overwrites_an_earlier_part() {
+        let dir = scratch_dir("names");
+        let log = fixture("part_a.txt");
+        let end = std::fs::metadata(&log).unwrap().len();
+        let written = write_parts(&log, &[part("pull", 0, end), part("pull", 0, end)], &dir).unwrap();
+        let names: Vec<_> = written.iter().map(|p| p.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names, ["WoWCombatLog-030225_210000-pull.txt", "WoWCombatLog-030225_210000-pull-2.txt"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parts_merge_back_into_the_log() {
+        let dir = scratch_dir("round_trip");
+        let log = fixture("part_b.txt");
+        let cut = offset_of(&log, "ENCOUNTER_END");
+        let end = std::fs::metadata(&log).unwrap().len();
+        let written = write_parts(&log, &[part("before", 0, cut), part("end", cut, end)], &dir).unwrap();
+
+        let merged = dir.join("merged.txt");
+        let report = merge(&written, &merged).unwrap();
+        // The second part's added header is the one already in force
+        assert_eq!(report.headers_dropped, 1);
+        assert_eq!(report.overlapping_lines, 0);
+        assert_eq!(std::fs::read_to_string(&merged).unwrap(), std::fs::read_to_string(&log).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+//! How players' numbers move across a night: per-encounter values for each player through a
+//! log, with a fitted trend line so improvement (or fatigue) shows at a glance, and who kept
+//! standing in the same mechanics pull after pull
+
+use std::collections::HashMap;
+
+use crate::models::*;
+
+/// Avoidable vs unavoidable damage taken per player across the boss pulls and Mythic+ keys
+/// of a log, in order. Trash, dungeon runs and other combat are left out.
+pub fn avoidable_trend<'a>(encounters: impl IntoIterator<Item = &'a EncounterSummary>) -> AvoidableTrend {
+    let mut trend = AvoidableTrend::default();
+    let mut players: Vec<PlayerAvoidableTrend> = Vec::new();
+    for enc in encounters.into_iter().filter(|e| e.encounter_type == "boss" || e.encounter_type == "mythic_plus") {
+        let encounter = trend.encounters.len();
+        trend.encounters.push(TrendEncounter {
+            index: enc.index,
+            stable_id: enc.stable_id.clone(),
+            name: enc.name.clone(),
+            start_time: enc.start_time.clone(),
+            success: enc.success,
+        });
+        let avoidable: HashMap<&str, u64> = enc.avoidable_damage.players.iter()
+            .map(|p| (p.player_name.as_str(), p.damage))
+            .collect();
+        for p in enc.players.iter().filter(|p| p.damage_taken > 0) {
+            let avoidable_damage = avoidable.get(p.name.as_str()).copied().unwrap_or(0).min(p.damage_taken);
+            let point = AvoidableTrendPoint {
+                encounter,
+                avoidable_damage,
+                unavoidable_damage: p.damage_taken - avoidable_damage,
+                avoidable_pct: avoidable_damage as f64 / p.damage_taken as f64 * 100.0,
+            };
+            let player = match players.iter().position(|t| t.player_name == p.name) {
+                Some(i) => &mut players[i],
+                None => {
+                    players.push(PlayerAvoidableTrend {
+                        player_name: p.name.clone(),
+                        damage_taken: 0,
+                        avoidable_damage: 0,
+                        unavoidable_damage: 0,
+                        avoidable_pct: 0.0,
+                        avoidable_pct_slope: 0.0,
+                        points: Vec::new(),
+                    });
+                    players.last_mut().unwrap()
+                }
+            };
+            player.damage_taken += p.damage_taken;
+            player.avoidable_damage += point.avoidable_damage;
+            player.unavoidable_damage += point.unavoidable_damage;
+            player.points.push(point);
+        }
+    }
+
+    for player in &mut players {
+        player.avoidable_pct = if player.damage_taken > 0 {
+            player.avoidable_damage as f64 / player.damage_taken as f64 * 100.0
+        } else {
+            0.0
+        };
+        player.avoidable_pct_slope = slope(player.points.iter().map(|p| (p.encounter as f64, p.avoidable_pct)));
+    }
+    players.sort_by_key(|p| std::cmp::Reverse(p.avoidable_damage));
+    trend.players = players;
+    trend
+}
+
+/// Column a mechanic hit table is sorted by, largest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MechanicHitSort {
+    #[default]
+    Hits,
+    Damage,
+    HitsPerPull,
+    PullsHit,
+}
+
+impl MechanicHitSort {
+    /// "hits", "damage", "hits_per_pull" or "pulls_hit"; anything else is None
+    pub fn parse(text: &str) -> Option<MechanicHitSort> {
+        match text.to_ascii_lowercase().as_str() {
+            "hits" => Some(MechanicHitSort::Hits),
+            "damage" => Some(MechanicHitSort::Damage),
+            "hits_per_pull" => Some(MechanicHitSort::HitsPerPull),
+            "pulls_hit" => Some(MechanicHitSort::PullsHit),
+            _ => None,
+        }
+    }
+}
+
+/// Avoidable-mechanic hits per player across all pulls of each boss (per difficulty) in a
+/// log, bosses in the order they were first pulled. Players who were in a pull count towards
+/// its pulls even without a hit.
+pub fn mechanic_hits<'a>(encounters: impl IntoIterator<Item = &'a EncounterSummary>, sort: MechanicHitSort) -> Vec<BossMechanicHits> {
+    let mut bosses: Vec<BossMechanicHits> = Vec::new();
+    for enc in encounters.into_iter().filter(|e| e.encounter_type == "boss") {
+        let boss = match bosses.iter().position(|b| b.encounter_id == enc.encounter_id && b.difficulty_name == enc.difficulty_name) {
+            Some(i) => &mut bosses[i],
+            None => {
+                bosses.push(BossMechanicHits {
+                    encounter_id: enc.encounter_id,
+                    name: enc.name.clone(),
+                    difficulty_name: enc.difficulty_name.clone(),
+                    pulls: 0,
+                    kills: 0,
+                    spells: Vec::new(),
+                    players: Vec::new(),
+                });
+                bosses.last_mut().unwrap()
+            }
+        };
+        boss.pulls += 1;
+        boss.kills += enc.success as u32;
+        for p in &enc.players {
+            let hits: Vec<&AvoidableHit> = enc.avoidable_hits.iter().filter(|h| h.player_name == p.name).collect();
+            let player = match boss.players.iter().position(|t| t.player_name == p.name) {
+                Some(i) => &mut boss.players[i],
+                None => {
+                    boss.players.push(PlayerMechanicHits {
+                        player_name: p.name.clone(),
+                        hits: 0,
+                        damage: 0,
+                        pulls: 0,
+                        pulls_hit: 0,
+                        hits_per_pull: 0.0,
+                        spells: Vec::new(),
+                    });
+                    boss.players.last_mut().unwrap()
+                }
+            };
+            player.pulls += 1;
+            player.pulls_hit += !hits.is_empty() as u32;
+            for hit in hits {
+                player.hits += 1;
+                player.damage += hit.amount;
+                add_spell_hit(&mut player.spells, hit);
+                add_spell_hit(&mut boss.spells, hit);
+            }
+        }
+    }
+
+    for boss in &mut bosses {
+        boss.players.retain(|p| p.hits > 0);
+        for player in &mut boss.players {
+            player.hits_per_pull = player.hits as f64 / player.pulls.max(1) as f64;
+            player.spells.sort_by_key(|s| std::cmp::Reverse(s.hits));
+        }
+        boss.spells.sort_by_key(|s| std::cmp::Reverse(s.hits));
+        boss.players.sort_by(|a, b| {
+            let order = match sort {
+                MechanicHitSort::Hits => b.hits.cmp(&a.hits),
+                MechanicHitSort::Damage => b.damage.cmp(&a.damage),
+                MechanicHitSort::HitsPerPull => b.hits_per_pull.partial_cmp(&a.hits_per_pull).unwrap_or(std::cmp::Ordering::Equal),
+                MechanicHitSort::PullsHit => b.pulls_hit.cmp(&a.pulls_hit),
+            };
+            order.then(b.hits.cmp(&a.hits)).then_with(|| a.player_name.cmp(&b.player_name))
+        });
+    }
+    bosses
+}
+
+fn add_spell_hit(spells: &mut Vec<MechanicSpellHits>, hit: &AvoidableHit) {
+    match spells.iter_mut().find(|s| s.spell_id == hit.spell_id) {
+        Some(spell) => {
+            spell.hits += 1;
+            spell.damage += hit.amount;
+        }
+        None => spells.push(MechanicSpellHits {
+            spell_id: hit.spell_id,
+            spell_name: hit.spell_name.clone(),
+            hits: 1,
+            damage: hit.amount,
+        }),
+    }
+}
+
+/// Slope of the least-squares line through the points (0 with fewer than two x values)
+pub(crate) fn slope(points: impl Iterator<Item = (f64, f64)> + Clone) -> f64 {
+    let n = points.clone().count() as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let mean_x = points.clone().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.clone().map(|(_, y)| y).sum::<f64>() / n;
+    let (covariance, variance) = points.fold((0.0, 0.0), |(cov, var), (x, y)| {
+        (cov + (x - mean_x) * (y - mean_y), var + (x - mean_x) * (x - mean_x))
+    });
+    if variance > 0.0 { covariance / variance } else { 0.0 }
+}
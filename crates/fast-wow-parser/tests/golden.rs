@@ -0,0 +1,246 @@
+//! Golden-file tests: every log excerpt in `tests/fixtures` is parsed and its summary
+//! compared with the `.json` file of the same name.
+//!
+//! After an intended change to the output, rewrite the golden files with
+//! `UPDATE_GOLDEN=1 cargo test -p fast-wow-parser --test golden` and review the diff.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let mut logs: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("reading {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    logs.sort();
+    logs
+}
+
+/// Floats are summed in hash map order, so the last digits can vary between runs
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+}
+
+/// Path to the first difference between two JSON values, with both sides
+fn first_difference(expected: &Value, actual: &Value, path: &str) -> Option<String> {
+    match (expected, actual) {
+        (Value::Number(a), Value::Number(b)) if a.is_f64() || b.is_f64() => {
+            let (a, b) = (a.as_f64().unwrap_or(f64::NAN), b.as_f64().unwrap_or(f64::NAN));
+            (!close(a, b)).then(|| format!("{}: expected {}, got {}", path, a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            for key in a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))) {
+                let (x, y) = (a.get(key).unwrap_or(&Value::Null), b.get(key).unwrap_or(&Value::Null));
+                if let Some(diff) = first_difference(x, y, &format!("{}.{}", path, key)) {
+                    return Some(diff);
+                }
+            }
+            None
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                if let Some(diff) = first_difference(x, y, &format!("{}[{}]", path, i)) {
+                    return Some(diff);
+                }
+            }
+            (a.len() != b.len()).then(|| format!("{}: {} items expected, got {}", path, a.len(), b.len()))
+        }
+        _ => (expected != actual).then(|| format!("{}: expected {}, got {}", path, expected, actual)),
+    }
+}
+
+#[test]
+fn summaries_match_golden_files() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let logs = fixtures();
+    assert!(!logs.is_empty(), "no fixtures found");
+
+    let mut failures = Vec::new();
+    for log in &logs {
+        let summary = fast_wow_parser::parse_combat_log(log)
+            .unwrap_or_else(|e| panic!("parsing {}: {}", log.display(), e));
+        assert!(summary.parse_error.is_none(), "{} crashed the parser: {:?}", log.display(), summary.parse_error);
+        let actual = serde_json::to_value(&summary).unwrap();
+        let golden = log.with_extension("json");
+
+        if update {
+            std::fs::write(&golden, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+            continue;
+        }
+        let expected: Value = match std::fs::read_to_string(&golden) {
+            Ok(text) => serde_json::from_str(&text).unwrap(),
+            Err(_) => {
+                failures.push(format!("{}: no golden file, run with UPDATE_GOLDEN=1", golden.display()));
+                continue;
+            }
+        };
+        if let Some(diff) = first_difference(&expected, &actual, "$") {
+            failures.push(format!("{}: {}", log.display(), diff));
+        }
+    }
+    assert!(failures.is_empty(), "summaries differ from golden files:\n{}", failures.join("\n"));
+}
+
+/// Parsing a fixture line by line with the streaming parser gives the same summary as
+/// parsing the file
+#[test]
+fn streaming_matches_file_parse() {
+    for log in fixtures() {
+        let from_file = serde_json::to_value(fast_wow_parser::parse_combat_log(&log).unwrap()).unwrap();
+        let text = std::fs::read_to_string(&log).unwrap();
+        let name = log.file_name().unwrap().to_str().unwrap();
+        let mut parser = fast_wow_parser::CombatLogParser::new(name);
+        for line in text.lines() {
+            parser.process_line(line);
+        }
+        let streamed = serde_json::to_value(parser.finish()).unwrap();
+        assert_eq!(first_difference(&from_file, &streamed, "$"), None, "{}", log.display());
+    }
+}
+
+/// Whether a map key fits a `patternProperties` pattern; the models only have integer keys
+fn key_matches(pattern: &str, key: &str) -> bool {
+    match pattern {
+        r"^\d+$" => !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()),
+        r"^-?\d+$" => key.parse::<i64>().is_ok(),
+        _ => true,
+    }
+}
+
+/// First place `value` doesn't fit `schema`, resolving `$ref`s against `root`. Stricter than
+/// JSON Schema in one way: an object key the schema doesn't list is an error, so a field
+/// serialized but missing from the schema is caught.
+fn schema_mismatch(root: &Value, schema: &Value, value: &Value, path: &str) -> Option<String> {
+    if let Some(name) = schema.get("$ref").and_then(Value::as_str).and_then(|r| r.strip_prefix("#/$defs/")) {
+        return schema_mismatch(root, &root["$defs"][name], value, path);
+    }
+    if let Some(branches) = schema.get("anyOf").or_else(|| schema.get("oneOf")).and_then(Value::as_array) {
+        let mismatches: Vec<String> = branches.iter().filter_map(|b| schema_mismatch(root, b, value, path)).collect();
+        return (mismatches.len() == branches.len()).then(|| mismatches.join(" / "));
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            return Some(format!("{}: {} is not one of {:?}", path, value, options));
+        }
+    }
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let fits = |t: &str| match t {
+            "null" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "string" => value.is_string(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => true,
+        };
+        if !types.iter().any(|t| fits(t)) {
+            return Some(format!("{}: {} is not {:?}", path, value, types));
+        }
+    }
+    match value {
+        Value::Object(fields) => {
+            for required in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !fields.contains_key(required) {
+                    return Some(format!("{}: missing {}", path, required));
+                }
+            }
+            for (key, field) in fields {
+                let field_schema = schema.get("properties").and_then(|p| p.get(key))
+                    .or_else(|| schema.get("patternProperties")?.as_object()?.iter()
+                        .find(|(pattern, _)| key_matches(pattern, key)).map(|(_, s)| s))
+                    .or_else(|| schema.get("additionalProperties").filter(|s| s.is_object()));
+                let Some(field_schema) = field_schema else {
+                    return Some(format!("{}: {} is not in the schema", path, key));
+                };
+                if let Some(mismatch) = schema_mismatch(root, field_schema, field, &format!("{}.{}", path, key)) {
+                    return Some(mismatch);
+                }
+            }
+            None
+        }
+        Value::Array(items) => {
+            let prefix = schema.get("prefixItems").and_then(Value::as_array);
+            items.iter().enumerate().find_map(|(i, item)| {
+                let item_schema = prefix.and_then(|p| p.get(i)).or_else(|| schema.get("items"))?;
+                schema_mismatch(root, item_schema, item, &format!("{}[{}]", path, i))
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Every fixture's summary fits the published JSON Schema (`/api/schema`)
+#[test]
+fn summaries_fit_the_schema() {
+    let schema = fast_wow_parser::summary_schema();
+    for log in fixtures() {
+        let summary = serde_json::to_value(fast_wow_parser::parse_combat_log(&log).unwrap()).unwrap();
+        assert_eq!(schema_mismatch(&schema, &schema, &summary, "$"), None, "{}", log.display());
+    }
+}
+
+/// Re-parsing one encounter's byte range knows the zone it was in, though its ZONE_CHANGE
+/// is outside the range, and so gives it the same instance metadata as the full parse
+#[test]
+fn range_parse_keeps_the_zone() {
+    for log in fixtures() {
+        let full = fast_wow_parser::parse_combat_log(&log).unwrap();
+        for enc in full.encounters.iter().filter(|e| e.range.is_some()) {
+            let sliced = fast_wow_parser::parser::parse_combat_log_range(&log, Default::default(), enc.range.as_ref().unwrap()).unwrap();
+            let zone = sliced.zone_changes.first().map(|z| z.zone_id);
+            assert!(zone.is_some_and(|id| full.zone_changes.iter().any(|z| z.zone_id == id)), "{}: {} has no zone", log.display(), enc.name);
+            let again = sliced.encounters.iter().find(|e| e.start_time == enc.start_time)
+                .unwrap_or_else(|| panic!("{}: {} missing from its range", log.display(), enc.name));
+            assert_eq!((&again.instance_name, &again.season, &again.instance_icon), (&enc.instance_name, &enc.season, &enc.instance_icon),
+                "{}: {}", log.display(), enc.name);
+        }
+    }
+}
+
+/// A player redacted as private appears nowhere in the serialized summary, replays or spell
+/// searches, by name or GUID
+#[test]
+fn redacted_players_appear_nowhere() {
+    use fast_wow_parser::anonymize;
+    use fast_wow_parser::models::ReplayData;
+    use fast_wow_parser::parser;
+
+    for log in fixtures() {
+        let summary = fast_wow_parser::parse_combat_log(&log).unwrap();
+        for player in summary.encounters.iter().flat_map(|e| &e.players) {
+            let names = std::slice::from_ref(&player.name);
+            let bare = player.name.split('-').next().unwrap();
+            let assert_absent = |what: &str, text: &str| {
+                for needle in [bare, player.guid.as_str()] {
+                    assert!(!text.contains(needle), "{}: {} is still in the redacted {}", log.display(), needle, what);
+                }
+            };
+
+            let mut encounters = summary.encounters.clone();
+            anonymize::redact_players(&mut encounters, names);
+            assert_absent("summary", &serde_json::to_string(&encounters).unwrap());
+
+            for enc in &summary.encounters {
+                let mut replay = ReplayData::from_encounter(enc, Vec::new());
+                anonymize::redact_replay(&mut replay, &enc.players, names);
+                assert_absent("replay", &serde_json::to_string(&replay).unwrap());
+            }
+
+            let abilities = player.abilities.iter().chain(&player.heal_abilities).chain(&player.damage_taken_abilities);
+            for spell_id in abilities.map(|a| a.spell_id).filter(|id| *id > 0).take(3) {
+                let (spell_name, hits) = parser::find_spell_events(&log, spell_id).unwrap();
+                let mut result = parser::group_spell_hits(spell_id, spell_name, &hits, &summary.encounters);
+                anonymize::redact_spell_search(&mut result, names);
+                assert_absent("spell search", &serde_json::to_string(&result).unwrap());
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 import { Routes, Route } from 'react-router-dom'
 import Header from './components/Header'
 import Footer from './components/Footer'
+import NoticeBanner from './components/NoticeBanner'
 import SpellTooltipGlobal from './components/SpellTooltip'
 import LogList from './pages/LogList'
 import EncounterList from './pages/EncounterList'
@@ -11,6 +12,7 @@ export default function App() {
         <>
             <Header />
             <div className="container" id="app">
+                <NoticeBanner />
                 <Routes>
                     <Route path="/" element={<LogList />} />
                     <Route path="/log/:filename" element={<EncounterList />} />
@@ -1,7 +1,14 @@
-import type { LogFileInfo, CombatLogSummary } from './types';
+import type { LogFileInfo, CombatLogSummary, EncounterNotes, SpellSearchResult, SearchHit, LiveUpdate, RouteComparison } from './types';
 
 const API_BASE = '';
 
+/** `?anonymize=1` on the page carries over to report requests, so a shared link stays anonymized */
+function shareQuery(params = new URLSearchParams()): string {
+    if (new URLSearchParams(window.location.search).has('anonymize')) params.set('anonymize', 'true');
+    const query = params.toString();
+    return query ? `?${query}` : '';
+}
+
 export async function fetchLogs(): Promise<LogFileInfo[]> {
     const res = await fetch(`${API_BASE}/api/logs`);
     if (!res.ok) throw new Error(await res.text());
@@ -12,16 +19,113 @@ export interface SummaryResponse {
     summary: CombatLogSummary;
     cacheStatus: string;
     parseTime: string;
+    /** Short wipes and resets left out of `summary` (see `includeTrivial`) */
+    trivialHidden: number;
 }
 
-export async function fetchSummary(filename: string, noCache = false): Promise<SummaryResponse> {
+/** Window width time series are downsampled to on the server; full resolution without one */
+export type Resolution = '1s' | '5s' | '30s';
+
+export async function fetchSummary(filename: string, noCache = false, includeTrivial = false, resolution?: Resolution): Promise<SummaryResponse> {
     const opts: RequestInit = noCache ? { cache: 'no-store' } : {};
-    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/summary`, opts);
+    const params = new URLSearchParams(includeTrivial ? { include_trivial: 'true' } : {});
+    if (resolution) params.set('resolution', resolution);
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/summary${shareQuery(params)}`, opts);
     if (!res.ok) throw new Error(await res.text());
     const cacheStatus = res.headers.get('X-Cache-Status') || 'UNKNOWN';
     const parseTime = res.headers.get('X-Parse-Time') || '0';
+    const trivialHidden = Number(res.headers.get('X-Trivial-Hidden') || '0');
     const summary = await res.json();
-    return { summary, cacheStatus, parseTime };
+    return { summary, cacheStatus, parseTime, trivialHidden };
+}
+
+export async function fetchGrouped(filename: string, includeTrivial = false): Promise<import('./types').InstanceGroup[]> {
+    const params = new URLSearchParams(includeTrivial ? { include_trivial: 'true' } : {});
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/grouped?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchAvoidableTrend(filename: string, includeTrivial = false): Promise<import('./types').AvoidableTrend> {
+    const params = new URLSearchParams(includeTrivial ? { include_trivial: 'true' } : {});
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/avoidable_trend?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export type MechanicHitSort = 'hits' | 'damage' | 'hits_per_pull' | 'pulls_hit';
+
+export async function fetchMechanicHits(filename: string, sort: MechanicHitSort = 'hits', includeTrivial = false): Promise<import('./types').BossMechanicHits[]> {
+    const params = new URLSearchParams({ sort, ...(includeTrivial ? { include_trivial: 'true' } : {}) });
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/mechanic_hits?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchVault(weeksAgo = 0): Promise<import('./types').VaultWeek> {
+    const params = new URLSearchParams(weeksAgo > 0 ? { weeks_ago: String(weeksAgo) } : {});
+    const res = await fetch(`${API_BASE}/api/vault?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchLeaderboards(filter: { season?: string; difficulty?: string } = {}): Promise<import('./types').Leaderboards> {
+    const params = new URLSearchParams(Object.entries(filter).filter(([, v]) => v) as [string, string][]);
+    const res = await fetch(`${API_BASE}/api/leaderboards?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchPlanner(query: { encounter?: string; difficulty?: string } = {}): Promise<import('./types').Planner> {
+    const params = new URLSearchParams(Object.entries(query).filter(([, v]) => v) as [string, string][]);
+    const res = await fetch(`${API_BASE}/api/planner?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchFatigue(weeks?: number): Promise<import('./types').Fatigue> {
+    const params = new URLSearchParams(weeks ? { weeks: String(weeks) } : {});
+    const res = await fetch(`${API_BASE}/api/fatigue?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchDungeonStats(zoneId: number): Promise<import('./types').DungeonStats> {
+    const res = await fetch(`${API_BASE}/api/dungeons/${zoneId}/stats`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchCharacterBuilds(character: string): Promise<import('./types').BuildTimeline> {
+    const res = await fetch(`${API_BASE}/api/characters/${encodeURIComponent(character)}/builds`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchCharacterAliases(): Promise<import('./types').CharacterAlias[]> {
+    const res = await fetch(`${API_BASE}/api/characters/aliases`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function setCharacterAlias(alias: string, character: string): Promise<import('./types').CharacterAlias> {
+    const res = await fetch(`${API_BASE}/api/characters/aliases`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ alias, character }),
+    });
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function removeCharacterAlias(alias: string): Promise<void> {
+    const res = await fetch(`${API_BASE}/api/characters/aliases/${encodeURIComponent(alias)}`, { method: 'DELETE' });
+    if (!res.ok) throw new Error(await res.text());
+}
+
+export async function setFavorite(filename: string, favorite: boolean): Promise<void> {
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/favorite`, { method: favorite ? 'POST' : 'DELETE' });
+    if (!res.ok) throw new Error(await res.text());
 }
 
 export async function fetchSpellTooltips(): Promise<Record<string, { name?: string; icon_url?: string; description?: string }>> {
@@ -34,8 +138,141 @@ export async function fetchSpellTooltips(): Promise<Record<string, { name?: stri
     }
 }
 
-export async function fetchReplayData(filename: string, index: number): Promise<import('./types').ReplayData> {
-    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${index}/replay`);
+export async function fetchReplayData(filename: string, stableId: string, resolution?: Resolution): Promise<import('./types').ReplayData> {
+    const params = new URLSearchParams(resolution ? { resolution } : {});
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/replay${shareQuery(params)}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+/** Details!-style text summary of the damage or healing meter, for pasting into chat */
+export async function fetchExportText(filename: string, stableId: string, meter: 'damage' | 'healing'): Promise<string> {
+    const params = new URLSearchParams({ meter });
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/export${shareQuery(params)}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.text();
+}
+
+/** Join the logs of one session into a single log next to the earliest of them */
+export async function mergeLogs(filenames: string[]): Promise<import('./types').MergedLog> {
+    const res = await fetch(`${API_BASE}/api/logs/merge`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ filenames }),
+    });
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+/** Split a log into one log per play session or per encounter, written to a Split folder next to it */
+export async function splitLog(filename: string, by: 'session' | 'encounter'): Promise<import('./types').SplitFile[]> {
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/split?by=${by}`, { method: 'POST' });
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+/** Download link for an Excel workbook of one encounter, or of the whole log without `stableId` */
+export function xlsxUrl(filename: string, stableId?: string, includeTrivial = false): string {
+    const log = `${API_BASE}/api/logs/${encodeURIComponent(filename)}`;
+    if (stableId) return `${log}/encounter/${stableId}/xlsx`;
+    return `${log}/xlsx${includeTrivial ? '?include_trivial=true' : ''}`;
+}
+
+export async function fetchAbilityTimeline(filename: string, stableId: string, player: string, resolution?: Resolution): Promise<import('./types').AbilityTimeline> {
+    const params = new URLSearchParams({ player });
+    if (resolution) params.set('resolution', resolution);
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/abilities${shareQuery(params)}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchAuraSnapshot(filename: string, stableId: string, player: string, time: number): Promise<import('./types').AuraSnapshot> {
+    const params = new URLSearchParams({ player, time: String(time) });
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/auras${shareQuery(params)}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchAuraLanes(filename: string, stableId: string, options: { player?: string; keyOnly?: boolean } = {}): Promise<import('./types').PlayerAuraLanes[]> {
+    const params = new URLSearchParams();
+    if (options.player) params.set('player', options.player);
+    if (options.keyOnly) params.set('key_only', 'true');
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/aura_lanes${shareQuery(params)}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function saveEncounterNotes(filename: string, stableId: string, text: string, tags: string[]): Promise<EncounterNotes> {
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/notes`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ text, tags }),
+    });
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function addBookmark(filename: string, stableId: string, name: string, offsetSecs: number): Promise<import('./types').Bookmark> {
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/bookmarks`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ name, offset_secs: offsetSecs }),
+    });
     if (!res.ok) throw new Error(await res.text());
     return res.json();
 }
+
+export async function deleteBookmark(filename: string, stableId: string, id: number): Promise<void> {
+    const res = await fetch(`${API_BASE}/api/logs/${encodeURIComponent(filename)}/encounter/${stableId}/bookmarks/${id}`, { method: 'DELETE' });
+    if (!res.ok) throw new Error(await res.text());
+}
+
+export async function searchSpell(filename: string, spellId: number): Promise<SpellSearchResult> {
+    const res = await fetch(`${API_BASE}/api/search/spell?id=${spellId}&file=${encodeURIComponent(filename)}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function searchEncounters(query: string): Promise<SearchHit[]> {
+    const res = await fetch(`${API_BASE}/api/search?q=${encodeURIComponent(query)}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+/** Subscribe to live mode updates, with the view of `player` (a GUID from the roster) if
+ * given; returns a function that closes the connection */
+export function connectLive(onUpdate: (update: LiveUpdate | null) => void, player?: string | null): () => void {
+    const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+    const socket = new WebSocket(`${protocol}//${window.location.host}${API_BASE}/api/live`);
+    socket.onmessage = e => onUpdate(JSON.parse(e.data));
+    if (player) socket.onopen = () => socket.send(JSON.stringify({ player }));
+    return () => socket.close();
+}
+
+export async function compareRuns(aFile: string, a: string, bFile: string, b: string): Promise<RouteComparison> {
+    const params = new URLSearchParams({ a_file: aFile, a, b_file: bFile, b });
+    const res = await fetch(`${API_BASE}/api/compare?${params}`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function fetchJobs(): Promise<import('./types').ParseJob[]> {
+    const res = await fetch(`${API_BASE}/api/jobs`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+/** Things the user should fix, such as advanced combat logging being off */
+export async function fetchNotices(): Promise<import('./types').Notice[]> {
+    const res = await fetch(`${API_BASE}/api/notices`);
+    if (!res.ok) throw new Error(await res.text());
+    return res.json();
+}
+
+export async function dismissNotice(id: string): Promise<void> {
+    const res = await fetch(`${API_BASE}/api/notices/${encodeURIComponent(id)}`, { method: 'DELETE' });
+    if (!res.ok) throw new Error(await res.text());
+}
+
+/** Download link for the Config.wtf line that switches advanced combat logging on */
+export const advancedLoggingSnippetUrl = `${API_BASE}/api/notices/advanced_logging/config.wtf`;
@@ -0,0 +1,44 @@
+import { useEffect, useState } from 'react'
+import { useLocation } from 'react-router-dom'
+import { advancedLoggingSnippetUrl, dismissNotice, fetchNotices } from '../api'
+import type { Notice } from '../types'
+
+const POLL_MS = 10000
+
+/** Settings to fix in the game, e.g. advanced combat logging being off; shown on every page */
+export default function NoticeBanner() {
+    const [notices, setNotices] = useState<Notice[]>([])
+    const location = useLocation()
+
+    // Opening a log can raise one, so check on every page change too
+    useEffect(() => {
+        const poll = () => fetchNotices().then(setNotices).catch(() => { })
+        poll()
+        const timer = setInterval(poll, POLL_MS)
+        return () => clearInterval(timer)
+    }, [location.pathname])
+
+    const dismiss = (id: string) => {
+        dismissNotice(id).catch(() => { })
+        setNotices(prev => prev.filter(n => n.id !== id))
+    }
+
+    if (notices.length === 0) return null
+    return (
+        <>
+            {notices.map(notice => (
+                <div key={notice.id} className="card" style={{ borderColor: 'var(--accent-orange)', marginBottom: 16 }}>
+                    <div className="card-header">
+                        <div className="card-title">⚠️ {notice.title}</div>
+                        <a href="#" onClick={e => { e.preventDefault(); dismiss(notice.id) }} title="Hide until another log raises it">dismiss</a>
+                    </div>
+                    <p style={{ margin: '8px 0' }}>{notice.message}</p>
+                    <p style={{ margin: 0 }}>
+                        <strong>Fix:</strong> {notice.fix}
+                        {notice.id === 'advanced_logging' && <> <a href={advancedLoggingSnippetUrl} download>Download the Config.wtf line</a></>}
+                    </p>
+                </div>
+            ))}
+        </>
+    )
+}
@@ -64,6 +64,8 @@ export function spellHtml(
     opts?: { color?: string; iconSize?: number; stopClick?: boolean }
 ): string {
     const tt = getTooltip(spellId, spellName) || {} as { icon_url?: string; name?: string; description?: string }
+    // The tooltip store's name is the same for every client locale; the log's name is the fallback
+    if (tt.name && spellId) spellName = tt.name
     const linkColor = opts?.color || 'var(--accent-gold)'
     const iconSize = opts?.iconSize || 18
     const iconHtml = tt.icon_url ? `<img class="spell-icon" src="${tt.icon_url}" alt="" loading="lazy" style="width:${iconSize}px;height:${iconSize}px">` : ''
@@ -0,0 +1,23 @@
+import { useEffect, useState } from 'react';
+import type { ParseJob } from '../types';
+import { fetchJobs } from '../api';
+
+const POLL_MS = 1000;
+
+/** The server's queued and running parses, polled while `active` */
+export function useParseJobs(active: boolean): ParseJob[] {
+    const [jobs, setJobs] = useState<ParseJob[]>([]);
+
+    useEffect(() => {
+        if (!active) return;
+        let cancelled = false;
+        const poll = () => fetchJobs()
+            .then(all => { if (!cancelled) setJobs(all.filter(j => j.status === 'queued' || j.status === 'running')) })
+            .catch(() => { });
+        poll();
+        const timer = setInterval(poll, POLL_MS);
+        return () => { cancelled = true; clearInterval(timer) };
+    }, [active]);
+
+    return jobs;
+}
@@ -1,18 +1,19 @@
-import { useEffect, useState, useRef, useCallback, useMemo } from 'react'
+import { Fragment, useEffect, useState, useRef, useCallback, useMemo } from 'react'
 import { useParams, Link, useNavigate, useSearchParams } from 'react-router-dom'
-import { fetchSummary, fetchReplayData } from '../../api'
-import type { CombatLogSummary, EncounterSummary, PlayerSummary, AbilityBreakdown, KeySegment, TrashPull, ReplayData } from '../../types'
+import { fetchSummary, fetchReplayData, fetchAbilityTimeline, fetchExportText, addBookmark, xlsxUrl } from '../../api'
+import type { CombatLogSummary, EncounterSummary, PlayerSummary, AbilityBreakdown, KeySegment, TrashPull, ReplayData, ViewBox, AbilityTimeline, PracticeSession, ArenaMatch, DepletionReport, Insight, Bookmark, SchoolSplit, CooldownUsage, AddTimeline } from '../../types'
 import { formatDuration, formatNumber, classColor, roleIcon, getSchoolColor } from '../../utils'
 import { spellHtml } from '../../components/SpellTooltip'
 import { useSpellTooltips } from '../../hooks/useSpellTooltips'
 
-type TabId = 'damage' | 'healing' | 'taken' | 'deaths' | 'abilities' | 'enemies' | 'buffs' | 'debuffs' | 'replay'
+type TabId = 'damage' | 'healing' | 'taken' | 'deaths' | 'efficiency' | 'abilities' | 'enemies' | 'buffs' | 'debuffs' | 'replay'
 
 const TABS: { id: TabId; icon: string; label: string; always?: boolean }[] = [
     { id: 'damage', icon: '⚔️', label: 'Damage Done', always: true },
     { id: 'healing', icon: '💚', label: 'Healing Done', always: true },
     { id: 'taken', icon: '🛡️', label: 'Damage Taken', always: true },
     { id: 'deaths', icon: '💀', label: 'Deaths', always: true },
+    { id: 'efficiency', icon: '🎯', label: 'Efficiency', always: true },
     { id: 'abilities', icon: '📊', label: 'Abilities', always: true },
     { id: 'enemies', icon: '👹', label: 'Enemies', always: true },
     { id: 'buffs', icon: '🔮', label: 'Buff Uptime', always: true },
@@ -61,7 +62,7 @@ function getFilteredEncData(
         for (const p of players) {
             const existing = playerMap.get(p.guid)
             if (!existing) {
-                playerMap.set(p.guid, { ...p, abilities: [...p.abilities], heal_abilities: [...p.heal_abilities], damage_taken_abilities: [...p.damage_taken_abilities] })
+                playerMap.set(p.guid, { ...p, abilities: copyAbilities(p.abilities), heal_abilities: copyAbilities(p.heal_abilities), damage_taken_abilities: copyAbilities(p.damage_taken_abilities) })
             } else {
                 existing.damage_done += p.damage_done
                 existing.healing_done += p.healing_done
@@ -118,16 +119,32 @@ function getFilteredEncData(
     }
 }
 
+// Rows are the same ability when they share a spell ID, whatever name each was logged with;
+// pet groups (spell ID 0) match by pet name
+function sameAbility(a: AbilityBreakdown, b: AbilityBreakdown): boolean {
+    return a.spell_id === b.spell_id && (a.spell_id !== 0 || a.spell_name === b.spell_name)
+}
+
 function mergeAbilities(target: AbilityBreakdown[], source: AbilityBreakdown[]) {
     for (const sa of source) {
-        const existing = target.find(a => a.spell_id === sa.spell_id)
+        const existing = target.find(a => sameAbility(a, sa))
         if (existing) {
             existing.total_amount += sa.total_amount
             existing.hit_count += sa.hit_count
+            if (sa.sub_abilities?.length) {
+                if (!existing.sub_abilities) existing.sub_abilities = []
+                mergeAbilities(existing.sub_abilities, sa.sub_abilities)
+            }
         } else {
-            target.push({ ...sa })
+            target.push(...copyAbilities([sa]))
         }
     }
+    target.sort((a, b) => b.total_amount - a.total_amount)
+}
+
+// Merging adds into the rows, so it works on copies of the parsed data
+function copyAbilities(list: AbilityBreakdown[]): AbilityBreakdown[] {
+    return list.map(a => ({ ...a, sub_abilities: a.sub_abilities ? copyAbilities(a.sub_abilities) : undefined }))
 }
 
 export default function EncounterDetail() {
@@ -147,7 +164,9 @@ export default function EncounterDetail() {
     const [replayData, setReplayData] = useState<ReplayData | null>(null)
     const [replayLoading, setReplayLoading] = useState(false)
 
-    const encIndex = Number(index)
+    // The URL holds the stable ID; plain indices still work for old links
+    const stableIdx = summary?.encounters.findIndex(e => e.stable_id === index) ?? -1
+    const encIndex = stableIdx >= 0 ? stableIdx : Number(index)
 
     // Sync activeTab when URL tab param changes (e.g. browser back/forward)
     useEffect(() => {
@@ -157,7 +176,8 @@ export default function EncounterDetail() {
 
     useEffect(() => {
         if (!filename) return
-        fetchSummary(filename)
+        // Short pulls stay reachable from links and search
+        fetchSummary(filename, false, true)
             .then(({ summary }) => { setSummary(summary); setLoading(false) })
             .catch(e => { setError(e.message); setLoading(false) })
     }, [filename])
@@ -201,7 +221,7 @@ export default function EncounterDetail() {
         if (activeTab === 'replay') {
             if (!replayData && !replayLoading && filename) {
                 setReplayLoading(true)
-                fetchReplayData(filename, encIndex)
+                fetchReplayData(filename, enc.stable_id)
                     .then(data => { setReplayData(data); setReplayLoading(false) })
                     .catch(() => setReplayLoading(false))
             }
@@ -209,7 +229,13 @@ export default function EncounterDetail() {
             if (replayData && enc.replay_timeline === undefined) {
                 enc.replay_timeline = replayData.replay_timeline
                 enc.boss_positions = replayData.boss_positions
+                enc.enemy_casts = replayData.enemy_casts
+                enc.mechanic_markers = replayData.mechanic_markers
+                enc.replay_maps = replayData.maps
+                enc.replay_camera = replayData.camera
+                enc.avoidable_hits = replayData.avoidable_hits
                 enc.raw_ability_events = replayData.raw_ability_events
+                enc.bookmarks = replayData.bookmarks
             }
         }
 
@@ -241,7 +267,7 @@ export default function EncounterDetail() {
         if (activeTab === 'replay' && enc.replay_timeline && enc.replay_timeline.length > 0) {
             // Clean up any previous replay before starting a new one
             if ((window as any).__replayCleanup) { (window as any).__replayCleanup(); (window as any).__replayCleanup = null }
-            setTimeout(() => initReplayControls(enc), 0)
+            setTimeout(() => initReplayControls(enc, filename), 0)
         }
         // Init timeline range slider for damage tab
         if (activeTab === 'damage' && enc.time_bucketed_player_damage && Object.keys(enc.time_bucketed_player_damage).length > 0 && enc.encounter_type === 'boss') {
@@ -357,6 +383,15 @@ export default function EncounterDetail() {
                 const pid = tabBtn.getAttribute('data-detail-pid')!
                 const tab = tabBtn.getAttribute('data-detail-tab')!
                 showDetailTab(pid, tab)
+                // The per-ability timeline is fetched the first time it is opened
+                const panel = document.getElementById(`${pid}-timeline`)
+                if (tab === 'timeline' && panel && !panel.dataset.loaded && enc && filename) {
+                    panel.dataset.loaded = 'true'
+                    panel.innerHTML = '<div style="padding:12px;color:var(--text-muted);font-size:12px">Loading…</div>'
+                    fetchAbilityTimeline(filename, enc.stable_id, panel.dataset.abilityTimeline!, '5s')
+                        .then(timeline => { panel.innerHTML = renderAbilityTimeline(timeline) })
+                        .catch(err => { panel.innerHTML = `<div style="padding:12px;color:var(--accent-red);font-size:12px">${err.message}</div>` })
+                }
                 return
             }
             // Handle death recap toggle
@@ -417,9 +452,10 @@ export default function EncounterDetail() {
             // Navigate to encounter
             const encNav = target.closest('[data-enc-index]')
             if (encNav) {
-                const navIdx = encNav.getAttribute('data-enc-index')!
+                const navIdx = Number(encNav.getAttribute('data-enc-index'))
+                const navId = summary?.encounters[navIdx]?.stable_id ?? navIdx
                 const tabParam = activeTab !== 'damage' ? `?tab=${activeTab}` : ''
-                navigate(`/log/${encodeURIComponent(filename!)}/encounter/${navIdx}${tabParam}`)
+                navigate(`/log/${encodeURIComponent(filename!)}/encounter/${navId}${tabParam}`)
                 return
             }
         }
@@ -488,7 +524,7 @@ export default function EncounterDetail() {
     // Stats
     const totalDmg = enc.players.reduce((s, p) => s + p.damage_done, 0)
     const totalHeal = enc.players.reduce((s, p) => s + p.healing_done, 0)
-    const typeIcon = enc.encounter_type === 'mythic_plus' ? '🗝️' : enc.encounter_type === 'dungeon' ? '🏰' : enc.encounter_type === 'trash' ? '🗑️' : '⚔️'
+    const typeIcon = enc.encounter_type === 'mythic_plus' ? '🗝️' : enc.encounter_type === 'dungeon' ? '🏰' : enc.encounter_type === 'trash' ? '🗑️' : enc.encounter_type === 'practice' ? '🎯' : enc.encounter_type === 'arena' ? '🏟️' : '⚔️'
     const hasReplay = (enc.replay_timeline && enc.replay_timeline.length > 0) || replayData !== null || enc.encounter_type === 'boss'
 
     return (
@@ -500,14 +536,23 @@ export default function EncounterDetail() {
 
             <h1 className="page-title">{typeIcon} {enc.name}</h1>
             <p className="page-subtitle">
-                {enc.encounter_type !== 'trash' && (
+                {enc.encounter_type !== 'trash' && enc.encounter_type !== 'combat' && enc.encounter_type !== 'practice' && !isSoloShuffle(enc) && (
                     <span className={`encounter-result ${enc.success ? 'kill' : 'wipe'}`} style={{ fontSize: 13 }}>
                         {enc.encounter_type === 'mythic_plus'
                             ? (enc.success ? '✓ Timed' : '✗ Depleted')
-                            : (enc.success ? '✓ Kill' : '✗ Wipe')}
+                            : enc.encounter_type === 'arena'
+                                ? (enc.success ? '✓ Victory' : '✗ Defeat')
+                                : (enc.success ? '✓ Kill' : '✗ Wipe')}
                     </span>
                 )}
                 &nbsp; {formatDuration(enc.duration_secs)} — {enc.difficulty_name} — {enc.group_size} players
+                &nbsp; <a href={`/e/${enc.stable_id}`} title="Copy a short link to this encounter"
+                    onClick={e => { e.preventDefault(); navigator.clipboard?.writeText(`${window.location.origin}/e/${enc.stable_id}`) }}>🔗 Copy link</a>
+                &nbsp; <a href={`${window.location.pathname}?anonymize=1`} title="Copy a link that shows players as Tank1, Healer2, DPS3..."
+                    onClick={e => { e.preventDefault(); navigator.clipboard?.writeText(`${window.location.origin}${window.location.pathname}?anonymize=1`) }}>🕶 Copy anonymized link</a>
+                &nbsp; <a href="#" title="Copy the damage (or, on the Healing tab, healing) meter as Details! text for Discord"
+                    onClick={e => { e.preventDefault(); if (filename) fetchExportText(filename, enc.stable_id, activeTab === 'healing' ? 'healing' : 'damage').then(text => navigator.clipboard?.writeText(text)).catch(() => {}) }}>📋 Copy Details! text</a>
+                {filename && <>&nbsp; <a href={xlsxUrl(filename, enc.stable_id)} download title="Players, abilities, deaths, buffs and segments as an Excel workbook">📊 Excel</a></>}
             </p>
 
             <div className="stats-grid">
@@ -659,15 +704,83 @@ export default function EncounterDetail() {
                                                 transition: 'opacity 0.2s, background 0.2s',
                                                 minWidth: 18,
                                             }}
-                                            title={`Pull ${pull.pull_index + 1} (${formatDuration(pull.duration_secs)})`}
+                                            title={`Pull ${pull.pull_index + 1} (${formatDuration(pull.duration_secs)})${pull.opener?.aggro_lost ? ' — an enemy went for a non-tank first' : ''}`}
                                         >
-                                            {`P${pull.pull_index + 1}`}
+                                            {`P${pull.pull_index + 1}`}{pull.opener?.aggro_lost && <span style={{ color: 'var(--accent-orange)' }}>⚠</span>}
                                         </div>
                                     )
                                 })}
                             </div>
                         )
                     })()}
+                    {/* Opener of a single selected pull, for tank review */}
+                    {(() => {
+                        if (!selectedSegments || selectedSegments.size !== 1 || !selectedPulls || selectedPulls.size !== 1) return null
+                        const seg = enc.segments.find(s => s.index === Array.from(selectedSegments)[0])
+                        const pull = seg?.pulls?.find(p => p.pull_index === Array.from(selectedPulls)[0])
+                        const opener = pull?.opener
+                        if (!opener) return null
+                        const at = (secs: number) => `${secs < 0 ? '−' : '+'}${Math.abs(secs).toFixed(1)}s`
+                        const firstTarget = opener.first_targets[0]
+                        const offTank = opener.first_targets.filter(t => !t.on_tank).length
+                        const tanking = pull?.tanking || []
+                        const pullSecs = Math.max(pull?.duration_secs || 0, 1)
+                        return (
+                            <>
+                            <div style={{ display: 'flex', flexWrap: 'wrap', gap: 14, marginTop: 6, fontSize: 12, color: 'var(--text-secondary)' }}>
+                                {opener.opened_by && <span>🎯 Opened by <strong>{opener.opened_by}</strong></span>}
+                                {firstTarget && (
+                                    <span title={opener.first_targets.map(t => `${t.enemy_name} → ${t.player_name} (${at(t.offset_secs)})`).join('\n')}>
+                                        🛡️ First aggro: <strong>{firstTarget.player_name}</strong> ({firstTarget.enemy_name})
+                                        {offTank > 0 && <span style={{ color: 'var(--accent-orange)' }}> · {offTank} of {opener.first_targets.length} enemies went for a non-tank</span>}
+                                    </span>
+                                )}
+                                {opener.top_early_damage && (
+                                    <span title={`Damage in the first 5 seconds of the pull; the most by a tank was ${formatNumber(opener.top_early_damage.tank_damage)}`}>
+                                        🔥 Early damage: <strong>{opener.top_early_damage.player_name}</strong> {formatNumber(opener.top_early_damage.damage)}
+                                        {opener.top_early_damage.damage > opener.top_early_damage.tank_damage && <span style={{ color: 'var(--accent-orange)' }}> · out-damaged the tanks</span>}
+                                    </span>
+                                )}
+                                {opener.redirects.length > 0
+                                    ? opener.redirects.map((r, i) => <span key={`r${i}`}>↪️ {r.spell_name}: {r.caster_name} → {r.target_name} ({at(r.offset_secs)})</span>)
+                                    : <span style={{ color: 'var(--text-muted)' }}>↪️ No Misdirection/Tricks</span>}
+                                {opener.taunts.length > 0 && <span>📢 {opener.taunts.length} {opener.taunts.length === 1 ? 'taunt' : 'taunts'} (first {at(opener.taunts[0].offset_secs)})</span>}
+                            </div>
+                            {/* Tanked by: who each enemy was attacking, loose time in orange */}
+                            {tanking.length > 0 && (
+                                <div style={{ marginTop: 6, display: 'grid', gridTemplateColumns: 'minmax(120px, max-content) 1fr max-content', gap: '3px 10px', alignItems: 'center', fontSize: 11 }}>
+                                    {tanking.map((enemy, i) => (
+                                        <Fragment key={`t${i}`}>
+                                            <span style={{ color: 'var(--text-secondary)' }}>{enemy.enemy_name}</span>
+                                            <div style={{ position: 'relative', height: 10, background: 'rgba(255,255,255,0.04)', borderRadius: 3 }}>
+                                                {enemy.spans.map((s, j) => (
+                                                    <div
+                                                        key={j}
+                                                        title={`${s.player_name} ${at(s.start_offset_secs)} – ${at(s.end_offset_secs)} (${s.hits} hits)`}
+                                                        style={{
+                                                            position: 'absolute',
+                                                            left: `${s.start_offset_secs / pullSecs * 100}%`,
+                                                            width: `${Math.max((s.end_offset_secs - s.start_offset_secs) / pullSecs * 100, 0.5)}%`,
+                                                            top: 0,
+                                                            bottom: 0,
+                                                            borderRadius: 3,
+                                                            background: s.on_tank ? 'var(--accent-blue)' : 'var(--accent-orange)',
+                                                            opacity: 0.8,
+                                                        }}
+                                                    />
+                                                ))}
+                                            </div>
+                                            <span style={{ color: enemy.loose_secs > 0 ? 'var(--accent-orange)' : 'var(--text-muted)' }}>
+                                                {enemy.loose_secs > 0 ? `${enemy.loose_secs.toFixed(1)}s loose` : 'tanked'}
+                                                {enemy.target_swaps > 0 && ` · ${enemy.target_swaps} ${enemy.target_swaps === 1 ? 'swap' : 'swaps'}`}
+                                            </span>
+                                        </Fragment>
+                                    ))}
+                                </div>
+                            )}
+                            </>
+                        )
+                    })()}
                     <div style={{ display: 'flex', justifyContent: 'flex-end', marginTop: 4 }}>
                         <span style={{ fontSize: 10, color: 'var(--text-muted)', opacity: 0.7 }}>Ctrl+click to multi-select</span>
                     </div>
@@ -707,6 +820,7 @@ function renderTab(
         case 'healing': return renderHealingTab(enc, getTooltip)
         case 'taken': return renderDamageTakenTab(enc, getTooltip)
         case 'deaths': return renderDeathsTab(enc, getTooltip)
+        case 'efficiency': return renderEfficiencyTab(enc)
         case 'abilities': return renderAbilitiesTab(enc, getTooltip)
         case 'enemies': return renderEnemiesTab(enc, getTooltip)
         case 'buffs': return renderBuffUptimeTab(enc, getTooltip)
@@ -717,7 +831,9 @@ function renderTab(
 }
 
 function renderDamageTab(enc: EncounterSummary, getTooltip: (id: number, name?: string) => any): string {
-    const maxDmg = Math.max(...enc.players.map(p => p.damage_done), 1)
+    // Room for the reference lines too, so a group below typical shows it
+    const referenceDamage = (p: PlayerSummary) => (p.reference_dps ?? 0) * enc.duration_secs
+    const maxDmg = Math.max(...enc.players.map(p => Math.max(p.damage_done, referenceDamage(p))), 1)
     if (enc.players.length === 0) return '<div class="empty-state"><div class="title">No damage data</div></div>'
 
     // Boss HP timeline + slider
@@ -761,8 +877,15 @@ function renderDamageTab(enc: EncounterSummary, getTooltip: (id: number, name?:
     </div>`
     }
 
+    const practiceHtml = enc.practice ? renderPractice(enc.practice, dur, getTooltip) : ''
+    const addsHtml = enc.add_timeline ? renderAddTimeline(enc.add_timeline, dur) : ''
+    const arenaHtml = enc.arena ? renderArenaRounds(enc.arena) : ''
+    const depletionHtml = enc.depletion_report ? renderDepletionReport(enc.depletion_report) : ''
+    const insightsHtml = renderInsights(enc.insights ?? [])
+    const cooldownsHtml = renderCooldownUsage(enc, getTooltip)
+
     const sorted = [...enc.players].sort((a, b) => b.damage_done - a.damage_done)
-    return sliderHtml + `<table class="data-table">
+    return sliderHtml + addsHtml + practiceHtml + arenaHtml + insightsHtml + depletionHtml + `<table class="data-table">
     <thead><tr><th>#</th><th>Player</th><th>Damage Done</th><th></th><th class="num">DPS</th></tr></thead>
     <tbody>${sorted.map((p, i) => {
         const pid = `dmg-${p.guid.replace(/[^a-zA-Z0-9]/g, '')}`
@@ -771,28 +894,33 @@ function renderDamageTab(enc: EncounterSummary, getTooltip: (id: number, name?:
         <td class="rank ${i < 3 ? 'rank-' + (i + 1) : ''}">${i + 1}</td>
         <td><span title="${ri.label}" style="font-size:12px;margin-right:4px">${ri.icon}</span><strong style="color:${classColor(p.class_name)}">${p.name}</strong>${p.spec_name ? `<span style="color:${classColor(p.class_name)};opacity:0.6;font-size:11px;margin-left:6px">${p.spec_name} ${p.class_name}</span>` : ''}</td>
         <td class="num">${formatNumber(p.damage_done)}</td>
-        <td class="bar-cell"><div class="bar-container"><div class="bar-fill" style="width:${(p.damage_done / maxDmg * 100).toFixed(1)}%;background:linear-gradient(90deg, var(--accent-purple), var(--accent-blue));opacity:0.8"></div><div class="bar-label">${formatNumber(p.damage_done)}</div></div></td>
-        <td class="num" style="color:var(--accent-orange);font-weight:600">${formatNumber(Math.round(p.dps))}</td>
+        <td class="bar-cell"><div class="bar-container"><div class="bar-fill" style="width:${(p.damage_done / maxDmg * 100).toFixed(1)}%;background:linear-gradient(90deg, var(--accent-purple), var(--accent-blue));opacity:0.8"></div><div class="bar-label">${formatNumber(p.damage_done)}</div>${renderReferenceLine(p, enc, referenceDamage(p) / maxDmg * 100)}</div></td>
+        <td class="num" style="color:var(--accent-orange);font-weight:600">${formatNumber(Math.round(p.dps))}${renderPerformanceDelta(p, 'dps')}</td>
       </tr>
       <tr id="${pid}" class="detail-row" style="display:none">
         <td colspan="5" style="padding:0">
           <div style="display:flex;gap:8px;padding:8px 12px 0 12px">
             <button data-detail-pid="${pid}" data-detail-tab="abilities" style="padding:4px 12px;font-size:11px;font-weight:600;border:1px solid var(--accent-purple);background:rgba(139,92,246,0.13);color:var(--accent-purple);border-radius:6px;cursor:pointer">Abilities</button>
             <button data-detail-pid="${pid}" data-detail-tab="targets" style="padding:4px 12px;font-size:11px;font-weight:600;border:1px solid var(--border-color);background:transparent;color:var(--text-muted);border-radius:6px;cursor:pointer">Targets</button>
+            ${hasBuckets && isRaid ? `<button data-detail-pid="${pid}" data-detail-tab="timeline" style="padding:4px 12px;font-size:11px;font-weight:600;border:1px solid var(--border-color);background:transparent;color:var(--text-muted);border-radius:6px;cursor:pointer">Timeline</button>` : ''}
+            ${p.schools ? `<button data-detail-pid="${pid}" data-detail-tab="schools" style="padding:4px 12px;font-size:11px;font-weight:600;border:1px solid var(--border-color);background:transparent;color:var(--text-muted);border-radius:6px;cursor:pointer">Schools</button>` : ''}
           </div>
           <div id="${pid}-abilities" class="ability-panel">${renderAbilityBreakdown(p.abilities || [], p.damage_done, getTooltip)}</div>
           <div id="${pid}-targets" class="ability-panel" style="display:none">${renderTargetBreakdown(p.abilities || [], p.damage_done, getTooltip)}</div>
+          ${hasBuckets && isRaid ? `<div id="${pid}-timeline" class="ability-panel" style="display:none" data-ability-timeline="${p.guid}"></div>` : ''}
+          ${p.schools ? `<div id="${pid}-schools" class="ability-panel" style="display:none">${renderSchoolSplit(p.schools.damage_done, 'Damage done by school')}</div>` : ''}
         </td>
       </tr>`
-    }).join('')}</tbody></table>`
+    }).join('')}</tbody></table>${cooldownsHtml}`
 }
 
 function renderHealingTab(enc: EncounterSummary, getTooltip: (id: number, name?: string) => any): string {
     const sorted = [...enc.players].filter(p => p.healing_done > 0).sort((a, b) => b.healing_done - a.healing_done)
     if (sorted.length === 0) return '<div class="empty-state"><div class="title">No healing data</div></div>'
     const maxHeal = Math.max(...sorted.map(p => p.healing_done), 1)
+    const anySaves = sorted.some(p => p.deaths_prevented > 0)
     return `<table class="data-table">
-    <thead><tr><th>#</th><th>Player</th><th>Healing Done</th><th></th><th class="num">HPS</th></tr></thead>
+    <thead><tr><th>#</th><th>Player</th><th>Healing Done</th><th></th><th class="num">HPS</th>${anySaves ? '<th class="num" title="Estimated deaths prevented: heals on a player below 15% HP who then took at least that much damage within 2 seconds and lived">Saves</th>' : ''}</tr></thead>
     <tbody>${sorted.map((p, i) => {
         const pid = `heal-${p.guid.replace(/[^a-zA-Z0-9]/g, '')}`
         const abilities = (p.heal_abilities?.length > 0) ? p.heal_abilities : (p.abilities || [])
@@ -801,21 +929,90 @@ function renderHealingTab(enc: EncounterSummary, getTooltip: (id: number, name?:
         <td><strong style="color:${classColor(p.class_name)}">${p.name}</strong>${p.spec_name ? `<span style="color:${classColor(p.class_name)};opacity:0.6;font-size:11px;margin-left:6px">${p.spec_name} ${p.class_name}</span>` : ''}</td>
         <td class="num">${formatNumber(p.healing_done)}</td>
         <td class="bar-cell"><div class="bar-container"><div class="bar-fill" style="width:${(p.healing_done / maxHeal * 100).toFixed(1)}%;background:linear-gradient(90deg, var(--accent-green), var(--accent-cyan));opacity:0.8"></div><div class="bar-label">${formatNumber(p.healing_done)}</div></div></td>
-        <td class="num" style="color:var(--accent-green);font-weight:600">${formatNumber(Math.round(p.hps))}</td>
+        <td class="num" style="color:var(--accent-green);font-weight:600">${formatNumber(Math.round(p.hps))}${renderPerformanceDelta(p, 'hps')}</td>
+        ${anySaves ? `<td class="num">${p.deaths_prevented || '—'}</td>` : ''}
       </tr>
       <tr id="${pid}" class="detail-row" style="display:none">
-        <td colspan="5" style="padding:0"><div class="ability-panel">${renderAbilityBreakdown(abilities, p.healing_done, getTooltip)}</div></td>
+        <td colspan="${anySaves ? 6 : 5}" style="padding:0"><div class="ability-panel">${renderAbilityBreakdown(abilities, p.healing_done, getTooltip)}</div></td>
       </tr>`
-    }).join('')}</tbody></table>`
+    }).join('')}</tbody></table>${renderHealingReport(enc)}`
+}
+
+/** Healer × target matrix, to check who actually healed whom (raid bosses) */
+/** "+12% vs your average" next to a player's DPS or HPS, from their earlier pulls in the history */
+function renderPerformanceDelta(player: PlayerSummary, metric: 'dps' | 'hps'): string {
+    const delta = player.performance_delta
+    if (!delta) return ''
+    const pct = metric === 'dps' ? delta.dps_delta_pct : delta.hps_delta_pct
+    const average = metric === 'dps' ? delta.average_dps : delta.average_hps
+    if (average <= 0) return ''
+    const color = pct >= 0 ? 'var(--accent-green)' : 'var(--accent-red)'
+    const title = `Average ${formatNumber(Math.round(average))} ${metric.toUpperCase()} over ${delta.samples} earlier ${delta.samples === 1 ? 'pull' : 'pulls'}`
+    return `<div title="${title}" style="font-size:10px;font-weight:500;color:${color}">${pct >= 0 ? '+' : ''}${pct.toFixed(0)}% vs your average</div>`
+}
+
+/** Where the spec's typical DPS at this key level or raid difficulty would put the bar */
+function renderReferenceLine(player: PlayerSummary, enc: EncounterSummary, pct: number): string {
+    if (!player.reference_dps) return ''
+    const context = enc.encounter_type === 'mythic_plus' ? `+${enc.key_level}` : enc.difficulty_name.replace(' (Raid)', '')
+    const title = `Typical ${player.spec_name} ${player.class_name} at ${context}: ${formatNumber(Math.round(player.reference_dps))} DPS`
+    return `<div title="${title}" style="position:absolute;top:0;bottom:0;left:${Math.min(pct, 100).toFixed(1)}%;width:2px;background:var(--text-secondary);opacity:0.7"></div>`
+}
+
+function renderHealingReport(enc: EncounterSummary): string {
+    const report = enc.healing_report
+    if (!report?.healers.length || !report.targets.length) return ''
+    const byGuid = new Map(enc.players.map(p => [p.guid, p]))
+    const nameCell = (guid: string, name: string) => {
+        const p = byGuid.get(guid)
+        return p ? `<strong style="color:${classColor(p.class_name)}">${name}</strong>` : name
+    }
+    return `<div style="margin-top:16px">
+      <div style="font-size:12px;font-weight:600;color:var(--text-secondary);margin-bottom:4px">Healing assignments — effective healing by healer on each player</div>
+      <table class="data-table">
+        <thead><tr><th>Healer</th>${report.targets.map(t => `<th class="num" title="${t.role}">${nameCell(t.guid, t.name)}</th>`).join('')}<th class="num">Total</th></tr></thead>
+        <tbody>${report.healers.map((h, i) => `<tr>
+          <td>${nameCell(h.guid, h.name)}</td>
+          ${report.amounts[i].map((amount, j) => {
+              const share = report.targets[j].total > 0 ? amount / report.targets[j].total * 100 : 0
+              return `<td class="num" title="${share.toFixed(0)}% of ${report.targets[j].name}'s healing">${amount > 0 ? formatNumber(amount) : '—'}</td>`
+          }).join('')}
+          <td class="num">${formatNumber(h.total)}</td>
+        </tr>`).join('')}</tbody>
+      </table>
+    </div>`
+}
+
+/** Each player's 0-100 fight efficiency, with the points of every part and the formula */
+function renderEfficiencyTab(enc: EncounterSummary): string {
+    const sorted = enc.players.filter(p => p.efficiency).sort((a, b) => b.efficiency!.score - a.efficiency!.score)
+    if (sorted.length === 0) return '<div class="empty-state"><div class="title">No efficiency data</div></div>'
+    const scoreColor = (score: number) => score >= 85 ? 'var(--accent-green)' : score >= 60 ? 'var(--accent-orange)' : 'var(--accent-red)'
+    return `<table class="data-table">
+    <thead><tr><th>#</th><th>Player</th><th class="num">Score</th><th class="num" title="Damage share (healing share for healers) against an even split of the group">Output /40</th><th class="num" title="Damage taken that wasn't from avoidable abilities">Avoidable /25</th><th class="num" title="10 off per death">Deaths /20</th><th class="num" title="Interrupts and dispels against the group's most">Utility /15</th></tr></thead>
+    <tbody>${sorted.map((p, i) => {
+        const e = p.efficiency!
+        return `<tr class="animate-in" style="animation-delay:${i * 20}ms">
+        <td class="rank ${i < 3 ? 'rank-' + (i + 1) : ''}">${i + 1}</td>
+        <td><strong style="color:${classColor(p.class_name)}">${p.name}</strong>${p.spec_name ? `<span style="color:${classColor(p.class_name)};opacity:0.6;font-size:11px;margin-left:6px">${p.spec_name} ${p.class_name}</span>` : ''}</td>
+        <td class="num" style="color:${scoreColor(e.score)};font-weight:700">${e.score.toFixed(1)}</td>
+        <td class="num" title="${e.output_share.toFixed(1)}% of the ${p.role === 'healer' ? 'healing' : 'damage'} against an even ${e.even_share.toFixed(1)}%">${e.output_points.toFixed(1)}</td>
+        <td class="num" title="${formatNumber(e.avoidable_damage)} of ${formatNumber(p.damage_taken)} taken was avoidable">${e.avoidable_points.toFixed(1)}</td>
+        <td class="num" title="${p.deaths} death(s)">${e.death_points.toFixed(1)}</td>
+        <td class="num" title="${p.interrupts} interrupt(s), ${p.dispels} dispel(s)">${e.utility_points.toFixed(1)}</td>
+      </tr>`
+    }).join('')}</tbody></table>
+    ${enc.efficiency_formula ? `<div style="margin-top:12px;font-size:11px;color:var(--text-muted)">${enc.efficiency_formula}</div>` : ''}`
 }
 
 function renderDamageTakenTab(enc: EncounterSummary, getTooltip: (id: number, name?: string) => any): string {
-    const sorted = [...enc.players].filter(p => (p.damage_taken || 0) > 0).sort((a, b) => (b.damage_taken || 0) - (a.damage_taken || 0))
+    const sorted = [...enc.players].filter(p => (p.damage_taken || 0) > 0 || p.crowd_control?.events.length).sort((a, b) => (b.damage_taken || 0) - (a.damage_taken || 0))
     if (sorted.length === 0) return '<div class="empty-state"><div class="title">No damage taken data</div></div>'
     const maxTaken = Math.max(...sorted.map(p => p.damage_taken || 0), 1)
     const dur = enc.duration_secs || 1
+    const anyCc = sorted.some(p => p.crowd_control?.events.length)
     return `<table class="data-table">
-    <thead><tr><th>#</th><th>Player</th><th>Damage Taken</th><th></th><th>DTPS</th></tr></thead>
+    <thead><tr><th>#</th><th>Player</th><th>Damage Taken</th><th></th><th>DTPS</th>${anyCc ? '<th class="num" title="Time spent stunned, incapacitated, disoriented, silenced or rooted">CC\'d</th>' : ''}</tr></thead>
     <tbody>${sorted.map((p, i) => {
         const pid = `taken-${p.guid.replace(/[^a-zA-Z0-9]/g, '')}`
         const dtps = Math.round((p.damage_taken || 0) / dur)
@@ -825,11 +1022,93 @@ function renderDamageTakenTab(enc: EncounterSummary, getTooltip: (id: number, na
         <td class="num">${formatNumber(p.damage_taken || 0)}</td>
         <td class="bar-cell"><div class="bar-container"><div class="bar-fill" style="width:${((p.damage_taken || 0) / maxTaken * 100).toFixed(1)}%;background:linear-gradient(90deg, var(--accent-red), var(--accent-orange));opacity:0.8"></div></div></td>
         <td class="num dps">${formatNumber(dtps)}</td>
+        ${anyCc ? `<td class="num">${p.crowd_control?.events.length ? `${p.crowd_control.pct.toFixed(1)}%` : '—'}</td>` : ''}
       </tr>
       <tr id="${pid}" class="detail-row" style="display:none">
-        <td colspan="5" style="padding:0"><div class="ability-panel">${renderAbilityBreakdown(p.damage_taken_abilities || [], p.damage_taken || 0, getTooltip)}</div></td>
+        <td colspan="${anyCc ? 6 : 5}" style="padding:0"><div class="ability-panel">${renderAbilityBreakdown(p.damage_taken_abilities || [], p.damage_taken || 0, getTooltip)}${p.schools ? renderSchoolSplit(p.schools.damage_taken, 'Damage taken by school') : ''}${renderDefensiveCoverage(enc, p, getTooltip)}${renderCrowdControl(p, getTooltip)}</div></td>
       </tr>`
-    }).join('')}</tbody></table>`
+    }).join('')}</tbody></table>${renderMechanics(enc, getTooltip)}`
+}
+
+/** Physical/magic split and per-school totals, as a stacked bar and a table */
+function renderSchoolSplit(split: SchoolSplit, title: string): string {
+    const total = split.schools.reduce((sum, s) => sum + s.amount, 0)
+    if (total === 0) return ''
+    const physicalPct = split.physical / total * 100
+    const magicPct = split.magic / total * 100
+    return `<div style="padding:8px 12px">
+      <div style="font-size:12px;font-weight:600;color:var(--text-secondary);margin-bottom:4px">${title} — ${physicalPct.toFixed(0)}% physical, ${magicPct.toFixed(0)}% magic</div>
+      <div style="display:flex;height:10px;border-radius:5px;overflow:hidden;margin-bottom:6px">${split.schools.map(s =>
+        `<div title="${s.name}: ${s.pct.toFixed(1)}%" style="width:${s.pct.toFixed(2)}%;background:${getSchoolColor(s.school)}"></div>`).join('')}</div>
+      <table class="data-table">
+        <thead><tr><th>School</th><th class="num">Amount</th><th class="num">%</th></tr></thead>
+        <tbody>${split.schools.map(s => `<tr>
+          <td style="color:${getSchoolColor(s.school)}">${s.name}</td>
+          <td class="num">${formatNumber(s.amount)}</td>
+          <td class="num">${s.pct.toFixed(1)}%</td>
+        </tr>`).join('')}</tbody>
+      </table>
+    </div>`
+}
+
+/** Damage the whole group took from each enemy ability, to see which mechanic costs the most */
+function renderMechanics(enc: EncounterSummary, getTooltip: (id: number, name?: string) => any): string {
+    const mechanics = enc.mechanics || []
+    if (!mechanics.length) return ''
+    const maxDamage = Math.max(...mechanics.map(m => m.total_damage), 1)
+    return `<div style="margin-top:16px">
+      <div style="font-size:12px;font-weight:600;color:var(--text-secondary);margin-bottom:4px">Mechanics — damage taken by enemy ability across the group</div>
+      <table class="data-table">
+        <thead><tr><th>Ability</th><th>From</th><th>Damage</th><th></th><th class="num">Hits</th><th class="num">Avg hit</th><th class="num" title="Killing blows">Deaths</th></tr></thead>
+        <tbody>${mechanics.map(m => `<tr>
+          <td>${spellHtml(m.spell_id, m.spell_name, m.wowhead_url, getTooltip, { iconSize: 16 })}</td>
+          <td style="font-size:11px;color:var(--text-muted)">${m.enemies.join(', ')}</td>
+          <td class="num">${formatNumber(m.total_damage)}</td>
+          <td class="bar-cell"><div class="bar-container"><div class="bar-fill" style="width:${(m.total_damage / maxDamage * 100).toFixed(1)}%;background:linear-gradient(90deg, var(--accent-red), var(--accent-orange));opacity:0.8"></div></div></td>
+          <td class="num">${m.hits}</td>
+          <td class="num">${formatNumber(m.average_hit)}</td>
+          <td class="num"${m.deaths > 0 ? ' style="color:var(--accent-red);font-weight:600"' : ''}>${m.deaths || '—'}</td>
+        </tr>`).join('')}</tbody>
+      </table>
+    </div>`
+}
+
+/** Dangerous hits a player took, and how many of them with no defensive up */
+function renderDefensiveCoverage(enc: EncounterSummary, player: PlayerSummary, getTooltip: (id: number, name?: string) => any): string {
+    const rows = (enc.defensive_coverage || []).filter(c => c.player_guid === player.guid)
+    if (!rows.length) return ''
+    return `<div style="padding:8px 12px">
+      <div style="font-size:12px;font-weight:600;color:var(--text-secondary);margin-bottom:4px">Dangerous hits — ${rows.reduce((n, c) => n + c.undefended, 0)} taken with no defensive</div>
+      <table class="data-table">
+        <thead><tr><th>Ability</th><th class="num">Hits</th><th class="num">No defensive</th><th class="num">Damage</th></tr></thead>
+        <tbody>${rows.map(c => `<tr>
+          <td>${spellHtml(c.spell_id, c.spell_name, `https://www.wowhead.com/spell=${c.spell_id}`, getTooltip, { iconSize: 16 })}</td>
+          <td class="num">${c.hits}</td>
+          <td class="num"${c.undefended > 0 ? ' style="color:var(--accent-red)"' : ''}>${c.undefended}</td>
+          <td class="num">${formatNumber(c.undefended_damage)}</td>
+        </tr>`).join('')}</tbody>
+      </table>
+    </div>`
+}
+
+/** Crowd control a player received: what, from whom, when and for how long */
+function renderCrowdControl(player: PlayerSummary, getTooltip: (id: number, name?: string) => any): string {
+    const cc = player.crowd_control
+    if (!cc?.events.length) return ''
+    const fmtTime = (s: number) => { const m = Math.floor(s / 60); const sec = Math.floor(s % 60); return m + ':' + (sec < 10 ? '0' : '') + sec }
+    return `<div style="padding:8px 12px">
+      <div style="font-size:12px;font-weight:600;color:var(--text-secondary);margin-bottom:4px">Crowd control received — ${cc.total_secs.toFixed(1)}s (${cc.pct.toFixed(1)}% of the fight)</div>
+      <table class="data-table">
+        <thead><tr><th>Time</th><th>Spell</th><th>Type</th><th>From</th><th class="num">Duration</th></tr></thead>
+        <tbody>${cc.events.map(e => `<tr>
+          <td>${fmtTime(e.time_ms / 1000)}</td>
+          <td>${spellHtml(e.spell_id, e.spell_name, `https://www.wowhead.com/spell=${e.spell_id}`, getTooltip, { iconSize: 16 })}</td>
+          <td style="text-transform:capitalize">${e.kind}</td>
+          <td>${e.source_name}</td>
+          <td class="num">${e.duration_secs.toFixed(1)}s</td>
+        </tr>`).join('')}</tbody>
+      </table>
+    </div>`
 }
 
 function renderDeathsTab(enc: EncounterSummary, getTooltip: (id: number, name?: string) => any): string {
@@ -881,11 +1160,12 @@ function renderDeathsTab(enc: EncounterSummary, getTooltip: (id: number, name?:
             <span style="flex:1"></span>
             ${kbSource ? `<span style="color:var(--text-secondary);font-size:12px">${kbSource}</span>` : ''}
             ${overkill > 0 ? `<span style="background:rgba(239,68,68,0.15);color:var(--accent-red);padding:2px 8px;border-radius:4px;font-size:11px;font-weight:600">Overkill: ${formatNumber(overkill)}</span>` : ''}
+            ${d.one_shot ? `<span style="background:var(--accent-red);color:white;padding:2px 8px;border-radius:4px;font-size:11px;font-weight:700">ONE-SHOT</span>` : ''}
           </div>`
         return `<div class="death-item animate-in" style="animation-delay:${i * 30}ms">
       <div class="death-header" data-toggle-recap="${i}">
         <span class="expand-icon">${hasRecap ? '▶' : ''}</span>
-        <span class="death-time">${fmtTime(d.time_into_fight_secs)}</span>
+        <span class="death-time">${fmtTime(d.time_ms / 1000)}</span>
         <span class="death-player">${d.player_name}</span>
         <span class="death-source">Killed by ${kbSource}${kbSpell !== 'Unknown' ? ` with ${kbSpell}` : ''}</span>
       </div>
@@ -910,7 +1190,7 @@ function renderDeathsTab(enc: EncounterSummary, getTooltip: (id: number, name?:
             const amtColor = evType === 'healing' ? 'var(--accent-green)' : evType === 'damage' || evType === 'death' ? 'var(--accent-red)' : 'var(--text-muted)'
             const amtStr = r.amount > 0 ? `${amtPrefix}${formatNumber(r.amount)}` : '—'
             // Active debuffs at this moment
-            const activeDebuffs = getActiveDebuffsAt(d.player_guid, r.time_into_fight_secs)
+            const activeDebuffs = getActiveDebuffsAt(d.player_guid, r.time_ms / 1000)
             const debuffIconsHtml = activeDebuffs.length > 0
                 ? `<div style="display:flex;gap:2px;flex-wrap:wrap;align-items:center">${activeDebuffs.map(db => {
                     const tip = getTooltip(db.spell_id, db.spell_name)
@@ -919,7 +1199,7 @@ function renderDeathsTab(enc: EncounterSummary, getTooltip: (id: number, name?:
                 }).join('')}</div>`
                 : ''
             return `<tr style="border-bottom:1px solid rgba(255,255,255,0.03)">
-              <td style="padding:5px 8px;color:var(--text-muted);width:40px;white-space:nowrap">${fmtTime(r.time_into_fight_secs)}</td>
+              <td style="padding:5px 8px;color:var(--text-muted);width:40px;white-space:nowrap">${fmtTime(r.time_ms / 1000)}</td>
               <td style="padding:5px 6px;width:50px"><span style="background:${badgeBg};color:${badgeColor};padding:1px 6px;border-radius:3px;font-size:10px;font-weight:700;letter-spacing:0.3px">${badgeLabel}</span></td>
               <td style="padding:5px 6px;width:60px;text-align:right;font-weight:600;color:${amtColor};font-size:12px">${amtStr}</td>
               <td style="padding:5px 8px">${spellHtml(r.spell_id, r.spell_name, r.wowhead_url, getTooltip, { color: 'var(--accent-blue)', iconSize: 16 })}</td>
@@ -1205,9 +1485,9 @@ function renderReplayTab(enc: EncounterSummary): string {
 
     // Death markers
     const deathMarkers = (enc.deaths || []).map(d => {
-        const pct = (d.time_into_fight_secs / dur * 100).toFixed(2)
-        return `<span class="replay-death-marker" style="left:${pct}%" title="💀 ${d.player_name} died at ${fmtTime(d.time_into_fight_secs)}${d.killing_blow_spell ? ' — ' + d.killing_blow_spell : ''}">💀</span>`
-    }).join('')
+        const pct = (d.time_ms / 1000 / dur * 100).toFixed(2)
+        return `<span class="replay-death-marker" style="left:${pct}%" title="💀 ${d.player_name} died at ${fmtTime(d.time_ms / 1000)}${d.killing_blow_spell ? ' — ' + d.killing_blow_spell : ''}">💀</span>`
+    }).join('') + (enc.bookmarks || []).map(renderBookmarkMarker(dur)).join('')
 
     // Boss HP section
     const hasBossHp = enc.boss_hp_timeline && enc.boss_hp_timeline.length > 0
@@ -1232,9 +1512,15 @@ function renderReplayTab(enc: EncounterSummary): string {
         </div>`
     }).join('')
 
-    // Map section
+    // Map section, with the server's camera presets to reframe it
+    const presets = enc.replay_camera?.presets || []
+    const presetButtons = presets.length > 1 ? `<div style="display:flex;flex-wrap:wrap;gap:4px;margin-bottom:8px" id="replay-camera">${presets.map((p, i) => {
+        const when = p.from_secs != null && p.to_secs != null ? ` (${fmtTime(p.from_secs)} – ${fmtTime(p.to_secs)})` : ''
+        return `<button class="replay-speed" data-camera-preset="${i}" title="${p.name}${when}, ${p.zoom.toFixed(1)}× zoom">${p.name}</button>`
+    }).join('')}</div>` : ''
     const mapSection = hasPositions ? `<div class="replay-map-wrap">
-      <div class="replay-map-label">Position Map</div>
+      <div class="replay-map-label">Position Map<span id="replay-map-name"></span></div>
+      ${presetButtons}
       <canvas class="replay-map-canvas" id="replay-map"></canvas>
     </div>` : ''
 
@@ -1244,9 +1530,11 @@ function renderReplayTab(enc: EncounterSummary): string {
       <input type="range" class="replay-slider" id="replay-slider" min="0" max="${(dur * 10).toFixed(0)}" value="0" step="1">
       <div class="replay-time" id="replay-time">0:00 / ${fmtTime(dur)}</div>
       <div class="replay-speed" id="replay-speed-btn" title="Click to change speed">1×</div>
+      <button id="replay-bookmark-btn" title="Bookmark this moment">🔖</button>
     </div>
     <div class="replay-death-markers" id="replay-markers">${deathMarkers}</div>
     ${bossSection}
+    ${enc.enemy_casts?.length ? '<div class="replay-casts" id="replay-casts" style="min-height:22px;display:flex;flex-wrap:wrap;gap:6px;margin-bottom:8px"></div>' : ''}
     <div class="replay-map-section">
       <div class="replay-raid-frames">
         <div class="replay-raid-label">Party / Raid Frames</div>
@@ -1257,26 +1545,236 @@ function renderReplayTab(enc: EncounterSummary): string {
   </div>`
 }
 
+/** A clickable bookmark on the replay marker bar */
+const renderBookmarkMarker = (dur: number) => (b: Bookmark): string => {
+    const time = `${Math.floor(b.offset_secs / 60)}:${String(Math.floor(b.offset_secs % 60)).padStart(2, '0')}`
+    const name = b.name.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/"/g, '&quot;')
+    return `<span class="replay-death-marker" data-bookmark-secs="${b.offset_secs}" style="left:${(b.offset_secs / dur * 100).toFixed(2)}%;cursor:pointer" title="🔖 ${name} (${time})">🔖</span>`
+}
+
 // ========== Interactive handlers ==========
 
 function showDetailTab(pid: string, tab: string) {
-    const abilities = document.getElementById(`${pid}-abilities`)
-    const targets = document.getElementById(`${pid}-targets`)
-    if (abilities) abilities.style.display = tab === 'abilities' ? '' : 'none'
-    if (targets) targets.style.display = tab === 'targets' ? '' : 'none'
-    // Update button styles
-    const abBtn = document.querySelector(`[data-detail-pid="${pid}"][data-detail-tab="abilities"]`) as HTMLElement
-    const tgBtn = document.querySelector(`[data-detail-pid="${pid}"][data-detail-tab="targets"]`) as HTMLElement
-    if (abBtn) {
-        abBtn.style.borderColor = tab === 'abilities' ? 'var(--accent-purple)' : 'var(--border-color)'
-        abBtn.style.background = tab === 'abilities' ? 'rgba(139,92,246,0.13)' : 'transparent'
-        abBtn.style.color = tab === 'abilities' ? 'var(--accent-purple)' : 'var(--text-muted)'
+    for (const t of ['abilities', 'targets', 'timeline', 'schools']) {
+        const panel = document.getElementById(`${pid}-${t}`)
+        if (panel) panel.style.display = tab === t ? '' : 'none'
+        // Update button styles
+        const btn = document.querySelector(`[data-detail-pid="${pid}"][data-detail-tab="${t}"]`) as HTMLElement
+        if (btn) {
+            btn.style.borderColor = tab === t ? 'var(--accent-purple)' : 'var(--border-color)'
+            btn.style.background = tab === t ? 'rgba(139,92,246,0.13)' : 'transparent'
+            btn.style.color = tab === t ? 'var(--accent-purple)' : 'var(--text-muted)'
+        }
+    }
+}
+
+const TIMELINE_COLORS = ['var(--accent-purple)', 'var(--accent-orange)', 'var(--accent-cyan)', 'var(--accent-green)', 'var(--accent-red)', 'var(--accent-blue)']
+
+/** A player's top abilities as damage per 5 seconds, one line each, to show burst windows */
+function renderAbilityTimeline(timeline: AbilityTimeline): string {
+    const top = timeline.abilities.slice(0, TIMELINE_COLORS.length)
+    if (top.length === 0) return '<div style="padding:12px;color:var(--text-muted);font-size:12px">No damage over time recorded</div>'
+    const step = 5
+    const slots = Math.max(Math.ceil(timeline.duration_secs / step), 1)
+    const series = top.map(a => {
+        const sums = new Array(slots).fill(0)
+        for (const [sec, amount] of a.buckets) sums[Math.min(Math.floor(sec / step), slots - 1)] += amount
+        return sums
+    })
+    const maxVal = Math.max(...series.flat(), 1)
+    const w = 600, h = 120
+    const x = (i: number) => (slots > 1 ? i / (slots - 1) * w : 0).toFixed(1)
+    const lines = series.map((sums, i) =>
+        `<polyline points="${sums.map((v, j) => `${x(j)},${(h - v / maxVal * h).toFixed(1)}`).join(' ')}" fill="none" stroke="${TIMELINE_COLORS[i]}" stroke-width="1.5" vector-effect="non-scaling-stroke"/>`
+    ).join('')
+    const fmtTime = (s: number) => { const m = Math.floor(s / 60); const sec = Math.floor(s % 60); return m + ':' + (sec < 10 ? '0' : '') + sec }
+    return `<div style="padding:12px">
+      <svg viewBox="0 0 ${w} ${h}" preserveAspectRatio="none" style="width:100%;height:${h}px;display:block;background:var(--bg-secondary);border-radius:6px">${lines}</svg>
+      <div style="display:flex;justify-content:space-between;font-size:10px;color:var(--text-muted);margin-top:2px"><span>0:00</span><span>damage per ${step}s</span><span>${fmtTime(timeline.duration_secs)}</span></div>
+      <div style="display:flex;flex-wrap:wrap;gap:12px;margin-top:8px;font-size:11px">
+        ${top.map((a, i) => `<span><span style="display:inline-block;width:10px;height:3px;background:${TIMELINE_COLORS[i]};vertical-align:middle;margin-right:4px"></span>${a.spell_name || 'Spell ' + a.spell_id} <span style="color:var(--text-muted)">${formatNumber(a.total)}</span></span>`).join('')}
+      </div>
+    </div>`
+}
+
+/** One lane per kind of add with a bar per spawn, from first seen until it died (solid) or
+ * despawned (faded), to spot adds left up too long and spawns piling up */
+function renderAddTimeline(timeline: AddTimeline, dur: number): string {
+    const adds = timeline.enemies.filter(e => !e.is_boss)
+    if (adds.length === 0) return ''
+    const fmtTime = (s: number) => { const m = Math.floor(s / 60); const sec = Math.floor(s % 60); return m + ':' + (sec < 10 ? '0' : '') + sec }
+    const lanes = new Map<string, typeof adds>()
+    for (const e of adds) {
+        if (!lanes.has(e.name)) lanes.set(e.name, [])
+        lanes.get(e.name)!.push(e)
     }
-    if (tgBtn) {
-        tgBtn.style.borderColor = tab === 'targets' ? 'var(--accent-purple)' : 'var(--border-color)'
-        tgBtn.style.background = tab === 'targets' ? 'rgba(139,92,246,0.13)' : 'transparent'
-        tgBtn.style.color = tab === 'targets' ? 'var(--accent-purple)' : 'var(--text-muted)'
+    const rows = [...lanes.entries()].map(([name, spawns]) => {
+        const avgAlive = spawns.reduce((sum, e) => sum + e.last_seen_secs - e.first_seen_secs, 0) / spawns.length
+        const bars = spawns.map(e => {
+            const left = e.first_seen_secs / dur * 100
+            const width = Math.max((e.last_seen_secs - e.first_seen_secs) / dur * 100, 0.3)
+            return `<div title="${name}: ${fmtTime(e.first_seen_secs)} – ${fmtTime(e.last_seen_secs)}${e.died ? '' : ' (not killed)'}" style="position:absolute;top:3px;bottom:3px;left:${left.toFixed(2)}%;width:${width.toFixed(2)}%;background:var(--accent-orange);border-radius:2px;opacity:${e.died ? 0.8 : 0.35}"></div>`
+        }).join('')
+        return `<div style="display:flex;align-items:center;gap:8px;margin-top:4px">
+          <div style="width:160px;flex-shrink:0;font-size:11px;color:var(--text-secondary);overflow:hidden;text-overflow:ellipsis;white-space:nowrap" title="${name}: ${spawns.length} spawn(s), ${avgAlive.toFixed(0)}s alive on average">${name} <span style="color:var(--text-muted)">×${spawns.length}</span></div>
+          <div style="position:relative;flex:1;height:16px;background:var(--bg-secondary);border-radius:4px">${bars}</div>
+        </div>`
+    }).join('')
+    return `<div style="margin-bottom:20px;padding:16px 20px;background:var(--bg-card);border:1px solid var(--border-color);border-radius:12px">
+      <div style="display:flex;align-items:center;justify-content:space-between;margin-bottom:4px">
+        <span style="font-size:13px;font-weight:600;color:var(--text-secondary)">👾 Adds</span>
+        <span style="font-size:12px;color:var(--text-muted)">Up to ${timeline.peak_alive} alive at once (${fmtTime(timeline.peak_offset_secs)})</span>
+      </div>
+      ${rows}
+      <div style="display:flex;justify-content:space-between;font-size:10px;color:var(--text-muted);margin-top:2px;padding-left:168px"><span>0:00</span><span>${fmtTime(dur)}</span></div>
+    </div>`
+}
+
+/** Target dummy session: cooldown use times and every cast on a rotation strip */
+function renderPractice(practice: PracticeSession, dur: number, getTooltip: (id: number, name?: string) => any): string {
+    const fmtTime = (s: number) => { const m = Math.floor(s / 60); const sec = Math.floor(s % 60); return m + ':' + (sec < 10 ? '0' : '') + sec }
+    return practice.players.map(p => {
+        // One colour per spell, in order of first cast
+        const spellOrder = [...new Set(p.casts.map(c => c.spell_id))]
+        const colorFor = (id: number) => TIMELINE_COLORS[spellOrder.indexOf(id) % TIMELINE_COLORS.length]
+        const strip = p.casts.map(c =>
+            `<div title="${fmtTime(c.offset_secs)} ${c.spell_name}" style="position:absolute;left:${(c.offset_secs / dur * 100).toFixed(2)}%;top:4px;bottom:4px;width:3px;border-radius:1px;background:${colorFor(c.spell_id)}"></div>`
+        ).join('')
+        return `<div style="margin-bottom:20px;padding:16px 20px;background:var(--bg-card);border:1px solid var(--border-color);border-radius:12px">
+      <div style="display:flex;align-items:center;justify-content:space-between;margin-bottom:8px">
+        <span style="font-size:13px;font-weight:600;color:var(--text-secondary)">🎯 ${p.name} — practice</span>
+        <span style="font-size:13px;font-weight:700;color:var(--accent-orange)">${formatNumber(Math.round(p.dps))} DPS · ${p.casts.length} casts</span>
+      </div>
+      <div style="position:relative;height:24px;background:var(--bg-secondary);border-radius:6px;border:1px solid var(--border-color)">${strip}</div>
+      <div style="display:flex;justify-content:space-between;font-size:10px;color:var(--text-muted);margin-top:2px"><span>0:00</span><span>rotation</span><span>${fmtTime(dur)}</span></div>
+      ${renderCooldownTable(p.cooldowns, getTooltip)}
+    </div>`
+    }).join('')
+}
+
+/** Each player's major damage cooldowns in a key or boss pull, with how late they were pressed */
+function renderCooldownUsage(enc: EncounterSummary, getTooltip: (id: number, name?: string) => any): string {
+    const players = enc.cooldown_usage ?? []
+    if (!players.length) return ''
+    const byGuid = new Map(enc.players.map(p => [p.guid, p]))
+    return `<div style="margin-top:16px">
+      <div style="font-size:12px;font-weight:600;color:var(--text-secondary);margin-bottom:4px">Cooldown usage — major damage cooldowns against their base cooldown</div>
+      ${players.map(pc => {
+          const p = byGuid.get(pc.guid)
+          return `<div style="margin-top:8px"><strong style="color:${p ? classColor(p.class_name) : 'inherit'}">${pc.name}</strong>${renderCooldownTable(pc.cooldowns, getTooltip)}</div>`
+      }).join('')}
+    </div>`
+}
+
+/** Uses of each cooldown, plus drift and lost uses for the major damage cooldowns */
+function renderCooldownTable(cooldowns: CooldownUsage[], getTooltip: (id: number, name?: string) => any): string {
+    if (!cooldowns.length) return ''
+    const fmtTime = (s: number) => { const m = Math.floor(s / 60); const sec = Math.floor(s % 60); return m + ':' + (sec < 10 ? '0' : '') + sec }
+    const anyDrift = cooldowns.some(cd => cd.drift)
+    const rows = cooldowns.map(cd => {
+        const drift = cd.drift
+        return `<tr>
+          <td style="padding:4px 8px">${spellHtml(cd.spell_id, cd.spell_name, `https://www.wowhead.com/spell=${cd.spell_id}`, getTooltip, { iconSize: 16 })}</td>
+          <td class="num" style="padding:4px 8px">${cd.uses.length}${drift ? ` / ${drift.potential_uses}` : ''}</td>
+          <td style="padding:4px 8px;color:var(--text-muted);font-size:12px">${cd.uses.map(fmtTime).join(', ')}</td>
+          ${anyDrift ? `<td class="num" style="padding:4px 8px"${drift ? ` title="Base cooldown ${formatDuration(drift.base_cooldown_secs)}"` : ''}>${drift?.average_drift_secs != null ? `${drift.average_drift_secs.toFixed(1)}s` : '—'}</td>
+          <td class="num" style="padding:4px 8px${drift && drift.lost_uses > 0 ? ';color:var(--accent-red);font-weight:600' : ''}">${drift ? drift.lost_uses : '—'}</td>` : ''}
+        </tr>`
+    }).join('')
+    return `<table class="data-table" style="margin-top:12px">
+        <thead><tr><th>Cooldown</th><th class="num">Uses</th><th>Used at</th>${anyDrift ? '<th class="num" title="Average time past the base cooldown between uses">Avg drift</th><th class="num" title="Uses the fight had room for (one at the pull, one every base cooldown after) that were not made">Lost uses</th>' : ''}</tr></thead>
+        <tbody>${rows}</tbody></table>`
+}
+
+/** Solo Shuffle matches have no match winner, only round winners */
+function isSoloShuffle(enc: EncounterSummary): boolean {
+    return enc.arena?.bracket.includes('Shuffle') ?? false
+}
+
+/** Post-mortem of an untimed key: timer lost to deaths and wipes, and the sections that fell behind the best run */
+function renderDepletionReport(report: DepletionReport): string {
+    const facts = [
+        report.over_time_secs != null ? `<span><strong style="color:var(--accent-red)">${formatDuration(report.over_time_secs)}</strong> over time</span>` : '',
+        `<span><strong>${report.deaths}</strong> ${report.deaths === 1 ? 'death' : 'deaths'} (${formatDuration(report.death_penalty_secs)} penalty)</span>`,
+        report.enemy_forces_shortfall ? `<span><strong>${report.enemy_forces_shortfall}</strong> enemy forces short of the best run</span>` : '',
+    ].filter(Boolean).join('')
+    const wipes = report.biggest_wipes.length > 0 ? `<div style="margin-top:8px;font-size:12px;color:var(--text-muted)">Wipes: ${report.biggest_wipes.map(w => `${w.segment_name ?? formatDuration(w.time_ms / 1000)}${w.run_back_secs != null ? ` (${formatDuration(w.run_back_secs)} run back)` : ''}`).join(' · ')}</div>` : ''
+    const best = report.best_run
+    const sections = best ? `<table class="data-table" style="margin-top:12px">
+        <thead><tr><th>Section</th><th class="num">Time</th><th class="num">Best run (+${best.key_level})</th><th class="num">Lost</th><th class="num">Deaths</th></tr></thead>
+        <tbody>${report.sections.map(s => `<tr>
+          <td>${s.name}</td>
+          <td class="num">${formatDuration(s.duration_secs)}</td>
+          <td class="num">${s.best_secs != null ? formatDuration(s.best_secs) : '—'}</td>
+          <td class="num" style="color:${(s.delta_secs ?? 0) > 0 ? 'var(--accent-red)' : 'var(--accent-green)'}">${s.delta_secs != null ? `${s.delta_secs > 0 ? '+' : '-'}${formatDuration(Math.abs(s.delta_secs))}` : '—'}</td>
+          <td class="num">${s.deaths}</td>
+        </tr>`).join('')}</tbody>
+      </table>` : ''
+    return `<div style="margin-bottom:20px;padding:16px 20px;background:var(--bg-card);border:1px solid var(--border-color);border-radius:12px">
+      <div style="font-size:13px;font-weight:600;color:var(--text-secondary);margin-bottom:8px">⏱️ Key post-mortem</div>
+      <div style="display:flex;flex-wrap:wrap;gap:16px;font-size:13px">${facts}</div>
+      ${wipes}${sections}
+    </div>`
+}
+
+const INSIGHT_COLORS: Record<Insight['severity'], string> = {
+    info: 'var(--accent-blue)',
+    warning: 'var(--accent-orange)',
+    critical: 'var(--accent-red)',
+}
+
+/** What the insight rules found, most severe first */
+function renderInsights(insights: Insight[]): string {
+    if (insights.length === 0) return ''
+    const order = ['critical', 'warning', 'info']
+    const sorted = [...insights].sort((a, b) => order.indexOf(a.severity) - order.indexOf(b.severity))
+    return `<div style="margin-bottom:20px;padding:16px 20px;background:var(--bg-card);border:1px solid var(--border-color);border-radius:12px">
+      <div style="font-size:13px;font-weight:600;color:var(--text-secondary);margin-bottom:8px">💡 Insights</div>
+      ${sorted.map(i => `<div style="font-size:13px;padding:3px 0;border-left:3px solid ${INSIGHT_COLORS[i.severity] ?? 'var(--border-color)'};padding-left:8px;margin-bottom:4px">${i.message}</div>`).join('')}
+    </div>`
+}
+
+const TEAM_COLORS = ['var(--accent-green)', 'var(--accent-orange)']
+
+/** Arena rounds: winner, first death and each player's damage, healing and CC */
+function renderArenaRounds(arena: ArenaMatch): string {
+    const shuffle = arena.bracket.includes('Shuffle')
+    // Solo Shuffle standings: rounds won by each player
+    const wins = new Map<string, { name: string; wins: number }>()
+    for (const r of arena.rounds) {
+        for (const p of r.players) {
+            const entry = wins.get(p.guid) ?? { name: p.name, wins: 0 }
+            if (r.winning_team === p.team) entry.wins++
+            wins.set(p.guid, entry)
+        }
     }
+    const standings = shuffle ? `<div style="display:flex;flex-wrap:wrap;gap:12px;margin-bottom:12px;font-size:12px">
+      ${[...wins.values()].sort((a, b) => b.wins - a.wins).map(w => `<span><strong>${w.name}</strong> <span style="color:var(--text-muted)">${w.wins}-${arena.rounds.length - w.wins}</span></span>`).join('')}
+    </div>` : ''
+    const rounds = arena.rounds.map(r => {
+        const result = r.winning_team == null
+            ? '<span style="color:var(--text-muted)">Draw</span>'
+            : `<span style="color:${TEAM_COLORS[r.winning_team] ?? 'inherit'}">Team ${r.winning_team + 1} wins</span>`
+        return `<div style="margin-top:12px">
+      <div style="display:flex;gap:12px;align-items:baseline;font-size:13px;margin-bottom:4px">
+        <strong>Round ${r.number}</strong>${result}
+        <span style="color:var(--text-muted)">${formatDuration(r.duration_secs)}${r.first_death ? ` · 💀 ${r.first_death}` : ''}</span>
+      </div>
+      <table class="data-table">
+        <thead><tr><th>Player</th><th class="num">Damage</th><th class="num">Healing</th><th class="num">CC</th></tr></thead>
+        <tbody>${r.players.map(p => `<tr>
+          <td><span style="display:inline-block;width:8px;height:8px;border-radius:50%;background:${TEAM_COLORS[p.team] ?? 'var(--text-muted)'};margin-right:6px"></span>${p.name}</td>
+          <td class="num">${formatNumber(p.damage_done)}</td>
+          <td class="num">${formatNumber(p.healing_done)}</td>
+          <td class="num">${p.cc_applied}</td>
+        </tr>`).join('')}</tbody>
+      </table>
+    </div>`
+    }).join('')
+    return `<div style="margin-bottom:20px;padding:16px 20px;background:var(--bg-card);border:1px solid var(--border-color);border-radius:12px">
+      <div style="font-size:13px;font-weight:600;color:var(--text-secondary);margin-bottom:8px">🏟️ ${arena.bracket} — ${arena.rounds.length} ${arena.rounds.length === 1 ? 'round' : 'rounds'}</div>
+      ${standings}${rounds}
+    </div>`
 }
 
 // selectBuffPlayer is now handled via the buff-player-select dropdown change handler
@@ -1382,7 +1880,7 @@ function drawUptimeBar(ctx: CanvasRenderingContext2D, x1: number, x2: number, st
 
 // ========== Replay controls ==========
 
-function initReplayControls(enc: EncounterSummary) {
+function initReplayControls(enc: EncounterSummary, filename?: string) {
     // Cancel any previous replay animation
     if ((window as any).__replayCleanup) { (window as any).__replayCleanup(); (window as any).__replayCleanup = null }
 
@@ -1397,6 +1895,12 @@ function initReplayControls(enc: EncounterSummary) {
     const timeline = enc.replay_timeline || []
     const bossHpTimeline = enc.boss_hp_timeline || []
     const bossPos = (enc as any).boss_positions || []
+    const maps = enc.replay_maps || []
+    const avoidableHits = (enc.avoidable_hits || []).filter(h => h.pos_x != null && h.pos_y != null)
+    const enemyCasts = enc.enemy_casts || []
+    const mechanicMarkers = enc.mechanic_markers || []
+    const castsEl = document.getElementById('replay-casts')
+    const mapName = document.getElementById('replay-map-name')
 
     // Build time index for fast lookup
     const timeIndex: { time: number; startIdx: number; endIdx: number }[] = []
@@ -1408,21 +1912,9 @@ function initReplayControls(enc: EncounterSummary) {
         timeIndex.push({ time: t, startIdx: start, endIdx: idx })
     }
 
-    // Compute position bounding box for the map
-    let minX = Infinity, maxX = -Infinity, minY = Infinity, maxY = -Infinity
-    for (const s of timeline) {
-        if (s.pos_x != null && s.pos_y != null) {
-            const gx = s.pos_y, gy = -s.pos_x
-            if (gx < minX) minX = gx; if (gx > maxX) maxX = gx
-            if (gy < minY) minY = gy; if (gy > maxY) maxY = gy
-        }
-    }
-    for (const bp of bossPos) {
-        const bgx = bp[2], bgy = -bp[1]
-        if (bgx < minX) minX = bgx; if (bgx > maxX) maxX = bgx
-        if (bgy < minY) minY = bgy; if (bgy > maxY) maxY = bgy
-    }
-    const hasPositions = minX < Infinity
+    // The server frames the fight (see camera.rs); the map draws world (x, y) as (y, -x)
+    const camera = enc.replay_camera
+    const hasPositions = camera?.bounds != null
     const pad = 25
 
     // Auto-size canvas
@@ -1433,9 +1925,14 @@ function initReplayControls(enc: EncounterSummary) {
         mapCanvas.width = sz; mapCanvas.height = sz; mapW = sz; mapH = sz
     }
 
-    const rangeX = (maxX - minX) || 1, rangeY = (maxY - minY) || 1
-    const scale = Math.min((mapW - pad * 2) / rangeX, (mapH - pad * 2) / rangeY)
-    const offsetX = (mapW - rangeX * scale) / 2, offsetY = (mapH - rangeY * scale) / 2
+    let minX = 0, minY = 0, scale = 1, offsetX = 0, offsetY = 0
+    const frame = (view: ViewBox) => {
+        minX = view.min_y; minY = -view.max_x
+        const rangeX = (view.max_y - view.min_y) || 1, rangeY = (view.max_x - view.min_x) || 1
+        scale = Math.min((mapW - pad * 2) / rangeX, (mapH - pad * 2) / rangeY)
+        offsetX = (mapW - rangeX * scale) / 2; offsetY = (mapH - rangeY * scale) / 2
+    }
+    if (camera?.bounds) frame(camera.bounds)
 
     let playing = false
     let animFrame: number | null = null
@@ -1493,6 +1990,30 @@ function initReplayControls(enc: EncounterSummary) {
             }
         }
 
+        // Update current map
+        if (mapName && maps.length > 0) {
+            const current = maps.filter(m => m.offset_secs <= t).pop() || maps[0]
+            mapName.textContent = ` — ${current.name}`
+            if (mapCanvas) mapCanvas.dataset.mapId = String(current.map_id)
+        }
+
+        // Enemy cast bars: what is being cast right now, and casts that just ended
+        if (castsEl) {
+            const CAST_LINGER_SECS = 1
+            castsEl.innerHTML = enemyCasts
+                .filter(c => c.start_secs <= t && t <= c.end_secs + CAST_LINGER_SECS)
+                .map(c => {
+                    const done = t > c.end_secs
+                    const pct = done ? 100 : (c.end_secs > c.start_secs ? (t - c.start_secs) / (c.end_secs - c.start_secs) * 100 : 100)
+                    const color = done && c.outcome === 'interrupted' ? 'var(--accent-green)' : done && c.outcome === 'failed' ? 'var(--text-muted)' : 'var(--accent-orange)'
+                    const label = `${c.enemy_name}: ${c.spell_name}${done && c.outcome !== 'success' ? ` (${c.outcome})` : ''}`
+                    return `<div title="${label}" style="position:relative;width:180px;height:18px;border:1px solid var(--border-color);border-radius:4px;overflow:hidden;font-size:10px">
+                      <div style="position:absolute;inset:0;width:${pct.toFixed(0)}%;background:${color};opacity:0.35"></div>
+                      <div style="position:relative;padding:2px 6px;white-space:nowrap;overflow:hidden;text-overflow:ellipsis">${label}</div>
+                    </div>`
+                }).join('')
+        }
+
         // Update boss HP
         const bossFill = document.getElementById('replay-boss-fill')
         const bossText = document.getElementById('replay-boss-text')
@@ -1563,6 +2084,33 @@ function initReplayControls(enc: EncounterSummary) {
                 ctx.fillText(s.name.split('-')[0], cx, cy - 12)
             }
 
+            // Avoidable hits from the last few seconds (fading red crosses)
+            const HIT_FADE_SECS = 3
+            for (const h of avoidableHits) {
+                const age = t - h.offset_secs
+                if (age < 0 || age > HIT_FADE_SECS) continue
+                const hx = offsetX + (h.pos_y! - minX) * scale, hy = offsetY + (-h.pos_x! - minY) * scale
+                ctx.strokeStyle = `rgba(239,68,68,${(1 - age / HIT_FADE_SECS).toFixed(2)})`
+                ctx.lineWidth = 2
+                ctx.beginPath(); ctx.moveTo(hx - 5, hy - 5); ctx.lineTo(hx + 5, hy + 5)
+                ctx.moveTo(hx + 5, hy - 5); ctx.lineTo(hx - 5, hy + 5); ctx.stroke()
+            }
+
+            // Untargeted enemy casts from the last few seconds (fading purple rings)
+            const MARKER_FADE_SECS = 4
+            for (const m of mechanicMarkers) {
+                const age = t - m.offset_secs
+                if (age < 0 || age > MARKER_FADE_SECS) continue
+                const mx = offsetX + (m.pos_y - minX) * scale, my = offsetY + (-m.pos_x - minY) * scale
+                const alpha = (1 - age / MARKER_FADE_SECS).toFixed(2)
+                ctx.strokeStyle = `rgba(168,85,247,${alpha})`
+                ctx.lineWidth = 2
+                ctx.beginPath(); ctx.arc(mx, my, 10 + age * 3, 0, Math.PI * 2); ctx.stroke()
+                ctx.fillStyle = `rgba(168,85,247,${alpha})`
+                ctx.font = '10px Inter, sans-serif'
+                ctx.fillText(m.spell_name, mx, my + 22 + age * 3)
+            }
+
             // Draw boss marker (red diamond)
             if (bossPos.length > 0) {
                 let bLo = 0, bHi = bossPos.length - 1, bBest = -1
@@ -1592,6 +2140,37 @@ function initReplayControls(enc: EncounterSummary) {
 
     slider.addEventListener('input', () => updateDisplay(parseInt(slider.value)))
 
+    // Camera presets reframe the map; an area preset also jumps to when the fight got there
+    document.getElementById('replay-camera')?.addEventListener('click', e => {
+        const i = (e.target as HTMLElement).dataset.cameraPreset
+        const preset = i != null ? camera?.presets[parseInt(i)] : undefined
+        if (!preset) return
+        frame(preset.view)
+        if (preset.from_secs != null && (parseInt(slider.value) / 10 < preset.from_secs || parseInt(slider.value) / 10 > (preset.to_secs ?? dur))) {
+            slider.value = String(Math.round(preset.from_secs * 10))
+        }
+        updateDisplay(parseInt(slider.value))
+    })
+
+    // Bookmarks jump the replay to their time; the button bookmarks the current one
+    const markers = document.getElementById('replay-markers')
+    markers?.addEventListener('click', e => {
+        const secs = (e.target as HTMLElement).dataset.bookmarkSecs
+        if (secs == null) return
+        slider.value = String(Math.round(parseFloat(secs) * 10))
+        updateDisplay(parseInt(slider.value))
+    })
+    document.getElementById('replay-bookmark-btn')?.addEventListener('click', () => {
+        const name = filename && window.prompt('Bookmark name')?.trim()
+        if (!filename || !name) return
+        addBookmark(filename, enc.stable_id, name, parseInt(slider.value) / 10)
+            .then(bookmark => {
+                enc.bookmarks = [...(enc.bookmarks || []), bookmark].sort((a, b) => a.offset_secs - b.offset_secs)
+                markers?.insertAdjacentHTML('beforeend', renderBookmarkMarker(dur)(bookmark))
+            })
+            .catch(err => window.alert(`Failed to save bookmark: ${err.message}`))
+    })
+
     playBtn.addEventListener('click', () => {
         playing = !playing
         playBtn.textContent = playing ? '⏸' : '▶'
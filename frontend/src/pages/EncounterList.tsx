@@ -1,8 +1,9 @@
 import { useEffect, useState } from 'react'
 import { useParams, Link, useNavigate } from 'react-router-dom'
-import { fetchSummary } from '../api'
-import type { CombatLogSummary, EncounterSummary, ZoneChange } from '../types'
-import { formatDuration, formatNumber } from '../utils'
+import { fetchSummary, splitLog, xlsxUrl } from '../api'
+import type { CombatLogSummary, EncounterSummary, InstanceVisit, SplitFile, ZoneChange } from '../types'
+import { formatBytes, formatDuration, formatNumber } from '../utils'
+import { useParseJobs } from '../hooks/useParseJobs'
 
 export default function EncounterList() {
     const { filename } = useParams<{ filename: string }>()
@@ -11,20 +12,33 @@ export default function EncounterList() {
     const [loading, setLoading] = useState(true)
     const [error, setError] = useState<string | null>(null)
     const [modalPulls, setModalPulls] = useState<{ name: string; pulls: EncounterSummary[] } | null>(null)
+    const [showTrivial, setShowTrivial] = useState(false)
+    const [trivialHidden, setTrivialHidden] = useState(0)
+    const [splitResult, setSplitResult] = useState<{ by: string; files?: SplitFile[]; error?: string } | null>(null)
+    const jobs = useParseJobs(loading)
 
     useEffect(() => {
         if (!filename) return
-        fetchSummary(filename)
-            .then(({ summary }) => { setSummary(summary); setLoading(false) })
+        fetchSummary(filename, false, showTrivial)
+            .then(({ summary, trivialHidden }) => { setSummary(summary); setTrivialHidden(trivialHidden); setLoading(false) })
             .catch(e => { setError(e.message); setLoading(false) })
-    }, [filename])
+    }, [filename, showTrivial])
 
     if (loading) {
+        const job = jobs.find(j => j.filename === filename)
+        const ahead = job ? jobs.filter(j => j.id < job.id && j.filename !== filename).length : 0
         return (
             <div className="loading">
                 <div className="spinner" />
-                <div className="loading-text">Parsing combat log...</div>
-                <div className="loading-sub">This may take a moment for large files</div>
+                <div className="loading-text">
+                    {job?.status === 'queued' ? 'Waiting for other parses to finish...' : 'Parsing combat log...'}
+                    {job?.status === 'running' && ` ${job.progress_pct.toFixed(0)}%`}
+                </div>
+                <div className="loading-sub">
+                    {job
+                        ? <>{formatBytes(job.bytes_read)} of {formatBytes(job.size_bytes)} read in {formatDuration(job.elapsed_secs)} · needs about {formatBytes(job.memory_estimate_bytes)} of memory{ahead > 0 && <> · {ahead} other parse{ahead > 1 ? 's' : ''} in the queue</>}</>
+                        : 'This may take a moment for large files'}
+                </div>
             </div>
         )
     }
@@ -37,12 +51,14 @@ export default function EncounterList() {
         return (
             <>
                 <Link to="/" className="back-btn">← Back to logs</Link>
-                <div className="empty-state"><div className="icon">🔍</div><div className="title">No encounters found</div><p>This log file doesn't contain any encounters.</p></div>
+                <div className="empty-state"><div className="icon">🔍</div><div className="title">No encounters found</div><p>This log file doesn't contain any encounters.{trivialHidden > 0 && <> <a href="#" onClick={e => { e.preventDefault(); setShowTrivial(true) }}>Show {trivialHidden} short pulls</a></>}</p></div>
             </>
         )
     }
 
-    const bossEncs = encounters.filter(e => e.encounter_type !== 'trash')
+    // Fights outside encounters: open world, raid trash, and target dummy practice
+    const isOtherCombat = (e: EncounterSummary) => e.encounter_type === 'combat' || e.encounter_type === 'practice'
+    const bossEncs = encounters.filter(e => e.encounter_type !== 'trash' && e.encounter_type !== 'arena' && !isOtherCombat(e))
     const kills = bossEncs.filter(e => e.success).length
     const wipes = bossEncs.filter(e => !e.success).length
     const zones = [...new Set(summary.zone_changes.map(z => z.zone_name))]
@@ -50,7 +66,9 @@ export default function EncounterList() {
     // Split M+, dungeon, and raid encounters
     const mplusEncs = encounters.filter(e => e.encounter_type === 'mythic_plus')
     const dungeonEncs = encounters.filter(e => e.encounter_type === 'dungeon')
-    const raidEncs = encounters.filter(e => e.encounter_type !== 'mythic_plus' && e.encounter_type !== 'dungeon')
+    const raidEncs = encounters.filter(e => e.encounter_type !== 'mythic_plus' && e.encounter_type !== 'dungeon' && e.encounter_type !== 'arena' && !isOtherCombat(e))
+    const combatEncs = encounters.filter(isOtherCombat)
+    const arenaEncs = encounters.filter(e => e.encounter_type === 'arena')
 
     // Zone resolution
     const zc = (summary.zone_changes || []).slice().sort((a, b) => new Date(a.timestamp).getTime() - new Date(b.timestamp).getTime())
@@ -61,30 +79,75 @@ export default function EncounterList() {
         return zone
     }
 
-    // Group raid encounters into sessions
-    interface Session { encounters: EncounterSummary[] }
+    // Instance visit each encounter started in
+    const visits = summary.instance_visits || []
+    function visitFor(enc: EncounterSummary): InstanceVisit | undefined {
+        return visits.find(v => v.encounter_ids.includes(enc.stable_id))
+    }
+
+    // Group raid encounters into sessions: one per instance visit, or by gaps when the
+    // log has no zone changes
+    interface Session { encounters: EncounterSummary[]; visit?: InstanceVisit }
     const sessions: Session[] = []
     if (raidEncs.length > 0) {
         let cur: EncounterSummary[] = [raidEncs[0]]
         for (let i = 1; i < raidEncs.length; i++) {
             const prev = raidEncs[i - 1], c = raidEncs[i]
-            const gapMin = (new Date(c.start_time).getTime() - new Date(prev.end_time || prev.start_time).getTime()) / 60000
-            if (gapMin > 30 || c.difficulty_id !== prev.difficulty_id || zoneFor(c) !== zoneFor(prev)) {
-                sessions.push({ encounters: cur }); cur = [c]
+            const visit = visitFor(c)
+            const split = visit
+                ? visit !== visitFor(prev)
+                : (new Date(c.start_time).getTime() - new Date(prev.end_time || prev.start_time).getTime()) / 60000 > 30
+                    || c.difficulty_id !== prev.difficulty_id || zoneFor(c) !== zoneFor(prev)
+            if (split) {
+                sessions.push({ encounters: cur, visit: visitFor(cur[0]) }); cur = [c]
             } else { cur.push(c) }
         }
-        sessions.push({ encounters: cur })
+        sessions.push({ encounters: cur, visit: visitFor(cur[0]) })
+    }
+
+    function runSplit(by: 'session' | 'encounter') {
+        setSplitResult({ by })
+        splitLog(filename!, by)
+            .then(files => setSplitResult({ by, files }))
+            .catch(e => setSplitResult({ by, error: e.message }))
     }
 
     function goToEncounter(enc: EncounterSummary) {
-        navigate(`/log/${encodeURIComponent(filename!)}/encounter/${enc.index}`)
+        navigate(`/log/${encodeURIComponent(filename!)}/encounter/${enc.stable_id}`)
     }
 
     return (
         <>
             <Link to="/" className="back-btn">← Back to logs</Link>
             <h1 className="page-title">{zones.join(', ') || filename}</h1>
-            <p className="page-subtitle">{bossEncs.length} encounters — {kills} kills, {wipes} wipes</p>
+            <p className="page-subtitle">
+                {bossEncs.length} encounters — {kills} kills, {wipes} wipes
+                {trivialHidden > 0 && <> · <a href="#" onClick={e => { e.preventDefault(); setShowTrivial(true) }}>show {trivialHidden} short pulls</a></>}
+                {showTrivial && <> · <a href="#" onClick={e => { e.preventDefault(); setShowTrivial(false) }}>hide short pulls</a></>}
+                {' · '}<a href={xlsxUrl(filename!, undefined, showTrivial)} download title="Every encounter's players, abilities, deaths, buffs and segments as one Excel workbook">📊 Export to Excel</a>
+                {' · '}✂️ Split by <a href="#" onClick={e => { e.preventDefault(); runSplit('session') }} title="One log per play session, for archiving">session</a>
+                {' / '}<a href="#" onClick={e => { e.preventDefault(); runSplit('encounter') }} title="One log per boss pull or key, for sharing">encounter</a>
+            </p>
+            {splitResult && (
+                <div className="card" style={{ marginBottom: 16 }}>
+                    {splitResult.error ? <>⚠️ Split failed: {splitResult.error}</>
+                        : !splitResult.files ? <>Splitting by {splitResult.by}...</>
+                        : splitResult.files.length === 0 ? <>Nothing to split by {splitResult.by}.</>
+                        : <>
+                            Wrote {splitResult.files.length} log{splitResult.files.length > 1 ? 's' : ''} to the Split folder:
+                            <ul style={{ margin: '8px 0 0' }}>
+                                {splitResult.files.map(f => (
+                                    <li key={f.filename}><Link to={`/log/${encodeURIComponent(f.filename)}`}>{f.label}</Link> — {f.start_time}, {formatBytes(f.size_bytes)}</li>
+                                ))}
+                            </ul>
+                        </>}
+                </div>
+            )}
+            {summary.parse_error && (
+                <div className="card" style={{ borderColor: 'var(--accent-red)', marginBottom: 16 }} title={summary.parse_error.line}>
+                    ⚠️ The parser crashed on line {summary.parse_error.line_number} ({summary.parse_error.message}); only the log up to that line is shown. Please report it with the line.
+                </div>
+            )}
 
             <div className="stats-grid">
                 <div className="stat-card"><div className="stat-value">{bossEncs.length}</div><div className="stat-label">Encounters</div></div>
@@ -210,6 +273,8 @@ export default function EncounterList() {
                                     <span>🔴 End {timeFmt(endD)}</span>
                                     <span style={{ color: 'var(--accent-green)' }}>✓ {sKills} kills</span>
                                     <span style={{ color: 'var(--accent-red)' }}>✗ {sWipes} wipes</span>
+                                    {session.visit && <span>⏱ {formatDuration(session.visit.duration_secs)} in instance</span>}
+                                    {session.visit && <span title={session.visit.players.join(', ')}>👥 {session.visit.players.length} present</span>}
                                 </div>
                             </div>
 
@@ -263,6 +328,58 @@ export default function EncounterList() {
                         </div>
                     )
                 })}
+
+                {/* Arena */}
+                {arenaEncs.length > 0 && (
+                    <div style={{ gridColumn: '1 / -1', margin: '24px 0 8px 0', display: 'flex', alignItems: 'center', gap: 12 }}>
+                        <div style={{ height: 1, flex: 1, background: 'var(--border-color)' }} />
+                        <div style={{ fontSize: 14, fontWeight: 600, color: 'var(--accent-gold)', textTransform: 'uppercase', letterSpacing: 1, whiteSpace: 'nowrap' }}>Arena</div>
+                        <div style={{ height: 1, flex: 1, background: 'var(--border-color)' }} />
+                    </div>
+                )}
+                {arenaEncs.map((enc, ai) => {
+                    const shuffle = enc.arena?.bracket.includes('Shuffle') ?? false
+                    return (
+                        <div key={enc.stable_id} className="card encounter-card animate-in" style={{ animationDelay: `${ai * 30}ms` }} onClick={() => goToEncounter(enc)}>
+                            <div className="card-header">
+                                <div className="card-title">🏟️ {enc.name}</div>
+                                {!shuffle && enc.arena?.winning_team != null && (
+                                    <span className={`encounter-result ${enc.success ? 'kill' : 'wipe'}`}>{enc.success ? '✓ Victory' : '✗ Defeat'}</span>
+                                )}
+                            </div>
+                            <div className="card-meta">
+                                <span>🕐 {new Date(enc.start_time).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}</span>
+                                <span>⚔️ {enc.difficulty_name}</span>
+                                {shuffle && <span>🔁 {enc.arena?.rounds.length} rounds</span>}
+                                <span>⏱ {formatDuration(enc.duration_secs)}</span>
+                                {enc.deaths.length > 0 && <span>💀 {enc.deaths.length} deaths</span>}
+                            </div>
+                        </div>
+                    )
+                })}
+
+                {/* Other combat */}
+                {combatEncs.length > 0 && (
+                    <div style={{ gridColumn: '1 / -1', margin: '24px 0 8px 0', display: 'flex', alignItems: 'center', gap: 12 }}>
+                        <div style={{ height: 1, flex: 1, background: 'var(--border-color)' }} />
+                        <div style={{ fontSize: 14, fontWeight: 600, color: 'var(--accent-gold)', textTransform: 'uppercase', letterSpacing: 1, whiteSpace: 'nowrap' }}>Other combat</div>
+                        <div style={{ height: 1, flex: 1, background: 'var(--border-color)' }} />
+                    </div>
+                )}
+                {combatEncs.map((enc, ci) => (
+                    <div key={enc.stable_id} className="card encounter-card animate-in" style={{ animationDelay: `${ci * 30}ms` }} onClick={() => goToEncounter(enc)}>
+                        <div className="card-header">
+                            <div className="card-title">{enc.encounter_type === 'practice' ? '🎯' : '⚔️'} {enc.name}</div>
+                        </div>
+                        <div className="card-meta">
+                            <span>🕐 {new Date(enc.start_time).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}</span>
+                            <span>⏱ {formatDuration(enc.duration_secs)}</span>
+                            <span>👥 {enc.group_size} {enc.group_size === 1 ? 'player' : 'players'}</span>
+                            <span>💥 {formatNumber(Math.round(enc.players.reduce((sum, p) => sum + p.dps, 0)))} DPS</span>
+                            {enc.deaths.length > 0 && <span>💀 {enc.deaths.length} deaths</span>}
+                        </div>
+                    </div>
+                ))}
             </div>
             {/* Pulls modal */}
             {modalPulls && (
@@ -284,6 +401,7 @@ export default function EncounterList() {
                         <div style={{ fontSize: 13, color: 'var(--text-secondary)', marginBottom: 14 }}>
                             {modalPulls.pulls.length} pulls — {modalPulls.pulls.filter(p => p.success).length} kills, {modalPulls.pulls.filter(p => !p.success).length} wipes
                         </div>
+                        {modalPulls.pulls.length >= 3 && <PullProgressChart pulls={modalPulls.pulls} />}
                         <div style={{ display: 'flex', flexDirection: 'column', gap: 6 }}>
                             {modalPulls.pulls.map((p, pi) => (
                                 <div
@@ -310,3 +428,29 @@ export default function EncounterList() {
         </>
     )
 }
+
+/** Boss HP % left (line) and time of the first death (dots) per pull, oldest pull first */
+function PullProgressChart({ pulls }: { pulls: EncounterSummary[] }) {
+    const w = 440, h = 90
+    const x = (i: number) => (pulls.length > 1 ? i / (pulls.length - 1) : 0.5) * (w - 16) + 8
+    const hpLeft = pulls.map(p => p.success ? 0 : p.boss_hp_pct ?? 100)
+    const firstDeaths = pulls.map(p => p.deaths.length > 0 ? Math.min(...p.deaths.map(d => d.time_ms / 1000)) : null)
+    const maxSecs = Math.max(...pulls.map(p => p.duration_secs), 1)
+    const phases = pulls.map(p => p.phases.length > 0 ? Math.max(...p.phases.map(ph => ph.phase_id)) : null)
+    return (
+        <div style={{ marginBottom: 14 }}>
+            <svg viewBox={`0 0 ${w} ${h}`} style={{ width: '100%', height: h, display: 'block', background: 'var(--bg-secondary)', borderRadius: 8 }}>
+                <polyline points={hpLeft.map((hp, i) => `${x(i)},${(4 + hp / 100 * (h - 8)).toFixed(1)}`).join(' ')} fill="none" stroke="var(--accent-purple)" strokeWidth={2} />
+                {firstDeaths.map((secs, i) => secs != null && (
+                    <circle key={i} cx={x(i)} cy={(h - 4 - secs / maxSecs * (h - 8)).toFixed(1)} r={3} fill="var(--accent-red)">
+                        <title>Pull {i + 1}: first death at {formatDuration(secs)}{phases[i] != null ? `, reached phase ${phases[i]}` : ''}</title>
+                    </circle>
+                ))}
+            </svg>
+            <div style={{ display: 'flex', gap: 14, fontSize: 11, color: 'var(--text-muted)', marginTop: 4 }}>
+                <span style={{ color: 'var(--accent-purple)' }}>— Boss HP left (lower is closer)</span>
+                <span style={{ color: 'var(--accent-red)' }}>● First death (higher is later)</span>
+            </div>
+        </div>
+    )
+}
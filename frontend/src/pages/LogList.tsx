@@ -1,12 +1,22 @@
-import { useEffect, useState } from 'react'
+import { useEffect, useState, type MouseEvent } from 'react'
 import { Link } from 'react-router-dom'
-import { fetchLogs } from '../api'
-import type { LogFileInfo } from '../types'
+import { fetchLogs, searchEncounters, connectLive, setFavorite, mergeLogs } from '../api'
+import type { LogFileInfo, LogProbe, SearchHit, LiveUpdate, MergedLog } from '../types'
+import { formatDuration } from '../utils'
 
 export default function LogList() {
     const [logs, setLogs] = useState<LogFileInfo[]>([])
     const [loading, setLoading] = useState(true)
     const [error, setError] = useState<string | null>(null)
+    const [query, setQuery] = useState('')
+    const [results, setResults] = useState<SearchHit[] | null>(null)
+    const [live, setLive] = useState<LiveUpdate | null>(null)
+    // The character picked for the personal live view, remembered per browser so each
+    // teammate on the LAN keeps theirs
+    const [livePlayer, setLivePlayer] = useState<string | null>(() => localStorage.getItem('livePlayer'))
+    // Logs picked to merge into one, and the last merge's result
+    const [toMerge, setToMerge] = useState<string[]>([])
+    const [merged, setMerged] = useState<MergedLog | null>(null)
 
     useEffect(() => {
         fetchLogs()
@@ -14,6 +24,45 @@ export default function LogList() {
             .catch(e => { setError(e.message); setLoading(false) })
     }, [])
 
+    useEffect(() => connectLive(setLive, livePlayer), [livePlayer])
+
+    const pickLivePlayer = (guid: string) => {
+        if (guid) localStorage.setItem('livePlayer', guid)
+        else localStorage.removeItem('livePlayer')
+        setLivePlayer(guid || null)
+    }
+
+    const toggleFavorite = (e: MouseEvent, log: LogFileInfo) => {
+        e.preventDefault()
+        setFavorite(log.filename, !log.favorite)
+            .then(() => setLogs(prev => prev.map(l => l.filename === log.filename ? { ...l, favorite: !log.favorite } : l)))
+            .catch(err => setError(err.message))
+    }
+
+    const toggleMerge = (e: MouseEvent, log: LogFileInfo) => {
+        e.preventDefault()
+        setToMerge(prev => prev.includes(log.filename) ? prev.filter(f => f !== log.filename) : [...prev, log.filename])
+    }
+
+    const runMerge = () => {
+        mergeLogs(toMerge)
+            .then(result => {
+                setMerged(result)
+                setToMerge([])
+                return fetchLogs().then(setLogs)
+            })
+            .catch(err => setError(err.message))
+    }
+
+    // Search as you type, once typing pauses
+    useEffect(() => {
+        if (!query.trim()) { setResults(null); return }
+        const timer = setTimeout(() => {
+            searchEncounters(query).then(setResults).catch(() => setResults([]))
+        }, 250)
+        return () => clearTimeout(timer)
+    }, [query])
+
     if (loading) {
         return (
             <div className="loading">
@@ -50,6 +99,53 @@ export default function LogList() {
             </div>
             <h1 className="page-title">Combat Log Files</h1>
             <p className="page-subtitle">{logs.length} log files found — click one to analyze</p>
+            {live?.key_pace && <KeyPaceBanner pace={live.key_pace} />}
+            {live && live.roster.length > 0 && <LivePlayerPanel live={live} picked={livePlayer} onPick={pickLivePlayer} />}
+            <input
+                type="search"
+                value={query}
+                onChange={e => setQuery(e.target.value)}
+                placeholder="Search bosses, dungeons, players, dates… (e.g. ansurek mythic feb)"
+                style={{ width: '100%', padding: '10px 14px', marginBottom: 16, borderRadius: 8, border: '1px solid var(--border-color)', background: 'var(--bg-input)', color: 'var(--text-primary)', fontSize: 14 }}
+            />
+            {results && (
+                <div className="card-grid" style={{ marginBottom: 24 }}>
+                    {results.length === 0 && <p className="page-subtitle">No encounters match “{query}”. Only logs opened at least once are searchable.</p>}
+                    {results.map(hit => (
+                        <Link
+                            key={hit.stable_id}
+                            to={`/log/${encodeURIComponent(hit.filename)}/encounter/${hit.stable_id}`}
+                            className="card"
+                            style={{ textDecoration: 'none' }}
+                        >
+                            <div className="card-header">
+                                <div className="card-title">{hit.name}</div>
+                                {hit.encounter_type !== 'trash' && hit.encounter_type !== 'combat' && hit.encounter_type !== 'practice' && (
+                                    <span className={`encounter-result ${hit.success ? 'kill' : 'wipe'}`}>{hit.success ? '✓' : '✗'}</span>
+                                )}
+                            </div>
+                            <div className="card-meta">
+                                <span>{hit.difficulty_name}{hit.instance_name ? ` — ${hit.instance_name}` : ''}</span>
+                                <span>🕒 {hit.start_time.split('.')[0]}</span>
+                                {hit.matched_players.length > 0 && <span>👤 {hit.matched_players.join(', ')}</span>}
+                            </div>
+                        </Link>
+                    ))}
+                </div>
+            )}
+            {(toMerge.length > 0 || merged) && (
+                <div className="card" style={{ marginBottom: 16 }}>
+                    {toMerge.length > 0 ? <>
+                        {toMerge.length} log{toMerge.length > 1 ? 's' : ''} picked to merge{' '}
+                        <button onClick={runMerge} disabled={toMerge.length < 2}>Merge into one log</button>{' '}
+                        <a href="#" onClick={e => { e.preventDefault(); setToMerge([]) }}>clear</a>
+                    </> : merged && <>
+                        Merged into <Link to={`/log/${encodeURIComponent(merged.filename)}`}>{merged.filename}</Link>: {merged.lines.toLocaleString()} lines
+                        {merged.overlapping_lines > 0 && <>, {merged.overlapping_lines.toLocaleString()} overlapping lines dropped</>}
+                        {merged.headers_dropped > 0 && <>, {merged.headers_dropped} repeated headers dropped</>}
+                    </>}
+                </div>
+            )}
             <div className="card-grid">
                 {logs.map((log, i) => (
                     <Link
@@ -60,14 +156,96 @@ export default function LogList() {
                     >
                         <div className="card-header">
                             <div className="card-title">📜 {log.date_str}</div>
-                            <div className="card-badge">{log.size_display}</div>
+                            <div className="card-badge">
+                                <span
+                                    onClick={e => toggleFavorite(e, log)}
+                                    title={log.favorite ? 'Favorite: never deleted by the retention policy' : 'Mark as favorite'}
+                                    style={{ cursor: 'pointer', marginRight: 6 }}
+                                >{log.favorite ? '★' : '☆'}</span>
+                                <span
+                                    onClick={e => toggleMerge(e, log)}
+                                    title={toMerge.includes(log.filename) ? 'Picked to merge' : 'Pick to merge with other logs of the same session'}
+                                    style={{ cursor: 'pointer', marginRight: 6, opacity: toMerge.includes(log.filename) ? 1 : 0.4 }}
+                                >🔗</span>
+                                {log.size_display}
+                            </div>
                         </div>
                         <div className="card-meta">
                             <span>📄 {log.filename}</span>
+                            {log.active && <span title="WoW is still writing to this log">🟢 Recording</span>}
                         </div>
+                        {log.probe && <ProbeSummary probe={log.probe} />}
                     </Link>
                 ))}
             </div>
         </>
     )
 }
+
+/** The personal live view: pick a character, then see their cooldowns and latest death */
+function LivePlayerPanel({ live, picked, onPick }: { live: LiveUpdate; picked: string | null; onPick: (guid: string) => void }) {
+    const player = live.player
+    const death = player?.last_death
+    return (
+        <div className="card" style={{ marginBottom: 16 }}>
+            <div className="card-header">
+                <div className="card-title">🔴 Live: {player ? `${player.name}${player.spec_name ? ` (${player.spec_name} ${player.class_name})` : ''}` : 'pick your character'}</div>
+                <select
+                    value={picked ?? ''}
+                    onChange={e => onPick(e.target.value)}
+                    style={{ padding: '4px 8px', borderRadius: 6, border: '1px solid var(--border-color)', background: 'var(--bg-input)', color: 'var(--text-primary)' }}
+                >
+                    <option value="">—</option>
+                    {live.roster.map(p => <option key={p.guid} value={p.guid}>{p.name}{p.role ? ` (${p.role})` : ''}</option>)}
+                </select>
+            </div>
+            {player && (
+                <div className="card-meta" style={{ flexWrap: 'wrap' }}>
+                    {player.cooldowns.map(c => (
+                        <span key={c.spell_id} title={`${c.category}, ${formatDuration(c.cooldown_secs)} cooldown`} style={{ color: c.ready_in_secs > 0 ? 'var(--text-muted)' : 'var(--accent-green)' }}>
+                            {c.spell_name}: {c.ready_in_secs > 0 ? formatDuration(c.ready_in_secs) : 'ready'}
+                        </span>
+                    ))}
+                </div>
+            )}
+            {death && (
+                <div className="card-meta" style={{ flexDirection: 'column', alignItems: 'flex-start' }}>
+                    <span>💀 Died at {formatDuration(death.time_ms / 1000)} to {death.killing_blow_spell ?? 'unknown'}{death.killing_blow_source ? ` (${death.killing_blow_source})` : ''}{death.could_have_used.length > 0 && ` — unused: ${death.could_have_used.join(', ')}`}</span>
+                    {death.recap.slice(-5).map((e, i) => (
+                        <span key={i} style={{ fontSize: 12 }}>{e.event_type === 'damage' ? '🔻' : e.event_type === 'healing' ? '💚' : '✨'} {e.spell_name} ({e.source_name}) {e.amount.toLocaleString()}{e.max_hp > 0 && ` — ${Math.round(e.current_hp / e.max_hp * 100)}%`}</span>
+                    ))}
+                </div>
+            )}
+        </div>
+    )
+}
+
+/** Bosses and keys found by the quick scan, so the right file can be picked before parsing it */
+function ProbeSummary({ probe }: { probe: LogProbe }) {
+    if (probe.boss_pulls === 0 && probe.keys.length === 0) return null
+    const keys = probe.keys.map(k => `${k.dungeon} +${k.level}${k.success === false ? ' (depleted)' : ''}`)
+    const bosses = probe.bosses.map(b => `${b.name} (${b.difficulty_name}) ${b.kills > 0 ? '✔' : `${b.pulls}×`}`)
+    return (
+        <div className="card-meta">
+            {probe.keys.length > 0 && <span title={keys.join('\n')}>🔑 {probe.keys.length === 1 ? keys[0] : `${probe.keys.length} keys`}</span>}
+            {probe.boss_pulls > 0 && <span title={bosses.join('\n')}>⚔️ {probe.boss_pulls} pulls, {probe.boss_kills} kills</span>}
+        </div>
+    )
+}
+
+function KeyPaceBanner({ pace }: { pace: NonNullable<LiveUpdate['key_pace']> }) {
+    const color = pace.on_pace === false ? 'var(--accent-red)' : 'var(--accent-green)'
+    return (
+        <div className="card" style={{ marginBottom: 16, borderColor: color }}>
+            <div className="card-header">
+                <div className="card-title">🔴 Live: {pace.dungeon_name} +{pace.key_level}</div>
+                {pace.on_pace !== null && <div className="card-badge" style={{ color }}>{pace.on_pace ? 'On pace' : 'Behind'}</div>}
+            </div>
+            <div className="card-meta">
+                <span>⏱️ {formatDuration(pace.elapsed_secs)}{pace.par_time_secs !== null && ` / ${formatDuration(pace.par_time_secs)}`}</span>
+                <span>💀 Bosses {pace.bosses_killed}{pace.boss_count !== null && `/${pace.boss_count}`}{pace.expected_bosses !== null && ` (par: ${pace.expected_bosses.toFixed(1)})`}</span>
+                {pace.projected_finish_secs !== null && <span>🏁 Projected {formatDuration(pace.projected_finish_secs)}</span>}
+            </div>
+        </div>
+    )
+}
@@ -1,40 +1,145 @@
-// TypeScript interfaces matching the Rust models in models.rs
+// TypeScript interfaces matching the Rust models in crates/fast-wow-parser/src/models.rs
 
 export interface LogFileInfo {
     filename: string;
     size_bytes: number;
     size_display: string;
     date_str: string;
+    active: boolean;
+    /** Kept by the retention policy however old it gets */
+    favorite: boolean;
+    /** Bosses and keys in the file, from a quick scan */
+    probe?: LogProbe;
+}
+
+export interface LogProbe {
+    boss_pulls: number;
+    boss_kills: number;
+    bosses: { name: string; difficulty_name: string; pulls: number; kills: number }[];
+    keys: { dungeon: string; level: number; success: boolean | null }[];
 }
 
 export interface CombatLogSummary {
     filename: string;
     log_version: number | null;
     build_version: string | null;
+    log_format: 'retail' | 'classic';
+    /** False when the log was recorded with advanced combat logging off */
+    advanced_logging: boolean;
     encounters: EncounterSummary[];
     zone_changes: ZoneChange[];
+    instance_visits: InstanceVisit[];
+    stats: ParseStats;
+    /** Reports of custom event handlers registered with the parser, by name */
+    extensions?: Record<string, unknown>;
+    /** Set when the parser crashed on a line; the summary only covers the log up to it */
+    parse_error?: ParseError;
+    profile?: ParseProfile;
+}
+
+export interface ParseStats {
+    lines: number;
+    /** Event types the parser doesn't know (usually new in a patch), most frequent first */
+    unknown_events: UnknownEvent[];
+}
+
+export interface UnknownEvent {
+    event_type: string;
+    count: number;
+    samples: string[];
+}
+
+export interface ParseError {
+    line_number: number;
+    byte_offset: number;
+    line: string;
+    message: string;
+}
+
+export interface ParseProfile {
+    total_secs: number;
+    io_secs: number;
+    tokenize_secs: number;
+    handle_secs: number;
+    summary_secs: number;
+    lines: number;
+    events: EventTiming[];
+}
+
+export interface EventTiming {
+    event_type: string;
+    count: number;
+    secs: number;
 }
 
 export interface EncounterSummary {
     index: number;
+    /** Hash of start time + encounter ID; unlike index, stays valid as the log grows */
+    stable_id: string;
     encounter_id: number;
     name: string;
     difficulty_id: number;
     difficulty_name: string;
+    instance_name: string | null;
+    season: string | null;
+    instance_icon: string | null;
     group_size: number;
     success: boolean;
     duration_secs: number;
     start_time: string;
     end_time: string;
     key_level: number | null;
-    affixes: number[];
+    par_time_secs: number | null;
+    timed: boolean | null;
+    plus_level: number | null;
+    time_remaining_secs: number | null;
+    affixes: Affix[];
     encounter_type: string; // "boss", "mythic_plus", "trash"
     boss_encounters: BossEncounter[];
     players: PlayerSummary[];
     deaths: DeathEvent[];
+    near_deaths: NearDeath[];
+    wipes: WipeEvent[];
+    duration_excluding_wipes_secs: number | null;
     segments: KeySegment[];
+    top_count_pulls: CountPull[];
     buff_uptimes: Record<string, BuffUptime[]>;
+    key_buff_uptimes: Record<string, BuffUptime[]>;
     enemy_breakdowns: EnemyBreakdown[];
+    enemy_damage_taken: EnemyDamageTaken[];
+    /** Damage taken from each enemy ability across the group, most damage first */
+    mechanics: Mechanic[];
+    priority_targets: PriorityTarget[];
+    boss_trash_split: BossTrashSplit[];
+    pad_targets: PadTarget[];
+    /** Biggest raid-wide damage windows; `covered: false` ones are healer CD gaps */
+    healer_cd_coverage: RaidDamageEvent[];
+    tank_swaps: TankSwap[];
+    /** Major damage cooldowns and their drift, for keys and boss pulls */
+    cooldown_usage: PlayerCooldowns[];
+    /** Set for raid boss pulls */
+    add_timeline: AddTimeline | null;
+    /** Set for target dummy sessions (`encounter_type` "practice") */
+    practice: PracticeSession | null;
+    /** Set for arena matches (`encounter_type` "arena") */
+    arena: ArenaMatch | null;
+    /** Who healed whom, for raid boss encounters */
+    healing_report: HealingReport | null;
+    /** Mythic+ keys that weren't timed */
+    depletion_report: DepletionReport | null;
+    /** How the players' `efficiency` scores are calculated */
+    efficiency_formula?: string;
+    /** Findings of the insight rules, in rule order */
+    insights: Insight[];
+    /** Values of the configured custom metric rules, in rule order */
+    custom_metrics?: CustomMetric[];
+    avoidable_damage: AvoidableDamage;
+    avoidable_hits?: AvoidableHit[];
+    /** Hits from dangerous enemy abilities and how many found no defensive up; empty with buff timelines off */
+    defensive_coverage: DefensiveCoverage[];
+    pre_pull: PullWindow | null;
+    post_pull: PullWindow | null;
+    notes?: EncounterNotes;
     boss_hp_pct: number | null;
     boss_max_hp: number | null;
     phases: PhaseBreakdown[];
@@ -42,7 +147,19 @@ export interface EncounterSummary {
     boss_hp_timeline: [number, number][];
     replay_timeline?: HpSnapshot[];
     boss_positions?: [number, number, number][];
+    enemy_casts?: EnemyCast[];
+    mechanic_markers?: MechanicMarker[];
+    replay_maps?: ReplayMap[];
+    replay_camera?: ReplayCamera;
     raw_ability_events?: [number, string, number, string, number, number, string][];
+    bookmarks?: Bookmark[];
+}
+
+export interface Affix {
+    id: number;
+    name: string;
+    description: string;
+    icon_url: string;
 }
 
 export interface BossEncounter {
@@ -70,24 +187,98 @@ export interface KeySegment {
     end_time: string;
     players: PlayerSummary[];
     deaths: DeathEvent[];
+    near_deaths: NearDeath[];
     buff_uptimes: Record<string, BuffUptime[]>;
+    key_buff_uptimes: Record<string, BuffUptime[]>;
     enemy_breakdowns: EnemyBreakdown[];
+    enemy_damage_taken: EnemyDamageTaken[];
+    priority_targets: PriorityTarget[];
+    phases: PhaseBreakdown[];
+    boss_hp_timeline: [number, number][];
     pulls: TrashPull[];
+    pre_pull: PullWindow | null;
+    post_pull: PullWindow | null;
 }
 
 export interface TrashPull {
     pull_index: number;
     duration_secs: number;
     start_time_offset: number;
+    enemy_forces: number;
     enemies: PullEnemy[];
     players: PlayerSummary[];
     deaths: DeathEvent[];
+    opener: PullOpener;
+    /** Who each enemy was attacking over the pull, in order of its first hit */
+    tanking: EnemyTanking[];
+}
+
+/** Offsets are seconds from the pull start */
+export interface EnemyTanking {
+    enemy_name: string;
+    npc_id: number | null;
+    spans: TankedSpan[];
+    target_swaps: number;
+    /** Seconds spent attacking players who aren't tanks */
+    loose_secs: number;
+}
+
+export interface TankedSpan {
+    start_offset_secs: number;
+    end_offset_secs: number;
+    player_name: string;
+    on_tank: boolean;
+    hits: number;
+}
+
+/** Offsets are seconds from the pull start, negative before it */
+export interface PullOpener {
+    opened_by: string | null;
+    first_targets: FirstTarget[];
+    taunts: OpenerCast[];
+    redirects: OpenerCast[];
+    /** The non-tank with the most damage in the first 5 seconds, a threat proxy */
+    top_early_damage: EarlyDamage | null;
+    /** Some enemy's first melee swing went to a non-tank */
+    aggro_lost: boolean;
+}
+
+export interface EarlyDamage {
+    player_name: string;
+    damage: number;
+    /** The most any tank did in the same seconds */
+    tank_damage: number;
+}
+
+export interface FirstTarget {
+    offset_secs: number;
+    enemy_name: string;
+    player_name: string;
+    on_tank: boolean;
+}
+
+export interface OpenerCast {
+    offset_secs: number;
+    caster_name: string;
+    spell_id: number;
+    spell_name: string;
+    target_name: string;
+}
+
+export interface CountPull {
+    segment_index: number;
+    segment_name: string;
+    pull_index: number;
+    enemy_forces: number;
+    duration_secs: number;
+    forces_per_min: number;
 }
 
 export interface PullEnemy {
     name: string;
     damage_taken: number;
     mob_type: string;
+    enemy_forces: number;
 }
 
 export interface PlayerSummary {
@@ -95,16 +286,161 @@ export interface PlayerSummary {
     name: string;
     class_name: string;
     spec_name: string;
-    role: string;
+    role: 'tank' | 'healer' | 'dps';
+    spec_inferred: boolean;
+    /** Talent entry IDs picked (ranks left out), sorted; absent without COMBATANT_INFO */
+    talents?: number[];
     damage_done: number;
     healing_done: number;
     damage_taken: number;
     deaths: number;
+    near_deaths: number;
+    saves: number;
+    /** Estimated deaths this player's heals prevented (low-HP heals followed by a would-be lethal amount of damage) */
+    deaths_prevented: number;
+    interrupts: number;
+    /** Dispels, purges and spellsteals */
+    dispels: number;
     dps: number;
     hps: number;
+    damage_rank: number;
+    damage_share: number;
+    healing_rank: number;
+    healing_share: number;
     abilities: AbilityBreakdown[];
     heal_abilities: AbilityBreakdown[];
     damage_taken_abilities: AbilityBreakdown[];
+    damage_spikes: DamageSpike[];
+    cast_stats: CastStats;
+    target_count_split: TargetCountSplit;
+    filtered_damage: number;
+    filtered_dps: number;
+    healing_split: HealingSplit;
+    crowd_control: CrowdControlReceived;
+    /** Against this player's own earlier pulls of the same boss and difficulty, or keys of the same dungeon and level */
+    performance_delta?: PerformanceDelta;
+    /** Median DPS of the spec at this key level or raid difficulty, from the benchmark table */
+    reference_dps?: number;
+    /** 0-100 fight efficiency; only on whole-encounter rows */
+    efficiency?: FightEfficiency;
+    /** Damage done and taken by spell school; only on whole-encounter rows */
+    schools?: SchoolProfile;
+    /** Damage done split into direct, periodic, pet and proc; absent on pull rows */
+    damage_types?: DamageTypeSplit;
+}
+
+export interface DamageTypeSplit {
+    direct: number;
+    periodic: number;
+    pet: number;
+    procs: number;
+    direct_pct: number;
+    periodic_pct: number;
+    pet_pct: number;
+    procs_pct: number;
+    /** Each ability's part of each type, largest first; a spell that hits and ticks is listed twice */
+    abilities: { spell_id: number; spell_name: string; damage_type: 'direct' | 'periodic' | 'pet' | 'proc'; amount: number }[];
+}
+
+export interface SchoolProfile {
+    damage_done: SchoolSplit;
+    damage_taken: SchoolSplit;
+}
+
+export interface SchoolSplit {
+    /** Plain physical */
+    physical: number;
+    /** Every other school, mixed ones included */
+    magic: number;
+    /** Largest first */
+    schools: SchoolAmount[];
+}
+
+export interface SchoolAmount {
+    /** Bit mask as in the log (1 physical, 2 holy, 4 fire, ...) */
+    school: number;
+    name: string;
+    amount: number;
+    pct: number;
+}
+
+/** The parts add up to `score`: output (max 40), avoidable damage (25), deaths (20), utility (15) */
+export interface FightEfficiency {
+    score: number;
+    output_points: number;
+    avoidable_points: number;
+    death_points: number;
+    utility_points: number;
+    output_share: number;
+    even_share: number;
+    avoidable_damage: number;
+}
+
+export interface PerformanceDelta {
+    /** Earlier encounters averaged */
+    samples: number;
+    average_dps: number;
+    dps_delta_pct: number;
+    average_hps: number;
+    hps_delta_pct: number;
+}
+
+/** Crowd control a player was under; overlapping effects count once in `total_secs` */
+export interface CrowdControlReceived {
+    events: CrowdControlEvent[];
+    total_secs: number;
+    pct: number;
+}
+
+export interface CrowdControlEvent {
+    spell_id: number;
+    spell_name: string;
+    /** stun, incapacitate, disorient, silence, root, or control (configured spells) */
+    kind: string;
+    source_name: string;
+    /** Milliseconds from the pull */
+    time_ms: number;
+    duration_secs: number;
+}
+
+export interface HealingSplit {
+    external: number;
+    self_healing: number;
+    leech: number;
+    absorbs: number;
+}
+
+export interface TargetCountSplit {
+    single_target: number;
+    cleave: number;
+    aoe: number;
+    single_target_pct: number;
+    cleave_pct: number;
+    aoe_pct: number;
+}
+
+export interface CastStats {
+    casts_started: number;
+    casts_completed: number;
+    casts_cancelled: number;
+    cancel_pct: number;
+    channels: number;
+    channels_clipped: number;
+}
+
+export interface DamageSpike {
+    start_offset_secs: number;
+    window_secs: number;
+    total_damage: number;
+    ended_in_death: boolean;
+    abilities: SpikeAbility[];
+}
+
+export interface SpikeAbility {
+    spell_id: number;
+    spell_name: string;
+    source_name: string;
+    amount: number;
 }
 
 export interface AbilityBreakdown {
@@ -128,6 +464,241 @@ export interface EnemyBreakdown {
     total_damage: number;
     kill_count: number;
     mob_type: string;
+    npc_id: number | null;
+    enemy_forces: number;
+    players: EnemyPlayerDamage[];
+}
+
+export interface PriorityTarget {
+    npc_id: number;
+    name: string;
+    spawns: number;
+    kills: number;
+    avg_lifetime_secs: number;
+    total_damage: number;
+    players: PriorityTargetPlayer[];
+}
+
+export interface PriorityTargetPlayer {
+    guid: string;
+    name: string;
+    damage: number;
+    dps: number;
+}
+
+export interface BossTrashSplit {
+    guid: string;
+    name: string;
+    boss_damage: number;
+    trash_damage: number;
+    boss_dps: number;
+    trash_dps: number;
+}
+
+export interface PadTarget {
+    name: string;
+    reason: 'despawned' | 'harmless';
+    spawns: number;
+    total_damage: number;
+}
+
+export interface RaidDamageEvent {
+    start_offset_secs: number;
+    window_secs: number;
+    total_damage: number;
+    players_hit: number;
+    abilities: SpikeAbility[];
+    cooldowns: HealerCdUse[];
+    covered: boolean;
+}
+
+export interface TankSwap {
+    offset_secs: number;
+    enemy_name: string;
+    from_tank: string;
+    to_tank: string;
+    taunt_spell: string | null;
+    debuff_name: string | null;
+    debuff_stacks: number;
+    /** Old tank's debuff stacks were above the taunt threshold */
+    late: boolean;
+}
+
+export interface HealingReportPlayer {
+    guid: string;
+    name: string;
+    role: string;
+    total: number;
+}
+
+/** Effective healing by healer (rows) on target (columns): `amounts[healer][target]` */
+export interface HealingReport {
+    healers: HealingReportPlayer[];
+    targets: HealingReportPlayer[];
+    amounts: number[][];
+}
+
+export interface DefensiveCoverage {
+    player_guid: string;
+    player_name: string;
+    spell_id: number;
+    spell_name: string;
+    hits: number;
+    /** Hits taken without a personal, external or raid defensive */
+    undefended: number;
+    undefended_damage: number;
+}
+
+export interface AvoidableHit {
+    offset_secs: number;
+    player_name: string;
+    spell_id: number;
+    spell_name: string;
+    amount: number;
+    pos_x: number | null;
+    pos_y: number | null;
+}
+
+/** Teams are 0 and 1 as in the log; Solo Shuffle reshuffles them every round */
+export interface ArenaMatch {
+    bracket: string;
+    log_team: number;
+    winning_team: number | null;
+    rounds: ArenaRound[];
+}
+
+export interface ArenaRound {
+    number: number;
+    start_time: string;
+    duration_secs: number;
+    /** null for a draw */
+    winning_team: number | null;
+    first_death: string | null;
+    players: ArenaRoundPlayer[];
+}
+
+export interface ArenaRoundPlayer {
+    guid: string;
+    name: string;
+    team: number;
+    damage_done: number;
+    healing_done: number;
+    cc_applied: number;
+}
+
+export interface PracticeSession {
+    players: PracticePlayer[];
+}
+
+export interface PracticePlayer {
+    guid: string;
+    name: string;
+    dps: number;
+    /** Spells never recast within 30s, with the second of each use */
+    cooldowns: CooldownUsage[];
+    /** Every cast in order */
+    casts: PracticeCast[];
+}
+
+export interface CooldownUsage {
+    spell_id: number;
+    spell_name: string;
+    uses: number[];
+    /** For major damage cooldowns */
+    drift?: CooldownDrift;
+}
+
+export interface CooldownDrift {
+    base_cooldown_secs: number;
+    /** Average time past the base cooldown between uses; null with fewer than two uses */
+    average_drift_secs: number | null;
+    /** One at the pull and one every base cooldown after */
+    potential_uses: number;
+    lost_uses: number;
+}
+
+export interface AddTimeline {
+    /** In order of appearance */
+    enemies: EnemyLifetime[];
+    /** Most adds alive at once, and when */
+    peak_alive: number;
+    peak_offset_secs: number;
+}
+
+/** One enemy GUID, from when it first traded damage with the group until it died or was last seen */
+export interface EnemyLifetime {
+    name: string;
+    npc_id: number | null;
+    first_seen_secs: number;
+    last_seen_secs: number;
+    died: boolean;
+    is_boss: boolean;
+}
+
+export interface PlayerCooldowns {
+    guid: string;
+    name: string;
+    cooldowns: CooldownUsage[];
+}
+
+export interface PracticeCast {
+    offset_secs: number;
+    spell_id: number;
+    spell_name: string;
+}
+
+export interface AvoidableDamage {
+    total_damage: number;
+    hits: number;
+    players: AvoidablePlayer[];
+    /** Hits binned into 5-yard cells, most hits first */
+    heat: HitHeatCell[];
+}
+
+export interface AvoidablePlayer {
+    player_name: string;
+    hits: number;
+    damage: number;
+    top_spell: string;
+}
+
+export interface HitHeatCell {
+    pos_x: number;
+    pos_y: number;
+    hits: number;
+    damage: number;
+}
+
+export interface HealerCdUse {
+    spell_id: number;
+    spell_name: string;
+    caster_name: string;
+    offset_secs: number;
+}
+
+export interface PullWindow {
+    window_secs: number;
+    damage: number;
+    healing: number;
+    players: PullWindowPlayer[];
+}
+
+export interface PullWindowPlayer {
+    guid: string;
+    name: string;
+    damage: number;
+    healing: number;
+}
+
+export interface EncounterNotes {
+    text: string;
+    tags: string[];
+    updated_at: string;
+}
+
+export interface EnemyDamageTaken {
+    enemy_name: string;
+    total_damage: number;
     players: EnemyPlayerDamage[];
 }
 
@@ -137,6 +708,18 @@ export interface EnemyPlayerDamage {
     damage: number;
 }
 
+export interface Mechanic {
+    spell_id: number;
+    spell_name: string;
+    wowhead_url: string;
+    enemies: string[];
+    total_damage: number;
+    hits: number;
+    average_hit: number;
+    /** Killing blows */
+    deaths: number;
+}
+
 export interface BuffUptime {
     spell_id: number;
     spell_name: string;
@@ -156,21 +739,89 @@ export interface BuffEvent {
     stacks: number;
 }
 
+export interface AuraSnapshot {
+    player_guid: string;
+    player_name: string;
+    time: number;
+    /** Buffs first, then debuffs, each by name */
+    auras: ActiveAura[];
+}
+
+export interface ActiveAura {
+    spell_id: number;
+    spell_name: string;
+    source_name: string;
+    aura_type: string;  // "BUFF" or "DEBUFF"
+    stacks: number;
+    /** When this application started, seconds into the fight (0 if already up at the pull) */
+    applied_at: number;
+    wowhead_url: string;
+}
+
+export interface PlayerAuraLanes {
+    player_guid: string;
+    player_name: string;
+    /** Buffs first, then debuffs, each highest uptime first */
+    lanes: AuraLane[];
+}
+
+export interface AuraLane {
+    spell_id: number;
+    spell_name: string;
+    source_name: string;
+    aura_type: string;  // "BUFF" or "DEBUFF"
+    uptime_pct: number;
+    wowhead_url: string;
+    /** In order, split where the stacks change; none overlap */
+    intervals: AuraInterval[];
+}
+
+export interface AuraInterval {
+    start: number;
+    end: number;
+    stacks: number;
+}
+
 export interface DeathEvent {
-    timestamp: string;
+    /** Milliseconds from the pull */
+    time_ms: number;
     player_name: string;
     player_guid: string;
     killing_blow_spell: string | null;
     killing_blow_source: string | null;
     killing_blow_amount: number | null;
     overkill: number | null;
-    time_into_fight_secs: number;
     recap: RecapEvent[];
+    could_have_used: string[];
+    time_dead_secs: number;
+    battle_res: boolean;
+    resurrected_by: string | null;
+    one_shot: boolean;
+}
+
+export interface NearDeath {
+    /** Milliseconds from the pull */
+    time_ms: number;
+    player_name: string;
+    spell_name: string;
+    source_name: string;
+    absorbed: number;
+    hp_remaining: number;
+    saved_by: string | null;
+    shield_spell: string | null;
+}
+
+export interface WipeEvent {
+    /** Milliseconds from the pull */
+    time_ms: number;
+    segment_index: number | null;
+    segment_name: string | null;
+    run_back_secs: number | null;
 }
 
 export interface RecapEvent {
-    timestamp: string;
-    time_into_fight_secs: number;
+    /** Milliseconds from the pull */
+    time_ms: number;
     event_type: string;
     amount: number;
     spell_name: string;
@@ -200,6 +851,410 @@ export interface ZoneChange {
     difficulty_id: number;
 }
 
+/** One stay in an instance; re-entering it shortly after leaving continues the visit */
+export interface InstanceVisit {
+    zone_id: number;
+    zone_name: string;
+    difficulty_id: number;
+    difficulty_name: string;
+    start_time: string;
+    end_time: string;
+    duration_secs: number;
+    /** Players seen acting during the visit */
+    players: string[];
+    /** Stable IDs of the encounters that started during the visit */
+    encounter_ids: string[];
+}
+
+/** Boss pulls in one instance and difficulty, from `/api/logs/{file}/grouped` */
+export interface InstanceGroup {
+    instance_name: string;
+    instance_icon: string | null;
+    difficulty_id: number;
+    difficulty_name: string;
+    bosses: BossGroup[];
+}
+
+export interface BossGroup {
+    encounter_id: number;
+    name: string;
+    pull_count: number;
+    kills: number;
+    /** 1-based pull number of the first kill */
+    first_kill_pull: number | null;
+    /** Duration of the fastest kill */
+    kill_time_secs: number | null;
+    /** Stable ID of the fastest kill, or the wipe with the boss lowest */
+    best_pull: string | null;
+    best_hp_pct: number | null;
+    total_secs: number;
+    /** Whether progress improved pull after pull up to the first kill; null under 3 pulls */
+    progression: ProgressionTrend | null;
+    /** Phase starts and durations across the pulls, against the first kill */
+    phase_timings: PhaseTiming[];
+    pulls: GroupedPull[];
+}
+
+export interface PhaseTiming {
+    phase_id: number;
+    pulls_reached: number;
+    /** Wipes that ended in the phase */
+    wipes: number;
+    average_start_secs: number;
+    /** Over the pulls that got past the phase */
+    average_duration_secs: number | null;
+    kill_start_secs: number | null;
+    kill_duration_secs: number | null;
+    /** Positive when pulls got there later than the kill did */
+    start_vs_kill_secs: number | null;
+    /** Positive when the phase took longer than on the kill */
+    duration_vs_kill_secs: number | null;
+}
+
+export interface ProgressionTrend {
+    pulls: number;
+    /** Boss HP % left per pull: negative is getting closer */
+    boss_hp_pct_slope: number;
+    phase_slope: number;
+    /** Seconds until the first death per pull: positive is living longer */
+    first_death_secs_slope: number;
+    direction: 'improving' | 'declining' | 'steady';
+}
+
+export interface GroupedPull {
+    index: number;
+    stable_id: string;
+    pull_number: number;
+    start_time: string;
+    duration_secs: number;
+    success: boolean;
+    boss_hp_pct: number | null;
+    deaths: number;
+    phase_reached: number | null;
+    first_death_secs: number | null;
+    phases: PullPhase[];
+}
+
+export interface PullPhase {
+    phase_id: number;
+    start_secs: number;
+    duration_secs: number;
+}
+
+export interface Insight {
+    rule_id: string;
+    severity: 'info' | 'warning' | 'critical';
+    message: string;
+    players: string[];
+}
+
+export interface CustomMetric {
+    name: string;
+    measure: 'count' | 'amount';
+    total: number;
+    /** Highest first */
+    players: { guid: string; name: string; value: number }[];
+}
+
+export interface DepletionReport {
+    over_time_secs: number | null;
+    deaths: number;
+    death_penalty_secs: number;
+    /** Longest run-back first */
+    biggest_wipes: WipeEvent[];
+    enemy_forces: number;
+    /** Most time lost against the best run first, once compared */
+    sections: DepletionSection[];
+    /** Highest timed run of the dungeon in the history */
+    best_run: BestRun | null;
+    enemy_forces_shortfall: number | null;
+}
+
+export interface DepletionSection {
+    name: string;
+    duration_secs: number;
+    deaths: number;
+    best_secs: number | null;
+    delta_secs: number | null;
+}
+
+export interface BestRun {
+    stable_id: string;
+    start_time: string;
+    key_level: number;
+    duration_secs: number;
+    enemy_forces: number;
+}
+
+/** A name or GUID the history views count as another character */
+export interface CharacterAlias {
+    alias: string;
+    character: string;
+}
+
+/** A character's boss pulls and keys with the spec and talents of each, and their build changes */
+export interface BuildTimeline {
+    character: string;
+    /** Oldest first */
+    runs: TimelineRun[];
+    /** Oldest first */
+    changes: BuildChange[];
+}
+
+export interface TimelineRun {
+    stable_id: string;
+    name: string;
+    difficulty_name: string;
+    start_time: string;
+    success: boolean;
+    spec: string;
+    dps: number;
+    hps: number;
+    /** DPS (HPS for healers) as a percentage of their own average on the same boss and difficulty */
+    relative_output_pct: number | null;
+    /** Which build it was on, going up by one at each change */
+    build: number;
+}
+
+/** A spec or talent change, at the first run on the new build */
+export interface BuildChange {
+    stable_id: string;
+    start_time: string;
+    spec_before: string;
+    spec_after: string;
+    talents_added: number[];
+    talents_removed: number[];
+    runs_before: number;
+    runs_after: number;
+    relative_output_before_pct: number | null;
+    relative_output_after_pct: number | null;
+}
+
+/** A log written by merging the logs of one session */
+export interface MergedLog {
+    filename: string;
+    lines: number;
+    /** Repeated COMBAT_LOG_VERSION headers left out */
+    headers_dropped: number;
+    /** Lines a log shared with the one before it */
+    overlapping_lines: number;
+}
+
+/** A log written by splitting a bigger one */
+export interface SplitFile {
+    filename: string;
+    /** "session", or the encounter's name and result */
+    label: string;
+    start_time: string;
+    size_bytes: number;
+}
+
+export interface DungeonStats {
+    zone_id: number;
+    name: string;
+    runs: number;
+    timed: number;
+    timed_pct: number;
+    /** Over completed runs */
+    average_duration_secs: number | null;
+    /** Boss-to-boss sections, each named after the boss ending it */
+    bosses: SectionTime[];
+    /** Most deaths per run first */
+    death_hotspots: SectionDeaths[];
+    key_levels: KeyLevelStats[];
+    /** Most runs first */
+    group_comps: GroupComp[];
+}
+
+export interface SectionTime {
+    name: string;
+    runs: number;
+    average_secs: number;
+    best_secs: number;
+}
+
+export interface SectionDeaths {
+    name: string;
+    runs: number;
+    deaths: number;
+    deaths_per_run: number;
+}
+
+export interface KeyLevelStats {
+    key_level: number;
+    runs: number;
+    completed: number;
+    timed: number;
+    timed_pct: number;
+}
+
+export interface GroupComp {
+    specs: string[];
+    runs: number;
+    timed: number;
+    timed_pct: number;
+}
+
+export interface Leaderboards {
+    /** One entry per spec, highest DPS first */
+    boss_dps: { boss: string; difficulty_name: string; entries: DpsEntry[] }[];
+    /** Fastest first */
+    fastest_keys: { dungeon: string; difficulty_name: string; entries: KeyTimeEntry[] }[];
+    most_deaths: { player: string; deaths: number; encounters: number }[];
+    seasons: string[];
+    difficulties: string[];
+}
+
+export interface DpsEntry {
+    spec: string;
+    player: string;
+    dps: number;
+    stable_id: string;
+    start_time: string;
+}
+
+export interface KeyTimeEntry {
+    duration_secs: number;
+    players: string[];
+    stable_id: string;
+    start_time: string;
+}
+
+export interface Planner {
+    encounter: string | null;
+    difficulty: string | null;
+    pulls: number;
+    kills: number;
+    /** Best first within each role: tanks, healers, then damage dealers */
+    characters: PlannerStats[];
+    /** Highest win rate first */
+    specs: PlannerStats[];
+    suggestion: { tanks: string[]; healers: string[]; dps: string[] };
+    encounters: string[];
+    difficulties: string[];
+}
+
+export interface PlannerStats {
+    /** Character name; absent on spec rows */
+    name?: string;
+    spec: string;
+    role: 'tank' | 'healer' | 'dps';
+    pulls: number;
+    kills: number;
+    win_rate: number;
+    average_dps: number;
+    average_hps: number;
+    deaths_per_pull: number;
+}
+
+export interface Fatigue {
+    nights: number;
+    pulls: number;
+    /** By hours into the night */
+    by_night_hour: FatiguePoint[];
+    /** By local clock hour, evening first */
+    by_clock_hour: FatiguePoint[];
+    /** First hour into the night where the group clearly fell off */
+    drop_off_hour: number | null;
+    /** Oldest first */
+    weeks: { week_start: string; nights: number; pulls: number; by_night_hour: FatiguePoint[] }[];
+}
+
+export interface FatiguePoint {
+    hour: number;
+    pulls: number;
+    kills: number;
+    deaths_per_pull: number;
+    avoidable_pct: number | null;
+    /** Players' DPS as a percentage of their own average on the same boss and difficulty */
+    relative_dps_pct: number | null;
+}
+
+export interface VaultWeek {
+    region: 'eu' | 'us';
+    week_start: string;
+    week_end: string;
+    characters: VaultCharacter[];
+}
+
+export interface VaultCharacter {
+    name: string;
+    runs: number;
+    /** Highest first */
+    key_levels: number[];
+    /** Key level rewarded by each vault slot (1, 4 and 8 runs), null while locked */
+    slots: [number | null, number | null, number | null];
+    keys: VaultKey[];
+}
+
+export interface VaultKey {
+    stable_id: string;
+    name: string;
+    start_time: string;
+    key_level: number;
+    timed: boolean;
+}
+
+export interface AvoidableTrend {
+    encounters: TrendEncounter[];
+    players: PlayerAvoidableTrend[];
+}
+
+export interface TrendEncounter {
+    index: number;
+    stable_id: string;
+    name: string;
+    start_time: string;
+    success: boolean;
+}
+
+export interface PlayerAvoidableTrend {
+    player_name: string;
+    damage_taken: number;
+    avoidable_damage: number;
+    unavoidable_damage: number;
+    avoidable_pct: number;
+    /** Change in avoidable share per encounter, in percentage points */
+    avoidable_pct_slope: number;
+    points: AvoidableTrendPoint[];
+}
+
+export interface AvoidableTrendPoint {
+    /** Position in `encounters` */
+    encounter: number;
+    avoidable_damage: number;
+    unavoidable_damage: number;
+    avoidable_pct: number;
+}
+
+export interface BossMechanicHits {
+    encounter_id: number;
+    name: string;
+    difficulty_name: string;
+    pulls: number;
+    kills: number;
+    spells: MechanicSpellHits[];
+    players: PlayerMechanicHits[];
+}
+
+export interface MechanicSpellHits {
+    spell_id: number;
+    spell_name: string;
+    hits: number;
+    damage: number;
+}
+
+export interface PlayerMechanicHits {
+    player_name: string;
+    hits: number;
+    damage: number;
+    /** Pulls of the boss they were in, and how many of those they got hit in */
+    pulls: number;
+    pulls_hit: number;
+    hits_per_pull: number;
+    spells: MechanicSpellHits[];
+}
+
 export interface SpellTooltip {
     name?: string;
     icon_url?: string;
@@ -209,5 +1264,251 @@ export interface SpellTooltip {
 export interface ReplayData {
     replay_timeline: HpSnapshot[];
     boss_positions: [number, number, number][];
+    enemy_casts: EnemyCast[];
+    mechanic_markers: MechanicMarker[];
+    maps: ReplayMap[];
+    camera: ReplayCamera;
+    avoidable_hits: AvoidableHit[];
     raw_ability_events: [number, string, number, string, number, number, string][];
+    bookmarks: Bookmark[];
+}
+
+/** An enemy's cast, seconds into the fight */
+export interface EnemyCast {
+    start_secs: number;
+    end_secs: number;
+    enemy_name: string;
+    spell_id: number;
+    spell_name: string;
+    outcome: 'success' | 'interrupted' | 'failed' | 'unfinished';
+}
+
+/** An enemy cast without a target (usually a ground effect), where the log placed it */
+export interface MechanicMarker {
+    offset_secs: number;
+    enemy_name: string;
+    spell_id: number;
+    spell_name: string;
+    pos_x: number;
+    pos_y: number;
+}
+
+/** A named point in an encounter to jump the replay to */
+export interface Bookmark {
+    id: number;
+    name: string;
+    /** Seconds from the start of the fight */
+    offset_secs: number;
+    created_at: string;
+}
+
+/** One ability's damage over the fight: [elapsed second, damage] for each second it hit */
+export interface AbilityDamageSeries {
+    spell_id: number;
+    spell_name: string;
+    total: number;
+    buckets: [number, number][];
+}
+
+export interface AbilityTimeline {
+    player_guid: string;
+    player_name: string;
+    duration_secs: number;
+    abilities: AbilityDamageSeries[];
+}
+
+export interface ReplayMap {
+    offset_secs: number;
+    map_id: number;
+    name: string;
+    x_min: number;
+    x_max: number;
+    y_min: number;
+    y_max: number;
+}
+
+/** A view of the replay map in world coordinates (pos_x/pos_y as in the log) */
+export interface ViewBox {
+    min_x: number;
+    max_x: number;
+    min_y: number;
+    max_y: number;
+}
+
+/** Framing for the replay map; `bounds` is null without positions */
+export interface ReplayCamera {
+    bounds: ViewBox | null;
+    presets: CameraPreset[];
+}
+
+export interface CameraPreset {
+    name: string;
+    view: ViewBox;
+    zoom: number;
+    from_secs: number | null;
+    to_secs: number | null;
+}
+
+export interface SpellSearchResult {
+    spell_id: number;
+    spell_name: string;
+    wowhead_url: string;
+    encounters: SpellSearchEncounter[];
+    outside_encounters: number;
+}
+
+export interface SpellSearchEncounter {
+    index: number;
+    stable_id: string;
+    name: string;
+    encounter_type: string;
+    start_time: string;
+    casts: number;
+    damage: number;
+    heals: number;
+    auras: number;
+    hits: SpellHit[];
+}
+
+export interface SpellHit {
+    /** Milliseconds from the start of the encounter */
+    time_ms: number;
+    kind: 'cast' | 'damage' | 'heal' | 'aura';
+    source_name: string;
+    dest_name: string;
+}
+
+export interface SearchHit {
+    stable_id: string;
+    filename: string;
+    name: string;
+    instance_name: string | null;
+    difficulty_name: string;
+    encounter_type: string;
+    start_time: string;
+    success: boolean;
+    matched_players: string[];
+    score: number;
+}
+
+export interface KeyPace {
+    dungeon_name: string;
+    key_level: number;
+    elapsed_secs: number;
+    par_time_secs: number | null;
+    time_remaining_secs: number | null;
+    bosses_killed: number;
+    boss_count: number | null;
+    boss_kill_secs: number[];
+    expected_bosses: number | null;
+    projected_finish_secs: number | null;
+    on_pace: boolean | null;
+}
+
+/** Pushed over /api/live; null when no log is being recorded */
+export interface LiveUpdate {
+    filename: string;
+    /** False when the log is being recorded without advanced combat logging */
+    advanced_logging: boolean;
+    key_pace: KeyPace | null;
+    roster: LiveRosterEntry[];
+    /** The view of the character this client picked */
+    player: LivePlayer | null;
+}
+
+export interface LiveRosterEntry {
+    guid: string;
+    name: string;
+    class_name: string;
+    role: string;
+}
+
+/** One group member's view of the fight in progress */
+export interface LivePlayer {
+    guid: string;
+    name: string;
+    class_name: string;
+    spec_name: string;
+    role: string;
+    last_death: DeathEvent | null;
+    cooldowns: LiveCooldown[];
+}
+
+export interface LiveCooldown {
+    spell_id: number;
+    spell_name: string;
+    category: string;
+    cooldown_secs: number;
+    /** 0 when ready */
+    ready_in_secs: number;
+}
+
+/** Two runs of the same dungeon; deltas are b - a */
+export interface RouteComparison {
+    dungeon_name: string;
+    a: RunInfo;
+    b: RunInfo;
+    sections: SectionDiff[];
+    count_checkpoints: CountCheckpoint[];
+}
+
+export interface RunInfo {
+    stable_id: string;
+    start_time: string;
+    key_level: number | null;
+    duration_secs: number;
+    timed: boolean | null;
+    deaths: number;
+}
+
+export interface SectionDiff {
+    name: string;
+    a: SectionStats | null;
+    b: SectionStats | null;
+    time_delta_secs: number | null;
+    deaths_delta: number | null;
+}
+
+export interface SectionStats {
+    end_secs: number;
+    duration_secs: number;
+    trash_secs: number;
+    boss_secs: number;
+    deaths: number;
+    pulls: number;
+    enemy_forces: number;
+    avg_pull_forces: number;
+    largest_pull_forces: number;
+}
+
+export interface CountCheckpoint {
+    enemy_forces: number;
+    a_secs: number | null;
+    b_secs: number | null;
+    delta_secs: number | null;
+}
+
+/** A full parse the server has queued, is running or recently finished (`/api/jobs`) */
+export interface ParseJob {
+    id: number;
+    filename: string;
+    kind: 'summary' | 'encounter' | 'replay' | 'refresh';
+    status: 'queued' | 'running' | 'done' | 'failed';
+    size_bytes: number;
+    bytes_read: number;
+    progress_pct: number;
+    elapsed_secs: number;
+    memory_estimate_bytes: number;
+    error?: string;
+}
+
+/** Something the user should fix, e.g. a game setting */
+export interface Notice {
+    id: string;
+    title: string;
+    message: string;
+    /** What to do about it */
+    fix: string;
+    /** The log that raised it */
+    filename: string;
 }
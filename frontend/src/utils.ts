@@ -12,6 +12,12 @@ export function formatDuration(secs: number): string {
     return `${m}:${s.toString().padStart(2, '0')}`;
 }
 
+export function formatBytes(bytes: number): string {
+    if (bytes >= 1024 ** 3) return `${(bytes / 1024 ** 3).toFixed(1)} GB`;
+    if (bytes >= 1024 ** 2) return `${(bytes / 1024 ** 2).toFixed(0)} MB`;
+    return `${Math.max(1, Math.round(bytes / 1024))} KB`;
+}
+
 const CLASS_COLORS: Record<string, string> = {
     'Warrior': '#C79C6E',
     'Paladin': '#F58CBA',
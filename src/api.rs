@@ -1,76 +1,739 @@
 use axum::{
-    extract::{Path, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, Request, State,
+    },
     http::StatusCode,
-    response::{Html, Json},
-    routing::{get, post},
+    middleware::{self, Next},
+    response::{Html, Json, Redirect, Response},
+    routing::{delete, get, post},
     Router,
 };
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{Mutex, Notify};
-use std::collections::HashMap;
+use tokio::sync::{mpsc, watch, Mutex, Notify};
+use std::collections::{HashMap, HashSet};
 use rust_embed::Embed;
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
 
-use crate::models::*;
-use crate::parser;
+use fast_wow_parser::models::*;
+use fast_wow_parser::anonymize::{self, Pseudonyms};
+use fast_wow_parser::resolution::Resolution;
+use fast_wow_parser::{auras, compare, export, grouping, log_file, merge, parser, probe, split, trend, AnalysisFeatures, ParseOptions};
+
+use crate::benchmarks::Benchmarks;
+use crate::builds::{self, BuildTimeline};
+use crate::calendar;
+use crate::download;
+use crate::dungeon_stats;
+use crate::fatigue::{self, Fatigue, FatigueQuery};
+use crate::config::{AppConfig, Region};
+use crate::history::{CharacterAlias, History, ParsedLog, SearchHit};
+use crate::instance::InstanceInfo;
+use crate::integrity::{Integrity, IntegrityReport};
+use crate::jobs::{self, JobInfo, Jobs};
+use crate::leaderboard::{self, LeaderboardFilter, Leaderboards};
+use crate::planner::{self, Planner, PlannerQuery};
+use crate::live::LiveUpdate;
+use crate::night_report::{self, NightReport};
+use crate::log_index::{LogIndex, LogIndexReport};
+use crate::notices::{self, Notice, Notices};
+use crate::retention::{Retention, RetentionReport};
+use crate::settings::Settings;
+use crate::startup;
+use crate::summary_cache::SummaryCache;
+use crate::thumbnail::{self, ThumbnailQuery};
+use crate::vault::{self, VaultWeek};
 
 #[derive(Embed)]
 #[folder = "frontend/dist"]
 struct FrontendAssets;
 
 struct AppState {
-    log_dir: Arc<std::sync::Mutex<PathBuf>>,
-    cache: Mutex<HashMap<String, (u64, CombatLogSummary)>>,
+    settings: Arc<Settings>,
+    /// Where each log under the log directory is
+    log_index: Arc<LogIndex>,
+    /// Parses by log and parser schema version
+    cache: Mutex<SummaryCache>,
     shutdown: Arc<Notify>,
+    /// Parse timings accumulated across `?profile=true` requests
+    profile_stats: Mutex<ProfileStats>,
+    config: AppConfig,
+    /// User notes, kept separately from the parse cache
+    history: Mutex<History>,
+    /// Logs being re-parsed in the background
+    refreshing: Mutex<HashSet<String>>,
+    /// Latest snapshot of the log being recorded
+    live: watch::Receiver<Option<LiveUpdate>>,
+    /// Full parses queued, running and recently finished
+    jobs: Arc<Jobs>,
+    /// Quick scans for the log list: filename -> (file size when probed, probe)
+    probes: Mutex<HashMap<String, (u64, LogProbe)>>,
+    /// What the retention policy has done with old logs
+    retention: Arc<Retention>,
+    /// What the startup check found
+    integrity: Arc<Integrity>,
+    /// Things the user should fix, also shown by the GUI
+    notices: Arc<Notices>,
+    /// Typical DPS per spec, for players' `reference_dps`
+    benchmarks: Benchmarks,
+}
+
+/// Let a burst of appends settle before re-parsing a changed log
+const REFRESH_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How often history changes a save couldn't write are retried
+const HISTORY_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[derive(Default, Clone, Serialize)]
+struct ProfileStats {
+    parses: u32,
+    totals: ParseProfile,
 }
 
-pub fn create_router(log_dir: Arc<std::sync::Mutex<PathBuf>>, shutdown: Arc<Notify>) -> Router {
+pub fn create_router(
+    settings: Arc<Settings>,
+    shutdown: Arc<Notify>,
+    config: AppConfig,
+    jobs: Arc<Jobs>,
+    retention: Arc<Retention>,
+    integrity: Arc<Integrity>,
+    notices: Arc<Notices>,
+) -> Router {
+    let log_index = Arc::new(startup::time("log index", || LogIndex::build(&settings.log_dir())));
+    let live = crate::live::spawn(log_index.clone(), config.private_players.clone());
+    let mut history = startup::time("history load", History::load);
+    let report = startup::time("integrity check", || integrity.check(&mut history, &settings.log_dir()));
+    if startup::enabled() {
+        startup::time("embedded assets", || {
+            FrontendAssets::iter().filter_map(|path| FrontendAssets::get(&path)).map(|file| file.data.len()).sum::<usize>()
+        });
+    }
     let state = Arc::new(AppState {
-        log_dir,
-        cache: Mutex::new(HashMap::new()),
+        settings,
+        log_index,
+        cache: Mutex::new(SummaryCache::default()),
         shutdown,
+        profile_stats: Mutex::new(ProfileStats::default()),
+        config,
+        history: Mutex::new(history),
+        refreshing: Mutex::new(HashSet::new()),
+        live,
+        jobs,
+        probes: Mutex::new(HashMap::new()),
+        retention,
+        integrity,
+        notices,
+        benchmarks: Benchmarks::load(),
     });
 
+    let changes = crate::watcher::spawn(state.settings.subscribe_log_dir());
+    tokio::spawn(warm_cache_on_change(state.clone(), changes));
+    tokio::spawn(forget_old_log_dir(state.clone(), state.settings.subscribe_log_dir()));
+    if state.config.retention.enabled {
+        tokio::spawn(apply_retention(state.clone()));
+    }
+    tokio::spawn(flush_history(state.clone()));
+    if let Some(dir) = &state.config.frontend_dir {
+        tracing::info!("Serving the frontend from {} (dev mode)", dir.display());
+    }
+    tokio::spawn(check_live_log(state.clone()));
+    if state.config.night_report.enabled {
+        tokio::spawn(schedule_night_report(state.clone()));
+    }
+    if state.config.benchmarks_url.is_some() {
+        tokio::spawn(refresh_benchmarks(state.clone()));
+    }
+    // A log whose recording outlived the app may be missing its last encounters
+    let interrupted = crate::live::interrupted_log(&state.log_index);
+    for filename in report.reindex.into_iter().chain(interrupted) {
+        match state.log_index.find(&filename) {
+            Some(path) => spawn_refresh(state.clone(), filename, path),
+            None => tracing::warn!(filename = %filename, "outdated log not found for re-indexing"),
+        }
+    }
+
     Router::new()
         .route("/logo.png", get(serve_logo))
         .route("/favicon.png", get(serve_favicon))
         .route("/api/logs", get(list_logs))
+        .route("/api/logs/merge", post(merge_logs))
+        .route("/api/logs/{filename}/favorite", post(add_favorite).delete(remove_favorite))
         .route("/api/logs/{filename}/summary", get(log_summary))
+        .route("/api/logs/{filename}/grouped", get(log_grouped))
+        .route("/api/logs/{filename}/avoidable_trend", get(log_avoidable_trend))
+        .route("/api/logs/{filename}/mechanic_hits", get(log_mechanic_hits))
+        .route("/api/logs/{filename}/xlsx", get(log_xlsx))
+        .route("/api/logs/{filename}/split", post(split_log))
         .route("/api/logs/{filename}/encounter/{index}", get(encounter_detail))
         .route("/api/logs/{filename}/encounter/{index}/replay", get(encounter_replay))
+        .route("/api/logs/{filename}/encounter/{index}/abilities", get(encounter_ability_timeline))
+        .route("/api/logs/{filename}/encounter/{index}/auras", get(encounter_aura_snapshot))
+        .route("/api/logs/{filename}/encounter/{index}/aura_lanes", get(encounter_aura_lanes))
+        .route("/api/logs/{filename}/encounter/{index}/threat", get(encounter_threat_review))
+        .route("/api/logs/{filename}/encounter/{index}/notes", post(save_encounter_notes))
+        .route("/api/logs/{filename}/encounter/{index}/bookmarks", get(list_bookmarks).post(add_bookmark))
+        .route("/api/logs/{filename}/encounter/{index}/bookmarks/{id}", delete(remove_bookmark))
+        .route("/api/logs/{filename}/encounter/{index}/export", get(encounter_export))
+        .route("/api/logs/{filename}/encounter/{index}/xlsx", get(encounter_xlsx))
+        .route("/api/logs/{filename}/encounter/{index}/thumbnail.png", get(encounter_thumbnail))
+        .route("/api/live", get(live_socket))
+        .route("/api/jobs", get(list_jobs))
+        .route("/api/compare", get(compare_runs))
+        .route("/api/search", get(search_encounters))
+        .route("/api/search/spell", get(search_spell))
+        .route("/api/vault", get(vault_progress))
+        .route("/api/leaderboards", get(local_leaderboards))
+        .route("/api/planner", get(roster_planner))
+        .route("/api/fatigue", get(raid_fatigue))
+        .route("/api/dungeons/{zone_id}/stats", get(dungeon_stats))
+        .route("/api/characters/aliases", get(list_character_aliases).post(set_character_alias))
+        .route("/api/characters/aliases/{alias}", delete(remove_character_alias))
+        .route("/api/characters/{character}/builds", get(character_builds))
+        .route("/api/calendar.ics", get(session_calendar))
         .route("/api/spell_tooltips", get(serve_spell_tooltips))
+        .route("/api/schema", get(summary_schema))
+        .route("/e/{stable_id}", get(encounter_short_link))
+        .merge(admin_routes())
+        .route("/api/instance", get(instance_info))
+        .route("/api/notices", get(list_notices))
+        .route("/api/benchmarks", get(spec_benchmarks))
+        .route("/api/notices/{id}", delete(dismiss_notice))
+        .route("/api/notices/advanced_logging/config.wtf", get(advanced_logging_snippet))
         .fallback(get(embedded_frontend))
+        .layer(middleware::from_fn(tag_client))
+        .layer(middleware::from_fn(request_span))
         .with_state(state)
 }
 
-/// Serve embedded frontend assets, with SPA fallback to index.html
-async fn embedded_frontend(uri: axum::http::Uri) -> impl axum::response::IntoResponse {
+/// Re-parse cached logs in the background when they change (a split, a restored file), so
+/// the next request doesn't pay for it. The log WoW is writing is left to the requests.
+async fn warm_cache_on_change(state: Arc<AppState>, mut changes: mpsc::Receiver<PathBuf>) {
+    while let Some(path) = changes.recv().await {
+        state.log_index.note_change(&path);
+        tokio::time::sleep(REFRESH_DEBOUNCE).await;
+        let mut changed = HashSet::from([path]);
+        while let Ok(path) = changes.try_recv() {
+            state.log_index.note_change(&path);
+            changed.insert(path);
+        }
+        for path in changed {
+            let Some(filename) = path.file_name().and_then(|n| n.to_str()).map(|n| n.to_string()) else {
+                continue;
+            };
+            let Ok(metadata) = log_metadata(&path).await else { continue };
+            // A log WoW is still writing would be re-parsed in full every few seconds;
+            // it's refreshed when someone opens it
+            if log_file::is_active(&metadata) {
+                continue;
+            }
+            let current_size = metadata.len();
+            let stale = state.cache.lock().await
+                .get(&filename)
+                .is_some_and(|(cached_size, _)| *cached_size != current_size);
+            if stale {
+                spawn_refresh(state.clone(), filename, path);
+            }
+        }
+    }
+}
+
+/// Drop the parses and probes of the old folder's logs when the GUI switches log directories,
+/// so a log of the same name in the new folder isn't served from the cache
+async fn forget_old_log_dir(state: Arc<AppState>, mut log_dir: watch::Receiver<PathBuf>) {
+    while log_dir.changed().await.is_ok() {
+        let dir = log_dir.borrow_and_update().clone();
+        let cached = {
+            let mut cache = state.cache.lock().await;
+            let cached = cache.len();
+            cache.clear();
+            cached
+        };
+        state.probes.lock().await.clear();
+        tracing::info!(dir = %dir.display(), cached, "log directory changed, parse cache cleared");
+        let indexing = state.clone();
+        if let Err(e) = tokio::task::spawn_blocking(move || indexing.log_index.rebuild(&dir)).await {
+            tracing::error!("log index rebuild failed: {}", e);
+        }
+    }
+}
+
+/// Retry history saves that failed, every `HISTORY_FLUSH_INTERVAL`, so changes kept only in
+/// memory don't wait for the next change to reach the disk
+async fn flush_history(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(HISTORY_FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = state.history.lock().await.flush() {
+            tracing::warn!("history still not saved: {}", e);
+        }
+    }
+}
+
+/// Fetch the spec benchmark table from `benchmarks_url` every `benchmarks::REFRESH_INTERVAL`.
+/// A newer one is applied to the parses in the cache as well.
+async fn refresh_benchmarks(state: Arc<AppState>) {
+    let Some(url) = state.config.benchmarks_url.clone() else {
+        return;
+    };
+    let mut interval = tokio::time::interval(crate::benchmarks::REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        match state.benchmarks.refresh(&url).await {
+            Ok(true) => {
+                let table = state.benchmarks.table();
+                for (_, summary) in state.cache.lock().await.values_mut() {
+                    table.annotate(&mut summary.encounters);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("spec benchmarks not refreshed: {}", e),
+        }
+    }
+}
+
+/// Run the end-of-night report every day at the configured time
+async fn schedule_night_report(state: Arc<AppState>) {
+    let Ok(at) = chrono::NaiveTime::parse_from_str(&state.config.night_report.at, "%H:%M") else {
+        tracing::warn!("night report not scheduled: \"{}\" isn't a time like 04:00", state.config.night_report.at);
+        return;
+    };
+    loop {
+        let now = chrono::Local::now();
+        let next = night_report::next_run(at, now);
+        tracing::info!(at = %next, "next night report");
+        tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+        match run_night_report(&state).await {
+            Ok(Some(_)) => {}
+            Ok(None) => tracing::info!("no boss pulls or keys for the night report"),
+            Err(e) => tracing::warn!("night report failed: {}", e),
+        }
+    }
+}
+
+/// Parse the logs written in the last night, write the report of its pulls and keys as an HTML
+/// page, and post it to the Discord webhook if one is set. `None` for a night without any.
+async fn run_night_report(state: &AppState) -> Result<Option<NightReport>, String> {
+    let now = chrono::Utc::now();
+    let since = std::time::SystemTime::now() - night_report::NIGHT_LENGTH.to_std().unwrap_or_default();
+    for (filename, path) in state.log_index.logs() {
+        let written = log_metadata(&path).await.and_then(|m| m.modified()).ok();
+        if written.is_none_or(|written| written < since) {
+            continue;
+        }
+        if let Err((_, e)) = ensure_cached(state, &filename, path).await {
+            tracing::warn!(filename = %filename, "left out of the night report: {}", e);
+        }
+    }
+
+    let encounters: Vec<EncounterSummary> = {
+        let cache = state.cache.lock().await;
+        let mut seen = HashSet::new();
+        cache.values()
+            .flat_map(|(_, summary)| &summary.encounters)
+            .filter(|enc| night_report::in_night(enc, now) && !state.config.trivial.is_trivial(enc))
+            // A merged or split log has the same encounters as the one it came from
+            .filter(|enc| seen.insert(enc.stable_id.clone()))
+            .cloned()
+            .collect()
+    };
+    let Some(mut report) = night_report::build(&encounters, &state.config.private_players, now) else {
+        return Ok(None);
+    };
+
+    let path = night_report::html_path(&report.date);
+    let html = report.html();
+    let written = {
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || crate::history::write_durably(&path, html.as_bytes()))
+            .await
+            .map_err(|e| format!("Task failed: {}", e))?
+    };
+    written.map_err(|e| format!("Couldn't save {}: {}", path.display(), e))?;
+    report.html_path = Some(path.display().to_string());
+    tracing::info!(date = %report.date, pulls = report.pulls, keys = report.keys.len(), path = %path.display(), "night report written");
+
+    if let Some(webhook) = &state.config.night_report.discord_webhook {
+        match night_report::post_to_discord(webhook, &report.discord_text()).await {
+            Ok(()) => tracing::info!(date = %report.date, "night report posted to Discord"),
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    Ok(Some(report))
+}
+
+/// Delete old logs per the retention policy, every `retention::CHECK_INTERVAL`.
+/// Handled logs leave the cache and the log list.
+async fn apply_retention(state: Arc<AppState>) {
+    tokio::time::sleep(crate::retention::STARTUP_DELAY).await;
+    let mut interval = tokio::time::interval(crate::retention::CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let (parsed, favorites): (HashMap<String, ParsedLog>, HashSet<String>) = {
+            let history = state.history.lock().await;
+            (history.parsed_logs(), history.favorites().iter().cloned().collect())
+        };
+        let running = state.clone();
+        let handled = match tokio::task::spawn_blocking(move || {
+            running.retention.run(&running.log_index, &running.config.retention, &parsed, &favorites)
+        }).await {
+            Ok(handled) => handled,
+            Err(e) => {
+                tracing::error!("retention task failed: {}", e);
+                continue;
+            }
+        };
+        if !handled.is_empty() {
+            let mut cache = state.cache.lock().await;
+            let mut probes = state.probes.lock().await;
+            for entry in &handled {
+                cache.remove(&entry.filename);
+                probes.remove(&entry.filename);
+                state.log_index.remove(&entry.filename);
+            }
+        }
+    }
+}
+
+/// Re-parse a log with the configured options and replace its cache entry.
+/// Does nothing if a refresh of that log is already running.
+fn spawn_refresh(state: Arc<AppState>, filename: String, path: PathBuf) {
+    tokio::spawn(async move {
+        if !state.refreshing.lock().await.insert(filename.clone()) {
+            return;
+        }
+        tracing::info!(filename = %filename, "refreshing summary in the background");
+        // In its own task, so the flag is cleared however the refresh ends, a panic included
+        let refresh = tokio::spawn({
+            let (state, filename) = (state.clone(), filename.clone());
+            async move {
+                let size = log_metadata(&path).await.map(|m| m.len()).unwrap_or(0);
+                let options = state.config.parse.clone();
+                let start = std::time::Instant::now();
+                let summary = parse_job(&state, &filename, "refresh", path, options).await?;
+                tracing::info!(filename = %filename, elapsed_secs = start.elapsed().as_secs_f64(), "refreshed summary");
+                cache_summary(&state, filename, size, summary).await;
+                Ok::<(), String>(())
+            }
+        });
+        match refresh.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::error!(filename = %filename, "background parse failed: {}", e),
+            Err(e) => tracing::error!(filename = %filename, "background parse crashed: {}", e),
+        }
+        state.refreshing.lock().await.remove(&filename);
+    });
+}
+
+/// Parse a whole log with `options` through the job queue
+async fn parse_job(state: &AppState, filename: &str, kind: &'static str, path: PathBuf, options: ParseOptions) -> Result<CombatLogSummary, String> {
+    let metadata = log_metadata(&path).await.ok();
+    let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+    let started = std::time::Instant::now();
+    let summary = state.jobs.run(filename, kind, size, move |progress| {
+        parser::parse_combat_log_with_progress(&path, options, false, progress)
+    }).await?;
+    startup::parse_finished(started.elapsed());
+    if let Some(written) = metadata.and_then(|m| m.modified().ok()) {
+        report_advanced_logging(state, filename, written, summary.advanced_logging);
+    }
+    Ok(summary)
+}
+
+/// Raise or clear the advanced combat logging notice from a log written at `written`
+fn report_advanced_logging(state: &AppState, filename: &str, written: std::time::SystemTime, advanced_logging: bool) {
+    let notice = (!advanced_logging).then(|| Notice::advanced_logging_off(filename));
+    state.notices.report(notices::ADVANCED_LOGGING, written, notice);
+}
+
+/// Check the log being recorded, so the notice comes up while there's still time to fix the
+/// setting before the pull
+async fn check_live_log(state: Arc<AppState>) {
+    let mut live = state.live.clone();
+    while live.changed().await.is_ok() {
+        let Some((filename, advanced_logging)) = live.borrow_and_update().as_ref()
+            .map(|update| (update.filename.clone(), update.advanced_logging))
+        else {
+            continue;
+        };
+        report_advanced_logging(&state, &filename, std::time::SystemTime::now(), advanced_logging);
+    }
+}
+
+/// The spec benchmark table in use
+async fn spec_benchmarks(State(state): State<Arc<AppState>>) -> Json<fast_wow_parser::benchmarks::BenchmarkTable> {
+    Json(state.benchmarks.table().as_ref().clone())
+}
+
+/// Notices up, for the web app's banner
+async fn list_notices(State(state): State<Arc<AppState>>) -> Json<Vec<Notice>> {
+    Json(state.notices.list())
+}
+
+async fn dismiss_notice(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> StatusCode {
+    if state.notices.dismiss(&id) { StatusCode::NO_CONTENT } else { StatusCode::NOT_FOUND }
+}
+
+/// A `Config.wtf` snippet that switches advanced combat logging on, to paste into
+/// `WTF\Config.wtf` while the game is closed
+async fn advanced_logging_snippet() -> impl axum::response::IntoResponse {
+    let headers = [
+        (axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+        (axum::http::header::CONTENT_DISPOSITION, "attachment; filename=\"advanced_logging.wtf\""),
+    ];
+    (headers, format!("{}\n", notices::ADVANCED_LOGGING_CVAR))
+}
+
+/// Parses queued, running and recently finished, newest first
+async fn list_jobs(State(state): State<Arc<AppState>>) -> Json<Vec<JobInfo>> {
+    Json(state.jobs.list())
+}
+
+/// Cache a parse and index its encounters in the history DB so short links can find them,
+/// and fill in what needs the history (performance deltas, depletion report baselines)
+async fn cache_summary(state: &AppState, filename: String, size: u64, mut summary: CombatLogSummary) {
+    // The log as it was parsed, for the retention policy to leave it alone once it grows;
+    // unknown if it has grown already
+    let parsed = match state.log_index.find(&filename) {
+        Some(path) => log_metadata(&path).await.ok().and_then(|m| ParsedLog::of(&m)).filter(|p| p.size == size),
+        None => None,
+    };
+    {
+        let mut history = state.history.lock().await;
+        if let Err(e) = history.record_encounters(&filename, parsed, &summary.encounters) {
+            tracing::warn!(filename = %filename, "failed to record encounters in history: {}", e);
+        }
+        history.annotate(&mut summary.encounters);
+    }
+    state.benchmarks.table().annotate(&mut summary.encounters);
+    state.cache.lock().await.insert(&filename, size, summary);
+}
+
+/// Resolve an `/e/<stable id>` short link to the encounter page of the log it came from
+async fn encounter_short_link(
+    State(state): State<Arc<AppState>>,
+    Path(stable_id): Path<String>,
+) -> Result<Redirect, (StatusCode, String)> {
+    let stable_id = stable_id.to_ascii_lowercase();
+    let filename = state.history.lock().await
+        .encounter_file(&stable_id)
+        .map(|f| f.to_string())
+        .ok_or((StatusCode::NOT_FOUND, "Unknown encounter link".to_string()))?;
+    tracing::debug!(filename = %filename, stable_id = %stable_id, "resolved short link");
+    Ok(Redirect::to(&format!("/log/{}/encounter/{}", download::encode_path_segment(&filename), stable_id)))
+}
+
+/// Live mode WebSocket: sends the current snapshot as JSON on connect and after every change.
+/// A client picks its character by sending `{"player": "<guid>"}` (`null` to go back to the
+/// shared view) and from then on also gets that character's view.
+async fn live_socket(State(state): State<Arc<AppState>>, ws: WebSocketUpgrade) -> Response {
+    let updates = state.live.clone();
+    ws.on_upgrade(move |socket| send_live_updates(socket, updates))
+}
+
+#[derive(Deserialize)]
+struct LiveSelection {
+    player: Option<String>,
+}
+
+async fn send_live_updates(mut socket: WebSocket, mut updates: watch::Receiver<Option<LiveUpdate>>) {
+    tracing::debug!("live client connected");
+    let mut player: Option<String> = None;
+    'session: loop {
+        let json = {
+            let update = updates.borrow_and_update();
+            let message = update.as_ref().map(|u| u.for_player(player.as_deref()));
+            serde_json::to_string(&message).unwrap_or_default()
+        };
+        if socket.send(Message::Text(json.into())).await.is_err() {
+            break;
+        }
+        loop {
+            tokio::select! {
+                changed = updates.changed() => match changed {
+                    Ok(()) => break,
+                    Err(_) => break 'session,
+                },
+                // Clients only pick their character; anything else but a close or error is ignored
+                msg = socket.recv() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Ok(selection) = serde_json::from_str::<LiveSelection>(&text) {
+                            player = selection.player;
+                            break;
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    _ => break 'session,
+                },
+            }
+        }
+    }
+    tracing::debug!("live client disconnected");
+}
+
+/// Wrap each request in a span and log its status and duration
+async fn request_span(req: Request, next: Next) -> Response {
+    let span = tracing::info_span!("request", method = %req.method(), path = %req.uri().path());
+    async move {
+        let start = std::time::Instant::now();
+        let response = next.run(req).await;
+        tracing::info!(
+            status = response.status().as_u16(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "request finished"
+        );
+        response
+    }
+    .instrument(span)
+    .await
+}
+
+/// Diagnostics and actions for the person running the app. They expose file paths and
+/// request details and can start parses and webhook posts, so they only answer the local
+/// machine (see `local_only`), even when the server is bound to every interface.
+fn admin_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/admin/logs", get(admin_logs))
+        .route("/api/admin/profile", get(admin_profile))
+        .route("/api/admin/unknown_events", get(admin_unknown_events))
+        .route("/api/admin/retention", get(admin_retention))
+        .route("/api/admin/integrity", get(admin_integrity))
+        .route("/api/admin/log_index", get(admin_log_index))
+        .route("/api/admin/night_report", post(admin_night_report))
+        .route_layer(middleware::from_fn(local_only))
+}
+
+/// 403 for requests from other machines. Needs the router served with
+/// `ConnectInfo<SocketAddr>`; without it every request is refused.
+async fn local_only(req: Request, next: Next) -> Result<Response, StatusCode> {
+    let local = req.extensions().get::<ConnectInfo<SocketAddr>>()
+        .is_some_and(|ConnectInfo(addr)| addr.ip().is_loopback());
+    if !local {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(next.run(req).await)
+}
+
+/// Run each request as its client's, for the parse queue to take turns between clients. Needs
+/// the router served with `ConnectInfo<SocketAddr>`; without it every request is the
+/// background's.
+async fn tag_client(req: Request, next: Next) -> Response {
+    let client = req.extensions().get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| jobs::BACKGROUND_CLIENT.to_string());
+    jobs::for_client(client, next.run(req)).await
+}
+
+#[derive(Deserialize)]
+struct LogTailQuery {
+    lines: Option<usize>,
+}
+
+/// Last N lines of the app's own log file (default 200), for debugging user reports
+async fn admin_logs(Query(query): Query<LogTailQuery>) -> Json<Vec<String>> {
+    let lines = query.lines.unwrap_or(200).min(5000);
+    Json(crate::logging::tail(lines))
+}
+
+/// Content-Security-Policy of the web app: its own scripts (and the inline handlers of the
+/// template-rendered tabs), inline styles, the Google font, spell icons from any HTTPS host and
+/// the live-mode WebSocket
+const CONTENT_SECURITY_POLICY: &str = "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+    style-src 'self' 'unsafe-inline' https://fonts.googleapis.com; font-src 'self' https://fonts.gstatic.com; \
+    img-src 'self' data: https:; connect-src 'self' ws: wss:; object-src 'none'; base-uri 'self'; frame-ancestors 'self'";
+
+/// Serve embedded frontend assets, with SPA fallback to index.html. With `frontend_dir` set,
+/// files there come first and aren't cached, so a frontend rebuild shows on reload.
+async fn embedded_frontend(
+    State(state): State<Arc<AppState>>,
+    uri: axum::http::Uri,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    use axum::response::IntoResponse;
     let path = uri.path().trim_start_matches('/');
+    let dev_dir = state.config.frontend_dir.as_deref();
 
     // Try to serve the exact file
+    if let Some(data) = read_frontend_file(dev_dir, path).await {
+        return frontend_response(path, data, None, "no-store");
+    }
     if let Some(file) = FrontendAssets::get(path) {
-        let mime = mime_guess::from_path(path).first_or_octet_stream();
-        return (
-            StatusCode::OK,
-            [(axum::http::header::CONTENT_TYPE, mime.as_ref().to_string())],
-            file.data.to_vec(),
-        );
+        return embedded_response(path, file, &headers);
     }
 
     // SPA fallback: serve index.html for any unmatched route
+    if let Some(data) = read_frontend_file(dev_dir, "index.html").await {
+        return frontend_response("index.html", data, None, "no-store");
+    }
     if let Some(index) = FrontendAssets::get("index.html") {
-        return (
-            StatusCode::OK,
-            [(axum::http::header::CONTENT_TYPE, "text/html".to_string())],
-            index.data.to_vec(),
-        );
+        return embedded_response("index.html", index, &headers);
     }
 
     (
         StatusCode::NOT_FOUND,
         [(axum::http::header::CONTENT_TYPE, "text/plain".to_string())],
         b"Not Found".to_vec(),
-    )
+    ).into_response()
+}
+
+/// `path` under the frontend dev folder, if one is set and has it; paths leading out of the
+/// folder are refused
+async fn read_frontend_file(dir: Option<&std::path::Path>, path: &str) -> Option<Vec<u8>> {
+    let dir = dir?;
+    let relative = std::path::Path::new(path);
+    if path.is_empty() || !relative.components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+        return None;
+    }
+    tokio::fs::read(dir.join(relative)).await.ok()
+}
+
+/// How long the browser may keep a built-in file: Vite's `assets/` have the content hash in
+/// their name, so they never change; everything else, index.html first, is checked each time
+fn frontend_cache_control(path: &str) -> &'static str {
+    if path.starts_with("assets/") { "public, max-age=31536000, immutable" } else { "no-cache" }
+}
+
+/// A built-in file, or 304 Not Modified when the browser's copy (`If-None-Match`) is current
+fn embedded_response(path: &str, file: rust_embed::EmbeddedFile, request: &axum::http::HeaderMap) -> Response {
+    use axum::response::IntoResponse;
+    let hash: String = file.metadata.sha256_hash()[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    let etag = format!("\"{}\"", hash);
+    let cache_control = frontend_cache_control(path);
+    let current = request.get(axum::http::header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"));
+    if current {
+        let headers = [
+            (axum::http::header::ETAG, etag),
+            (axum::http::header::CACHE_CONTROL, cache_control.to_string()),
+        ];
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+    frontend_response(path, file.data.into_owned(), Some(etag), cache_control)
+}
+
+/// A frontend file with its content type, caching and the security headers
+fn frontend_response(path: &str, data: Vec<u8>, etag: Option<String>, cache_control: &str) -> Response {
+    use axum::http::{header, HeaderValue};
+    use axum::response::IntoResponse;
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let headers = [
+        (header::CONTENT_TYPE, mime.as_ref().to_string()),
+        (header::CACHE_CONTROL, cache_control.to_string()),
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        (header::REFERRER_POLICY, "no-referrer".to_string()),
+        (header::X_FRAME_OPTIONS, "SAMEORIGIN".to_string()),
+    ];
+    let mut response = (StatusCode::OK, headers, data).into_response();
+    if mime.essence_str() == "text/html" {
+        response.headers_mut().insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static(CONTENT_SECURITY_POLICY));
+    }
+    if let Some(etag) = etag.and_then(|etag| HeaderValue::from_str(&etag).ok()) {
+        response.headers_mut().insert(header::ETAG, etag);
+    }
+    response
 }
 
 async fn serve_logo() -> impl axum::response::IntoResponse {
@@ -86,198 +749,821 @@ async fn serve_spell_tooltips() -> impl axum::response::IntoResponse {
     ([(axum::http::header::CONTENT_TYPE, "application/json")], json)
 }
 
+/// JSON Schema of the log summary and the encounters and players in it
+async fn summary_schema() -> Json<serde_json::Value> {
+    Json(fast_wow_parser::summary_schema())
+}
+
 async fn list_logs(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<LogFileInfo>>, (StatusCode, String)> {
-    let dir = state.log_dir.lock().unwrap().clone();
-
     let mut logs: Vec<LogFileInfo> = Vec::new();
-    let mut dirs_to_scan = vec![dir];
-
-    while let Some(scan_dir) = dirs_to_scan.pop() {
-        let entries = match std::fs::read_dir(&scan_dir) {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                dirs_to_scan.push(path);
+    let mut paths: HashMap<String, PathBuf> = HashMap::new();
+    for (filename, path) in state.log_index.logs() {
+        let metadata = match log_metadata(&path).await {
+            Ok(metadata) => Some(metadata),
+            // Deleted since it was indexed
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                state.log_index.remove(&filename);
                 continue;
             }
-            if path.extension().and_then(|e| e.to_str()) == Some("txt") {
-                if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                    if filename.starts_with("WoWCombatLog") {
-                        let metadata = std::fs::metadata(&path).ok();
-                        let size_bytes = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-
-                        logs.push(LogFileInfo {
-                            filename: filename.to_string(),
-                            size_bytes,
-                            size_display: format_size(size_bytes),
-                            date_str: extract_date_from_filename(filename),
-                        });
-                    }
-                }
-            }
-        }
+            Err(_) => None,
+        };
+        let size_bytes = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let active = metadata.as_ref().is_some_and(log_file::is_active);
+
+        logs.push(LogFileInfo {
+            filename: filename.clone(),
+            size_bytes,
+            size_display: format_size(size_bytes),
+            date_str: extract_date_from_filename(&filename),
+            active,
+            favorite: false,
+            probe: None,
+        });
+        paths.insert(filename, path);
     }
 
     // Sort by actual date (convert MMDDYY_HHMMSS to YYMMDD_HHMMSS for correct chronological order)
     logs.sort_by(|a, b| {
         let key = |f: &str| -> String {
             let name = f.trim_start_matches("WoWCombatLog-").trim_end_matches(".txt");
-            if name.len() >= 6 {
-                format!("{}{}{}{}", &name[4..6], &name[0..2], &name[2..4], &name[6..])
-            } else {
-                name.to_string()
+            match (name.get(0..2), name.get(2..4), name.get(4..6), name.get(6..)) {
+                (Some(month), Some(day), Some(year), Some(rest)) => format!("{}{}{}{}", year, month, day, rest),
+                _ => name.to_string(),
             }
         };
         key(&b.filename).cmp(&key(&a.filename))
     });
     logs.dedup_by(|a, b| a.filename == b.filename);
+    {
+        let history = state.history.lock().await;
+        for log in &mut logs {
+            log.favorite = history.is_favorite(&log.filename);
+        }
+    }
 
+    attach_probes(&state, &mut logs, paths).await;
     Ok(Json(logs))
 }
 
+/// Fill in each log's probe, scanning the ones that are new or have grown since
+async fn attach_probes(state: &AppState, logs: &mut [LogFileInfo], mut paths: HashMap<String, PathBuf>) {
+    let mut probes = state.probes.lock().await;
+    let stale: Vec<(String, u64, PathBuf)> = logs.iter()
+        .filter(|log| probes.get(&log.filename).is_none_or(|(size, _)| *size != log.size_bytes))
+        .filter_map(|log| Some((log.filename.clone(), log.size_bytes, paths.remove(&log.filename)?)))
+        .collect();
+    if !stale.is_empty() {
+        let start = std::time::Instant::now();
+        let count = stale.len();
+        let scanned = tokio::task::spawn_blocking(move || {
+            stale.into_iter()
+                .filter_map(|(filename, size, path)| match probe::probe_log(&path) {
+                    Ok(probe) => Some((filename, (size, probe))),
+                    Err(e) => {
+                        tracing::warn!(filename = %filename, "probe failed: {}", e);
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        }).await;
+        match scanned {
+            Ok(scanned) => probes.extend(scanned),
+            Err(e) => tracing::error!("probe task failed: {}", e),
+        }
+        tracing::debug!(files = count, elapsed_secs = start.elapsed().as_secs_f64(), "probed logs");
+    }
+    for log in logs {
+        log.probe = probes.get(&log.filename).map(|(_, probe)| probe.clone());
+    }
+}
+
+/// Parse timings accumulated across profiled parses
+async fn admin_profile(State(state): State<Arc<AppState>>) -> Json<ProfileStats> {
+    Json(state.profile_stats.lock().await.clone())
+}
+
+/// What the retention policy has deleted since startup
+async fn admin_retention(State(state): State<Arc<AppState>>) -> Json<RetentionReport> {
+    let mut report = state.retention.report();
+    report.enabled = state.config.retention.enabled;
+    Json(report)
+}
+
+/// What the startup check of the history and Logs folder repaired and found
+async fn admin_integrity(State(state): State<Arc<AppState>>) -> Json<Option<IntegrityReport>> {
+    Json(state.integrity.report())
+}
+
+/// The log directory index, with the folders and files it couldn't read
+async fn admin_log_index(State(state): State<Arc<AppState>>) -> Json<LogIndexReport> {
+    Json(state.log_index.report())
+}
+
+/// Run the end-of-night report now, as the schedule would (saving it, and posting it if a
+/// webhook is set); `null` when the last 24 hours have no boss pulls or keys
+async fn admin_night_report(State(state): State<Arc<AppState>>) -> Result<Json<Option<NightReport>>, (StatusCode, String)> {
+    run_night_report(&state).await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Identifies this app to a second launch looking for a running instance
+async fn instance_info() -> Json<InstanceInfo> {
+    Json(crate::instance::info())
+}
+
+/// Event types the parser didn't recognize, across every cached log, most frequent first
+async fn admin_unknown_events(State(state): State<Arc<AppState>>) -> Json<Vec<UnknownEvent>> {
+    let mut merged: Vec<UnknownEvent> = Vec::new();
+    for (_, summary) in state.cache.lock().await.values() {
+        for unknown in &summary.stats.unknown_events {
+            match merged.iter_mut().find(|m| m.event_type == unknown.event_type) {
+                Some(m) => {
+                    m.count += unknown.count;
+                    let room = parser::MAX_UNKNOWN_SAMPLES.saturating_sub(m.samples.len());
+                    m.samples.extend(unknown.samples.iter().take(room).cloned());
+                }
+                None => merged.push(unknown.clone()),
+            }
+        }
+    }
+    merged.sort_by_key(|m| std::cmp::Reverse(m.count));
+    Json(merged)
+}
+
+/// Per-request overrides of the configured parse options
+#[derive(Deserialize)]
+struct ParseOverrides {
+    recap_window_secs: Option<f64>,
+    recap_max_events: Option<usize>,
+    /// Comma-separated NPC IDs, e.g. `priority_npcs=204773,214350`
+    priority_npcs: Option<String>,
+    pre_pull_secs: Option<f64>,
+    post_pull_secs: Option<f64>,
+    taunt_stack_threshold: Option<u32>,
+    /// Comma-separated spell IDs, e.g. `avoidable_spells=424888,426860`
+    avoidable_spells: Option<String>,
+    /// Comma-separated spell IDs, e.g. `dangerous_spells=445936`
+    dangerous_spells: Option<String>,
+    major_hit_pct: Option<f64>,
+    trash_area_names: Option<bool>,
+    /// Comma-separated analysis features to switch off, e.g. `disable=replay,buff_timelines`
+    disable: Option<String>,
+    /// Comma-separated analysis features to switch on that `config.json` turned off
+    enable: Option<String>,
+}
+
+impl ParseOverrides {
+    fn apply(&self, base: &ParseOptions) -> ParseOptions {
+        ParseOptions {
+            recap_window_secs: self.recap_window_secs.unwrap_or(base.recap_window_secs),
+            recap_max_events: self.recap_max_events.unwrap_or(base.recap_max_events),
+            priority_npc_ids: match &self.priority_npcs {
+                Some(ids) => ids.split(',').filter_map(|id| id.trim().parse().ok()).collect(),
+                None => base.priority_npc_ids.clone(),
+            },
+            pre_pull_secs: self.pre_pull_secs.unwrap_or(base.pre_pull_secs),
+            post_pull_secs: self.post_pull_secs.unwrap_or(base.post_pull_secs),
+            taunt_stack_threshold: self.taunt_stack_threshold.unwrap_or(base.taunt_stack_threshold),
+            avoidable_spell_ids: match &self.avoidable_spells {
+                Some(ids) => ids.split(',').filter_map(|id| id.trim().parse().ok()).collect(),
+                None => base.avoidable_spell_ids.clone(),
+            },
+            proc_spell_ids: base.proc_spell_ids.clone(),
+            crowd_control_spell_ids: base.crowd_control_spell_ids.clone(),
+            dangerous_spell_ids: match &self.dangerous_spells {
+                Some(ids) => ids.split(',').filter_map(|id| id.trim().parse().ok()).collect(),
+                None => base.dangerous_spell_ids.clone(),
+            },
+            major_hit_pct: self.major_hit_pct.unwrap_or(base.major_hit_pct),
+            training_dummy_npc_ids: base.training_dummy_npc_ids.clone(),
+            trash_area_names: self.trash_area_names.unwrap_or(base.trash_area_names),
+            area_names: base.area_names.clone(),
+            custom_metrics: base.custom_metrics.clone(),
+            features: self.features(base.features),
+        }
+        .normalized()
+    }
+
+    /// Unknown feature names are ignored
+    fn features(&self, base: AnalysisFeatures) -> AnalysisFeatures {
+        let mut features = base;
+        for (names, enabled) in [(&self.enable, true), (&self.disable, false)] {
+            for name in names.iter().flat_map(|names| names.split(',')) {
+                features.set(name.trim(), enabled);
+            }
+        }
+        features
+    }
+}
+
+#[derive(Deserialize)]
+struct SummaryQuery {
+    /// Re-parse (bypassing the cache) and include a timing breakdown
+    #[serde(default)]
+    profile: bool,
+    /// Keep the encounters the trivial filter would hide
+    #[serde(default)]
+    include_trivial: bool,
+}
+
 async fn log_summary(
     State(state): State<Arc<AppState>>,
     Path(filename): Path<String>,
+    Query(query): Query<SummaryQuery>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+    Query(resolution): Query<ResolutionQuery>,
 ) -> Result<axum::response::Response, (StatusCode, String)> {
     use axum::response::IntoResponse;
 
+    // Only results parsed with the configured options are cached
+    let options = overrides.apply(&state.config.parse);
+    let cacheable = options == state.config.parse;
+
     // Sanitize filename
     if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
         return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
     }
 
     // Search recursively for the file
-    let log_dir = state.log_dir.lock().unwrap().clone();
-    let path = find_file_recursive(&log_dir, &filename)
+    let path = state.log_index.find(&filename)
         .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
 
     // Check current file size
-    let current_size = std::fs::metadata(&path)
+    let current_size = log_metadata(&path).await
         .map(|m| m.len())
         .unwrap_or(0);
 
-    // Check cache — if file size unchanged, return cached result instantly
-    {
-        let cache = state.cache.lock().await;
-        if let Some((cached_size, cached_summary)) = cache.get(&filename) {
-            if *cached_size == current_size {
-                println!("📦 Cache HIT for {} (size unchanged: {} bytes)", filename, current_size);
-                let headers = [
-                    ("X-Cache-Status", "HIT".to_string()),
-                    ("X-Parse-Time", "0".to_string()),
-                ];
-                return Ok((headers, Json(cached_summary.clone())).into_response());
+    // Check cache — if file size unchanged, return cached result instantly. If the file
+    // grew, serve the stale result while a background re-parse catches up.
+    if !query.profile && cacheable {
+        let cached = state.cache.lock().await.get(&filename).cloned();
+        if let Some((cached_size, mut summary)) = cached {
+            let status = if cached_size == current_size {
+                tracing::debug!(filename = %filename, size = current_size, "summary cache hit");
+                "HIT"
+            } else {
+                tracing::info!(filename = %filename, old_size = cached_size, size = current_size, "summary cache stale, refreshing");
+                spawn_refresh(state.clone(), filename.clone(), path.clone());
+                "REFRESHING"
+            };
+            anonymize::redact_players(&mut summary.encounters, &state.config.private_players);
+            resolution.apply_to_summary(&mut summary);
+            // Numbered before trivial encounters are dropped, to match the detail and replay routes
+            let pseudonyms = share.anonymize.then(|| Pseudonyms::from_encounters(&summary.encounters));
+            let hidden = hide_trivial(&state, &query, &mut summary);
+            let headers = [
+                ("X-Cache-Status", status.to_string()),
+                ("X-Parse-Time", "0".to_string()),
+                ("X-Trivial-Hidden", hidden.to_string()),
+            ];
+            if let Some(pseudonyms) = pseudonyms {
+                return Ok((headers, anonymized(&pseudonyms, &summary)?).into_response());
             }
-            println!("🔄 Cache STALE for {} (size changed: {} -> {} bytes)", filename, cached_size, current_size);
-        } else {
-            println!("🆕 No cache for {}, parsing... ({} bytes)", filename, current_size);
+            attach_notes(&state, &filename, &mut summary.encounters).await;
+            return Ok((headers, Json(summary)).into_response());
         }
+        tracing::info!(filename = %filename, size = current_size, "summary not cached, parsing");
     }
 
     // File changed or not cached — parse it
     let fname = filename.clone();
     let parse_filename = filename.clone();
-    let summary = tokio::task::spawn_blocking(move || {
+    let span = tracing::Span::current();
+    let profile = query.profile;
+    let summary = state.jobs.run(&filename, "summary", current_size, move |progress| {
+        let _enter = span.enter();
         let start = std::time::Instant::now();
-        let result = parser::parse_combat_log(&path);
+        let result = parser::parse_combat_log_with_progress(&path, options, profile, progress);
         let elapsed = start.elapsed().as_secs_f64();
-        println!("⏱️  Parsed {} in {:.1}s", parse_filename, elapsed);
+        match &result {
+            Ok(s) => tracing::info!(filename = %parse_filename, elapsed_secs = elapsed, encounters = s.encounters.len(), "parsed log"),
+            Err(e) => tracing::error!(filename = %parse_filename, elapsed_secs = elapsed, "parse failed: {}", e),
+        }
         result.map(|s| (s, elapsed))
     })
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
     let (summary, parse_time) = summary;
 
-    // Store in cache
-    {
-        let mut cache = state.cache.lock().await;
-        cache.insert(fname, (current_size, summary.clone()));
+    if let Some(profile) = &summary.profile {
+        let mut stats = state.profile_stats.lock().await;
+        stats.parses += 1;
+        stats.totals.merge(profile);
     }
 
+    // Store in cache (without the one-off profile)
+    if cacheable {
+        let mut cached = summary.clone();
+        cached.profile = None;
+        cache_summary(&state, fname, current_size, cached).await;
+    }
+
+    let mut summary = summary;
+    anonymize::redact_players(&mut summary.encounters, &state.config.private_players);
+    resolution.apply_to_summary(&mut summary);
+    // Numbered before trivial encounters are dropped, to match the detail and replay routes
+    let pseudonyms = share.anonymize.then(|| Pseudonyms::from_encounters(&summary.encounters));
+    let hidden = hide_trivial(&state, &query, &mut summary);
     let headers = [
         ("X-Cache-Status", "PARSED".to_string()),
         ("X-Parse-Time", format!("{:.2}", parse_time)),
+        ("X-Trivial-Hidden", hidden.to_string()),
     ];
+    if let Some(pseudonyms) = pseudonyms {
+        return Ok((headers, anonymized(&pseudonyms, &summary)?).into_response());
+    }
+    attach_notes(&state, &filename, &mut summary.encounters).await;
     Ok((headers, Json(summary)).into_response())
 }
 
-async fn encounter_detail(
+/// Drop trivial encounters unless the request asked for them; returns how many were dropped
+fn hide_trivial(state: &AppState, query: &SummaryQuery, summary: &mut CombatLogSummary) -> usize {
+    if query.include_trivial {
+        return 0;
+    }
+    let before = summary.encounters.len();
+    summary.encounters.retain(|enc| !state.config.trivial.is_trivial(enc));
+    before - summary.encounters.len()
+}
+
+#[derive(Deserialize)]
+struct GroupedQuery {
+    /// Keep the pulls the trivial filter would hide
+    #[serde(default)]
+    include_trivial: bool,
+}
+
+/// Boss pulls grouped as instance -> boss -> pulls, with per-boss pull count, best pull and
+/// kill time
+async fn log_grouped(
     State(state): State<Arc<AppState>>,
-    Path((filename, index)): Path<(String, usize)>,
-) -> Result<Json<EncounterSummary>, (StatusCode, String)> {
+    Path(filename): Path<String>,
+    Query(query): Query<GroupedQuery>,
+) -> Result<Json<Vec<InstanceGroup>>, (StatusCode, String)> {
     // Sanitize filename
     if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
         return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
     }
 
-    let log_dir = state.log_dir.lock().unwrap().clone();
-    let path = find_file_recursive(&log_dir, &filename)
+    let path = state.log_index.find(&filename)
         .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+    ensure_cached(&state, &filename, path).await?;
 
-    // Check current file size
-    let current_size = std::fs::metadata(&path)
-        .map(|m| m.len())
-        .unwrap_or(0);
-
-    // Check cache first — if file size unchanged, use cached summary
-    {
-        let cache = state.cache.lock().await;
-        if let Some((cached_size, cached_summary)) = cache.get(&filename) {
-            if *cached_size == current_size {
-                println!("📦 Cache HIT for {} encounter {} (size unchanged)", filename, index);
-                return cached_summary.encounters.iter().nth(index)
-                    .cloned()
-                    .map(Json)
-                    .ok_or((StatusCode::NOT_FOUND, "Encounter not found".to_string()));
-            }
-        }
-    }
-
-    // Not cached or file changed — parse it
-    println!("🔄 Parsing {} for encounter {} (no cache)", filename, index);
-    let fname = filename.clone();
-    let summary = tokio::task::spawn_blocking(move || {
-        parser::parse_combat_log(&path)
-    })
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
-
-    let result = summary.encounters.iter().nth(index)
-        .cloned()
-        .map(Json)
-        .ok_or((StatusCode::NOT_FOUND, "Encounter not found".to_string()));
-
-    // Store in cache for future requests
-    {
-        let mut cache = state.cache.lock().await;
-        cache.insert(fname, (current_size, summary));
-    }
-
-    result
+    let cache = state.cache.lock().await;
+    let (_, summary) = cache.get(&filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+    let encounters = summary.encounters.iter()
+        .filter(|enc| query.include_trivial || !state.config.trivial.is_trivial(enc));
+    Ok(Json(grouping::group_encounters(encounters)))
 }
 
-async fn encounter_replay(
+/// Avoidable vs unavoidable damage taken per player across a log's boss pulls and keys
+async fn log_avoidable_trend(
     State(state): State<Arc<AppState>>,
-    Path((filename, index)): Path<(String, usize)>,
-) -> Result<Json<ReplayData>, (StatusCode, String)> {
-    // Sanitize filename
+    Path(filename): Path<String>,
+    Query(query): Query<GroupedQuery>,
+    Query(share): Query<ShareQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
     if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
         return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
     }
 
-    let log_dir = state.log_dir.lock().unwrap().clone();
-    let path = find_file_recursive(&log_dir, &filename)
+    let path = state.log_index.find(&filename)
         .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+    ensure_cached(&state, &filename, path).await?;
 
-    let current_size = std::fs::metadata(&path)
+    let mut encounters: Vec<EncounterSummary> = {
+        let cache = state.cache.lock().await;
+        let (_, summary) = cache.get(&filename)
+            .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+        summary.encounters.iter()
+            .filter(|enc| query.include_trivial || !state.config.trivial.is_trivial(enc))
+            .cloned()
+            .collect()
+    };
+    // Private players are left out rather than folded into one row: their avoidable damage
+    // isn't kept per row
+    for enc in &mut encounters {
+        enc.players.retain(|p| !anonymize::is_listed(&p.name, &state.config.private_players));
+    }
+    let avoidable_trend = trend::avoidable_trend(&encounters);
+    if share.anonymize {
+        let pseudonyms = log_pseudonyms(&state, &filename, &[]).await;
+        return Ok(anonymized(&pseudonyms, &avoidable_trend)?.into_response());
+    }
+    Ok(Json(avoidable_trend).into_response())
+}
+
+#[derive(Deserialize)]
+struct MechanicHitsQuery {
+    /// "hits" (default), "damage", "hits_per_pull" or "pulls_hit"
+    sort: Option<String>,
+}
+
+/// Avoidable-mechanic hits per player across all pulls of each boss in a log
+async fn log_mechanic_hits(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(query): Query<GroupedQuery>,
+    Query(hits_query): Query<MechanicHitsQuery>,
+    Query(share): Query<ShareQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+    let sort = match hits_query.sort.as_deref() {
+        Some(text) => trend::MechanicHitSort::parse(text).ok_or((StatusCode::BAD_REQUEST, format!("Unknown sort: {}", text)))?,
+        None => trend::MechanicHitSort::default(),
+    };
+
+    let path = state.log_index.find(&filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+    ensure_cached(&state, &filename, path).await?;
+
+    let mut encounters: Vec<EncounterSummary> = {
+        let cache = state.cache.lock().await;
+        let (_, summary) = cache.get(&filename)
+            .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+        summary.encounters.iter()
+            .filter(|enc| enc.encounter_type == "boss")
+            .filter(|enc| query.include_trivial || !state.config.trivial.is_trivial(enc))
+            .cloned()
+            .collect()
+    };
+    // As for the avoidable trend, private players are left out
+    for enc in &mut encounters {
+        enc.players.retain(|p| !anonymize::is_listed(&p.name, &state.config.private_players));
+    }
+    let mechanic_hits = trend::mechanic_hits(&encounters, sort);
+    if share.anonymize {
+        let pseudonyms = log_pseudonyms(&state, &filename, &[]).await;
+        return Ok(anonymized(&pseudonyms, &mechanic_hits)?.into_response());
+    }
+    Ok(Json(mechanic_hits).into_response())
+}
+
+/// `?resolution=1s|5s|30s`: time series in windows of that width instead of as parsed
+#[derive(Deserialize)]
+struct ResolutionQuery {
+    resolution: Option<Resolution>,
+}
+
+impl ResolutionQuery {
+    fn apply_to_summary(&self, summary: &mut CombatLogSummary) {
+        if let Some(resolution) = self.resolution {
+            for enc in &mut summary.encounters {
+                resolution.apply_to_encounter(enc);
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ShareQuery {
+    /// Replace player names and GUIDs with role pseudonyms ("Tank1", "Healer2", "DPS3"),
+    /// and leave out notes, for reports shared publicly
+    #[serde(default)]
+    anonymize: bool,
+}
+
+/// Pseudonyms for a log: the cached summary's players first, so an encounter is numbered
+/// the same way as in the anonymized summary, then any of `players` the cache lacks
+async fn log_pseudonyms(state: &AppState, filename: &str, players: &[PlayerSummary]) -> Pseudonyms {
+    let mut pseudonyms = state.cache.lock().await.get(filename)
+        .map(|(_, summary)| Pseudonyms::from_encounters(&summary.encounters))
+        .unwrap_or_default();
+    pseudonyms.add_players(players);
+    pseudonyms
+}
+
+fn anonymized<T: Serialize>(pseudonyms: &Pseudonyms, report: &T) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    pseudonyms.anonymize(report)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to anonymize: {}", e)))
+}
+
+/// How a route names an encounter: the stable ID, or (for old links) its index in the log
+enum EncounterKey {
+    Stable(String),
+    Index(usize),
+}
+
+impl EncounterKey {
+    fn parse(raw: &str) -> Self {
+        let is_stable = raw.len() == 16 && raw.chars().all(|c| c.is_ascii_hexdigit());
+        match raw.parse() {
+            Ok(index) if !is_stable => EncounterKey::Index(index),
+            _ => EncounterKey::Stable(raw.to_ascii_lowercase()),
+        }
+    }
+
+    fn find<'a>(&self, encounters: &'a [EncounterSummary]) -> Option<&'a EncounterSummary> {
+        match self {
+            EncounterKey::Stable(id) => encounters.iter().find(|e| e.stable_id == *id),
+            EncounterKey::Index(index) => encounters.get(*index),
+        }
+    }
+}
+
+impl std::fmt::Display for EncounterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncounterKey::Stable(id) => f.write_str(id),
+            EncounterKey::Index(index) => write!(f, "#{}", index),
+        }
+    }
+}
+
+async fn encounter_detail(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+    Query(resolution): Query<ResolutionQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let Json(mut encounter) = find_encounter_detail(&state, filename.clone(), EncounterKey::parse(&key), overrides).await?;
+    anonymize::redact_players(std::slice::from_mut(&mut encounter), &state.config.private_players);
+    if let Some(resolution) = resolution.resolution {
+        resolution.apply_to_encounter(&mut encounter);
+    }
+    if !share.anonymize {
+        return Ok(Json(encounter).into_response());
+    }
+    encounter.notes = None;
+    let pseudonyms = log_pseudonyms(&state, &filename, &encounter.players).await;
+    Ok(anonymized(&pseudonyms, &encounter)?.into_response())
+}
+
+async fn find_encounter_detail(
+    state: &Arc<AppState>,
+    filename: String,
+    key: EncounterKey,
+    overrides: ParseOverrides,
+) -> Result<Json<EncounterSummary>, (StatusCode, String)> {
+    let options = overrides.apply(&state.config.parse);
+    let cacheable = options == state.config.parse;
+
+    // Sanitize filename
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+
+    let path = state.log_index.find(&filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+
+    // Check current file size
+    let current_size = log_metadata(&path).await
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    // Check cache first — if file size unchanged, use cached summary
+    if cacheable {
+        let cached = {
+            let cache = state.cache.lock().await;
+            cache.get(&filename)
+                .filter(|(cached_size, _)| *cached_size == current_size)
+                .map(|(_, cached_summary)| key.find(&cached_summary.encounters).cloned())
+        };
+        if let Some(encounter) = cached {
+            tracing::debug!(filename = %filename, key = %key, "encounter cache hit");
+            return with_notes(state, &filename, encounter).await;
+        }
+    }
+
+    // Logs are append-only, so an earlier parse's byte range for this encounter is still
+    // valid: re-parse just that slice instead of the whole file
+    let known_range = {
+        let cache = state.cache.lock().await;
+        cache.get(&filename)
+            .and_then(|(_, cached_summary)| key.find(&cached_summary.encounters))
+            .and_then(|enc| enc.range.clone().map(|r| (r, enc.index, enc.stable_id.clone())))
+    };
+    if let Some((range, index, stable_id)) = known_range {
+        let range_path = path.clone();
+        let range_options = options.clone();
+        let start = std::time::Instant::now();
+        let partial = tokio::task::spawn_blocking(move || {
+            parser::parse_combat_log_range(&range_path, range_options, &range)
+        }).await;
+        let encounter = match partial {
+            Ok(Ok(summary)) => summary.encounters.into_iter()
+                .find(|e| e.stable_id == stable_id),
+            Ok(Err(e)) => {
+                tracing::warn!(filename = %filename, key = %key, "range parse failed: {}", e);
+                None
+            }
+            Err(e) => {
+                tracing::warn!(filename = %filename, key = %key, "range parse task failed: {}", e);
+                None
+            }
+        };
+        if let Some(mut enc) = encounter {
+            tracing::info!(filename = %filename, key = %key, elapsed_secs = start.elapsed().as_secs_f64(), "parsed encounter range");
+            // A range parse only sees this encounter, so its index comes from the full parse
+            enc.index = index;
+            return with_notes(state, &filename, Some(enc)).await;
+        }
+        tracing::debug!(filename = %filename, key = %key, "encounter range didn't match, falling back to a full parse");
+    }
+
+    // Not cached or file changed — parse it
+    tracing::info!(filename = %filename, key = %key, "encounter not cached, parsing");
+    let fname = filename.clone();
+    let summary = parse_job(state, &filename, "encounter", path, options).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let encounter = key.find(&summary.encounters).cloned();
+
+    // Store in cache for future requests
+    if cacheable {
+        cache_summary(state, fname, current_size, summary).await;
+    }
+
+    with_notes(state, &filename, encounter).await
+}
+
+/// Fill in user notes from the history store (they're never part of the cached parse)
+async fn attach_notes(state: &AppState, filename: &str, encounters: &mut [EncounterSummary]) {
+    let history = state.history.lock().await;
+    for enc in encounters.iter_mut() {
+        enc.notes = history.notes(filename, &enc.stable_id).cloned();
+    }
+}
+
+/// Fill in notes and history annotations, which a range or uncached parse hasn't had from
+/// `cache_summary`
+async fn with_notes(
+    state: &AppState,
+    filename: &str,
+    encounter: Option<EncounterSummary>,
+) -> Result<Json<EncounterSummary>, (StatusCode, String)> {
+    let mut enc = encounter.ok_or((StatusCode::NOT_FOUND, "Encounter not found".to_string()))?;
+    let history = state.history.lock().await;
+    enc.notes = history.notes(filename, &enc.stable_id).cloned();
+    history.annotate(std::slice::from_mut(&mut enc));
+    Ok(Json(enc))
+}
+
+#[derive(Deserialize)]
+struct NotesRequest {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Save notes and tags for an encounter; empty text and no tags clears them
+async fn save_encounter_notes(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Json(req): Json<NotesRequest>,
+) -> Result<Json<EncounterNotes>, (StatusCode, String)> {
+    // Sanitize filename
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+
+    let stable_id = stable_id_of(&state, &filename, EncounterKey::parse(&key)).await?;
+
+    let notes = EncounterNotes {
+        text: req.text.trim().to_string(),
+        tags: req.tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+        updated_at: chrono::Local::now().to_rfc3339(),
+    };
+    state.history.lock().await
+        .set_notes(&filename, &stable_id, notes.clone())
+        .map_err(|e| {
+            tracing::error!(filename = %filename, stable_id = %stable_id, "failed to save notes: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save notes: {}", e))
+        })?;
+    tracing::info!(filename = %filename, stable_id = %stable_id, "saved encounter notes");
+    Ok(Json(notes))
+}
+
+/// Stable ID of an encounter. Notes and bookmarks are keyed by it so they stay attached as
+/// the log grows; an index is resolved through the cached parse.
+async fn stable_id_of(state: &AppState, filename: &str, key: EncounterKey) -> Result<String, (StatusCode, String)> {
+    match key {
+        EncounterKey::Stable(id) => Ok(id),
+        key @ EncounterKey::Index(_) => {
+            let cache = state.cache.lock().await;
+            cache.get(filename)
+                .and_then(|(_, cached_summary)| key.find(&cached_summary.encounters))
+                .map(|enc| enc.stable_id.clone())
+                .ok_or((StatusCode::NOT_FOUND, "Encounter not found".to_string()))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BookmarkRequest {
+    name: String,
+    offset_secs: f64,
+}
+
+async fn list_bookmarks(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+) -> Result<Json<Vec<Bookmark>>, (StatusCode, String)> {
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+    let stable_id = stable_id_of(&state, &filename, EncounterKey::parse(&key)).await?;
+    Ok(Json(state.history.lock().await.bookmarks(&filename, &stable_id).to_vec()))
+}
+
+/// Bookmark a point in an encounter for review sessions to jump the replay to
+async fn add_bookmark(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Json(req): Json<BookmarkRequest>,
+) -> Result<Json<Bookmark>, (StatusCode, String)> {
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Bookmark name is empty".to_string()));
+    }
+    if !req.offset_secs.is_finite() || req.offset_secs < 0.0 {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid offset: {}", req.offset_secs)));
+    }
+    let stable_id = stable_id_of(&state, &filename, EncounterKey::parse(&key)).await?;
+    let bookmark = state.history.lock().await
+        .add_bookmark(&filename, &stable_id, name, req.offset_secs)
+        .map_err(|e| {
+            tracing::error!(filename = %filename, stable_id = %stable_id, "failed to save bookmark: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save bookmark: {}", e))
+        })?;
+    tracing::info!(filename = %filename, stable_id = %stable_id, id = bookmark.id, "added replay bookmark");
+    Ok(Json(bookmark))
+}
+
+async fn remove_bookmark(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key, id)): Path<(String, String, u32)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+    let stable_id = stable_id_of(&state, &filename, EncounterKey::parse(&key)).await?;
+    let removed = state.history.lock().await
+        .remove_bookmark(&filename, &stable_id, id)
+        .map_err(|e| {
+            tracing::error!(filename = %filename, stable_id = %stable_id, "failed to save bookmarks: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save bookmarks: {}", e))
+        })?;
+    if removed { Ok(StatusCode::NO_CONTENT) } else { Err((StatusCode::NOT_FOUND, "Bookmark not found".to_string())) }
+}
+
+async fn encounter_replay(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(share): Query<ShareQuery>,
+    Query(resolution): Query<ResolutionQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let (players, mut replay) = find_replay(&state, filename.clone(), EncounterKey::parse(&key)).await?;
+    anonymize::redact_replay(&mut replay, &players, &state.config.private_players);
+    if let Some(resolution) = resolution.resolution {
+        resolution.apply_to_replay(&mut replay);
+    }
+    if !share.anonymize {
+        return Ok(Json(replay).into_response());
+    }
+    // Bookmark names are free text, like notes
+    replay.bookmarks.clear();
+    let pseudonyms = log_pseudonyms(&state, &filename, &players).await;
+    Ok(anonymized(&pseudonyms, &replay)?.into_response())
+}
+
+/// Replay data of an encounter, and its players (for pseudonyms)
+async fn find_replay(
+    state: &Arc<AppState>,
+    filename: String,
+    key: EncounterKey,
+) -> Result<(Vec<PlayerSummary>, ReplayData), (StatusCode, String)> {
+    // Sanitize filename
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+
+    let path = state.log_index.find(&filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+
+    let current_size = log_metadata(&path).await
         .map(|m| m.len())
         .unwrap_or(0);
 
@@ -286,46 +1572,746 @@ async fn encounter_replay(
         let cache = state.cache.lock().await;
         if let Some((cached_size, cached_summary)) = cache.get(&filename) {
             if *cached_size == current_size {
-                println!("📦 Replay cache HIT for {} encounter {}", filename, index);
-                let enc = cached_summary.encounters.iter().nth(index)
+                tracing::debug!(filename = %filename, key = %key, "replay cache hit");
+                let enc = key.find(&cached_summary.encounters)
                     .ok_or((StatusCode::NOT_FOUND, "Encounter not found".to_string()))?;
-                return Ok(Json(ReplayData {
-                    replay_timeline: enc.replay_timeline.clone(),
-                    boss_positions: enc.boss_positions.clone(),
-                    raw_ability_events: enc.raw_ability_events.clone(),
-                }));
+                let bookmarks = state.history.lock().await.bookmarks(&filename, &enc.stable_id).to_vec();
+                return Ok((enc.players.clone(), ReplayData::from_encounter(enc, bookmarks)));
             }
         }
     }
 
     // Parse if not cached
     let fname = filename.clone();
-    let summary = tokio::task::spawn_blocking(move || {
-        parser::parse_combat_log(&path)
-    })
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let options = state.config.parse.clone();
+    let summary = parse_job(state, &filename, "replay", path, options).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
-    let enc = summary.encounters.iter().nth(index)
+    let enc = key.find(&summary.encounters)
         .ok_or((StatusCode::NOT_FOUND, "Encounter not found".to_string()))?;
 
-    let result = Ok(Json(ReplayData {
-        replay_timeline: enc.replay_timeline.clone(),
-        boss_positions: enc.boss_positions.clone(),
-        raw_ability_events: enc.raw_ability_events.clone(),
-    }));
+    let bookmarks = state.history.lock().await.bookmarks(&filename, &enc.stable_id).to_vec();
+    let result = Ok((enc.players.clone(), ReplayData::from_encounter(enc, bookmarks)));
 
     // Store in cache
-    {
-        let mut cache = state.cache.lock().await;
-        cache.insert(fname, (current_size, summary));
-    }
+    cache_summary(state, fname, current_size, summary).await;
 
     result
 }
 
-fn format_size(bytes: u64) -> String {
+#[derive(Deserialize)]
+struct AbilityTimelineQuery {
+    /// GUID, name or (on anonymized requests) pseudonym of the player
+    player: String,
+}
+
+/// One player's damage per ability and elapsed second, for graphing burst windows
+async fn encounter_ability_timeline(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(query): Query<AbilityTimelineQuery>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+    Query(resolution): Query<ResolutionQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let Json(mut encounter) = find_encounter_detail(&state, filename.clone(), EncounterKey::parse(&key), overrides).await?;
+    anonymize::redact_players(std::slice::from_mut(&mut encounter), &state.config.private_players);
+    let pseudonyms = if share.anonymize {
+        Some(log_pseudonyms(&state, &filename, &encounter.players).await)
+    } else {
+        None
+    };
+    let player = encounter.players.iter()
+        .find(|p| {
+            p.guid == query.player || p.name == query.player
+                || pseudonyms.as_ref().is_some_and(|ps| ps.get(&p.guid) == Some(query.player.as_str()))
+        })
+        .ok_or((StatusCode::NOT_FOUND, "Player not found".to_string()))?;
+    let mut timeline = AbilityTimeline {
+        player_guid: player.guid.clone(),
+        player_name: player.name.clone(),
+        duration_secs: encounter.duration_secs,
+        abilities: encounter.ability_damage_over_time.get(&player.guid).cloned().unwrap_or_default(),
+    };
+    if let Some(resolution) = resolution.resolution {
+        for series in &mut timeline.abilities {
+            resolution.apply_to_ability_series(series);
+        }
+    }
+    match pseudonyms {
+        Some(pseudonyms) => Ok(anonymized(&pseudonyms, &timeline)?.into_response()),
+        None => Ok(Json(timeline).into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AuraSnapshotQuery {
+    /// GUID, name or (on anonymized requests) pseudonym of the player
+    player: String,
+    /// Seconds into the fight
+    time: f64,
+}
+
+/// The auras (with stacks) up on one player at a moment of the fight
+async fn encounter_aura_snapshot(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(query): Query<AuraSnapshotQuery>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    if !query.time.is_finite() || query.time < 0.0 {
+        return Err((StatusCode::BAD_REQUEST, "time must be seconds into the fight".to_string()));
+    }
+    let Json(mut encounter) = find_encounter_detail(&state, filename.clone(), EncounterKey::parse(&key), overrides).await?;
+    anonymize::redact_players(std::slice::from_mut(&mut encounter), &state.config.private_players);
+    let pseudonyms = if share.anonymize {
+        Some(log_pseudonyms(&state, &filename, &encounter.players).await)
+    } else {
+        None
+    };
+    let player = encounter.players.iter()
+        .find(|p| {
+            p.guid == query.player || p.name == query.player
+                || pseudonyms.as_ref().is_some_and(|ps| ps.get(&p.guid) == Some(query.player.as_str()))
+        })
+        .ok_or((StatusCode::NOT_FOUND, "Player not found".to_string()))?;
+    let uptimes = encounter.buff_uptimes.get(&player.guid).map(Vec::as_slice).unwrap_or_default();
+    let snapshot = AuraSnapshot {
+        player_guid: player.guid.clone(),
+        player_name: player.name.clone(),
+        time: query.time,
+        auras: auras::auras_at(uptimes, query.time),
+    };
+    match pseudonyms {
+        Some(pseudonyms) => Ok(anonymized(&pseudonyms, &snapshot)?.into_response()),
+        None => Ok(Json(snapshot).into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AuraLanesQuery {
+    /// GUID, name or (on anonymized requests) pseudonym of one player; all players without it
+    player: Option<String>,
+    /// Only the curated key buffs (raid buffs, food, flasks, lust, major externals)
+    #[serde(default)]
+    key_only: bool,
+}
+
+/// Every player's buffs and debuffs as lanes of intervals, in one payload for the buff band view
+async fn encounter_aura_lanes(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(query): Query<AuraLanesQuery>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let Json(mut encounter) = find_encounter_detail(&state, filename.clone(), EncounterKey::parse(&key), overrides).await?;
+    anonymize::redact_players(std::slice::from_mut(&mut encounter), &state.config.private_players);
+    let pseudonyms = if share.anonymize {
+        Some(log_pseudonyms(&state, &filename, &encounter.players).await)
+    } else {
+        None
+    };
+    let uptimes = if query.key_only { &encounter.key_buff_uptimes } else { &encounter.buff_uptimes };
+    let lanes: Vec<PlayerAuraLanes> = encounter.players.iter()
+        .filter(|p| query.player.as_ref().is_none_or(|player| {
+            p.guid == *player || p.name == *player
+                || pseudonyms.as_ref().is_some_and(|ps| ps.get(&p.guid) == Some(player.as_str()))
+        }))
+        .map(|p| PlayerAuraLanes {
+            player_guid: p.guid.clone(),
+            player_name: p.name.clone(),
+            lanes: auras::lanes(uptimes.get(&p.guid).map(Vec::as_slice).unwrap_or_default(), encounter.duration_secs),
+        })
+        .collect();
+    if query.player.is_some() && lanes.is_empty() {
+        return Err((StatusCode::NOT_FOUND, "Player not found".to_string()));
+    }
+    match pseudonyms {
+        Some(pseudonyms) => Ok(anonymized(&pseudonyms, &lanes)?.into_response()),
+        None => Ok(Json(lanes).into_response()),
+    }
+}
+
+/// Every pull opener of a Mythic+ key in order: who opened, first melee targets, taunts and
+/// Misdirection/Tricks, for tank review
+async fn encounter_threat_review(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let Json(encounter) = find_encounter_detail(&state, filename.clone(), EncounterKey::parse(&key), overrides).await?;
+    let review = ThreatReview {
+        encounter_name: encounter.name.clone(),
+        pulls: encounter.segments.iter()
+            .flat_map(|segment| segment.pulls.iter().map(|pull| ThreatReviewPull {
+                segment_index: segment.index,
+                segment_name: segment.name.clone(),
+                pull_index: pull.pull_index,
+                start_time_offset: pull.start_time_offset,
+                duration_secs: pull.duration_secs,
+                opener: pull.opener.clone(),
+                tanking: pull.tanking.clone(),
+            }))
+            .collect(),
+    };
+    if share.anonymize {
+        let pseudonyms = log_pseudonyms(&state, &filename, &encounter.players).await;
+        Ok(anonymized(&pseudonyms, &review)?.into_response())
+    } else {
+        Ok(Json(review).into_response())
+    }
+}
+
+/// Players listed in a text export by default, as in Details!
+const EXPORT_PLAYERS: usize = 10;
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    /// "damage" (default) or "healing"
+    meter: Option<String>,
+    players: Option<usize>,
+}
+
+/// A Details!-style text summary of one meter, for pasting into Discord
+async fn encounter_export(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(query): Query<ExportQuery>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    let meter = match query.meter.as_deref() {
+        Some(text) => export::Meter::parse(text).ok_or((StatusCode::BAD_REQUEST, format!("Unknown meter: {}", text)))?,
+        None => export::Meter::Damage,
+    };
+    let Json(mut encounter) = find_encounter_detail(&state, filename.clone(), EncounterKey::parse(&key), overrides).await?;
+    anonymize::redact_players(std::slice::from_mut(&mut encounter), &state.config.private_players);
+    if share.anonymize {
+        let pseudonyms = log_pseudonyms(&state, &filename, &encounter.players).await;
+        for player in &mut encounter.players {
+            if let Some(pseudonym) = pseudonyms.get(&player.guid) {
+                player.name = pseudonym.to_string();
+            }
+        }
+    }
+    let text = export::details_text(&encounter, meter, query.players.unwrap_or(EXPORT_PLAYERS).max(1));
+    Ok(download::inline(text.into_bytes(), "text/plain; charset=utf-8", &headers))
+}
+
+/// One encounter as an Excel workbook (players, abilities, deaths, buffs, segments)
+async fn encounter_xlsx(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(overrides): Query<ParseOverrides>,
+    Query(share): Query<ShareQuery>,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    let Json(mut encounter) = find_encounter_detail(&state, filename.clone(), EncounterKey::parse(&key), overrides).await?;
+    anonymize::redact_players(std::slice::from_mut(&mut encounter), &state.config.private_players);
+    let pseudonyms = if share.anonymize {
+        Some(log_pseudonyms(&state, &filename, &encounter.players).await)
+    } else {
+        None
+    };
+    let name = format!("{}-{}.xlsx", filename.trim_end_matches(".txt"), encounter.stable_id);
+    xlsx_response(vec![encounter], pseudonyms, log_written(&state, &filename).await, name, &headers).await
+}
+
+/// A small PNG chart of one encounter (group damage over time, boss HP), for link previews
+/// and reports; `width=` and `height=` in pixels
+async fn encounter_thumbnail(
+    State(state): State<Arc<AppState>>,
+    Path((filename, key)): Path<(String, String)>,
+    Query(query): Query<ThumbnailQuery>,
+    Query(overrides): Query<ParseOverrides>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let Json(encounter) = find_encounter_detail(&state, filename, EncounterKey::parse(&key), overrides).await?;
+    let png = tokio::task::spawn_blocking(move || thumbnail::encounter_thumbnail(&encounter, &query))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?;
+    let headers = [
+        (axum::http::header::CONTENT_TYPE, "image/png"),
+        (axum::http::header::CACHE_CONTROL, "public, max-age=300"),
+    ];
+    Ok((headers, png).into_response())
+}
+
+/// Every encounter of a log as one Excel workbook, trivial ones only if asked for
+async fn log_xlsx(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(query): Query<GroupedQuery>,
+    Query(share): Query<ShareQuery>,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+    let path = state.log_index.find(&filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+    ensure_cached(&state, &filename, path).await?;
+
+    let mut encounters: Vec<EncounterSummary> = {
+        let cache = state.cache.lock().await;
+        let (_, summary) = cache.get(&filename)
+            .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+        summary.encounters.iter()
+            .filter(|enc| query.include_trivial || !state.config.trivial.is_trivial(enc))
+            .cloned()
+            .collect()
+    };
+    anonymize::redact_players(&mut encounters, &state.config.private_players);
+    let pseudonyms = if share.anonymize {
+        Some(log_pseudonyms(&state, &filename, &[]).await)
+    } else {
+        None
+    };
+    let name = format!("{}.xlsx", filename.trim_end_matches(".txt"));
+    xlsx_response(encounters, pseudonyms, log_written(&state, &filename).await, name, &headers).await
+}
+
+#[derive(Deserialize)]
+struct SplitQuery {
+    /// "session" or "encounter"
+    by: String,
+}
+
+/// One log written by a split
+#[derive(Serialize)]
+struct SplitFile {
+    filename: String,
+    label: String,
+    start_time: String,
+    size_bytes: u64,
+}
+
+/// Split a log into one log per play session or per encounter, written to a `Split` folder next
+/// to it; the new logs are listed like any other
+async fn split_log(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(query): Query<SplitQuery>,
+) -> Result<Json<Vec<SplitFile>>, (StatusCode, String)> {
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+    let path = state.log_index.find(&filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+
+    let parts = match query.by.as_str() {
+        "session" => {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || split::sessions(&path))
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        }
+        "encounter" => {
+            ensure_cached(&state, &filename, path.clone()).await?;
+            let cache = state.cache.lock().await;
+            let (_, summary) = cache.get(&filename)
+                .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+            split::encounter_parts(&summary.encounters)
+        }
+        other => return Err((StatusCode::BAD_REQUEST, format!("Unknown split \"{}\"; expected session or encounter", other))),
+    };
+    if parts.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let out_dir = path.parent().map(|dir| dir.join("Split")).unwrap_or_else(|| PathBuf::from("Split"));
+    let written = {
+        let (path, parts) = (path.clone(), parts.clone());
+        tokio::task::spawn_blocking(move || split::write_parts(&path, &parts, &out_dir))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
+            .map_err(|e| {
+                tracing::error!(filename = %filename, "failed to split log: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to split log: {}", e))
+            })?
+    };
+    tracing::info!(filename = %filename, by = %query.by, parts = written.len(), "split log");
+
+    let files = written.iter().zip(&parts).map(|(written_path, part)| {
+        state.log_index.note_change(written_path);
+        SplitFile {
+            filename: written_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            label: part.label.clone(),
+            start_time: part.start_time.clone(),
+            size_bytes: std::fs::metadata(written_path).map(|m| m.len()).unwrap_or(0),
+        }
+    }).collect();
+    Ok(Json(files))
+}
+
+#[derive(Deserialize)]
+struct MergeRequest {
+    filenames: Vec<String>,
+}
+
+/// The log a merge wrote
+#[derive(Serialize)]
+struct MergedLog {
+    filename: String,
+    #[serde(flatten)]
+    report: merge::MergeReport,
+}
+
+/// Join the logs of one session into a single log next to the earliest of them, named after
+/// it with a `-merged` suffix
+async fn merge_logs(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<MergeRequest>,
+) -> Result<Json<MergedLog>, (StatusCode, String)> {
+    let mut filenames = req.filenames;
+    filenames.sort_by_cached_key(|f| (extract_date_from_filename(f), f.clone()));
+    filenames.dedup();
+    if filenames.len() < 2 {
+        return Err((StatusCode::BAD_REQUEST, "Pick at least two logs to merge".to_string()));
+    }
+    let mut paths = Vec::with_capacity(filenames.len());
+    for filename in &filenames {
+        if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+            return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+        }
+        paths.push(state.log_index.find(filename)
+            .ok_or((StatusCode::NOT_FOUND, format!("Log file not found: {}", filename)))?);
+    }
+
+    let filename = format!("{}-merged.txt", filenames[0].trim_end_matches(".txt"));
+    let out_path = paths[0].with_file_name(&filename);
+    let report = {
+        let out_path = out_path.clone();
+        tokio::task::spawn_blocking(move || merge::merge(&paths, &out_path))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
+            .map_err(|e| match e {
+                merge::MergeError::Exists => (StatusCode::CONFLICT, format!("{} already exists", filename)),
+                merge::MergeError::Failed(e) => {
+                    tracing::error!(filename = %filename, "failed to merge logs: {}", e);
+                    (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to merge logs: {}", e))
+                }
+            })?
+    };
+    tracing::info!(filename = %filename, logs = filenames.len(), lines = report.lines, headers_dropped = report.headers_dropped, overlapping_lines = report.overlapping_lines, "merged logs");
+    state.log_index.note_change(&out_path);
+    Ok(Json(MergedLog { filename, report }))
+}
+
+/// When a log was last written: the date its exports carry, so the same log exports to the
+/// same bytes and an interrupted download can be resumed
+async fn log_written(state: &AppState, filename: &str) -> chrono::DateTime<chrono::Utc> {
+    let written = match state.log_index.find(filename) {
+        Some(path) => log_metadata(&path).await.and_then(|m| m.modified()).ok(),
+        None => None,
+    };
+    written.map_or_else(chrono::Utc::now, chrono::DateTime::from)
+}
+
+/// `log_file::metadata` off the async runtime, as its share violation retries sleep
+async fn log_metadata(path: &std::path::Path) -> std::io::Result<std::fs::Metadata> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || log_file::metadata(&path))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Build the workbook off the async runtime and serve it as a (resumable) download named `name`
+async fn xlsx_response(
+    encounters: Vec<EncounterSummary>,
+    pseudonyms: Option<Pseudonyms>,
+    created: chrono::DateTime<chrono::Utc>,
+    name: String,
+    request: &axum::http::HeaderMap,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    let start = std::time::Instant::now();
+    let count = encounters.len();
+    let bytes = tokio::task::spawn_blocking(move || crate::xlsx::workbook(&encounters, created, pseudonyms.as_ref()))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
+        .map_err(|e| {
+            tracing::error!(name = %name, "failed to build workbook: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build workbook: {}", e))
+        })?;
+    tracing::info!(name = %name, encounters = count, bytes = bytes.len(), elapsed_secs = start.elapsed().as_secs_f64(), "built workbook");
+    Ok(download::attachment(bytes, &name, "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", request))
+}
+
+#[derive(Deserialize)]
+struct CompareQuery {
+    a_file: String,
+    a: String,
+    /// Defaults to `a_file`
+    b_file: Option<String>,
+    b: String,
+}
+
+/// Route diff of two Mythic+ runs of the same dungeon, possibly from different logs
+async fn compare_runs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CompareQuery>,
+) -> Result<Json<RouteComparison>, (StatusCode, String)> {
+    let b_file = query.b_file.unwrap_or_else(|| query.a_file.clone());
+    let a = load_encounter(&state, &query.a_file, &EncounterKey::parse(&query.a)).await?;
+    let b = load_encounter(&state, &b_file, &EncounterKey::parse(&query.b)).await?;
+    compare::compare_runs(&a, &b)
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+/// Parse a log with the configured options and cache it, unless the cache is current
+async fn ensure_cached(state: &AppState, filename: &str, path: PathBuf) -> Result<(), (StatusCode, String)> {
+    let current_size = log_metadata(&path).await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let cached = state.cache.lock().await
+        .get(filename)
+        .is_some_and(|(cached_size, _)| *cached_size == current_size);
+    if !cached {
+        let options = state.config.parse.clone();
+        let summary = parse_job(state, filename, "summary", path, options).await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        cache_summary(state, filename.to_string(), current_size, summary).await;
+    }
+    Ok(())
+}
+
+/// An encounter from the cached parse of a log, parsing (and caching) it first if needed
+async fn load_encounter(state: &AppState, filename: &str, key: &EncounterKey) -> Result<EncounterSummary, (StatusCode, String)> {
+    // Sanitize filename
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+
+    let path = state.log_index.find(filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+    ensure_cached(state, filename, path).await?;
+
+    let cache = state.cache.lock().await;
+    cache.get(filename)
+        .and_then(|(_, summary)| key.find(&summary.encounters).cloned())
+        .ok_or((StatusCode::NOT_FOUND, "Encounter not found".to_string()))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+}
+
+/// Ranked search over every encounter in the history index (all logs parsed so far)
+async fn search_encounters(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Json<Vec<SearchHit>> {
+    Json(state.history.lock().await.search(&query.q))
+}
+
+#[derive(Deserialize)]
+struct VaultQuery {
+    /// Overrides the configured region
+    region: Option<Region>,
+    /// 0 for the current week, 1 for the one before, ...
+    #[serde(default)]
+    weeks_ago: u32,
+}
+
+/// Mythic+ keys each character finished this reset week (or an earlier one), from the
+/// history of every log parsed so far
+async fn vault_progress(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<VaultQuery>,
+) -> Json<VaultWeek> {
+    let region = query.region.unwrap_or(state.config.region);
+    let start = vault::week_start(region, chrono::Utc::now()) - chrono::Duration::weeks(query.weeks_ago as i64);
+    let keys = state.history.lock().await.completed_keys();
+    Json(vault::vault_week(&keys, region, start, &state.config.private_players))
+}
+
+/// Best DPS per boss and spec, fastest keys and most deaths over the history, optionally
+/// for one season (`?season=season 2`) or difficulty (`?difficulty=mythic +10`)
+async fn local_leaderboards(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<LeaderboardFilter>,
+) -> Json<Leaderboards> {
+    let encounters = state.history.lock().await.leaderboard_encounters();
+    Json(leaderboard::leaderboards(&encounters, &filter, &state.config.private_players))
+}
+
+/// Which characters and specs did best on one boss or dungeon, with a suggested roster
+/// (`?encounter=queen ansurek&difficulty=mythic`)
+async fn roster_planner(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PlannerQuery>,
+) -> Json<Planner> {
+    let encounters = state.history.lock().await.leaderboard_encounters();
+    Json(planner::plan(&encounters, &query, &state.config.private_players))
+}
+
+/// Deaths, avoidable damage and DPS of raid pulls by the hour of the night, over the history
+/// or its last few reset weeks (`?weeks=4`)
+async fn raid_fatigue(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FatigueQuery>,
+) -> Json<Fatigue> {
+    let region = query.region.unwrap_or(state.config.region);
+    let pulls = state.history.lock().await.fatigue_pulls();
+    Json(fatigue::fatigue(&pulls, &query, region, chrono::Utc::now()))
+}
+
+/// Every run of one dungeon in the history added up: time per boss, death hotspots, timed
+/// rate per key level and group comps
+async fn dungeon_stats(
+    State(state): State<Arc<AppState>>,
+    Path(zone_id): Path<u64>,
+) -> Result<Json<dungeon_stats::DungeonStats>, (StatusCode, String)> {
+    let runs = state.history.lock().await.dungeon_runs(zone_id);
+    dungeon_stats::dungeon_stats(zone_id, &runs)
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, format!("No runs of dungeon {} in the history", zone_id)))
+}
+
+/// Keep a log out of the retention policy's reach
+async fn add_favorite(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    set_favorite(&state, &filename, true).await
+}
+
+async fn remove_favorite(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    set_favorite(&state, &filename, false).await
+}
+
+async fn set_favorite(state: &AppState, filename: &str, favorite: bool) -> Result<StatusCode, (StatusCode, String)> {
+    state.history.lock().await
+        .set_favorite(filename, favorite)
+        .map_err(|e| {
+            tracing::error!(filename = %filename, "failed to save favorites: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save favorites: {}", e))
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A character's boss pulls and keys over the history with the spec and talents of each, and
+/// where they changed builds with how they did before and after
+async fn character_builds(
+    State(state): State<Arc<AppState>>,
+    Path(character): Path<String>,
+) -> Result<Json<BuildTimeline>, (StatusCode, String)> {
+    let not_found = || (StatusCode::NOT_FOUND, format!("No runs of {} in the history", character));
+    if anonymize::is_listed(&character, &state.config.private_players) {
+        return Err(not_found());
+    }
+    let runs = state.history.lock().await.build_runs(&character);
+    builds::timeline(&character, runs).map(Json).ok_or_else(not_found)
+}
+
+async fn list_character_aliases(State(state): State<Arc<AppState>>) -> Json<Vec<CharacterAlias>> {
+    Json(state.history.lock().await.aliases().to_vec())
+}
+
+/// Merge a name or GUID into another character for the history views (averages, vault,
+/// leaderboards, calendar)
+async fn set_character_alias(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CharacterAlias>,
+) -> Result<Json<CharacterAlias>, (StatusCode, String)> {
+    let (alias, character) = (req.alias.trim(), req.character.trim());
+    if alias.is_empty() || character.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Alias and character are required".to_string()));
+    }
+    if parser::eq_ignore_case(alias, character) {
+        return Err((StatusCode::BAD_REQUEST, "A character can't be an alias of itself".to_string()));
+    }
+    let entry = state.history.lock().await
+        .set_alias(alias, character)
+        .map_err(|e| {
+            tracing::error!(alias = %alias, "failed to save character alias: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save alias: {}", e))
+        })?;
+    tracing::info!(alias = %entry.alias, character = %entry.character, "merged character");
+    Ok(Json(entry))
+}
+
+async fn remove_character_alias(
+    State(state): State<Arc<AppState>>,
+    Path(alias): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let removed = state.history.lock().await
+        .remove_alias(&alias)
+        .map_err(|e| {
+            tracing::error!(alias = %alias, "failed to save character aliases: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save aliases: {}", e))
+        })?;
+    if removed { Ok(StatusCode::NO_CONTENT) } else { Err((StatusCode::NOT_FOUND, "Alias not found".to_string())) }
+}
+
+/// Raid sessions and Mythic+ nights from the history as an iCalendar feed
+async fn session_calendar(State(state): State<Arc<AppState>>) -> impl axum::response::IntoResponse {
+    let encounters = state.history.lock().await.calendar_encounters();
+    let ics = calendar::calendar(&encounters, &state.config.private_players);
+    ([(axum::http::header::CONTENT_TYPE, "text/calendar; charset=utf-8")], ics)
+}
+
+#[derive(Deserialize)]
+struct SpellSearchQuery {
+    id: u64,
+    file: String,
+}
+
+/// Every encounter of a log where a spell was cast, dealt damage, healed or applied an aura
+async fn search_spell(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SpellSearchQuery>,
+) -> Result<Json<SpellSearchResult>, (StatusCode, String)> {
+    let filename = query.file;
+    // Sanitize filename
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+
+    let path = state.log_index.find(&filename)
+        .ok_or((StatusCode::NOT_FOUND, "Log file not found".to_string()))?;
+
+    let start = std::time::Instant::now();
+    let search_path = path.clone();
+    let (spell_name, hits) = tokio::task::spawn_blocking(move || parser::find_spell_events(&search_path, query.id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task failed: {}", e)))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    // Encounter boundaries come from the parse; make sure it's current
+    ensure_cached(&state, &filename, path).await?;
+
+    let mut result = {
+        let cache = state.cache.lock().await;
+        let encounters = cache.get(&filename).map(|(_, summary)| summary.encounters.as_slice()).unwrap_or_default();
+        parser::group_spell_hits(query.id, spell_name, &hits, encounters)
+    };
+    anonymize::redact_spell_search(&mut result, &state.config.private_players);
+    tracing::info!(filename = %filename, spell_id = query.id, hits = hits.len(), elapsed_secs = start.elapsed().as_secs_f64(), "spell search");
+    Ok(Json(result))
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
     if bytes >= 1_073_741_824 {
         format!("{:.1} GB", bytes as f64 / 1_073_741_824.0)
     } else if bytes >= 1_048_576 {
@@ -342,7 +2328,7 @@ fn extract_date_from_filename(filename: &str) -> String {
     let name = filename.trim_start_matches("WoWCombatLog-").trim_end_matches(".txt");
     let parts: Vec<&str> = name.split('_').collect();
     if let Some(date_part) = parts.first() {
-        if date_part.len() == 6 {
+        if date_part.len() == 6 && date_part.bytes().all(|b| b.is_ascii_digit()) {
             let month = &date_part[0..2];
             let day = &date_part[2..4];
             let year = &date_part[4..6];
@@ -352,20 +2338,3 @@ fn extract_date_from_filename(filename: &str) -> String {
     "Unknown".to_string()
 }
 
-/// Recursively search for a file by name in a directory tree
-fn find_file_recursive(dir: &std::path::Path, target: &str) -> Option<std::path::PathBuf> {
-    let mut dirs = vec![dir.to_path_buf()];
-    while let Some(d) = dirs.pop() {
-        if let Ok(entries) = std::fs::read_dir(&d) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    dirs.push(path);
-                } else if path.file_name().and_then(|n| n.to_str()) == Some(target) {
-                    return Some(path);
-                }
-            }
-        }
-    }
-    None
-}
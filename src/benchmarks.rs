@@ -0,0 +1,60 @@
+//! The spec benchmark table players are annotated with (see `fast_wow_parser::benchmarks`):
+//! the bundled one, or a newer one fetched from `benchmarks_url` in config.json
+//!
+//! A fetched table is checked once a day and kept in the app data dir, so it survives
+//! restarts and a day without network. It only replaces the current one if it is newer.
+
+use fast_wow_parser::benchmarks::BenchmarkTable;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Time between fetches of the table
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const BENCHMARKS_FILE: &str = "spec_benchmarks.json";
+
+/// The table in use, shared by the request handlers
+pub struct Benchmarks {
+    table: RwLock<Arc<BenchmarkTable>>,
+}
+
+impl Benchmarks {
+    /// The table fetched last time, if newer than the bundled one
+    pub fn load() -> Self {
+        let builtin = BenchmarkTable::builtin();
+        let saved = std::fs::read_to_string(benchmarks_path()).ok()
+            .and_then(|contents| serde_json::from_str::<BenchmarkTable>(&contents).ok())
+            .filter(|saved| saved.updated > builtin.updated);
+        Benchmarks { table: RwLock::new(Arc::new(saved.unwrap_or(builtin))) }
+    }
+
+    pub fn table(&self) -> Arc<BenchmarkTable> {
+        self.table.read().unwrap().clone()
+    }
+
+    /// Fetch the table at `url`; a newer one than in use replaces it and is saved. Returns
+    /// whether it did.
+    pub async fn refresh(&self, url: &str) -> Result<bool, String> {
+        let response = reqwest::get(url).await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+        let contents = response.text().await.map_err(|e| format!("Failed to read {}: {}", url, e))?;
+        let table: BenchmarkTable = serde_json::from_str(&contents).map_err(|e| format!("Invalid table from {}: {}", url, e))?;
+        if table.updated <= self.table().updated {
+            return Ok(false);
+        }
+        let path = benchmarks_path();
+        if let Err(e) = crate::history::write_durably(&path, contents.as_bytes()) {
+            tracing::warn!("Couldn't save {}: {}", path.display(), e);
+        }
+        tracing::info!(updated = %table.updated, specs = table.specs.len(), "spec benchmarks refreshed");
+        *self.table.write().unwrap() = Arc::new(table);
+        Ok(true)
+    }
+}
+
+/// `%LOCALAPPDATA%\wowlogger\spec_benchmarks.json`, next to the history
+fn benchmarks_path() -> PathBuf {
+    crate::history::history_path().with_file_name(BENCHMARKS_FILE)
+}
@@ -0,0 +1,6 @@
+//! Spell tooltip fetcher and cooldown table generator; see `wowlogger::spell_fetcher`
+
+#[tokio::main]
+async fn main() {
+    wowlogger::spell_fetcher::run().await;
+}
@@ -0,0 +1,173 @@
+//! A character's builds over the history: every boss pull and key they were in with the spec
+//! and talents they had, and where either changed, with how they did on the build before and
+//! after ("did the new build actually improve my numbers")
+//!
+//! Talents are compared by the entries picked, not their ranks, so moving a point within a
+//! talent isn't a change. Runs without talents (no COMBATANT_INFO, or records from before they
+//! were kept) carry the last known ones. Output is compared with the character's own average
+//! on the same boss and difficulty (or dungeon and key level), like the fatigue curves, so a
+//! new build tried on an easier boss doesn't look better by itself.
+
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::vault::log_time;
+
+/// A boss pull or key the character was in, from the history
+pub struct BuildRun {
+    pub stable_id: String,
+    pub name: String,
+    pub difficulty_name: String,
+    pub start_time: String,
+    pub success: bool,
+    /// What their output is compared within (same boss and difficulty)
+    pub bracket: Option<String>,
+    /// "Fire Mage"
+    pub spec: String,
+    pub role: String,
+    pub talents: Vec<u32>,
+    pub dps: f64,
+    pub hps: f64,
+}
+
+#[derive(Serialize)]
+pub struct BuildTimeline {
+    pub character: String,
+    /// Oldest first
+    pub runs: Vec<TimelineRun>,
+    /// Oldest first
+    pub changes: Vec<BuildChange>,
+}
+
+#[derive(Serialize)]
+pub struct TimelineRun {
+    pub stable_id: String,
+    pub name: String,
+    pub difficulty_name: String,
+    pub start_time: String,
+    pub success: bool,
+    pub spec: String,
+    pub dps: f64,
+    pub hps: f64,
+    /// DPS (HPS for healers) as a percentage of their own average in the same bracket; none
+    /// where they were only seen once
+    pub relative_output_pct: Option<f64>,
+    /// Which build it was on, counting from 0; goes up by one at each change
+    pub build: u32,
+}
+
+/// A spec or talent change, marked at the first run on the new build
+#[derive(Serialize)]
+pub struct BuildChange {
+    pub stable_id: String,
+    pub start_time: String,
+    pub spec_before: String,
+    pub spec_after: String,
+    /// Talent entry IDs picked and dropped; both empty for a spec change without talents known
+    pub talents_added: Vec<u32>,
+    pub talents_removed: Vec<u32>,
+    /// Runs on the build before and after, until the change before and after it
+    pub runs_before: u32,
+    pub runs_after: u32,
+    /// Average `relative_output_pct` on the build before and after
+    pub relative_output_before_pct: Option<f64>,
+    pub relative_output_after_pct: Option<f64>,
+}
+
+fn output(run: &BuildRun) -> f64 {
+    if run.role == "healer" { run.hps } else { run.dps }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Sorted entries in `after` but not `before`
+fn missing_from(before: &[u32], after: &[u32]) -> Vec<u32> {
+    after.iter().filter(|t| before.binary_search(t).is_err()).copied().collect()
+}
+
+/// The build timeline of `character` from their `runs`; `None` without any
+pub fn timeline(character: &str, runs: Vec<BuildRun>) -> Option<BuildTimeline> {
+    let now = Utc::now();
+    let mut runs: Vec<_> = runs.into_iter()
+        .filter_map(|run| Some((log_time(&run.start_time, now)?, run)))
+        .collect();
+    if runs.is_empty() {
+        return None;
+    }
+    runs.sort_by_key(|(start, _)| *start);
+
+    // Own average per bracket, over the runs with any output
+    let mut sums: HashMap<&str, (f64, u32)> = HashMap::new();
+    for (_, run) in &runs {
+        if let Some(bracket) = run.bracket.as_deref().filter(|_| output(run) > 0.0) {
+            let (sum, count) = sums.entry(bracket).or_default();
+            *sum += output(run);
+            *count += 1;
+        }
+    }
+    let relative = |run: &BuildRun| {
+        let (sum, count) = *sums.get(run.bracket.as_deref()?)?;
+        (count > 1 && output(run) > 0.0).then(|| output(run) / (sum / count as f64) * 100.0)
+    };
+
+    let mut timeline = Vec::with_capacity(runs.len());
+    let mut changes = Vec::new();
+    let mut spec = String::new();
+    let mut talents: Vec<u32> = Vec::new();
+    let mut build = 0;
+    for (_, run) in &runs {
+        let new_spec = !run.spec.is_empty() && !spec.is_empty() && run.spec != spec;
+        let new_talents = !run.talents.is_empty() && !talents.is_empty() && run.talents != talents;
+        if new_spec || new_talents {
+            build += 1;
+            changes.push(BuildChange {
+                stable_id: run.stable_id.clone(),
+                start_time: run.start_time.clone(),
+                spec_before: spec.clone(),
+                spec_after: if run.spec.is_empty() { spec.clone() } else { run.spec.clone() },
+                talents_added: if new_talents { missing_from(&talents, &run.talents) } else { Vec::new() },
+                talents_removed: if new_talents { missing_from(&run.talents, &talents) } else { Vec::new() },
+                runs_before: 0,
+                runs_after: 0,
+                relative_output_before_pct: None,
+                relative_output_after_pct: None,
+            });
+        }
+        if !run.spec.is_empty() {
+            spec = run.spec.clone();
+        }
+        if !run.talents.is_empty() {
+            talents = run.talents.clone();
+        }
+        timeline.push(TimelineRun {
+            stable_id: run.stable_id.clone(),
+            name: run.name.clone(),
+            difficulty_name: run.difficulty_name.clone(),
+            start_time: run.start_time.clone(),
+            success: run.success,
+            spec: run.spec.clone(),
+            dps: run.dps,
+            hps: run.hps,
+            relative_output_pct: relative(run),
+            build,
+        });
+    }
+
+    for (i, change) in changes.iter_mut().enumerate() {
+        let on_build = |build: usize| timeline.iter().filter(move |run| run.build as usize == build);
+        change.runs_before = on_build(i).count() as u32;
+        change.runs_after = on_build(i + 1).count() as u32;
+        change.relative_output_before_pct = mean(on_build(i).filter_map(|run| run.relative_output_pct));
+        change.relative_output_after_pct = mean(on_build(i + 1).filter_map(|run| run.relative_output_pct));
+    }
+
+    Some(BuildTimeline {
+        character: character.to_string(),
+        runs: timeline,
+        changes,
+    })
+}
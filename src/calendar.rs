@@ -0,0 +1,166 @@
+//! iCalendar feed of raid sessions and Mythic+ nights, for mirroring attendance and
+//! progress into a guild calendar
+//!
+//! Boss pulls and keys from the history are grouped into sessions: a new one starts after an
+//! hour without an encounter, and raids and keys are kept apart.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::vault::log_time;
+
+/// Quiet time that ends a session
+pub(crate) const SESSION_GAP: Duration = Duration::hours(1);
+
+/// A boss pull or key from the history
+pub struct CalendarEncounter {
+    pub stable_id: String,
+    pub name: String,
+    pub instance_name: Option<String>,
+    pub difficulty_name: String,
+    /// Whether it is a Mythic+ key rather than a raid boss
+    pub key: bool,
+    pub start_time: String,
+    pub duration_secs: f64,
+    pub success: bool,
+    /// Keys only
+    pub timed: Option<bool>,
+    pub players: Vec<String>,
+}
+
+struct Session<'a> {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    encounters: Vec<&'a CalendarEncounter>,
+}
+
+/// Sessions of consecutive raid pulls or keys, oldest first
+fn sessions<'a>(encounters: &'a [CalendarEncounter], now: DateTime<Utc>) -> Vec<Session<'a>> {
+    let mut timed: Vec<(DateTime<Utc>, &CalendarEncounter)> = encounters.iter()
+        .filter_map(|enc| Some((log_time(&enc.start_time, now)?, enc)))
+        .collect();
+    timed.sort_by_key(|(start, _)| *start);
+    let mut sessions: Vec<Session> = Vec::new();
+    for (start, enc) in timed {
+        let end = start + Duration::milliseconds((enc.duration_secs * 1000.0) as i64);
+        let open = sessions.iter_mut().rev()
+            .find(|s| s.encounters[0].key == enc.key)
+            .filter(|s| start - s.end < SESSION_GAP);
+        match open {
+            Some(session) => {
+                session.end = session.end.max(end);
+                session.encounters.push(enc);
+            }
+            None => sessions.push(Session { start, end, encounters: vec![enc] }),
+        }
+    }
+    sessions
+}
+
+impl Session<'_> {
+    fn summary(&self) -> String {
+        if self.encounters[0].key {
+            let timed = self.encounters.iter().filter(|e| e.timed == Some(true)).count();
+            let keys = self.encounters.len();
+            return format!("Mythic+: {} {} ({} timed)", keys, if keys == 1 { "key" } else { "keys" }, timed);
+        }
+        let mut instances: Vec<String> = Vec::new();
+        for enc in &self.encounters {
+            let instance = format!("{} {}", enc.difficulty_name, enc.instance_name.as_deref().unwrap_or("Raid"));
+            if !instances.contains(&instance) {
+                instances.push(instance);
+            }
+        }
+        let kills = self.kills().len();
+        format!("{}: {} {} killed, {} pulls", instances.join(" / "), kills, if kills == 1 { "boss" } else { "bosses" }, self.encounters.len())
+    }
+
+    /// Bosses killed, in kill order
+    fn kills(&self) -> Vec<&str> {
+        let mut kills: Vec<&str> = Vec::new();
+        for enc in self.encounters.iter().filter(|e| e.success) {
+            if !kills.contains(&enc.name.as_str()) {
+                kills.push(&enc.name);
+            }
+        }
+        kills
+    }
+
+    fn description(&self, hidden: &[String]) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        if self.encounters[0].key {
+            for enc in &self.encounters {
+                let result = match enc.timed {
+                    Some(true) => "timed",
+                    _ if enc.success => "over time",
+                    _ => "abandoned",
+                };
+                lines.push(format!("{} ({}, {})", enc.name, result, format_duration(enc.duration_secs)));
+            }
+        } else {
+            let kills = self.kills();
+            lines.push(format!("Bosses killed: {}", if kills.is_empty() { "none".to_string() } else { kills.join(", ") }));
+            lines.push(format!("Wipes: {}", self.encounters.iter().filter(|e| !e.success).count()));
+        }
+        let mut players: Vec<&str> = Vec::new();
+        for player in self.encounters.iter().flat_map(|e| &e.players) {
+            if !players.contains(&player.as_str()) && !fast_wow_parser::anonymize::is_listed(player, hidden) {
+                players.push(player);
+            }
+        }
+        lines.push(format!("Players: {}", players.join(", ")));
+        lines.join("\n")
+    }
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Escape a TEXT value (RFC 5545 3.3.11)
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Write a content line, folded at 75 octets (RFC 5545 3.1)
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// The feed, with one event per session; players in `hidden` (the private players) are
+/// left off the attendance
+pub fn calendar(encounters: &[CalendarEncounter], hidden: &[String]) -> String {
+    let now = Utc::now();
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//wowlogger//Fast WoW Combat Analyser//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "X-WR-CALNAME:WoW sessions");
+    for session in sessions(encounters, now) {
+        push_line(&mut out, "BEGIN:VEVENT");
+        // The first encounter's ID keeps the event stable as later logs extend the history
+        push_line(&mut out, &format!("UID:{}@wowlogger", session.encounters[0].stable_id));
+        push_line(&mut out, &format!("DTSTAMP:{}", ics_time(now)));
+        push_line(&mut out, &format!("DTSTART:{}", ics_time(session.start)));
+        push_line(&mut out, &format!("DTEND:{}", ics_time(session.end)));
+        push_line(&mut out, &format!("SUMMARY:{}", escape_text(&session.summary())));
+        push_line(&mut out, &format!("DESCRIPTION:{}", escape_text(&session.description(hidden))));
+        push_line(&mut out, "END:VEVENT");
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
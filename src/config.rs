@@ -0,0 +1,147 @@
+//! App configuration from `config.json` next to the executable
+
+use fast_wow_parser::models::EncounterSummary;
+use fast_wow_parser::ParseOptions;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const CONFIG_FILE: &str = "config.json";
+
+/// Everything is optional; a missing file or field keeps the defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// The Logs folder to watch; found from the WoW install when unset
+    pub log_dir: Option<PathBuf>,
+    /// Which game version's Logs folder to pick when several are found ("retail", "classic",
+    /// "classic_era", "ptr", ...)
+    pub game: Option<String>,
+    /// Parser tunables (death recap window, event caps)
+    pub parse: ParseOptions,
+    /// Players (name or Name-Realm) whose individual numbers and breakdowns are never
+    /// served; they show up as one combined "Private" row
+    pub private_players: Vec<String>,
+    /// Which encounters are too small to list unless asked for
+    pub trivial: TrivialFilter,
+    /// Game region, for when the weekly reset happens
+    pub region: Region,
+    /// What happens to old raw logs once their encounters are in the history
+    pub retention: RetentionPolicy,
+    /// Where to fetch a newer spec benchmark table from, daily; the bundled one is used without
+    pub benchmarks_url: Option<String>,
+    /// Serve the web app from this folder (e.g. `frontend/dist`) instead of the copy built into
+    /// the executable, uncached, for working on the frontend; also `--frontend-dir <path>`
+    pub frontend_dir: Option<PathBuf>,
+    /// The end-of-night report, written (and posted) on a schedule
+    pub night_report: NightReportConfig,
+}
+
+/// Game region; EU resets on Wednesday morning, the US (and Oceania) on Tuesday
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    #[default]
+    Eu,
+    Us,
+}
+
+/// Housekeeping for the Logs folder (see `retention`). Off unless enabled, as it removes files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub enabled: bool,
+    /// Logs last written to longer ago than this are deleted
+    pub after_days: u32,
+    pub action: RetentionAction,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy { enabled: false, after_days: 30, action: RetentionAction::Delete }
+    }
+}
+
+/// When the end-of-night report runs and where it goes (see `night_report`). Off unless enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NightReportConfig {
+    pub enabled: bool,
+    /// Local time it runs at, "HH:MM"; it covers the 24 hours before
+    pub at: String,
+    /// Discord webhook URL the report is posted to; without it the report is only saved
+    pub discord_webhook: Option<String>,
+}
+
+impl Default for NightReportConfig {
+    fn default() -> Self {
+        NightReportConfig { enabled: false, at: "04:00".to_string(), discord_webhook: None }
+    }
+}
+
+/// What is done with a log past its retention
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    /// "compress", from before compressing was dropped (the app couldn't open a gzipped log),
+    /// is read as delete
+    #[default]
+    #[serde(alias = "compress")]
+    Delete,
+}
+
+/// Accidental pulls and instant resets: hidden from the encounter list unless the
+/// summary is requested with `?include_trivial=true`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrivialFilter {
+    /// Encounters shorter than this are trivial
+    pub min_duration_secs: f64,
+    /// Encounters where the group dealt less damage than this are trivial
+    pub min_damage: u64,
+}
+
+impl Default for TrivialFilter {
+    fn default() -> Self {
+        TrivialFilter { min_duration_secs: 10.0, min_damage: 0 }
+    }
+}
+
+impl TrivialFilter {
+    /// Boss kills and Mythic+ keys are never trivial, however short
+    pub fn is_trivial(&self, enc: &EncounterSummary) -> bool {
+        let boss_kill = enc.success && matches!(enc.encounter_type.as_str(), "boss" | "dungeon");
+        if boss_kill || enc.encounter_type == "mythic_plus" {
+            return false;
+        }
+        let damage: u64 = enc.players.iter().map(|p| p.damage_done).sum();
+        enc.duration_secs < self.min_duration_secs || damage < self.min_damage
+    }
+}
+
+/// `config.json` next to the executable, falling back to the working directory
+pub fn config_path() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(CONFIG_FILE)))
+        .filter(|p| p.exists())
+        .unwrap_or_else(|| CONFIG_FILE.into())
+}
+
+pub fn load() -> AppConfig {
+    let path = config_path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return AppConfig::default();
+    };
+    match serde_json::from_str::<AppConfig>(&contents) {
+        Ok(mut config) => {
+            tracing::info!("Loaded config from {}", path.display());
+            // Parse options compare against the normalized ones of each request
+            config.parse = config.parse.normalized();
+            config
+        }
+        Err(e) => {
+            tracing::warn!("Ignoring {}: {}", path.display(), e);
+            AppConfig::default()
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Exports served as resumable downloads: `Accept-Ranges` on every response, and a `Range`
+//! request (one range) answered with 206 Partial Content, so a big workbook cut off on a flaky
+//! LAN or phone connection carries on where it stopped instead of starting over
+//!
+//! An export is built again for every request, so each response carries an ETag of its bytes;
+//! with `If-Range` the rest is only sent as a range if it is still the same file, and in full
+//! otherwise. Exports must come out byte for byte the same from the same log for that to work.
+
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+/// What a request asks for out of `len` bytes
+#[derive(Debug, PartialEq)]
+enum Requested {
+    Full,
+    /// First and last byte, inclusive
+    Range(u64, u64),
+    Unsatisfiable,
+}
+
+/// `data` as a download named `name`, whole or the range the request asks for
+pub fn attachment(data: Vec<u8>, name: &str, content_type: &str, request: &HeaderMap) -> Response {
+    let disposition = [(header::CONTENT_DISPOSITION, content_disposition(name))];
+    (disposition, resumable(data, content_type, request)).into_response()
+}
+
+/// `data` shown in the browser rather than saved (a text export), whole or the range the
+/// request asks for
+pub fn inline(data: Vec<u8>, content_type: &str, request: &HeaderMap) -> Response {
+    resumable(data, content_type, request)
+}
+
+fn resumable(data: Vec<u8>, content_type: &str, request: &HeaderMap) -> Response {
+    let etag = etag(&data);
+    let len = data.len() as u64;
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::ACCEPT_RANGES, "bytes".to_string()),
+        (header::ETAG, etag.clone()),
+    ];
+    match requested(request, len, &etag) {
+        Requested::Full => (headers, data).into_response(),
+        Requested::Range(first, last) => {
+            let range = [(header::CONTENT_RANGE, format!("bytes {}-{}/{}", first, last, len))];
+            let part = data[first as usize..=last as usize].to_vec();
+            (StatusCode::PARTIAL_CONTENT, headers, range, part).into_response()
+        }
+        Requested::Unsatisfiable => {
+            let range = [(header::CONTENT_RANGE, format!("bytes */{}", len))];
+            (StatusCode::RANGE_NOT_SATISFIABLE, headers, range).into_response()
+        }
+    }
+}
+
+/// Percent-encode everything but unreserved characters (RFC 3986)
+pub fn encode_path_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// `Content-Disposition` of a download named `name`. Header values are ASCII, so a name with
+/// other characters (a split log named after a boss in the client's language) goes in
+/// `filename*` as UTF-8 (RFC 6266), after an ASCII `filename` for clients without it.
+fn content_disposition(name: &str) -> String {
+    let ascii: String = name.chars()
+        .map(|c| if (c.is_ascii_graphic() || c == ' ') && c != '"' && c != '\\' { c } else { '_' })
+        .collect();
+    if ascii == name {
+        return format!("attachment; filename=\"{}\"", name);
+    }
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", ascii, encode_path_segment(name))
+}
+
+/// Stable for the same bytes, across requests, restarts and builds: 64-bit FNV-1a, like
+/// `stable_encounter_id`
+fn etag(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("\"{:016x}-{:x}\"", hash, data.len())
+}
+
+/// The range asked for; a `Range` the server can't honour (several ranges, bad syntax, or an
+/// `If-Range` for other bytes) gets the whole file, as RFC 9110 allows
+fn requested(request: &HeaderMap, len: u64, etag: &str) -> Requested {
+    let Some(range) = request.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return Requested::Full;
+    };
+    if request.get(header::IF_RANGE).and_then(|v| v.to_str().ok()).is_some_and(|tag| tag.trim() != etag) {
+        return Requested::Full;
+    }
+    parse_range(range, len)
+}
+
+/// `bytes=first-last`, `bytes=first-` or `bytes=-suffix_length`
+fn parse_range(range: &str, len: u64) -> Requested {
+    let Some(spec) = range.trim().strip_prefix("bytes=") else {
+        return Requested::Full;
+    };
+    if spec.contains(',') {
+        return Requested::Full;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return Requested::Full;
+    };
+    let (first, last) = match (first.trim(), last.trim()) {
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return Requested::Unsatisfiable,
+            Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            Err(_) => return Requested::Full,
+        },
+        (first, "") => match first.parse::<u64>() {
+            Ok(first) => (first, len.saturating_sub(1)),
+            Err(_) => return Requested::Full,
+        },
+        (first, last) => match (first.parse::<u64>(), last.parse::<u64>()) {
+            (Ok(first), Ok(last)) if first <= last => (first, last.min(len.saturating_sub(1))),
+            _ => return Requested::Full,
+        },
+    };
+    if len == 0 || first >= len {
+        return Requested::Unsatisfiable;
+    }
+    Requested::Range(first, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn range_request(range: &str, if_range: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_str(range).unwrap());
+        if let Some(tag) = if_range {
+            headers.insert(header::IF_RANGE, HeaderValue::from_str(tag).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn reads_first_last_and_open_ranges() {
+        assert_eq!(parse_range("bytes=0-9", 100), Requested::Range(0, 9));
+        assert_eq!(parse_range("bytes=90-", 100), Requested::Range(90, 99));
+        // A last byte past the end is cut to the end
+        assert_eq!(parse_range("bytes=90-500", 100), Requested::Range(90, 99));
+    }
+
+    #[test]
+    fn reads_suffix_ranges() {
+        assert_eq!(parse_range("bytes=-10", 100), Requested::Range(90, 99));
+        // Longer than the file: all of it
+        assert_eq!(parse_range("bytes=-500", 100), Requested::Range(0, 99));
+        assert_eq!(parse_range("bytes=-0", 100), Requested::Unsatisfiable);
+    }
+
+    #[test]
+    fn refuses_a_range_past_the_end() {
+        assert_eq!(parse_range("bytes=100-", 100), Requested::Unsatisfiable);
+        assert_eq!(parse_range("bytes=150-200", 100), Requested::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), Requested::Unsatisfiable);
+        let response = inline(vec![0; 100], "text/plain", &range_request("bytes=150-", None));
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */100");
+    }
+
+    #[test]
+    fn sends_everything_for_ranges_it_cannot_serve() {
+        assert_eq!(parse_range("bytes=0-9,20-29", 100), Requested::Full);
+        assert_eq!(parse_range("bytes=9-0", 100), Requested::Full);
+        assert_eq!(parse_range("items=0-9", 100), Requested::Full);
+        assert_eq!(parse_range("bytes=a-b", 100), Requested::Full);
+        let response = inline(vec![0; 100], "text/plain", &range_request("bytes=0-9,20-29", None));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn serves_the_range_only_while_if_range_matches() {
+        let data = b"0123456789".to_vec();
+        let tag = etag(&data);
+        assert_eq!(requested(&range_request("bytes=5-", Some(&tag)), 10, &tag), Requested::Range(5, 9));
+        assert_eq!(requested(&range_request("bytes=5-", Some("\"stale\"")), 10, &tag), Requested::Full);
+
+        let response = inline(data.clone(), "text/plain", &range_request("bytes=5-", Some(&tag)));
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 5-9/10");
+        let response = inline(data, "text/plain", &range_request("bytes=5-", Some("\"stale\"")));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn names_ascii_downloads_plainly() {
+        assert_eq!(content_disposition("WoWCombatLog-030225_210000.txt"), "attachment; filename=\"WoWCombatLog-030225_210000.txt\"");
+        assert_eq!(content_disposition("night report.xlsx"), "attachment; filename=\"night report.xlsx\"");
+    }
+
+    #[test]
+    fn names_other_downloads_in_utf8_with_an_ascii_fallback() {
+        assert_eq!(
+            content_disposition("Königin Ansurek.txt"),
+            "attachment; filename=\"K_nigin Ansurek.txt\"; filename*=UTF-8''K%C3%B6nigin%20Ansurek.txt"
+        );
+        // Quotes and backslashes can't go in the quoted name
+        assert_eq!(
+            content_disposition("a\"b\\c.txt"),
+            "attachment; filename=\"a_b_c.txt\"; filename*=UTF-8''a%22b%5Cc.txt"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Every run of one dungeon in the history added up, to help a team pick which key to push:
+//! time per boss, where people die, how often each key level was timed and which group
+//! comps ran it
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Group comps listed, most runs first
+const TOP_COMPS: usize = 5;
+
+/// A run of the dungeon from the history
+pub struct DungeonRun {
+    pub name: String,
+    pub key_level: u32,
+    /// Finished, in time or not (abandoned keys aren't)
+    pub completed: bool,
+    pub timed: bool,
+    pub duration_secs: f64,
+    /// Boss-to-boss sections and their duration
+    pub sections: Vec<(String, f64)>,
+    /// Deaths per section; empty for runs recorded before deaths were kept
+    pub section_deaths: Vec<(String, u32)>,
+    /// "Fire Mage" per player
+    pub specs: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DungeonStats {
+    pub zone_id: u64,
+    pub name: String,
+    pub runs: u32,
+    pub timed: u32,
+    pub timed_pct: f64,
+    /// Over completed runs
+    pub average_duration_secs: Option<f64>,
+    /// Boss-to-boss sections in dungeon order, each named after the boss ending it
+    pub bosses: Vec<SectionTime>,
+    /// Sections by deaths per run, most first
+    pub death_hotspots: Vec<SectionDeaths>,
+    /// Lowest level first
+    pub key_levels: Vec<KeyLevelStats>,
+    /// Most runs first
+    pub group_comps: Vec<GroupComp>,
+}
+
+#[derive(Serialize)]
+pub struct SectionTime {
+    pub name: String,
+    pub runs: u32,
+    pub average_secs: f64,
+    pub best_secs: f64,
+}
+
+#[derive(Serialize)]
+pub struct SectionDeaths {
+    pub name: String,
+    /// Runs with deaths recorded for the section
+    pub runs: u32,
+    pub deaths: u32,
+    pub deaths_per_run: f64,
+}
+
+#[derive(Serialize)]
+pub struct KeyLevelStats {
+    pub key_level: u32,
+    pub runs: u32,
+    pub completed: u32,
+    pub timed: u32,
+    pub timed_pct: f64,
+}
+
+#[derive(Serialize)]
+pub struct GroupComp {
+    /// Sorted, e.g. ["Blood Death Knight", "Fire Mage", ...]
+    pub specs: Vec<String>,
+    pub runs: u32,
+    pub timed: u32,
+    pub timed_pct: f64,
+}
+
+fn pct(part: u32, whole: u32) -> f64 {
+    if whole > 0 { part as f64 / whole as f64 * 100.0 } else { 0.0 }
+}
+
+/// Stats over `runs` of one dungeon; None without any
+pub fn dungeon_stats(zone_id: u64, runs: &[DungeonRun]) -> Option<DungeonStats> {
+    let name = runs.first()?.name.clone();
+    let count = runs.len() as u32;
+    let timed = runs.iter().filter(|r| r.timed).count() as u32;
+    let completed: Vec<&DungeonRun> = runs.iter().filter(|r| r.completed).collect();
+
+    // Section times from completed runs only; an abandoned key stops partway through one
+    let mut bosses: Vec<(String, Vec<f64>)> = Vec::new();
+    for (section, secs) in completed.iter().flat_map(|r| &r.sections) {
+        match bosses.iter_mut().find(|(name, _)| name == section) {
+            Some((_, times)) => times.push(*secs),
+            None => bosses.push((section.clone(), vec![*secs])),
+        }
+    }
+
+    let mut hotspots: Vec<SectionDeaths> = Vec::new();
+    for (section, deaths) in runs.iter().flat_map(|r| &r.section_deaths) {
+        let hotspot = match hotspots.iter_mut().position(|h| h.name == *section) {
+            Some(i) => &mut hotspots[i],
+            None => {
+                hotspots.push(SectionDeaths { name: section.clone(), runs: 0, deaths: 0, deaths_per_run: 0.0 });
+                hotspots.last_mut().unwrap()
+            }
+        };
+        hotspot.runs += 1;
+        hotspot.deaths += deaths;
+    }
+    for hotspot in &mut hotspots {
+        hotspot.deaths_per_run = hotspot.deaths as f64 / hotspot.runs.max(1) as f64;
+    }
+    hotspots.retain(|h| h.deaths > 0);
+    hotspots.sort_by(|a, b| b.deaths_per_run.partial_cmp(&a.deaths_per_run).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut key_levels: Vec<KeyLevelStats> = Vec::new();
+    for run in runs {
+        let level = match key_levels.iter().position(|l| l.key_level == run.key_level) {
+            Some(i) => &mut key_levels[i],
+            None => {
+                key_levels.push(KeyLevelStats { key_level: run.key_level, runs: 0, completed: 0, timed: 0, timed_pct: 0.0 });
+                key_levels.last_mut().unwrap()
+            }
+        };
+        level.runs += 1;
+        level.completed += run.completed as u32;
+        level.timed += run.timed as u32;
+    }
+    for level in &mut key_levels {
+        level.timed_pct = pct(level.timed, level.runs);
+    }
+    key_levels.sort_by_key(|l| l.key_level);
+
+    let mut comps: HashMap<Vec<String>, (u32, u32)> = HashMap::new();
+    for run in runs.iter().filter(|r| !r.specs.is_empty()) {
+        let mut specs = run.specs.clone();
+        specs.sort();
+        let comp = comps.entry(specs).or_default();
+        comp.0 += 1;
+        comp.1 += run.timed as u32;
+    }
+    let mut group_comps: Vec<GroupComp> = comps.into_iter()
+        .map(|(specs, (runs, timed))| GroupComp { specs, runs, timed, timed_pct: pct(timed, runs) })
+        .collect();
+    group_comps.sort_by(|a, b| b.runs.cmp(&a.runs).then(b.timed.cmp(&a.timed)).then_with(|| a.specs.cmp(&b.specs)));
+    group_comps.truncate(TOP_COMPS);
+
+    Some(DungeonStats {
+        zone_id,
+        name,
+        runs: count,
+        timed,
+        timed_pct: pct(timed, count),
+        average_duration_secs: (!completed.is_empty())
+            .then(|| completed.iter().map(|r| r.duration_secs).sum::<f64>() / completed.len() as f64),
+        bosses: bosses.into_iter().map(|(name, times)| SectionTime {
+            name,
+            runs: times.len() as u32,
+            average_secs: times.iter().sum::<f64>() / times.len() as f64,
+            best_secs: times.iter().copied().fold(f64::INFINITY, f64::min),
+        }).collect(),
+        death_hotspots: hotspots,
+        key_levels,
+        group_comps,
+    })
+}
@@ -0,0 +1,221 @@
+//! How a raid night wears on the group: deaths, avoidable damage and DPS of boss pulls by the
+//! hour, across every night in the history and week by week, to help decide when to call it
+//!
+//! Pulls are grouped into nights like the calendar's raid sessions (a new one starts after an
+//! hour without a pull). DPS is compared with each player's own average on the same boss and
+//! difficulty, so a late night on the last, hardest boss doesn't read as fatigue by itself.
+
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::calendar::SESSION_GAP;
+use crate::config::Region;
+use crate::vault::{self, log_time};
+
+/// Pulls an hour needs before it can mark the drop-off
+const MIN_HOUR_PULLS: u32 = 5;
+
+/// Fall in relative DPS from the first hour, in percentage points, that marks the drop-off
+const DPS_DROP_PCT: f64 = 5.0;
+
+/// Rise in deaths per pull from the first hour that marks the drop-off (1.5 = half again)
+const DEATHS_RISE: f64 = 1.5;
+
+/// A raid boss pull from the history
+pub struct FatiguePull {
+    pub start_time: String,
+    /// What the players' DPS is compared within (same boss and difficulty)
+    pub bracket: Option<String>,
+    pub success: bool,
+    /// Damage taken by the whole group, and how much of it was avoidable; both 0 for records
+    /// from before they were kept
+    pub damage_taken: u64,
+    pub avoidable_damage: u64,
+    pub players: Vec<FatiguePlayer>,
+}
+
+pub struct FatiguePlayer {
+    pub name: String,
+    pub dps: f64,
+    pub deaths: u32,
+}
+
+#[derive(Default, Deserialize)]
+pub struct FatigueQuery {
+    /// Only the last this many reset weeks (this one included)
+    pub weeks: Option<u32>,
+    pub region: Option<Region>,
+}
+
+#[derive(Serialize)]
+pub struct Fatigue {
+    pub nights: u32,
+    pub pulls: u32,
+    /// By hours into the night, first hour first
+    pub by_night_hour: Vec<FatiguePoint>,
+    /// By local clock hour the pull started in, evening first
+    pub by_clock_hour: Vec<FatiguePoint>,
+    /// First hour into the night where DPS falls or deaths rise clearly past the first hour's;
+    /// none while the night holds up (or there are too few pulls to tell)
+    pub drop_off_hour: Option<u32>,
+    /// Oldest first
+    pub weeks: Vec<WeekFatigue>,
+}
+
+/// One reset week's nights by the hour
+#[derive(Serialize)]
+pub struct WeekFatigue {
+    /// The reset that started it (RFC 3339, UTC)
+    pub week_start: String,
+    pub nights: u32,
+    pub pulls: u32,
+    pub by_night_hour: Vec<FatiguePoint>,
+}
+
+#[derive(Serialize)]
+pub struct FatiguePoint {
+    /// Hours into the night, or the clock hour (0-23)
+    pub hour: u32,
+    pub pulls: u32,
+    pub kills: u32,
+    pub deaths_per_pull: f64,
+    /// Share of damage taken that was avoidable; none without pulls that kept it
+    pub avoidable_pct: Option<f64>,
+    /// Players' DPS as a percentage of their own average on the same boss and difficulty; none
+    /// without players seen there more than once
+    pub relative_dps_pct: Option<f64>,
+}
+
+#[derive(Default)]
+struct Totals {
+    pulls: u32,
+    kills: u32,
+    deaths: u32,
+    damage_taken: u64,
+    avoidable_damage: u64,
+    relative_dps: Vec<f64>,
+}
+
+impl Totals {
+    fn add(&mut self, pull: &FatiguePull, relative_dps: Option<f64>) {
+        self.pulls += 1;
+        self.kills += pull.success as u32;
+        self.deaths += pull.players.iter().map(|p| p.deaths).sum::<u32>();
+        self.damage_taken += pull.damage_taken;
+        self.avoidable_damage += pull.avoidable_damage.min(pull.damage_taken);
+        self.relative_dps.extend(relative_dps);
+    }
+
+    fn point(&self, hour: u32) -> FatiguePoint {
+        FatiguePoint {
+            hour,
+            pulls: self.pulls,
+            kills: self.kills,
+            deaths_per_pull: self.deaths as f64 / self.pulls as f64,
+            avoidable_pct: (self.damage_taken > 0).then(|| self.avoidable_damage as f64 / self.damage_taken as f64 * 100.0),
+            relative_dps_pct: (!self.relative_dps.is_empty())
+                .then(|| self.relative_dps.iter().sum::<f64>() / self.relative_dps.len() as f64),
+        }
+    }
+}
+
+/// Average DPS of each player in each bracket, over the pulls they did damage in; players seen
+/// there only once are left out, as they'd only be compared with themselves
+fn bracket_averages<'a>(pulls: &[&'a FatiguePull]) -> HashMap<(&'a str, &'a str), f64> {
+    let mut sums: HashMap<(&str, &str), (f64, u32)> = HashMap::new();
+    for pull in pulls {
+        let Some(bracket) = pull.bracket.as_deref() else { continue };
+        for player in pull.players.iter().filter(|p| p.dps > 0.0) {
+            let (sum, count) = sums.entry((bracket, &player.name)).or_default();
+            *sum += player.dps;
+            *count += 1;
+        }
+    }
+    sums.into_iter()
+        .filter(|(_, (_, count))| *count > 1)
+        .map(|(key, (sum, count))| (key, sum / count as f64))
+        .collect()
+}
+
+/// The pull's DPS against its players' averages, in percent
+fn relative_dps(pull: &FatiguePull, averages: &HashMap<(&str, &str), f64>) -> Option<f64> {
+    let bracket = pull.bracket.as_deref()?;
+    let ratios: Vec<f64> = pull.players.iter()
+        .filter(|p| p.dps > 0.0)
+        .filter_map(|p| Some(p.dps / averages.get(&(bracket, p.name.as_str()))? * 100.0))
+        .collect();
+    (!ratios.is_empty()).then(|| ratios.iter().sum::<f64>() / ratios.len() as f64)
+}
+
+/// Night hours with pulls, first hour first
+fn points(hours: BTreeMap<u32, Totals>) -> Vec<FatiguePoint> {
+    hours.iter().map(|(&hour, totals)| totals.point(hour)).collect()
+}
+
+/// First hour past the first one with enough pulls where DPS falls `DPS_DROP_PCT` points or
+/// deaths per pull rise by `DEATHS_RISE` times
+fn drop_off_hour(by_night_hour: &[FatiguePoint]) -> Option<u32> {
+    let mut hours = by_night_hour.iter().filter(|p| p.pulls >= MIN_HOUR_PULLS);
+    let first = hours.next()?;
+    hours.find(|p| {
+        let dps_drop = matches!((first.relative_dps_pct, p.relative_dps_pct), (Some(a), Some(b)) if a - b >= DPS_DROP_PCT);
+        let deaths_rise = first.deaths_per_pull > 0.0 && p.deaths_per_pull >= first.deaths_per_pull * DEATHS_RISE;
+        dps_drop || deaths_rise
+    }).map(|p| p.hour)
+}
+
+/// The fatigue curves of `pulls` in the last `query.weeks` reset weeks of `region`
+pub fn fatigue(pulls: &[FatiguePull], query: &FatigueQuery, region: Region, now: DateTime<Utc>) -> Fatigue {
+    let since = query.weeks.map(|weeks| vault::week_start(region, now) - Duration::weeks(weeks.saturating_sub(1) as i64));
+    let mut timed: Vec<(DateTime<Utc>, &FatiguePull)> = pulls.iter()
+        .filter_map(|pull| Some((log_time(&pull.start_time, now)?, pull)))
+        .filter(|(start, _)| since.is_none_or(|since| *start >= since))
+        .collect();
+    timed.sort_by_key(|(start, _)| *start);
+    let averages = bracket_averages(&timed.iter().map(|(_, pull)| *pull).collect::<Vec<_>>());
+
+    let mut by_night_hour: BTreeMap<u32, Totals> = BTreeMap::new();
+    let mut by_clock_hour: BTreeMap<u32, Totals> = BTreeMap::new();
+    let mut weeks: BTreeMap<DateTime<Utc>, (u32, u32, BTreeMap<u32, Totals>)> = BTreeMap::new();
+    let mut nights = 0;
+    let mut night: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+    for (start, pull) in &timed {
+        let night_start = match night {
+            Some((night_start, last)) if *start - last < SESSION_GAP => night_start,
+            _ => {
+                nights += 1;
+                weeks.entry(vault::week_start(region, *start)).or_default().0 += 1;
+                *start
+            }
+        };
+        night = Some((night_start, *start));
+
+        let hour = (*start - night_start).num_hours() as u32;
+        let clock_hour = start.with_timezone(&Local).hour();
+        let relative = relative_dps(pull, &averages);
+        by_night_hour.entry(hour).or_default().add(pull, relative);
+        by_clock_hour.entry(clock_hour).or_default().add(pull, relative);
+        let week = weeks.entry(vault::week_start(region, *start)).or_default();
+        week.1 += 1;
+        week.2.entry(hour).or_default().add(pull, relative);
+    }
+
+    let by_night_hour = points(by_night_hour);
+    // Clock hours from noon, so a night running past midnight stays in order
+    let mut by_clock_hour = points(by_clock_hour);
+    by_clock_hour.sort_by_key(|p| (p.hour + 12) % 24);
+    Fatigue {
+        nights,
+        pulls: timed.len() as u32,
+        drop_off_hour: drop_off_hour(&by_night_hour),
+        by_night_hour,
+        by_clock_hour,
+        weeks: weeks.into_iter().map(|(week_start, (nights, pulls, hours))| WeekFatigue {
+            week_start: week_start.to_rfc3339(),
+            nights,
+            pulls,
+            by_night_hour: points(hours),
+        }).collect(),
+    }
+}
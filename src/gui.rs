@@ -1,6 +1,12 @@
-use std::path::PathBuf;
 use std::sync::{Arc, Mutex, OnceLock};
 use tokio::sync::Notify;
+use wowlogger::jobs::{JobStatus, Jobs};
+use wowlogger::integrity::Integrity;
+use wowlogger::notices::Notices;
+use wowlogger::lan;
+use wowlogger::retention::Retention;
+use wowlogger::settings::Settings;
+use wowlogger::startup;
 
 use windows::core::*;
 use windows::Win32::Foundation::*;
@@ -30,12 +36,34 @@ struct ODS_FLAGS(pub u32);
 const ID_OPEN: i32 = 101;
 const ID_STOP: i32 = 102;
 const ID_CHANGE: i32 = 103;
+/// Timer refreshing the parse, housekeeping and startup check status lines
+const TIMER_JOBS: usize = 1;
 const WND_W: i32 = 500;
-const WND_H: i32 = 620;
+const WND_H: i32 = 855;
+/// Where the QR code of the LAN address is drawn
+const QR_X: i32 = 160;
+const QR_Y: i32 = 605;
+const QR_SIZE: i32 = 180;
+/// Window class, also how a second launch finds this window
+const WINDOW_CLASS: &str = "WowLogViewerCtrl";
 
 static SHUTDOWN: OnceLock<Arc<Notify>> = OnceLock::new();
 static PORT_NUM: OnceLock<u16> = OnceLock::new();
-static SHARED_LOG_DIR: OnceLock<Arc<Mutex<PathBuf>>> = OnceLock::new();
+static SETTINGS: OnceLock<Arc<Settings>> = OnceLock::new();
+static JOBS: OnceLock<Arc<Jobs>> = OnceLock::new();
+static RETENTION: OnceLock<Arc<Retention>> = OnceLock::new();
+static INTEGRITY: OnceLock<Arc<Integrity>> = OnceLock::new();
+static NOTICES: OnceLock<Arc<Notices>> = OnceLock::new();
+/// HWND of the parse status label
+static JOBS_LABEL_HWND: OnceLock<Mutex<isize>> = OnceLock::new();
+/// HWND of the housekeeping label, refreshed with the parse status
+static RETENTION_LABEL_HWND: OnceLock<Mutex<isize>> = OnceLock::new();
+/// HWND of the startup check label, refreshed with the parse status
+static INTEGRITY_LABEL_HWND: OnceLock<Mutex<isize>> = OnceLock::new();
+/// HWND of the notices label, refreshed with the parse status
+static NOTICES_LABEL_HWND: OnceLock<Mutex<isize>> = OnceLock::new();
+/// QR code modules of the LAN address, for opening the app on a phone
+static PHONE_QR: OnceLock<Option<Vec<Vec<bool>>>> = OnceLock::new();
 /// HWND of the directory label so we can update its text
 /// Raw HWND pointer as isize (Send+Sync safe)
 static DIR_LABEL_HWND: OnceLock<Mutex<isize>> = OnceLock::new();
@@ -45,38 +73,72 @@ fn wide(s: &str) -> Vec<u16> {
 }
 
 /// Run the native Win32 GUI window (blocks until closed)
-pub fn run(shutdown: Arc<Notify>, log_dir: Arc<Mutex<PathBuf>>, port: u16) {
+pub fn run(shutdown: Arc<Notify>, settings: Arc<Settings>, port: u16, jobs: Arc<Jobs>, retention: Arc<Retention>, integrity: Arc<Integrity>, notices: Arc<Notices>) {
     SHUTDOWN.set(shutdown).ok();
     PORT_NUM.set(port).ok();
-    SHARED_LOG_DIR.set(log_dir).ok();
+    SETTINGS.set(settings).ok();
+    JOBS.set(jobs).ok();
+    RETENTION.set(retention).ok();
+    INTEGRITY.set(integrity).ok();
+    NOTICES.set(notices).ok();
     DIR_LABEL_HWND.set(Mutex::new(0)).ok();
+    JOBS_LABEL_HWND.set(Mutex::new(0)).ok();
+    RETENTION_LABEL_HWND.set(Mutex::new(0)).ok();
+    INTEGRITY_LABEL_HWND.set(Mutex::new(0)).ok();
+    NOTICES_LABEL_HWND.set(Mutex::new(0)).ok();
     unsafe { create_and_run() };
 }
 
+/// Bring the window of an instance that is already running to the front
+pub fn focus_existing() {
+    let cls = wide(WINDOW_CLASS);
+    unsafe {
+        if let Ok(hwnd) = FindWindowW(PCWSTR(cls.as_ptr()), PCWSTR::null()) {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+            let _ = SetForegroundWindow(hwnd);
+        }
+    }
+}
+
 unsafe fn get_instance() -> HINSTANCE {
     let h = GetModuleHandleW(PCWSTR::null()).unwrap_or_default();
     HINSTANCE(h.0 as _)
 }
 
 fn dir_display_text() -> String {
-    let dir = SHARED_LOG_DIR
+    let dir = SETTINGS
         .get()
-        .map(|d| d.lock().unwrap().display().to_string())
+        .map(|s| s.log_dir().display().to_string())
         .unwrap_or_default();
-    let short = if dir.len() > 46 {
-        format!("{}...", &dir[..46])
+    // By characters: a user folder in Cyrillic or Korean is several bytes per letter
+    let short = if dir.chars().count() > 46 {
+        format!("{}...", dir.chars().take(46).collect::<String>())
     } else {
         dir
     };
     format!("Logs: {}", short)
 }
 
+/// What the server is parsing, e.g. "Parsing WoWCombatLog.txt: 42% (1 queued)"
+fn jobs_status_text() -> String {
+    let active = JOBS.get().map(|jobs| jobs.active()).unwrap_or_default();
+    let queued = active.iter().filter(|job| job.status == JobStatus::Queued).count();
+    let Some(running) = active.iter().find(|job| job.status == JobStatus::Running) else {
+        return if queued > 0 { format!("{} parse(s) queued", queued) } else { String::new() };
+    };
+    let mut text = format!("Parsing {}: {:.0}%", running.filename, running.progress_pct);
+    if queued > 0 {
+        text.push_str(&format!(" ({} queued)", queued));
+    }
+    text
+}
+
 unsafe fn create_and_run() {
     let instance = get_instance();
-    let cls = wide("WowLogViewerCtrl");
+    let cls = wide(WINDOW_CLASS);
 
     // Load the embedded icon resource
-    let icon = LoadIconW(instance, PCWSTR(1 as _)).unwrap_or_default();
+    let icon = startup::time("window icon", || LoadIconW(instance, PCWSTR(1 as _)).unwrap_or_default());
 
     let wc = WNDCLASSEXW {
         cbSize: size_of::<WNDCLASSEXW>() as u32,
@@ -117,6 +179,34 @@ unsafe fn create_and_run() {
     }
 }
 
+/// Draw `modules` as a black-on-white QR code in a `size`-pixel square, with the quiet zone
+/// scanners need around it
+unsafe fn draw_qr(hdc: HDC, modules: &[Vec<bool>], x: i32, y: i32, size: i32) {
+    const QUIET_ZONE: i32 = 4;
+    let count = modules.len() as i32 + QUIET_ZONE * 2;
+    let module = (size / count).max(1);
+    let offset = (size - module * count) / 2;
+
+    let white = CreateSolidBrush(COLORREF(0x00FFFFFF));
+    let mut background = RECT { left: x, top: y, right: x + size, bottom: y + size };
+    FillRect(hdc, &mut background, white);
+    let _ = DeleteObject(white);
+
+    let black = CreateSolidBrush(COLORREF(0x00000000));
+    for (row, line) in modules.iter().enumerate() {
+        for (col, &dark) in line.iter().enumerate() {
+            if !dark {
+                continue;
+            }
+            let left = x + offset + (col as i32 + QUIET_ZONE) * module;
+            let top = y + offset + (row as i32 + QUIET_ZONE) * module;
+            let mut cell = RECT { left, top, right: left + module, bottom: top + module };
+            FillRect(hdc, &mut cell, black);
+        }
+    }
+    let _ = DeleteObject(black);
+}
+
 unsafe fn make_font(height: i32, bold: bool) -> HFONT {
     let weight = if bold { 700 } else { 400 };
     let face = wide("Segoe UI");
@@ -223,13 +313,13 @@ unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPARAM)
 
             // Logo icon at the top
             let inst = get_instance();
-            let icon_handle = LoadImageW(
+            let icon_handle = startup::time("logo icon", || LoadImageW(
                 inst,
                 PCWSTR(1 as _),
                 IMAGE_ICON,
                 256, 256,
                 LR_DEFAULTCOLOR,
-            );
+            ));
             if let Ok(icon_h) = icon_handle {
                 if let Ok(static_hwnd) = CreateWindowExW(
                     WINDOW_EX_STYLE::default(),
@@ -262,8 +352,40 @@ unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPARAM)
             add_button(hwnd, "Open in Browser", 20, 400, 222, 44, ID_OPEN, font);
             add_button(hwnd, "Stop Server", 254, 400, 222, 44, ID_STOP, font);
 
+            // Parse status, refreshed by TIMER_JOBS
+            let jobs_hwnd = add_label(hwnd, "", 20, 470, 460, 20, font_sm, true);
+            if let Some(lock) = JOBS_LABEL_HWND.get() {
+                *lock.lock().unwrap() = jobs_hwnd.0 as isize;
+            }
+            // What the retention policy did with old logs
+            let retention_hwnd = add_label(hwnd, "", 20, 495, 460, 20, font_sm, true);
+            if let Some(lock) = RETENTION_LABEL_HWND.get() {
+                *lock.lock().unwrap() = retention_hwnd.0 as isize;
+            }
+            // What the startup check of the history repaired or found
+            let integrity_hwnd = add_label(hwnd, "", 20, 520, 460, 20, font_sm, true);
+            if let Some(lock) = INTEGRITY_LABEL_HWND.get() {
+                *lock.lock().unwrap() = integrity_hwnd.0 as isize;
+            }
+            // Settings to fix in the game, e.g. advanced combat logging
+            let notices_hwnd = add_label(hwnd, "", 20, 545, 460, 20, font_sm, true);
+            if let Some(lock) = NOTICES_LABEL_HWND.get() {
+                *lock.lock().unwrap() = notices_hwnd.0 as isize;
+            }
+            SetTimer(hwnd, TIMER_JOBS, 500, None);
+
+            // Opening the app on a phone: the LAN address as a QR code (drawn in WM_PAINT),
+            // and the name it is advertised under over mDNS
+            let lan_url = lan::lan_url(port);
+            PHONE_QR.set(lan_url.as_deref().and_then(lan::qr_code)).ok();
+            let phone_text = match &lan_url {
+                Some(url) => format!("On your phone: {}  or  {}", url, lan::mdns_url(port)),
+                None => format!("On your phone: {}", lan::mdns_url(port)),
+            };
+            add_label(hwnd, &phone_text, 20, 577, 460, 20, font_sm, true);
+
             // Credits
-            add_label(hwnd, "Made with \u{2665} by D4GGe  \u{2022}  v0.4.0", 20, 530, 460, 20, font_sm, true);
+            add_label(hwnd, "Made with \u{2665} by D4GGe  \u{2022}  v0.4.0", 20, 797, 460, 20, font_sm, true);
 
             LRESULT(0)
         }
@@ -281,9 +403,9 @@ unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPARAM)
                 }
                 ID_CHANGE => {
                     // Open folder picker to change log directory
-                    let current = SHARED_LOG_DIR
+                    let current = SETTINGS
                         .get()
-                        .map(|d| d.lock().unwrap().display().to_string())
+                        .map(|s| s.log_dir().display().to_string())
                         .unwrap_or_default();
 
                     if let Some(new_path) = rfd::FileDialog::new()
@@ -291,9 +413,9 @@ unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPARAM)
                         .set_directory(&current)
                         .pick_folder()
                     {
-                        // Update the shared log_dir
-                        if let Some(shared) = SHARED_LOG_DIR.get() {
-                            *shared.lock().unwrap() = new_path;
+                        // The server drops its caches and re-watches the new folder
+                        if let Some(settings) = SETTINGS.get() {
+                            settings.set_log_dir(new_path);
                         }
                         // Update the label text
                         if let Some(lock) = DIR_LABEL_HWND.get() {
@@ -310,6 +432,49 @@ unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPARAM)
             }
             LRESULT(0)
         }
+        WM_TIMER if wp.0 == TIMER_JOBS => {
+            if let Some(lock) = JOBS_LABEL_HWND.get() {
+                let raw = *lock.lock().unwrap();
+                if raw != 0 {
+                    let text = wide(&jobs_status_text());
+                    let _ = SetWindowTextW(HWND(raw as _), PCWSTR(text.as_ptr()));
+                }
+            }
+            if let Some(lock) = RETENTION_LABEL_HWND.get() {
+                let raw = *lock.lock().unwrap();
+                if raw != 0 {
+                    let status = RETENTION.get().map(|r| r.status_text()).unwrap_or_default();
+                    let text = wide(&status);
+                    let _ = SetWindowTextW(HWND(raw as _), PCWSTR(text.as_ptr()));
+                }
+            }
+            if let Some(lock) = INTEGRITY_LABEL_HWND.get() {
+                let raw = *lock.lock().unwrap();
+                if raw != 0 {
+                    let status = INTEGRITY.get().map(|i| i.status_text()).unwrap_or_default();
+                    let text = wide(&status);
+                    let _ = SetWindowTextW(HWND(raw as _), PCWSTR(text.as_ptr()));
+                }
+            }
+            if let Some(lock) = NOTICES_LABEL_HWND.get() {
+                let raw = *lock.lock().unwrap();
+                if raw != 0 {
+                    let status = NOTICES.get().map(|n| n.status_text()).unwrap_or_default();
+                    let text = wide(&status);
+                    let _ = SetWindowTextW(HWND(raw as _), PCWSTR(text.as_ptr()));
+                }
+            }
+            LRESULT(0)
+        }
+        WM_PAINT => {
+            let mut ps = PAINTSTRUCT::default();
+            let hdc = BeginPaint(hwnd, &mut ps);
+            if let Some(Some(modules)) = PHONE_QR.get() {
+                draw_qr(hdc, modules, QR_X, QR_Y, QR_SIZE);
+            }
+            let _ = EndPaint(hwnd, &ps);
+            LRESULT(0)
+        }
         WM_CLOSE => {
             if let Some(s) = SHUTDOWN.get() {
                 s.notify_one();
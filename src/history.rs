@@ -0,0 +1,912 @@
+//! History database: data that outlives the parse cache — encounter notes, replay
+//! bookmarks, character aliases, favorite logs and what each log was parsed from, and an index of
+//! every parsed encounter (which log it came from, for short links and search, how each
+//! player did and on which spec and talents, for comparing them against their own average and
+//! earlier builds, and how keys went, for comparing a depleted key against the best run of its
+//! dungeon)
+//!
+//! An encounter found in two logs (a copied log, or logging restarted mid-fight) has two
+//! records; every history view counts only the longest one.
+//!
+//! Stored as a single JSON file in the app data dir, rewritten on every change.
+
+use fast_wow_parser::{anonymize, compare};
+use fast_wow_parser::parser::eq_ignore_case;
+use fast_wow_parser::models::{BestRun, Bookmark, EncounterNotes, EncounterSummary, PerformanceDelta};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::builds::BuildRun;
+use crate::calendar::CalendarEncounter;
+use crate::dungeon_stats::DungeonRun;
+use crate::fatigue::{FatiguePlayer, FatiguePull};
+use crate::leaderboard::{LeaderboardEncounter, LeaderboardPlayer};
+use crate::vault::{self, CompletedKey};
+
+const HISTORY_FILE: &str = "history.json";
+
+/// Earlier encounters a player's average is taken over, most recent first
+const ROLLING_AVERAGE_ENCOUNTERS: usize = 10;
+
+/// Records of the same boss or key with the same players starting this close together are
+/// one encounter logged twice
+const DUPLICATE_START_SECS: i64 = 5;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct HistoryData {
+    /// "<log filename>#<stable encounter ID>" -> notes
+    notes: HashMap<String, EncounterNotes>,
+    /// "<log filename>#<stable encounter ID>" -> bookmarks, in replay order
+    bookmarks: HashMap<String, Vec<Bookmark>>,
+    /// Stable encounter ID -> where and what it was, for `/e/<id>` links and search
+    encounters: HashMap<String, EncounterRecord>,
+    /// Names and GUIDs the user merged into another character, sorted by alias
+    aliases: Vec<CharacterAlias>,
+    /// Log filenames the retention policy never touches, sorted
+    favorites: Vec<String>,
+    /// Log filename -> the file as it was when its encounters were last recorded
+    parsed_logs: HashMap<String, ParsedLog>,
+}
+
+/// A log's size and last write time when it was parsed, to tell whether it grew since
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParsedLog {
+    pub size: u64,
+    /// Milliseconds since the Unix epoch
+    pub modified_ms: u64,
+}
+
+impl ParsedLog {
+    pub fn of(metadata: &std::fs::Metadata) -> Option<Self> {
+        let modified = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+        Some(ParsedLog { size: metadata.len(), modified_ms: modified.as_millis() as u64 })
+    }
+}
+
+/// One name ("Name-OldRealm", "Name") or player GUID of a character that history views
+/// should count as another, e.g. after a realm transfer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterAlias {
+    pub alias: String,
+    /// The name it is shown and counted as
+    pub character: String,
+}
+
+/// What the history keeps about a parsed encounter
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct EncounterRecord {
+    filename: String,
+    #[serde(default)]
+    encounter_id: u64,
+    name: String,
+    instance_name: Option<String>,
+    #[serde(default)]
+    season: Option<String>,
+    difficulty_name: String,
+    encounter_type: String,
+    start_time: String,
+    #[serde(default)]
+    duration_secs: f64,
+    #[serde(default)]
+    group_size: u32,
+    success: bool,
+    players: Vec<String>,
+    /// What the players' numbers are compared within (see `bracket`)
+    #[serde(default)]
+    bracket: Option<String>,
+    #[serde(default)]
+    performance: Vec<PlayerPerformance>,
+    /// Damage taken by the whole group, and how much of it was avoidable
+    #[serde(default)]
+    damage_taken: u64,
+    #[serde(default)]
+    avoidable_damage: u64,
+    /// Mythic+ keys only
+    #[serde(default)]
+    key: Option<KeyRecord>,
+    /// Parser that indexed it; another version's record is replaced when its log is parsed again
+    #[serde(default)]
+    parser_version: String,
+    /// Summary schema it was indexed from; records of another schema are dropped on startup
+    #[serde(default)]
+    schema_version: u32,
+}
+
+/// How a Mythic+ key went
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct KeyRecord {
+    zone_id: u64,
+    key_level: u32,
+    timed: bool,
+    duration_secs: f64,
+    enemy_forces: u32,
+    /// Boss-to-boss sections and their duration
+    sections: Vec<(String, f64)>,
+    /// Deaths in each section
+    #[serde(default)]
+    section_deaths: Vec<(String, u32)>,
+}
+
+/// A player's throughput in a recorded encounter
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct PlayerPerformance {
+    guid: String,
+    #[serde(default)]
+    name: String,
+    /// "Fire Mage"
+    #[serde(default)]
+    spec: String,
+    /// "tank", "healer" or "dps"; empty in records from before it was kept
+    #[serde(default)]
+    role: String,
+    /// Talent entries picked; empty without COMBATANT_INFO or from before they were kept
+    #[serde(default)]
+    talents: Vec<u32>,
+    dps: f64,
+    hps: f64,
+    #[serde(default)]
+    deaths: u32,
+}
+
+/// Encounters a player's numbers are comparable across: the same boss on the same
+/// difficulty, or a key of the same dungeon and level
+fn bracket(enc: &EncounterSummary) -> Option<String> {
+    match enc.encounter_type.as_str() {
+        "boss" => Some(format!("boss:{}:{}", enc.encounter_id, enc.difficulty_id)),
+        "mythic_plus" => enc.key_level.map(|level| format!("key:{}:{}", enc.encounter_id, level)),
+        _ => None,
+    }
+}
+
+/// Percent `value` is above `average` (negative when below)
+fn delta_pct(value: f64, average: f64) -> f64 {
+    if average > 0.0 { (value - average) / average * 100.0 } else { 0.0 }
+}
+
+impl EncounterRecord {
+    /// Whether both are the same encounter, from two log files (a copied log, or logging
+    /// restarted mid-fight): same boss or dungeon, start within a few seconds, same players
+    fn same_encounter(&self, other: &EncounterRecord, now: DateTime<Utc>) -> bool {
+        if self.encounter_type != other.encounter_type || self.encounter_id != other.encounter_id || self.name != other.name {
+            return false;
+        }
+        let (Some(a), Some(b)) = (vault::log_time(&self.start_time, now), vault::log_time(&other.start_time, now)) else {
+            return false;
+        };
+        let players = |r: &EncounterRecord| r.players.iter().map(|p| p.to_lowercase()).collect::<BTreeSet<_>>();
+        (a - b).num_seconds().abs() <= DUPLICATE_START_SECS && players(self) == players(other)
+    }
+
+    fn new(filename: &str, enc: &EncounterSummary) -> Self {
+        EncounterRecord {
+            filename: filename.to_string(),
+            encounter_id: enc.encounter_id,
+            name: enc.name.clone(),
+            instance_name: enc.instance_name.clone(),
+            season: enc.season.clone(),
+            difficulty_name: enc.difficulty_name.clone(),
+            encounter_type: enc.encounter_type.clone(),
+            start_time: enc.start_time.clone(),
+            duration_secs: enc.duration_secs,
+            group_size: enc.group_size,
+            success: enc.success,
+            players: enc.players.iter().map(|p| p.name.clone()).collect(),
+            bracket: bracket(enc),
+            performance: enc.players.iter()
+                .map(|p| PlayerPerformance {
+                    guid: p.guid.clone(),
+                    name: p.name.clone(),
+                    spec: format!("{} {}", p.spec_name, p.class_name).trim().to_string(),
+                    role: p.role.clone(),
+                    talents: p.talents.clone(),
+                    dps: p.dps,
+                    hps: p.hps,
+                    deaths: p.deaths,
+                })
+                .collect(),
+            damage_taken: enc.players.iter().map(|p| p.damage_taken).sum(),
+            avoidable_damage: enc.avoidable_damage.total_damage,
+            key: (enc.encounter_type == "mythic_plus").then(|| KeyRecord {
+                zone_id: enc.encounter_id,
+                key_level: enc.key_level.unwrap_or(0),
+                timed: enc.timed.unwrap_or(false),
+                duration_secs: enc.duration_secs,
+                enemy_forces: compare::enemy_forces(enc),
+                sections: compare::section_times(enc),
+                section_deaths: compare::section_deaths(enc),
+            }),
+            parser_version: fast_wow_parser::VERSION.to_string(),
+            schema_version: fast_wow_parser::SCHEMA_VERSION,
+        }
+    }
+}
+
+/// A search match, best first
+#[derive(Serialize)]
+pub struct SearchHit {
+    pub stable_id: String,
+    pub filename: String,
+    pub name: String,
+    pub instance_name: Option<String>,
+    pub difficulty_name: String,
+    pub encounter_type: String,
+    pub start_time: String,
+    pub success: bool,
+    /// Players whose names matched a search term
+    pub matched_players: Vec<String>,
+    pub score: u32,
+}
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const MAX_SEARCH_HITS: usize = 50;
+
+/// Lowercased alphanumeric words ("Ky'veza" -> "kyveza")
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split_whitespace()
+        .map(|w| w.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect::<String>())
+        .filter(|w| !w.is_empty())
+}
+
+fn matches_words(text: &str, term: &str) -> bool {
+    words(text).any(|w| w.starts_with(term))
+}
+
+/// Month name, day or year of a log timestamp ("2/14/2025 20:15:03.123": feb, 14, 2025)
+fn date_matches(start_time: &str, term: &str) -> bool {
+    let (year, month, day, _) = recency_key(start_time);
+    let month_name = MONTHS.get((month as usize).wrapping_sub(1));
+    month_name.is_some_and(|name| term.starts_with(name))
+        || term.parse::<u32>().is_ok_and(|n| n == day || (year > 0 && n == year))
+}
+
+pub struct History {
+    path: PathBuf,
+    data: HistoryData,
+    /// Why the history file couldn't be loaded, until `check` reports it
+    unreadable: Option<String>,
+    /// Changes the last save failed to write, for `flush` to retry
+    dirty: bool,
+}
+
+/// What `History::check` found
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoryCheck {
+    pub encounters: usize,
+    /// Problems fixed without losing anything the history views use
+    pub repaired: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Logs whose records were dropped as outdated, to index again
+    pub reindex: Vec<String>,
+}
+
+/// `%LOCALAPPDATA%\wowlogger\history.json` on Windows (the platform equivalent elsewhere)
+pub fn history_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("wowlogger")
+        .join(HISTORY_FILE)
+}
+
+fn notes_key(filename: &str, stable_id: &str) -> String {
+    format!("{}#{}", filename, stable_id)
+}
+
+impl History {
+    /// Load the history file, starting empty if it is missing or unreadable. An unreadable
+    /// file is moved aside to `history.json.corrupt` rather than overwritten by the next save.
+    pub fn load() -> Self {
+        let path = history_path();
+        let mut unreadable = None;
+        let data = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                let aside = path.with_extension("json.corrupt");
+                tracing::warn!("Ignoring {}: {}", path.display(), e);
+                unreadable = Some(match std::fs::rename(&path, &aside) {
+                    Ok(()) => format!("The history file was unreadable ({}); started a new one and kept the old one as {}", e, aside.display()),
+                    Err(rename_error) => format!("The history file was unreadable ({}) and couldn't be moved aside: {}", e, rename_error),
+                });
+                HistoryData::default()
+            }),
+            Err(_) => HistoryData::default(),
+        };
+        History { path, data, unreadable, dirty: false }
+    }
+
+    /// Validate the history against `logs` (the filenames of every raw log in the Logs
+    /// folder) and the running parser. Records that can't be used or were indexed from another
+    /// summary schema, and favorites of logs that are gone, are dropped; the rest is reported.
+    pub fn check(&mut self, logs: &HashSet<String>) -> HistoryCheck {
+        let mut check = HistoryCheck::default();
+        let mut changed = false;
+        check.repaired.extend(self.unreadable.take());
+
+        let tmp = self.path.with_extension("json.tmp");
+        if tmp.exists() {
+            match std::fs::remove_file(&tmp) {
+                Ok(()) => check.repaired.push("Removed a history save that was interrupted".to_string()),
+                Err(e) => check.warnings.push(format!("Couldn't remove {}: {}", tmp.display(), e)),
+            }
+        }
+
+        let now = Utc::now();
+        let before = self.data.encounters.len();
+        self.data.encounters.retain(|_, record| vault::log_time(&record.start_time, now).is_some());
+        let dropped = before - self.data.encounters.len();
+        if dropped > 0 {
+            check.repaired.push(format!("Dropped {} encounter record(s) without a readable start time", dropped));
+            changed = true;
+        }
+        let before = self.data.encounters.len();
+        let mut reindex = BTreeSet::new();
+        self.data.encounters.retain(|_, record| {
+            let current = record.schema_version == fast_wow_parser::SCHEMA_VERSION;
+            if !current && logs.contains(&record.filename) {
+                reindex.insert(record.filename.clone());
+            }
+            current
+        });
+        let outdated = before - self.data.encounters.len();
+        if outdated > 0 {
+            check.repaired.push(format!(
+                "Dropped {} encounter record(s) indexed by another parser version; {} log(s) are re-indexed in the background",
+                outdated,
+                reindex.len(),
+            ));
+            check.reindex = reindex.into_iter().collect();
+            changed = true;
+        }
+        check.encounters = self.data.encounters.len();
+
+        let before = self.data.favorites.len();
+        self.data.favorites.retain(|filename| logs.contains(filename));
+        let dropped = before - self.data.favorites.len();
+        if dropped > 0 {
+            check.repaired.push(format!("Unmarked {} favorite log(s) that are no longer in the Logs folder", dropped));
+            changed = true;
+        }
+        let before = self.data.parsed_logs.len();
+        self.data.parsed_logs.retain(|filename, _| logs.contains(filename));
+        changed |= self.data.parsed_logs.len() != before;
+
+        let missing = self.recorded_logs().into_iter().filter(|filename| !logs.contains(*filename)).count();
+        if missing > 0 {
+            check.warnings.push(format!(
+                "{} log(s) with encounters in the history are no longer in the Logs folder (moved or deleted); their encounter pages and short links won't open",
+                missing,
+            ));
+        }
+
+        if changed {
+            if let Err(e) = self.save() {
+                check.warnings.push(format!("Couldn't save the repaired history: {}", e));
+            }
+        }
+        check
+    }
+
+    pub fn notes(&self, filename: &str, stable_id: &str) -> Option<&EncounterNotes> {
+        self.data.notes.get(&notes_key(filename, stable_id))
+    }
+
+    /// Replace the notes for an encounter; empty text and tags remove them
+    pub fn set_notes(&mut self, filename: &str, stable_id: &str, notes: EncounterNotes) -> std::io::Result<()> {
+        let key = notes_key(filename, stable_id);
+        if notes.text.trim().is_empty() && notes.tags.is_empty() {
+            self.data.notes.remove(&key);
+        } else {
+            self.data.notes.insert(key, notes);
+        }
+        self.save()
+    }
+
+    pub fn bookmarks(&self, filename: &str, stable_id: &str) -> &[Bookmark] {
+        self.data.bookmarks.get(&notes_key(filename, stable_id)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Bookmark a point in an encounter; returns the new bookmark
+    pub fn add_bookmark(&mut self, filename: &str, stable_id: &str, name: String, offset_secs: f64) -> std::io::Result<Bookmark> {
+        let bookmarks = self.data.bookmarks.entry(notes_key(filename, stable_id)).or_default();
+        let bookmark = Bookmark {
+            id: bookmarks.iter().map(|b| b.id).max().unwrap_or(0) + 1,
+            name,
+            offset_secs,
+            created_at: chrono::Local::now().to_rfc3339(),
+        };
+        bookmarks.push(bookmark.clone());
+        bookmarks.sort_by(|a, b| a.offset_secs.partial_cmp(&b.offset_secs).unwrap_or(std::cmp::Ordering::Equal));
+        self.save()?;
+        Ok(bookmark)
+    }
+
+    /// Remove a bookmark; false if the encounter has none with that ID
+    pub fn remove_bookmark(&mut self, filename: &str, stable_id: &str, id: u32) -> std::io::Result<bool> {
+        let key = notes_key(filename, stable_id);
+        let Some(bookmarks) = self.data.bookmarks.get_mut(&key) else {
+            return Ok(false);
+        };
+        let before = bookmarks.len();
+        bookmarks.retain(|b| b.id != id);
+        if bookmarks.len() == before {
+            return Ok(false);
+        }
+        if bookmarks.is_empty() {
+            self.data.bookmarks.remove(&key);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn favorites(&self) -> &[String] {
+        &self.data.favorites
+    }
+
+    pub fn is_favorite(&self, filename: &str) -> bool {
+        self.data.favorites.binary_search_by(|f| f.as_str().cmp(filename)).is_ok()
+    }
+
+    /// Mark or unmark a log as a favorite
+    pub fn set_favorite(&mut self, filename: &str, favorite: bool) -> std::io::Result<()> {
+        match (self.data.favorites.binary_search_by(|f| f.as_str().cmp(filename)), favorite) {
+            (Err(at), true) => self.data.favorites.insert(at, filename.to_string()),
+            (Ok(at), false) => {
+                self.data.favorites.remove(at);
+            }
+            _ => return Ok(()),
+        }
+        self.save()
+    }
+
+    /// Logs with at least one encounter in the history
+    pub fn recorded_logs(&self) -> HashSet<&str> {
+        self.data.encounters.values().map(|record| record.filename.as_str()).collect()
+    }
+
+    /// Logs with at least one encounter in the history, as they were when last parsed
+    pub fn parsed_logs(&self) -> HashMap<String, ParsedLog> {
+        let recorded = self.recorded_logs();
+        self.data.parsed_logs.iter()
+            .filter(|(filename, _)| recorded.contains(filename.as_str()))
+            .map(|(filename, parsed)| (filename.clone(), *parsed))
+            .collect()
+    }
+
+    pub fn aliases(&self) -> &[CharacterAlias] {
+        &self.data.aliases
+    }
+
+    /// Count `alias` (a name or GUID, any case) as `character` from now on. Aliases stay one
+    /// level deep: a target that is itself an alias is followed, and aliases of `alias` move
+    /// to `character`.
+    pub fn set_alias(&mut self, alias: &str, character: &str) -> std::io::Result<CharacterAlias> {
+        self.data.aliases.retain(|a| {
+            let flipped = eq_ignore_case(&a.alias, character) && eq_ignore_case(&a.character, alias);
+            !eq_ignore_case(&a.alias, alias) && !flipped
+        });
+        let character = self.canonical_name(character);
+        for existing in &mut self.data.aliases {
+            if eq_ignore_case(&existing.character, alias) {
+                existing.character = character.clone();
+            }
+        }
+        let entry = CharacterAlias { alias: alias.to_string(), character };
+        self.data.aliases.push(entry.clone());
+        self.data.aliases.retain(|a| !eq_ignore_case(&a.alias, &a.character));
+        self.data.aliases.sort_by_key(|a| a.alias.to_lowercase());
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// Stop merging `alias`; false if it wasn't one
+    pub fn remove_alias(&mut self, alias: &str) -> std::io::Result<bool> {
+        let before = self.data.aliases.len();
+        self.data.aliases.retain(|a| !eq_ignore_case(&a.alias, alias));
+        if self.data.aliases.len() == before {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn alias_of(&self, name_or_guid: &str) -> Option<&str> {
+        self.data.aliases.iter()
+            .find(|a| eq_ignore_case(&a.alias, name_or_guid))
+            .map(|a| a.character.as_str())
+    }
+
+    /// The character a player name counts as: its alias, the alias of the name without realm,
+    /// or the name itself
+    pub fn canonical_name(&self, name: &str) -> String {
+        let bare = name.split('-').next().unwrap_or(name);
+        self.alias_of(name)
+            .or_else(|| self.alias_of(bare))
+            .unwrap_or(name)
+            .to_string()
+    }
+
+    /// What a player is matched across encounters by: their merged character if the GUID or
+    /// name has an alias, otherwise the GUID
+    fn character_id(&self, guid: &str, name: &str) -> String {
+        match self.alias_of(guid) {
+            Some(character) => character.to_string(),
+            None => {
+                let canonical = self.canonical_name(name);
+                if canonical != name { canonical } else { guid.to_string() }
+            }
+        }
+    }
+
+    /// Recorded encounters, without the duplicates of ones from another log
+    fn records(&self) -> impl Iterator<Item = (&String, &EncounterRecord)> {
+        let duplicates = self.duplicates();
+        self.data.encounters.iter().filter(move |(stable_id, _)| !duplicates.contains(stable_id.as_str()))
+    }
+
+    /// Stable IDs of records that repeat another one (see `EncounterRecord::same_encounter`).
+    /// Of each set the longest record is kept, as the other may have started mid-fight.
+    fn duplicates(&self) -> HashSet<&str> {
+        let now = Utc::now();
+        let mut timed: Vec<(DateTime<Utc>, &String, &EncounterRecord)> = self.data.encounters.iter()
+            .filter_map(|(stable_id, record)| Some((vault::log_time(&record.start_time, now)?, stable_id, record)))
+            .collect();
+        timed.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+        let mut duplicates = HashSet::new();
+        for (i, (start, stable_id, record)) in timed.iter().enumerate() {
+            if duplicates.contains(stable_id.as_str()) {
+                continue;
+            }
+            for (other_start, other_id, other) in &timed[i + 1..] {
+                if (*other_start - *start).num_seconds() > DUPLICATE_START_SECS {
+                    break;
+                }
+                if !record.same_encounter(other, now) {
+                    continue;
+                }
+                if other.duration_secs > record.duration_secs {
+                    duplicates.insert(stable_id.as_str());
+                    break;
+                }
+                duplicates.insert(other_id.as_str());
+            }
+        }
+        duplicates
+    }
+
+    /// The log an encounter was last seen in
+    pub fn encounter_file(&self, stable_id: &str) -> Option<&str> {
+        self.data.encounters.get(stable_id).map(|r| r.filename.as_str())
+    }
+
+    /// Index a log's encounters, parsed from the file as `parsed` describes it (`None` if it
+    /// changed during the parse); only writes the file if something changed
+    pub fn record_encounters(&mut self, filename: &str, parsed: Option<ParsedLog>, encounters: &[EncounterSummary]) -> std::io::Result<()> {
+        let mut changed = false;
+        match parsed {
+            Some(parsed) => changed |= self.data.parsed_logs.insert(filename.to_string(), parsed) != Some(parsed),
+            None => changed |= self.data.parsed_logs.remove(filename).is_some(),
+        }
+        for enc in encounters {
+            let record = EncounterRecord::new(filename, enc);
+            if self.data.encounters.get(&enc.stable_id) != Some(&record) {
+                self.data.encounters.insert(enc.stable_id.clone(), record);
+                changed = true;
+            }
+        }
+        if changed {
+            self.save()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fill in what a parse can't know on its own: performance deltas and the best run a
+    /// depleted key is compared against
+    pub fn annotate(&self, encounters: &mut [EncounterSummary]) {
+        self.annotate_performance(encounters);
+        self.annotate_depletion(encounters);
+    }
+
+    /// Set each player's `performance_delta`: their DPS and HPS against the average of their
+    /// last few earlier encounters in the same bracket. Players seen in none are left without.
+    fn annotate_performance(&self, encounters: &mut [EncounterSummary]) {
+        for enc in encounters.iter_mut() {
+            let Some(bracket) = bracket(enc) else {
+                continue;
+            };
+            let started = recency_key(&enc.start_time);
+            let this = EncounterRecord::new("", enc);
+            let now = Utc::now();
+            let mut earlier: Vec<(_, &EncounterRecord)> = self.records()
+                .filter(|(stable_id, record)| **stable_id != enc.stable_id && record.bracket.as_deref() == Some(bracket.as_str()))
+                .filter(|(_, record)| !record.same_encounter(&this, now))
+                .map(|(_, record)| (recency_key(&record.start_time), record))
+                .filter(|(key, _)| *key < started)
+                .collect();
+            earlier.sort_by(|a, b| b.0.cmp(&a.0));
+            for player in enc.players.iter_mut() {
+                let id = self.character_id(&player.guid, &player.name);
+                let past: Vec<&PlayerPerformance> = earlier.iter()
+                    .filter_map(|(_, record)| record.performance.iter().find(|p| self.character_id(&p.guid, &p.name) == id))
+                    .take(ROLLING_AVERAGE_ENCOUNTERS)
+                    .collect();
+                if past.is_empty() {
+                    continue;
+                }
+                let samples = past.len() as f64;
+                let average_dps = past.iter().map(|p| p.dps).sum::<f64>() / samples;
+                let average_hps = past.iter().map(|p| p.hps).sum::<f64>() / samples;
+                player.performance_delta = Some(PerformanceDelta {
+                    samples: past.len() as u32,
+                    average_dps,
+                    dps_delta_pct: delta_pct(player.dps, average_dps),
+                    average_hps,
+                    hps_delta_pct: delta_pct(player.hps, average_hps),
+                });
+            }
+        }
+    }
+
+    /// Compare each depletion report against the highest timed key of the same dungeon (the
+    /// fastest one at that level)
+    fn annotate_depletion(&self, encounters: &mut [EncounterSummary]) {
+        for enc in encounters.iter_mut() {
+            let Some(report) = enc.depletion_report.as_mut() else {
+                continue;
+            };
+            let best = self.records()
+                .filter_map(|(stable_id, record)| Some((stable_id, record, record.key.as_ref()?)))
+                .filter(|(_, _, key)| key.timed && key.zone_id == enc.encounter_id)
+                .max_by(|a, b| a.2.key_level.cmp(&b.2.key_level)
+                    .then_with(|| b.2.duration_secs.partial_cmp(&a.2.duration_secs).unwrap_or(std::cmp::Ordering::Equal)));
+            if let Some((stable_id, record, key)) = best {
+                let best = BestRun {
+                    stable_id: stable_id.clone(),
+                    start_time: record.start_time.clone(),
+                    key_level: key.key_level,
+                    duration_secs: key.duration_secs,
+                    enemy_forces: key.enemy_forces,
+                };
+                report.set_best_run(best, &key.sections);
+            }
+        }
+    }
+
+    /// Every Mythic+ key in the history that was finished, timed or not
+    pub fn completed_keys(&self) -> Vec<CompletedKey> {
+        self.records()
+            .filter(|(_, record)| record.success)
+            .filter_map(|(stable_id, record)| Some(CompletedKey {
+                stable_id: stable_id.clone(),
+                name: record.name.clone(),
+                start_time: record.start_time.clone(),
+                key_level: record.key.as_ref()?.key_level,
+                timed: record.key.as_ref()?.timed,
+                players: record.players.iter().map(|p| self.canonical_name(p)).collect(),
+            }))
+            .collect()
+    }
+
+    /// Every run of a dungeon (by instance ID) in the history, for its aggregate stats
+    pub fn dungeon_runs(&self, zone_id: u64) -> Vec<DungeonRun> {
+        let mut runs: Vec<(&str, DungeonRun)> = self.records()
+            .filter_map(|(_, record)| {
+                let key = record.key.as_ref().filter(|k| k.zone_id == zone_id)?;
+                Some((record.start_time.as_str(), DungeonRun {
+                    name: record.name.clone(),
+                    key_level: key.key_level,
+                    completed: record.success,
+                    timed: key.timed,
+                    duration_secs: key.duration_secs,
+                    sections: key.sections.clone(),
+                    section_deaths: key.section_deaths.clone(),
+                    specs: record.performance.iter().map(|p| p.spec.clone()).filter(|s| !s.is_empty()).collect(),
+                }))
+            })
+            .collect();
+        // Oldest first, so sections keep the order the dungeon is run in
+        runs.sort_by_key(|(start_time, _)| recency_key(start_time));
+        runs.into_iter().map(|(_, run)| run).collect()
+    }
+
+    /// Raid boss pulls and keys in the history, for the calendar feed. Records from before the
+    /// group size was kept count as raid pulls.
+    pub fn calendar_encounters(&self) -> Vec<CalendarEncounter> {
+        self.records()
+            .filter(|(_, record)| match record.encounter_type.as_str() {
+                "boss" => record.group_size == 0 || record.group_size > 5,
+                "mythic_plus" => true,
+                _ => false,
+            })
+            .map(|(stable_id, record)| CalendarEncounter {
+                stable_id: stable_id.clone(),
+                name: record.name.clone(),
+                instance_name: record.instance_name.clone(),
+                difficulty_name: record.difficulty_name.clone(),
+                key: record.encounter_type == "mythic_plus",
+                start_time: record.start_time.clone(),
+                duration_secs: record.duration_secs,
+                success: record.success,
+                timed: record.key.as_ref().map(|k| k.timed),
+                players: record.players.iter().map(|p| self.canonical_name(p)).collect(),
+            })
+            .collect()
+    }
+
+    /// Raid boss pulls in the history, for the fatigue curves. Records from before the group
+    /// size was kept count as raid pulls.
+    pub fn fatigue_pulls(&self) -> Vec<FatiguePull> {
+        self.records()
+            .filter(|(_, record)| record.encounter_type == "boss" && (record.group_size == 0 || record.group_size > 5))
+            .map(|(_, record)| FatiguePull {
+                start_time: record.start_time.clone(),
+                bracket: record.bracket.clone(),
+                success: record.success,
+                damage_taken: record.damage_taken,
+                avoidable_damage: record.avoidable_damage,
+                players: record.performance.iter().map(|p| FatiguePlayer {
+                    name: self.character_id(&p.guid, &p.name),
+                    dps: p.dps,
+                    deaths: p.deaths,
+                }).collect(),
+            })
+            .collect()
+    }
+
+    /// Every boss pull and key in the history, for the leaderboards
+    pub fn leaderboard_encounters(&self) -> Vec<LeaderboardEncounter> {
+        self.records()
+            .filter(|(_, record)| matches!(record.encounter_type.as_str(), "boss" | "mythic_plus"))
+            .map(|(stable_id, record)| LeaderboardEncounter {
+                stable_id: stable_id.clone(),
+                name: record.name.clone(),
+                encounter_type: record.encounter_type.clone(),
+                difficulty_name: record.difficulty_name.clone(),
+                season: record.season.clone(),
+                start_time: record.start_time.clone(),
+                success: record.success,
+                duration_secs: record.key.as_ref().map_or(0.0, |k| k.duration_secs),
+                timed: record.key.as_ref().map(|k| k.timed),
+                players: record.performance.iter().map(|p| LeaderboardPlayer {
+                    name: self.canonical_name(&p.name),
+                    spec: p.spec.clone(),
+                    role: p.role.clone(),
+                    dps: p.dps,
+                    hps: p.hps,
+                    deaths: p.deaths,
+                }).collect(),
+            })
+            .collect()
+    }
+
+    /// Every boss pull and key `character` (a name, name-realm, GUID or alias) was in, for
+    /// their build timeline
+    pub fn build_runs(&self, character: &str) -> Vec<BuildRun> {
+        let wanted = self.canonical_name(character);
+        let is_character = |p: &PlayerPerformance| {
+            p.guid.eq_ignore_ascii_case(character)
+                || self.alias_of(&p.guid).is_some_and(|c| eq_ignore_case(c, &wanted))
+                || anonymize::is_listed(&self.canonical_name(&p.name), std::slice::from_ref(&wanted))
+        };
+        self.records()
+            .filter(|(_, record)| matches!(record.encounter_type.as_str(), "boss" | "mythic_plus"))
+            .filter_map(|(stable_id, record)| {
+                let player = record.performance.iter().find(|p| is_character(p))?;
+                Some(BuildRun {
+                    stable_id: stable_id.clone(),
+                    name: record.name.clone(),
+                    difficulty_name: record.difficulty_name.clone(),
+                    start_time: record.start_time.clone(),
+                    success: record.success,
+                    bracket: record.bracket.clone(),
+                    spec: player.spec.clone(),
+                    role: player.role.clone(),
+                    talents: player.talents.clone(),
+                    dps: player.dps,
+                    hps: player.hps,
+                })
+            })
+            .collect()
+    }
+
+    /// Encounters matching every word of `query` against boss/dungeon names, difficulty,
+    /// player names and dates ("ansurek mythic feb"). Names weigh more than players and
+    /// dates; ties go to the most recent.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let terms: Vec<String> = words(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: Vec<SearchHit> = self.records().filter_map(|(stable_id, record)| {
+            let mut score = 0;
+            let mut matched_players = Vec::new();
+            for term in &terms {
+                let mut term_score = 0;
+                if matches_words(&record.name, term) {
+                    term_score += 5;
+                }
+                if record.instance_name.as_deref().is_some_and(|n| matches_words(n, term)) {
+                    term_score += 4;
+                }
+                if matches_words(&record.difficulty_name, term) || matches_words(&record.encounter_type.replace('_', " "), term) {
+                    term_score += 3;
+                }
+                for player in record.players.iter().filter(|p| matches_words(p, term)) {
+                    term_score += 3;
+                    if !matched_players.contains(player) {
+                        matched_players.push(player.clone());
+                    }
+                }
+                if date_matches(&record.start_time, term) {
+                    term_score += 2;
+                }
+                if term_score == 0 && record.filename.to_lowercase().contains(term.as_str()) {
+                    term_score += 1;
+                }
+                if term_score == 0 {
+                    return None;
+                }
+                score += term_score;
+            }
+            Some(SearchHit {
+                stable_id: stable_id.clone(),
+                filename: record.filename.clone(),
+                name: record.name.clone(),
+                instance_name: record.instance_name.clone(),
+                difficulty_name: record.difficulty_name.clone(),
+                encounter_type: record.encounter_type.clone(),
+                start_time: record.start_time.clone(),
+                success: record.success,
+                matched_players,
+                score,
+            })
+        }).collect();
+        // Best score first, then newest
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| recency_key(&b.start_time).cmp(&recency_key(&a.start_time))));
+        hits.truncate(MAX_SEARCH_HITS);
+        hits
+    }
+
+    /// Write changes an earlier save couldn't (the file locked by a backup tool or antivirus,
+    /// a full disk); called periodically by the server
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        if self.dirty {
+            self.save()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn save(&mut self) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.data)?;
+        let saved = write_durably(&self.path, json.as_bytes());
+        self.dirty = saved.is_err();
+        saved
+    }
+}
+
+/// Write to a temp file, flush it to disk and rename it over `path`, so a crash or power
+/// loss leaves either the old file or the new one, never a truncated one
+pub(crate) fn write_durably(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&tmp, path)
+}
+
+/// (year, month, day, time) from "M/D[/YYYY] HH:MM:SS.mmm", for newest-first ordering
+fn recency_key(start_time: &str) -> (u32, u32, u32, String) {
+    let (date, time) = start_time.split_once(' ').unwrap_or((start_time, ""));
+    let mut parts = date.split('/').map(|p| p.parse::<u32>().unwrap_or(0));
+    let month = parts.next().unwrap_or(0);
+    let day = parts.next().unwrap_or(0);
+    let year = parts.next().unwrap_or(0);
+    (year, month, day, time.to_string())
+}
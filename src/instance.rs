@@ -0,0 +1,96 @@
+//! One app per port: a second launch finds the first through the port it listens on
+//!
+//! The port is claimed before anything else starts, so a busy port is found out at once
+//! instead of as a server that silently never came up. Whoever holds it is asked for
+//! `/api/instance`; if that is this app, the user can open the running one or start another
+//! on a free port. A port nobody answers on is tried again for a few seconds first, as it is
+//! often only held by an instance still shutting down or a socket the system hasn't let go of.
+
+use serde::Serialize;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Identifies this app in `/api/instance`
+pub const APP_NAME: &str = "wowlogger";
+
+/// How far past the default port to look for a free one for a second instance
+const PORT_SEARCH: u16 = 20;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Wait before binding a refused port again, doubled on each try
+const BIND_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Tries after the first (0.1 + 0.2 + ... + 1.6 = 3.1 s of waiting in all)
+const BIND_RETRIES: u32 = 5;
+
+/// What a running instance answers on `/api/instance`
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceInfo {
+    pub app: &'static str,
+    pub version: &'static str,
+    pub pid: u32,
+}
+
+pub fn info() -> InstanceInfo {
+    InstanceInfo { app: APP_NAME, version: env!("CARGO_PKG_VERSION"), pid: std::process::id() }
+}
+
+/// Who has a port
+pub enum PortOwner {
+    /// Nobody; it is ours now
+    Free(TcpListener),
+    /// Another instance of this app
+    Running,
+    /// Some other program
+    Other(io::Error),
+}
+
+/// Listen on `port`, or find out who already does
+pub fn claim(port: u16) -> PortOwner {
+    let mut error = match bind(port) {
+        Ok(listener) => return PortOwner::Free(listener),
+        Err(e) => e,
+    };
+    if is_running(port) {
+        tracing::info!(port, "another instance is running: {}", error);
+        return PortOwner::Running;
+    }
+    let mut delay = BIND_RETRY_DELAY;
+    for attempt in 1..=BIND_RETRIES {
+        tracing::info!(port, attempt, "port refused, trying again in {} ms: {}", delay.as_millis(), error);
+        std::thread::sleep(delay);
+        match bind(port) {
+            Ok(listener) => return PortOwner::Free(listener),
+            Err(e) => error = e,
+        }
+        delay *= 2;
+    }
+    tracing::warn!(port, "port is taken by another program: {}", error);
+    PortOwner::Other(error)
+}
+
+/// The first free port after `port`, already listened on
+pub fn next_free_port(port: u16) -> Option<(u16, TcpListener)> {
+    (port.saturating_add(1)..=port.saturating_add(PORT_SEARCH))
+        .find_map(|p| bind(p).ok().map(|listener| (p, listener)))
+}
+
+fn bind(port: u16) -> io::Result<TcpListener> {
+    TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+}
+
+/// Whether this app answers on `port`
+fn is_running(port: u16) -> bool {
+    let probe = || -> io::Result<String> {
+        let mut stream = TcpStream::connect_timeout(&SocketAddr::from((Ipv4Addr::LOCALHOST, port)), PROBE_TIMEOUT)?;
+        stream.set_read_timeout(Some(PROBE_TIMEOUT))?;
+        stream.set_write_timeout(Some(PROBE_TIMEOUT))?;
+        stream.write_all(b"GET /api/instance HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    };
+    probe().is_ok_and(|response| response.contains(&format!("\"app\":\"{}\"", APP_NAME)))
+}
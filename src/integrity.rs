@@ -0,0 +1,134 @@
+//! Startup check of the history database against the Logs folder and the running parser
+//!
+//! A history file that doesn't load, records of logs that are gone or leftovers of an
+//! interrupted write would otherwise surface later as notes that vanished or links that go
+//! nowhere. They are repaired where that loses nothing and reported otherwise, through
+//! `/api/admin/integrity` and the GUI. Records indexed from another summary schema are
+//! dropped and their logs parsed again. The parse cache lives in memory and starts empty, so
+//! there is nothing on disk to check there.
+
+use chrono::Local;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::history::History;
+
+/// What the startup check found
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    pub checked_at: String,
+    pub elapsed_secs: f64,
+    pub parser_version: &'static str,
+    /// Raw logs in the Logs folder
+    pub logs: usize,
+    /// Encounters in the history, after repairs
+    pub encounters: usize,
+    pub repaired: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Logs whose outdated history records were dropped, re-parsed in the background
+    pub reindex: Vec<String>,
+}
+
+/// The startup check's report, shared by the server and the GUI
+#[derive(Default)]
+pub struct Integrity {
+    report: Mutex<Option<IntegrityReport>>,
+}
+
+impl Integrity {
+    /// `None` until the check has run
+    pub fn report(&self) -> Option<IntegrityReport> {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// For the GUI, e.g. "Startup check: 1 repaired, 2 warning(s)"
+    pub fn status_text(&self) -> String {
+        let report = self.report.lock().unwrap();
+        let Some(report) = report.as_ref() else {
+            return String::new();
+        };
+        if report.repaired.is_empty() && report.warnings.is_empty() {
+            return format!("Startup check: OK ({} encounters, {} logs)", report.encounters, report.logs);
+        }
+        let mut found = Vec::new();
+        if !report.repaired.is_empty() {
+            found.push(format!("{} repaired", report.repaired.len()));
+        }
+        if !report.warnings.is_empty() {
+            found.push(format!("{} warning(s)", report.warnings.len()));
+        }
+        format!("Startup check: {} (see /api/admin/integrity)", found.join(", "))
+    }
+
+    /// Check `history` against the logs under `log_dir` and keep the report
+    pub fn check(&self, history: &mut History, log_dir: &Path) -> IntegrityReport {
+        let start = Instant::now();
+        let mut repaired = Vec::new();
+        let mut warnings = Vec::new();
+
+        let (logs, partial_archives) = scan_logs(log_dir);
+        for path in partial_archives {
+            match std::fs::remove_file(&path) {
+                Ok(()) => repaired.push(format!("Removed {}, left by an interrupted compression", path.display())),
+                Err(e) => warnings.push(format!("Couldn't remove {}: {}", path.display(), e)),
+            }
+        }
+        let history_check = history.check(&logs);
+        repaired.extend(history_check.repaired);
+        warnings.extend(history_check.warnings);
+
+        let report = IntegrityReport {
+            checked_at: Local::now().to_rfc3339(),
+            elapsed_secs: start.elapsed().as_secs_f64(),
+            parser_version: fast_wow_parser::VERSION,
+            logs: logs.len(),
+            encounters: history_check.encounters,
+            repaired,
+            warnings,
+            reindex: history_check.reindex,
+        };
+        for problem in &report.repaired {
+            tracing::warn!("startup check repaired: {}", problem);
+        }
+        for problem in &report.warnings {
+            tracing::warn!("startup check: {}", problem);
+        }
+        tracing::info!(logs = report.logs, encounters = report.encounters, elapsed_secs = report.elapsed_secs, "startup check done");
+        *self.report.lock().unwrap() = Some(report.clone());
+        report
+    }
+}
+
+/// Filenames of the raw logs under `dir`, and the paths of half-written `.gz.part` archives
+fn scan_logs(dir: &Path) -> (HashSet<String>, Vec<std::path::PathBuf>) {
+    let mut logs = HashSet::new();
+    let mut partial = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(d) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&d) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !filename.starts_with("WoWCombatLog") {
+                continue;
+            }
+            if filename.ends_with(".txt") {
+                logs.insert(filename.to_string());
+            } else if filename.ends_with(".txt.gz.part") {
+                partial.push(path.clone());
+            }
+        }
+    }
+    (logs, partial)
+}
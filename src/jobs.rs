@@ -0,0 +1,427 @@
+//! Full parses the server has queued, is running or recently ran, for `/api/jobs` and the
+//! GUI status line
+//!
+//! A parse of a multi-gigabyte log takes a while and a lot of memory, so only a few run at
+//! once; the rest wait for a slot. Each running job reports how far into the file it is.
+//!
+//! With several people on the LAN parsing at once, the queue is fair rather than first come,
+//! first served: logs under `LARGE_LOG_BYTES` go before large ones, and a large log never gets
+//! the last slot, so one person's 3 GB parse can't hold up everyone else's smaller ones.
+//! Refreshes of a log whose stale summary is already being served go after parses someone is
+//! waiting on. Otherwise clients (by IP address) take turns, the one that started a parse
+//! longest ago going next. Encounters of a cached log are re-parsed from their byte range
+//! outside the queue, as that only takes a moment.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Parses allowed to run at the same time
+const MAX_CONCURRENT_PARSES: usize = 2;
+
+/// Logs this big or bigger may only take `MAX_CONCURRENT_PARSES - 1` slots
+const LARGE_LOG_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Client of the parses the server starts by itself (cache refreshes and warming)
+pub const BACKGROUND_CLIENT: &str = "background";
+
+tokio::task_local! {
+    static CLIENT: String;
+}
+
+/// Run `request` for `client` (its IP address), so the parses it queues take turns with
+/// other clients'
+pub async fn for_client<F: Future>(client: String, request: F) -> F::Output {
+    CLIENT.scope(client, request).await
+}
+
+fn current_client() -> String {
+    CLIENT.try_with(|client| client.clone()).unwrap_or_else(|_| BACKGROUND_CLIENT.to_string())
+}
+
+/// Finished jobs kept for the list
+const MAX_FINISHED_JOBS: usize = 20;
+
+/// Rough peak memory of a parse per byte of log, measured on Mythic+ logs
+const MEMORY_PER_LOG_BYTE: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+/// A job as `/api/jobs` reports it
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub filename: String,
+    /// What asked for the parse: "summary", "encounter", "replay" or "refresh"
+    pub kind: &'static str,
+    /// IP address of who asked for it, or "background"
+    pub client: String,
+    pub status: JobStatus,
+    pub size_bytes: u64,
+    pub bytes_read: u64,
+    pub progress_pct: f64,
+    /// Time spent waiting and parsing so far, or in total once finished
+    pub elapsed_secs: f64,
+    /// Expected peak memory of the parse, from the file size
+    pub memory_estimate_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct Job {
+    id: u64,
+    filename: String,
+    kind: &'static str,
+    client: String,
+    size_bytes: u64,
+    progress: Arc<AtomicU64>,
+    queued_at: Instant,
+    running: bool,
+    /// Total time and the error, if it failed
+    finished: Option<(Duration, Option<String>)>,
+}
+
+impl Job {
+    fn waiting(&self) -> bool {
+        !self.running && self.finished.is_none()
+    }
+
+    fn large(&self) -> bool {
+        self.size_bytes >= LARGE_LOG_BYTES
+    }
+
+    fn info(&self) -> JobInfo {
+        let (status, elapsed, error) = match &self.finished {
+            Some((elapsed, None)) => (JobStatus::Done, *elapsed, None),
+            Some((elapsed, Some(e))) => (JobStatus::Failed, *elapsed, Some(e.clone())),
+            None if self.running => (JobStatus::Running, self.queued_at.elapsed(), None),
+            None => (JobStatus::Queued, self.queued_at.elapsed(), None),
+        };
+        let bytes_read = match status {
+            JobStatus::Done => self.size_bytes,
+            _ => self.progress.load(Ordering::Relaxed).min(self.size_bytes),
+        };
+        JobInfo {
+            id: self.id,
+            filename: self.filename.clone(),
+            kind: self.kind,
+            client: self.client.clone(),
+            status,
+            size_bytes: self.size_bytes,
+            bytes_read,
+            progress_pct: if self.size_bytes > 0 { bytes_read as f64 / self.size_bytes as f64 * 100.0 } else { 100.0 },
+            elapsed_secs: elapsed.as_secs_f64(),
+            memory_estimate_bytes: self.size_bytes.saturating_mul(MEMORY_PER_LOG_BYTE),
+            error,
+        }
+    }
+}
+
+#[derive(Default)]
+struct JobList {
+    next_id: u64,
+    /// Oldest first
+    jobs: VecDeque<Job>,
+    /// Parses started so far, and the count as of each client's last one
+    turns: u64,
+    last_turn: HashMap<String, u64>,
+}
+
+impl JobList {
+    /// The queued job to start next, if a slot is free for it
+    fn next(&self) -> Option<u64> {
+        let running: Vec<&Job> = self.jobs.iter().filter(|job| job.running && job.finished.is_none()).collect();
+        if running.len() >= MAX_CONCURRENT_PARSES {
+            return None;
+        }
+        let large_slot_free = running.iter().filter(|job| job.large()).count() < MAX_CONCURRENT_PARSES - 1;
+        self.jobs.iter()
+            .filter(|job| job.waiting() && (large_slot_free || !job.large()))
+            .min_by_key(|job| {
+                let client_running = running.iter().filter(|other| other.client == job.client).count();
+                let last_turn = self.last_turn.get(&job.client).copied().unwrap_or(0);
+                (job.large(), job.kind == "refresh", client_running, last_turn, job.id)
+            })
+            .map(|job| job.id)
+    }
+
+    /// Mark job `id` running if it is the one to start next
+    fn start_if_next(&mut self, id: u64) -> bool {
+        if self.next() != Some(id) {
+            return false;
+        }
+        self.turns += 1;
+        let turn = self.turns;
+        let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+            return false;
+        };
+        job.running = true;
+        let client = job.client.clone();
+        self.last_turn.insert(client, turn);
+        true
+    }
+}
+
+/// The parse queue, shared by the server and the GUI
+#[derive(Default)]
+pub struct Jobs {
+    list: Mutex<JobList>,
+    /// Woken when a job starts or finishes, for the queued ones to check if it's their turn
+    changed: Notify,
+}
+
+impl Jobs {
+    /// Every job still queued or running plus the most recent finished ones, newest first
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.list.lock().unwrap().jobs.iter().rev().map(Job::info).collect()
+    }
+
+    /// Jobs queued or running, oldest first
+    pub fn active(&self) -> Vec<JobInfo> {
+        self.list.lock().unwrap().jobs.iter()
+            .filter(|job| job.finished.is_none())
+            .map(Job::info)
+            .collect()
+    }
+
+    /// Queue `parse` for `filename` (`size_bytes` long) and run it on a blocking thread once
+    /// it's its turn (see the module doc). `parse` is handed the counter to store its byte
+    /// offset in. Dropped while queued, the job is cancelled; once started, the parse runs to
+    /// the end and keeps its slot until then.
+    pub async fn run<T: Send + 'static>(
+        self: &Arc<Self>,
+        filename: &str,
+        kind: &'static str,
+        size_bytes: u64,
+        parse: impl FnOnce(Arc<AtomicU64>) -> Result<T, String> + Send + 'static,
+    ) -> Result<T, String> {
+        let progress = Arc::new(AtomicU64::new(0));
+        let id = {
+            let mut list = self.list.lock().unwrap();
+            list.next_id += 1;
+            let id = list.next_id;
+            list.jobs.push_back(Job {
+                id,
+                filename: filename.to_string(),
+                kind,
+                client: current_client(),
+                size_bytes,
+                progress: progress.clone(),
+                queued_at: Instant::now(),
+                running: false,
+                finished: None,
+            });
+            id
+        };
+        // The request may be dropped while waiting; the job mustn't stay queued forever
+        let _cancelled = CancelIfQueued { jobs: self, id };
+
+        loop {
+            let notified = self.changed.notified();
+            tokio::pin!(notified);
+            // Registered before checking, so a slot freed in between isn't missed
+            notified.as_mut().enable();
+            if self.list.lock().unwrap().start_if_next(id) {
+                break;
+            }
+            notified.await;
+        }
+        // Another job may be able to start in a second free slot
+        self.changed.notify_waiters();
+
+        // Finished by the parse itself, even if the request is gone by then or it panics
+        let finished = FinishOnDrop { jobs: self.clone(), id, error: Some("Parse panicked".to_string()) };
+        tokio::task::spawn_blocking(move || {
+            let mut finished = finished;
+            let result = parse(progress);
+            finished.error = result.as_ref().err().cloned();
+            result
+        }).await
+            .unwrap_or_else(|e| Err(format!("Task failed: {}", e)))
+    }
+
+    fn finish(&self, id: u64, error: Option<String>) {
+        self.update(id, |job| {
+            if job.finished.is_none() {
+                job.finished = Some((job.queued_at.elapsed(), error));
+            }
+        });
+        self.prune();
+        self.changed.notify_waiters();
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.list.lock().unwrap().jobs.iter_mut().find(|job| job.id == id) {
+            change(job);
+        }
+    }
+
+    /// Drop the oldest finished jobs beyond `MAX_FINISHED_JOBS`, and the turns of clients
+    /// without any left
+    fn prune(&self) {
+        let mut list = self.list.lock().unwrap();
+        let mut finished = list.jobs.iter().filter(|job| job.finished.is_some()).count();
+        list.jobs.retain(|job| {
+            if job.finished.is_some() && finished > MAX_FINISHED_JOBS {
+                finished -= 1;
+                return false;
+            }
+            true
+        });
+        let JobList { jobs, last_turn, .. } = &mut *list;
+        last_turn.retain(|client, _| jobs.iter().any(|job| &job.client == client));
+    }
+}
+
+/// Marks a job cancelled if `Jobs::run` is dropped before the job started
+struct CancelIfQueued<'a> {
+    jobs: &'a Jobs,
+    id: u64,
+}
+
+impl Drop for CancelIfQueued<'_> {
+    fn drop(&mut self) {
+        let queued = self.jobs.list.lock().unwrap().jobs.iter().any(|job| job.id == self.id && job.waiting());
+        if queued {
+            self.jobs.finish(self.id, Some("Cancelled".to_string()));
+        }
+    }
+}
+
+/// Finishes a running job with `error` once the parse is done with it
+struct FinishOnDrop {
+    jobs: Arc<Jobs>,
+    id: u64,
+    error: Option<String>,
+}
+
+impl Drop for FinishOnDrop {
+    fn drop(&mut self) {
+        self.jobs.finish(self.id, self.error.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn job(id: u64, client: &str, kind: &'static str, size_bytes: u64, running: bool) -> Job {
+        Job {
+            id,
+            filename: format!("WoWCombatLog-{}.txt", id),
+            kind,
+            client: client.to_string(),
+            size_bytes,
+            progress: Arc::default(),
+            queued_at: Instant::now(),
+            running,
+            finished: None,
+        }
+    }
+
+    fn job_list(jobs: Vec<Job>) -> JobList {
+        JobList { jobs: jobs.into(), ..JobList::default() }
+    }
+
+    #[test]
+    fn next_waits_for_a_free_slot() {
+        let list = job_list(vec![job(1, "a", "summary", 10, true), job(2, "b", "summary", 10, true), job(3, "c", "summary", 10, false)]);
+        assert_eq!(list.next(), None);
+    }
+
+    #[test]
+    fn next_puts_small_logs_and_other_clients_first() {
+        let running = job(1, "a", "summary", 10, true);
+        let list = job_list(vec![running, job(2, "a", "summary", 10, false), job(3, "b", "summary", 10, false)]);
+        assert_eq!(list.next(), Some(3));
+
+        let list = job_list(vec![job(1, "a", "summary", LARGE_LOG_BYTES, false), job(2, "a", "summary", 10, false)]);
+        assert_eq!(list.next(), Some(2));
+
+        let list = job_list(vec![job(1, "a", "refresh", 10, false), job(2, "a", "summary", 10, false)]);
+        assert_eq!(list.next(), Some(2));
+    }
+
+    #[test]
+    fn next_keeps_a_slot_from_large_logs() {
+        let list = job_list(vec![job(1, "a", "summary", LARGE_LOG_BYTES, true), job(2, "b", "summary", LARGE_LOG_BYTES, false)]);
+        assert_eq!(list.next(), None);
+    }
+
+    /// A parse that runs until `release` gets a message
+    fn blocking_parse(jobs: &Arc<Jobs>, filename: &str) -> (tokio::task::JoinHandle<Result<(), String>>, mpsc::Sender<()>) {
+        let (release, released) = mpsc::channel::<()>();
+        let jobs = jobs.clone();
+        let filename = filename.to_string();
+        let handle = tokio::spawn(async move {
+            jobs.run(&filename, "summary", 10, move |_| {
+                let _ = released.recv();
+                Ok(())
+            }).await
+        });
+        (handle, release)
+    }
+
+    async fn wait_for(jobs: &Jobs, filename: &str, status: JobStatus) -> JobInfo {
+        for _ in 0..500 {
+            if let Some(info) = jobs.list().into_iter().find(|j| j.filename == filename && j.status == status) {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{} never became {:?}: {:?}", filename, status, jobs.list());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dropped_request_keeps_the_slot_until_the_parse_ends() {
+        let jobs = Arc::new(Jobs::default());
+        let (first, release_first) = blocking_parse(&jobs, "first");
+        let (_second, release_second) = blocking_parse(&jobs, "second");
+        wait_for(&jobs, "first", JobStatus::Running).await;
+        wait_for(&jobs, "second", JobStatus::Running).await;
+
+        first.abort();
+        let (_third, release_third) = blocking_parse(&jobs, "third");
+        wait_for(&jobs, "third", JobStatus::Queued).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(wait_for(&jobs, "first", JobStatus::Running).await.error, None);
+        assert_eq!(wait_for(&jobs, "third", JobStatus::Queued).await.error, None);
+
+        release_first.send(()).unwrap();
+        wait_for(&jobs, "first", JobStatus::Done).await;
+        wait_for(&jobs, "third", JobStatus::Running).await;
+        release_second.send(()).unwrap();
+        release_third.send(()).unwrap();
+        wait_for(&jobs, "third", JobStatus::Done).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dropped_request_cancels_a_queued_job() {
+        let jobs = Arc::new(Jobs::default());
+        let (_first, release_first) = blocking_parse(&jobs, "first");
+        let (_second, release_second) = blocking_parse(&jobs, "second");
+        wait_for(&jobs, "first", JobStatus::Running).await;
+        wait_for(&jobs, "second", JobStatus::Running).await;
+        let (third, _release_third) = blocking_parse(&jobs, "third");
+        wait_for(&jobs, "third", JobStatus::Queued).await;
+
+        third.abort();
+        let cancelled = wait_for(&jobs, "third", JobStatus::Failed).await;
+        assert_eq!(cancelled.error.as_deref(), Some("Cancelled"));
+        release_first.send(()).unwrap();
+        release_second.send(()).unwrap();
+        wait_for(&jobs, "second", JobStatus::Done).await;
+    }
+}
@@ -0,0 +1,53 @@
+//! Opening the app from other devices on the network, a phone on the same Wi-Fi in
+//! particular: the server is advertised over mDNS as `fwca.local`, and the GUI shows a QR
+//! code of its address
+//!
+//! The QR code carries the LAN IP rather than the `.local` name, since not every phone
+//! resolves mDNS names in the browser.
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+/// Host name the server is advertised under
+pub const HOST_NAME: &str = "fwca.local.";
+const SERVICE_TYPE: &str = "_http._tcp.local.";
+const INSTANCE_NAME: &str = "Fast WoW Combat Analyzer";
+
+/// Advertise the HTTP server on `port` for as long as the returned daemon lives. `None` (and a
+/// warning in the app log) if mDNS isn't available, e.g. with no network.
+pub fn advertise(port: u16) -> Option<ServiceDaemon> {
+    let daemon = ServiceDaemon::new()
+        .map_err(|e| tracing::warn!("mDNS unavailable: {}", e))
+        .ok()?;
+    let service = ServiceInfo::new(SERVICE_TYPE, INSTANCE_NAME, HOST_NAME, "", port, &[("path", "/")][..])
+        .map(ServiceInfo::enable_addr_auto)
+        .and_then(|service| daemon.register(service));
+    match service {
+        Ok(()) => {
+            tracing::info!("advertised as {}", mdns_url(port));
+            Some(daemon)
+        }
+        Err(e) => {
+            tracing::warn!("mDNS advertisement failed: {}", e);
+            None
+        }
+    }
+}
+
+/// The address the server is advertised under, e.g. `http://fwca.local:3000`
+pub fn mdns_url(port: u16) -> String {
+    format!("http://{}:{}", HOST_NAME.trim_end_matches('.'), port)
+}
+
+/// The address other devices on the network reach the server at by IP, if this machine has
+/// a LAN address
+pub fn lan_url(port: u16) -> Option<String> {
+    local_ip_address::local_ip().ok().map(|ip| format!("http://{}:{}", ip, port))
+}
+
+/// `text` as a QR code: rows of modules, `true` for dark
+pub fn qr_code(text: &str) -> Option<Vec<Vec<bool>>> {
+    let code = qrcode::QrCode::new(text).ok()?;
+    let width = code.width();
+    let modules: Vec<bool> = code.to_colors().into_iter().map(|c| c == qrcode::Color::Dark).collect();
+    Some(modules.chunks(width).map(<[bool]>::to_vec).collect())
+}
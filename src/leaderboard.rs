@@ -0,0 +1,182 @@
+//! Guild-internal leaderboards over every encounter in the history: best DPS on each boss
+//! by spec, fastest keys and most deaths
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Entries kept on each board
+const BOARD_SIZE: usize = 10;
+
+/// An encounter from the history with what the boards need
+pub struct LeaderboardEncounter {
+    pub stable_id: String,
+    pub name: String,
+    pub encounter_type: String,
+    pub difficulty_name: String,
+    pub season: Option<String>,
+    pub start_time: String,
+    pub success: bool,
+    pub duration_secs: f64,
+    /// Mythic+ keys only: whether it was timed
+    pub timed: Option<bool>,
+    pub players: Vec<LeaderboardPlayer>,
+}
+
+pub struct LeaderboardPlayer {
+    pub name: String,
+    /// "Fire Mage"
+    pub spec: String,
+    /// "tank", "healer" or "dps"; empty for records from before it was kept
+    pub role: String,
+    pub dps: f64,
+    pub hps: f64,
+    pub deaths: u32,
+}
+
+/// Narrow the boards down; both match case-insensitively ("season 2", "mythic +10")
+#[derive(Default, Deserialize)]
+pub struct LeaderboardFilter {
+    /// Part of the season label
+    pub season: Option<String>,
+    /// Difficulty name, e.g. "Heroic" or "Mythic +12"
+    pub difficulty: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Leaderboards {
+    /// Highest DPS on each boss and difficulty, best player of each spec (kills only)
+    pub boss_dps: Vec<BossDpsBoard>,
+    /// Fastest timed runs of each dungeon and key level
+    pub fastest_keys: Vec<KeyTimeBoard>,
+    /// Deaths per player over boss pulls and keys
+    pub most_deaths: Vec<DeathCount>,
+    /// Values the filters can take
+    pub seasons: Vec<String>,
+    pub difficulties: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct BossDpsBoard {
+    pub boss: String,
+    pub difficulty_name: String,
+    /// Highest first, one per spec
+    pub entries: Vec<DpsEntry>,
+}
+
+#[derive(Serialize)]
+pub struct DpsEntry {
+    pub spec: String,
+    pub player: String,
+    pub dps: f64,
+    pub stable_id: String,
+    pub start_time: String,
+}
+
+#[derive(Serialize)]
+pub struct KeyTimeBoard {
+    pub dungeon: String,
+    pub difficulty_name: String,
+    /// Fastest first
+    pub entries: Vec<KeyTimeEntry>,
+}
+
+#[derive(Serialize)]
+pub struct KeyTimeEntry {
+    pub duration_secs: f64,
+    pub players: Vec<String>,
+    pub stable_id: String,
+    pub start_time: String,
+}
+
+#[derive(Serialize)]
+pub struct DeathCount {
+    pub player: String,
+    pub deaths: u32,
+    /// Boss pulls and keys they were in
+    pub encounters: u32,
+}
+
+impl LeaderboardFilter {
+    fn matches(&self, enc: &LeaderboardEncounter) -> bool {
+        let contains = |text: &str, term: &str| text.to_lowercase().contains(&term.to_lowercase());
+        self.season.as_deref().is_none_or(|term| enc.season.as_deref().is_some_and(|s| contains(s, term)))
+            && self.difficulty.as_deref().is_none_or(|term| enc.difficulty_name.eq_ignore_ascii_case(term.trim()))
+    }
+}
+
+/// Dungeon name of a key ("Ara-Kara, City of Echoes +10" -> "Ara-Kara, City of Echoes")
+pub(crate) fn dungeon_name(key_name: &str) -> &str {
+    key_name.rsplit_once(" +").map_or(key_name, |(name, _)| name)
+}
+
+/// Build the boards from `encounters`, leaving out players in `hidden` (the private players)
+pub fn leaderboards(encounters: &[LeaderboardEncounter], filter: &LeaderboardFilter, hidden: &[String]) -> Leaderboards {
+    let ranked: Vec<&LeaderboardEncounter> = encounters.iter()
+        .filter(|e| matches!(e.encounter_type.as_str(), "boss" | "mythic_plus"))
+        .collect();
+    let seasons: BTreeSet<String> = ranked.iter().filter_map(|e| e.season.clone()).collect();
+    let difficulties: BTreeSet<String> = ranked.iter().map(|e| e.difficulty_name.clone()).collect();
+    let visible = |p: &&LeaderboardPlayer| !fast_wow_parser::anonymize::is_listed(&p.name, hidden);
+
+    let mut boss_dps: HashMap<(&str, &str), HashMap<&str, DpsEntry>> = HashMap::new();
+    let mut fastest_keys: HashMap<(&str, &str), Vec<KeyTimeEntry>> = HashMap::new();
+    let mut deaths: HashMap<&str, DeathCount> = HashMap::new();
+    for enc in ranked.iter().filter(|e| filter.matches(e)) {
+        for player in enc.players.iter().filter(visible) {
+            let count = deaths.entry(&player.name).or_insert_with(|| DeathCount { player: player.name.clone(), deaths: 0, encounters: 0 });
+            count.deaths += player.deaths;
+            count.encounters += 1;
+        }
+        match enc.encounter_type.as_str() {
+            "boss" if enc.success => {
+                let board = boss_dps.entry((&enc.name, &enc.difficulty_name)).or_default();
+                for player in enc.players.iter().filter(visible).filter(|p| p.dps > 0.0 && !p.spec.is_empty()) {
+                    if board.get(player.spec.as_str()).is_none_or(|best| player.dps > best.dps) {
+                        board.insert(&player.spec, DpsEntry {
+                            spec: player.spec.clone(),
+                            player: player.name.clone(),
+                            dps: player.dps,
+                            stable_id: enc.stable_id.clone(),
+                            start_time: enc.start_time.clone(),
+                        });
+                    }
+                }
+            }
+            "mythic_plus" if enc.timed == Some(true) => {
+                fastest_keys.entry((dungeon_name(&enc.name), &enc.difficulty_name)).or_default().push(KeyTimeEntry {
+                    duration_secs: enc.duration_secs,
+                    players: enc.players.iter().filter(visible).map(|p| p.name.clone()).collect(),
+                    stable_id: enc.stable_id.clone(),
+                    start_time: enc.start_time.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut boss_dps: Vec<BossDpsBoard> = boss_dps.into_iter().map(|((boss, difficulty), specs)| {
+        let mut entries: Vec<DpsEntry> = specs.into_values().collect();
+        entries.sort_by(|a, b| b.dps.partial_cmp(&a.dps).unwrap_or(std::cmp::Ordering::Equal));
+        BossDpsBoard { boss: boss.to_string(), difficulty_name: difficulty.to_string(), entries }
+    }).collect();
+    boss_dps.sort_by(|a, b| a.boss.cmp(&b.boss).then_with(|| a.difficulty_name.cmp(&b.difficulty_name)));
+
+    let mut fastest_keys: Vec<KeyTimeBoard> = fastest_keys.into_iter().map(|((dungeon, difficulty), mut entries)| {
+        entries.sort_by(|a, b| a.duration_secs.partial_cmp(&b.duration_secs).unwrap_or(std::cmp::Ordering::Equal));
+        entries.truncate(BOARD_SIZE);
+        KeyTimeBoard { dungeon: dungeon.to_string(), difficulty_name: difficulty.to_string(), entries }
+    }).collect();
+    fastest_keys.sort_by(|a, b| a.dungeon.cmp(&b.dungeon).then_with(|| a.difficulty_name.cmp(&b.difficulty_name)));
+
+    let mut most_deaths: Vec<DeathCount> = deaths.into_values().filter(|d| d.deaths > 0).collect();
+    most_deaths.sort_by(|a, b| b.deaths.cmp(&a.deaths).then_with(|| a.player.cmp(&b.player)));
+    most_deaths.truncate(BOARD_SIZE);
+
+    Leaderboards {
+        boss_dps,
+        fastest_keys,
+        most_deaths,
+        seasons: seasons.into_iter().collect(),
+        difficulties: difficulties.into_iter().collect(),
+    }
+}
@@ -1,3 +1,33 @@
-pub mod parser;
-pub mod models;
 pub mod api;
+pub mod benchmarks;
+pub mod builds;
+pub mod calendar;
+pub mod config;
+pub mod download;
+pub mod dungeon_stats;
+pub mod fatigue;
+pub mod history;
+pub mod instance;
+pub mod integrity;
+pub mod jobs;
+pub mod lan;
+pub mod leaderboard;
+pub mod live;
+pub mod log_index;
+pub mod logging;
+pub mod night_report;
+pub mod notices;
+pub mod planner;
+pub mod retention;
+pub mod server;
+pub mod settings;
+pub mod spell_fetcher;
+pub mod startup;
+pub mod summary_cache;
+pub mod thumbnail;
+pub mod vault;
+pub mod watcher;
+pub mod wow_install;
+pub mod xlsx;
+
+pub use fast_wow_parser::{anonymize, compare, game_data, guid, log_file, log_format, models, options, parser};
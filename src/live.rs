@@ -0,0 +1,251 @@
+//! Live mode: follow the log WoW is writing and publish a snapshot after every batch of
+//! new lines, for the `/api/live` WebSocket
+//!
+//! Only the newest log that is still being written is followed, picked from the log index
+//! the watcher keeps up to date rather than walking the log folder. The first poll parses it
+//! from the start (so a key already in progress is picked up); later polls only feed the
+//! lines appended since.
+//!
+//! Where the tail is gets checkpointed every [`CHECKPOINT_INTERVAL`], so after a crash or
+//! power loss mid-raid the restarted app picks the log up again from the start of the fight
+//! in progress rather than re-reading all of it. The checkpoint is removed once WoW stops
+//! writing the log; one still there at startup means the app went down while recording (see
+//! [`interrupted_log`]).
+//!
+//! One tailer serves every client. A snapshot carries each group member's personal view,
+//! and a client that picked its character (teammates opening the app over the LAN) only
+//! gets that one sent along with the shared part; see [`LiveUpdate::for_player`].
+
+use fast_wow_parser::models::{DeathEvent, KeyPace, LivePlayer};
+use fast_wow_parser::{anonymize, log_file, parser, CombatLogParser};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::log_index::LogIndex;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(15);
+const CHECKPOINT_FILE: &str = "live_checkpoint.json";
+
+/// What live clients receive; `None` on the channel means nothing is being recorded
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveUpdate {
+    pub filename: String,
+    /// False when the log is being recorded without advanced combat logging
+    pub advanced_logging: bool,
+    /// Set while a Mythic+ key is in progress
+    pub key_pace: Option<KeyPace>,
+    /// The group, for clients to pick their character from
+    pub roster: Vec<RosterEntry>,
+    /// Each group member's view; a client is only sent the one it picked
+    #[serde(skip)]
+    pub players: Vec<LivePlayer>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RosterEntry {
+    pub guid: String,
+    pub name: String,
+    pub class_name: String,
+    pub role: String,
+}
+
+/// What one client is sent: the shared update and the view of its character, if it picked one
+#[derive(Serialize)]
+pub struct LiveMessage<'a> {
+    #[serde(flatten)]
+    pub update: &'a LiveUpdate,
+    pub player: Option<&'a LivePlayer>,
+}
+
+impl LiveUpdate {
+    /// The message for a client that picked the character with `guid`
+    pub fn for_player(&self, guid: Option<&str>) -> LiveMessage<'_> {
+        LiveMessage { update: self, player: guid.and_then(|guid| self.players.iter().find(|p| p.guid == guid)) }
+    }
+}
+
+struct Tail {
+    path: PathBuf,
+    filename: String,
+    parser: CombatLogParser,
+    offset: u64,
+    /// Each player's latest death, kept once the fight it happened in is over
+    deaths: HashMap<String, DeathEvent>,
+    /// When the tail was last checkpointed
+    checkpointed: Option<Instant>,
+}
+
+/// Where the live tail was in the log it follows
+#[derive(Debug, Serialize, Deserialize)]
+struct Checkpoint {
+    filename: String,
+    /// Bytes read; a file shorter than this has been replaced
+    offset: u64,
+    /// Where the fight in progress started, to parse again from
+    resume_offset: u64,
+}
+
+/// `live_checkpoint.json` next to the history file
+fn checkpoint_path() -> PathBuf {
+    crate::history::history_path().with_file_name(CHECKPOINT_FILE)
+}
+
+impl Checkpoint {
+    fn load() -> Option<Checkpoint> {
+        let contents = std::fs::read_to_string(checkpoint_path()).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn save(&self) {
+        let saved = serde_json::to_vec(self).map_err(std::io::Error::from)
+            .and_then(|json| crate::history::write_durably(&checkpoint_path(), &json));
+        if let Err(e) = saved {
+            tracing::warn!("Couldn't checkpoint live mode: {}", e);
+        }
+    }
+
+    fn remove() {
+        let _ = std::fs::remove_file(checkpoint_path());
+    }
+}
+
+/// The log live mode was following when the app last went down, if WoW has stopped writing
+/// it since. Its newest encounters may never have reached the history, so the caller
+/// indexes it again. The checkpoint is consumed.
+pub fn interrupted_log(index: &LogIndex) -> Option<String> {
+    let checkpoint = Checkpoint::load()?;
+    if newest_active_log(index).is_some_and(|active| active.file_name().is_some_and(|n| *n == *checkpoint.filename)) {
+        return None;
+    }
+    Checkpoint::remove();
+    Some(checkpoint.filename)
+}
+
+/// Follow the active log among the indexed ones on a background task; the receiver always
+/// holds the latest update. `private_players` are left out of it.
+pub fn spawn(index: Arc<LogIndex>, private_players: Vec<String>) -> watch::Receiver<Option<LiveUpdate>> {
+    let (tx, rx) = watch::channel(None);
+    tokio::spawn(async move {
+        let mut tail: Option<Tail> = None;
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        while !tx.is_closed() {
+            interval.tick().await;
+            let index = index.clone();
+            let private_players = private_players.clone();
+            let polled = tokio::task::spawn_blocking(move || poll(&index, tail, &private_players)).await;
+            let (next, update) = match polled {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::error!("live tail task failed: {}", e);
+                    (None, None)
+                }
+            };
+            tail = next;
+            match update {
+                Some(update) => {
+                    tx.send_replace(Some(update));
+                }
+                None if tail.is_none() => {
+                    tx.send_if_modified(|current| current.take().is_some());
+                }
+                None => {}
+            }
+        }
+    });
+    rx
+}
+
+/// Feed whatever was appended since the last poll. Returns the tail to keep and an update
+/// if anything new was read.
+fn poll(index: &LogIndex, tail: Option<Tail>, private_players: &[String]) -> (Option<Tail>, Option<LiveUpdate>) {
+    let Some(active) = newest_active_log(index) else {
+        // The recording ended while we followed it; the watcher indexes the finished log
+        if tail.is_some() {
+            Checkpoint::remove();
+        }
+        return (None, None);
+    };
+    let size = log_file::metadata(&active).map(|m| m.len()).unwrap_or(0);
+    let mut tail = match tail {
+        // A shorter file than we've read means it was replaced; start over
+        Some(tail) if tail.path == active && size >= tail.offset => tail,
+        _ => {
+            let filename = active.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string();
+            tracing::info!(filename = %filename, "live mode following log");
+            let mut tail = Tail { path: active, parser: CombatLogParser::new(filename.clone()), filename, offset: 0, deaths: HashMap::new(), checkpointed: None };
+            resume(&mut tail, size);
+            tail
+        }
+    };
+    if size == tail.offset {
+        return (Some(tail), None);
+    }
+    match parser::tail_combat_log(&tail.path, &mut tail.parser, tail.offset) {
+        Ok(offset) if offset != tail.offset => {
+            tail.offset = offset;
+            let mut players = tail.parser.live_players();
+            for player in &mut players {
+                match &player.last_death {
+                    Some(death) => {
+                        tail.deaths.insert(player.guid.clone(), death.clone());
+                    }
+                    None => player.last_death = tail.deaths.get(&player.guid).cloned(),
+                }
+            }
+            if tail.checkpointed.is_none_or(|at| at.elapsed() >= CHECKPOINT_INTERVAL) {
+                Checkpoint { filename: tail.filename.clone(), offset, resume_offset: tail.parser.resume_offset() }.save();
+                tail.checkpointed = Some(Instant::now());
+            }
+            anonymize::redact_live_players(&mut players, private_players);
+            let update = LiveUpdate {
+                filename: tail.filename.clone(),
+                advanced_logging: tail.parser.advanced_logging(),
+                key_pace: tail.parser.key_pace(),
+                roster: players.iter()
+                    .map(|p| RosterEntry { guid: p.guid.clone(), name: p.name.clone(), class_name: p.class_name.clone(), role: p.role.clone() })
+                    .collect(),
+                players,
+            };
+            (Some(tail), Some(update))
+        }
+        Ok(_) => (Some(tail), None),
+        Err(e) => {
+            tracing::warn!("live tail of {} failed: {}", tail.path.display(), e);
+            (Some(tail), None)
+        }
+    }
+}
+
+/// Start a new tail at the checkpoint left for its log by an earlier run, if any: from the
+/// start of the fight that was in progress, with the log's header first
+fn resume(tail: &mut Tail, size: u64) {
+    let Some(checkpoint) = Checkpoint::load() else { return };
+    if checkpoint.filename != tail.filename || size < checkpoint.offset || checkpoint.resume_offset == 0 {
+        return;
+    }
+    match parser::read_log_header(&tail.path, &mut tail.parser) {
+        Ok(()) => {
+            tracing::info!(filename = %tail.filename, offset = checkpoint.resume_offset, "live mode resuming from checkpoint");
+            tail.offset = checkpoint.resume_offset;
+        }
+        Err(e) => tracing::warn!("Couldn't resume live mode from its checkpoint: {}", e),
+    }
+}
+
+/// The most recently modified combat log that WoW is still writing, if any
+fn newest_active_log(index: &LogIndex) -> Option<PathBuf> {
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for (_, path) in index.logs() {
+        let Ok(metadata) = log_file::metadata(&path) else { continue };
+        let Ok(modified) = metadata.modified() else { continue };
+        if log_file::is_active(&metadata) && newest.as_ref().is_none_or(|(t, _)| modified > *t) {
+            newest = Some((modified, path));
+        }
+    }
+    newest.map(|(_, path)| path)
+}
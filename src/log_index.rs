@@ -0,0 +1,230 @@
+//! Index of the combat logs under the log directory
+//!
+//! Walking the Logs folder on every request was slow on big folders and gave up without a
+//! word on folders it couldn't read. The index is built once, kept current from the
+//! filesystem watcher's events and rebuilt when the log directory changes; a lookup that
+//! misses queues a rebuild on a blocking thread (at most every `REBUILD_INTERVAL`) in case an
+//! event was lost, rather than walking the folder on the request's. Folders
+//! and files that can't be read (Windows Defender's Controlled Folder Access, permissions)
+//! are kept with their error for `/api/admin/log_index`. On Windows the walk uses `\\?\`
+//! paths, so logs nested deeper than 260 characters are found too.
+
+use chrono::Local;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::log_file;
+
+/// Least time between rebuilds caused by a lookup that missed
+const REBUILD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Whether `filename` is a raw combat log
+pub fn is_log_file(filename: &str) -> bool {
+    filename.starts_with("WoWCombatLog") && filename.ends_with(".txt")
+}
+
+/// A folder or file the walk couldn't read
+#[derive(Debug, Clone, Serialize)]
+pub struct WalkError {
+    pub path: String,
+    pub error: String,
+}
+
+/// What the index holds, for `/api/admin/log_index`
+#[derive(Debug, Clone, Serialize)]
+pub struct LogIndexReport {
+    pub dir: String,
+    pub built_at: String,
+    pub build_secs: f64,
+    pub logs: usize,
+    pub errors: Vec<WalkError>,
+}
+
+struct Walk {
+    dir: PathBuf,
+    logs: HashMap<String, PathBuf>,
+    errors: Vec<WalkError>,
+    built_at: String,
+    build_secs: f64,
+    started: Instant,
+}
+
+pub struct LogIndex {
+    walk: RwLock<Walk>,
+    /// Set while a rebuild queued by `find` runs
+    rebuilding: AtomicBool,
+}
+
+impl LogIndex {
+    pub fn build(dir: &Path) -> Self {
+        LogIndex { walk: RwLock::new(walk(dir)), rebuilding: AtomicBool::new(false) }
+    }
+
+    /// Walk `dir` again, e.g. after the log directory changed
+    pub fn rebuild(&self, dir: &Path) {
+        let walked = walk(dir);
+        *self.walk.write().unwrap() = walked;
+    }
+
+    /// Where the log `filename` is. A miss queues a rebuild once in a while, in case the
+    /// watcher missed the file, for a later lookup to find it; outside a tokio runtime the
+    /// rebuild runs right away instead.
+    pub fn find(self: &Arc<Self>, filename: &str) -> Option<PathBuf> {
+        if let Some(path) = self.lookup(filename) {
+            return Some(path);
+        }
+        let dir = {
+            let walk = self.walk.read().unwrap();
+            if walk.started.elapsed() < REBUILD_INTERVAL {
+                return None;
+            }
+            walk.dir.clone()
+        };
+        if self.rebuilding.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        tracing::debug!(filename = %filename, "log not indexed, walking the log directory again");
+        let index = self.clone();
+        let rebuild = move || {
+            index.refresh(&dir);
+            index.rebuilding.store(false, Ordering::Release);
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(rebuild);
+                None
+            }
+            Err(_) => {
+                rebuild();
+                self.lookup(filename)
+            }
+        }
+    }
+
+    /// Walk `dir` again, unless the log directory changed meanwhile (its own rebuild wins)
+    fn refresh(&self, dir: &Path) {
+        let walked = walk(dir);
+        let mut current = self.walk.write().unwrap();
+        if current.dir == dir {
+            *current = walked;
+        }
+    }
+
+    /// The indexed path of `filename`, forgetting it if it is gone
+    fn lookup(&self, filename: &str) -> Option<PathBuf> {
+        let path = self.walk.read().unwrap().logs.get(filename).cloned()?;
+        if path.exists() {
+            return Some(path);
+        }
+        self.remove(filename);
+        None
+    }
+
+    /// Every indexed log: (filename, path)
+    pub fn logs(&self) -> Vec<(String, PathBuf)> {
+        self.walk.read().unwrap().logs.iter().map(|(name, path)| (name.clone(), path.clone())).collect()
+    }
+
+    /// A path the watcher saw created or written to: a new log, or a new folder to walk
+    pub fn note_change(&self, path: &Path) {
+        if path.is_dir() {
+            let walked = walk(path);
+            let mut walk = self.walk.write().unwrap();
+            walk.logs.extend(walked.logs);
+            walk.errors.extend(walked.errors);
+            return;
+        }
+        let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+            return;
+        };
+        if is_log_file(filename) && path.exists() {
+            self.walk.write().unwrap().logs.insert(filename.to_string(), long_path(path));
+        }
+    }
+
+    /// Forget a log that was deleted
+    pub fn remove(&self, filename: &str) {
+        self.walk.write().unwrap().logs.remove(filename);
+    }
+
+    pub fn report(&self) -> LogIndexReport {
+        let walk = self.walk.read().unwrap();
+        LogIndexReport {
+            dir: walk.dir.display().to_string(),
+            built_at: walk.built_at.clone(),
+            build_secs: walk.build_secs,
+            logs: walk.logs.len(),
+            errors: walk.errors.clone(),
+        }
+    }
+}
+
+/// Every log under `dir`; unreadable folders and files are recorded rather than skipped
+fn walk(dir: &Path) -> Walk {
+    let started = Instant::now();
+    let mut logs = HashMap::new();
+    let mut errors = Vec::new();
+    let mut error = |path: &Path, e: io::Error| {
+        tracing::warn!(path = %path.display(), "log directory walk: {}", e);
+        errors.push(WalkError { path: path.display().to_string(), error: e.to_string() });
+    };
+    let mut dirs = vec![long_path(dir)];
+    while let Some(d) = dirs.pop() {
+        let entries = match log_file::with_retry(|| std::fs::read_dir(&d)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                error(&d, e);
+                continue;
+            }
+        };
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    error(&d, e);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => dirs.push(path),
+                Ok(_) => {
+                    if let Some(filename) = path.file_name().and_then(|n| n.to_str()).filter(|n| is_log_file(n)) {
+                        // The first one found wins, as with the old walk
+                        logs.entry(filename.to_string()).or_insert(path);
+                    }
+                }
+                Err(e) => error(&path, e),
+            }
+        }
+    }
+    let build_secs = started.elapsed().as_secs_f64();
+    tracing::info!(dir = %dir.display(), logs = logs.len(), errors = errors.len(), build_secs, "log directory indexed");
+    Walk { dir: dir.to_path_buf(), logs, errors, built_at: Local::now().to_rfc3339(), build_secs, started }
+}
+
+/// `path` in its `\\?\` form on Windows, which isn't limited to 260 characters
+#[cfg(windows)]
+fn long_path(path: &Path) -> PathBuf {
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let text = absolute.as_os_str().to_string_lossy();
+    if text.starts_with(r"\\?\") {
+        absolute
+    } else if let Some(share) = text.strip_prefix(r"\\") {
+        PathBuf::from(format!(r"\\?\UNC\{}", share))
+    } else {
+        PathBuf::from(format!(r"\\?\{}", text))
+    }
+}
+
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
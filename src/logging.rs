@@ -0,0 +1,68 @@
+//! Logging setup: a daily-rotated log file in the app data dir (plus the console in debug builds)
+
+use std::path::PathBuf;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+const LOG_FILE_PREFIX: &str = "wowlogger";
+const MAX_LOG_FILES: usize = 7;
+
+/// `%LOCALAPPDATA%\wowlogger\logs` on Windows (the platform equivalent elsewhere)
+pub fn log_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("wowlogger")
+        .join("logs")
+}
+
+/// Install the global subscriber. Keep the returned guard alive for the lifetime of the
+/// program so buffered lines are flushed on exit. Level defaults to `info`, override with `RUST_LOG`.
+pub fn init() -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let (file_layer, guard) = match RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir())
+    {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(fmt::layer().with_writer(writer).with_ansi(false)), Some(guard))
+        }
+        Err(_) => (None, None),
+    };
+
+    let console_layer = cfg!(debug_assertions).then(fmt::layer);
+
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(console_layer)
+        .try_init();
+    guard
+}
+
+/// Last `n` lines of the most recent log file
+pub fn tail(n: usize) -> Vec<String> {
+    let latest = std::fs::read_dir(log_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|f| f.to_str())
+                .is_some_and(|f| f.starts_with(LOG_FILE_PREFIX))
+        })
+        .max_by_key(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok());
+
+    let Some(path) = latest else {
+        return Vec::new();
+    };
+    let contents = std::fs::read_to_string(path).unwrap_or_default();
+    let lines: Vec<&str> = contents.lines().collect();
+    lines[lines.len().saturating_sub(n)..].iter().map(|l| l.to_string()).collect()
+}
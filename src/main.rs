@@ -1,85 +1,211 @@
 // Hide console window in release builds (double-click friendly)
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::net::TcpListener;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use tokio::sync::Notify;
+use wowlogger::instance::{self, PortOwner};
+use wowlogger::server::{self, Shared};
+use wowlogger::startup::{self, PROFILE_STARTUP_FLAG};
 
-mod api;
 mod gui;
-mod models;
-mod parser;
 
 const DEFAULT_LOG_DIR: &str = r"C:\World of Warcraft\_retail_\Logs";
 const PORT: u16 = 3000;
 
+/// Command-line option serving the frontend from a folder (see `AppConfig::frontend_dir`)
+const FRONTEND_DIR_FLAG: &str = "--frontend-dir";
+
 fn main() {
-    // 1. Resolve log directory
-    let log_dir = resolve_log_dir();
-
-    // 2. Shared mutable log_dir (GUI can change it at runtime)
-    let shared_log_dir = Arc::new(Mutex::new(log_dir));
-
-    // 3. Setup cross-thread shutdown signal
-    let shutdown = Arc::new(Notify::new());
-    let shutdown_for_server = shutdown.clone();
-    let shutdown_for_api = shutdown.clone();
-
-    // 4. Start HTTP server in background thread (with its own tokio runtime)
-    let server_log_dir = shared_log_dir.clone();
-    let server_handle = std::thread::spawn(move || {
-        let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-        rt.block_on(async {
-            let app = api::create_router(server_log_dir, shutdown_for_api);
-            let listener = match tokio::net::TcpListener::bind(format!("0.0.0.0:{}", PORT)).await {
-                Ok(l) => l,
-                Err(_e) => {
-                    #[cfg(debug_assertions)]
-                    eprintln!("Failed to bind port {}: {}", PORT, _e);
-                    return;
-                }
-            };
-            axum::serve(listener, app)
-                .with_graceful_shutdown(async move {
-                    shutdown_for_server.notified().await;
-                })
-                .await
-                .ok();
-        });
-    });
+    if std::env::args().any(|arg| arg == PROFILE_STARTUP_FLAG) {
+        startup::enable();
+    }
+
+    // 0. Logging (file in the app data dir; guard flushes it on exit)
+    let _log_guard = wowlogger::logging::init();
+    tracing::info!("wowlogger {} starting", env!("CARGO_PKG_VERSION"));
+
+    // 0b. Claim the port, or hand over to the instance already running
+    let Some((port, listener)) = startup::time("port binding", claim_port) else {
+        return;
+    };
+
+    // 1. Load config.json and resolve the log directory
+    let mut config = wowlogger::config::load();
+    if let Some(dir) = frontend_dir_arg() {
+        config.frontend_dir = Some(dir);
+    }
+    let log_dir = resolve_log_dir(&config);
 
-    // Brief pause so server is ready before opening browser
-    std::thread::sleep(std::time::Duration::from_millis(600));
+    // 2. State shared by the server and the window (the GUI can change the log dir at runtime)
+    let shared = Shared::new(log_dir);
 
-    // 5. Open browser automatically
-    let _ = open::that(format!("http://localhost:{}", PORT));
+    // 3. Start HTTP server in background thread; it says when it accepts connections
+    let (ready, server_ready) = std::sync::mpsc::channel::<()>();
+    let server_handle = server::spawn(listener, port, config, &shared, ready);
 
-    // 6. Run the native GUI window (blocks until closed or Stop pressed)
-    gui::run(shutdown.clone(), shared_log_dir.clone(), PORT);
+    // Advertise the server as fwca.local for phones and other machines on the network
+    let _mdns = wowlogger::lan::advertise(port);
 
-    // 7. Wait for server thread to finish gracefully
+    // 4. Open browser automatically once the server is up (not at all if it failed to start),
+    // without holding up the window
+    std::thread::spawn(move || {
+        if server_ready.recv().is_ok() {
+            let _ = open::that(format!("http://localhost:{}", port));
+            startup::mark("browser opened");
+        }
+    });
+
+    // 5. Run the native GUI window (blocks until closed or Stop pressed)
+    let Shared { settings, shutdown, jobs, retention, integrity, notices } = shared;
+    gui::run(shutdown, settings, port, jobs, retention, integrity, notices);
+
+    // 6. Wait for server thread to finish gracefully
     let _ = server_handle.join();
 }
 
-fn resolve_log_dir() -> PathBuf {
-    // Check CLI argument first (skip dialog)
-    if let Some(arg) = std::env::args().nth(1) {
+/// Listen on `PORT`. If this app already runs there, offer to open it or to start a second
+/// instance on another port; if another program has it, offer another port. `None` to exit.
+fn claim_port() -> Option<(u16, TcpListener)> {
+    use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+    let second_instance = || {
+        let found = instance::next_free_port(PORT);
+        if found.is_none() {
+            MessageDialog::new()
+                .set_level(MessageLevel::Error)
+                .set_title("Fast WoW Combat Analyzer")
+                .set_description(format!("No free port found after {}.", PORT))
+                .show();
+        }
+        found
+    };
+    match instance::claim(PORT) {
+        PortOwner::Free(listener) => Some((PORT, listener)),
+        PortOwner::Running => {
+            let choice = MessageDialog::new()
+                .set_level(MessageLevel::Info)
+                .set_title("Fast WoW Combat Analyzer")
+                .set_description(format!(
+                    "Fast WoW Combat Analyzer is already running on port {}.\n\n\
+                     Yes: open it in the browser\n\
+                     No: run a second instance on another port\n\
+                     Cancel: do nothing",
+                    PORT
+                ))
+                .set_buttons(MessageButtons::YesNoCancel)
+                .show();
+            match choice {
+                MessageDialogResult::Yes => {
+                    gui::focus_existing();
+                    let _ = open::that(format!("http://localhost:{}", PORT));
+                    None
+                }
+                MessageDialogResult::No => second_instance(),
+                _ => None,
+            }
+        }
+        PortOwner::Other(e) => {
+            let choice = MessageDialog::new()
+                .set_level(MessageLevel::Warning)
+                .set_title("Fast WoW Combat Analyzer")
+                .set_description(format!("Port {} is used by another program ({}).\n\nRun on another port instead?", PORT, e))
+                .set_buttons(MessageButtons::OkCancel)
+                .show();
+            match choice {
+                MessageDialogResult::Ok => second_instance(),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// The folder after `--frontend-dir` on the command line
+fn frontend_dir_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    args.find(|arg| arg == FRONTEND_DIR_FLAG)?;
+    args.next().map(PathBuf::from)
+}
+
+/// The log folder passed on the command line: the first argument that isn't an option
+fn log_dir_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == FRONTEND_DIR_FLAG {
+            args.next();
+            continue;
+        }
+        if arg == PROFILE_STARTUP_FLAG {
+            continue;
+        }
+        return Some(arg);
+    }
+    None
+}
+
+fn resolve_log_dir(config: &wowlogger::config::AppConfig) -> PathBuf {
+    use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+    // Check CLI argument first (skip dialog), then config.json
+    if let Some(arg) = log_dir_arg() {
         let p = PathBuf::from(&arg);
         if p.exists() {
             return p;
         }
     }
+    if let Some(dir) = config.log_dir.as_ref().filter(|dir| dir.exists()) {
+        return dir.clone();
+    }
+
+    // The Logs folders of the WoW install, narrowed to the configured game version
+    let mut detected = wowlogger::wow_install::detect();
+    if let Some(game) = &config.game {
+        detected.retain(|f| f.game.eq_ignore_ascii_case(game.trim_matches('_')));
+    }
+    // Game versions that have logs, else those with a Logs folder at all
+    let used: Vec<_> = detected.iter().filter(|f| f.last_log.is_some()).collect();
+    let candidates = if used.is_empty() { detected.iter().filter(|f| f.path.exists()).collect() } else { used };
+    match candidates.as_slice() {
+        [] => {}
+        [only] => return only.path.clone(),
+        [first, second, ..] => {
+            // Two installs of the same version (on different drives) need telling apart
+            let (first_label, second_label) = match (first.display_name(), second.display_name()) {
+                (a, b) if a == b => (format!("{} (1)", a), format!("{} (2)", b)),
+                labels => labels,
+            };
+            let choice = MessageDialog::new()
+                .set_level(MessageLevel::Info)
+                .set_title("Fast WoW Combat Analyzer")
+                .set_description(format!(
+                    "Several WoW versions were found. Which combat logs should be analyzed?\n\n\
+                     {}: {}\n{}: {}\n\n\
+                     Set \"game\" or \"log_dir\" in config.json to skip this question.",
+                    first_label, first.path.display(), second_label, second.path.display(),
+                ))
+                .set_buttons(MessageButtons::YesNoCancelCustom(first_label.clone(), second_label.clone(), "Other folder...".to_string()))
+                .show();
+            match choice {
+                MessageDialogResult::Custom(label) if label == first_label => return first.path.clone(),
+                MessageDialogResult::Custom(label) if label == second_label => return second.path.clone(),
+                _ => {}
+            }
+        }
+    }
 
-    // Use default path if it exists
+    // Use default path if it exists and nothing was detected
     let default = PathBuf::from(DEFAULT_LOG_DIR);
-    if default.exists() {
+    if detected.is_empty() && default.exists() {
         return default;
     }
 
-    // Default not found — show folder picker
+    // Nothing found, or another folder wanted — show folder picker, opened on the install
+    let start = detected.first()
+        .and_then(|f| f.path.parent())
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from(r"C:\"));
     match rfd::FileDialog::new()
         .set_title("Select WoW Combat Log Directory")
-        .set_directory(r"C:\")
+        .set_directory(start)
         .pick_folder()
     {
         Some(path) => path,
@@ -0,0 +1,262 @@
+//! The end-of-night report: what the raid or Mythic+ night got done, built on a schedule from
+//! the logs written that day, saved as a static HTML page and optionally posted to a Discord
+//! webhook
+//!
+//! A report covers the boss pulls and keys that started in the 24 hours before it runs, so a
+//! night going past midnight is one report when it runs the next morning.
+
+use chrono::{DateTime, Duration, Local, NaiveTime, Utc};
+use fast_wow_parser::anonymize::is_listed;
+use fast_wow_parser::export::short_number;
+use fast_wow_parser::models::EncounterSummary;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::vault::log_time;
+
+/// How far back a report looks
+pub const NIGHT_LENGTH: Duration = Duration::hours(24);
+
+/// Longest message a Discord webhook takes
+const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+const REPORTS_DIR: &str = "reports";
+
+#[derive(Serialize)]
+pub struct NightReport {
+    /// Day the first pull of the night started, "2025-03-14"
+    pub date: String,
+    pub pulls: u32,
+    pub kills: u32,
+    pub wipes: u32,
+    pub deaths: u32,
+    /// Time spent in boss pulls and keys
+    pub combat_secs: f64,
+    /// In the order first pulled
+    pub bosses: Vec<BossNight>,
+    pub keys: Vec<KeyNight>,
+    /// The HTML page written for it
+    pub html_path: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BossNight {
+    pub name: String,
+    pub difficulty_name: String,
+    pub pulls: u32,
+    /// Pull the kill came on, if it died
+    pub kill_pull: Option<u32>,
+    pub kill_secs: Option<f64>,
+    /// Lowest boss HP reached on a wipe
+    pub best_hp_pct: Option<f64>,
+    pub deaths: u32,
+    /// Highest DPS of the kill
+    pub top_dps: Option<TopDps>,
+}
+
+#[derive(Serialize)]
+pub struct TopDps {
+    pub player: String,
+    pub spec: String,
+    pub dps: f64,
+}
+
+#[derive(Serialize)]
+pub struct KeyNight {
+    pub name: String,
+    pub timed: bool,
+    pub completed: bool,
+    pub duration_secs: f64,
+    pub deaths: u32,
+}
+
+/// Next time the report runs at `at` (local time), after `now`
+pub fn next_run(at: NaiveTime, now: DateTime<Local>) -> DateTime<Local> {
+    let today = now.date_naive().and_time(at);
+    [today, today + Duration::days(1)].into_iter()
+        .filter_map(|naive| naive.and_local_timezone(Local).earliest())
+        .find(|run| *run > now)
+        .unwrap_or(now + Duration::days(1))
+}
+
+/// Whether `enc` goes in the report run at `now`: a boss pull or key started in the night before
+pub fn in_night(enc: &EncounterSummary, now: DateTime<Utc>) -> bool {
+    matches!(enc.encounter_type.as_str(), "boss" | "mythic_plus")
+        && log_time(&enc.start_time, now).is_some_and(|start| start > now - NIGHT_LENGTH && start <= now)
+}
+
+/// The report of `encounters` (from `in_night`), leaving players in `hidden` (the private
+/// players) off the top DPS; `None` for a night without any
+pub fn build(encounters: &[EncounterSummary], hidden: &[String], now: DateTime<Utc>) -> Option<NightReport> {
+    let mut night: Vec<(DateTime<Utc>, &EncounterSummary)> = encounters.iter()
+        .filter_map(|enc| Some((log_time(&enc.start_time, now)?, enc)))
+        .collect();
+    night.sort_by_key(|(start, _)| *start);
+    let (first, _) = night.first()?;
+
+    let mut report = NightReport {
+        date: first.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+        pulls: 0,
+        kills: 0,
+        wipes: 0,
+        deaths: 0,
+        combat_secs: 0.0,
+        bosses: Vec::new(),
+        keys: Vec::new(),
+        html_path: None,
+    };
+    for (_, enc) in night {
+        let deaths = enc.deaths.len() as u32;
+        report.deaths += deaths;
+        report.combat_secs += enc.duration_secs;
+        if enc.encounter_type == "mythic_plus" {
+            report.keys.push(KeyNight {
+                name: enc.name.clone(),
+                timed: enc.timed == Some(true),
+                completed: enc.success,
+                duration_secs: enc.duration_secs,
+                deaths,
+            });
+            continue;
+        }
+
+        report.pulls += 1;
+        if enc.success { report.kills += 1 } else { report.wipes += 1 }
+        let index = match report.bosses.iter().position(|b| b.name == enc.name && b.difficulty_name == enc.difficulty_name) {
+            Some(index) => index,
+            None => {
+                report.bosses.push(BossNight {
+                    name: enc.name.clone(),
+                    difficulty_name: enc.difficulty_name.clone(),
+                    pulls: 0,
+                    kill_pull: None,
+                    kill_secs: None,
+                    best_hp_pct: None,
+                    deaths: 0,
+                    top_dps: None,
+                });
+                report.bosses.len() - 1
+            }
+        };
+        let boss = &mut report.bosses[index];
+        boss.pulls += 1;
+        boss.deaths += deaths;
+        if enc.success && boss.kill_pull.is_none() {
+            boss.kill_pull = Some(boss.pulls);
+            boss.kill_secs = Some(enc.duration_secs);
+            boss.top_dps = enc.players.iter()
+                .filter(|p| !is_listed(&p.name, hidden))
+                .max_by(|a, b| a.dps.partial_cmp(&b.dps).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|p| TopDps {
+                    player: p.name.clone(),
+                    spec: format!("{} {}", p.spec_name, p.class_name).trim().to_string(),
+                    dps: p.dps,
+                });
+        } else if let Some(hp) = enc.boss_hp_pct.filter(|_| !enc.success) {
+            boss.best_hp_pct = Some(boss.best_hp_pct.map_or(hp, |best| best.min(hp)));
+        }
+    }
+    Some(report)
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+impl NightReport {
+    /// "3 bosses killed, 14 wipes, 17 pulls in 2:31:10, 42 deaths"
+    fn headline(&self) -> String {
+        let mut parts = Vec::new();
+        if self.pulls > 0 {
+            parts.push(format!("{} {} killed, {} {}", self.kills, if self.kills == 1 { "boss" } else { "bosses" }, self.wipes, if self.wipes == 1 { "wipe" } else { "wipes" }));
+        }
+        if !self.keys.is_empty() {
+            let timed = self.keys.iter().filter(|k| k.timed).count();
+            parts.push(format!("{} {} ({} timed)", self.keys.len(), if self.keys.len() == 1 { "key" } else { "keys" }, timed));
+        }
+        parts.push(format!("{} in combat, {} {}", format_duration(self.combat_secs), self.deaths, if self.deaths == 1 { "death" } else { "deaths" }));
+        parts.join(", ")
+    }
+
+    /// One line per boss or key, e.g. "Heroic Ulgrax: killed on pull 3 (4:12), top DPS ..."
+    fn lines(&self) -> Vec<(bool, String)> {
+        let mut lines = Vec::new();
+        for boss in &self.bosses {
+            let line = match (boss.kill_pull, boss.kill_secs) {
+                (Some(pull), Some(secs)) => {
+                    let top = boss.top_dps.as_ref()
+                        .map(|top| format!(", top DPS {} ({}) {}", top.player, top.spec, short_number(top.dps)))
+                        .unwrap_or_default();
+                    format!("{} {}: killed on pull {} ({}){}", boss.difficulty_name, boss.name, pull, format_duration(secs), top)
+                }
+                _ => {
+                    let best = boss.best_hp_pct.map(|hp| format!(", best {:.1}%", hp)).unwrap_or_default();
+                    format!("{} {}: {} {}{}", boss.difficulty_name, boss.name, boss.pulls, if boss.pulls == 1 { "pull" } else { "pulls" }, best)
+                }
+            };
+            lines.push((boss.kill_pull.is_some(), line));
+        }
+        for key in &self.keys {
+            let result = if key.timed { "timed" } else if key.completed { "over time" } else { "abandoned" };
+            let deaths = format!("{} {}", key.deaths, if key.deaths == 1 { "death" } else { "deaths" });
+            lines.push((key.timed, format!("{}: {} ({}), {}", key.name, result, format_duration(key.duration_secs), deaths)));
+        }
+        lines
+    }
+
+    /// The report as a Discord message, cut to fit one
+    pub fn discord_text(&self) -> String {
+        let mut text = format!("**Night of {}**: {}", self.date, self.headline());
+        for (good, line) in self.lines() {
+            let line = format!("\n{} {}", if good { "\u{2705}" } else { "\u{274C}" }, line);
+            if text.chars().count() + line.chars().count() > DISCORD_MESSAGE_LIMIT {
+                break;
+            }
+            text.push_str(&line);
+        }
+        text
+    }
+
+    /// The report as a self-contained HTML page
+    pub fn html(&self) -> String {
+        let rows: String = self.lines().iter()
+            .map(|(good, line)| format!("<li class=\"{}\">{}</li>\n", if *good { "good" } else { "bad" }, escape_html(line)))
+            .collect();
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n<title>Night of {date}</title>\n\
+             <style>body{{font-family:Inter,system-ui,sans-serif;background:#0a0e1a;color:#e5e7eb;max-width:760px;margin:40px auto;padding:0 16px}}\
+             h1{{font-size:22px}}p{{color:#9ca3af}}ul{{list-style:none;padding:0}}li{{background:#1a1f35;border-radius:8px;padding:10px 14px;margin:6px 0}}\
+             li.good{{border-left:4px solid #22c55e}}li.bad{{border-left:4px solid #ef4444}}</style>\n</head>\n<body>\n\
+             <h1>Night of {date}</h1>\n<p>{headline}</p>\n<ul>\n{rows}</ul>\n</body>\n</html>\n",
+            date = escape_html(&self.date),
+            headline = escape_html(&self.headline()),
+            rows = rows,
+        )
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// `%LOCALAPPDATA%\wowlogger\reports\<date>.html`, next to the history
+pub fn html_path(date: &str) -> PathBuf {
+    crate::history::history_path().with_file_name(REPORTS_DIR).join(format!("{}.html", date))
+}
+
+/// Post `text` to a Discord webhook
+pub async fn post_to_discord(webhook_url: &str, text: &str) -> Result<(), String> {
+    reqwest::Client::new()
+        .post(webhook_url)
+        .json(&serde_json::json!({ "content": text }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map(|_| ())
+        .map_err(|e| format!("Failed to post to the Discord webhook: {}", e))
+}
@@ -0,0 +1,115 @@
+//! Things the user should do something about, found while the app runs: shown on the GUI
+//! status line and served at `/api/notices` for the web app
+//!
+//! Each notice is about one condition, raised or cleared by what the newest log says about
+//! it, so opening an old log recorded with a setting since fixed doesn't bring its notice back.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Id of the notice raised when a log was recorded without advanced combat logging
+pub const ADVANCED_LOGGING: &str = "advanced_logging";
+
+/// `Config.wtf` line that switches advanced combat logging on
+pub const ADVANCED_LOGGING_CVAR: &str = "SET advancedCombatLogging \"1\"";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notice {
+    pub id: &'static str,
+    pub title: String,
+    pub message: String,
+    /// What to do about it, step by step
+    pub fix: String,
+    /// The log that raised it
+    pub filename: String,
+}
+
+impl Notice {
+    /// `filename` was recorded with advanced combat logging off
+    pub fn advanced_logging_off(filename: &str) -> Self {
+        Notice {
+            id: ADVANCED_LOGGING,
+            title: "Advanced combat logging is off".to_string(),
+            message: format!(
+                "{} was recorded without advanced combat logging, so it has no positions, HP or power: \
+                 the replay, heat maps and death recap HP stay empty, and some amounts can't be read.",
+                filename,
+            ),
+            fix: format!(
+                "In WoW, open Options > System > Network and tick Advanced Combat Logging, or type \
+                 /console advancedCombatLogging 1 in chat. It applies from the next log. To set it \
+                 while the game is closed, add {} to WTF\\Config.wtf.",
+                ADVANCED_LOGGING_CVAR,
+            ),
+            filename: filename.to_string(),
+        }
+    }
+}
+
+/// The notices up, shared by the server and the GUI
+#[derive(Default)]
+pub struct Notices {
+    notices: Mutex<HashMap<&'static str, Entry>>,
+}
+
+struct Entry {
+    /// When the log that last decided the notice was written
+    decided: SystemTime,
+    notice: Option<Notice>,
+    /// Dismissed by the user; stays hidden until a different log raises it
+    dismissed: bool,
+}
+
+impl Notices {
+    /// What a log written at `written` says about the condition `id`: `Some` raises the
+    /// notice, `None` clears it. A log older than the one that last decided is ignored.
+    pub fn report(&self, id: &'static str, written: SystemTime, notice: Option<Notice>) {
+        let mut notices = self.notices.lock().unwrap();
+        let current = notices.get(id);
+        if current.is_some_and(|entry| entry.decided > written) {
+            return;
+        }
+        let same_log = |entry: &Entry| entry.notice.as_ref().map(|n| &n.filename) == notice.as_ref().map(|n| &n.filename);
+        let dismissed = current.is_some_and(|entry| entry.dismissed && same_log(entry));
+        if let Some(notice) = &notice {
+            if !dismissed && current.is_none_or(|entry| entry.notice.is_none() || entry.dismissed) {
+                tracing::info!(id = id, filename = %notice.filename, "notice raised: {}", notice.title);
+            }
+        }
+        notices.insert(id, Entry { decided: written, notice, dismissed });
+    }
+
+    /// Hide a notice until another log raises it
+    pub fn dismiss(&self, id: &str) -> bool {
+        let mut notices = self.notices.lock().unwrap();
+        match notices.get_mut(id) {
+            Some(entry) if entry.notice.is_some() && !entry.dismissed => {
+                entry.dismissed = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn list(&self) -> Vec<Notice> {
+        let mut list: Vec<Notice> = self.notices.lock().unwrap().values()
+            .filter(|entry| !entry.dismissed)
+            .filter_map(|entry| entry.notice.clone())
+            .collect();
+        list.sort_by_key(|n| n.id);
+        list
+    }
+
+    /// For the GUI, e.g. "⚠ Advanced combat logging is off (see the web app)"; empty with no
+    /// notices up
+    pub fn status_text(&self) -> String {
+        let list = self.list();
+        match list.as_slice() {
+            [] => String::new(),
+            [notice] => format!("\u{26A0} {} (see the web app)", notice.title),
+            [notice, rest @ ..] => format!("\u{26A0} {} and {} more (see the web app)", notice.title, rest.len()),
+        }
+    }
+}
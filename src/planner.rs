@@ -0,0 +1,238 @@
+//! Roster suggestions for one boss or dungeon from the history: which of the guild's characters
+//! and specs did best on it (win rate, average DPS or HPS, deaths per pull), and a pick for
+//! each role slot of a typical pull
+//!
+//! Characters are ranked within their role by throughput, DPS for tanks and damage dealers and
+//! HPS for healers; only characters with at least `MIN_PULLS` pulls are picked. Records from
+//! before roles were kept count as healers when they healed more than they damaged.
+
+use fast_wow_parser::parser::eq_ignore_case;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::leaderboard::{dungeon_name, LeaderboardEncounter, LeaderboardPlayer};
+
+/// Pulls a character needs on the encounter before it is suggested
+pub const MIN_PULLS: u32 = 2;
+
+/// The boss or dungeon to plan for
+#[derive(Default, Deserialize)]
+pub struct PlannerQuery {
+    /// Boss or dungeon name, any case ("Queen Ansurek", "ara-kara, city of echoes"); none lists
+    /// the encounters there are
+    pub encounter: Option<String>,
+    /// Difficulty name, e.g. "Heroic" or "Mythic +12"
+    pub difficulty: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Planner {
+    pub encounter: Option<String>,
+    pub difficulty: Option<String>,
+    pub pulls: u32,
+    pub kills: u32,
+    /// Best first within each role: tanks, healers, then damage dealers
+    pub characters: Vec<CharacterStats>,
+    /// Highest win rate first
+    pub specs: Vec<SpecStats>,
+    pub suggestion: RosterSuggestion,
+    /// Values the query can take
+    pub encounters: Vec<String>,
+    pub difficulties: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct CharacterStats {
+    pub name: String,
+    /// The spec they played most on it
+    pub spec: String,
+    pub role: String,
+    pub pulls: u32,
+    pub kills: u32,
+    pub win_rate: f64,
+    pub average_dps: f64,
+    pub average_hps: f64,
+    pub deaths_per_pull: f64,
+}
+
+#[derive(Serialize)]
+pub struct SpecStats {
+    /// "Fire Mage"
+    pub spec: String,
+    pub role: String,
+    pub pulls: u32,
+    pub kills: u32,
+    pub win_rate: f64,
+    pub average_dps: f64,
+    pub average_hps: f64,
+    pub deaths_per_pull: f64,
+}
+
+/// Characters for each role slot, as many as the most usual pull had of that role
+#[derive(Default, Serialize)]
+pub struct RosterSuggestion {
+    pub tanks: Vec<String>,
+    pub healers: Vec<String>,
+    pub dps: Vec<String>,
+}
+
+#[derive(Default)]
+struct Totals {
+    pulls: u32,
+    kills: u32,
+    dps: f64,
+    hps: f64,
+    deaths: u32,
+}
+
+impl Totals {
+    fn add(&mut self, player: &LeaderboardPlayer, success: bool) {
+        self.pulls += 1;
+        self.kills += success as u32;
+        self.dps += player.dps;
+        self.hps += player.hps;
+        self.deaths += player.deaths;
+    }
+
+    fn win_rate(&self) -> f64 {
+        self.kills as f64 / self.pulls as f64 * 100.0
+    }
+
+    fn average_dps(&self) -> f64 {
+        self.dps / self.pulls as f64
+    }
+
+    fn average_hps(&self) -> f64 {
+        self.hps / self.pulls as f64
+    }
+
+    fn deaths_per_pull(&self) -> f64 {
+        self.deaths as f64 / self.pulls as f64
+    }
+}
+
+/// Boss name, or the dungeon of a key ("Ara-Kara, City of Echoes +10" -> "Ara-Kara, City of
+/// Echoes")
+fn encounter_name(enc: &LeaderboardEncounter) -> &str {
+    if enc.encounter_type == "mythic_plus" { dungeon_name(&enc.name) } else { &enc.name }
+}
+
+/// The player's role, or one guessed from their numbers for records without it
+fn role(player: &LeaderboardPlayer) -> &str {
+    match player.role.as_str() {
+        "" if player.hps > player.dps => "healer",
+        "" => "dps",
+        role => role,
+    }
+}
+
+fn role_order(role: &str) -> u8 {
+    match role {
+        "tank" => 0,
+        "healer" => 1,
+        _ => 2,
+    }
+}
+
+/// What a character is ranked by within its role
+fn throughput(character: &CharacterStats) -> f64 {
+    if character.role == "healer" { character.average_hps } else { character.average_dps }
+}
+
+/// Plan a roster for the encounter in `query` from `encounters`, leaving out players in
+/// `hidden` (the private players)
+pub fn plan(encounters: &[LeaderboardEncounter], query: &PlannerQuery, hidden: &[String]) -> Planner {
+    let names: BTreeSet<String> = encounters.iter().map(|e| encounter_name(e).to_string()).collect();
+    let matching: Vec<&LeaderboardEncounter> = encounters.iter()
+        .filter(|e| query.encounter.as_deref().is_some_and(|name| eq_ignore_case(encounter_name(e), name.trim())))
+        .collect();
+    let difficulties: BTreeSet<String> = matching.iter().map(|e| e.difficulty_name.clone()).collect();
+    let pulls: Vec<&LeaderboardEncounter> = matching.into_iter()
+        .filter(|e| query.difficulty.as_deref().is_none_or(|d| e.difficulty_name.eq_ignore_ascii_case(d.trim())))
+        .collect();
+    let visible = |p: &&LeaderboardPlayer| !fast_wow_parser::anonymize::is_listed(&p.name, hidden);
+
+    let mut by_character: HashMap<&str, (Totals, HashMap<&str, u32>, &str)> = HashMap::new();
+    let mut by_spec: HashMap<(&str, &str), Totals> = HashMap::new();
+    let mut comps: HashMap<[u32; 3], u32> = HashMap::new();
+    for enc in &pulls {
+        let mut comp = [0; 3];
+        for player in &enc.players {
+            comp[role_order(role(player)) as usize] += 1;
+        }
+        *comps.entry(comp).or_default() += 1;
+
+        for player in enc.players.iter().filter(visible) {
+            let (totals, specs, last_role) = by_character.entry(&player.name).or_default();
+            totals.add(player, enc.success);
+            *specs.entry(&player.spec).or_default() += 1;
+            *last_role = role(player);
+            if !player.spec.is_empty() {
+                by_spec.entry((&player.spec, role(player))).or_default().add(player, enc.success);
+            }
+        }
+    }
+
+    let mut characters: Vec<CharacterStats> = by_character.into_iter().map(|(name, (totals, specs, role))| {
+        let spec = specs.into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map_or_else(String::new, |(spec, _)| spec.to_string());
+        CharacterStats {
+            name: name.to_string(),
+            spec,
+            role: role.to_string(),
+            pulls: totals.pulls,
+            kills: totals.kills,
+            win_rate: totals.win_rate(),
+            average_dps: totals.average_dps(),
+            average_hps: totals.average_hps(),
+            deaths_per_pull: totals.deaths_per_pull(),
+        }
+    }).collect();
+    characters.sort_by(|a, b| {
+        role_order(&a.role).cmp(&role_order(&b.role))
+            .then_with(|| throughput(b).partial_cmp(&throughput(a)).unwrap_or(std::cmp::Ordering::Equal))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut specs: Vec<SpecStats> = by_spec.into_iter().map(|((spec, role), totals)| SpecStats {
+        spec: spec.to_string(),
+        role: role.to_string(),
+        pulls: totals.pulls,
+        kills: totals.kills,
+        win_rate: totals.win_rate(),
+        average_dps: totals.average_dps(),
+        average_hps: totals.average_hps(),
+        deaths_per_pull: totals.deaths_per_pull(),
+    }).collect();
+    specs.sort_by(|a, b| {
+        b.win_rate.partial_cmp(&a.win_rate).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.pulls.cmp(&a.pulls))
+            .then_with(|| a.spec.cmp(&b.spec))
+    });
+
+    // Slots of the most usual comp, the larger one on a tie
+    let slots = comps.into_iter()
+        .max_by_key(|&(comp, count)| (count, comp.iter().sum::<u32>(), comp))
+        .map_or([0; 3], |(comp, _)| comp);
+    let pick = |role_index: usize| -> Vec<String> {
+        characters.iter()
+            .filter(|c| role_order(&c.role) as usize == role_index && c.pulls >= MIN_PULLS)
+            .take(slots[role_index] as usize)
+            .map(|c| c.name.clone())
+            .collect()
+    };
+    let suggestion = RosterSuggestion { tanks: pick(0), healers: pick(1), dps: pick(2) };
+
+    Planner {
+        encounter: query.encounter.clone(),
+        difficulty: query.difficulty.clone(),
+        pulls: pulls.len() as u32,
+        kills: pulls.iter().filter(|e| e.success).count() as u32,
+        characters,
+        specs,
+        suggestion,
+        encounters: names.into_iter().collect(),
+        difficulties: difficulties.into_iter().collect(),
+    }
+}
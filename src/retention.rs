@@ -0,0 +1,134 @@
+//! Housekeeping for the Logs folder: a raid night easily logs a few gigabytes, and WoW never
+//! cleans up after itself
+//!
+//! With a retention policy configured, raw logs last written to more than `after_days` ago are
+//! deleted, but only once their encounters are in the history, only if the log hasn't changed
+//! since it was parsed, and never if the log is still being written or is a favorite. What was
+//! done is kept for `/api/admin/retention` and the GUI status line.
+
+use chrono::Local;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{RetentionAction, RetentionPolicy};
+use crate::history::ParsedLog;
+use crate::log_file;
+use crate::log_index::LogIndex;
+
+/// Time between checks of the Logs folder
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The first check waits this long after startup, so it doesn't compete with the first page load
+pub const STARTUP_DELAY: Duration = Duration::from_secs(60);
+
+/// Handled logs kept for the report
+const MAX_ENTRIES: usize = 50;
+
+/// A log the policy deleted
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionEntry {
+    pub filename: String,
+    pub action: RetentionAction,
+    pub bytes_freed: u64,
+    pub at: String,
+}
+
+/// What housekeeping has done since the app started
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionReport {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<String>,
+    pub logs_deleted: u32,
+    pub bytes_freed: u64,
+    /// Most recent first
+    pub entries: VecDeque<RetentionEntry>,
+    /// Logs that couldn't be handled on the last run
+    pub errors: Vec<String>,
+}
+
+/// The housekeeping report, shared by the server and the GUI
+#[derive(Default)]
+pub struct Retention {
+    report: Mutex<RetentionReport>,
+}
+
+impl Retention {
+    pub fn report(&self) -> RetentionReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// For the GUI, e.g. "Housekeeping: 3 log(s) deleted, 4.2 GB freed"; empty until
+    /// something was done
+    pub fn status_text(&self) -> String {
+        let report = self.report.lock().unwrap();
+        let mut done = Vec::new();
+        if report.logs_deleted > 0 {
+            done.push(format!("{} log(s) deleted", report.logs_deleted));
+        }
+        if !report.errors.is_empty() {
+            done.push(format!("{} failed", report.errors.len()));
+        }
+        if done.is_empty() {
+            return String::new();
+        }
+        format!("Housekeeping: {}, {} freed", done.join(", "), crate::api::format_size(report.bytes_freed))
+    }
+
+    /// Apply `policy` to the logs in `index`. `parsed` are the logs the history has
+    /// encounters of, as they were when parsed: a log that changed since is left alone, as
+    /// what it gained was never recorded. `favorites` are never touched. Returns the logs handled.
+    pub fn run(&self, index: &LogIndex, policy: &RetentionPolicy, parsed: &HashMap<String, ParsedLog>, favorites: &HashSet<String>) -> Vec<RetentionEntry> {
+        let max_age = Duration::from_secs(u64::from(policy.after_days) * 24 * 60 * 60);
+        let mut handled = Vec::new();
+        let mut errors = Vec::new();
+        for (filename, path, metadata) in expired_logs(index, max_age) {
+            if favorites.contains(&filename) {
+                continue;
+            }
+            if parsed.get(&filename).is_none_or(|p| ParsedLog::of(&metadata).as_ref() != Some(p)) {
+                continue;
+            }
+            match log_file::with_retry(|| std::fs::remove_file(&path)) {
+                Ok(()) => {
+                    let bytes_freed = metadata.len();
+                    tracing::info!(filename = %filename, action = ?policy.action, bytes_freed, "retention policy applied");
+                    handled.push(RetentionEntry { filename, action: policy.action, bytes_freed, at: Local::now().to_rfc3339() });
+                }
+                Err(e) => {
+                    tracing::warn!(filename = %filename, "retention policy failed: {}", e);
+                    errors.push(format!("{}: {}", filename, e));
+                }
+            }
+        }
+
+        let mut report = self.report.lock().unwrap();
+        report.enabled = policy.enabled;
+        report.last_run = Some(Local::now().to_rfc3339());
+        for entry in &handled {
+            report.logs_deleted += 1;
+            report.bytes_freed += entry.bytes_freed;
+            report.entries.push_front(entry.clone());
+        }
+        report.entries.truncate(MAX_ENTRIES);
+        report.errors = errors;
+        handled
+    }
+}
+
+/// Indexed logs last written to longer than `max_age` ago, none still being written
+fn expired_logs(index: &LogIndex, max_age: Duration) -> Vec<(String, PathBuf, std::fs::Metadata)> {
+    let now = SystemTime::now();
+    index.logs().into_iter()
+        .filter_map(|(filename, path)| {
+            let metadata = log_file::metadata(&path).ok()?;
+            let expired = !log_file::is_active(&metadata) && metadata.modified().ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > max_age);
+            expired.then_some((filename, path, metadata))
+        })
+        .collect()
+}
@@ -0,0 +1,76 @@
+//! The HTTP server of the desktop app: the state it shares with the window and the thread it
+//! runs on. The `wowlogger` binary only adds the dialogs, the browser and the window.
+
+use crate::api;
+use crate::config::AppConfig;
+use crate::integrity::Integrity;
+use crate::jobs::Jobs;
+use crate::notices::Notices;
+use crate::retention::Retention;
+use crate::settings::Settings;
+use crate::startup;
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tokio::sync::Notify;
+
+/// State the server shares with the window
+#[derive(Clone)]
+pub struct Shared {
+    /// Settings the window can change at runtime (the log dir)
+    pub settings: Arc<Settings>,
+    /// Stops the server (and the window) when notified
+    pub shutdown: Arc<Notify>,
+    /// Parse queue, watched by the window's status line
+    pub jobs: Arc<Jobs>,
+    /// Retention policy report, also shown by the window
+    pub retention: Arc<Retention>,
+    /// Startup check of the history against the Logs folder, run by the server as it starts
+    pub integrity: Arc<Integrity>,
+    /// Things the user should fix (e.g. advanced combat logging off), raised by the server
+    pub notices: Arc<Notices>,
+}
+
+impl Shared {
+    pub fn new(log_dir: PathBuf) -> Self {
+        Self {
+            settings: Arc::new(Settings::new(log_dir)),
+            shutdown: Arc::new(Notify::new()),
+            jobs: Arc::default(),
+            retention: Arc::default(),
+            integrity: Arc::default(),
+            notices: Arc::default(),
+        }
+    }
+}
+
+/// Serve the app on `listener` from a thread with its own tokio runtime, until `shutdown` is
+/// notified. `ready` gets a message once connections are accepted, never if listening fails.
+pub fn spawn(listener: TcpListener, port: u16, config: AppConfig, shared: &Shared, ready: Sender<()>) -> JoinHandle<()> {
+    let shared = shared.clone();
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+        rt.block_on(async {
+            let shutdown = shared.shutdown.clone();
+            let app = api::create_router(shared.settings, shared.shutdown, config, shared.jobs, shared.retention, shared.integrity, shared.notices);
+            let listener = match listener.set_nonblocking(true).and_then(|()| tokio::net::TcpListener::from_std(listener)) {
+                Ok(l) => l,
+                Err(e) => {
+                    tracing::error!("Failed to listen on port {}: {}", port, e);
+                    return;
+                }
+            };
+            startup::mark("listener accepting");
+            let _ = ready.send(());
+            // Clients' addresses, for the parse queue to take turns between them
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(async move {
+                    shutdown.notified().await;
+                })
+                .await
+                .ok();
+        });
+    })
+}
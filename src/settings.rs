@@ -0,0 +1,44 @@
+//! Settings that can change while the app runs, shared by the GUI and the server
+//!
+//! Only the log directory so far: the GUI can point the app at another Logs folder at any
+//! time. Readers take the current value; the tasks that depend on it (the filesystem
+//! watcher, live mode, the server's caches) subscribe and are told when it changes.
+
+use std::path::PathBuf;
+use tokio::sync::watch;
+
+pub struct Settings {
+    log_dir: watch::Sender<PathBuf>,
+}
+
+impl Settings {
+    pub fn new(log_dir: PathBuf) -> Self {
+        Settings { log_dir: watch::Sender::new(log_dir) }
+    }
+
+    /// The combat log directory
+    pub fn log_dir(&self) -> PathBuf {
+        self.log_dir.borrow().clone()
+    }
+
+    /// Point the app at another log directory and notify subscribers; `false` if it was
+    /// already the one in use
+    pub fn set_log_dir(&self, dir: PathBuf) -> bool {
+        let changed = self.log_dir.send_if_modified(|current| {
+            if *current == dir {
+                return false;
+            }
+            *current = dir;
+            true
+        });
+        if changed {
+            tracing::info!(dir = %self.log_dir().display(), "log directory changed");
+        }
+        changed
+    }
+
+    /// Follow the log directory; the receiver sees every change after this call
+    pub fn subscribe_log_dir(&self) -> watch::Receiver<PathBuf> {
+        self.log_dir.subscribe()
+    }
+}
@@ -3,15 +3,20 @@
 //! Scans WoW combat logs, extracts unique spell IDs, and fetches tooltip data
 //! (name, description, icon URL) from the Blizzard Game Data API.
 //!
+//! With `--cooldowns` it instead regenerates the parser's bundled spell cooldown table
+//! from the talent trees of every specialization.
+//!
 //! Usage:
 //!   spell_fetcher [LOG_DIR] [--region eu|us|kr|tw]
+//!   spell_fetcher --cooldowns [--region eu|us|kr|tw]
 //!
 //! Environment variables:
 //!   BLIZZARD_CLIENT_ID     - OAuth2 client ID
 //!   BLIZZARD_CLIENT_SECRET - OAuth2 client secret
 
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use fast_wow_parser::game_data::{self, CooldownCategory};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -19,6 +24,7 @@ use std::sync::{Arc, Mutex};
 const DEFAULT_LOG_DIR: &str = r"C:\World of Warcraft\_retail_\Logs";
 const OUTPUT_FILE: &str = "frontend/spell_tooltips.json";
 const CONCURRENCY: usize = 10;
+const COOLDOWNS_FILE: &str = "crates/fast-wow-parser/src/game_data/spell_cooldowns.rs";
 
 // ── Data types ───────────────────────────────────────────────────────────────
 
@@ -299,10 +305,134 @@ async fn fetch_spell_wowhead(
     })
 }
 
+// ── Spell cooldown table ─────────────────────────────────────────────────────
+
+/// A row of the generated cooldown table
+struct CooldownRow {
+    cooldown_secs: f64,
+    category: CooldownCategory,
+    class: String,
+    name: String,
+}
+
+/// Base cooldown from a tooltip's cooldown line ("2 min cooldown", "45 sec cooldown")
+fn parse_cooldown(text: &str) -> Option<f64> {
+    let mut words = text.split_whitespace();
+    let amount: f64 = words.next()?.parse().ok()?;
+    match words.next()? {
+        "min" => Some(amount * 60.0),
+        "sec" => Some(amount),
+        "hr" | "hour" | "hours" => Some(amount * 3600.0),
+        _ => None,
+    }
+}
+
+/// Every (spell_id, name, cooldown) in a talent tree, wherever the tooltips are nested
+fn collect_tooltip_cooldowns(value: &serde_json::Value, out: &mut Vec<(u64, String, f64)>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if let Some(tooltip) = map.get("spell_tooltip") {
+                let spell = &tooltip["spell"];
+                let cooldown = tooltip["cooldown"].as_str().and_then(parse_cooldown);
+                if let (Some(id), Some(cooldown)) = (spell["id"].as_u64(), cooldown) {
+                    let name = spell["name"].as_str().unwrap_or_default().to_string();
+                    out.push((id, name, cooldown));
+                }
+            }
+            for child in map.values() {
+                collect_tooltip_cooldowns(child, out);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_tooltip_cooldowns(item, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+async fn get_json(
+    client: &reqwest::Client,
+    token: &str,
+    url: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let resp = client
+        .get(url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(format!("{} returned {}", url, resp.status()).into());
+    }
+    Ok(resp.json().await?)
+}
+
+/// Regenerate the bundled cooldown table: the rows already in it, with cooldowns updated
+/// and new spells added from every specialization's talent tree
+async fn generate_cooldowns(
+    client: &reqwest::Client,
+    token: &str,
+    region: &str,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut rows: BTreeMap<u64, CooldownRow> = game_data::spell_cooldowns().iter()
+        .map(|c| (c.spell_id, CooldownRow {
+            cooldown_secs: c.cooldown_secs,
+            category: c.category,
+            class: c.class.to_string(),
+            name: c.name.to_string(),
+        }))
+        .collect();
+
+    let index_url = format!(
+        "https://{}.api.blizzard.com/data/wow/talent-tree/index?namespace=static-{}&locale=en_US",
+        region, region
+    );
+    let index = get_json(client, token, &index_url).await?;
+    let trees = index["spec_talent_trees"].as_array().cloned().unwrap_or_default();
+    for tree in &trees {
+        let Some(href) = tree["key"]["href"].as_str() else { continue };
+        let tree = match get_json(client, token, &format!("{}&locale=en_US", href)).await {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("   ⚠ {}", e);
+                continue;
+            }
+        };
+        let class = tree["playable_class"]["name"].as_str().unwrap_or_default().to_string();
+        let mut found = Vec::new();
+        collect_tooltip_cooldowns(&tree, &mut found);
+        eprintln!("   ✓ {} {}: {} spells with a cooldown", tree["playable_specialization"]["name"].as_str().unwrap_or_default(), class, found.len());
+        for (spell_id, name, cooldown_secs) in found {
+            rows.entry(spell_id)
+                .and_modify(|row| row.cooldown_secs = cooldown_secs)
+                .or_insert_with(|| CooldownRow { cooldown_secs, category: CooldownCategory::Other, class: class.clone(), name });
+        }
+    }
+
+    let mut out = String::from(
+        "// Generated by `spell_fetcher --cooldowns` from the Blizzard talent trees, on top of the rows\n\
+         // already here. Rows can be added or fixed by hand; the generator keeps their category and\n\
+         // class and only updates the cooldown when the API has one.\n\n\
+         use super::{CooldownCategory, SpellCooldown};\n\n\
+         /// Base cooldowns, sorted by spell id\n\
+         pub(super) const SPELL_COOLDOWNS: &[SpellCooldown] = &[\n",
+    );
+    for (spell_id, row) in &rows {
+        out.push_str(&format!(
+            "    SpellCooldown {{ spell_id: {}, cooldown_secs: {:?}, category: CooldownCategory::{:?}, class: {:?}, name: {:?} }},\n",
+            spell_id, row.cooldown_secs, row.category, row.class, row.name
+        ));
+    }
+    out.push_str("];\n");
+    std::fs::write(COOLDOWNS_FILE, out)?;
+    Ok(rows.len())
+}
+
 // ── Main ─────────────────────────────────────────────────────────────────────
 
-#[tokio::main]
-async fn main() {
+/// Run the tool with the process's arguments (see the module docs)
+pub async fn run() {
     // Load .env file if present (no external dependency needed)
     if let Ok(contents) = std::fs::read_to_string(".env") {
         for line in contents.lines() {
@@ -327,25 +457,35 @@ async fn main() {
     // Parse region flag
     let mut region = "eu".to_string();
     let mut log_dir_arg: Option<String> = None;
+    let mut cooldowns = false;
     let mut i = 1;
     while i < args.len() {
         if args[i] == "--region" && i + 1 < args.len() {
             region = args[i + 1].to_lowercase();
             i += 2;
+        } else if args[i] == "--cooldowns" {
+            cooldowns = true;
+            i += 1;
         } else {
             log_dir_arg = Some(args[i].clone());
             i += 1;
         }
     }
 
-    // Resolve log directory
+    // Resolve log directory: the argument, else the most recently used one of the WoW install
     let log_dir = log_dir_arg
         .map(PathBuf::from)
+        .or_else(|| {
+            let mut detected = crate::wow_install::detect();
+            detected.sort_by_key(|f| std::cmp::Reverse(f.last_log));
+            detected.into_iter().map(|f| f.path).find(|path| path.exists())
+        })
         .unwrap_or_else(|| PathBuf::from(DEFAULT_LOG_DIR));
 
-    if !log_dir.exists() {
+    if !cooldowns && !log_dir.exists() {
         eprintln!("❌ Log directory not found: {}", log_dir.display());
         eprintln!("   Usage: spell_fetcher [LOG_DIR] [--region eu|us|kr|tw]");
+        eprintln!("          spell_fetcher --cooldowns [--region eu|us|kr|tw]");
         std::process::exit(1);
     }
 
@@ -373,6 +513,23 @@ async fn main() {
         std::process::exit(1);
     }
 
+    if cooldowns {
+        eprintln!("\n🔑 Authenticating with Blizzard API ({} region)...", region);
+        let client = reqwest::Client::new();
+        let result = match get_oauth_token(&client, &client_id, &client_secret).await {
+            Ok(token) => generate_cooldowns(&client, &token, &region).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(count) => eprintln!("\n✅ Done! Wrote {} spell cooldowns to {}", count, COOLDOWNS_FILE),
+            Err(e) => {
+                eprintln!("❌ Failed to generate the cooldown table: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     // 1. Scan logs for spell IDs
     eprintln!("\n🔍 Scanning combat logs...");
     let all_spell_ids = match scan_logs_for_spell_ids(&log_dir) {
@@ -0,0 +1,87 @@
+//! Startup profiling for `--profile-startup`: how long each step of a cold start takes (port
+//! binding, history and log index, embedded assets, the window's icons, the listener being up
+//! and the first parse), written to the log as each one finishes and as one summary after the
+//! first parse
+//!
+//! Without the flag every call here is a no-op, apart from running the timed closure.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Command-line option switching profiling on
+pub const PROFILE_STARTUP_FLAG: &str = "--profile-startup";
+
+struct Profile {
+    started: Instant,
+    /// (step, how long it took, when it finished since start)
+    steps: Mutex<Vec<(&'static str, Duration, Duration)>>,
+    first_parse_seen: Mutex<bool>,
+}
+
+static PROFILE: OnceLock<Profile> = OnceLock::new();
+
+/// Start profiling, counting from now; call first thing in `main`
+pub fn enable() {
+    let _ = PROFILE.set(Profile {
+        started: Instant::now(),
+        steps: Mutex::new(Vec::new()),
+        first_parse_seen: Mutex::new(false),
+    });
+}
+
+pub fn enabled() -> bool {
+    PROFILE.get().is_some()
+}
+
+/// Note that `step` took `took`
+pub fn record(step: &'static str, took: Duration) {
+    let Some(profile) = PROFILE.get() else {
+        return;
+    };
+    let at = profile.started.elapsed();
+    tracing::info!("startup: {} took {} ms ({} ms since start)", step, took.as_millis(), at.as_millis());
+    profile.steps.lock().unwrap().push((step, took, at));
+}
+
+/// Run `f` as `step`
+pub fn time<T>(step: &'static str, f: impl FnOnce() -> T) -> T {
+    if !enabled() {
+        return f();
+    }
+    let started = Instant::now();
+    let result = f();
+    record(step, started.elapsed());
+    result
+}
+
+/// Note a moment of the start (e.g. the browser opening), with no duration of its own
+pub fn mark(step: &'static str) {
+    record(step, Duration::ZERO);
+}
+
+/// Note a parse that took `took`; the first one ends the profile with its summary
+pub fn parse_finished(took: Duration) {
+    let Some(profile) = PROFILE.get() else {
+        return;
+    };
+    {
+        let mut seen = profile.first_parse_seen.lock().unwrap();
+        if *seen {
+            return;
+        }
+        *seen = true;
+    }
+    record("first parse", took);
+    tracing::info!("startup profile:\n{}", summary());
+}
+
+/// The steps so far, one per line in the order they finished
+pub fn summary() -> String {
+    let Some(profile) = PROFILE.get() else {
+        return String::new();
+    };
+    profile.steps.lock().unwrap().iter()
+        .map(|(step, took, at)| format!("  {:>6} ms  {:<24} (took {} ms)", at.as_millis(), step, took.as_millis()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
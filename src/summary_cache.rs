@@ -0,0 +1,64 @@
+//! The parse cache: each log's summary, keyed by the log and the parser schema that produced it
+//!
+//! A summary is only served to the schema version it was parsed with, so an entry from older
+//! parser logic is a miss rather than a stale hit. The history's encounter index carries the
+//! same version and is invalidated on startup (see `History::check`).
+
+use fast_wow_parser::models::CombatLogSummary;
+use std::collections::HashMap;
+
+/// A log's cache key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub filename: String,
+    pub schema_version: u32,
+}
+
+impl CacheKey {
+    /// `filename` as parsed by the running parser
+    pub fn current(filename: &str) -> Self {
+        CacheKey { filename: filename.to_string(), schema_version: fast_wow_parser::SCHEMA_VERSION }
+    }
+}
+
+/// filename + schema -> (file size when parsed, summary)
+#[derive(Default)]
+pub struct SummaryCache {
+    entries: HashMap<CacheKey, (u64, CombatLogSummary)>,
+}
+
+impl SummaryCache {
+    /// The summary of `filename` parsed by the running parser, with the file size it was parsed at
+    pub fn get(&self, filename: &str) -> Option<&(u64, CombatLogSummary)> {
+        self.entries.get(&CacheKey::current(filename))
+    }
+
+    pub fn insert(&mut self, filename: &str, size: u64, summary: CombatLogSummary) {
+        self.entries.insert(CacheKey::current(filename), (size, summary));
+    }
+
+    /// Forget every version of `filename`
+    pub fn remove(&mut self, filename: &str) {
+        self.entries.retain(|key, _| key.filename != filename);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &(u64, CombatLogSummary)> {
+        self.entries.values()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut (u64, CombatLogSummary)> {
+        self.entries.values_mut()
+    }
+}
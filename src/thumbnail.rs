@@ -0,0 +1,184 @@
+//! Small PNG charts of an encounter for link previews and reports that can't run the web app
+//! (Discord embeds, e-mail): the group's damage over time as a filled sparkline, with the boss
+//! HP curve over it for boss pulls
+//!
+//! Drawn into a plain RGBA buffer and encoded as PNG here, so no browser or image library is
+//! needed: a sparkline is only a few columns and a polyline.
+
+use fast_wow_parser::models::EncounterSummary;
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use serde::Deserialize;
+use std::io::Write;
+
+const DEFAULT_WIDTH: u32 = 320;
+const DEFAULT_HEIGHT: u32 = 96;
+/// Largest image served, either way
+const MAX_SIZE: u32 = 1200;
+/// Width of the moving average the damage curve is smoothed with
+const SMOOTHING_SECS: f64 = 5.0;
+
+/// The web app's colors (`index.css`): card background, damage orange and boss HP red
+const BACKGROUND: [u8; 4] = [0x1a, 0x1f, 0x35, 0xff];
+const DAMAGE_LINE: [u8; 4] = [0xf9, 0x73, 0x16, 0xff];
+const DAMAGE_FILL: [u8; 4] = [0xf9, 0x73, 0x16, 0x50];
+const BOSS_HP: [u8; 4] = [0xef, 0x44, 0x44, 0xff];
+
+#[derive(Deserialize)]
+pub struct ThumbnailQuery {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl ThumbnailQuery {
+    fn size(&self) -> (u32, u32) {
+        (
+            self.width.unwrap_or(DEFAULT_WIDTH).clamp(16, MAX_SIZE),
+            self.height.unwrap_or(DEFAULT_HEIGHT).clamp(16, MAX_SIZE),
+        )
+    }
+}
+
+/// An RGBA image, rows top to bottom
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, background: [u8; 4]) -> Self {
+        Canvas { width, height, pixels: vec![background; (width * height) as usize] }
+    }
+
+    /// Paint `color` over the pixel, by its alpha
+    fn blend(&mut self, x: i64, y: i64, color: [u8; 4]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let pixel = &mut self.pixels[(y as u32 * self.width + x as u32) as usize];
+        let alpha = color[3] as u32;
+        for channel in 0..3 {
+            pixel[channel] = ((color[channel] as u32 * alpha + pixel[channel] as u32 * (255 - alpha)) / 255) as u8;
+        }
+    }
+
+    /// Fill column `x` from `top` down to the bottom edge
+    fn fill_below(&mut self, x: i64, top: f64, color: [u8; 4]) {
+        for y in top.round() as i64..self.height as i64 {
+            self.blend(x, y, color);
+        }
+    }
+
+    /// A line two pixels thick, stepped along its longer axis
+    fn line(&mut self, (x0, y0): (f64, f64), (x1, y1): (f64, f64), color: [u8; 4]) {
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as i64;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let x = (x0 + (x1 - x0) * t).round() as i64;
+            let y = (y0 + (y1 - y0) * t).round() as i64;
+            self.blend(x, y, color);
+            self.blend(x, y + 1, color);
+        }
+    }
+
+    /// A line through one point per column
+    fn polyline(&mut self, ys: &[f64], color: [u8; 4]) {
+        for (x, pair) in ys.windows(2).enumerate() {
+            self.line((x as f64, pair[0]), (x as f64 + 1.0, pair[1]), color);
+        }
+    }
+
+    /// The image as a PNG file: 8-bit RGBA, unfiltered rows
+    fn png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((self.width * self.height * 4 + self.height) as usize);
+        for row in self.pixels.chunks(self.width as usize) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        let compressed = encoder.write_all(&raw).and_then(|()| encoder.finish()).unwrap_or_default();
+
+        let mut header = Vec::with_capacity(13);
+        header.extend(self.width.to_be_bytes());
+        header.extend(self.height.to_be_bytes());
+        header.extend([8, 6, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &compressed);
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend(crc.sum().to_be_bytes());
+}
+
+/// Group damage per second, over the whole fight
+fn damage_per_second(enc: &EncounterSummary) -> Vec<f64> {
+    let seconds = enc.duration_secs.ceil().max(1.0) as usize;
+    let mut damage = vec![0.0; seconds];
+    for (&second, players) in &enc.time_bucketed_player_damage {
+        if let Some(slot) = damage.get_mut(second as usize) {
+            *slot += players.values().sum::<u64>() as f64;
+        }
+    }
+    damage
+}
+
+/// `per_second` smoothed and resampled to one value per column
+fn columns(per_second: &[f64], width: u32) -> Vec<f64> {
+    let half_window = SMOOTHING_SECS / 2.0;
+    (0..width).map(|x| {
+        let t = (x as f64 + 0.5) / width as f64 * per_second.len() as f64;
+        let from = (t - half_window).floor().max(0.0) as usize;
+        let to = ((t + half_window).ceil() as usize).clamp(from + 1, per_second.len());
+        per_second[from..to].iter().sum::<f64>() / (to - from) as f64
+    }).collect()
+}
+
+/// Boss HP % at `t`, from the samples around it
+fn hp_at(timeline: &[(f64, f64)], t: f64) -> f64 {
+    let after = timeline.partition_point(|&(secs, _)| secs < t);
+    match (after.checked_sub(1).map(|i| timeline[i]), timeline.get(after)) {
+        (Some((t0, hp0)), Some(&(t1, hp1))) if t1 > t0 => hp0 + (hp1 - hp0) * (t - t0) / (t1 - t0),
+        (Some((_, hp)), _) | (None, Some(&(_, hp))) => hp,
+        (None, None) => 100.0,
+    }
+}
+
+/// The thumbnail of `enc` as PNG bytes
+pub fn encounter_thumbnail(enc: &EncounterSummary, query: &ThumbnailQuery) -> Vec<u8> {
+    let (width, height) = query.size();
+    let mut canvas = Canvas::new(width, height, BACKGROUND);
+    // A pixel of room at the top and bottom for the 2px lines
+    let plot_height = height as f64 - 3.0;
+    let to_y = |fraction: f64| 1.0 + plot_height * (1.0 - fraction.clamp(0.0, 1.0));
+
+    let damage = columns(&damage_per_second(enc), width);
+    let peak = damage.iter().copied().fold(0.0, f64::max);
+    if peak > 0.0 {
+        let ys: Vec<f64> = damage.iter().map(|d| to_y(d / peak)).collect();
+        for (x, &y) in ys.iter().enumerate() {
+            canvas.fill_below(x as i64, y, DAMAGE_FILL);
+        }
+        canvas.polyline(&ys, DAMAGE_LINE);
+    }
+
+    if !enc.boss_hp_timeline.is_empty() {
+        let ys: Vec<f64> = (0..width)
+            .map(|x| hp_at(&enc.boss_hp_timeline, (x as f64 + 0.5) / width as f64 * enc.duration_secs))
+            .map(|hp| to_y(hp / 100.0))
+            .collect();
+        canvas.polyline(&ys, BOSS_HP);
+    }
+    canvas.png()
+}
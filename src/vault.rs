@@ -0,0 +1,142 @@
+//! Great Vault progress: the Mythic+ keys each character completed since the weekly reset
+//!
+//! Log timestamps are in the local time of the PC that wrote the log, so they're converted
+//! with this machine's time zone before being compared with the reset.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use serde::Serialize;
+
+use crate::config::Region;
+
+/// Runs that unlock each Mythic+ slot of the Great Vault
+const VAULT_SLOT_RUNS: [usize; 3] = [1, 4, 8];
+
+/// A finished key, from the history
+pub struct CompletedKey {
+    pub stable_id: String,
+    pub name: String,
+    pub start_time: String,
+    pub key_level: u32,
+    pub timed: bool,
+    pub players: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct VaultWeek {
+    pub region: Region,
+    /// The reset that started the week and the next one (RFC 3339, UTC)
+    pub week_start: String,
+    pub week_end: String,
+    /// Most runs first
+    pub characters: Vec<VaultCharacter>,
+}
+
+#[derive(Serialize)]
+pub struct VaultCharacter {
+    pub name: String,
+    pub runs: u32,
+    /// Level of every key completed this week, highest first
+    pub key_levels: Vec<u32>,
+    /// Key level each vault slot rewards (the best, 4th best and 8th best run); None until
+    /// enough runs unlock it
+    pub slots: [Option<u32>; 3],
+    /// Oldest first
+    pub keys: Vec<VaultKey>,
+}
+
+#[derive(Serialize)]
+pub struct VaultKey {
+    pub stable_id: String,
+    pub name: String,
+    pub start_time: String,
+    pub key_level: u32,
+    pub timed: bool,
+}
+
+impl Region {
+    /// Day and UTC hour of the weekly reset
+    fn reset(self) -> (Weekday, u32) {
+        match self {
+            Region::Eu => (Weekday::Wed, 4),
+            Region::Us => (Weekday::Tue, 15),
+        }
+    }
+}
+
+/// The last reset at or before `now`
+pub fn week_start(region: Region, now: DateTime<Utc>) -> DateTime<Utc> {
+    let (weekday, hour) = region.reset();
+    let days_back = (now.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    let reset = (now.date_naive() - Duration::days(days_back as i64))
+        .and_hms_opt(hour, 0, 0)
+        .unwrap_or_default()
+        .and_utc();
+    if reset > now { reset - Duration::weeks(1) } else { reset }
+}
+
+/// "M/D[/YYYY] HH:MM:SS.mmm" in local time; logs without a year are taken to be from the
+/// last twelve months
+pub(crate) fn log_time(start_time: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (date, time) = start_time.split_once(' ')?;
+    let mut parts = date.split('/').map(|p| p.parse::<i32>().ok());
+    let month = parts.next()?? as u32;
+    let day = parts.next()?? as u32;
+    let year = parts.next().flatten();
+    let time = chrono::NaiveTime::parse_from_str(time, "%H:%M:%S%.f").ok()?;
+    let at = |year: i32| {
+        let naive = NaiveDateTime::new(NaiveDate::from_ymd_opt(year, month, day)?, time);
+        Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc))
+    };
+    match year {
+        Some(year) => at(year),
+        None => at(now.year()).filter(|t| *t <= now).or_else(|| at(now.year() - 1)),
+    }
+}
+
+/// Keys completed in the week starting `start`, per character; characters in `hidden`
+/// (the private players) are left out
+pub fn vault_week(keys: &[CompletedKey], region: Region, start: DateTime<Utc>, hidden: &[String]) -> VaultWeek {
+    let end = start + Duration::weeks(1);
+    let now = Utc::now();
+    let mut week: Vec<(&CompletedKey, DateTime<Utc>)> = keys.iter()
+        .filter_map(|key| Some((key, log_time(&key.start_time, now)?)))
+        .filter(|(_, at)| *at >= start && *at < end)
+        .collect();
+    week.sort_by_key(|(_, at)| *at);
+
+    let mut characters: Vec<VaultCharacter> = Vec::new();
+    for (key, _) in &week {
+        for player in key.players.iter().filter(|p| !fast_wow_parser::anonymize::is_listed(p, hidden)) {
+            let index = match characters.iter().position(|c| c.name == *player) {
+                Some(index) => index,
+                None => {
+                    characters.push(VaultCharacter { name: player.clone(), runs: 0, key_levels: Vec::new(), slots: [None; 3], keys: Vec::new() });
+                    characters.len() - 1
+                }
+            };
+            characters[index].keys.push(VaultKey {
+                stable_id: key.stable_id.clone(),
+                name: key.name.clone(),
+                start_time: key.start_time.clone(),
+                key_level: key.key_level,
+                timed: key.timed,
+            });
+        }
+    }
+    for character in &mut characters {
+        character.runs = character.keys.len() as u32;
+        character.key_levels = character.keys.iter().map(|k| k.key_level).collect();
+        character.key_levels.sort_unstable_by(|a, b| b.cmp(a));
+        for (slot, runs) in character.slots.iter_mut().zip(VAULT_SLOT_RUNS) {
+            *slot = character.key_levels.get(runs - 1).copied();
+        }
+    }
+    characters.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.name.cmp(&b.name)));
+
+    VaultWeek {
+        region,
+        week_start: start.to_rfc3339(),
+        week_end: end.to_rfc3339(),
+        characters,
+    }
+}
@@ -0,0 +1,60 @@
+//! Filesystem watcher for the combat log directory
+//!
+//! Reports paths of log files that were created or written to. The directory can be changed
+//! from the GUI at runtime; the new one is watched as soon as it is set.
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use tokio::sync::{mpsc, watch};
+
+/// Watch the log directory (recursively) on a background task, following it when it
+/// changes; changed file paths arrive on the receiver
+pub fn spawn(mut log_dir: watch::Receiver<PathBuf>) -> mpsc::Receiver<PathBuf> {
+    let (tx, rx) = mpsc::channel(256);
+    tokio::spawn(async move {
+        loop {
+            let dir = log_dir.borrow_and_update().clone();
+            // Dropped (and stopped) when the directory changes
+            let _watcher = watch(&dir, tx.clone());
+            tokio::select! {
+                changed = log_dir.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                _ = tx.closed() => break,
+            }
+        }
+    });
+    rx
+}
+
+fn watch(dir: &std::path::Path, changes: mpsc::Sender<PathBuf>) -> Option<RecommendedWatcher> {
+    let handler = move |res: notify::Result<notify::Event>| match res {
+        Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+            for path in event.paths {
+                // Full channel means a refresh is already pending; dropping is fine
+                let _ = changes.try_send(path);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("log watcher error: {}", e),
+    };
+    let mut watcher = match notify::recommended_watcher(handler) {
+        Ok(w) => w,
+        Err(e) => {
+            tracing::warn!("Failed to create log watcher: {}", e);
+            return None;
+        }
+    };
+    match watcher.watch(dir, RecursiveMode::Recursive) {
+        Ok(()) => {
+            tracing::info!("Watching {} for log changes", dir.display());
+            Some(watcher)
+        }
+        Err(e) => {
+            tracing::warn!("Failed to watch {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
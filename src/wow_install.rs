@@ -0,0 +1,139 @@
+//! Finding the WoW installation and its Logs folders, so the app works without being pointed
+//! at them
+//!
+//! The install is looked up in the registry (where the Battle.net launcher records it), then
+//! under the launcher's default install path, then in the usual places. Each game version
+//! lives in its own folder of the install (`_retail_`, `_classic_`, `_classic_era_`,
+//! `_ptr_`, ...) with its own Logs folder.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const INSTALL_FOLDER: &str = "World of Warcraft";
+const COMMON_ROOTS: &[&str] = &[
+    r"C:\Program Files (x86)",
+    r"C:\Program Files",
+    r"C:\",
+    r"D:\Program Files (x86)",
+    r"D:\",
+];
+
+/// One game version's Logs folder
+#[derive(Debug, Clone, Serialize)]
+pub struct WowLogFolder {
+    /// "retail", "classic", "classic_era", "ptr", ...
+    pub game: String,
+    pub path: PathBuf,
+    /// When the newest combat log in it was last written; None if it has none yet
+    #[serde(skip)]
+    pub last_log: Option<SystemTime>,
+}
+
+impl WowLogFolder {
+    /// "Retail", "Classic era", "Ptr"
+    pub fn display_name(&self) -> String {
+        let name = self.game.replace('_', " ");
+        let mut chars = name.chars();
+        chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+    }
+}
+
+/// Every game version's Logs folder in every install found, retail first, then the most
+/// recently used
+pub fn detect() -> Vec<WowLogFolder> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for root in registry_install().into_iter()
+        .chain(launcher_install())
+        .chain(COMMON_ROOTS.iter().map(|root| Path::new(root).join(INSTALL_FOLDER)))
+    {
+        if root.is_dir() && !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+
+    let mut folders: Vec<WowLogFolder> = roots.iter().flat_map(|root| game_folders(root)).collect();
+    folders.sort_by_key(|f| (f.game != "retail", std::cmp::Reverse(f.last_log)));
+    if !folders.is_empty() {
+        tracing::info!("detected WoW log folders: {}", folders.iter().map(|f| f.path.display().to_string()).collect::<Vec<_>>().join(", "));
+    }
+    folders
+}
+
+/// The `_<game>_` folders of an install, with where their logs go (the Logs folder needn't
+/// exist yet; WoW creates it the first time combat logging is switched on)
+fn game_folders(root: &Path) -> Vec<WowLogFolder> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries.flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let game = name.strip_prefix('_')?.strip_suffix('_')?.to_string();
+            if game.is_empty() {
+                return None;
+            }
+            let path = entry.path().join("Logs");
+            let last_log = newest_log(&path);
+            Some(WowLogFolder { game, path, last_log })
+        })
+        .collect()
+}
+
+fn newest_log(logs: &Path) -> Option<SystemTime> {
+    std::fs::read_dir(logs).ok()?
+        .flatten()
+        .filter(|entry| crate::log_index::is_log_file(&entry.file_name().to_string_lossy()))
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .max()
+}
+
+/// The install the Battle.net launcher registered. Its `InstallPath` is the retail folder, so
+/// the install is its parent.
+#[cfg(windows)]
+fn registry_install() -> Option<PathBuf> {
+    use windows::core::w;
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+
+    let mut buf = [0u16; 1024];
+    let mut size = std::mem::size_of_val(&buf) as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            w!(r"SOFTWARE\WOW6432Node\Blizzard Entertainment\World of Warcraft"),
+            w!("InstallPath"),
+            RRF_RT_REG_SZ,
+            None,
+            Some(buf.as_mut_ptr().cast()),
+            Some(&mut size as *mut u32),
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    let install_path = PathBuf::from(String::from_utf16_lossy(&buf[..len]));
+    let folder_name = install_path.file_name()?.to_string_lossy().into_owned();
+    if folder_name.starts_with('_') {
+        install_path.parent().map(Path::to_path_buf)
+    } else {
+        Some(install_path)
+    }
+}
+
+#[cfg(not(windows))]
+fn registry_install() -> Option<PathBuf> {
+    None
+}
+
+/// `World of Warcraft` under the default install path set in the Battle.net launcher
+/// (`%APPDATA%\Battle.net\Battle.net.config`)
+fn launcher_install() -> Option<PathBuf> {
+    let config = dirs::config_dir()?.join("Battle.net").join("Battle.net.config");
+    let contents = std::fs::read_to_string(config).ok()?;
+    let config: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let root = config.pointer("/Client/Install/DefaultInstallPath")?.as_str()?;
+    Some(Path::new(root).join(INSTALL_FOLDER))
+}
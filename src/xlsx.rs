@@ -0,0 +1,238 @@
+//! Excel workbook export of one encounter or a whole log, for guild analysts who'd rather
+//! sort and chart in a spreadsheet than read JSON
+//!
+//! One sheet per table: players, abilities, deaths, buffs and Mythic+ segments. Every row
+//! starts with the encounter it belongs to, so a whole log's pulls can be filtered and
+//! pivoted together.
+
+use fast_wow_parser::anonymize::Pseudonyms;
+use fast_wow_parser::models::{AbilityBreakdown, EncounterSummary};
+use chrono::{DateTime, Utc};
+use rust_xlsxwriter::{Color, DocProperties, ExcelDateTime, Format, FormatBorder, Workbook, XlsxError};
+
+/// A typed cell, written with the number format its column needs
+enum Cell {
+    Text(String),
+    /// Damage, healing and other totals: "1,234,567"
+    Amount(u64),
+    /// Per-second rates, seconds and stacks: "1234.5"
+    Decimal(f64),
+    Count(u32),
+    /// Spell IDs, written without thousands separators
+    Id(u64),
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::Text(text)
+    }
+}
+
+/// The first columns of every row: which encounter it belongs to
+const ENCOUNTER_COLUMNS: [&str; 3] = ["Encounter", "Difficulty", "Start"];
+
+fn encounter_cells(enc: &EncounterSummary) -> Vec<Cell> {
+    vec![enc.name.as_str().into(), enc.difficulty_name.as_str().into(), enc.start_time.as_str().into()]
+}
+
+/// The workbook for `encounters`, as the bytes of an .xlsx file. With `pseudonyms`, player
+/// names in every text cell are replaced as in the anonymized JSON.
+pub fn workbook(encounters: &[EncounterSummary], created: DateTime<Utc>, pseudonyms: Option<&Pseudonyms>) -> Result<Vec<u8>, XlsxError> {
+    let anonymized = |mut rows: Vec<Vec<Cell>>| {
+        if let Some(pseudonyms) = pseudonyms {
+            pseudonyms.apply_to_strings(rows.iter_mut().flatten().filter_map(|cell| match cell {
+                Cell::Text(text) => Some(text),
+                _ => None,
+            }));
+        }
+        rows
+    };
+    let mut workbook = Workbook::new();
+    workbook.set_properties(&DocProperties::new().set_creation_datetime(&ExcelDateTime::from_timestamp(created.timestamp())?));
+    add_sheet(&mut workbook, "Players", &[
+        "Player", "Class", "Spec", "Role", "Damage", "DPS", "Damage %", "Healing", "HPS", "Healing %", "Damage Taken", "Deaths", "Interrupts", "Dispels", "Efficiency",
+    ], anonymized(player_rows(encounters)))?;
+    add_sheet(&mut workbook, "Abilities", &[
+        "Player", "Type", "Spell ID", "Ability", "Amount", "Hits", "% of Player",
+    ], anonymized(ability_rows(encounters)))?;
+    add_sheet(&mut workbook, "Deaths", &[
+        "Time (s)", "Player", "Killing Blow", "Source", "Amount", "Overkill", "One-Shot", "Time Dead (s)", "Resurrected By",
+    ], anonymized(death_rows(encounters)))?;
+    add_sheet(&mut workbook, "Buffs", &[
+        "Player", "Spell ID", "Aura", "Type", "Source", "Uptime (s)", "Uptime %", "Avg Stacks", "Max Stacks",
+    ], anonymized(buff_rows(encounters)))?;
+    add_sheet(&mut workbook, "Segments", &[
+        "#", "Type", "Segment", "Start", "End", "Duration (s)", "Deaths", "Damage",
+    ], anonymized(segment_rows(encounters)))?;
+    workbook.save_to_buffer()
+}
+
+fn player_rows(encounters: &[EncounterSummary]) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::new();
+    for enc in encounters {
+        for p in &enc.players {
+            let mut row = encounter_cells(enc);
+            row.extend([
+                p.name.as_str().into(),
+                p.class_name.as_str().into(),
+                p.spec_name.as_str().into(),
+                p.role.as_str().into(),
+                Cell::Amount(p.damage_done),
+                Cell::Decimal(p.dps),
+                Cell::Decimal(p.damage_share),
+                Cell::Amount(p.healing_done),
+                Cell::Decimal(p.hps),
+                Cell::Decimal(p.healing_share),
+                Cell::Amount(p.damage_taken),
+                Cell::Count(p.deaths),
+                Cell::Count(p.interrupts),
+                Cell::Count(p.dispels),
+                p.efficiency.as_ref().map_or(Cell::Text(String::new()), |e| Cell::Decimal(e.score)),
+            ]);
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+fn ability_rows(encounters: &[EncounterSummary]) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::new();
+    for enc in encounters {
+        for p in &enc.players {
+            let lists: [(&str, &[AbilityBreakdown], u64); 3] = [
+                ("Damage", &p.abilities, p.damage_done),
+                ("Healing", &p.heal_abilities, p.healing_done),
+                ("Damage Taken", &p.damage_taken_abilities, p.damage_taken),
+            ];
+            for (kind, abilities, total) in lists {
+                for ability in abilities {
+                    let mut row = encounter_cells(enc);
+                    row.extend([
+                        p.name.as_str().into(),
+                        kind.into(),
+                        Cell::Id(ability.spell_id),
+                        ability.spell_name.as_str().into(),
+                        Cell::Amount(ability.total_amount),
+                        Cell::Count(ability.hit_count),
+                        Cell::Decimal(if total > 0 { ability.total_amount as f64 / total as f64 * 100.0 } else { 0.0 }),
+                    ]);
+                    rows.push(row);
+                }
+            }
+        }
+    }
+    rows
+}
+
+fn death_rows(encounters: &[EncounterSummary]) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::new();
+    for enc in encounters {
+        for death in &enc.deaths {
+            let mut row = encounter_cells(enc);
+            row.extend([
+                Cell::Decimal(death.time_ms as f64 / 1000.0),
+                death.player_name.as_str().into(),
+                death.killing_blow_spell.clone().unwrap_or_default().into(),
+                death.killing_blow_source.clone().unwrap_or_default().into(),
+                Cell::Amount(death.killing_blow_amount.unwrap_or(0)),
+                Cell::Amount(death.overkill.unwrap_or(0).max(0) as u64),
+                if death.one_shot { "Yes" } else { "" }.into(),
+                Cell::Decimal(death.time_dead_secs),
+                death.resurrected_by.clone().unwrap_or_default().into(),
+            ]);
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+/// Every aura tracked on each player (keyed by GUID in the summary), players in meter order
+fn buff_rows(encounters: &[EncounterSummary]) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::new();
+    for enc in encounters {
+        for p in &enc.players {
+            let Some(uptimes) = enc.buff_uptimes.get(&p.guid) else {
+                continue;
+            };
+            for uptime in uptimes {
+                let mut row = encounter_cells(enc);
+                row.extend([
+                    p.name.as_str().into(),
+                    Cell::Id(uptime.spell_id),
+                    uptime.spell_name.as_str().into(),
+                    uptime.aura_type.as_str().into(),
+                    uptime.source_name.as_str().into(),
+                    Cell::Decimal(uptime.uptime_secs),
+                    Cell::Decimal(uptime.uptime_pct),
+                    Cell::Decimal(uptime.avg_stacks),
+                    Cell::Count(uptime.max_stacks),
+                ]);
+                rows.push(row);
+            }
+        }
+    }
+    rows
+}
+
+/// Boss and trash segments of Mythic+ keys; other encounters have none
+fn segment_rows(encounters: &[EncounterSummary]) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::new();
+    for enc in encounters {
+        for segment in &enc.segments {
+            let mut row = encounter_cells(enc);
+            row.extend([
+                Cell::Count(segment.index as u32 + 1),
+                segment.segment_type.as_str().into(),
+                segment.name.as_str().into(),
+                segment.start_time.as_str().into(),
+                segment.end_time.as_str().into(),
+                Cell::Decimal(segment.duration_secs),
+                Cell::Count(segment.deaths.len() as u32),
+                Cell::Amount(segment.players.iter().map(|p| p.damage_done).sum()),
+            ]);
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+/// A sheet with a bold, frozen, filterable header row and columns sized to fit
+fn add_sheet(workbook: &mut Workbook, name: &str, headers: &[&str], rows: Vec<Vec<Cell>>) -> Result<(), XlsxError> {
+    let header = Format::new()
+        .set_bold()
+        .set_font_color(Color::White)
+        .set_background_color(Color::RGB(0x1F2A44))
+        .set_border_bottom(FormatBorder::Thin);
+    let amount = Format::new().set_num_format("#,##0");
+    let decimal = Format::new().set_num_format("#,##0.0");
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(name)?;
+    let columns: Vec<&str> = ENCOUNTER_COLUMNS.iter().chain(headers).copied().collect();
+    for (col, title) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, &header)?;
+    }
+    for (i, cells) in rows.iter().enumerate() {
+        let row = i as u32 + 1;
+        for (col, cell) in cells.iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                Cell::Text(text) => sheet.write_string(row, col, text)?,
+                Cell::Amount(value) => sheet.write_number_with_format(row, col, *value as f64, &amount)?,
+                Cell::Decimal(value) => sheet.write_number_with_format(row, col, *value, &decimal)?,
+                Cell::Count(value) => sheet.write_number(row, col, *value)?,
+                Cell::Id(value) => sheet.write_number(row, col, *value as f64)?,
+            };
+        }
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofilter(0, 0, rows.len() as u32, columns.len() as u16 - 1)?;
+    sheet.autofit();
+    Ok(())
+}